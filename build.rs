@@ -0,0 +1,20 @@
+//! Generates the daemon's gRPC service from `proto/` when the `grpc`
+//! feature is enabled; other builds do not need protoc.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto");
+        // The vendored protoc, so building does not depend on a system install
+        let protoc = protoc_bin_vendored::protoc_bin_path()?;
+        // SAFETY: the build script is single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        let well_known = protoc_bin_vendored::include_path()?;
+        tonic_prost_build::configure().compile_protos(
+            &[std::path::Path::new("proto/aca/daemon/v1/daemon.proto")],
+            &[std::path::Path::new("proto"), well_known.as_path()],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Budget policy for context injected into task prompts.
+//!
+//! Context is taken in priority order — conversation history, then pinned
+//! files, then imported context — until the total input token cap is
+//! reached, and each source is also limited to a percentage of that cap.
+//! Truncation is deterministic: history keeps its most recent part, other
+//! sources keep their beginning, and both are marked as truncated.
+
+use super::token_breakdown::{ContextSource, ContextTokens, estimate_tokens};
+use serde::{Deserialize, Serialize};
+
+/// Marker placed where context was cut to fit the budget
+pub const TRUNCATION_MARKER: &str = "[... truncated to fit the context budget ...]";
+
+/// Characters per estimated token, matching [`estimate_tokens`]
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Limits on context injected into each task's prompt
+///
+/// All limits are off by default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextBudgetConfig {
+    /// Cap on estimated input tokens: system prompt, context and task
+    pub max_input_tokens: Option<u64>,
+    /// Share of `max_input_tokens` conversation history may use, in percent
+    pub history_percent: Option<u8>,
+    /// Share of `max_input_tokens` all pinned files together may use
+    pub pinned_percent: Option<u8>,
+    /// Share of `max_input_tokens` imported context may use
+    pub imported_percent: Option<u8>,
+}
+
+/// Context from one source, as it will be sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextPiece {
+    pub source: ContextSource,
+    pub content: String,
+}
+
+impl ContextPiece {
+    pub fn tokens(&self) -> ContextTokens {
+        ContextTokens {
+            source: self.source.clone(),
+            tokens: estimate_tokens(&self.content),
+        }
+    }
+}
+
+/// Lower values are kept first
+fn priority(source: &ContextSource) -> u8 {
+    match source {
+        ContextSource::History => 0,
+        ContextSource::Pinned(_) => 1,
+        ContextSource::Imported => 2,
+    }
+}
+
+impl ContextBudgetConfig {
+    /// Token cap for a source, if one applies
+    pub fn source_cap(&self, source: &ContextSource) -> Option<u64> {
+        let percent = match source {
+            ContextSource::History => self.history_percent,
+            ContextSource::Pinned(_) => self.pinned_percent,
+            ContextSource::Imported => self.imported_percent,
+        }?;
+        let total = self.max_input_tokens?;
+        Some(total * u64::from(percent.min(100)) / 100)
+    }
+
+    /// Tokens held back for history when budgeting context sent once per run
+    ///
+    /// History is assembled per request but has the highest priority, so its
+    /// share is reserved before pinned and imported context are fitted.
+    pub fn history_reserve(&self) -> u64 {
+        self.source_cap(&ContextSource::History).unwrap_or(0)
+    }
+
+    /// Fit `pieces` into what remains after `fixed_tokens` of prompt
+    ///
+    /// Pieces are taken in priority order (stable for equal priority);
+    /// pieces with no room left are dropped.
+    pub fn fit(&self, fixed_tokens: u64, mut pieces: Vec<ContextPiece>) -> Vec<ContextPiece> {
+        pieces.sort_by_key(|piece| priority(&piece.source));
+        let mut remaining = self
+            .max_input_tokens
+            .map(|total| total.saturating_sub(fixed_tokens));
+        let mut used_by_kind = [0u64; 3];
+
+        let mut fitted = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            let kind = priority(&piece.source) as usize;
+            let cap = self
+                .source_cap(&piece.source)
+                .map(|cap| cap.saturating_sub(used_by_kind[kind]));
+            let limit = match (cap, remaining) {
+                (Some(cap), Some(remaining)) => Some(cap.min(remaining)),
+                (cap, remaining) => cap.or(remaining),
+            };
+
+            let content = match limit {
+                Some(limit) => truncate(
+                    &piece.content,
+                    limit,
+                    piece.source == ContextSource::History,
+                ),
+                None => piece.content,
+            };
+            if content.is_empty() {
+                continue;
+            }
+
+            let tokens = estimate_tokens(&content);
+            used_by_kind[kind] += tokens;
+            if let Some(remaining) = remaining.as_mut() {
+                *remaining = remaining.saturating_sub(tokens);
+            }
+            fitted.push(ContextPiece {
+                source: piece.source,
+                content,
+            });
+        }
+        fitted
+    }
+}
+
+/// Cut `content` to at most `tokens`, keeping the end when `keep_tail`
+fn truncate(content: &str, tokens: u64, keep_tail: bool) -> String {
+    if estimate_tokens(content) <= tokens {
+        return content.to_string();
+    }
+    let max_chars = (tokens as usize).saturating_mul(CHARS_PER_TOKEN);
+    // Room for the marker and the newline separating it from the content
+    let Some(keep) = max_chars.checked_sub(TRUNCATION_MARKER.len() + 1) else {
+        return String::new();
+    };
+    if keep == 0 {
+        return String::new();
+    }
+
+    if keep_tail {
+        let mut start = content.len() - keep;
+        while !content.is_char_boundary(start) {
+            start += 1;
+        }
+        format!("{}\n{}", TRUNCATION_MARKER, &content[start..])
+    } else {
+        let mut end = keep;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}\n{}", &content[..end], TRUNCATION_MARKER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn piece(source: ContextSource, tokens: usize) -> ContextPiece {
+        ContextPiece {
+            source,
+            content: "x".repeat(tokens * CHARS_PER_TOKEN),
+        }
+    }
+
+    #[test]
+    fn test_unlimited_budget_keeps_everything() {
+        let pieces = vec![
+            piece(ContextSource::Imported, 500),
+            piece(ContextSource::History, 1000),
+        ];
+        let fitted = ContextBudgetConfig::default().fit(10_000, pieces);
+        assert_eq!(fitted.len(), 2);
+        assert_eq!(fitted[0].source, ContextSource::History);
+        assert_eq!(fitted[0].content.len(), 4000);
+    }
+
+    #[test]
+    fn test_priority_order_and_source_caps() {
+        let budget = ContextBudgetConfig {
+            max_input_tokens: Some(1000),
+            history_percent: Some(30),
+            pinned_percent: Some(40),
+            imported_percent: None,
+        };
+        let pieces = vec![
+            piece(ContextSource::Imported, 400),
+            piece(ContextSource::Pinned(PathBuf::from("a.md")), 250),
+            piece(ContextSource::Pinned(PathBuf::from("b.md")), 250),
+            piece(ContextSource::History, 500),
+        ];
+
+        // 200 fixed tokens leave 800: history 300 (cap), pinned 400 (cap
+        // shared by both files), imported gets the last 100
+        let fitted = budget.fit(200, pieces.clone());
+        let tokens: Vec<u64> = fitted.iter().map(|p| p.tokens().tokens).collect();
+        assert_eq!(tokens, vec![300, 250, 150, 100]);
+        assert_eq!(
+            fitted[1].source,
+            ContextSource::Pinned(PathBuf::from("a.md"))
+        );
+
+        // History keeps its most recent part, other sources their start
+        assert!(fitted[0].content.starts_with(TRUNCATION_MARKER));
+        assert!(fitted[2].content.ends_with(TRUNCATION_MARKER));
+
+        // Deterministic, and sources without room are dropped
+        assert_eq!(budget.fit(200, pieces.clone()), fitted);
+        let tight = budget.fit(750, pieces);
+        assert_eq!(tight.len(), 1);
+        assert_eq!(tight[0].source, ContextSource::History);
+    }
+
+    #[test]
+    fn test_truncate_respects_char_boundaries() {
+        let content = "é".repeat(200);
+        let head = truncate(&content, 40, false);
+        let tail = truncate(&content, 40, true);
+        assert!(estimate_tokens(&head) <= 40);
+        assert!(estimate_tokens(&tail) <= 40);
+        assert!(head.starts_with('é') && tail.ends_with('é'));
+        assert_eq!(truncate(&content, 5, false), "");
+        assert_eq!(truncate("short", 5, true), "short");
+    }
+}
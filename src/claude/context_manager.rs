@@ -1,5 +1,7 @@
-use crate::claude::types::{ClaudeMessage, ContextConfig, ConversationContext, SessionId};
-use std::collections::HashMap;
+use crate::claude::types::{
+    ClaudeMessage, ContextConfig, ConversationContext, MessageRole, SessionId,
+};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -18,6 +20,104 @@ pub struct OptimizedContext {
     pub compression_ratio: f64,
 }
 
+/// Conversation history selected for one task
+#[derive(Debug, Clone, Default)]
+pub struct RelevantHistory {
+    /// Running summary of messages evicted from the context
+    pub summary: Option<String>,
+    /// Messages of the selected turns, in conversation order
+    pub messages: Vec<ClaudeMessage>,
+    /// Turns left out because they were less relevant to the task
+    pub turns_omitted: usize,
+}
+
+impl RelevantHistory {
+    /// Keep only the last `turns` turns, counting the rest as omitted
+    pub fn keep_recent_turns(&mut self, turns: usize) {
+        let all = split_turns(&self.messages);
+        let dropped = all.len().saturating_sub(turns);
+        if dropped == 0 {
+            return;
+        }
+        let skip: usize = all[..dropped].iter().map(|turn| turn.len()).sum();
+        self.messages.drain(..skip);
+        self.turns_omitted += dropped;
+    }
+}
+
+/// Lines kept in a context's running summary
+const MAX_SUMMARY_LINES: usize = 20;
+
+/// Characters of an evicted message kept in the summary
+const SUMMARY_EXCERPT_CHARS: usize = 120;
+
+/// Words too common to say anything about relevance
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "that", "this", "from", "into", "are", "was", "were", "will",
+    "should", "would", "can", "could", "not", "all", "any", "use", "using", "has", "have", "but",
+    "its", "then", "than", "also", "when", "what", "which", "each", "make", "sure", "please",
+];
+
+/// Lowercase words of at least three characters, minus stopwords
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Share of the task's terms that also appear in `content`, from 0 to 1
+pub fn task_similarity(content: &str, task: &str) -> f64 {
+    let task_terms = terms(task);
+    if task_terms.is_empty() {
+        return 0.0;
+    }
+    let content_terms = terms(content);
+    task_terms.intersection(&content_terms).count() as f64 / task_terms.len() as f64
+}
+
+/// Split messages into turns, each starting at a user message
+fn split_turns(messages: &[ClaudeMessage]) -> Vec<&[ClaudeMessage]> {
+    let mut turns = Vec::new();
+    let mut start = 0;
+    for (idx, message) in messages.iter().enumerate() {
+        if idx > start && matches!(message.role, MessageRole::User) {
+            turns.push(&messages[start..idx]);
+            start = idx;
+        }
+    }
+    if start < messages.len() {
+        turns.push(&messages[start..]);
+    }
+    turns
+}
+
+/// Append one line per evicted message to `summary`, keeping the newest lines
+fn extend_summary(summary: Option<&str>, evicted: &[ClaudeMessage]) -> Option<String> {
+    let mut lines: Vec<String> = summary
+        .map(|summary| summary.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    for message in evicted {
+        let role = match message.role {
+            MessageRole::User => "User",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::System => "System",
+        };
+        let first_line = message.content.lines().next().unwrap_or_default();
+        let mut excerpt: String = first_line.chars().take(SUMMARY_EXCERPT_CHARS).collect();
+        if excerpt.len() < message.content.len() {
+            excerpt.push_str("...");
+        }
+        lines.push(format!("- {}: {}", role, excerpt));
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let skip = lines.len().saturating_sub(MAX_SUMMARY_LINES);
+    Some(lines[skip..].join("\n"))
+}
+
 impl ContextManager {
     pub fn new(config: ContextConfig) -> Self {
         Self {
@@ -26,6 +126,19 @@ impl ContextManager {
         }
     }
 
+    /// All conversation contexts, for persisting them
+    pub async fn export_contexts(&self) -> Vec<ConversationContext> {
+        self.contexts.lock().await.values().cloned().collect()
+    }
+
+    /// Add contexts saved by a previous run, replacing any with the same session
+    pub async fn import_contexts(&self, contexts: Vec<ConversationContext>) {
+        let mut current = self.contexts.lock().await;
+        for context in contexts {
+            current.insert(context.session_id, context);
+        }
+    }
+
     pub async fn get_or_create_context(&self, session_id: SessionId) -> ConversationContext {
         let mut contexts = self.contexts.lock().await;
 
@@ -117,6 +230,7 @@ impl ContextManager {
 
         // Filter messages
         let mut new_messages = Vec::new();
+        let mut evicted = Vec::new();
         let mut new_token_count = 0;
 
         for (idx, message) in context.messages.iter().enumerate() {
@@ -125,10 +239,13 @@ impl ContextManager {
                 if let Some(tokens) = message.token_count {
                     new_token_count += tokens;
                 }
+            } else {
+                evicted.push(message.clone());
             }
         }
 
-        // Update context
+        // Update context, remembering what was dropped in the running summary
+        context.context_summary = extend_summary(context.context_summary.as_deref(), &evicted);
         context.messages = new_messages;
         context.total_tokens = new_token_count;
 
@@ -167,9 +284,9 @@ impl ContextManager {
 
             // Role factor (assistant messages might be more important to keep)
             let role_factor = match message.role {
-                crate::claude::types::MessageRole::Assistant => 0.4,
-                crate::claude::types::MessageRole::User => 0.3,
-                crate::claude::types::MessageRole::System => 0.5,
+                MessageRole::Assistant => 0.4,
+                MessageRole::User => 0.3,
+                MessageRole::System => 0.5,
             };
             score += role_factor * 0.3;
 
@@ -219,6 +336,66 @@ impl ContextManager {
         (keyword_count as f64 / important_keywords.len() as f64).min(1.0)
     }
 
+    /// History worth replaying for `task`
+    ///
+    /// Turns are scored by how many of the task's terms they share, with
+    /// recency as a tie-breaker, and the `max_relevant_turns` best are kept
+    /// in conversation order. The latest turn is always kept. Returns `None`
+    /// when the session has no history.
+    pub async fn relevant_history(
+        &self,
+        session_id: SessionId,
+        task: &str,
+    ) -> Option<RelevantHistory> {
+        let context = self.get_context(session_id).await?;
+        if context.messages.is_empty() {
+            return None;
+        }
+
+        let turns = split_turns(&context.messages);
+        let limit = self.config.max_relevant_turns;
+        if limit == 0 || turns.len() <= limit {
+            return Some(RelevantHistory {
+                summary: context.context_summary,
+                messages: context.messages,
+                turns_omitted: 0,
+            });
+        }
+
+        let last = turns.len() - 1;
+        let mut scored: Vec<(usize, f64)> = turns
+            .iter()
+            .enumerate()
+            .take(last)
+            .map(|(idx, turn)| {
+                let text: Vec<&str> = turn.iter().map(|m| m.content.as_str()).collect();
+                let recency = (idx + 1) as f64 / turns.len() as f64;
+                (
+                    idx,
+                    task_similarity(&text.join("\n"), task) + recency * 0.01,
+                )
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut keep: Vec<usize> = scored
+            .into_iter()
+            .take(limit - 1)
+            .map(|(idx, _)| idx)
+            .collect();
+        keep.push(last);
+        keep.sort_unstable();
+
+        Some(RelevantHistory {
+            summary: context.context_summary,
+            messages: keep
+                .iter()
+                .flat_map(|&idx| turns[idx].iter().cloned())
+                .collect(),
+            turns_omitted: turns.len() - keep.len(),
+        })
+    }
+
     pub async fn get_context(&self, session_id: SessionId) -> Option<ConversationContext> {
         let contexts = self.contexts.lock().await;
         contexts.get(&session_id).cloned()
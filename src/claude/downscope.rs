@@ -0,0 +1,113 @@
+//! Progressive context reduction after a context overflow.
+//!
+//! When the provider rejects a request as too long, the request is retried
+//! with less context instead of failing the task: imported context files
+//! are dropped first, then all but the most recent conversation turn, and
+//! finally every turn, leaving only the running summary of the conversation.
+
+/// How much optional context a request carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContextScope {
+    /// Everything the budget allows
+    Full,
+    /// Without imported context files
+    WithoutRetrieved,
+    /// Also without conversation turns before the most recent one
+    RecentHistory,
+    /// Only the summary of earlier conversation
+    SummaryOnly,
+}
+
+impl ContextScope {
+    /// The next smaller scope, if any is left
+    pub fn next(self) -> Option<Self> {
+        match self {
+            ContextScope::Full => Some(ContextScope::WithoutRetrieved),
+            ContextScope::WithoutRetrieved => Some(ContextScope::RecentHistory),
+            ContextScope::RecentHistory => Some(ContextScope::SummaryOnly),
+            ContextScope::SummaryOnly => None,
+        }
+    }
+
+    /// What this scope drops compared to the one before it, for logs
+    pub fn dropped(self) -> &'static str {
+        match self {
+            ContextScope::Full => "nothing",
+            ContextScope::WithoutRetrieved => "imported context files",
+            ContextScope::RecentHistory => "older conversation history",
+            ContextScope::SummaryOnly => "remaining conversation turns, keeping the summary",
+        }
+    }
+
+    pub fn includes_retrieved(self) -> bool {
+        self == ContextScope::Full
+    }
+}
+
+/// Phrases providers use to reject a prompt longer than the model accepts
+const OVERFLOW_PHRASES: &[&str] = &[
+    "prompt is too long",
+    "input is too long",
+    "context length",
+    "context window",
+    "context_length_exceeded",
+    "maximum context",
+    "too many tokens",
+];
+
+/// Whether a provider error message reports a context overflow
+pub fn is_context_overflow(message: &str) -> bool {
+    let message = message.to_lowercase();
+    OVERFLOW_PHRASES
+        .iter()
+        .any(|phrase| message.contains(phrase))
+}
+
+/// Prompt and maximum token counts from messages such as
+/// "prompt is too long: 210000 tokens > 200000 maximum"
+pub fn overflow_token_counts(message: &str) -> Option<(u64, u64)> {
+    let mut numbers = message
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.parse::<u64>().ok());
+    let current = numbers.next()?;
+    let max = numbers.next()?;
+    Some((current, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes_shrink_in_order() {
+        let mut scope = ContextScope::Full;
+        let mut dropped = Vec::new();
+        while let Some(next) = scope.next() {
+            assert!(next > scope);
+            dropped.push(next.dropped());
+            scope = next;
+        }
+        assert_eq!(
+            dropped,
+            vec![
+                "imported context files",
+                "older conversation history",
+                "remaining conversation turns, keeping the summary"
+            ]
+        );
+        assert!(ContextScope::Full.includes_retrieved());
+        assert!(!ContextScope::RecentHistory.includes_retrieved());
+    }
+
+    #[test]
+    fn test_overflow_messages_are_recognized() {
+        let message = "Prompt is too long: 210000 tokens > 200000 maximum";
+        assert!(is_context_overflow(message));
+        assert_eq!(overflow_token_counts(message), Some((210000, 200000)));
+        assert!(is_context_overflow(
+            "This model's maximum context length is 128000 tokens"
+        ));
+        assert!(!is_context_overflow("Rate limit exceeded"));
+    }
+}
@@ -114,6 +114,7 @@ impl ErrorRecoveryManager {
             ClaudeError::CircuitBreakerOpen => false,
             ClaudeError::ContextTooLarge { .. } => false, // Needs different handling
             ClaudeError::MaxRetriesExceeded => false,
+            ClaudeError::Timeout { .. } => true,
             ClaudeError::Unknown(_) => attempt < 2, // Only retry once for unknown errors
         }
     }
@@ -166,6 +167,7 @@ impl ErrorRecoveryManager {
             ClaudeError::InvalidRequest(_) => "InvalidRequest",
             ClaudeError::CircuitBreakerOpen => "CircuitBreakerOpen",
             ClaudeError::MaxRetriesExceeded => "MaxRetriesExceeded",
+            ClaudeError::Timeout { .. } => "Timeout",
             ClaudeError::Unknown(_) => "Unknown",
         };
 
@@ -34,12 +34,22 @@
 //!
 //! The `result` field contains the actual response text which is extracted and returned.
 
-use crate::claude::{ContextManager, ErrorRecoveryManager, RateLimiter, UsageTracker, types::*};
+use crate::claude::downscope::{ContextScope, is_context_overflow, overflow_token_counts};
+use crate::claude::usage_tracker::cache_cost_and_savings;
+use crate::claude::{
+    ContextBudgetConfig, ContextManager, ContextPiece, ContextSource, ContextTokens,
+    ErrorRecoveryManager, RateLimiter, SharedRateLimiter, TokenBreakdown, UsageTracker,
+    token_breakdown, types::*,
+};
 use crate::env;
+use crate::executor::NixShell;
 use crate::llm::provider_logger::{LogContext, ProviderLogger, ToolUse};
+use crate::mcp::McpServers;
+use crate::session::{ConversationState, HeartbeatMonitor, InteractionRecord, PayloadBytes};
+use crate::task::suspend::{ProcessRegistration, ProcessRegistry, kill_process_group};
 use crate::task::types::{Task, TaskStatus};
 use chrono::{DateTime, Utc};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -47,16 +57,166 @@ use tokio::process::Command;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+/// [`TaskRequest::context`] key holding the `--dump-context` output path
+const DUMP_CONTEXT_KEY: &str = "dump_context";
+
+/// [`TaskRequest::context`] key holding the model a task was routed to
+const MODEL_KEY: &str = "model";
+
+/// Model passed to `claude --model` when a task is not routed to another
+pub const DEFAULT_MODEL: &str = "sonnet";
+
+/// Model to run `request` with
+fn request_model(request: &TaskRequest) -> &str {
+    request
+        .context
+        .get(MODEL_KEY)
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_MODEL)
+}
+
+/// Read the CLI's output as it runs, calling `on_output` for every line and
+/// echoing them to the terminal when `echo` is set
+pub(super) async fn read_output(
+    mut child: tokio::process::Child,
+    echo: bool,
+    on_output: impl Fn(),
+) -> Result<std::process::Output, ClaudeError> {
+    use std::io::Write;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| ClaudeError::Unknown("Failed to capture stdout".to_string()))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| ClaudeError::Unknown("Failed to capture stderr".to_string()))?;
+
+    let mut stdout_reader = BufReader::new(stdout);
+    let mut stderr_reader = BufReader::new(stderr);
+
+    let mut stdout_buffer = Vec::new();
+    let mut stderr_buffer = Vec::new();
+    let mut stdout_line = Vec::new();
+    let mut stderr_line = Vec::new();
+    let (mut stdout_done, mut stderr_done) = (false, false);
+
+    // Read output line by line until both streams close
+    while !(stdout_done && stderr_done) {
+        tokio::select! {
+            result = stdout_reader.read_until(b'\n', &mut stdout_line), if !stdout_done => {
+                match result {
+                    Ok(0) => stdout_done = true,
+                    Ok(_) => {
+                        on_output();
+                        if echo {
+                            let mut terminal = std::io::stdout();
+                            let _ = terminal.write_all(&stdout_line);
+                            let _ = terminal.flush();
+                        }
+                        stdout_buffer.append(&mut stdout_line);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Error reading stdout: {}", e);
+                        stdout_done = true;
+                    }
+                }
+            }
+            result = stderr_reader.read_until(b'\n', &mut stderr_line), if !stderr_done => {
+                match result {
+                    Ok(0) => stderr_done = true,
+                    Ok(_) => {
+                        on_output();
+                        if echo {
+                            let mut terminal = std::io::stderr();
+                            let _ = terminal.write_all(&stderr_line);
+                            let _ = terminal.flush();
+                        }
+                        stderr_buffer.append(&mut stderr_line);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Error reading stderr: {}", e);
+                        stderr_done = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Wait for process to complete
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| ClaudeError::Unknown(format!("Failed to wait for child process: {}", e)))?;
+
+    Ok(std::process::Output {
+        status,
+        stdout: stdout_buffer,
+        stderr: stderr_buffer,
+    })
+}
+
+/// Tool results in stream-json output, by the id of the tool use they answer
+fn extract_tool_results_from_stream(
+    stdout: &[u8],
+) -> std::collections::HashMap<String, serde_json::Value> {
+    let mut results = std::collections::HashMap::new();
+    for line in String::from_utf8_lossy(stdout).lines() {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            continue;
+        };
+        if json.get("type").and_then(|t| t.as_str()) != Some("user") {
+            continue;
+        }
+        let Some(content) = json
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+        else {
+            continue;
+        };
+        for item in content {
+            if item.get("type").and_then(|t| t.as_str()) == Some("tool_result")
+                && let Some(id) = item.get("tool_use_id").and_then(|id| id.as_str())
+            {
+                results.insert(
+                    id.to_string(),
+                    item.get("content").cloned().unwrap_or_default(),
+                );
+            }
+        }
+    }
+    results
+}
+
 #[derive(Debug)]
 pub struct ClaudeCodeInterface {
     config: ClaudeConfig,
     workspace_root: PathBuf,
     rate_limiter: Arc<RateLimiter>,
+    shared_limiter: Option<SharedRateLimiter>,
     context_manager: Arc<ContextManager>,
     usage_tracker: Arc<UsageTracker>,
     #[allow(dead_code)]
     error_recovery: Arc<ErrorRecoveryManager>,
     session_pool: Arc<Mutex<SessionPool>>,
+    processes: Arc<ProcessRegistry>,
+    /// Interactions not yet taken by [`Self::take_interactions`]
+    interactions: Arc<Mutex<Vec<InteractionRecord>>>,
+    /// Context assembled outside the interface, e.g. pinned files
+    context: Vec<ContextPiece>,
+    /// Limits history is fitted into per request
+    context_budget: ContextBudgetConfig,
+    /// Description of the probed execution environment, added to system messages
+    environment: std::sync::RwLock<Option<String>>,
+    /// Development shell the `claude` CLI is started in, if any
+    nix: std::sync::RwLock<Option<NixShell>>,
+    /// MCP servers the `claude` CLI is started with, if any
+    mcp: Option<McpServers>,
+    /// Liveness monitor told about each line of output, if any
+    heartbeat: Option<Arc<HeartbeatMonitor>>,
 }
 
 #[derive(Debug)]
@@ -78,6 +238,14 @@ struct ClaudeSession {
 impl ClaudeCodeInterface {
     pub async fn new(config: ClaudeConfig, workspace_root: PathBuf) -> Result<Self, ClaudeError> {
         let rate_limiter = Arc::new(RateLimiter::new(config.rate_limits.clone()));
+        let shared_limiter =
+            SharedRateLimiter::from_config(&config.shared_rate_limit, &config.rate_limits);
+        if let Some(ref shared) = shared_limiter {
+            tracing::info!(
+                "Sharing rate limits with other aca processes via {}",
+                shared.path().display()
+            );
+        }
         let context_manager = Arc::new(ContextManager::new(config.context_config.clone()));
         let usage_tracker = Arc::new(UsageTracker::new(config.usage_tracking.clone()));
         let error_recovery = Arc::new(ErrorRecoveryManager::new(config.error_config.clone()));
@@ -91,13 +259,112 @@ impl ClaudeCodeInterface {
             config,
             workspace_root,
             rate_limiter,
+            shared_limiter,
             context_manager,
             usage_tracker,
             error_recovery,
             session_pool,
+            processes: Arc::new(ProcessRegistry::new()),
+            interactions: Arc::new(Mutex::new(Vec::new())),
+            context: Vec::new(),
+            context_budget: ContextBudgetConfig::default(),
+            environment: std::sync::RwLock::new(None),
+            nix: std::sync::RwLock::new(None),
+            mcp: None,
+            heartbeat: None,
         })
     }
 
+    /// Context injected by the caller, reported in token breakdowns and
+    /// context dumps
+    pub fn with_context(mut self, context: Vec<ContextPiece>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Fit conversation history into `budget` for each request
+    pub fn with_context_budget(mut self, budget: ContextBudgetConfig) -> Self {
+        self.context_budget = budget;
+        self
+    }
+
+    /// Start the `claude` CLI with `servers`' tools available and allowed
+    pub fn with_mcp(mut self, servers: Option<McpServers>) -> Self {
+        self.mcp = servers;
+        self
+    }
+
+    /// Record the CLI's output as activity on `heartbeat`
+    pub fn with_heartbeat(mut self, heartbeat: Arc<HeartbeatMonitor>) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    /// Describe the execution environment to the model in every task's system message
+    pub fn set_environment(&self, description: String) {
+        *self.environment.write().unwrap() = Some(description);
+    }
+
+    /// Start the `claude` CLI inside `shell`, or directly again with `None`
+    pub fn set_nix_shell(&self, shell: Option<NixShell>) {
+        *self.nix.write().unwrap() = shell;
+    }
+
+    /// System message for a task: the configured prompt and the environment
+    pub fn system_message(&self) -> Option<String> {
+        let environment = self.environment.read().unwrap().clone();
+        match (self.config.system_prompt.clone(), environment) {
+            (Some(prompt), Some(environment)) if !prompt.is_empty() => {
+                Some(format!("{}\n\n{}", prompt, environment))
+            }
+            (_, Some(environment)) => Some(environment),
+            (prompt, None) => prompt,
+        }
+    }
+
+    /// Running `claude` subprocesses by task, for suspending paused tasks
+    pub fn process_registry(&self) -> Arc<ProcessRegistry> {
+        self.processes.clone()
+    }
+
+    /// Interactions made since the last call, oldest first
+    ///
+    /// Each `claude` invocation is recorded whether it succeeded or not, so
+    /// callers can persist them alongside the task they belong to.
+    pub async fn take_interactions(&self) -> Vec<InteractionRecord> {
+        std::mem::take(&mut *self.interactions.lock().await)
+    }
+
+    async fn record_interaction(
+        &self,
+        logger: &ProviderLogger,
+        ctx: &LogContext,
+        payload: PayloadBytes,
+        usage: Option<&TokenUsage>,
+        exit_code: Option<i32>,
+        error: Option<String>,
+    ) {
+        let duration = (Utc::now() - ctx.started_at).to_std().unwrap_or_default();
+        let interaction = InteractionRecord {
+            request_id: ctx.request_id,
+            // Task requests are identified by their task's id
+            task_id: ctx.request_id,
+            provider: logger.provider_name().to_string(),
+            model: ctx.model.clone(),
+            started_at: ctx.started_at,
+            duration_ms: duration.as_millis() as u64,
+            input_tokens: usage.map_or(0, |usage| usage.input_tokens),
+            output_tokens: usage.map_or(0, |usage| usage.output_tokens),
+            estimated_cost: usage.map_or(0.0, |usage| usage.estimated_cost),
+            exit_code,
+            success: error.is_none(),
+            error,
+            log_files: logger.request_files(ctx),
+            payload,
+        };
+        self.interactions.lock().await.push(interaction);
+    }
+
     pub async fn execute_task_request(
         &self,
         request: TaskRequest,
@@ -132,7 +399,10 @@ impl ClaudeCodeInterface {
         logger: &ProviderLogger,
     ) -> Result<TaskResponse, ClaudeError> {
         // Apply rate limiting
-        let _permit = self.rate_limiter.acquire_permit(request).await?;
+        let permit = self.rate_limiter.acquire_permit(request).await?;
+        if let Some(ref shared) = self.shared_limiter {
+            shared.acquire(permit.tokens_consumed).await?;
+        }
 
         // Get conversation context
         let _context = self.context_manager.get_or_create_context(session.id).await;
@@ -175,24 +445,90 @@ impl ClaudeCodeInterface {
         Ok(response)
     }
 
+    /// Run the request, retrying with less context while the provider
+    /// reports that the prompt is too long
     async fn execute_claude_code_request(
         &self,
         session_id: SessionId,
         request: &TaskRequest,
         logger: &ProviderLogger,
+    ) -> Result<TaskResponse, ClaudeError> {
+        let mut scope = ContextScope::Full;
+        loop {
+            let result = self
+                .execute_scoped_request(
+                    session_id,
+                    &self.scoped_request(request, scope),
+                    logger,
+                    scope,
+                )
+                .await;
+            let Err(ClaudeError::ContextTooLarge { current, max }) = &result else {
+                return result;
+            };
+            let Some(next) = scope.next() else {
+                return result;
+            };
+
+            let message = format!(
+                "Context too long for task {} ({} > {} tokens); retrying without {}",
+                request.id,
+                current,
+                max,
+                next.dropped()
+            );
+            tracing::warn!("{}", message);
+            logger
+                .log_event(
+                    &LogContext::new(request.id, request_model(request)),
+                    &message,
+                )
+                .await
+                .ok();
+            scope = next;
+        }
+    }
+
+    /// The request with context outside `scope` removed from its system message
+    fn scoped_request(&self, request: &TaskRequest, scope: ContextScope) -> TaskRequest {
+        let mut request = request.clone();
+        if !scope.includes_retrieved()
+            && let Some(system_message) = request.system_message.as_mut()
+        {
+            for piece in self
+                .context
+                .iter()
+                .filter(|piece| piece.source.in_system_prompt())
+            {
+                *system_message = system_message.replace(&piece.content, "");
+            }
+        }
+        request
+    }
+
+    async fn execute_scoped_request(
+        &self,
+        session_id: SessionId,
+        request: &TaskRequest,
+        logger: &ProviderLogger,
+        scope: ContextScope,
     ) -> Result<TaskResponse, ClaudeError> {
         let start_time = Instant::now();
+        let model = request_model(request);
 
-        let ctx = LogContext::new(request.id, "sonnet")
+        let ctx = LogContext::new(request.id, model)
             .with_metadata("task_type", &request.task_type)
             .with_metadata("priority", format!("{:?}", request.priority));
 
-        let contextual_prompt = self
-            .build_contextual_prompt(session_id, &request.description)
+        let (contextual_prompt, history_tokens) = self
+            .build_contextual_prompt(session_id, request, scope)
             .await;
+        let bytes_sent = (contextual_prompt.len()
+            + request.system_message.as_ref().map_or(0, String::len))
+            as u64;
 
-        const ALLOWED_TOOLS: &str =
-            "Read,Write,Edit,Bash,Glob,Grep,MultiEdit,Task,TodoWrite,SlashCommand";
+        let (allowed_tools, permission_mode) = self.tool_access(request);
+        let disallowed_tools = request.tool_permissions.disallowed_tools.join(",");
 
         let track_tool_uses = logger.is_tool_tracking_enabled();
         let output_format = if track_tool_uses {
@@ -201,7 +537,15 @@ impl ClaudeCodeInterface {
             "json"
         };
 
-        let mut command = Command::new("claude");
+        let mut command = match self.nix.read().unwrap().as_ref() {
+            Some(shell) => shell.command("claude"),
+            None => Command::new("claude"),
+        };
+        // Abandoned attempts (e.g. stall restarts) must not leave the CLI running
+        command.kill_on_drop(true);
+        // In its own process group, so a timeout also kills the tools it started
+        #[cfg(unix)]
+        command.process_group(0);
         command
             .arg("--print")
             .arg("--output-format")
@@ -213,16 +557,26 @@ impl ClaudeCodeInterface {
 
         command
             .arg("--allowedTools")
-            .arg(ALLOWED_TOOLS)
+            .arg(&allowed_tools)
             .arg("--permission-mode")
-            .arg("acceptEdits");
+            .arg(permission_mode);
 
         let mut log_cmd = format!(
-            "claude --print {}--output-format {} --allowedTools {ALLOWED_TOOLS} --permission-mode acceptEdits",
+            "claude --print {}--output-format {} --allowedTools {allowed_tools} --permission-mode {permission_mode}",
             if track_tool_uses { "--verbose " } else { "" },
             output_format,
         );
 
+        if !disallowed_tools.is_empty() {
+            command.arg("--disallowedTools").arg(&disallowed_tools);
+            log_cmd.push_str(&format!(" --disallowedTools {}", disallowed_tools));
+        }
+
+        if let Some(mcp) = &self.mcp {
+            command.arg("--mcp-config").arg(mcp.config_path());
+            log_cmd.push_str(&format!(" --mcp-config {}", mcp.config_path().display()));
+        }
+
         if let Some(ref system_msg) = request.system_message {
             command.arg("--append-system-prompt").arg(system_msg);
             log_cmd.push_str(&format!(
@@ -233,17 +587,26 @@ impl ClaudeCodeInterface {
 
         command
             .arg("--model")
-            .arg("sonnet")
+            .arg(model)
             .arg("--")
             .arg(&contextual_prompt);
 
+        if let Some(dump_path) = request.context.get(DUMP_CONTEXT_KEY) {
+            self.dump_context(
+                Path::new(dump_path),
+                request,
+                &format!("{} --model {} -- <prompt>", log_cmd, model),
+                &contextual_prompt,
+            );
+        }
+
         command
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null());
 
         logger
-            .log_command_start(&ctx, &format!("{} --model sonnet -- <prompt>", log_cmd))
+            .log_command_start(&ctx, &format!("{} --model {} -- <prompt>", log_cmd, model))
             .await
             .map_err(|e| ClaudeError::Unknown(format!("Failed to log command start: {}", e)))?;
 
@@ -259,12 +622,16 @@ impl ClaudeCodeInterface {
             .await
             .ok();
 
+        logger.save_prompt(&ctx, &contextual_prompt).await.ok();
+
         if logger.is_command_tracking_enabled() {
             let full_command = format!(
-                "#!/bin/bash\n# Claude Code Command\n# Task ID: {}\n# Generated: {}\n# Provider: claude\n# Model: sonnet\n\n{} --model sonnet -- {}\n",
+                "#!/bin/bash\n# Claude Code Command\n# Task ID: {}\n# Generated: {}\n# Provider: claude\n# Model: {}\n\n{} --model {} -- {}\n",
                 request.id,
                 Utc::now().format("%Y-%m-%d %H:%M:%S%.3f UTC"),
+                model,
                 log_cmd,
+                model,
                 shell_escape::escape(contextual_prompt.clone().into())
             );
             logger.save_command_script(&ctx, &full_command).await.ok();
@@ -272,24 +639,35 @@ impl ClaudeCodeInterface {
 
         let output = if self.config.show_subprocess_output {
             tracing::info!("Streaming subprocess output to terminal...");
-            self.execute_with_streaming(command).await?
+            self.execute_with_streaming(request.id, command).await
         } else {
-            command.output().await.map_err(|e| {
-                let error_msg = format!("Failed to execute claude command: {}", e);
-                let logger_clone = logger.clone();
-                let ctx_clone = ctx.clone();
-                let error_msg_clone = error_msg.clone();
-                tokio::spawn(async move {
-                    logger_clone
-                        .log_error(&ctx_clone, &error_msg_clone)
-                        .await
-                        .ok();
-                });
-                ClaudeError::Unknown(error_msg)
-            })?
+            self.execute_captured(request.id, command).await
+        };
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                logger.log_error(&ctx, &e.to_string()).await.ok();
+                self.record_interaction(
+                    logger,
+                    &ctx,
+                    PayloadBytes {
+                        sent: bytes_sent,
+                        received: 0,
+                    },
+                    None,
+                    None,
+                    Some(e.to_string()),
+                )
+                .await;
+                return Err(e);
+            }
         };
 
         let execution_time = start_time.elapsed();
+        let payload = PayloadBytes {
+            sent: bytes_sent,
+            received: output.stdout.len() as u64,
+        };
 
         logger
         .log_event(
@@ -311,6 +689,8 @@ impl ClaudeCodeInterface {
         if track_tool_uses
             && let Ok(raw_tool_uses) = self.extract_tool_uses_from_stream(&output.stdout)
         {
+            // Results arrive separately, keyed by the id of their tool use
+            let mut tool_results = extract_tool_results_from_stream(&output.stdout);
             let tool_uses: Vec<ToolUse> = raw_tool_uses
                 .into_iter()
                 .map(|tool_json| ToolUse {
@@ -323,16 +703,72 @@ impl ClaudeCodeInterface {
                         .get("input")
                         .cloned()
                         .unwrap_or(serde_json::json!({})),
-                    output: None, // Claude stream doesn't include output in tool_use
+                    output: tool_json
+                        .get("id")
+                        .and_then(|id| id.as_str())
+                        .and_then(|id| tool_results.remove(id)),
                     timestamp: Utc::now(),
                 })
                 .collect();
 
+            for tool_use in &tool_uses {
+                if let Some((server, tool)) = crate::mcp::parse_tool_name(&tool_use.tool_name) {
+                    logger
+                        .log_event(
+                            &ctx,
+                            &format!(
+                                "MCP tool {}/{} called with {}",
+                                server, tool, tool_use.input
+                            ),
+                        )
+                        .await
+                        .ok();
+                }
+            }
+
             if !tool_uses.is_empty() {
                 logger.save_tool_uses(&ctx, &tool_uses).await.ok();
             }
         }
 
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reported = self.reported_result(&stdout, track_tool_uses);
+        let reported_error = reported
+            .as_ref()
+            .filter(|json| json.get("is_error").and_then(|e| e.as_bool()) == Some(true))
+            .and_then(|json| json.get("result").and_then(|r| r.as_str()));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let failed_stderr = (!output.status.success()).then_some(stderr.as_ref());
+        if let Some(message) = [reported_error, failed_stderr]
+            .into_iter()
+            .flatten()
+            .find(|message| is_context_overflow(message))
+        {
+            let (current, max) = overflow_token_counts(message).unwrap_or_else(|| {
+                (
+                    self.estimate_tokens(&contextual_prompt)
+                        + request
+                            .system_message
+                            .as_deref()
+                            .map(|message| self.estimate_tokens(message))
+                            .unwrap_or(0),
+                    self.config.session_config.context_window_size as u64,
+                )
+            });
+            let error = ClaudeError::ContextTooLarge { current, max };
+            logger.log_error(&ctx, &error.to_string()).await.ok();
+            self.record_interaction(
+                logger,
+                &ctx,
+                payload,
+                None,
+                output.status.code(),
+                Some(error.to_string()),
+            )
+            .await;
+            return Err(error);
+        }
+
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             let error_msg = format!(
@@ -342,10 +778,18 @@ impl ClaudeCodeInterface {
             );
 
             logger.log_error(&ctx, &error_msg).await.ok();
+            self.record_interaction(
+                logger,
+                &ctx,
+                payload,
+                None,
+                output.status.code(),
+                Some(error_msg.clone()),
+            )
+            .await;
             return Err(ClaudeError::Unknown(error_msg));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
         let response_text = if stdout.trim().is_empty() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             if stderr.trim().is_empty() {
@@ -377,10 +821,18 @@ impl ClaudeCodeInterface {
         let input_tokens = self.estimate_tokens(&request.description);
         let output_tokens = self.estimate_tokens(&response_text);
         let total_tokens = input_tokens + output_tokens;
+        let token_breakdown =
+            self.token_breakdown(request, history_tokens, output_tokens, reported.as_ref());
+        // The CLI caches its stable prompt prefix itself and reports the traffic
+        let (cache_cost, cache_savings) = cache_cost_and_savings(
+            token_breakdown.cache_read_tokens,
+            token_breakdown.cache_creation_tokens,
+        );
         let estimated_cost = self
             .usage_tracker
             .estimate_cost_for_tokens(input_tokens, output_tokens)
-            .await;
+            .await
+            + cache_cost;
 
         logger
             .log_completion(
@@ -394,24 +846,147 @@ impl ClaudeCodeInterface {
             .await
             .ok();
 
+        let token_usage = TokenUsage {
+            input_tokens,
+            output_tokens,
+            total_tokens,
+            estimated_cost,
+            cache_read_tokens: token_breakdown.cache_read_tokens,
+            cache_creation_tokens: token_breakdown.cache_creation_tokens,
+            cache_savings,
+        };
+        self.record_interaction(
+            logger,
+            &ctx,
+            payload,
+            Some(&token_usage),
+            output.status.code(),
+            None,
+        )
+        .await;
+
         Ok(TaskResponse {
             task_id: request.id,
             response_text,
             tool_uses: vec![],
-            token_usage: TokenUsage {
-                input_tokens,
-                output_tokens,
-                total_tokens,
-                estimated_cost,
-            },
+            token_usage,
+            token_breakdown,
             execution_time,
-            model_used: "sonnet".to_string(),
+            model_used: model.to_string(),
         })
     }
 
     fn estimate_tokens(&self, text: &str) -> u64 {
-        // Simple token estimation: roughly 4 characters per token
-        (text.len() as f64 / 4.0).ceil() as u64
+        token_breakdown::estimate_tokens(text)
+    }
+
+    /// Write exactly what is sent for a request, for `--dump-context`
+    fn dump_context(&self, path: &Path, request: &TaskRequest, command: &str, prompt: &str) {
+        let mut dump = format!("# Context sent for task {}\n\n", request.id);
+        dump.push_str(&format!("## Command\n\n```\n{}\n```\n\n", command));
+        if let Some(system_message) = &request.system_message {
+            dump.push_str("## System prompt (--append-system-prompt)\n\n");
+            dump.push_str(system_message);
+            dump.push_str("\n\n");
+        }
+        for piece in &self.context {
+            if let ContextSource::Pinned(pinned) = &piece.source {
+                dump.push_str(&format!(
+                    "## Instructions file: pinned {}\n\n",
+                    pinned.display()
+                ));
+                dump.push_str(&piece.content);
+                dump.push_str("\n\n");
+            }
+        }
+        dump.push_str("## Prompt\n\n");
+        dump.push_str(prompt);
+        dump.push('\n');
+
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, dump));
+        match written {
+            Ok(()) => eprintln!(
+                "📝 Context for task {} written to {}",
+                request.id,
+                path.display()
+            ),
+            Err(e) => tracing::warn!("Failed to write context dump {:?}: {}", path, e),
+        }
+    }
+
+    /// Final result object of `json` or `stream-json` output
+    fn reported_result(&self, stdout: &str, stream: bool) -> Option<serde_json::Value> {
+        if !stream {
+            return serde_json::from_str(stdout.trim()).ok();
+        }
+        stdout
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line.trim()).ok())
+            .find(|json| json.get("type").and_then(|t| t.as_str()) == Some("result"))
+    }
+
+    /// Split a request's tokens by where they came from
+    ///
+    /// Imported context is part of the system message, so it is subtracted
+    /// from the system prompt rather than counted twice.
+    fn token_breakdown(
+        &self,
+        request: &TaskRequest,
+        history_tokens: u64,
+        output_tokens: u64,
+        reported: Option<&serde_json::Value>,
+    ) -> TokenBreakdown {
+        let mut context = Vec::new();
+        if history_tokens > 0 {
+            context.push(ContextTokens {
+                source: ContextSource::History,
+                tokens: history_tokens,
+            });
+        }
+        context.extend(self.context.iter().map(ContextPiece::tokens));
+
+        let embedded: u64 = context
+            .iter()
+            .filter(|context| context.source.in_system_prompt())
+            .map(|context| context.tokens)
+            .sum();
+        let system_prompt_tokens = request
+            .system_message
+            .as_deref()
+            .map(|message| self.estimate_tokens(message).saturating_sub(embedded))
+            .unwrap_or(0);
+
+        let breakdown = TokenBreakdown {
+            system_prompt_tokens,
+            context,
+            task_tokens: self.estimate_tokens(&request.description),
+            output_tokens,
+            ..Default::default()
+        };
+        match reported {
+            Some(reported) => breakdown.with_reported_usage(reported),
+            None => breakdown,
+        }
+    }
+
+    /// `--allowedTools` and `--permission-mode` values for `request`
+    ///
+    /// The configured tools, plus those of the MCP servers, unless the task
+    /// names its own; less any the task disallows.
+    fn tool_access(&self, request: &TaskRequest) -> (String, &'static str) {
+        let mut allowed = self.config.allowed_tools.clone();
+        if let Some(mcp) = &self.mcp {
+            allowed.extend(mcp.allowed_tools());
+        }
+        let permissions = &request.tool_permissions;
+        let mode = permissions
+            .permission_mode
+            .unwrap_or(self.config.permission_mode);
+        (permissions.allowed_tools(&allowed).join(","), mode.as_str())
     }
 
     /// Extract tool uses from stream-json format (JSONL)
@@ -470,97 +1045,81 @@ impl ClaudeCodeInterface {
         ))
     }
 
+    /// Execute command capturing its output, registered for suspension
+    async fn execute_captured(
+        &self,
+        task_id: Uuid,
+        mut command: Command,
+    ) -> Result<std::process::Output, ClaudeError> {
+        let child = command.spawn().map_err(|e| {
+            ClaudeError::Unknown(format!("Failed to execute claude command: {}", e))
+        })?;
+        let pid = child.id();
+        let registration = pid.map(|pid| self.processes.register(task_id, pid));
+        self.wait_with_timeout(
+            registration.as_ref(),
+            read_output(child, false, || self.record_activity(task_id)),
+        )
+        .await
+    }
+
     /// Execute command with streaming output to terminal
     async fn execute_with_streaming(
         &self,
+        task_id: Uuid,
         mut command: Command,
     ) -> Result<std::process::Output, ClaudeError> {
-        use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
-
-        let mut child = command.spawn().map_err(|e| {
+        let child = command.spawn().map_err(|e| {
             let error_msg = format!("Failed to spawn claude command: {}", e);
             ClaudeError::Unknown(error_msg)
         })?;
+        let pid = child.id();
+        let registration = pid.map(|pid| self.processes.register(task_id, pid));
+        self.wait_with_timeout(
+            registration.as_ref(),
+            read_output(child, true, || self.record_activity(task_id)),
+        )
+        .await
+    }
 
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| ClaudeError::Unknown("Failed to capture stdout".to_string()))?;
-        let stderr = child
-            .stderr
-            .take()
-            .ok_or_else(|| ClaudeError::Unknown("Failed to capture stderr".to_string()))?;
-
-        let mut stdout_reader = BufReader::new(stdout);
-        let mut stderr_reader = BufReader::new(stderr);
-
-        let mut stdout_buffer = Vec::new();
-        let mut stderr_buffer = Vec::new();
-        let mut stdout_line = String::new();
-        let mut stderr_line = String::new();
+    /// Count output from the CLI as progress, so a task that keeps
+    /// producing it is not taken for stalled
+    fn record_activity(&self, task_id: Uuid) {
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.record_activity(task_id);
+        }
+    }
 
-        // Stream output line by line
-        loop {
-            tokio::select! {
-                result = stdout_reader.read_line(&mut stdout_line) => {
-                    match result {
-                        Ok(0) => break, // EOF
-                        Ok(_) => {
-                            // Print to terminal
-                            print!("{}", stdout_line);
-                            use std::io::Write;
-                            let _ = std::io::stdout().flush();
-
-                            // Save to buffer
-                            stdout_buffer.extend_from_slice(stdout_line.as_bytes());
-                            stdout_line.clear();
-                        }
-                        Err(e) => {
-                            tracing::warn!("Error reading stdout: {}", e);
-                            break;
-                        }
-                    }
-                }
-                result = stderr_reader.read_line(&mut stderr_line) => {
-                    match result {
-                        Ok(0) => {}, // EOF on stderr
-                        Ok(_) => {
-                            // Print to terminal (stderr)
-                            eprint!("{}", stderr_line);
-                            use std::io::Write;
-                            let _ = std::io::stderr().flush();
-
-                            // Save to buffer
-                            stderr_buffer.extend_from_slice(stderr_line.as_bytes());
-                            stderr_line.clear();
-                        }
-                        Err(e) => {
-                            tracing::warn!("Error reading stderr: {}", e);
-                        }
-                    }
+    /// Wait for the registered CLI subprocess under the configured request
+    /// timeout, which stops counting while the task is paused
+    ///
+    /// On expiry the wait is abandoned, which kills the CLI, and the rest of
+    /// its process group (tools it started) is killed too.
+    async fn wait_with_timeout(
+        &self,
+        registration: Option<&ProcessRegistration>,
+        wait: impl std::future::Future<Output = Result<std::process::Output, ClaudeError>>,
+    ) -> Result<std::process::Output, ClaudeError> {
+        let Some(timeout) = self.config.request_timeout() else {
+            return wait.await;
+        };
+        let result = match registration {
+            Some(registration) => registration.timeout(timeout, wait).await,
+            None => tokio::time::timeout(timeout, wait).await.ok(),
+        };
+        match result {
+            Some(result) => result,
+            None => {
+                if let Some(pid) = registration.map(ProcessRegistration::pid)
+                    && let Err(e) = kill_process_group(pid).await
+                {
+                    tracing::debug!("Process group of claude {} already gone: {}", pid, e);
                 }
+                Err(ClaudeError::Timeout {
+                    timeout_secs: timeout.as_secs(),
+                })
             }
         }
-
-        // Read any remaining stderr
-        let mut remaining_stderr = Vec::new();
-        if let Ok(n) = stderr_reader.read_to_end(&mut remaining_stderr).await
-            && n > 0
-        {
-            eprint!("{}", String::from_utf8_lossy(&remaining_stderr));
-            stderr_buffer.extend_from_slice(&remaining_stderr);
-        }
-
-        // Wait for process to complete
-        let status = child.wait().await.map_err(|e| {
-            ClaudeError::Unknown(format!("Failed to wait for child process: {}", e))
-        })?;
-
-        Ok(std::process::Output {
-            status,
-            stdout: stdout_buffer,
-            stderr: stderr_buffer,
-        })
     }
 
     async fn get_or_create_session(&self) -> Result<ClaudeSession, ClaudeError> {
@@ -617,18 +1176,58 @@ impl ClaudeCodeInterface {
             priority: TaskPriority::Medium,
             estimated_tokens: Some(self.estimate_tokens(description)),
             system_message: None,
+            tool_permissions: Default::default(),
         }
     }
 
     pub async fn process_task(&self, task: &Task) -> Result<Task, ClaudeError> {
+        self.process_task_with_model(task, None).await
+    }
+
+    /// Process a task with `model` instead of [`DEFAULT_MODEL`]
+    pub async fn process_task_with_model(
+        &self,
+        task: &Task,
+        model: Option<&str>,
+    ) -> Result<Task, ClaudeError> {
+        self.process_task_with_system_message(task, model, self.system_message())
+            .await
+    }
+
+    /// Process a task with `model` and an explicit system message
+    ///
+    /// For callers that rewrite the system message before it is sent, such as
+    /// the prompt pseudonymizer.
+    pub async fn process_task_with_system_message(
+        &self,
+        task: &Task,
+        model: Option<&str>,
+        system_message: Option<String>,
+    ) -> Result<Task, ClaudeError> {
+        let mut context = std::collections::HashMap::new();
+        if let Some(model) = model {
+            context.insert(MODEL_KEY.to_string(), model.to_string());
+        }
+        if let Some(target) = &self.config.dump_context
+            && (target.eq_ignore_ascii_case(&task.title)
+                || task.id.to_string().starts_with(target.as_str()))
+        {
+            let path = env::context_dump_file_path(&self.workspace_root, &task.id.to_string());
+            context.insert(
+                DUMP_CONTEXT_KEY.to_string(),
+                path.to_string_lossy().into_owned(),
+            );
+        }
+
         let request = TaskRequest {
             id: task.id,
             task_type: "task_processing".to_string(),
             description: task.description.clone(),
-            context: std::collections::HashMap::new(),
+            context,
             priority: TaskPriority::Medium, // Default priority for now
             estimated_tokens: Some(self.estimate_tokens(&task.description)),
-            system_message: None,
+            system_message,
+            tool_permissions: task.metadata.tool_permissions.clone(),
         };
 
         // Create logger for this task
@@ -638,7 +1237,8 @@ impl ClaudeCodeInterface {
 
         let logger_config = crate::llm::provider_logger::ProviderLoggerConfig {
             enabled: true,
-            track_tool_uses: self.config.usage_tracking.track_tool_uses,
+            // MCP tool calls are only recorded from the tool stream
+            track_tool_uses: self.config.usage_tracking.track_tool_uses || self.mcp.is_some(),
             track_commands: true,
             max_preview_chars: 500,
         };
@@ -648,6 +1248,16 @@ impl ClaudeCodeInterface {
             .map_err(|e| ClaudeError::Unknown(format!("Failed to create logger: {}", e)))?;
 
         let response = self.execute_task_request(request, &logger).await?;
+        self.usage_tracker
+            .record_tag_usage(&task.metadata.cost_tags(), &response.token_usage)
+            .await;
+        if self.config.show_subprocess_output {
+            eprintln!(
+                "\n📊 Token breakdown for '{}':\n{}",
+                task.title,
+                response.token_breakdown.render()
+            );
+        }
 
         // Create updated task with response
         let mut updated_task = task.clone();
@@ -657,6 +1267,7 @@ impl ClaudeCodeInterface {
                 output: serde_json::json!({
                     "response": response.response_text,
                     "token_usage": response.token_usage,
+                    "token_breakdown": response.token_breakdown,
                     "model_used": response.model_used
                 }),
                 files_created: Vec::new(),
@@ -669,6 +1280,63 @@ impl ClaudeCodeInterface {
         Ok(updated_task)
     }
 
+    /// Conversation contexts and usage statistics, for checkpointing
+    pub async fn conversation_state(&self) -> ConversationState {
+        ConversationState {
+            contexts: self.context_manager.export_contexts().await,
+            usage: self.usage_tracker.snapshot().await,
+        }
+    }
+
+    /// Continue the conversation and usage statistics of a previous run
+    ///
+    /// The most recently active conversation becomes the shared session, so
+    /// the next task sees its history.
+    pub async fn restore_conversation_state(&self, state: ConversationState) {
+        let latest = state
+            .contexts
+            .iter()
+            .max_by_key(|context| context.last_activity)
+            .map(|context| (context.session_id, context.messages.len() as u32));
+        self.context_manager.import_contexts(state.contexts).await;
+        self.usage_tracker.restore(state.usage).await;
+
+        if let Some((id, message_count)) = latest {
+            let mut pool = self.session_pool.lock().await;
+            pool.active_sessions.clear();
+            pool.active_sessions.insert(
+                id,
+                ClaudeSession {
+                    id,
+                    created_at: Utc::now(),
+                    last_used: Utc::now(),
+                    message_count,
+                    is_busy: false,
+                },
+            );
+        }
+    }
+
+    /// Usage per cost tag for tasks processed by this interface
+    pub async fn tag_usage(&self) -> Vec<crate::claude::usage_tracker::TagUsage> {
+        self.usage_tracker.get_tag_usage().await
+    }
+
+    /// Usage recorded by this interface across all sessions
+    pub async fn total_usage(&self) -> crate::claude::usage_tracker::TotalUsage {
+        self.usage_tracker.get_total_usage().await
+    }
+
+    /// Tokens consumed by this interface during the current UTC day
+    pub async fn tokens_used_today(&self) -> u64 {
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        self.usage_tracker
+            .get_daily_usage(&today)
+            .await
+            .map(|usage| usage.total_tokens)
+            .unwrap_or(0)
+    }
+
     pub async fn get_interface_status(&self) -> ClaudeInterfaceStatus {
         let rate_status = self.rate_limiter.get_status().await;
         let usage_summary = self.usage_tracker.get_usage_summary(1).await;
@@ -691,27 +1359,94 @@ impl ClaudeCodeInterface {
     }
 
     /// Build a contextual prompt that includes conversation history for better continuity
+    ///
+    /// History is fitted into the context budget left after the system
+    /// prompt, the caller's context and the request itself. Also returns
+    /// the estimated tokens spent on the history.
     async fn build_contextual_prompt(
         &self,
         session_id: SessionId,
-        current_request: &str,
-    ) -> String {
-        // Get existing conversation context
-        if let Some(context) = self.context_manager.get_context(session_id).await
-            && !context.messages.is_empty()
+        request: &TaskRequest,
+        scope: ContextScope,
+    ) -> (String, u64) {
+        let current_request = request.description.as_str();
+
+        // Get the conversation history relevant to this task
+        if let Some(mut relevant) = self
+            .context_manager
+            .relevant_history(session_id, current_request)
+            .await
         {
-            // Format the conversation history
-            let history = self.format_conversation_history(&context.messages);
+            // After an overflow, keep the previous turn and then only the summary;
+            // the last turn is the current request
+            match scope {
+                ContextScope::Full | ContextScope::WithoutRetrieved => {}
+                ContextScope::RecentHistory => relevant.keep_recent_turns(2),
+                ContextScope::SummaryOnly => relevant.keep_recent_turns(0),
+            }
+            if relevant.messages.is_empty() && relevant.summary.is_none() {
+                return (current_request.to_string(), 0);
+            }
+
+            // Format the running summary and the selected turns
+            let mut history = self.format_conversation_history(&relevant.messages);
+            if relevant.turns_omitted > 0 {
+                history.insert_str(
+                    0,
+                    &format!(
+                        "[{} less relevant earlier turn(s) omitted]\n\n",
+                        relevant.turns_omitted
+                    ),
+                );
+            }
+            if let Some(summary) = relevant.summary {
+                history.insert_str(
+                    0,
+                    &format!("Summary of earlier conversation:\n{}\n\n", summary),
+                );
+            }
+
+            // Imported context is already counted in the system message
+            let fixed_tokens = request
+                .system_message
+                .as_deref()
+                .map(|message| self.estimate_tokens(message))
+                .unwrap_or(0)
+                + self
+                    .context
+                    .iter()
+                    .filter(|piece| !piece.source.in_system_prompt())
+                    .map(|piece| piece.tokens().tokens)
+                    .sum::<u64>()
+                + self.estimate_tokens(current_request);
+            let Some(history) = self
+                .context_budget
+                .fit(
+                    fixed_tokens,
+                    vec![ContextPiece {
+                        source: ContextSource::History,
+                        content: history,
+                    }],
+                )
+                .pop()
+            else {
+                return (current_request.to_string(), 0);
+            };
+            let history = history.content;
 
             // Build contextual prompt with history and current request
-            return format!(
+            let prompt = format!(
                 "Previous conversation context:\n{}\n\n--- Current Task ---\n{}",
                 history, current_request
             );
+            let history_tokens = self
+                .estimate_tokens(&prompt)
+                .saturating_sub(self.estimate_tokens(current_request));
+            return (prompt, history_tokens);
         }
 
         // If no context exists, just return the current request
-        current_request.to_string()
+        (current_request.to_string(), 0)
     }
 
     /// Format conversation messages into a readable conversation history
@@ -8,9 +8,13 @@
 //!
 //! - **[`ClaudeCodeInterface`]**: Main interface for Claude Code interactions
 //! - **`RateLimiter`**: Token bucket rate limiting with adaptive backoff
+//! - **`SharedRateLimiter`**: Machine-wide bucket shared across processes
+//! - **`TranscriptImporter`**: Summaries of earlier interactive Claude Code sessions
 //! - **`ContextManager`**: Conversation context optimization and compression
+//! - **`ContextBudgetConfig`**: Total and per-source caps on injected context
 //! - **`ErrorRecoveryManager`**: Circuit breaker and retry mechanisms
 //! - **`UsageTracker`**: Cost tracking and performance analytics
+//! - **`TokenBreakdown`**: Per-task token split by prompt component
 //!
 //! ## Key Features
 //!
@@ -73,12 +77,24 @@
 //! }
 //! ```
 
+/// Budget policy for context injected into task prompts.
+///
+/// Caps total input tokens and each context source's share, keeping
+/// history, then pinned files, then imported context.
+pub mod context_budget;
+
 /// Conversation context optimization and management.
 ///
 /// Handles intelligent context compression, relevance filtering,
 /// and conversation history optimization for efficient LLM interactions.
 pub mod context_manager;
 
+/// Progressive context reduction after a context overflow.
+///
+/// Retries requests the provider rejects as too long with imported context,
+/// then older history, then all turns but the summary dropped.
+pub mod downscope;
+
 /// Error recovery and circuit breaker implementation.
 ///
 /// Provides robust error handling with circuit breaker patterns,
@@ -97,6 +113,24 @@ pub mod interface;
 /// while maximizing throughput and minimizing latency.
 pub mod rate_limiter;
 
+/// Machine-wide rate limiting shared by concurrent aca processes.
+///
+/// Keeps a per-minute token and request bucket in a lock-protected file so
+/// several instances using one provider account stay under its limits.
+pub mod shared_limiter;
+
+/// Import of Claude Code's local session transcripts.
+///
+/// Summarizes the interactive sessions a user ran in a workspace so aca
+/// tasks start with what was already tried and decided.
+pub mod transcript_import;
+
+/// Per-task token accounting by prompt component.
+///
+/// Splits each request's tokens into system prompt, injected context by
+/// source, task and output, plus reported prompt cache hits.
+pub mod token_breakdown;
+
 /// Core types and configuration structures.
 ///
 /// Defines all data types, configuration structures, and enums
@@ -112,9 +146,14 @@ pub mod usage_tracker;
 #[cfg(test)]
 pub mod tests;
 
-pub use context_manager::ContextManager;
+pub use context_budget::{ContextBudgetConfig, ContextPiece};
+pub use context_manager::{ContextManager, RelevantHistory};
+pub use downscope::{ContextScope, is_context_overflow};
 pub use error_recovery::ErrorRecoveryManager;
 pub use interface::ClaudeCodeInterface;
 pub use rate_limiter::RateLimiter;
+pub use shared_limiter::SharedRateLimiter;
+pub use token_breakdown::{ContextSource, ContextTokens, TokenBreakdown};
+pub use transcript_import::{ClaudeTranscript, TranscriptImporter};
 pub use types::*;
 pub use usage_tracker::UsageTracker;
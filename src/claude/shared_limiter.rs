@@ -0,0 +1,266 @@
+use crate::claude::types::{ClaudeError, RateLimitConfig, SharedRateLimitConfig};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Usage of the current one-minute window, as stored in the bucket file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct SharedBucket {
+    window_start: DateTime<Utc>,
+    tokens: u64,
+    requests: u64,
+}
+
+/// Per-minute token and request bucket shared by processes through a file
+///
+/// Every acquisition takes an exclusive lock on the file, so the read,
+/// check and update of the bucket are atomic across processes.
+#[derive(Debug, Clone)]
+pub struct SharedRateLimiter {
+    path: PathBuf,
+    max_tokens_per_minute: u64,
+    max_requests_per_minute: u64,
+    max_wait: Duration,
+}
+
+impl SharedRateLimiter {
+    /// Limiter for an enabled config; unset limits fall back to `limits`
+    pub fn from_config(config: &SharedRateLimitConfig, limits: &RateLimitConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let path = config
+            .state_file
+            .clone()
+            .or_else(crate::env::shared_rate_limit_file_path)?;
+        Some(Self::new(
+            path,
+            config
+                .max_tokens_per_minute
+                .unwrap_or(limits.max_tokens_per_minute),
+            config
+                .max_requests_per_minute
+                .unwrap_or(limits.max_requests_per_minute),
+            Duration::from_secs(config.max_wait_secs),
+        ))
+    }
+
+    pub fn new(
+        path: PathBuf,
+        max_tokens_per_minute: u64,
+        max_requests_per_minute: u64,
+        max_wait: Duration,
+    ) -> Self {
+        Self {
+            path,
+            max_tokens_per_minute,
+            max_requests_per_minute,
+            max_wait,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Wait until the shared bucket has room for `tokens`, then take them
+    pub async fn acquire(&self, tokens: u64) -> Result<(), ClaudeError> {
+        let started = tokio::time::Instant::now();
+        loop {
+            let limiter = self.clone();
+            let wait = tokio::task::spawn_blocking(move || limiter.try_acquire(tokens, Utc::now()))
+                .await
+                .map_err(|e| ClaudeError::Unknown(e.to_string()))?
+                .map_err(|e| {
+                    ClaudeError::Unknown(format!(
+                        "Shared rate limit file {}: {}",
+                        self.path.display(),
+                        e
+                    ))
+                })?;
+
+            let Some(wait) = wait else {
+                return Ok(());
+            };
+            if started.elapsed() + wait > self.max_wait {
+                return Err(ClaudeError::RateLimit {
+                    message: format!(
+                        "Machine-wide rate limit still exhausted after {}s",
+                        started.elapsed().as_secs()
+                    ),
+                    reset_time: Utc::now() + wait,
+                });
+            }
+            info!(
+                "Machine-wide rate limit reached, waiting {:.1}s",
+                wait.as_secs_f64()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take `tokens` from the bucket at `now`, or return how long to wait
+    ///
+    /// A request larger than the whole per-minute budget is let through on
+    /// an otherwise empty window instead of waiting forever.
+    pub fn try_acquire(
+        &self,
+        tokens: u64,
+        now: DateTime<Utc>,
+    ) -> std::io::Result<Option<Duration>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        file.lock()?;
+
+        let window = chrono::Duration::minutes(1);
+        let mut bucket = match read_bucket(&mut file)? {
+            Some(bucket) if now - bucket.window_start < window => bucket,
+            _ => SharedBucket {
+                window_start: now,
+                tokens: 0,
+                requests: 0,
+            },
+        };
+
+        let empty = bucket.requests == 0;
+        let fits = bucket.requests < self.max_requests_per_minute
+            && bucket.tokens + tokens <= self.max_tokens_per_minute;
+        if !fits && !empty {
+            let wait = (bucket.window_start + window - now)
+                .to_std()
+                .unwrap_or_default();
+            debug!(
+                "Shared bucket full ({} tokens, {} requests this minute)",
+                bucket.tokens, bucket.requests
+            );
+            return Ok(Some(wait.max(Duration::from_millis(100))));
+        }
+
+        bucket.tokens += tokens;
+        bucket.requests += 1;
+        write_bucket(&mut file, &bucket)?;
+        Ok(None)
+    }
+
+    /// How long until the bucket has room again if it is exhausted at `now`,
+    /// without taking anything from it
+    pub fn exhausted_for(&self, now: DateTime<Utc>) -> std::io::Result<Option<Duration>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let window = chrono::Duration::minutes(1);
+        let Some(bucket) = read_bucket(&mut file)?.filter(|b| now - b.window_start < window) else {
+            return Ok(None);
+        };
+        if bucket.requests < self.max_requests_per_minute
+            && bucket.tokens < self.max_tokens_per_minute
+        {
+            return Ok(None);
+        }
+        Ok(Some(
+            (bucket.window_start + window - now)
+                .to_std()
+                .unwrap_or_default(),
+        ))
+    }
+}
+
+fn read_bucket(file: &mut File) -> std::io::Result<Option<SharedBucket>> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    // A missing or corrupt bucket just starts a new window
+    Ok(serde_json::from_str(&contents).ok())
+}
+
+fn write_bucket(file: &mut File, bucket: &SharedBucket) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&serde_json::to_vec(bucket)?)?;
+    file.sync_data()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_bucket_limits_across_limiters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache/rate_limit.json");
+        let first = SharedRateLimiter::new(path.clone(), 1000, 10, Duration::from_secs(60));
+        let second = SharedRateLimiter::new(path, 1000, 10, Duration::from_secs(60));
+        let now = Utc::now();
+
+        assert_eq!(first.try_acquire(600, now).unwrap(), None);
+        // The second process sees the first one's usage
+        let wait = second
+            .try_acquire(600, now + chrono::Duration::seconds(20))
+            .unwrap();
+        assert_eq!(wait, Some(Duration::from_secs(40)));
+        assert_eq!(
+            second
+                .try_acquire(400, now + chrono::Duration::seconds(20))
+                .unwrap(),
+            None
+        );
+
+        // The next minute starts a fresh window
+        assert_eq!(
+            second
+                .try_acquire(600, now + chrono::Duration::seconds(61))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_request_limit_and_oversized_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let limiter =
+            SharedRateLimiter::new(dir.path().join("bucket.json"), 100, 1, Duration::ZERO);
+        let now = Utc::now();
+
+        assert_eq!(limiter.exhausted_for(now).unwrap(), None);
+        // Larger than the per-minute budget, but alone in its window
+        assert_eq!(limiter.try_acquire(500, now).unwrap(), None);
+        assert!(limiter.try_acquire(1, now).unwrap().is_some());
+
+        // Peeking reports the stall without consuming anything
+        let later = now + chrono::Duration::seconds(45);
+        assert_eq!(
+            limiter.exhausted_for(later).unwrap(),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            limiter
+                .exhausted_for(now + chrono::Duration::seconds(60))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acquire_gives_up_after_max_wait() {
+        let dir = tempfile::tempdir().unwrap();
+        let limiter =
+            SharedRateLimiter::new(dir.path().join("bucket.json"), 100, 1, Duration::ZERO);
+        limiter.acquire(10).await.unwrap();
+        assert!(matches!(
+            limiter.acquire(10).await,
+            Err(ClaudeError::RateLimit { .. })
+        ));
+    }
+}
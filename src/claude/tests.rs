@@ -23,6 +23,22 @@ async fn create_test_logger() -> ProviderLogger {
         .expect("Failed to create test logger")
 }
 
+#[test]
+fn test_request_timeout() {
+    let config = ClaudeConfig::default();
+    assert_eq!(config.request_timeout(), Some(Duration::from_secs(3600)));
+
+    let unlimited = ClaudeConfig {
+        request_timeout_secs: 0,
+        ..ClaudeConfig::default()
+    };
+    assert_eq!(unlimited.request_timeout(), None);
+    assert_eq!(
+        ClaudeError::Timeout { timeout_secs: 60 }.to_string(),
+        "Claude CLI did not finish within 60s"
+    );
+}
+
 #[tokio::test]
 async fn test_claude_interface_creation() {
     let config = ClaudeConfig::default();
@@ -47,6 +63,7 @@ async fn test_task_request_execution() {
         priority: TaskPriority::Medium,
         estimated_tokens: Some(100),
         system_message: None,
+        tool_permissions: Default::default(),
     };
 
     let logger = create_test_logger().await;
@@ -78,6 +95,7 @@ async fn test_rate_limiting() {
         priority: TaskPriority::Medium,
         estimated_tokens: Some(50),
         system_message: None,
+        tool_permissions: Default::default(),
     };
 
     let logger = create_test_logger().await;
@@ -93,6 +111,7 @@ async fn test_rate_limiting() {
         priority: TaskPriority::Medium,
         estimated_tokens: Some(30),
         system_message: None,
+        tool_permissions: Default::default(),
     };
 
     let response2 = interface.execute_task_request(request2, &logger).await;
@@ -107,6 +126,7 @@ async fn test_rate_limiting() {
         priority: TaskPriority::Medium,
         estimated_tokens: Some(30),
         system_message: None,
+        tool_permissions: Default::default(),
     };
 
     let response3 = interface.execute_task_request(request3, &logger).await;
@@ -146,6 +166,114 @@ async fn test_context_management() {
     assert_eq!(updated_context.total_tokens, 10);
 }
 
+fn chat_message(role: MessageRole, content: &str) -> ClaudeMessage {
+    ClaudeMessage {
+        id: uuid::Uuid::new_v4(),
+        role,
+        content: content.to_string(),
+        timestamp: chrono::Utc::now(),
+        token_count: Some(10),
+        metadata: std::collections::HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_relevant_history_keeps_top_turns() {
+    let mut config = ClaudeConfig::default().context_config;
+    config.max_relevant_turns = 2;
+    let context_manager = ContextManager::new(config);
+    let session_id = uuid::Uuid::new_v4();
+    context_manager.get_or_create_context(session_id).await;
+
+    let turns = [
+        ("Add a database migration for users", "Created migration"),
+        ("Write the README introduction", "README updated"),
+        ("Tune the logging format", "Logging tuned"),
+        ("Document the deployment steps", "Docs written"),
+    ];
+    for (request, reply) in turns {
+        for message in [
+            chat_message(MessageRole::User, request),
+            chat_message(MessageRole::Assistant, reply),
+        ] {
+            context_manager
+                .add_message(session_id, message)
+                .await
+                .unwrap();
+        }
+    }
+
+    let relevant = context_manager
+        .relevant_history(session_id, "Add an index to the users migration")
+        .await
+        .unwrap();
+    let contents: Vec<&str> = relevant
+        .messages
+        .iter()
+        .map(|m| m.content.as_str())
+        .collect();
+    // The matching turn plus the latest one, in conversation order
+    assert_eq!(
+        contents,
+        vec![
+            "Add a database migration for users",
+            "Created migration",
+            "Document the deployment steps",
+            "Docs written",
+        ]
+    );
+    assert_eq!(relevant.turns_omitted, 2);
+
+    // Downscoping after a context overflow keeps only the latest turns
+    let mut recent = relevant.clone();
+    recent.keep_recent_turns(1);
+    assert_eq!(recent.messages.len(), 2);
+    assert_eq!(recent.messages[0].content, "Document the deployment steps");
+    assert_eq!(recent.turns_omitted, 3);
+    recent.keep_recent_turns(0);
+    assert!(recent.messages.is_empty());
+
+    assert!(
+        context_manager
+            .relevant_history(uuid::Uuid::new_v4(), "anything")
+            .await
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_evicted_messages_are_summarized() {
+    let mut config = ClaudeConfig::default().context_config;
+    config.max_history_length = 6;
+    // Only the most recent messages survive optimization
+    config.relevance_threshold = 1.0;
+    let context_manager = ContextManager::new(config);
+    let session_id = uuid::Uuid::new_v4();
+    context_manager.get_or_create_context(session_id).await;
+
+    for idx in 0..7 {
+        context_manager
+            .add_message(
+                session_id,
+                chat_message(MessageRole::User, &format!("Step {}", idx)),
+            )
+            .await
+            .unwrap();
+    }
+
+    let context = context_manager.get_context(session_id).await.unwrap();
+    assert!(context.messages.len() < 7);
+    let summary = context.context_summary.unwrap();
+    assert!(summary.contains("- User: Step"));
+
+    let relevant = context_manager
+        .relevant_history(session_id, "Step")
+        .await
+        .unwrap();
+    assert_eq!(relevant.summary.as_deref(), Some(summary.as_str()));
+    assert!((context_manager::task_similarity("Step 3 done", "next step") - 0.5).abs() < 1e-9);
+}
+
 #[tokio::test]
 async fn test_usage_tracking() {
     let config = UsageTrackingConfig {
@@ -170,7 +298,9 @@ async fn test_usage_tracking() {
             output_tokens: 30,
             total_tokens: 80,
             estimated_cost: 0.001,
+            ..Default::default()
         },
+        token_breakdown: Default::default(),
         execution_time: Duration::from_millis(500),
         model_used: "claude-3-mock".to_string(),
     };
@@ -215,3 +345,36 @@ async fn test_simple_task_creation() {
     assert_eq!(task_request.description, "Create a test function");
     assert!(task_request.estimated_tokens.is_some());
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_steady_output_is_not_a_stall() {
+    use crate::session::{HeartbeatConfig, HeartbeatMonitor};
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let heartbeat = HeartbeatMonitor::new(
+        HeartbeatConfig {
+            stall_timeout_secs: Some(1),
+            ..Default::default()
+        },
+        "test-session".to_string(),
+        temp.path().join("heartbeat.json"),
+    );
+    let task_id = uuid::Uuid::new_v4();
+    heartbeat.task_started(task_id, "Long build");
+
+    // Runs well past the stall timeout, printing a line every 300ms
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg("for i in 1 2 3 4 5 6 7 8; do echo step $i; sleep 0.3; done")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let output = tokio::select! {
+        output = interface::read_output(child, false, || heartbeat.record_activity(task_id)) => output.unwrap(),
+        _ = heartbeat.wait_for_stall(task_id) => panic!("a task producing output was reported as stalled"),
+    };
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 8);
+}
@@ -0,0 +1,219 @@
+//! Where a task's tokens go.
+//!
+//! The prompt sent for a task is assembled from the system prompt, injected
+//! context (conversation history, pinned files from the instructions file,
+//! imported Claude Code context) and the task itself. [`TokenBreakdown`]
+//! records the estimated size of each part, plus output tokens and the
+//! prompt cache usage the provider reports, so users can see which context
+//! settings are worth tuning.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Rough token count of `text`, at about four characters per token
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.len() as f64 / 4.0).ceil() as u64
+}
+
+/// Origin of context injected into a task's prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "path", rename_all = "snake_case")]
+pub enum ContextSource {
+    /// Earlier messages replayed from the conversation context
+    History,
+    /// A pinned file written into the instructions file
+    Pinned(PathBuf),
+    /// Imported Claude Code context appended to the system prompt
+    Imported,
+}
+
+impl ContextSource {
+    /// Whether this context is sent as part of the system prompt
+    pub fn in_system_prompt(&self) -> bool {
+        matches!(self, ContextSource::Imported)
+    }
+}
+
+impl std::fmt::Display for ContextSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextSource::History => write!(f, "history"),
+            ContextSource::Pinned(path) => write!(f, "pinned {}", path.display()),
+            ContextSource::Imported => write!(f, "imported context"),
+        }
+    }
+}
+
+/// Estimated tokens contributed by one context source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextTokens {
+    pub source: ContextSource,
+    pub tokens: u64,
+}
+
+/// Token usage of one request, split by where the tokens came from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenBreakdown {
+    /// System prompt, excluding context listed in `context`
+    pub system_prompt_tokens: u64,
+    pub context: Vec<ContextTokens>,
+    /// The task description itself
+    pub task_tokens: u64,
+    pub output_tokens: u64,
+    /// Prompt tokens served from the provider's cache
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's cache
+    pub cache_creation_tokens: u64,
+}
+
+impl TokenBreakdown {
+    pub fn context_tokens(&self) -> u64 {
+        self.context.iter().map(|context| context.tokens).sum()
+    }
+
+    pub fn prompt_tokens(&self) -> u64 {
+        self.system_prompt_tokens + self.context_tokens() + self.task_tokens
+    }
+
+    /// Add another request's usage, e.g. a follow-up turn of the same task
+    pub fn merge(&mut self, other: &TokenBreakdown) {
+        self.system_prompt_tokens += other.system_prompt_tokens;
+        self.task_tokens += other.task_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        for context in &other.context {
+            match self
+                .context
+                .iter_mut()
+                .find(|existing| existing.source == context.source)
+            {
+                Some(existing) => existing.tokens += context.tokens,
+                None => self.context.push(context.clone()),
+            }
+        }
+    }
+
+    /// Record the cache usage reported in `claude --output-format json` output
+    pub fn with_reported_usage(mut self, output: &serde_json::Value) -> Self {
+        if let Some(usage) = output.get("usage") {
+            let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+            self.cache_read_tokens = field("cache_read_input_tokens");
+            self.cache_creation_tokens = field("cache_creation_input_tokens");
+        }
+        self
+    }
+
+    /// Multi-line table for verbose output
+    pub fn render(&self) -> String {
+        let prompt = self.prompt_tokens().max(1);
+        let row = |label: &str, tokens: u64| {
+            format!(
+                "  {:<28} {:>8}  {:>3}%\n",
+                label,
+                tokens,
+                tokens * 100 / prompt
+            )
+        };
+
+        let mut out = String::new();
+        out.push_str(&row("system prompt", self.system_prompt_tokens));
+        for context in &self.context {
+            out.push_str(&row(
+                &format!("context: {}", context.source),
+                context.tokens,
+            ));
+        }
+        out.push_str(&row("task", self.task_tokens));
+        out.push_str(&format!(
+            "  {:<28} {:>8}\n",
+            "prompt total",
+            self.prompt_tokens()
+        ));
+        out.push_str(&format!("  {:<28} {:>8}\n", "output", self.output_tokens));
+        if self.cache_read_tokens + self.cache_creation_tokens > 0 {
+            out.push_str(&format!(
+                "  {:<28} {:>8} read, {} written\n",
+                "prompt cache", self.cache_read_tokens, self.cache_creation_tokens
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breakdown() -> TokenBreakdown {
+        TokenBreakdown {
+            system_prompt_tokens: 100,
+            context: vec![
+                ContextTokens {
+                    source: ContextSource::History,
+                    tokens: 250,
+                },
+                ContextTokens {
+                    source: ContextSource::Pinned(PathBuf::from("STYLE.md")),
+                    tokens: 50,
+                },
+            ],
+            task_tokens: 100,
+            output_tokens: 300,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_totals_and_merge() {
+        let mut total = breakdown();
+        assert_eq!(total.context_tokens(), 300);
+        assert_eq!(total.prompt_tokens(), 500);
+
+        let follow_up = TokenBreakdown {
+            context: vec![
+                ContextTokens {
+                    source: ContextSource::History,
+                    tokens: 50,
+                },
+                ContextTokens {
+                    source: ContextSource::Imported,
+                    tokens: 10,
+                },
+            ],
+            task_tokens: 20,
+            ..Default::default()
+        };
+        total.merge(&follow_up);
+        assert_eq!(total.context[0].tokens, 300);
+        assert_eq!(total.context[2].source, ContextSource::Imported);
+        assert_eq!(total.task_tokens, 120);
+        assert_eq!(total.prompt_tokens(), 580);
+    }
+
+    #[test]
+    fn test_reported_cache_usage_and_render() {
+        let output = serde_json::json!({
+            "result": "done",
+            "usage": {
+                "input_tokens": 12,
+                "cache_read_input_tokens": 4000,
+                "cache_creation_input_tokens": 350,
+                "output_tokens": 300
+            }
+        });
+        let breakdown = breakdown().with_reported_usage(&output);
+        assert_eq!(breakdown.cache_read_tokens, 4000);
+        assert_eq!(breakdown.cache_creation_tokens, 350);
+
+        let rendered = breakdown.render();
+        assert!(rendered.contains("context: history"));
+        assert!(rendered.contains("context: pinned STYLE.md"));
+        assert!(rendered.contains(" 50%"));
+        assert!(rendered.contains("4000 read, 350 written"));
+
+        // Without reported usage the cache line is omitted
+        assert!(!TokenBreakdown::default().render().contains("prompt cache"));
+    }
+}
@@ -0,0 +1,332 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Upper bound on the rendered summary, so it stays cheap to include in prompts
+pub const MAX_SUMMARY_CHARS: usize = 8000;
+
+/// Prompts kept per session; later ones are usually follow-ups
+const MAX_PROMPTS_PER_SESSION: usize = 5;
+
+/// Characters kept from each prompt or response
+const MAX_SNIPPET_CHARS: usize = 300;
+
+/// What one Claude Code session in the workspace did
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaudeTranscript {
+    pub session_id: String,
+    pub started: Option<DateTime<Utc>>,
+    pub ended: Option<DateTime<Utc>>,
+    /// Prompts typed by the user, in order
+    pub prompts: Vec<String>,
+    /// Summaries Claude Code recorded for the session
+    pub summaries: Vec<String>,
+    /// Files passed to edit and write tools
+    pub files_edited: BTreeSet<String>,
+    pub last_response: Option<String>,
+    pub message_count: usize,
+}
+
+impl ClaudeTranscript {
+    /// Parse a transcript (`<session>.jsonl`); unreadable lines are skipped
+    pub fn parse(session_id: &str, contents: &str) -> Self {
+        let mut transcript = Self {
+            session_id: session_id.to_string(),
+            started: None,
+            ended: None,
+            prompts: Vec::new(),
+            summaries: Vec::new(),
+            files_edited: BTreeSet::new(),
+            last_response: None,
+            message_count: 0,
+        };
+
+        for line in contents.lines() {
+            let Ok(entry) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if entry["isSidechain"].as_bool() == Some(true) {
+                continue;
+            }
+            if let Some(timestamp) = entry["timestamp"]
+                .as_str()
+                .and_then(|value| value.parse::<DateTime<Utc>>().ok())
+            {
+                transcript.started.get_or_insert(timestamp);
+                transcript.ended = Some(timestamp);
+            }
+
+            match entry["type"].as_str() {
+                Some("summary") => {
+                    if let Some(summary) = entry["summary"].as_str() {
+                        transcript.summaries.push(summary.to_string());
+                    }
+                }
+                Some("user") => transcript.add_user_message(&entry),
+                Some("assistant") => transcript.add_assistant_message(&entry),
+                _ => {}
+            }
+        }
+        transcript
+    }
+
+    fn add_user_message(&mut self, entry: &Value) {
+        if entry["isMeta"].as_bool() == Some(true)
+            || entry["isCompactSummary"].as_bool() == Some(true)
+        {
+            return;
+        }
+        // Tool results come back as user messages with block content
+        let text = match &entry["message"]["content"] {
+            Value::String(text) => text.clone(),
+            Value::Array(blocks) => blocks
+                .iter()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => return,
+        };
+        let text = text.trim();
+        // Slash commands and injected reminders are markup, not prompts
+        if text.is_empty() || text.starts_with('<') {
+            return;
+        }
+        self.message_count += 1;
+        let prompt = snippet(text);
+        // Repeated prompts ("continue", retries) add nothing
+        if self.prompts.len() < MAX_PROMPTS_PER_SESSION && !self.prompts.contains(&prompt) {
+            self.prompts.push(prompt);
+        }
+    }
+
+    fn add_assistant_message(&mut self, entry: &Value) {
+        let Value::Array(blocks) = &entry["message"]["content"] else {
+            return;
+        };
+        self.message_count += 1;
+        for block in blocks {
+            match block["type"].as_str() {
+                Some("text") => {
+                    if let Some(text) = block["text"].as_str().filter(|t| !t.trim().is_empty()) {
+                        self.last_response = Some(snippet(text.trim()));
+                    }
+                }
+                Some("tool_use") => {
+                    let input = &block["input"];
+                    if let Some(path) = input["file_path"]
+                        .as_str()
+                        .or_else(|| input["notebook_path"].as_str())
+                        && matches!(
+                            block["name"].as_str(),
+                            Some("Edit" | "MultiEdit" | "Write" | "NotebookEdit")
+                        )
+                    {
+                        self.files_edited.insert(path.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.prompts.is_empty() && self.summaries.is_empty()
+    }
+}
+
+/// Reads Claude Code's local transcripts for one workspace
+#[derive(Debug, Clone)]
+pub struct TranscriptImporter {
+    projects_dir: PathBuf,
+    workspace: PathBuf,
+}
+
+impl TranscriptImporter {
+    /// `projects_dir` is Claude Code's `~/.claude/projects`
+    pub fn new(projects_dir: PathBuf, workspace: PathBuf) -> Self {
+        Self {
+            projects_dir,
+            workspace,
+        }
+    }
+
+    /// Directory Claude Code keeps this workspace's transcripts in
+    ///
+    /// Claude Code names it after the absolute workspace path with every
+    /// character other than ASCII letters and digits replaced by `-`.
+    pub fn transcript_dir(&self) -> PathBuf {
+        let name: String = self
+            .workspace
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        self.projects_dir.join(name)
+    }
+
+    /// Sessions with at least one prompt or summary, oldest first
+    pub fn transcripts(&self) -> std::io::Result<Vec<ClaudeTranscript>> {
+        let dir = self.transcript_dir();
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("No Claude Code transcripts at {}", dir.display());
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut transcripts = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some(session_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => {
+                    let transcript = ClaudeTranscript::parse(session_id, &contents);
+                    if !transcript.is_empty() {
+                        transcripts.push(transcript);
+                    }
+                }
+                Err(e) => warn!("Skipping transcript {}: {}", path.display(), e),
+            }
+        }
+        transcripts.sort_by_key(|transcript| transcript.started);
+        Ok(transcripts)
+    }
+}
+
+/// Markdown summary of the most recent `max_sessions` transcripts
+///
+/// Newest sessions are rendered first and older ones are dropped once the
+/// summary reaches [`MAX_SUMMARY_CHARS`].
+pub fn render_summary(
+    transcripts: &[ClaudeTranscript],
+    workspace: &Path,
+    max_sessions: usize,
+) -> String {
+    let mut summary = String::from(
+        "# Earlier Claude Code sessions\n\n\
+         Summarized from the user's interactive Claude Code sessions in this \
+         workspace, newest first. Treat it as background on what was already \
+         tried and decided.\n",
+    );
+
+    for transcript in transcripts.iter().rev().take(max_sessions) {
+        let mut section = format!(
+            "\n## Session {}",
+            transcript.session_id.chars().take(8).collect::<String>()
+        );
+        if let Some(started) = transcript.started {
+            section.push_str(&format!(" ({})", started.format("%Y-%m-%d %H:%M UTC")));
+        }
+        section.push_str(&format!("\n\n{} messages.\n", transcript.message_count));
+
+        for text in &transcript.summaries {
+            section.push_str(&format!("Summary: {}\n", text));
+        }
+        if !transcript.prompts.is_empty() {
+            section.push_str("\nPrompts:\n");
+            for prompt in &transcript.prompts {
+                section.push_str(&format!("- {}\n", prompt.replace('\n', " ")));
+            }
+        }
+        if !transcript.files_edited.is_empty() {
+            // Workspace files are shown relative to the workspace
+            let files: Vec<String> = transcript
+                .files_edited
+                .iter()
+                .map(|file| match Path::new(file).strip_prefix(workspace) {
+                    Ok(relative) => relative.display().to_string(),
+                    Err(_) => file.clone(),
+                })
+                .collect();
+            section.push_str(&format!("\nFiles edited: {}\n", files.join(", ")));
+        }
+        if let Some(response) = &transcript.last_response {
+            section.push_str(&format!(
+                "\nLast response: {}\n",
+                response.replace('\n', " ")
+            ));
+        }
+
+        if summary.len() + section.len() > MAX_SUMMARY_CHARS {
+            break;
+        }
+        summary.push_str(&section);
+    }
+    summary
+}
+
+fn snippet(text: &str) -> String {
+    match text.char_indices().nth(MAX_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = r#"{"type":"summary","summary":"Add retry to HTTP client"}
+{"type":"user","timestamp":"2025-03-01T10:00:00Z","message":{"role":"user","content":"Add retries to the HTTP client"}}
+{"type":"user","timestamp":"2025-03-01T10:00:01Z","isMeta":true,"message":{"role":"user","content":"Caveat: internal"}}
+{"type":"assistant","timestamp":"2025-03-01T10:00:05Z","message":{"role":"assistant","content":[{"type":"text","text":"Adding exponential backoff."},{"type":"tool_use","name":"Edit","input":{"file_path":"/repo/src/http.rs"}},{"type":"tool_use","name":"Read","input":{"file_path":"/repo/src/lib.rs"}}]}}
+{"type":"user","timestamp":"2025-03-01T10:00:06Z","message":{"role":"user","content":[{"type":"tool_result","content":"ok"}]}}
+{"type":"user","timestamp":"2025-03-01T10:01:00Z","message":{"role":"user","content":"<command-name>/clear</command-name>"}}
+{"type":"assistant","isSidechain":true,"timestamp":"2025-03-01T10:02:00Z","message":{"role":"assistant","content":[{"type":"text","text":"subagent"}]}}
+not json
+{"type":"assistant","timestamp":"2025-03-01T10:03:00Z","message":{"role":"assistant","content":[{"type":"text","text":"Retries are capped at 5."}]}}"#;
+
+    #[test]
+    fn test_parse_transcript() {
+        let transcript = ClaudeTranscript::parse("abc123", TRANSCRIPT);
+        assert_eq!(transcript.summaries, vec!["Add retry to HTTP client"]);
+        assert_eq!(transcript.prompts, vec!["Add retries to the HTTP client"]);
+        assert_eq!(
+            transcript.files_edited.iter().collect::<Vec<_>>(),
+            vec!["/repo/src/http.rs"]
+        );
+        assert_eq!(
+            transcript.last_response.as_deref(),
+            Some("Retries are capped at 5.")
+        );
+        assert_eq!(transcript.message_count, 3);
+        assert_eq!(
+            transcript.ended.unwrap().to_rfc3339(),
+            "2025-03-01T10:03:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_import_workspace_transcripts() {
+        let home = tempfile::tempdir().unwrap();
+        let importer = TranscriptImporter::new(
+            home.path().join("projects"),
+            PathBuf::from("/home/dev/my.app"),
+        );
+        let dir = importer.transcript_dir();
+        assert!(dir.ends_with("-home-dev-my-app"));
+        assert!(importer.transcripts().unwrap().is_empty());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("abc12345-0000.jsonl"), TRANSCRIPT).unwrap();
+        std::fs::write(dir.join("empty.jsonl"), "{\"type\":\"mode\"}\n").unwrap();
+        let transcripts = importer.transcripts().unwrap();
+        assert_eq!(transcripts.len(), 1);
+
+        let summary = render_summary(&transcripts, Path::new("/repo"), 10);
+        assert!(summary.contains("## Session abc12345 (2025-03-01 10:00 UTC)"));
+        assert!(summary.contains("- Add retries to the HTTP client"));
+        assert!(summary.contains("Files edited: src/http.rs"));
+        assert!(summary.len() <= MAX_SUMMARY_CHARS);
+    }
+}
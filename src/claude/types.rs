@@ -1,6 +1,9 @@
+use super::token_breakdown::TokenBreakdown;
+use crate::task::ToolPermissions;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -19,6 +22,84 @@ pub struct ClaudeConfig {
     pub error_config: ErrorRecoveryConfig,
     /// Show subprocess stdout in real-time during task execution
     pub show_subprocess_output: bool,
+    /// System prompt appended to every task via `--append-system-prompt`
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Rate limits shared with other aca processes on this machine
+    #[serde(default)]
+    pub shared_rate_limit: SharedRateLimitConfig,
+    /// Title or id prefix of a task whose prompt is written to disk
+    /// (set from `--dump-context`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dump_context: Option<String>,
+    /// Tools the CLI may use without asking (`--allowedTools`)
+    #[serde(default = "default_allowed_tools")]
+    pub allowed_tools: Vec<String>,
+    /// Permission mode the CLI runs in (`--permission-mode`)
+    #[serde(default)]
+    pub permission_mode: PermissionMode,
+    /// Seconds a single CLI request may run before it is killed; 0 for no limit
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+fn default_request_timeout_secs() -> u64 {
+    3600
+}
+
+impl ClaudeConfig {
+    /// Limit on a single CLI request, if any
+    pub fn request_timeout(&self) -> Option<Duration> {
+        (self.request_timeout_secs > 0).then(|| Duration::from_secs(self.request_timeout_secs))
+    }
+}
+
+/// Tools the Claude CLI may use without asking unless configured otherwise
+pub const DEFAULT_ALLOWED_TOOLS: &[&str] = &[
+    "Read",
+    "Write",
+    "Edit",
+    "Bash",
+    "Glob",
+    "Grep",
+    "MultiEdit",
+    "Task",
+    "TodoWrite",
+    "SlashCommand",
+];
+
+fn default_allowed_tools() -> Vec<String> {
+    DEFAULT_ALLOWED_TOOLS
+        .iter()
+        .map(|tool| tool.to_string())
+        .collect()
+}
+
+/// How the Claude CLI handles tool uses that need permission
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    /// Refuse anything not allowed, as there is no one to ask
+    Default,
+    /// Accept file edits without asking
+    #[default]
+    AcceptEdits,
+    /// Analyze and plan without changing anything
+    Plan,
+    /// Skip all permission checks
+    BypassPermissions,
+}
+
+impl PermissionMode {
+    /// Value of `--permission-mode`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::AcceptEdits => "acceptEdits",
+            Self::Plan => "plan",
+            Self::BypassPermissions => "bypassPermissions",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,11 +119,49 @@ pub struct RateLimitConfig {
     pub max_backoff_delay: Duration,
 }
 
+/// Machine-wide rate limiting across concurrent aca processes
+///
+/// All processes that enable it draw from one per-minute bucket kept in a
+/// lock-protected file, so together they stay under the provider's limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharedRateLimitConfig {
+    pub enabled: bool,
+    /// Bucket file (default: `~/.cache/aca/rate_limit.json`)
+    pub state_file: Option<PathBuf>,
+    /// Tokens per minute for all processes together (default: `rate_limits`)
+    pub max_tokens_per_minute: Option<u64>,
+    /// Requests per minute for all processes together (default: `rate_limits`)
+    pub max_requests_per_minute: Option<u64>,
+    /// Give up with a rate-limit error after waiting this long
+    pub max_wait_secs: u64,
+}
+
+impl Default for SharedRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            state_file: None,
+            max_tokens_per_minute: None,
+            max_requests_per_minute: None,
+            max_wait_secs: 600,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextConfig {
     pub compression_threshold: f64,
     pub max_history_length: u32,
     pub relevance_threshold: f64,
+    /// Conversation turns replayed into each prompt, chosen by relevance to
+    /// the task; 0 replays the whole history
+    #[serde(default = "default_max_relevant_turns")]
+    pub max_relevant_turns: usize,
+}
+
+fn default_max_relevant_turns() -> usize {
+    8
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +192,9 @@ pub struct TaskRequest {
     pub priority: TaskPriority,
     pub estimated_tokens: Option<u64>,
     pub system_message: Option<String>,
+    /// Tool access the task asks for over the configured defaults
+    #[serde(default)]
+    pub tool_permissions: ToolPermissions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +211,9 @@ pub struct TaskResponse {
     pub response_text: String,
     pub tool_uses: Vec<ToolUse>,
     pub token_usage: TokenUsage,
+    /// Where the request's tokens went
+    #[serde(default)]
+    pub token_breakdown: TokenBreakdown,
     pub execution_time: Duration,
     pub model_used: String,
 }
@@ -102,12 +227,22 @@ pub struct ToolUse {
     pub execution_time: Duration,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenUsage {
+    /// Prompt tokens not served from or written to the prompt cache
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub total_tokens: u64,
     pub estimated_cost: f64,
+    /// Prompt tokens read from the provider's prompt cache
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
+    /// Cost avoided by cache reads, net of the cache write premium
+    #[serde(default)]
+    pub cache_savings: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -166,6 +301,8 @@ pub enum ClaudeError {
     CircuitBreakerOpen,
     #[error("Max retries exceeded")]
     MaxRetriesExceeded,
+    #[error("Claude CLI did not finish within {timeout_secs}s")]
+    Timeout { timeout_secs: u64 },
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -192,6 +329,7 @@ impl Default for ClaudeConfig {
                 compression_threshold: 0.8,
                 max_history_length: 100,
                 relevance_threshold: 0.3,
+                max_relevant_turns: default_max_relevant_turns(),
             },
             usage_tracking: UsageTrackingConfig {
                 track_tokens: true,
@@ -207,6 +345,12 @@ impl Default for ClaudeConfig {
                 enable_fallback_models: true,
             },
             show_subprocess_output: false, // Disabled by default, can be enabled via --verbose
+            system_prompt: None,
+            shared_rate_limit: SharedRateLimitConfig::default(),
+            dump_context: None,
+            allowed_tools: default_allowed_tools(),
+            permission_mode: PermissionMode::default(),
+            request_timeout_secs: default_request_timeout_secs(),
         }
     }
 }
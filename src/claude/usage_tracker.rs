@@ -1,24 +1,57 @@
 use crate::claude::types::{SessionId, TaskResponse, TokenUsage, UsageTrackingConfig};
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// Bucket for usage from tasks without any cost tag
+pub const UNTAGGED_COST_TAG: &str = "untagged";
+
+// Simple cost estimation - in practice this would be model-specific
+const INPUT_COST_PER_TOKEN: f64 = 0.000003; // $3 per million tokens
+const OUTPUT_COST_PER_TOKEN: f64 = 0.000015; // $15 per million tokens
+const CACHE_READ_COST_PER_TOKEN: f64 = 0.0000003; // 0.1x input
+const CACHE_WRITE_COST_PER_TOKEN: f64 = 0.00000375; // 1.25x input
+
+/// Estimated cost of uncached input and output tokens
+pub fn estimate_cost(input_tokens: u64, output_tokens: u64) -> f64 {
+    (input_tokens as f64 * INPUT_COST_PER_TOKEN) + (output_tokens as f64 * OUTPUT_COST_PER_TOKEN)
+}
+
+/// Cost of prompt cache traffic and what it saved over uncached input
+///
+/// Savings are net of the premium paid for cache writes, so they are
+/// negative until written prefixes are read back.
+pub fn cache_cost_and_savings(cache_read_tokens: u64, cache_creation_tokens: u64) -> (f64, f64) {
+    let read = cache_read_tokens as f64;
+    let written = cache_creation_tokens as f64;
+    let cost = read * CACHE_READ_COST_PER_TOKEN + written * CACHE_WRITE_COST_PER_TOKEN;
+    let savings = read * (INPUT_COST_PER_TOKEN - CACHE_READ_COST_PER_TOKEN)
+        - written * (CACHE_WRITE_COST_PER_TOKEN - INPUT_COST_PER_TOKEN);
+    (cost, savings)
+}
+
 #[derive(Debug)]
 pub struct UsageTracker {
     config: UsageTrackingConfig,
     usage_data: Arc<Mutex<UsageData>>,
 }
 
-#[derive(Debug, Default)]
-struct UsageData {
+/// Everything a [`UsageTracker`] has recorded
+///
+/// Checkpoints store it so a resumed session keeps its usage statistics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageData {
     sessions: HashMap<SessionId, SessionUsage>,
     daily_usage: HashMap<String, DailyUsage>, // Date string -> usage
+    tag_usage: HashMap<String, TagUsage>,     // Cost tag -> usage
     total_usage: TotalUsage,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionUsage {
     pub session_id: SessionId,
     pub start_time: DateTime<Utc>,
@@ -29,7 +62,7 @@ pub struct SessionUsage {
     pub average_response_time: Duration,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DailyUsage {
     pub date: String,
     pub total_tokens: u64,
@@ -40,18 +73,42 @@ pub struct DailyUsage {
     pub unique_sessions: u32,
 }
 
-#[derive(Debug, Clone, Default)]
+/// Usage attributed to a cost-center tag
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub total_tokens: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub task_count: u32,
+    pub total_cost: f64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_savings: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TotalUsage {
     pub total_tokens: u64,
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub total_requests: u64,
     pub total_cost: f64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Cost avoided by prompt caching, net of cache write premiums
+    pub cache_savings: f64,
     pub total_sessions: u64,
     pub first_request: Option<DateTime<Utc>>,
     pub last_request: Option<DateTime<Utc>>,
 }
 
+impl UsageData {
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty() && self.total_usage.total_requests == 0
+    }
+}
+
 impl UsageTracker {
     pub fn new(config: UsageTrackingConfig) -> Self {
         Self {
@@ -60,6 +117,16 @@ impl UsageTracker {
         }
     }
 
+    /// Copy of everything recorded so far
+    pub async fn snapshot(&self) -> UsageData {
+        self.usage_data.lock().await.clone()
+    }
+
+    /// Replace the recorded usage with a snapshot from a previous run
+    pub async fn restore(&self, data: UsageData) {
+        *self.usage_data.lock().await = data;
+    }
+
     pub async fn start_session(&self, session_id: SessionId) {
         if !self.config.track_tokens && !self.config.track_costs && !self.config.track_performance {
             return;
@@ -73,12 +140,7 @@ impl UsageTracker {
                 session_id,
                 start_time: Utc::now(),
                 last_activity: Utc::now(),
-                token_usage: TokenUsage {
-                    input_tokens: 0,
-                    output_tokens: 0,
-                    total_tokens: 0,
-                    estimated_cost: 0.0,
-                },
+                token_usage: TokenUsage::default(),
                 request_count: 0,
                 total_cost: 0.0,
                 average_response_time: Duration::from_millis(0),
@@ -105,10 +167,14 @@ impl UsageTracker {
                 session.token_usage.input_tokens += response.token_usage.input_tokens;
                 session.token_usage.output_tokens += response.token_usage.output_tokens;
                 session.token_usage.total_tokens += response.token_usage.total_tokens;
+                session.token_usage.cache_read_tokens += response.token_usage.cache_read_tokens;
+                session.token_usage.cache_creation_tokens +=
+                    response.token_usage.cache_creation_tokens;
             }
 
             if self.config.track_costs {
                 session.token_usage.estimated_cost += response.token_usage.estimated_cost;
+                session.token_usage.cache_savings += response.token_usage.cache_savings;
                 session.total_cost += response.token_usage.estimated_cost;
             }
 
@@ -149,10 +215,13 @@ impl UsageTracker {
             data.total_usage.total_tokens += response.token_usage.total_tokens;
             data.total_usage.input_tokens += response.token_usage.input_tokens;
             data.total_usage.output_tokens += response.token_usage.output_tokens;
+            data.total_usage.cache_read_tokens += response.token_usage.cache_read_tokens;
+            data.total_usage.cache_creation_tokens += response.token_usage.cache_creation_tokens;
         }
 
         if self.config.track_costs {
             data.total_usage.total_cost += response.token_usage.estimated_cost;
+            data.total_usage.cache_savings += response.token_usage.cache_savings;
         }
 
         if data.total_usage.first_request.is_none() {
@@ -182,6 +251,42 @@ impl UsageTracker {
         });
     }
 
+    /// Attribute a task's usage to each of its cost tags
+    ///
+    /// A task with several tags counts fully towards each of them; tasks
+    /// without tags are grouped under [`UNTAGGED_COST_TAG`].
+    pub async fn record_tag_usage(&self, tags: &[String], usage: &TokenUsage) {
+        let mut data = self.usage_data.lock().await;
+        let untagged = [UNTAGGED_COST_TAG.to_string()];
+        let tags = if tags.is_empty() { &untagged[..] } else { tags };
+
+        for tag in tags {
+            let entry = data
+                .tag_usage
+                .entry(tag.clone())
+                .or_insert_with(|| TagUsage {
+                    tag: tag.clone(),
+                    ..Default::default()
+                });
+            entry.task_count += 1;
+            entry.total_tokens += usage.total_tokens;
+            entry.input_tokens += usage.input_tokens;
+            entry.output_tokens += usage.output_tokens;
+            entry.total_cost += usage.estimated_cost;
+            entry.cache_read_tokens += usage.cache_read_tokens;
+            entry.cache_creation_tokens += usage.cache_creation_tokens;
+            entry.cache_savings += usage.cache_savings;
+        }
+    }
+
+    /// Usage per cost tag, most expensive first
+    pub async fn get_tag_usage(&self) -> Vec<TagUsage> {
+        let data = self.usage_data.lock().await;
+        let mut usage: Vec<TagUsage> = data.tag_usage.values().cloned().collect();
+        usage.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
+        usage
+    }
+
     pub async fn get_session_usage(&self, session_id: SessionId) -> Option<SessionUsage> {
         let data = self.usage_data.lock().await;
         data.sessions.get(&session_id).cloned()
@@ -247,12 +352,7 @@ impl UsageTracker {
     }
 
     pub async fn estimate_cost_for_tokens(&self, input_tokens: u64, output_tokens: u64) -> f64 {
-        // Simple cost estimation - in practice this would be model-specific
-        const INPUT_COST_PER_TOKEN: f64 = 0.000003; // $3 per million tokens
-        const OUTPUT_COST_PER_TOKEN: f64 = 0.000015; // $15 per million tokens
-
-        (input_tokens as f64 * INPUT_COST_PER_TOKEN)
-            + (output_tokens as f64 * OUTPUT_COST_PER_TOKEN)
+        estimate_cost(input_tokens, output_tokens)
     }
 }
 
@@ -2,11 +2,22 @@
 //!
 //! This module handles CLI argument parsing with subcommands:
 //! - `run`: Execute a file (auto-detects: task file, task list, or execution plan)
+//! - `resume`: Resume from a checkpoint, the latest by default
 //! - `interactive`: Run in interactive mode
-//! - `checkpoint`: Manage checkpoints (list, create, resume)
-//! - `show-config`: Show configuration discovery information
+//! - `checkpoints`: Manage checkpoints (list, create, resume)
+//! - `plan`: Dump a task file's plan or compare two plans
+//! - `config`: Show, create or validate configuration
+//! - `status`: Show the latest session's progress
+//! - `quick`: Run a one-line request as a single task and verify it
+//! - `usage`: Show recorded usage and cost (optionally per cost tag)
+//!
+//! The flags from before subcommands (`--task-file`, `--tasks`,
+//! `--execution-plan`, `--resume`, `--continue`, `--list-checkpoints`,
+//! `--create-checkpoint`, `--show-config` and `--interactive`) still work
+//! but are hidden from help.
 
-use super::tasks::TaskInput;
+use super::intelligent_parser::{DecompositionGranularity, DecompositionLimits};
+use super::tasks::{InlineSource, TaskInput};
 use crate::llm::types::ProviderType;
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
@@ -17,6 +28,8 @@ pub enum ProviderCliOption {
     ClaudeCode,
     #[clap(name = "openai-codex")]
     OpenAICodex,
+    #[clap(name = "openai-api", alias = "openai")]
+    OpenAIApi,
     Anthropic,
     #[clap(alias = "local")]
     LocalModel,
@@ -27,6 +40,7 @@ impl ProviderCliOption {
         match self {
             ProviderCliOption::ClaudeCode => ProviderType::ClaudeCode,
             ProviderCliOption::OpenAICodex => ProviderType::OpenAICodex,
+            ProviderCliOption::OpenAIApi => ProviderType::OpenAIApi,
             ProviderCliOption::Anthropic => ProviderType::Anthropic,
             ProviderCliOption::LocalModel => ProviderType::LocalModel,
         }
@@ -35,12 +49,33 @@ impl ProviderCliOption {
 
 #[derive(Debug)]
 pub enum ExecutionMode {
-    Batch(BatchConfig),
+    Batch(Box<BatchConfig>),
     Interactive(InteractiveConfig),
-    Resume(ResumeConfig),                   // Resume from checkpoint
-    ListCheckpoints { all_sessions: bool }, // List available checkpoints
-    CreateCheckpoint(String),               // Create manual checkpoint
-    ShowConfig,                             // Show configuration discovery info
+    Resume(ResumeConfig),                     // Resume from checkpoint
+    ListCheckpoints { all_sessions: bool },   // List available checkpoints
+    CreateCheckpoint(String),                 // Create manual checkpoint
+    ShowConfig,                               // Show configuration discovery info
+    InitConfig(ConfigInitConfig),             // Write a default config file
+    Status(StatusConfig),                     // Show the latest session's progress
+    ValidatePlan(PlanValidateConfig),         // Report plan file diagnostics
+    DiffPlans(PlanDiffConfig),                // Compare two dumped plans
+    Usage(UsageConfig),                       // Show recorded usage and cost
+    Stats(StatsConfig),                       // Show estimate calibration
+    Watch(WatchModeConfig),                   // Verify after edits and fix failures
+    FixBug(FixBugConfig),                     // Reproduce, fix and confirm a bug
+    Quick(QuickConfig),                       // One task, verified, no plan
+    IdeServer(IdeServerConfig),               // JSON-RPC over stdio for editors
+    ImportClaudeContext(ContextImportConfig), // Summarize Claude Code sessions as context
+    PruneLogs(LogsPruneConfig),               // Apply log retention limits now
+    RestoreSession(SessionRestoreConfig),     // Download a remote session backup
+    TaskTranscript(TranscriptConfig),         // Render a task's conversation
+    TaskWhy(TaskWhyConfig),                   // Explain why a task is waiting
+    PrDescription(PrDescriptionConfig),       // Render a run as a PR description
+    VerifyReport(ReportVerifyConfig),         // Check a signed report or ledger
+    Replay(ReplayConfig),                     // Step through a session's decisions
+    Coordinator(CoordinatorModeConfig),       // Lease a plan's tasks to workers
+    Worker(WorkerModeConfig),                 // Run tasks leased by a coordinator
+    Serve(ServeModeConfig),                   // Serve registered tenants as a daemon
 }
 
 #[derive(Debug)]
@@ -53,17 +88,61 @@ pub struct BatchConfig {
     pub use_intelligent_parser: bool,
     pub force_naive_parser: bool,
     pub context_hints: Vec<String>,
+    pub parse_timeout: Option<std::time::Duration>,
+    pub decomposition: DecompositionLimits,
     pub dump_plan: Option<PathBuf>,
+    pub dump_graph: Option<PathBuf>,
+    pub dump_context: Option<String>,
+    pub report_junit: Option<PathBuf>,
+    pub report_sarif: Option<PathBuf>,
     pub provider_override: Option<ProviderType>,
     pub model_override: Option<String>,
     pub use_containers: bool,
     pub container_image: String,
+    pub use_devcontainer: bool,
+    pub rerun_completed: bool,
+    pub approve: bool,
+    pub answers_file: Option<PathBuf>,
+    pub deadline: Option<String>,
+}
+
+impl BatchConfig {
+    /// Run `task_input` with every option at its default
+    pub fn new(task_input: TaskInput) -> Self {
+        Self {
+            task_input,
+            config_override: None,
+            workspace_override: None,
+            verbose: false,
+            dry_run: false,
+            use_intelligent_parser: false,
+            force_naive_parser: false,
+            context_hints: Vec::new(),
+            parse_timeout: None,
+            decomposition: DecompositionLimits::default(),
+            dump_plan: None,
+            dump_graph: None,
+            dump_context: None,
+            report_junit: None,
+            report_sarif: None,
+            provider_override: None,
+            model_override: None,
+            use_containers: false,
+            container_image: crate::executor::config::DEFAULT_CONTAINER_IMAGE.to_string(),
+            use_devcontainer: false,
+            rerun_completed: false,
+            approve: false,
+            answers_file: None,
+            deadline: None,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct InteractiveConfig {
     pub workspace: Option<PathBuf>,
     pub verbose: bool,
+    pub bell: bool,
 }
 
 #[derive(Debug)]
@@ -74,6 +153,189 @@ pub struct ResumeConfig {
     pub continue_latest: bool,
 }
 
+#[derive(Debug)]
+pub struct IdeServerConfig {
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ContextImportConfig {
+    pub workspace_override: Option<PathBuf>,
+    pub projects_dir: Option<PathBuf>, // Claude Code transcripts (default ~/.claude/projects)
+    pub max_sessions: usize,
+}
+
+#[derive(Debug)]
+pub struct LogsPruneConfig {
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+    pub session_id: Option<String>, // Only this session's logs, or all sessions
+}
+
+/// Output format of `aca task transcript`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TranscriptFormat {
+    /// Markdown document
+    Md,
+    /// The transcript as JSON
+    Json,
+}
+
+#[derive(Debug)]
+pub struct TranscriptConfig {
+    pub task_ref: String, // Task id or unique id prefix
+    pub format: TranscriptFormat,
+    pub workspace_override: Option<PathBuf>,
+}
+
+/// Output format of `aca task why`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WhyFormat {
+    /// Human-readable explanation
+    Text,
+    /// The explanation as JSON
+    Json,
+}
+
+#[derive(Debug)]
+pub struct TaskWhyConfig {
+    pub task_ref: String, // Task id or unique id prefix
+    pub format: WhyFormat,
+    pub workspace_override: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct PlanValidateConfig {
+    pub path: PathBuf, // Plan, structured config or task list file
+}
+
+/// Output format of `aca plan diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PlanDiffFormat {
+    /// One line per changed task
+    Text,
+    /// The differences as JSON
+    Json,
+}
+
+#[derive(Debug)]
+pub struct PlanDiffConfig {
+    pub old: PathBuf, // Earlier plan (JSON or TOML)
+    pub new: PathBuf, // Later plan (JSON or TOML)
+    pub format: PlanDiffFormat,
+}
+
+#[derive(Debug)]
+pub struct PrDescriptionConfig {
+    pub session_ref: Option<String>, // Session id prefix, or the latest session
+    pub workspace_override: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ReportVerifyConfig {
+    pub path: PathBuf,       // Signed report or ledger
+    pub key: Option<String>, // Trusted public key (hex or a file holding it)
+    pub workspace_override: Option<PathBuf>,
+}
+
+/// Output format of `aca replay`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReplayFormat {
+    /// One step at a time in a terminal, every step otherwise
+    Text,
+    /// All steps as JSON
+    Json,
+}
+
+#[derive(Debug)]
+pub struct ReplayConfig {
+    pub session_ref: String, // Session id or unique id prefix
+    pub format: ReplayFormat,
+    pub workspace_override: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct SessionRestoreConfig {
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+    pub session_id: Option<String>, // Only this session, or every backed-up session
+    pub remote_url: Option<String>, // Overrides remote_sync.url
+}
+
+#[derive(Debug)]
+pub struct CoordinatorModeConfig {
+    pub plan_file: PathBuf,
+    pub listen: String,
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct WorkerModeConfig {
+    pub coordinator: String,
+    pub worker_id: Option<String>, // Defaults to host name and process id
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+    pub verbose: bool,
+}
+
+#[derive(Debug)]
+pub struct ServeModeConfig {
+    pub config_file: PathBuf,        // Daemon configuration listing the tenants
+    pub grpc_listen: Option<String>, // Overrides the config's grpc_listen
+}
+
+#[derive(Debug)]
+pub struct WatchModeConfig {
+    pub verify_command: Option<String>,
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+    pub verbose: bool,
+    pub skip_confirmation: bool,
+}
+
+#[derive(Debug)]
+pub struct QuickConfig {
+    pub request: String, // One-liner, or `-` for stdin
+    pub verify_command: Option<String>,
+    pub skip_verify: bool,
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+    pub stream_output: bool,
+}
+
+#[derive(Debug)]
+pub struct FixBugConfig {
+    pub description: String, // Bug report, or `-` for stdin
+    pub test_command: Option<String>,
+    pub config_override: Option<PathBuf>,
+    pub workspace_override: Option<PathBuf>,
+    pub verbose: bool,
+}
+
+#[derive(Debug)]
+pub struct UsageConfig {
+    pub workspace_override: Option<PathBuf>,
+    pub by_tag: bool,
+}
+
+#[derive(Debug)]
+pub struct StatsConfig {
+    pub workspace_override: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ConfigInitConfig {
+    /// Write `./.aca/config.toml` instead of the user config
+    pub local: bool,
+}
+
+#[derive(Debug)]
+pub struct StatusConfig {
+    pub workspace_override: Option<PathBuf>,
+}
+
 #[derive(Debug, Parser)]
 #[command(name = "aca")]
 #[command(author = "Automatic Coding Agent Team")]
@@ -83,6 +345,7 @@ pub struct ResumeConfig {
 )]
 #[command(long_about = None)]
 #[command(arg_required_else_help = true)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
     /// Override default provider (e.g., Claude) with another configured provider
     #[arg(long = "provider", value_enum, global = true)]
@@ -90,16 +353,80 @@ pub struct Args {
     /// Override model to use when invoking the selected provider
     #[arg(long = "model", global = true)]
     pub model: Option<String>,
+    #[command(flatten)]
+    pub legacy: LegacyArgs,
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+/// Flags from before subcommands, kept as hidden aliases for them
+#[derive(Debug, Default, clap::Args)]
+pub struct LegacyArgs {
+    /// Run a file as a single task
+    #[arg(long = "task-file", value_name = "FILE", hide = true)]
+    pub task_file: Option<PathBuf>,
+    /// Run a task list (`aca run`)
+    #[arg(long = "tasks", value_name = "FILE", hide = true)]
+    pub tasks: Option<PathBuf>,
+    /// Run an execution plan (`aca run`)
+    #[arg(long = "execution-plan", value_name = "FILE", hide = true)]
+    pub execution_plan: Option<PathBuf>,
+    /// Resume from a checkpoint (`aca resume`)
+    #[arg(long = "resume", value_name = "CHECKPOINT_ID", hide = true)]
+    pub resume: Option<String>,
+    /// Resume from the latest checkpoint (`aca resume`)
+    #[arg(long = "continue", hide = true)]
+    pub continue_latest: bool,
+    /// List checkpoints (`aca checkpoints list`)
+    #[arg(long = "list-checkpoints", hide = true)]
+    pub list_checkpoints: bool,
+    /// Include checkpoints from all sessions
+    #[arg(long = "all-sessions", hide = true, requires = "list_checkpoints")]
+    pub all_sessions: bool,
+    /// Create a checkpoint (`aca checkpoints create`)
+    #[arg(long = "create-checkpoint", value_name = "DESCRIPTION", hide = true)]
+    pub create_checkpoint: Option<String>,
+    /// Show configuration discovery (`aca config show`)
+    #[arg(long = "show-config", hide = true)]
+    pub show_config: bool,
+    /// Run in interactive mode (`aca interactive`)
+    #[arg(long = "interactive", hide = true)]
+    pub interactive: bool,
+    /// Configuration file path
+    #[arg(short = 'c', long = "config", hide = true)]
+    pub config: Option<PathBuf>,
+    /// Workspace directory
+    #[arg(short = 'w', long = "workspace", hide = true)]
+    pub workspace: Option<PathBuf>,
+    /// Enable verbose output
+    #[arg(short = 'v', long = "verbose", hide = true)]
+    pub verbose: bool,
+    /// Show what would be executed without running
+    #[arg(short = 'n', long = "dry-run", hide = true)]
+    pub dry_run: bool,
+}
+
+/// Files `aca run` writes describing the plan before it runs
+#[derive(Debug, Default, clap::Args)]
+pub struct DumpArgs {
+    /// Dump execution plan to file (JSON or TOML format based on extension)
+    #[arg(long = "dump-plan", value_name = "FILE")]
+    pub dump_plan: Option<PathBuf>,
+    /// Write the task dependency graph to file (Graphviz .dot/.gv or Mermaid .mmd/.md)
+    #[arg(long = "dump-graph", value_name = "FILE")]
+    pub dump_graph: Option<PathBuf>,
+    /// Write the exact prompt sent for a task (title or id prefix) to .aca/context_dumps
+    #[arg(long = "dump-context", value_name = "TASK")]
+    pub dump_context: Option<String>,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Execute a file (auto-detects task file, task list, or execution plan)
     Run {
-        /// Path to file (task, task list, or execution plan)
-        file: PathBuf,
+        /// Path to file (task, task list, or execution plan), or `-` for stdin
+        #[arg(required_unless_present = "from_clipboard")]
+        file: Option<PathBuf>,
         /// Configuration file path
         #[arg(short = 'c', long = "config")]
         config: Option<PathBuf>,
@@ -121,15 +448,50 @@ pub enum Commands {
         /// Context hints for intelligent parser (can be used multiple times)
         #[arg(long = "context", value_name = "HINT")]
         context_hints: Vec<String>,
-        /// Dump execution plan to file (JSON or TOML format based on extension)
-        #[arg(long = "dump-plan", value_name = "FILE")]
-        dump_plan: Option<PathBuf>,
+        /// Give up on intelligent parsing after this many seconds and use the naive parser
+        #[arg(long = "parse-timeout", value_name = "SECS")]
+        parse_timeout: Option<u64>,
+        /// Most levels of nesting the intelligent parser may create (1 = top-level only)
+        #[arg(long = "max-depth", value_name = "N")]
+        max_depth: Option<usize>,
+        /// Most tasks the intelligent parser may create; finer ones are merged into their parents
+        #[arg(long = "max-tasks", value_name = "N")]
+        max_tasks: Option<usize>,
+        /// How finely the intelligent parser breaks tasks down
+        #[arg(long = "granularity", value_enum, default_value_t)]
+        granularity: DecompositionGranularity,
+        #[command(flatten)]
+        dump: Box<DumpArgs>,
+        /// Write task results as JUnit XML for CI test reporting
+        #[arg(long = "report-junit", value_name = "FILE")]
+        report_junit: Option<PathBuf>,
+        /// Write completion-gate findings as SARIF for code scanning
+        #[arg(long = "report-sarif", value_name = "FILE")]
+        report_sarif: Option<PathBuf>,
         /// Execute tasks inside a container (Docker/Podman)
         #[arg(long = "use-containers")]
         use_containers: bool,
         /// Container image to use (default: alpine:latest)
         #[arg(long = "container-image", default_value = crate::executor::config::DEFAULT_CONTAINER_IMAGE)]
         container_image: String,
+        /// Execute tasks in the workspace's dev container (.devcontainer/devcontainer.json)
+        #[arg(long = "devcontainer", conflicts_with = "container_image")]
+        devcontainer: bool,
+        /// Run tasks again even if an earlier run of this file completed them
+        #[arg(long = "rerun-completed")]
+        rerun_completed: bool,
+        /// Write the generated plan to a file to edit and confirm before it runs
+        #[arg(long = "approve")]
+        approve: bool,
+        /// Answer model questions from this file instead of prompting
+        #[arg(long = "answers", value_name = "FILE")]
+        answers: Option<PathBuf>,
+        /// Read the task description from the system clipboard
+        #[arg(long = "from-clipboard", conflicts_with = "file")]
+        from_clipboard: bool,
+        /// Warn if the plan cannot finish by then (e.g. 8h, 06:00, "2025-10-20 09:00")
+        #[arg(long = "deadline", value_name = "WHEN")]
+        deadline: Option<String>,
     },
     /// Run in interactive mode
     Interactive {
@@ -139,14 +501,361 @@ pub enum Commands {
         /// Enable verbose output
         #[arg(short = 'v', long = "verbose")]
         verbose: bool,
+        /// Don't ring the bell or send a desktop notification when long tasks finish
+        #[arg(long = "no-bell")]
+        no_bell: bool,
+    },
+    /// Resume from a checkpoint, the latest when no id is given
+    Resume {
+        /// Checkpoint ID to resume from
+        checkpoint_id: Option<String>,
+        /// Workspace directory override
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Enable verbose output
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
     },
     /// Manage checkpoints (list, create, resume)
-    Checkpoint {
+    #[command(alias = "checkpoint")]
+    Checkpoints {
         #[command(subcommand)]
         command: CheckpointCommands,
     },
-    /// Show configuration discovery information
+    /// Show configuration discovery information (same as `config show`)
+    #[command(hide = true)]
     ShowConfig,
+    /// Show, create and check configuration and plan files
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Show the latest session's progress in the workspace
+    Status {
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Dump and compare execution plans
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommands,
+    },
+    /// Show recorded usage and cost for the workspace
+    Usage {
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Break down spend per cost tag (`cost:<name>` task/plan tags)
+        #[arg(long = "by-tag")]
+        by_tag: bool,
+    },
+    /// Show how task duration estimates compare with actual run times
+    Stats {
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Watch the workspace, re-run verification after edits and fix failures
+    Watch {
+        /// Verification command, e.g. "cargo test" (default: watch.verify_command)
+        #[arg(long = "verify", value_name = "COMMAND")]
+        verify: Option<String>,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Enable verbose output
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
+        /// Create fix tasks without asking first
+        #[arg(short = 'y', long = "yes")]
+        yes: bool,
+    },
+    /// Reproduce a bug with a failing test, fix it and confirm the test passes
+    FixBug {
+        /// Bug description, or `-` to read it from stdin
+        description: String,
+        /// Command that runs the reproducing test (default: reported by the agent)
+        #[arg(long = "test-command", value_name = "COMMAND")]
+        test_command: Option<String>,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Enable verbose output
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
+    },
+    /// Make a small change in one task: no plan, verified, with a short diff summary
+    Quick {
+        /// What to do, e.g. "rename fetch_all to fetch_every_page", or `-` for stdin
+        request: String,
+        /// Verification command (default: verification.command, then watch.verify_command)
+        #[arg(long = "verify", value_name = "COMMAND")]
+        verify: Option<String>,
+        /// Don't run a verification command afterwards
+        #[arg(long = "no-verify", conflicts_with = "verify")]
+        no_verify: bool,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Don't stream the provider's output while the task runs
+        #[arg(short = 'q', long = "quiet")]
+        quiet: bool,
+    },
+    /// Serve editor extensions over JSON-RPC on stdin/stdout
+    IdeServer {
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Manage background context included in task prompts
+    Context {
+        #[command(subcommand)]
+        command: ContextCommands,
+    },
+    /// Manage session logs
+    Logs {
+        #[command(subcommand)]
+        command: LogsCommands,
+    },
+    /// Inspect tasks recorded in the workspace's sessions
+    Task {
+        #[command(subcommand)]
+        command: TaskCommands,
+    },
+    /// Step through a session's task starts, prompts, responses and diffs
+    Replay {
+        /// Session id, or a unique prefix of it
+        session_id: String,
+        /// Output format
+        #[arg(long = "format", value_enum, default_value = "text")]
+        format: ReplayFormat,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Render reports about recorded runs
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    /// Manage sessions
+    Session {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+    /// Serve a plan's tasks to `aca worker` processes until all finish
+    Coordinator {
+        /// Execution plan file (JSON or TOML)
+        plan: PathBuf,
+        /// Address to accept workers on
+        #[arg(long = "listen", value_name = "ADDR", default_value = crate::daemon::DEFAULT_COORDINATOR_ADDR)]
+        listen: String,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory (holds the coordinator state)
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Run tasks leased by an `aca coordinator`
+    Worker {
+        /// Coordinator address
+        #[arg(long = "coordinator", value_name = "ADDR", default_value = crate::daemon::DEFAULT_COORDINATOR_ADDR)]
+        coordinator: String,
+        /// Worker name shown by the coordinator (default: host name and process id)
+        #[arg(long = "id", value_name = "NAME")]
+        id: Option<String>,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Enable verbose output
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
+    },
+    /// Serve several project workspaces as a team daemon
+    Serve {
+        /// Daemon configuration file listing the tenants
+        #[arg(short = 'c', long = "config", value_name = "DAEMON_TOML")]
+        config: PathBuf,
+        /// Address to serve the gRPC interface on (needs the `grpc` feature)
+        #[arg(long = "grpc-listen", value_name = "ADDR")]
+        grpc_listen: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommands {
+    /// Show where configuration is looked for and which file is used
+    Show,
+    /// Write a default configuration file to ~/.aca/config.toml
+    Init {
+        /// Write ./.aca/config.toml instead
+        #[arg(long = "local")]
+        local: bool,
+    },
+    /// Report every problem in a plan, config or task list with its line and column
+    Validate {
+        /// Execution plan (JSON or TOML), structured config or task list
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PlanCommands {
+    /// Write the plan a task file would run, without running it
+    Dump {
+        /// Task file, task list or execution plan
+        file: PathBuf,
+        /// Where to write the plan (JSON or TOML format based on extension)
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: PathBuf,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Use intelligent LLM-based task parser (auto-enabled for task lists)
+        #[arg(long = "use-intelligent-parser")]
+        use_intelligent_parser: bool,
+        /// Force naive parser even for complex files
+        #[arg(long = "force-naive-parser")]
+        force_naive_parser: bool,
+        /// Context hints for intelligent parser (can be used multiple times)
+        #[arg(long = "context", value_name = "HINT")]
+        context_hints: Vec<String>,
+    },
+    /// Show tasks added, removed and modified between two plans, e.g. from --dump-plan
+    Diff {
+        /// Earlier plan (JSON or TOML)
+        old: PathBuf,
+        /// Later plan (JSON or TOML)
+        new: PathBuf,
+        /// Output format
+        #[arg(long = "format", value_enum, default_value = "text")]
+        format: PlanDiffFormat,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionCommands {
+    /// Restore sessions and checkpoints backed up with remote_sync
+    Restore {
+        /// Download from the remote backup (remote_sync.url or --url)
+        #[arg(long = "from-remote", required = true)]
+        from_remote: bool,
+        /// Remote URL to restore from instead of remote_sync.url
+        #[arg(long = "url", value_name = "URL")]
+        url: Option<String>,
+        /// Only restore this session
+        #[arg(long = "session", value_name = "ID")]
+        session: Option<String>,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TaskCommands {
+    /// Render a task's prompts, responses and tool uses for PRs and design docs
+    Transcript {
+        /// Task id, or a unique prefix of it
+        task_id: String,
+        /// Output format
+        #[arg(long = "format", value_enum, default_value = "md")]
+        format: TranscriptFormat,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Explain why a task is waiting: dependencies, limits, schedule and score
+    Why {
+        /// Task id, or a unique prefix of it
+        task_id: String,
+        /// Output format
+        #[arg(long = "format", value_enum, default_value = "text")]
+        format: WhyFormat,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReportCommands {
+    /// Render a run as a pull request description (summary, tasks, changes, cost)
+    PrDescription {
+        /// Session id or prefix (default: the most recently updated session)
+        #[arg(long = "session", value_name = "ID")]
+        session: Option<String>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+    /// Check that a signed run report or ledger was not altered after signing
+    Verify {
+        /// Signed file; its signature is read from `<file>.sig`
+        file: PathBuf,
+        /// Trusted public key, as hex or a file holding it (default: the workspace's
+        /// .aca/keys/report-signing.pub)
+        #[arg(long = "key", value_name = "KEY")]
+        key: Option<String>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LogsCommands {
+    /// Compress, expire and size-cap session logs using the log_retention settings
+    Prune {
+        /// Only prune this session's logs
+        #[arg(long = "session", value_name = "ID")]
+        session: Option<String>,
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ContextCommands {
+    /// Summarize this workspace's Claude Code sessions into .aca/context
+    ImportClaude {
+        /// Workspace directory
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<PathBuf>,
+        /// Most recent sessions to include
+        #[arg(long = "sessions", default_value_t = 10)]
+        sessions: usize,
+        /// Claude Code projects directory (default: ~/.claude/projects)
+        #[arg(long = "claude-dir", value_name = "DIR")]
+        claude_dir: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -195,15 +904,32 @@ impl Args {
                 use_intelligent_parser,
                 force_naive_parser,
                 context_hints,
-                dump_plan,
+                parse_timeout,
+                max_depth,
+                max_tasks,
+                granularity,
+                dump,
+                report_junit,
+                report_sarif,
                 use_containers,
                 container_image,
+                devcontainer,
+                rerun_completed,
+                approve,
+                answers,
+                from_clipboard,
+                deadline,
             }) => {
-                // Auto-detect file type based on extension
-                let task_input = Self::detect_file_type(file)?;
+                // Inline text from stdin or the clipboard, else auto-detect by extension
+                let task_input = match file {
+                    _ if *from_clipboard => TaskInput::Inline(InlineSource::Clipboard),
+                    Some(file) if file.as_os_str() == "-" => TaskInput::Inline(InlineSource::Stdin),
+                    Some(file) => Self::detect_file_type(file)?,
+                    None => return Err("A task file, `-` or --from-clipboard is required".into()),
+                };
                 let provider_override = self.provider.map(|p| p.into_provider_type());
 
-                Ok(ExecutionMode::Batch(BatchConfig {
+                Ok(ExecutionMode::Batch(Box::new(BatchConfig {
                     task_input,
                     config_override: config.clone(),
                     workspace_override: workspace.clone(),
@@ -212,20 +938,48 @@ impl Args {
                     use_intelligent_parser: *use_intelligent_parser,
                     force_naive_parser: *force_naive_parser,
                     context_hints: context_hints.clone(),
-                    dump_plan: dump_plan.clone(),
+                    parse_timeout: parse_timeout.map(std::time::Duration::from_secs),
+                    decomposition: DecompositionLimits {
+                        max_depth: *max_depth,
+                        max_tasks: *max_tasks,
+                        granularity: *granularity,
+                    },
+                    dump_plan: dump.dump_plan.clone(),
+                    dump_graph: dump.dump_graph.clone(),
+                    dump_context: dump.dump_context.clone(),
+                    report_junit: report_junit.clone(),
+                    report_sarif: report_sarif.clone(),
                     provider_override,
                     model_override: self.model.clone(),
                     use_containers: *use_containers,
                     container_image: container_image.clone(),
-                }))
-            }
-            Some(Commands::Interactive { workspace, verbose }) => {
-                Ok(ExecutionMode::Interactive(InteractiveConfig {
-                    workspace: workspace.clone(),
-                    verbose: *verbose,
-                }))
+                    use_devcontainer: *devcontainer,
+                    rerun_completed: *rerun_completed,
+                    approve: *approve,
+                    answers_file: answers.clone(),
+                    deadline: deadline.clone(),
+                })))
             }
-            Some(Commands::Checkpoint { command }) => match command {
+            Some(Commands::Interactive {
+                workspace,
+                verbose,
+                no_bell,
+            }) => Ok(ExecutionMode::Interactive(InteractiveConfig {
+                workspace: workspace.clone(),
+                verbose: *verbose,
+                bell: !*no_bell,
+            })),
+            Some(Commands::Resume {
+                checkpoint_id,
+                workspace,
+                verbose,
+            }) => Ok(ExecutionMode::Resume(ResumeConfig {
+                checkpoint_id: checkpoint_id.clone(),
+                workspace_override: workspace.clone(),
+                verbose: *verbose,
+                continue_latest: checkpoint_id.is_none(),
+            })),
+            Some(Commands::Checkpoints { command }) => match command {
                 CheckpointCommands::List { all_sessions } => Ok(ExecutionMode::ListCheckpoints {
                     all_sessions: *all_sessions,
                 }),
@@ -256,10 +1010,286 @@ impl Args {
                 }
             },
             Some(Commands::ShowConfig) => Ok(ExecutionMode::ShowConfig),
-            None => {
-                Err("No command specified. Use 'aca --help' to see available commands.".to_string())
+            Some(Commands::Config { command }) => match command {
+                ConfigCommands::Show => Ok(ExecutionMode::ShowConfig),
+                ConfigCommands::Init { local } => Ok(ExecutionMode::InitConfig(ConfigInitConfig {
+                    local: *local,
+                })),
+                ConfigCommands::Validate { file } => {
+                    Ok(ExecutionMode::ValidatePlan(PlanValidateConfig {
+                        path: file.clone(),
+                    }))
+                }
+            },
+            Some(Commands::Status { workspace }) => Ok(ExecutionMode::Status(StatusConfig {
+                workspace_override: workspace.clone(),
+            })),
+            Some(Commands::Plan { command }) => match command {
+                PlanCommands::Dump {
+                    file,
+                    output,
+                    config,
+                    workspace,
+                    use_intelligent_parser,
+                    force_naive_parser,
+                    context_hints,
+                } => Ok(ExecutionMode::Batch(Box::new(BatchConfig {
+                    config_override: config.clone(),
+                    workspace_override: workspace.clone(),
+                    dry_run: true,
+                    use_intelligent_parser: *use_intelligent_parser,
+                    force_naive_parser: *force_naive_parser,
+                    context_hints: context_hints.clone(),
+                    dump_plan: Some(output.clone()),
+                    provider_override: self.provider.map(|p| p.into_provider_type()),
+                    model_override: self.model.clone(),
+                    ..BatchConfig::new(Self::detect_file_type(file)?)
+                }))),
+                PlanCommands::Diff { old, new, format } => {
+                    Ok(ExecutionMode::DiffPlans(PlanDiffConfig {
+                        old: old.clone(),
+                        new: new.clone(),
+                        format: *format,
+                    }))
+                }
+            },
+            Some(Commands::Usage { workspace, by_tag }) => Ok(ExecutionMode::Usage(UsageConfig {
+                workspace_override: workspace.clone(),
+                by_tag: *by_tag,
+            })),
+            Some(Commands::Stats { workspace }) => Ok(ExecutionMode::Stats(StatsConfig {
+                workspace_override: workspace.clone(),
+            })),
+            Some(Commands::Watch {
+                verify,
+                config,
+                workspace,
+                verbose,
+                yes,
+            }) => Ok(ExecutionMode::Watch(WatchModeConfig {
+                verify_command: verify.clone(),
+                config_override: config.clone(),
+                workspace_override: workspace.clone(),
+                verbose: *verbose,
+                skip_confirmation: *yes,
+            })),
+            Some(Commands::FixBug {
+                description,
+                test_command,
+                config,
+                workspace,
+                verbose,
+            }) => Ok(ExecutionMode::FixBug(FixBugConfig {
+                description: description.clone(),
+                test_command: test_command.clone(),
+                config_override: config.clone(),
+                workspace_override: workspace.clone(),
+                verbose: *verbose,
+            })),
+            Some(Commands::Quick {
+                request,
+                verify,
+                no_verify,
+                config,
+                workspace,
+                quiet,
+            }) => Ok(ExecutionMode::Quick(QuickConfig {
+                request: request.clone(),
+                verify_command: verify.clone(),
+                skip_verify: *no_verify,
+                config_override: config.clone(),
+                workspace_override: workspace.clone(),
+                stream_output: !*quiet,
+            })),
+            Some(Commands::IdeServer { config, workspace }) => {
+                Ok(ExecutionMode::IdeServer(IdeServerConfig {
+                    config_override: config.clone(),
+                    workspace_override: workspace.clone(),
+                }))
             }
+            Some(Commands::Context { command }) => match command {
+                ContextCommands::ImportClaude {
+                    workspace,
+                    sessions,
+                    claude_dir,
+                } => Ok(ExecutionMode::ImportClaudeContext(ContextImportConfig {
+                    workspace_override: workspace.clone(),
+                    projects_dir: claude_dir.clone(),
+                    max_sessions: *sessions,
+                })),
+            },
+            Some(Commands::Logs { command }) => match command {
+                LogsCommands::Prune {
+                    session,
+                    config,
+                    workspace,
+                } => Ok(ExecutionMode::PruneLogs(LogsPruneConfig {
+                    config_override: config.clone(),
+                    workspace_override: workspace.clone(),
+                    session_id: session.clone(),
+                })),
+            },
+            Some(Commands::Task { command }) => match command {
+                TaskCommands::Transcript {
+                    task_id,
+                    format,
+                    workspace,
+                } => Ok(ExecutionMode::TaskTranscript(TranscriptConfig {
+                    task_ref: task_id.clone(),
+                    format: *format,
+                    workspace_override: workspace.clone(),
+                })),
+                TaskCommands::Why {
+                    task_id,
+                    format,
+                    workspace,
+                } => Ok(ExecutionMode::TaskWhy(TaskWhyConfig {
+                    task_ref: task_id.clone(),
+                    format: *format,
+                    workspace_override: workspace.clone(),
+                })),
+            },
+            Some(Commands::Replay {
+                session_id,
+                format,
+                workspace,
+            }) => Ok(ExecutionMode::Replay(ReplayConfig {
+                session_ref: session_id.clone(),
+                format: *format,
+                workspace_override: workspace.clone(),
+            })),
+            Some(Commands::Report { command }) => match command {
+                ReportCommands::PrDescription { session, workspace } => {
+                    Ok(ExecutionMode::PrDescription(PrDescriptionConfig {
+                        session_ref: session.clone(),
+                        workspace_override: workspace.clone(),
+                    }))
+                }
+                ReportCommands::Verify {
+                    file,
+                    key,
+                    workspace,
+                } => Ok(ExecutionMode::VerifyReport(ReportVerifyConfig {
+                    path: file.clone(),
+                    key: key.clone(),
+                    workspace_override: workspace.clone(),
+                })),
+            },
+            Some(Commands::Session { command }) => match command {
+                SessionCommands::Restore {
+                    from_remote: _,
+                    url,
+                    session,
+                    config,
+                    workspace,
+                } => Ok(ExecutionMode::RestoreSession(SessionRestoreConfig {
+                    config_override: config.clone(),
+                    workspace_override: workspace.clone(),
+                    session_id: session.clone(),
+                    remote_url: url.clone(),
+                })),
+            },
+            Some(Commands::Coordinator {
+                plan,
+                listen,
+                config,
+                workspace,
+            }) => Ok(ExecutionMode::Coordinator(CoordinatorModeConfig {
+                plan_file: plan.clone(),
+                listen: listen.clone(),
+                config_override: config.clone(),
+                workspace_override: workspace.clone(),
+            })),
+            Some(Commands::Worker {
+                coordinator,
+                id,
+                config,
+                workspace,
+                verbose,
+            }) => Ok(ExecutionMode::Worker(WorkerModeConfig {
+                coordinator: coordinator.clone(),
+                worker_id: id.clone(),
+                config_override: config.clone(),
+                workspace_override: workspace.clone(),
+                verbose: *verbose,
+            })),
+            Some(Commands::Serve {
+                config,
+                grpc_listen,
+            }) => Ok(ExecutionMode::Serve(ServeModeConfig {
+                config_file: config.clone(),
+                grpc_listen: grpc_listen.clone(),
+            })),
+            None => self.legacy_mode(),
+        }
+    }
+
+    /// The mode the pre-subcommand flags ask for
+    fn legacy_mode(&self) -> Result<ExecutionMode, String> {
+        let legacy = &self.legacy;
+        let modes = [
+            legacy.task_file.is_some(),
+            legacy.tasks.is_some(),
+            legacy.execution_plan.is_some(),
+            legacy.resume.is_some() || legacy.continue_latest,
+            legacy.list_checkpoints,
+            legacy.create_checkpoint.is_some(),
+            legacy.show_config,
+            legacy.interactive,
+        ];
+        if modes.iter().filter(|set| **set).count() > 1 {
+            return Err(
+                "Only one of --task-file, --tasks, --execution-plan, --resume/--continue, \
+                 --list-checkpoints, --create-checkpoint, --show-config and --interactive \
+                 may be given"
+                    .to_string(),
+            );
+        }
+
+        let task_input = match (&legacy.task_file, &legacy.tasks, &legacy.execution_plan) {
+            (Some(file), _, _) => Some(TaskInput::SingleFile(file.clone())),
+            (_, Some(file), _) => Some(TaskInput::TaskList(file.clone())),
+            (_, _, Some(file)) => Some(TaskInput::ExecutionPlan(file.clone())),
+            _ => None,
+        };
+        if let Some(task_input) = task_input {
+            return Ok(ExecutionMode::Batch(Box::new(BatchConfig {
+                config_override: legacy.config.clone(),
+                workspace_override: legacy.workspace.clone(),
+                verbose: legacy.verbose,
+                dry_run: legacy.dry_run,
+                provider_override: self.provider.map(|p| p.into_provider_type()),
+                model_override: self.model.clone(),
+                ..BatchConfig::new(task_input)
+            })));
+        }
+        if legacy.resume.is_some() || legacy.continue_latest {
+            return Ok(ExecutionMode::Resume(ResumeConfig {
+                checkpoint_id: legacy.resume.clone(),
+                workspace_override: legacy.workspace.clone(),
+                verbose: legacy.verbose,
+                continue_latest: legacy.continue_latest,
+            }));
+        }
+        if legacy.list_checkpoints {
+            return Ok(ExecutionMode::ListCheckpoints {
+                all_sessions: legacy.all_sessions,
+            });
+        }
+        if let Some(description) = &legacy.create_checkpoint {
+            return Ok(ExecutionMode::CreateCheckpoint(description.clone()));
         }
+        if legacy.show_config {
+            return Ok(ExecutionMode::ShowConfig);
+        }
+        if legacy.interactive {
+            return Ok(ExecutionMode::Interactive(InteractiveConfig {
+                workspace: legacy.workspace.clone(),
+                verbose: legacy.verbose,
+                bell: true,
+            }));
+        }
+        Err("No command specified. Use 'aca --help' to see available commands.".to_string())
     }
 
     /// Auto-detect file type based on extension
@@ -309,8 +1339,9 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
+            legacy: Default::default(),
             command: Some(Commands::Run {
-                file: PathBuf::from("tasks.md"),
+                file: Some(PathBuf::from("tasks.md")),
                 config: None,
                 workspace: None,
                 verbose: true,
@@ -318,9 +1349,21 @@ mod tests {
                 use_intelligent_parser: true,
                 force_naive_parser: false,
                 context_hints: vec!["hint1".to_string()],
-                dump_plan: None,
+                parse_timeout: Some(90),
+                max_depth: Some(2),
+                max_tasks: None,
+                granularity: DecompositionGranularity::Coarse,
+                dump: Default::default(),
+                report_junit: None,
+                report_sarif: None,
                 use_containers: false,
                 container_image: "alpine:latest".to_string(),
+                devcontainer: false,
+                rerun_completed: false,
+                approve: false,
+                answers: None,
+                from_clipboard: false,
+                deadline: None,
             }),
         };
         let mode = args.mode().unwrap();
@@ -330,6 +1373,15 @@ mod tests {
             assert!(config.verbose);
             assert!(config.use_intelligent_parser);
             assert_eq!(config.context_hints.len(), 1);
+            assert_eq!(
+                config.parse_timeout,
+                Some(std::time::Duration::from_secs(90))
+            );
+            assert_eq!(config.decomposition.max_depth, Some(2));
+            assert_eq!(
+                config.decomposition.granularity,
+                DecompositionGranularity::Coarse
+            );
         } else {
             panic!("Expected Batch mode");
         }
@@ -340,8 +1392,9 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
+            legacy: Default::default(),
             command: Some(Commands::Run {
-                file: PathBuf::from("plan.json"),
+                file: Some(PathBuf::from("plan.json")),
                 config: None,
                 workspace: None,
                 verbose: false,
@@ -349,9 +1402,21 @@ mod tests {
                 use_intelligent_parser: false,
                 force_naive_parser: false,
                 context_hints: vec![],
-                dump_plan: None,
+                parse_timeout: None,
+                max_depth: None,
+                max_tasks: None,
+                granularity: DecompositionGranularity::default(),
+                dump: Default::default(),
+                report_junit: None,
+                report_sarif: None,
                 use_containers: false,
                 container_image: "alpine:latest".to_string(),
+                devcontainer: false,
+                rerun_completed: true,
+                approve: false,
+                answers: Some(PathBuf::from("answers.toml")),
+                from_clipboard: false,
+                deadline: Some("8h".to_string()),
             }),
         };
         let mode = args.mode().unwrap();
@@ -359,18 +1424,367 @@ mod tests {
         if let ExecutionMode::Batch(config) = mode {
             assert!(matches!(config.task_input, TaskInput::ExecutionPlan(_)));
             assert!(config.dry_run);
+            assert!(config.rerun_completed);
+            assert_eq!(config.answers_file, Some(PathBuf::from("answers.toml")));
+            assert_eq!(config.deadline.as_deref(), Some("8h"));
         } else {
             panic!("Expected Batch mode");
         }
     }
 
+    #[test]
+    fn test_run_command_with_inline_input() {
+        let mode = Args::try_parse_from(["aca", "run", "-"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Batch(config) = mode else {
+            panic!("Expected Batch mode");
+        };
+        assert!(matches!(
+            config.task_input,
+            TaskInput::Inline(InlineSource::Stdin)
+        ));
+
+        let mode = Args::try_parse_from(["aca", "run", "--from-clipboard"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Batch(config) = mode else {
+            panic!("Expected Batch mode");
+        };
+        assert!(matches!(
+            config.task_input,
+            TaskInput::Inline(InlineSource::Clipboard)
+        ));
+
+        assert!(Args::try_parse_from(["aca", "run"]).is_err());
+        assert!(Args::try_parse_from(["aca", "run", "tasks.md", "--from-clipboard"]).is_err());
+    }
+
+    #[test]
+    fn test_watch_command() {
+        let mode = Args::try_parse_from(["aca", "watch", "--verify", "cargo test", "--yes"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Watch(config) = mode else {
+            panic!("Expected Watch mode");
+        };
+        assert_eq!(config.verify_command.as_deref(), Some("cargo test"));
+        assert!(config.skip_confirmation);
+    }
+
+    #[test]
+    fn test_quick_command() {
+        let mode = Args::try_parse_from(["aca", "quick", "Fix the typo in README", "--no-verify"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Quick(config) = mode else {
+            panic!("Expected Quick mode");
+        };
+        assert_eq!(config.request, "Fix the typo in README");
+        assert!(config.skip_verify);
+        assert!(config.stream_output);
+
+        assert!(
+            Args::try_parse_from(["aca", "quick", "x", "--verify", "make", "--no-verify"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_fix_bug_command() {
+        let mode = Args::try_parse_from([
+            "aca",
+            "fix-bug",
+            "Login redirects forever",
+            "--test-command",
+            "cargo test login",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::FixBug(config) = mode else {
+            panic!("Expected FixBug mode");
+        };
+        assert_eq!(config.description, "Login redirects forever");
+        assert_eq!(config.test_command.as_deref(), Some("cargo test login"));
+    }
+
+    #[test]
+    fn test_ide_server_command() {
+        let mode = Args::try_parse_from(["aca", "ide-server", "-w", "/repo"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::IdeServer(config) = mode else {
+            panic!("Expected IdeServer mode");
+        };
+        assert_eq!(config.workspace_override, Some(PathBuf::from("/repo")));
+    }
+
+    #[test]
+    fn test_run_command_dump_and_approve_flags() {
+        let mode = Args::try_parse_from([
+            "aca",
+            "run",
+            "tasks.md",
+            "--dump-graph",
+            "plan.mmd",
+            "--dump-plan",
+            "plan.json",
+            "--approve",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::Batch(config) = mode else {
+            panic!("Expected Batch mode");
+        };
+        assert_eq!(config.dump_graph, Some(PathBuf::from("plan.mmd")));
+        assert_eq!(config.dump_plan, Some(PathBuf::from("plan.json")));
+        assert_eq!(config.dump_context, None);
+        assert!(config.approve);
+    }
+
+    #[test]
+    fn test_context_import_claude_command() {
+        let mode = Args::try_parse_from(["aca", "context", "import-claude", "--sessions", "3"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::ImportClaudeContext(config) = mode else {
+            panic!("Expected ImportClaudeContext mode");
+        };
+        assert_eq!(config.max_sessions, 3);
+        assert_eq!(config.projects_dir, None);
+    }
+
+    #[test]
+    fn test_logs_prune_command() {
+        let mode = Args::try_parse_from(["aca", "logs", "prune", "--session", "abc"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::PruneLogs(config) = mode else {
+            panic!("Expected PruneLogs mode");
+        };
+        assert_eq!(config.session_id.as_deref(), Some("abc"));
+        assert_eq!(config.workspace_override, None);
+    }
+
+    #[test]
+    fn test_task_transcript_command() {
+        let mode = Args::try_parse_from(["aca", "task", "transcript", "3f2a", "--format", "json"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::TaskTranscript(config) = mode else {
+            panic!("Expected TaskTranscript mode");
+        };
+        assert_eq!(config.task_ref, "3f2a");
+        assert_eq!(config.format, TranscriptFormat::Json);
+
+        let mode = Args::try_parse_from(["aca", "task", "transcript", "3f2a"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::TaskTranscript(config) = mode else {
+            panic!("Expected TaskTranscript mode");
+        };
+        assert_eq!(config.format, TranscriptFormat::Md);
+    }
+
+    #[test]
+    fn test_replay_command() {
+        let mode = Args::try_parse_from(["aca", "replay", "9c1e", "--format", "json"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Replay(config) = mode else {
+            panic!("Expected Replay mode");
+        };
+        assert_eq!(config.session_ref, "9c1e");
+        assert_eq!(config.format, ReplayFormat::Json);
+    }
+
+    #[test]
+    fn test_task_why_command() {
+        let mode = Args::try_parse_from(["aca", "task", "why", "3f2a"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::TaskWhy(config) = mode else {
+            panic!("Expected TaskWhy mode");
+        };
+        assert_eq!(config.task_ref, "3f2a");
+        assert_eq!(config.format, WhyFormat::Text);
+
+        let mode = Args::try_parse_from(["aca", "task", "why", "3f2a", "--format", "json"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::TaskWhy(config) = mode else {
+            panic!("Expected TaskWhy mode");
+        };
+        assert_eq!(config.format, WhyFormat::Json);
+    }
+
+    #[test]
+    fn test_config_validate_command() {
+        let mode = Args::try_parse_from(["aca", "config", "validate", "plan.toml"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::ValidatePlan(config) = mode else {
+            panic!("Expected ValidatePlan mode");
+        };
+        assert_eq!(config.path, PathBuf::from("plan.toml"));
+    }
+
+    #[test]
+    fn test_plan_diff_command() {
+        let mode = Args::try_parse_from([
+            "aca", "plan", "diff", "old.json", "new.json", "--format", "json",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::DiffPlans(config) = mode else {
+            panic!("Expected DiffPlans mode");
+        };
+        assert_eq!(config.old, PathBuf::from("old.json"));
+        assert_eq!(config.new, PathBuf::from("new.json"));
+        assert_eq!(config.format, PlanDiffFormat::Json);
+    }
+
+    #[test]
+    fn test_report_pr_description_command() {
+        let mode = Args::try_parse_from(["aca", "report", "pr-description", "--session", "9c1e"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::PrDescription(config) = mode else {
+            panic!("Expected PrDescription mode");
+        };
+        assert_eq!(config.session_ref.as_deref(), Some("9c1e"));
+        assert_eq!(config.workspace_override, None);
+    }
+
+    #[test]
+    fn test_report_verify_command() {
+        let mode = Args::try_parse_from([
+            "aca",
+            "report",
+            "verify",
+            "run-report.json",
+            "--key",
+            "ab12",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::VerifyReport(config) = mode else {
+            panic!("Expected VerifyReport mode");
+        };
+        assert_eq!(config.path, PathBuf::from("run-report.json"));
+        assert_eq!(config.key.as_deref(), Some("ab12"));
+    }
+
+    #[test]
+    fn test_session_restore_command() {
+        let mode = Args::try_parse_from([
+            "aca",
+            "session",
+            "restore",
+            "--from-remote",
+            "--url",
+            "s3://bucket/aca",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::RestoreSession(config) = mode else {
+            panic!("Expected RestoreSession mode");
+        };
+        assert_eq!(config.remote_url.as_deref(), Some("s3://bucket/aca"));
+        assert_eq!(config.session_id, None);
+
+        // Only remote restores exist so far
+        assert!(Args::try_parse_from(["aca", "session", "restore"]).is_err());
+    }
+
+    #[test]
+    fn test_coordinator_and_worker_commands() {
+        let mode = Args::try_parse_from(["aca", "coordinator", "plan.toml"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Coordinator(config) = mode else {
+            panic!("Expected Coordinator mode");
+        };
+        assert_eq!(config.plan_file, PathBuf::from("plan.toml"));
+        assert_eq!(config.listen, crate::daemon::DEFAULT_COORDINATOR_ADDR);
+
+        let mode = Args::try_parse_from([
+            "aca",
+            "worker",
+            "--coordinator",
+            "10.0.0.5:7979",
+            "--id",
+            "gpu-1",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::Worker(config) = mode else {
+            panic!("Expected Worker mode");
+        };
+        assert_eq!(config.coordinator, "10.0.0.5:7979");
+        assert_eq!(config.worker_id.as_deref(), Some("gpu-1"));
+    }
+
+    #[test]
+    fn test_serve_command() {
+        let mode = Args::try_parse_from(["aca", "serve", "--config", "daemon.toml"])
+            .unwrap()
+            .mode()
+            .unwrap();
+        let ExecutionMode::Serve(config) = mode else {
+            panic!("Expected Serve mode");
+        };
+        assert_eq!(config.config_file, PathBuf::from("daemon.toml"));
+        assert_eq!(config.grpc_listen, None);
+
+        let mode = Args::try_parse_from([
+            "aca",
+            "serve",
+            "-c",
+            "daemon.toml",
+            "--grpc-listen",
+            "0.0.0.0:7980",
+        ])
+        .unwrap()
+        .mode()
+        .unwrap();
+        let ExecutionMode::Serve(config) = mode else {
+            panic!("Expected Serve mode");
+        };
+        assert_eq!(config.grpc_listen.as_deref(), Some("0.0.0.0:7980"));
+
+        // The tenants have to come from somewhere
+        assert!(Args::try_parse_from(["aca", "serve"]).is_err());
+    }
+
     #[test]
     fn test_run_command_with_toml() {
         let args = Args {
             provider: None,
             model: None,
+            legacy: Default::default(),
             command: Some(Commands::Run {
-                file: PathBuf::from("config.toml"),
+                file: Some(PathBuf::from("config.toml")),
                 config: None,
                 workspace: None,
                 verbose: false,
@@ -378,9 +1792,21 @@ mod tests {
                 use_intelligent_parser: false,
                 force_naive_parser: false,
                 context_hints: vec![],
-                dump_plan: None,
+                parse_timeout: None,
+                max_depth: None,
+                max_tasks: None,
+                granularity: DecompositionGranularity::default(),
+                dump: Default::default(),
+                report_junit: None,
+                report_sarif: None,
                 use_containers: false,
                 container_image: "alpine:latest".to_string(),
+                devcontainer: false,
+                rerun_completed: false,
+                approve: false,
+                answers: None,
+                from_clipboard: false,
+                deadline: None,
             }),
         };
         let mode = args.mode().unwrap();
@@ -482,9 +1908,11 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
+            legacy: Default::default(),
             command: Some(Commands::Interactive {
                 workspace: Some(PathBuf::from("/workspace")),
                 verbose: true,
+                no_bell: true,
             }),
         };
         let mode = args.mode().unwrap();
@@ -492,6 +1920,7 @@ mod tests {
         if let ExecutionMode::Interactive(config) = mode {
             assert!(config.workspace.is_some());
             assert!(config.verbose);
+            assert!(!config.bell);
         } else {
             panic!("Expected Interactive mode");
         }
@@ -502,6 +1931,7 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
+            legacy: Default::default(),
             command: None,
         };
         let result = args.mode();
@@ -513,7 +1943,8 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
-            command: Some(Commands::Checkpoint {
+            legacy: Default::default(),
+            command: Some(Commands::Checkpoints {
                 command: CheckpointCommands::List { all_sessions: true },
             }),
         };
@@ -531,7 +1962,8 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
-            command: Some(Commands::Checkpoint {
+            legacy: Default::default(),
+            command: Some(Commands::Checkpoints {
                 command: CheckpointCommands::Create {
                     description: "Test checkpoint".to_string(),
                 },
@@ -551,7 +1983,8 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
-            command: Some(Commands::Checkpoint {
+            legacy: Default::default(),
+            command: Some(Commands::Checkpoints {
                 command: CheckpointCommands::Resume {
                     checkpoint_id: "checkpoint-123".to_string(),
                     workspace: Some(PathBuf::from("/workspace")),
@@ -577,7 +2010,8 @@ mod tests {
         let args = Args {
             provider: None,
             model: None,
-            command: Some(Commands::Checkpoint {
+            legacy: Default::default(),
+            command: Some(Commands::Checkpoints {
                 command: CheckpointCommands::Resume {
                     checkpoint_id: "ignored".to_string(),
                     workspace: None,
@@ -596,4 +2030,118 @@ mod tests {
             panic!("Expected Resume mode");
         }
     }
+
+    #[test]
+    fn test_usage_by_tag() {
+        let args = Args {
+            provider: None,
+            model: None,
+            legacy: Default::default(),
+            command: Some(Commands::Usage {
+                workspace: Some(PathBuf::from("/tmp/ws")),
+                by_tag: true,
+            }),
+        };
+        let mode = args.mode().unwrap();
+
+        if let ExecutionMode::Usage(config) = mode {
+            assert_eq!(config.workspace_override, Some(PathBuf::from("/tmp/ws")));
+            assert!(config.by_tag);
+        } else {
+            panic!("Expected Usage mode");
+        }
+    }
+
+    #[test]
+    fn test_stats_workspace() {
+        let args = Args::try_parse_from(["aca", "stats", "--workspace", "/tmp/ws"]).unwrap();
+
+        let ExecutionMode::Stats(config) = args.mode().unwrap() else {
+            panic!("Expected Stats mode");
+        };
+        assert_eq!(config.workspace_override, Some(PathBuf::from("/tmp/ws")));
+    }
+
+    #[test]
+    fn test_resume_status_and_config_commands() {
+        let mode = |args: &[&str]| Args::try_parse_from(args).unwrap().mode().unwrap();
+
+        let ExecutionMode::Resume(config) = mode(&["aca", "resume"]) else {
+            panic!("Expected Resume mode");
+        };
+        assert!(config.continue_latest);
+        let ExecutionMode::Resume(config) = mode(&["aca", "resume", "cp-1", "-w", "/repo"]) else {
+            panic!("Expected Resume mode");
+        };
+        assert_eq!(config.checkpoint_id.as_deref(), Some("cp-1"));
+        assert!(!config.continue_latest);
+
+        assert!(matches!(
+            mode(&["aca", "checkpoints", "list"]),
+            ExecutionMode::ListCheckpoints {
+                all_sessions: false
+            }
+        ));
+        assert!(matches!(
+            mode(&["aca", "checkpoint", "create", "before refactor"]),
+            ExecutionMode::CreateCheckpoint(_)
+        ));
+        assert!(matches!(mode(&["aca", "status"]), ExecutionMode::Status(_)));
+        assert!(matches!(
+            mode(&["aca", "config", "show"]),
+            ExecutionMode::ShowConfig
+        ));
+        assert!(matches!(
+            mode(&["aca", "show-config"]),
+            ExecutionMode::ShowConfig
+        ));
+        assert!(matches!(
+            mode(&["aca", "config", "init", "--local"]),
+            ExecutionMode::InitConfig(ConfigInitConfig { local: true })
+        ));
+
+        let ExecutionMode::Batch(config) =
+            mode(&["aca", "plan", "dump", "tasks.md", "-o", "plan.json"])
+        else {
+            panic!("Expected Batch mode");
+        };
+        assert!(matches!(config.task_input, TaskInput::TaskList(_)));
+        assert!(config.dry_run);
+        assert_eq!(config.dump_plan, Some(PathBuf::from("plan.json")));
+    }
+
+    #[test]
+    fn test_legacy_flags() {
+        let mode = |args: &[&str]| Args::try_parse_from(args).unwrap().mode();
+
+        let Ok(ExecutionMode::Batch(config)) =
+            mode(&["aca", "--tasks", "todo.md", "-w", "/repo", "--dry-run"])
+        else {
+            panic!("Expected Batch mode");
+        };
+        assert!(matches!(config.task_input, TaskInput::TaskList(_)));
+        assert_eq!(config.workspace_override, Some(PathBuf::from("/repo")));
+        assert!(config.dry_run);
+        assert!(matches!(
+            mode(&["aca", "--task-file", "bug.txt"]),
+            Ok(ExecutionMode::Batch(config)) if matches!(config.task_input, TaskInput::SingleFile(_))
+        ));
+
+        let Ok(ExecutionMode::Resume(config)) = mode(&["aca", "--continue"]) else {
+            panic!("Expected Resume mode");
+        };
+        assert!(config.continue_latest);
+        assert!(matches!(
+            mode(&["aca", "--list-checkpoints", "--all-sessions"]),
+            Ok(ExecutionMode::ListCheckpoints { all_sessions: true })
+        ));
+        assert!(matches!(
+            mode(&["aca", "--show-config"]),
+            Ok(ExecutionMode::ShowConfig)
+        ));
+
+        assert!(mode(&["aca", "--show-config", "--continue"]).is_err());
+        // The old flags do not mix with subcommands
+        assert!(Args::try_parse_from(["aca", "--continue", "status"]).is_err());
+    }
 }
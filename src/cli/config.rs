@@ -7,7 +7,17 @@
 //! 4. Built-in defaults
 
 use crate::{
-    AgentConfig, claude::ClaudeConfig, env, session::SessionManagerConfig, task::TaskManagerConfig,
+    AgentConfig,
+    claude::{ClaudeConfig, ContextBudgetConfig},
+    daemon::DistributedConfig,
+    env,
+    executor::PlacementConfig,
+    llm::{BudgetConfig, instructions::InstructionsConfig},
+    session::{HeartbeatConfig, LogRetentionConfig, RemoteSyncConfig, SessionManagerConfig},
+    task::{
+        CompletionGateConfig, QuestionConfig, RefactorGuardConfig, ScheduleConfig,
+        TaskManagerConfig, WatchConfig,
+    },
 };
 use serde::{Deserialize, Serialize};
 use std::env as std_env;
@@ -21,6 +31,64 @@ pub struct DefaultAgentConfig {
     pub session_config: SessionManagerConfig,
     pub task_config: TaskManagerConfig,
     pub claude_config: ClaudeConfig,
+    #[serde(default)]
+    pub instructions: InstructionsConfig,
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub questions: QuestionConfig,
+    #[serde(default)]
+    pub watch: WatchConfig,
+    #[serde(default)]
+    pub refactor_guard: RefactorGuardConfig,
+    #[serde(default)]
+    pub completion_gates: CompletionGateConfig,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub log_retention: LogRetentionConfig,
+    #[serde(default)]
+    pub remote_sync: RemoteSyncConfig,
+    #[serde(default)]
+    pub distributed: DistributedConfig,
+    #[serde(default)]
+    pub placement: PlacementConfig,
+    #[serde(default)]
+    pub context_budget: ContextBudgetConfig,
+    #[serde(default)]
+    pub nix: crate::executor::NixConfig,
+    #[serde(default)]
+    pub offline: crate::llm::OfflineConfig,
+    #[serde(default)]
+    pub routing: crate::llm::RoutingConfig,
+    #[serde(default)]
+    pub verification: crate::task::VerificationConfig,
+    #[serde(default)]
+    pub spot_check: crate::task::SpotCheckConfig,
+    #[serde(default)]
+    pub container_runtime: crate::executor::ContainerRuntime,
+    #[serde(default)]
+    pub docker_context: Option<String>,
+    #[serde(default)]
+    pub policy: crate::policy::PolicyConfig,
+    #[serde(default)]
+    pub report_signing: crate::session::ReportSigningConfig,
+    #[serde(default)]
+    pub anonymization: crate::llm::AnonymizationConfig,
+    #[serde(default)]
+    pub mcp: crate::mcp::McpConfig,
+    #[serde(default)]
+    pub prompt_size: crate::session::PromptSizeConfig,
+    #[serde(default)]
+    pub rollup: crate::task::RollupConfig,
+    #[serde(default)]
+    pub wait: crate::task::WaitConfig,
+    #[serde(default)]
+    pub ports: crate::executor::PortConfig,
+    #[serde(default)]
+    pub text_files: crate::workspace::TextFileConfig,
 }
 
 impl Default for DefaultAgentConfig {
@@ -31,6 +99,35 @@ impl Default for DefaultAgentConfig {
             session_config: default_agent.session_config,
             task_config: default_agent.task_config,
             claude_config: default_agent.claude_config,
+            instructions: default_agent.instructions,
+            heartbeat: default_agent.heartbeat,
+            schedule: default_agent.schedule,
+            questions: default_agent.questions,
+            watch: default_agent.watch,
+            refactor_guard: default_agent.refactor_guard,
+            completion_gates: default_agent.completion_gates,
+            budget: default_agent.budget,
+            log_retention: default_agent.log_retention,
+            remote_sync: default_agent.remote_sync,
+            distributed: default_agent.distributed,
+            placement: default_agent.placement,
+            context_budget: default_agent.context_budget,
+            nix: default_agent.nix,
+            offline: default_agent.offline,
+            routing: default_agent.routing,
+            verification: default_agent.verification,
+            spot_check: default_agent.spot_check,
+            container_runtime: default_agent.container_runtime,
+            docker_context: default_agent.docker_context,
+            policy: default_agent.policy,
+            report_signing: default_agent.report_signing,
+            anonymization: default_agent.anonymization,
+            mcp: default_agent.mcp,
+            prompt_size: default_agent.prompt_size,
+            rollup: default_agent.rollup,
+            wait: default_agent.wait,
+            ports: default_agent.ports,
+            text_files: default_agent.text_files,
         }
     }
 }
@@ -45,10 +142,40 @@ impl DefaultAgentConfig {
         AgentConfig {
             workspace_path,
             setup_commands: Vec::new(), // Will be populated by task processing
+            services: Vec::new(),
             session_config: self.session_config.clone(),
             task_config: self.task_config.clone(),
             claude_config: self.claude_config.clone(),
             execution_mode: crate::executor::RuntimeMode::Host,
+            container_runtime: self.container_runtime,
+            docker_context: self.docker_context.clone(),
+            instructions: self.instructions.clone(),
+            heartbeat: self.heartbeat.clone(),
+            schedule: self.schedule.clone(),
+            questions: self.questions.clone(),
+            watch: self.watch.clone(),
+            refactor_guard: self.refactor_guard.clone(),
+            completion_gates: self.completion_gates.clone(),
+            budget: self.budget.clone(),
+            log_retention: self.log_retention.clone(),
+            remote_sync: self.remote_sync.clone(),
+            distributed: self.distributed.clone(),
+            placement: self.placement.clone(),
+            context_budget: self.context_budget.clone(),
+            nix: self.nix.clone(),
+            offline: self.offline.clone(),
+            routing: self.routing.clone(),
+            verification: self.verification.clone(),
+            spot_check: self.spot_check.clone(),
+            policy: self.policy.clone(),
+            report_signing: self.report_signing.clone(),
+            anonymization: self.anonymization.clone(),
+            mcp: self.mcp.clone(),
+            prompt_size: self.prompt_size.clone(),
+            rollup: self.rollup.clone(),
+            wait: self.wait.clone(),
+            ports: self.ports.clone(),
+            text_files: self.text_files.clone(),
         }
     }
 
@@ -138,13 +265,21 @@ impl ConfigDiscovery {
     /// Create a default config file in the user's home directory
     pub fn create_default_user_config() -> Result<PathBuf, Box<dyn std::error::Error>> {
         let home_dir = Self::get_home_dir().ok_or("Could not determine home directory")?;
+        Self::create_default_config(env::user_config_file_path(&home_dir))
+    }
 
-        let config_dir = env::user_config_dir_path(&home_dir);
-        let config_path = env::user_config_file_path(&home_dir);
+    /// Create a default config file in `dir`'s `.aca` directory
+    pub fn create_default_local_config(dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Self::create_default_config(env::local_config_file_path(dir))
+    }
 
+    /// Write the default config to `config_path` unless a file is already there
+    fn create_default_config(config_path: PathBuf) -> Result<PathBuf, Box<dyn std::error::Error>> {
         // Create directory if it doesn't exist
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir)?;
+        if let Some(config_dir) = config_path.parent()
+            && !config_dir.exists()
+        {
+            fs::create_dir_all(config_dir)?;
             info!("Created configuration directory: {:?}", config_dir);
         }
 
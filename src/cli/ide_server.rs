@@ -0,0 +1,540 @@
+//! JSON-RPC command server for editor integrations (`aca ide-server`)
+//!
+//! Speaks JSON-RPC 2.0 on stdin/stdout with LSP-style `Content-Length`
+//! framing, so Zed and VS Code extensions can reuse their language-client
+//! plumbing instead of re-implementing plan and session handling.
+//!
+//! | Method | Direction | Purpose |
+//! |--------|-----------|---------|
+//! | `initialize` | editor → aca | Handshake, returns server info |
+//! | `aca/submitTask` | editor → aca | Run selected text as a task, returns its edits |
+//! | `aca/progress` | aca → editor (notification) | Task status changes |
+//! | `aca/askQuestion` | aca → editor (request) | Question from the model; reply with `{ "answer": "..." }` or `null` |
+//! | `shutdown` / `exit` | editor → aca | Stop the server |
+//!
+//! The agent writes its changes to disk as usual. The `edits` of a
+//! `aca/submitTask` result list every file the task created, changed or
+//! deleted with its new text, so the editor can apply them to open buffers
+//! as workspace edits (keeping them undoable) rather than reloading.
+
+use crate::AgentSystem;
+use crate::task::{
+    FileImportance, FileRef, QuestionAnswer, QuestionHandler, QuestionRequest, TaskMetadata,
+    TaskPriority, TaskSpec, WorkspaceSnapshot,
+};
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+pub const INITIALIZE: &str = "initialize";
+pub const SHUTDOWN: &str = "shutdown";
+pub const EXIT: &str = "exit";
+pub const SUBMIT_TASK: &str = "aca/submitTask";
+pub const PROGRESS: &str = "aca/progress";
+pub const ASK_QUESTION: &str = "aca/askQuestion";
+
+/// `answered_by` recorded for answers given in the editor
+pub const ANSWERED_BY_EDITOR: &str = "editor";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// How often a running task's status is checked for progress notifications
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Read one framed message; `None` at end of input
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> std::io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "message without Content-Length header",
+        )
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Frame a message with its `Content-Length` header
+pub fn frame_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut framed = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    framed.extend_from_slice(body.as_bytes());
+    framed
+}
+
+/// 1-based, inclusive line range of an editor selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Parameters of `aca/submitTask`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitTaskParams {
+    /// Selected text
+    pub text: String,
+    /// What to do with the selection; without one the text is the task
+    #[serde(default)]
+    pub instruction: Option<String>,
+    /// File the selection is from, absolute or relative to the workspace
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub range: Option<LineRange>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl SubmitTaskParams {
+    pub fn task_spec(&self) -> TaskSpec {
+        let location = match (&self.path, self.range) {
+            (Some(path), Some(range)) => {
+                format!(
+                    " from {} (lines {}-{})",
+                    path.display(),
+                    range.start,
+                    range.end
+                )
+            }
+            (Some(path), None) => format!(" from {}", path.display()),
+            _ => String::new(),
+        };
+
+        let description = match &self.instruction {
+            Some(instruction) => format!(
+                "{}\n\nSelected text{}:\n```\n{}\n```",
+                instruction.trim(),
+                location,
+                self.text
+            ),
+            None if location.is_empty() => self.text.trim().to_string(),
+            None => format!("{}\n\n(Selected{}.)", self.text.trim(), location),
+        };
+
+        let title = self.title.clone().unwrap_or_else(|| {
+            let source = self.instruction.as_deref().unwrap_or(&self.text);
+            let line = source.trim().lines().next().unwrap_or("Editor task");
+            if line.chars().count() > 60 {
+                format!("{}...", line.chars().take(57).collect::<String>())
+            } else {
+                line.to_string()
+            }
+        });
+
+        TaskSpec {
+            title,
+            description,
+            dependencies: Vec::new(),
+            metadata: TaskMetadata {
+                priority: TaskPriority::High,
+                tags: vec!["ide".to_string()],
+                file_refs: self
+                    .path
+                    .iter()
+                    .map(|path| FileRef {
+                        path: path.clone(),
+                        repository: String::new(),
+                        line_range: self.range.map(|range| (range.start, range.end)),
+                        importance: FileImportance::Critical,
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            plan: None,
+        }
+    }
+}
+
+/// How a task changed a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileEditKind {
+    Created,
+    Changed,
+    Deleted,
+}
+
+/// One file edit of a task, with the file's new text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub kind: FileEditKind,
+    /// New contents; absent for deleted or non-UTF-8 files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Edits between two snapshots of the workspace at `root`
+pub fn workspace_edits(
+    root: &Path,
+    before: &WorkspaceSnapshot,
+    after: &WorkspaceSnapshot,
+) -> Vec<FileEdit> {
+    after
+        .changed_since(before)
+        .into_iter()
+        .map(|relative| {
+            let path = root.join(&relative);
+            let kind = if !after.contains(&relative) {
+                FileEditKind::Deleted
+            } else if !before.contains(&relative) {
+                FileEditKind::Created
+            } else {
+                FileEditKind::Changed
+            };
+            let text = match kind {
+                FileEditKind::Deleted => None,
+                _ => std::fs::read_to_string(&path).ok(),
+            };
+            FileEdit { path, kind, text }
+        })
+        .collect()
+}
+
+/// Outgoing messages plus the requests this server sent to the editor
+#[derive(Clone)]
+struct Outbox {
+    sender: mpsc::UnboundedSender<Value>,
+    next_id: Arc<AtomicU64>,
+    pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
+}
+
+impl Outbox {
+    fn send(&self, message: Value) {
+        // The writer only stops once the server is exiting
+        let _ = self.sender.send(message);
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn respond(&self, id: Value, result: std::result::Result<Value, (i64, String)>) {
+        self.send(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        });
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Option<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (reply, response) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, reply);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        response.await.ok()
+    }
+
+    fn resolve(&self, id: &Value, response: Value) {
+        let waiting = id
+            .as_u64()
+            .and_then(|id| self.pending.lock().unwrap().remove(&id));
+        match waiting {
+            Some(reply) => {
+                let _ = reply.send(response);
+            }
+            None => warn!("Response to unknown request {}", id),
+        }
+    }
+}
+
+/// Forwards model questions to the editor as `aca/askQuestion` requests
+struct EditorQuestionHandler {
+    outbox: Outbox,
+}
+
+impl QuestionHandler for EditorQuestionHandler {
+    fn ask<'a>(
+        &'a self,
+        request: &'a QuestionRequest,
+    ) -> BoxFuture<'a, Result<Option<QuestionAnswer>>> {
+        Box::pin(async move {
+            let response = self
+                .outbox
+                .request(ASK_QUESTION, serde_json::to_value(request)?)
+                .await;
+            let answer = response
+                .as_ref()
+                .and_then(|response| response.get("result"))
+                .and_then(|result| result.get("answer"))
+                .and_then(Value::as_str)
+                .map(|answer| QuestionAnswer {
+                    answer: answer.to_string(),
+                    answered_by: ANSWERED_BY_EDITOR.to_string(),
+                });
+            Ok(answer)
+        })
+    }
+}
+
+/// Serves editor requests against one agent system
+pub struct IdeServer {
+    agent: Arc<AgentSystem>,
+    workspace: PathBuf,
+    ignore: Vec<String>,
+    /// Tasks run one at a time so each result's edits are its own
+    running: tokio::sync::Mutex<()>,
+}
+
+impl IdeServer {
+    pub fn new(agent: Arc<AgentSystem>, workspace: PathBuf, ignore: Vec<String>) -> Self {
+        Self {
+            agent,
+            workspace,
+            ignore,
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Serve until `exit` or end of input
+    pub async fn serve<R, W>(self: Arc<Self>, input: R, mut output: W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (sender, mut outgoing) = mpsc::unbounded_channel::<Value>();
+        let writer = tokio::spawn(async move {
+            // `Null` marks the end of output once the input is done
+            while let Some(message) = outgoing.recv().await.filter(|m| !m.is_null()) {
+                output.write_all(&frame_message(&message)).await?;
+                output.flush().await?;
+            }
+            std::io::Result::Ok(())
+        });
+
+        let outbox = Outbox {
+            sender,
+            next_id: Arc::new(AtomicU64::new(1)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        };
+        self.agent
+            .set_question_handler(Arc::new(EditorQuestionHandler {
+                outbox: outbox.clone(),
+            }));
+
+        let mut input = input;
+        while let Some(body) = read_message(&mut input).await? {
+            let message: Value = match serde_json::from_str(&body) {
+                Ok(message) => message,
+                Err(e) => {
+                    outbox.respond(Value::Null, Err((PARSE_ERROR, e.to_string())));
+                    continue;
+                }
+            };
+
+            let id = message.get("id").cloned();
+            let Some(method) = message.get("method").and_then(Value::as_str) else {
+                // A response to one of our requests
+                if let Some(id) = id {
+                    outbox.resolve(&id, message);
+                }
+                continue;
+            };
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match (method, id) {
+                (EXIT, _) => break,
+                (INITIALIZE, Some(id)) => outbox.respond(
+                    id,
+                    Ok(json!({
+                        "serverInfo": { "name": "aca", "version": env!("CARGO_PKG_VERSION") },
+                        "capabilities": { "submitTask": true, "askQuestion": true }
+                    })),
+                ),
+                (SHUTDOWN, Some(id)) => outbox.respond(id, Ok(Value::Null)),
+                (SUBMIT_TASK, Some(id)) => {
+                    let server = self.clone();
+                    let outbox = outbox.clone();
+                    tokio::spawn(async move {
+                        let result = server.submit_task(params, &outbox).await;
+                        outbox.respond(id, result);
+                    });
+                }
+                (_, Some(id)) => outbox.respond(
+                    id,
+                    Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+                ),
+                // Other notifications (e.g. `initialized`) need no reply
+                (_, None) => {}
+            }
+        }
+
+        info!("IDE server input closed, stopping");
+        outbox.send(Value::Null);
+        writer.await??;
+        Ok(())
+    }
+
+    async fn submit_task(
+        &self,
+        params: Value,
+        outbox: &Outbox,
+    ) -> std::result::Result<Value, (i64, String)> {
+        let params: SubmitTaskParams =
+            serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+        let internal = |e: anyhow::Error| (INTERNAL_ERROR, e.to_string());
+
+        let _running = self.running.lock().await;
+        let before = WorkspaceSnapshot::capture(&self.workspace, &self.ignore)
+            .map_err(|e| internal(e.into()))?;
+
+        let task_manager = self.agent.task_manager();
+        let task_id = task_manager
+            .create_task(params.task_spec(), None)
+            .await
+            .map_err(internal)?;
+        outbox.notify(
+            PROGRESS,
+            json!({ "taskId": task_id, "status": "pending", "message": "Task created" }),
+        );
+
+        let processing = self.agent.process_task(task_id);
+        tokio::pin!(processing);
+        let mut last_status = "pending";
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut processing => break outcome,
+                _ = tokio::time::sleep(PROGRESS_POLL_INTERVAL) => {
+                    if let Ok(task) = task_manager.get_task(task_id).await {
+                        let status = task.status.name();
+                        if status != last_status {
+                            last_status = status;
+                            outbox.notify(PROGRESS, json!({ "taskId": task_id, "status": status }));
+                        }
+                    }
+                }
+            }
+        };
+
+        let task = task_manager.get_task(task_id).await.map_err(internal)?;
+        let after = WorkspaceSnapshot::capture(&self.workspace, &self.ignore)
+            .map_err(|e| internal(e.into()))?;
+        let edits = workspace_edits(&self.workspace, &before, &after);
+        outbox.notify(
+            PROGRESS,
+            json!({
+                "taskId": task_id,
+                "status": task.status.name(),
+                "message": match &outcome {
+                    Ok(()) => format!("{} file(s) edited", edits.len()),
+                    Err(e) => e.to_string(),
+                }
+            }),
+        );
+
+        Ok(json!({
+            "taskId": task_id,
+            "status": task.status.name(),
+            "response": task.response(),
+            "error": outcome.err().map(|e| e.to_string()),
+            "edits": edits,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_message_framing_round_trip() {
+        let first = json!({ "jsonrpc": "2.0", "id": 1, "method": INITIALIZE });
+        let second = json!({ "jsonrpc": "2.0", "method": EXIT, "params": { "note": "ü" } });
+        let mut stream = frame_message(&first);
+        stream.extend(frame_message(&second));
+
+        let mut reader = tokio::io::BufReader::new(stream.as_slice());
+        let read = |body: Option<String>| serde_json::from_str::<Value>(&body.unwrap()).unwrap();
+        assert_eq!(read(read_message(&mut reader).await.unwrap()), first);
+        assert_eq!(read(read_message(&mut reader).await.unwrap()), second);
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_submit_task_params_to_spec() {
+        let params: SubmitTaskParams = serde_json::from_value(json!({
+            "text": "fn add(a: i32, b: i32) -> i32 { a - b }",
+            "instruction": "Fix the bug in this function",
+            "path": "src/math.rs",
+            "range": { "start": 3, "end": 3 }
+        }))
+        .unwrap();
+        let spec = params.task_spec();
+        assert_eq!(spec.title, "Fix the bug in this function");
+        assert!(spec.description.contains("from src/math.rs (lines 3-3)"));
+        assert!(spec.description.contains("a - b"));
+        assert_eq!(spec.metadata.file_refs[0].line_range, Some((3, 3)));
+
+        let bare: SubmitTaskParams =
+            serde_json::from_value(json!({ "text": "TODO: add input validation" })).unwrap();
+        assert_eq!(bare.task_spec().description, "TODO: add input validation");
+    }
+
+    #[test]
+    fn test_workspace_edits() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        std::fs::write(root.join("keep.rs"), "fn a() {}").unwrap();
+        std::fs::write(root.join("gone.rs"), "fn b() {}").unwrap();
+        let before = WorkspaceSnapshot::capture(root, &[]).unwrap();
+
+        std::fs::write(root.join("keep.rs"), "fn a() { todo!() }").unwrap();
+        std::fs::remove_file(root.join("gone.rs")).unwrap();
+        std::fs::write(root.join("new.rs"), "fn c() {}").unwrap();
+        let after = WorkspaceSnapshot::capture(root, &[]).unwrap();
+
+        let edits = workspace_edits(root, &before, &after);
+        let kinds: Vec<_> = edits
+            .iter()
+            .map(|edit| (edit.path.file_name().unwrap().to_str().unwrap(), edit.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("gone.rs", FileEditKind::Deleted),
+                ("keep.rs", FileEditKind::Changed),
+                ("new.rs", FileEditKind::Created),
+            ]
+        );
+        assert_eq!(edits[1].text.as_deref(), Some("fn a() { todo!() }"));
+        assert!(edits[0].text.is_none());
+    }
+}
@@ -25,6 +25,11 @@
 //! - Proper dependency graph construction
 //! - Support for complex task relationships
 //!
+//! ### Response Validation
+//! Responses are validated against a JSON Schema of [`TaskAnalysisResult`] plus
+//! index checks, and the model is re-asked with the errors when they fail (see
+//! [`crate::llm::structured`]).
+//!
 //! ### System Message Support
 //! System messages are passed via Claude CLI's `--append-system-prompt` flag for
 //! clean separation of instructions from user content.
@@ -33,11 +38,17 @@
 //!
 //! - **Detail Preservation**: Typical 6 high-level tasks → 42+ detailed subtasks
 //! - **Reference Resolution**: Supports multiple linked files with cycle prevention
-//! - **Caching**: Responses cached by content hash for improved performance
+//! - **Caching**: Responses cached by content hash for improved performance; with
+//!   [`IntelligentTaskParser::with_analysis_store`] they are also saved to
+//!   `.aca/plans/<hash>.json` so a re-run of unchanged content skips the LLM
+//! - **Timeout**: Analysis gives up after [`DEFAULT_ANALYSIS_TIMEOUT`] (see
+//!   [`IntelligentTaskParser::with_timeout`]) so a stuck call cannot hang a run
 //!
 //! Unlike the naive `TaskLoader`, this parser understands semantic meaning and context.
 
-use crate::llm::{LLMProvider, LLMRequest};
+use crate::llm::{
+    DEFAULT_STRUCTURED_RETRIES, LLMError, LLMProvider, LLMRequest, execute_structured,
+};
 use crate::task::{
     ComplexityLevel, ContextRequirements, ExecutionPlan, TaskId, TaskMetadata, TaskPriority,
     TaskSpec,
@@ -46,8 +57,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 #[derive(Debug, Error)]
@@ -63,8 +75,17 @@ pub enum IntelligentParserError {
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("Task analysis timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Task analysis was cancelled")]
+    Cancelled,
 }
 
+/// How long task analysis may take before it is abandoned
+pub const DEFAULT_ANALYSIS_TIMEOUT: Duration = Duration::from_secs(300);
+
 /// Request for intelligent task analysis
 #[derive(Debug, Clone)]
 pub struct TaskAnalysisRequest {
@@ -76,6 +97,77 @@ pub struct TaskAnalysisRequest {
     pub context_hints: Vec<String>,
     /// Maximum number of tokens to use for analysis
     pub max_tokens: Option<u64>,
+    /// How finely the content may be broken down
+    pub decomposition: DecompositionLimits,
+}
+
+/// How small the tasks of an analysis should be
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DecompositionGranularity {
+    /// A few large tasks; trivial and simple leaves are folded into their parent
+    Coarse,
+    /// One task per meaningful unit of work; trivial leaves are folded in
+    #[default]
+    Medium,
+    /// Every step as its own task
+    Fine,
+}
+
+impl DecompositionGranularity {
+    /// Depth used when no explicit maximum is given
+    fn default_max_depth(self) -> Option<usize> {
+        match self {
+            Self::Coarse => Some(2),
+            Self::Medium => Some(3),
+            Self::Fine => None,
+        }
+    }
+
+    /// Leaves at or below this complexity are merged into their parent
+    fn merge_threshold(self) -> Option<ComplexityLevel> {
+        match self {
+            Self::Coarse => Some(ComplexityLevel::Simple),
+            Self::Medium => Some(ComplexityLevel::Trivial),
+            Self::Fine => None,
+        }
+    }
+
+    fn guidance(self) -> &'static str {
+        match self {
+            Self::Coarse => {
+                "coarse: prefer a few substantial tasks; keep small steps inside the description of the task they belong to"
+            }
+            Self::Medium => {
+                "medium: one task per meaningful unit of work; do not create tasks for trivial steps"
+            }
+            Self::Fine => "fine: break the work into individual, independently executable steps",
+        }
+    }
+}
+
+/// Limits on how an analysis may decompose the content
+///
+/// They are stated in the prompt and enforced afterwards: tasks nested deeper
+/// than `max_depth`, leaves too fine for the granularity, and the finest
+/// leaves beyond `max_tasks` are merged into their parents.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DecompositionLimits {
+    /// Levels of nesting, 1 meaning only top-level tasks (default by granularity)
+    pub max_depth: Option<usize>,
+    /// Total number of tasks
+    pub max_tasks: Option<usize>,
+    pub granularity: DecompositionGranularity,
+}
+
+impl DecompositionLimits {
+    fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+            .or_else(|| self.granularity.default_max_depth())
+            .map(|depth| depth.max(1))
+    }
 }
 
 /// Result of intelligent task analysis
@@ -125,10 +217,23 @@ pub enum ExecutionStrategy {
     Intelligent,
 }
 
+/// Task analysis saved in the workspace, keyed by content hash
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedAnalysis {
+    source_path: Option<PathBuf>,
+    saved_at: chrono::DateTime<chrono::Utc>,
+    analysis: TaskAnalysisResult,
+}
+
 /// Intelligent task parser that uses LLM for analysis
 pub struct IntelligentTaskParser {
     llm_provider: Arc<dyn LLMProvider>,
     enable_caching: bool,
+    timeout: Duration,
+    /// Workspace whose `.aca/plans` holds saved analyses
+    analysis_store: Option<PathBuf>,
+    /// Limits for analyses requested through [`Self::parse_file`]
+    decomposition: DecompositionLimits,
     cache: std::sync::Mutex<HashMap<String, TaskAnalysisResult>>,
 }
 
@@ -138,6 +243,9 @@ impl IntelligentTaskParser {
         Self {
             llm_provider,
             enable_caching: true,
+            timeout: DEFAULT_ANALYSIS_TIMEOUT,
+            analysis_store: None,
+            decomposition: DecompositionLimits::default(),
             cache: std::sync::Mutex::new(HashMap::new()),
         }
     }
@@ -147,10 +255,35 @@ impl IntelligentTaskParser {
         Self {
             llm_provider,
             enable_caching: false,
+            timeout: DEFAULT_ANALYSIS_TIMEOUT,
+            analysis_store: None,
+            decomposition: DecompositionLimits::default(),
             cache: std::sync::Mutex::new(HashMap::new()),
         }
     }
 
+    /// Limit how long [`Self::analyze_tasks`] waits for the LLM, retries included
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Decomposition limits for files parsed with [`Self::parse_file`]
+    pub fn with_decomposition(mut self, decomposition: DecompositionLimits) -> Self {
+        self.decomposition = decomposition;
+        self
+    }
+
+    /// Save each analysis under the workspace's `.aca/plans` and reuse it
+    /// when the same content is analyzed again
+    ///
+    /// Analyses are written as soon as the LLM returns, so one that took
+    /// minutes survives a crash before the plan starts executing.
+    pub fn with_analysis_store(mut self, workspace_root: impl Into<PathBuf>) -> Self {
+        self.analysis_store = Some(workspace_root.into());
+        self
+    }
+
     /// Analyze a task request and return structured analysis
     pub async fn analyze_tasks(
         &self,
@@ -165,6 +298,13 @@ impl IntelligentTaskParser {
                 debug!("Using cached task analysis for key: {}", cache_key);
                 return Ok(cached_result.clone());
             }
+
+            if let Some(analysis) = self.load_saved_analysis(&request) {
+                if let Ok(mut cache) = self.cache.lock() {
+                    cache.insert(cache_key, analysis.clone());
+                }
+                return Ok(analysis);
+            }
         }
 
         info!(
@@ -189,6 +329,7 @@ impl IntelligentTaskParser {
             temperature: Some(0.3), // Lower temperature for more consistent parsing
             model_preference: None,
             system_message: Some(self.get_system_message()),
+            response_schema: Some(task_analysis_schema()),
         };
 
         // Create a temporary logger for task analysis
@@ -211,25 +352,37 @@ impl IntelligentTaskParser {
             ))
         })?;
 
-        // Execute LLM request
-        let response = self
-            .llm_provider
-            .execute_request(llm_request, &logger)
-            .await?;
+        // Execute LLM request, re-asking when the response fails validation
+        let structured = tokio::time::timeout(
+            self.timeout,
+            execute_structured(
+                self.llm_provider.as_ref(),
+                llm_request,
+                &logger,
+                DEFAULT_STRUCTURED_RETRIES,
+                |analysis: &TaskAnalysisResult| {
+                    self.validate_analysis(analysis).map_err(|e| e.to_string())
+                },
+            ),
+        )
+        .await
+        .map_err(|_| IntelligentParserError::Timeout(self.timeout))?
+        .map_err(|e| match e {
+            LLMError::InvalidResponse { .. } => IntelligentParserError::ParseError(e.to_string()),
+            e => e.into(),
+        })?;
 
         info!(
-            "LLM analysis complete (tokens: input={}, output={})",
-            response.token_usage.input_tokens, response.token_usage.output_tokens
+            "LLM analysis complete after {} attempt(s) (tokens: input={}, output={})",
+            structured.attempts,
+            structured.response.token_usage.input_tokens,
+            structured.response.token_usage.output_tokens
         );
-
-        // Parse the LLM response
-        let analysis_result = self.parse_llm_response(&response.content)?;
-
-        // Validate the result
-        self.validate_analysis(&analysis_result)?;
+        let analysis_result = enforce_decomposition(structured.value, &request.decomposition);
 
         // Cache the result
         if self.enable_caching {
+            self.save_analysis(&request, &analysis_result);
             let cache_key = self.generate_cache_key(&request);
             if let Ok(mut cache) = self.cache.lock() {
                 cache.insert(cache_key, analysis_result.clone());
@@ -249,15 +402,10 @@ impl IntelligentTaskParser {
         let mut task_specs: Vec<TaskSpec> = Vec::new();
         let mut task_ids: Vec<TaskId> = Vec::new();
 
-        // Use a namespace UUID for generating deterministic task IDs
-        let namespace = uuid::Uuid::parse_str("6ba7b810-9dad-11d1-80b4-00c04fd430c8").unwrap(); // DNS namespace
-
         for analyzed_task in &analysis.tasks {
             let spec = self.analyzed_task_to_spec(analyzed_task.clone());
-            // Generate a deterministic TaskId using UUID v5 (name-based)
-            let task_name = format!("llm-task-{}", analyzed_task.title);
-            let task_id = uuid::Uuid::new_v5(&namespace, task_name.as_bytes());
-            task_ids.push(task_id);
+            // Deterministic ids so dependencies survive dumping the plan
+            task_ids.push(crate::task::plan_task_id(&analyzed_task.title));
             task_specs.push(spec);
         }
 
@@ -324,6 +472,7 @@ impl IntelligentTaskParser {
             source_path: Some(path.clone()),
             context_hints,
             max_tokens: Some(8192), // Increased for larger content with references
+            decomposition: self.decomposition.clone(),
         };
 
         let analysis = self.analyze_tasks(request).await?;
@@ -338,6 +487,71 @@ impl IntelligentTaskParser {
 
     // Private helper methods
 
+    /// File the analysis of `request` is saved to, when a store is configured
+    fn analysis_file(&self, request: &TaskAnalysisRequest) -> Option<PathBuf> {
+        let workspace_root = self.analysis_store.as_deref()?;
+        let mut hashed = request.content.clone();
+        for hint in &request.context_hints {
+            hashed.push('\n');
+            hashed.push_str(hint);
+        }
+        if request.decomposition != DecompositionLimits::default() {
+            hashed.push('\n');
+            hashed.push_str(&serde_json::to_string(&request.decomposition).unwrap_or_default());
+        }
+        let hash = crate::session::integrity::content_hash(hashed.as_bytes());
+        let hash = hash.trim_start_matches(crate::session::integrity::SHA256_PREFIX);
+        Some(crate::env::plan_analysis_file_path(workspace_root, hash))
+    }
+
+    /// A saved analysis of the same content that still passes validation
+    fn load_saved_analysis(&self, request: &TaskAnalysisRequest) -> Option<TaskAnalysisResult> {
+        let path = self.analysis_file(request)?;
+        let content = std::fs::read_to_string(&path).ok()?;
+        let saved: SavedAnalysis = match serde_json::from_str(&content) {
+            Ok(saved) => saved,
+            Err(e) => {
+                warn!("Ignoring unreadable saved analysis {:?}: {}", path, e);
+                return None;
+            }
+        };
+        if let Err(e) = self.validate_analysis(&saved.analysis) {
+            warn!("Ignoring invalid saved analysis {:?}: {}", path, e);
+            return None;
+        }
+        info!(
+            "Reusing task analysis saved at {} ({:?})",
+            saved.saved_at, path
+        );
+        Some(saved.analysis)
+    }
+
+    /// Write the analysis next to the plan records; failures only cost a re-run
+    fn save_analysis(&self, request: &TaskAnalysisRequest, analysis: &TaskAnalysisResult) {
+        let Some(path) = self.analysis_file(request) else {
+            return;
+        };
+        let saved = SavedAnalysis {
+            source_path: request.source_path.clone(),
+            saved_at: chrono::Utc::now(),
+            analysis: analysis.clone(),
+        };
+        let tmp_path = path.with_extension("json.tmp");
+        let written = serde_json::to_string_pretty(&saved)
+            .map_err(std::io::Error::other)
+            .and_then(|json| {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&tmp_path, json)?;
+                std::fs::rename(&tmp_path, &path)
+            });
+        match written {
+            Ok(()) => debug!("Saved task analysis to {:?}", path),
+            Err(e) => warn!("Failed to save task analysis to {:?}: {}", path, e),
+        }
+    }
+
     /// Resolve markdown file references and include their content
     fn resolve_file_references(
         &self,
@@ -399,6 +613,7 @@ impl IntelligentTaskParser {
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
         request.content.hash(&mut hasher);
         request.context_hints.hash(&mut hasher);
+        request.decomposition.hash(&mut hasher);
         format!("{:x}", hasher.finish())
     }
 
@@ -433,6 +648,25 @@ impl IntelligentTaskParser {
         prompt.push_str("Use parent_index to represent hierarchical relationships.\n");
         prompt.push_str("Include ALL technical details, requirements, and success criteria in task descriptions.\n\n");
 
+        let limits = &request.decomposition;
+        prompt.push_str("**Decomposition limits**:\n");
+        prompt.push_str(&format!(
+            "- Granularity: {}\n",
+            limits.granularity.guidance()
+        ));
+        if let Some(depth) = limits.max_depth() {
+            prompt.push_str(&format!(
+                "- At most {} level(s) of nesting (top-level tasks are level 1)\n",
+                depth
+            ));
+        }
+        if let Some(max_tasks) = limits.max_tasks {
+            prompt.push_str(&format!("- At most {} tasks in total\n", max_tasks));
+        }
+        prompt.push_str(
+            "Fold anything finer than these limits into the description of its parent task.\n\n",
+        );
+
         prompt.push_str("IMPORTANT: Respond with ONLY a valid JSON object (no markdown code blocks, no explanations, no additional text).\n\n");
         prompt.push_str("Expected JSON schema:\n");
         prompt.push_str(&self.get_response_schema());
@@ -513,51 +747,6 @@ Always respond with valid JSON matching the provided schema. Be precise, thoroug
         .to_string()
     }
 
-    fn parse_llm_response(
-        &self,
-        content: &str,
-    ) -> Result<TaskAnalysisResult, IntelligentParserError> {
-        // Try to extract JSON from markdown code blocks if present
-        let json_content = if let Some(start) = content.find("```json") {
-            let json_start = start + 7;
-            if let Some(end) = content[json_start..].find("```") {
-                content[json_start..json_start + end].trim()
-            } else {
-                content
-            }
-        } else if let Some(start) = content.find('{') {
-            // Find the first { and try to parse from there
-            &content[start..]
-        } else {
-            content
-        };
-
-        // Try to parse directly first
-        match serde_json::from_str::<TaskAnalysisResult>(json_content) {
-            Ok(result) => Ok(result),
-            Err(first_err) => {
-                // If that fails, try to parse as a JSON-encoded string (double-encoded JSON)
-                // This handles cases where the response is a JSON string containing escaped JSON
-                if let Ok(unescaped) = serde_json::from_str::<String>(json_content) {
-                    serde_json::from_str(&unescaped).map_err(|e| {
-                        IntelligentParserError::ParseError(format!(
-                            "Failed to parse unescaped JSON response: {}. Original error: {}. Content: {}",
-                            e,
-                            first_err,
-                            json_content.chars().take(200).collect::<String>()
-                        ))
-                    })
-                } else {
-                    Err(IntelligentParserError::ParseError(format!(
-                        "Failed to parse JSON response: {}. Content: {}",
-                        first_err,
-                        json_content.chars().take(200).collect::<String>()
-                    )))
-                }
-            }
-        }
-    }
-
     fn validate_analysis(
         &self,
         analysis: &TaskAnalysisResult,
@@ -622,11 +811,200 @@ Always respond with valid JSON matching the provided schema. Be precise, thoroug
                 file_refs: Vec::new(),
                 tags: task.tags,
                 context_requirements,
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
+            plan: None,
         }
     }
 }
 
+/// Merge tasks that break the decomposition limits into their parents
+///
+/// Leaves nested deeper than the depth limit go first, then leaves too fine
+/// for the granularity, then the deepest and simplest leaves until the task
+/// count fits. Root tasks are never merged.
+pub fn enforce_decomposition(
+    mut analysis: TaskAnalysisResult,
+    limits: &DecompositionLimits,
+) -> TaskAnalysisResult {
+    let before = analysis.tasks.len();
+
+    if let Some(max_depth) = limits.max_depth() {
+        while let Some(leaf) = (0..analysis.tasks.len())
+            .rev()
+            .find(|&i| is_leaf(&analysis.tasks, i) && depth(&analysis.tasks, i) > max_depth)
+        {
+            merge_into_parent(&mut analysis.tasks, leaf);
+        }
+    }
+
+    if let Some(threshold) = limits.granularity.merge_threshold() {
+        while let Some(leaf) = (0..analysis.tasks.len()).rev().find(|&i| {
+            let task = &analysis.tasks[i];
+            task.parent_index.is_some()
+                && task.complexity <= threshold
+                && is_leaf(&analysis.tasks, i)
+        }) {
+            merge_into_parent(&mut analysis.tasks, leaf);
+        }
+    }
+
+    if let Some(max_tasks) = limits.max_tasks {
+        while analysis.tasks.len() > max_tasks.max(1) {
+            let Some(leaf) = (0..analysis.tasks.len())
+                .filter(|&i| {
+                    analysis.tasks[i].parent_index.is_some() && is_leaf(&analysis.tasks, i)
+                })
+                .max_by_key(|&i| {
+                    (
+                        depth(&analysis.tasks, i),
+                        std::cmp::Reverse(analysis.tasks[i].complexity.clone()),
+                        i,
+                    )
+                })
+            else {
+                break;
+            };
+            merge_into_parent(&mut analysis.tasks, leaf);
+        }
+    }
+
+    if analysis.tasks.len() < before {
+        info!(
+            "Merged {} overly fine task(s) into their parents ({} remain)",
+            before - analysis.tasks.len(),
+            analysis.tasks.len()
+        );
+    }
+    analysis
+}
+
+fn is_leaf(tasks: &[AnalyzedTask], index: usize) -> bool {
+    !tasks.iter().any(|task| task.parent_index == Some(index))
+}
+
+/// Nesting level of a task, 1 for top-level tasks
+fn depth(tasks: &[AnalyzedTask], index: usize) -> usize {
+    let mut depth = 1;
+    let mut current = tasks[index].parent_index;
+    // Bounded so a parent cycle cannot loop forever
+    while let Some(parent) = current.filter(|_| depth <= tasks.len()) {
+        depth += 1;
+        current = tasks.get(parent).and_then(|task| task.parent_index);
+    }
+    depth
+}
+
+/// Fold a leaf task into its parent and renumber the remaining indices
+fn merge_into_parent(tasks: &mut Vec<AnalyzedTask>, leaf: usize) {
+    let Some(parent) = tasks[leaf].parent_index else {
+        return;
+    };
+    let merged = tasks.remove(leaf);
+    let parent = if parent > leaf { parent - 1 } else { parent };
+    let renumber = |index: usize| match index.cmp(&leaf) {
+        std::cmp::Ordering::Less => index,
+        std::cmp::Ordering::Equal => parent,
+        std::cmp::Ordering::Greater => index - 1,
+    };
+
+    for (i, task) in tasks.iter_mut().enumerate() {
+        task.parent_index = task.parent_index.map(renumber);
+        let mut dependencies: Vec<usize> = Vec::new();
+        for dep in task.dependencies.iter().map(|&dep| renumber(dep)) {
+            if dep != i && !dependencies.contains(&dep) {
+                dependencies.push(dep);
+            }
+        }
+        task.dependencies = dependencies;
+    }
+
+    let target = &mut tasks[parent];
+    target.description.push_str(&format!(
+        "\n\n- **{}**: {}",
+        merged.title, merged.description
+    ));
+    for dep in merged.dependencies.into_iter().map(renumber) {
+        if dep != parent && !target.dependencies.contains(&dep) {
+            target.dependencies.push(dep);
+        }
+    }
+    for file in merged.required_files {
+        if !target.required_files.contains(&file) {
+            target.required_files.push(file);
+        }
+    }
+    for tag in merged.tags {
+        if !target.tags.contains(&tag) {
+            target.tags.push(tag);
+        }
+    }
+    if let Some(extra) = merged.estimated_duration_secs {
+        target.estimated_duration_secs = Some(target.estimated_duration_secs.unwrap_or(0) + extra);
+    }
+}
+
+/// JSON Schema of [`TaskAnalysisResult`] used to validate responses
+fn task_analysis_schema() -> serde_json::Value {
+    let complexity = serde_json::json!({
+        "enum": ["Trivial", "Simple", "Moderate", "Complex", "Epic"]
+    });
+    let index = serde_json::json!({ "type": "integer", "minimum": 0 });
+    serde_json::json!({
+        "type": "object",
+        "required": ["tasks", "execution_strategy", "overall_complexity"],
+        "properties": {
+            "tasks": {
+                "type": "array",
+                "minItems": 1,
+                "items": {
+                    "type": "object",
+                    "required": [
+                        "title", "description", "dependencies", "priority",
+                        "complexity", "required_files", "tags"
+                    ],
+                    "properties": {
+                        "title": { "type": "string", "minLength": 1 },
+                        "description": { "type": "string" },
+                        "parent_index": { "anyOf": [index, { "type": "null" }] },
+                        "dependencies": { "type": "array", "items": index },
+                        "priority": {
+                            "enum": ["Critical", "High", "Normal", "Low", "Background"]
+                        },
+                        "complexity": complexity,
+                        "estimated_duration_secs": {
+                            "type": ["integer", "null"], "minimum": 0
+                        },
+                        "required_files": { "type": "array", "items": { "type": "string" } },
+                        "tags": { "type": "array", "items": { "type": "string" } }
+                    }
+                }
+            },
+            "execution_strategy": {
+                "anyOf": [
+                    { "enum": ["Sequential", "Intelligent"] },
+                    {
+                        "type": "object",
+                        "required": ["Parallel"],
+                        "properties": {
+                            "Parallel": {
+                                "type": "object",
+                                "required": ["max_concurrent"],
+                                "properties": { "max_concurrent": index }
+                            }
+                        }
+                    }
+                ]
+            },
+            "estimated_duration_secs": { "type": ["integer", "null"], "minimum": 0 },
+            "overall_complexity": complexity
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -638,11 +1016,15 @@ mod tests {
     // Mock LLM provider for testing
     struct MockLLMProvider {
         response: String,
+        delay: Duration,
     }
 
     impl MockLLMProvider {
         fn new(response: String) -> Self {
-            Self { response }
+            Self {
+                response,
+                delay: Duration::ZERO,
+            }
         }
     }
 
@@ -653,7 +1035,9 @@ mod tests {
             _logger: &'a crate::llm::provider_logger::ProviderLogger,
         ) -> BoxFuture<'a, Result<crate::llm::LLMResponse, LLMError>> {
             let response = self.response.clone();
+            let delay = self.delay;
             Box::pin(async move {
+                tokio::time::sleep(delay).await;
                 Ok(LLMResponse {
                     request_id: Uuid::new_v4(),
                     content: response,
@@ -663,6 +1047,7 @@ mod tests {
                         output_tokens: 200,
                         total_tokens: 300,
                         estimated_cost: 0.01,
+                        ..Default::default()
                     },
                     execution_time: Duration::from_millis(100),
                     provider_metadata: HashMap::new(),
@@ -734,6 +1119,7 @@ mod tests {
             source_path: None,
             context_hints: vec![],
             max_tokens: Some(2048),
+            decomposition: Default::default(),
         };
 
         let result = parser.analyze_tasks(request).await.unwrap();
@@ -786,6 +1172,7 @@ mod tests {
             source_path: Some(PathBuf::from("tasks.md")),
             context_hints: vec!["database project".to_string()],
             max_tokens: Some(2048),
+            decomposition: Default::default(),
         };
 
         let result = parser.analyze_tasks(request).await.unwrap();
@@ -793,4 +1180,219 @@ mod tests {
         assert_eq!(result.tasks.len(), 2);
         assert_eq!(result.tasks[1].parent_index, Some(0));
     }
+
+    #[tokio::test]
+    async fn test_invalid_analysis_is_rejected() {
+        // Valid JSON, but the parent index points past the task list
+        let mock_response = r#"{
+  "tasks": [
+    {
+      "title": "Orphan",
+      "description": "Has a missing parent",
+      "parent_index": 3,
+      "dependencies": [],
+      "priority": "Normal",
+      "complexity": "Simple",
+      "estimated_duration_secs": null,
+      "required_files": [],
+      "tags": []
+    }
+  ],
+  "execution_strategy": { "Parallel": { "max_concurrent": 2 } },
+  "estimated_duration_secs": null,
+  "overall_complexity": "Simple"
+}"#;
+
+        let provider = Arc::new(MockLLMProvider::new(mock_response.to_string()));
+        let parser = IntelligentTaskParser::without_caching(provider);
+        let request = TaskAnalysisRequest {
+            content: "Orphan".to_string(),
+            source_path: None,
+            context_hints: vec![],
+            max_tokens: None,
+            decomposition: Default::default(),
+        };
+
+        let error = parser.analyze_tasks(request).await.unwrap_err();
+        assert!(matches!(error, IntelligentParserError::ParseError(_)));
+        assert!(error.to_string().contains("invalid parent index 3"));
+    }
+
+    #[tokio::test]
+    async fn test_saved_analysis_is_reused_across_parsers() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mock_response = r#"{
+  "tasks": [
+    {
+      "title": "Write spec",
+      "description": "Write the spec",
+      "parent_index": null,
+      "dependencies": [],
+      "priority": "Normal",
+      "complexity": "Simple",
+      "estimated_duration_secs": null,
+      "required_files": [],
+      "tags": []
+    }
+  ],
+  "execution_strategy": "Sequential",
+  "estimated_duration_secs": null,
+  "overall_complexity": "Simple"
+}"#;
+        let request = || TaskAnalysisRequest {
+            content: "Write the spec".to_string(),
+            source_path: Some(PathBuf::from("spec.md")),
+            context_hints: vec![],
+            max_tokens: None,
+            decomposition: Default::default(),
+        };
+
+        let parser =
+            IntelligentTaskParser::new(Arc::new(MockLLMProvider::new(mock_response.to_string())))
+                .with_analysis_store(workspace.path());
+        parser.analyze_tasks(request()).await.unwrap();
+        let saved: Vec<_> = std::fs::read_dir(crate::env::plans_dir_path(workspace.path()))
+            .unwrap()
+            .collect();
+        assert_eq!(saved.len(), 1);
+
+        // A fresh parser whose LLM would answer with garbage reads the saved file
+        let parser =
+            IntelligentTaskParser::new(Arc::new(MockLLMProvider::new("not json".to_string())))
+                .with_analysis_store(workspace.path());
+        let result = parser.analyze_tasks(request()).await.unwrap();
+        assert_eq!(result.tasks[0].title, "Write spec");
+
+        // Different content is analyzed again
+        let mut changed = request();
+        changed.content.push_str(" and the tests");
+        assert!(parser.analyze_tasks(changed).await.is_err());
+    }
+
+    fn analyzed(
+        title: &str,
+        parent_index: Option<usize>,
+        complexity: ComplexityLevel,
+    ) -> AnalyzedTask {
+        AnalyzedTask {
+            title: title.to_string(),
+            description: format!("{} details", title),
+            parent_index,
+            dependencies: vec![],
+            priority: TaskPriority::Normal,
+            complexity,
+            estimated_duration_secs: Some(60),
+            required_files: vec![],
+            tags: vec![],
+        }
+    }
+
+    fn analysis(tasks: Vec<AnalyzedTask>) -> TaskAnalysisResult {
+        TaskAnalysisResult {
+            tasks,
+            execution_strategy: ExecutionStrategy::Sequential,
+            estimated_duration_secs: None,
+            overall_complexity: ComplexityLevel::Moderate,
+        }
+    }
+
+    #[test]
+    fn test_deep_and_trivial_leaves_are_merged() {
+        use ComplexityLevel::*;
+        let mut tasks = vec![
+            analyzed("Backend", None, Complex),
+            analyzed("Auth", Some(0), Moderate),
+            analyzed("Hash passwords", Some(1), Moderate),
+            analyzed("Rename variable", Some(0), Trivial),
+            analyzed("Deploy", None, Moderate),
+        ];
+        tasks[4].dependencies = vec![2];
+
+        let limits = DecompositionLimits {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let result = enforce_decomposition(analysis(tasks), &limits);
+        let titles: Vec<&str> = result.tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Backend", "Auth", "Deploy"]);
+        assert!(
+            result.tasks[1]
+                .description
+                .ends_with("- **Hash passwords**: Hash passwords details")
+        );
+        assert!(result.tasks[0].description.contains("**Rename variable**"));
+        assert_eq!(result.tasks[1].estimated_duration_secs, Some(120));
+        // The dependency on the merged task now points at the task that absorbed it
+        assert_eq!(result.tasks[2].dependencies, vec![1]);
+
+        let fine = DecompositionLimits {
+            granularity: DecompositionGranularity::Fine,
+            ..Default::default()
+        };
+        let kept = enforce_decomposition(
+            analysis(vec![
+                analyzed("Backend", None, Complex),
+                analyzed("Rename variable", Some(0), Trivial),
+            ]),
+            &fine,
+        );
+        assert_eq!(kept.tasks.len(), 2);
+    }
+
+    #[test]
+    fn test_max_tasks_merges_deepest_simplest_leaves_first() {
+        use ComplexityLevel::*;
+        let tasks = vec![
+            analyzed("Phase 1", None, Complex),
+            analyzed("Schema", Some(0), Moderate),
+            analyzed("Migrations", Some(1), Simple),
+            analyzed("Seed data", Some(1), Complex),
+            analyzed("Phase 2", None, Complex),
+            analyzed("API", Some(4), Moderate),
+        ];
+        let limits = DecompositionLimits {
+            max_tasks: Some(4),
+            granularity: DecompositionGranularity::Fine,
+            ..Default::default()
+        };
+        let result = enforce_decomposition(analysis(tasks), &limits);
+        let titles: Vec<&str> = result.tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Phase 1", "Schema", "Phase 2", "API"]);
+        assert_eq!(result.tasks[3].parent_index, Some(2));
+
+        // Roots are never merged away, even past the limit
+        let limits = DecompositionLimits {
+            max_tasks: Some(1),
+            ..Default::default()
+        };
+        let result = enforce_decomposition(
+            analysis(vec![
+                analyzed("A", None, Simple),
+                analyzed("B", None, Simple),
+            ]),
+            &limits,
+        );
+        assert_eq!(result.tasks.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_analysis_times_out() {
+        let provider = Arc::new(MockLLMProvider {
+            response: "{}".to_string(),
+            delay: Duration::from_secs(60),
+        });
+        let parser = IntelligentTaskParser::without_caching(provider)
+            .with_timeout(Duration::from_millis(50));
+        let request = TaskAnalysisRequest {
+            content: "Anything".to_string(),
+            source_path: None,
+            context_hints: vec![],
+            max_tokens: None,
+            decomposition: Default::default(),
+        };
+
+        let error = parser.analyze_tasks(request).await.unwrap_err();
+        assert!(matches!(error, IntelligentParserError::Timeout(_)));
+        assert_eq!(error.to_string(), "Task analysis timed out after 50ms");
+    }
 }
@@ -0,0 +1,148 @@
+//! Multi-line entry and history recall for the interactive prompt
+//!
+//! [`InputBuffer`] assembles one entry from one or more lines: a line ending
+//! in `<<` keeps reading until a blank line, and a line ending in `<<WORD`
+//! keeps reading until a line containing only `WORD`, so pasted specs with
+//! blank lines survive intact. [`recall`] resolves `!!` and `!N` against the
+//! prompt's history.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Terminator {
+    BlankLine,
+    Word(String),
+}
+
+/// Collects the lines of one interactive entry
+#[derive(Debug, Default)]
+pub struct InputBuffer {
+    terminator: Option<Terminator>,
+    lines: Vec<String>,
+}
+
+impl InputBuffer {
+    /// Add a line; returns the entry once it is complete
+    pub fn push(&mut self, line: &str) -> Option<String> {
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        let Some(terminator) = &self.terminator else {
+            let Some((head, terminator)) = heredoc_start(line) else {
+                return Some(line.to_string());
+            };
+            if !head.trim().is_empty() {
+                self.lines.push(head.trim_end().to_string());
+            }
+            self.terminator = Some(terminator);
+            return None;
+        };
+
+        let finished = match terminator {
+            Terminator::BlankLine => line.trim().is_empty(),
+            Terminator::Word(word) => line.trim() == word,
+        };
+        if !finished {
+            self.lines.push(line.to_string());
+            return None;
+        }
+        self.terminator = None;
+        Some(std::mem::take(&mut self.lines).join("\n"))
+    }
+
+    /// Whether an entry is partway through
+    pub fn is_continuing(&self) -> bool {
+        self.terminator.is_some()
+    }
+
+    /// Drop a partial entry; returns whether there was one
+    pub fn cancel(&mut self) -> bool {
+        self.lines.clear();
+        self.terminator.take().is_some()
+    }
+}
+
+/// Split `text <<WORD` into the text and its terminator
+fn heredoc_start(line: &str) -> Option<(&str, Terminator)> {
+    let (head, word) = line.trim_end().rsplit_once("<<")?;
+    // `a << b` or `cout<<` in pasted code is not a heredoc
+    let is_word = word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_word || !(head.is_empty() || head.ends_with(' ')) {
+        return None;
+    }
+    let terminator = match word {
+        "" => Terminator::BlankLine,
+        word => Terminator::Word(word.to_string()),
+    };
+    Some((head, terminator))
+}
+
+/// Resolve `!!` (last entry) or `!N` (entry N as listed by `history`)
+///
+/// Returns `None` for lines that are not recalls.
+pub fn recall(entries: &[String], line: &str) -> Option<Result<String, String>> {
+    let reference = line.trim().strip_prefix('!')?;
+    let entry = if reference == "!" {
+        entries.last()
+    } else {
+        let number = reference.parse::<usize>().ok()?;
+        number.checked_sub(1).and_then(|index| entries.get(index))
+    };
+    Some(
+        entry
+            .cloned()
+            .ok_or_else(|| format!("No history entry {}", line.trim())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiline_entries() {
+        let mut input = InputBuffer::default();
+        assert_eq!(
+            input.push("Fix the login form\n").as_deref(),
+            Some("Fix the login form")
+        );
+
+        // Blank-line terminated
+        assert_eq!(input.push("Add caching <<"), None);
+        assert!(input.is_continuing());
+        assert_eq!(input.push("- keep it in memory"), None);
+        assert_eq!(
+            input.push("").as_deref(),
+            Some("Add caching\n- keep it in memory")
+        );
+
+        // Heredoc keeps blank lines
+        assert_eq!(input.push("<<END"), None);
+        assert_eq!(input.push("# Spec"), None);
+        assert_eq!(input.push(""), None);
+        assert_eq!(input.push("Details"), None);
+        assert_eq!(input.push("END").as_deref(), Some("# Spec\n\nDetails"));
+
+        // Shift operators are not heredocs
+        assert_eq!(input.push("use x << 2").as_deref(), Some("use x << 2"));
+        assert_eq!(input.push("fix cout<<").as_deref(), Some("fix cout<<"));
+
+        assert_eq!(input.push("<<"), None);
+        assert_eq!(input.push("half a thought"), None);
+        assert!(input.cancel());
+        assert!(!input.is_continuing());
+        assert_eq!(input.push("next").as_deref(), Some("next"));
+    }
+
+    #[test]
+    fn test_recall() {
+        let entries = vec![
+            "tasks".to_string(),
+            "Add caching\n- keep it in memory".to_string(),
+        ];
+        assert_eq!(
+            recall(&entries, "!!"),
+            Some(Ok("Add caching\n- keep it in memory".to_string()))
+        );
+        assert_eq!(recall(&entries, "!1"), Some(Ok("tasks".to_string())));
+        assert!(matches!(recall(&entries, "!9"), Some(Err(_))));
+        assert_eq!(recall(&entries, "!important fix"), None);
+    }
+}
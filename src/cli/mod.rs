@@ -7,8 +7,15 @@
 //!
 //! - **[`Args`]**: Command-line argument parsing and validation
 //! - **[`TaskLoader`]**: Simple task parsing from files and markdown
+//! - **[`SubPlanParser`]**: Parses the sub-plan files that tasks expand into
 //! - **[`IntelligentTaskParser`]**: LLM-powered task decomposition and analysis
 //! - **[`ConfigDiscovery`]**: Configuration file discovery and loading
+//! - **[`validate_plan_file`]**: Located diagnostics for plan files before they run
+//! - **[`write_plan_for_review`]** / **[`load_reviewed_plan`]**: Editing a generated plan before it runs
+//! - **[`IdeServer`]**: JSON-RPC command server for editor extensions
+//! - **[`Reporter`]**: Per-task summaries and completion notifications in interactive mode
+//! - **[`ReplCommand`]**: Interactive prompt commands and their completions
+//! - **[`InputBuffer`]** / **[`recall`]**: Multi-line entry and `!N` history recall
 //!
 //! ## Key Features
 //!
@@ -77,13 +84,37 @@
 
 pub mod args;
 pub mod config;
+pub mod ide_server;
 pub mod intelligent_parser;
+pub mod line_input;
+pub mod plan_approval;
+pub mod plan_validation;
+pub mod questions;
+pub mod repl;
+pub mod reporter;
 pub mod tasks;
 
-pub use args::{Args, BatchConfig, ExecutionMode, InteractiveConfig};
+pub use args::{
+    Args, BatchConfig, ConfigInitConfig, ContextImportConfig, CoordinatorModeConfig, ExecutionMode,
+    FixBugConfig, IdeServerConfig, InteractiveConfig, LogsPruneConfig, PlanDiffConfig,
+    PlanDiffFormat, PlanValidateConfig, PrDescriptionConfig, QuickConfig, ReplayConfig,
+    ReplayFormat, ReportVerifyConfig, ServeModeConfig, SessionRestoreConfig, StatsConfig,
+    StatusConfig, TaskWhyConfig, TranscriptConfig, TranscriptFormat, UsageConfig, WatchModeConfig,
+    WhyFormat, WorkerModeConfig,
+};
 pub use config::{ConfigDiscovery, DefaultAgentConfig};
+pub use ide_server::IdeServer;
 pub use intelligent_parser::{
-    AnalyzedTask, ExecutionStrategy, IntelligentParserError, IntelligentTaskParser,
-    TaskAnalysisRequest, TaskAnalysisResult,
+    AnalyzedTask, DecompositionGranularity, DecompositionLimits, ExecutionStrategy,
+    IntelligentParserError, IntelligentTaskParser, TaskAnalysisRequest, TaskAnalysisResult,
+};
+pub use line_input::{InputBuffer, recall};
+pub use plan_approval::{load_reviewed_plan, write_plan_for_review};
+pub use plan_validation::{Diagnostic, PlanValidation, Severity, validate_plan_file};
+pub use questions::TerminalQuestionHandler;
+pub use repl::ReplCommand;
+pub use reporter::{Reporter, TaskSummary};
+pub use tasks::{
+    FileError, FrontMatter, InlineSource, ParseOptions, SimpleTask, SubPlanParser, TaskInput,
+    TaskListDocument, TaskLoader,
 };
-pub use tasks::{FileError, SimpleTask, TaskInput, TaskLoader};
@@ -0,0 +1,136 @@
+//! Review of a generated plan before it runs
+//!
+//! With `aca run --approve` the execution plan is written to a TOML file
+//! for the user to edit: task titles, descriptions and dependencies can be
+//! changed and tasks added or removed. The edited file is checked with the
+//! same diagnostics as `aca config validate`, loaded, and validated again,
+//! including for dependency cycles, before anything runs.
+
+use super::plan_validation::validate_plan_file;
+use super::tasks::{FileError, TaskLoader};
+use crate::task::{ExecutionPlan, plan_task_id};
+use std::path::Path;
+
+/// Write `plan` to `path` as TOML, headed by how to edit it and the id of
+/// each task
+pub fn write_plan_for_review(plan: &ExecutionPlan, path: &Path) -> Result<(), FileError> {
+    let toml = toml::to_string_pretty(plan)
+        .map_err(|e| FileError::Parse(format!("Failed to serialize plan: {}", e)))?;
+    let mut content = String::from(
+        "# Review this plan before it runs: edit task titles, descriptions and\n\
+         # dependencies, add or remove tasks, then save the file.\n\
+         #\n\
+         # Dependencies name tasks by id, and a task's id is derived from its\n\
+         # title, so renaming a task changes its id. Current ids:\n",
+    );
+    for spec in &plan.task_specs {
+        content.push_str(&format!(
+            "#   {}  {}\n",
+            plan_task_id(&spec.title),
+            spec.title
+        ));
+    }
+    content.push('\n');
+    content.push_str(&toml);
+
+    let io_error = |source| FileError::IoError {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    std::fs::write(path, content).map_err(io_error)
+}
+
+/// Load the plan at `path` after review
+///
+/// `Err` holds every problem found, ready to show the user.
+pub fn load_reviewed_plan(path: &Path) -> Result<ExecutionPlan, String> {
+    let validation = validate_plan_file(path).map_err(|e| e.to_string())?;
+    if validation.has_errors() {
+        return Err(validation.render());
+    }
+    let plan = TaskLoader::load_execution_plan(path).map_err(|e| e.to_string())?;
+    plan.validate()?;
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{TaskMetadata, TaskSpec};
+    use tempfile::TempDir;
+
+    fn plan() -> ExecutionPlan {
+        let task = |title: &str, dependencies| TaskSpec {
+            title: title.to_string(),
+            description: format!("{} description", title),
+            metadata: TaskMetadata::default(),
+            dependencies,
+            plan: None,
+        };
+        ExecutionPlan::new().with_tasks(vec![
+            task("Design schema", Vec::new()),
+            task("Build API", vec![plan_task_id("Design schema")]),
+        ])
+    }
+
+    #[test]
+    fn test_edited_plan_is_reloaded() {
+        let workspace = TempDir::new().unwrap();
+        let path = workspace.path().join("plans/approval.toml");
+        write_plan_for_review(&plan(), &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains(&format!(
+            "#   {}  Design schema\n",
+            plan_task_id("Design schema")
+        )));
+
+        let edited = content.replace(
+            "Build API description",
+            "Build the REST API with pagination",
+        );
+        std::fs::write(&path, edited).unwrap();
+        let reviewed = load_reviewed_plan(&path).unwrap();
+        assert_eq!(reviewed.task_count(), 2);
+        assert_eq!(
+            reviewed.task_specs[1].description,
+            "Build the REST API with pagination"
+        );
+        assert_eq!(
+            reviewed.task_specs[1].dependencies,
+            vec![plan_task_id("Design schema")]
+        );
+    }
+
+    #[test]
+    fn test_broken_edits_are_reported() {
+        let workspace = TempDir::new().unwrap();
+        let path = workspace.path().join("approval.toml");
+        write_plan_for_review(&plan(), &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        // Renaming a task leaves the dependency on its old id dangling
+        std::fs::write(
+            &path,
+            content.replace("title = \"Design schema\"", "title = \"Design tables\""),
+        )
+        .unwrap();
+        let error = load_reviewed_plan(&path).unwrap_err();
+        assert!(
+            error.contains("does not match any task in this plan"),
+            "{}",
+            error
+        );
+
+        // Each task depending on the other
+        let mut cyclic = plan();
+        cyclic.task_specs[0].dependencies = vec![plan_task_id("Build API")];
+        write_plan_for_review(&cyclic, &path).unwrap();
+        assert_eq!(
+            load_reviewed_plan(&path).unwrap_err(),
+            "Dependency cycle: Design schema -> Build API -> Design schema"
+        );
+    }
+}
@@ -0,0 +1,667 @@
+//! Plan file diagnostics
+//!
+//! Checks execution plans (JSON or TOML), structured TOML configs and
+//! markdown task lists before anything runs, reporting every problem found
+//! with its file, line and column and, where one is obvious, a suggested
+//! fix. A plan that would fail to load produces at least one error here;
+//! a plan that loads may still get warnings (a referenced file that does
+//! not exist yet, a task list without tasks).
+//!
+//! Used by `aca config validate` and `aca run --dry-run`.
+
+use super::tasks::{FileError, TaskLoader};
+use crate::task::plan_task_id;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const PRIORITIES: &[&str] = &["Critical", "High", "Normal", "Low", "Background"];
+const COMPLEXITIES: &[&str] = &["Trivial", "Simple", "Moderate", "Complex", "Epic"];
+const TASK_LIST_PRIORITIES: &[&str] =
+    &["critical", "high", "normal", "medium", "low", "background"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// One problem in a plan file; lines and columns are 1-based
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// Suggested fix
+    pub help: Option<String>,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.severity,
+            self.message
+        )?;
+        if let Some(help) = &self.help {
+            write!(f, "\n  help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every diagnostic found in one plan file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanValidation {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl PlanValidation {
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    /// Diagnostics one per paragraph, followed by a summary line
+    pub fn render(&self) -> String {
+        let mut out: Vec<String> = self.diagnostics.iter().map(|d| d.to_string()).collect();
+        out.push(format!(
+            "{} error(s), {} warning(s)",
+            self.error_count(),
+            self.warning_count()
+        ));
+        out.join("\n")
+    }
+}
+
+/// Validate a plan file, choosing the checks by its extension
+///
+/// `.json` files are execution plans; `.toml` files are execution plans when
+/// they have `task_specs` and structured configs otherwise; anything else is
+/// a task list. Only failing to read the file is an `Err`.
+pub fn validate_plan_file(path: &Path) -> Result<PlanValidation, FileError> {
+    let content = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => FileError::NotFound {
+            path: path.to_path_buf(),
+        },
+        _ => FileError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        },
+    })?;
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let mut checker = Checker::new(path, &content);
+    match extension.as_str() {
+        "json" => checker.check_json(),
+        "toml" => checker.check_toml(),
+        _ => checker.check_task_list(),
+    }
+    Ok(PlanValidation {
+        diagnostics: checker.diagnostics,
+    })
+}
+
+struct Checker<'a> {
+    path: &'a Path,
+    content: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(path: &'a Path, content: &'a str) -> Self {
+        Self {
+            path,
+            content,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, severity: Severity, offset: usize, message: String, help: Option<String>) {
+        let (line, column) = line_column(self.content, offset);
+        self.push_at(severity, line, column, message, help);
+    }
+
+    fn push_at(
+        &mut self,
+        severity: Severity,
+        line: usize,
+        column: usize,
+        message: String,
+        help: Option<String>,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            path: self.path.to_path_buf(),
+            line,
+            column,
+            message,
+            help,
+        });
+    }
+
+    fn check_json(&mut self) {
+        let value: Value = match serde_json::from_str(self.content) {
+            Ok(value) => value,
+            Err(e) => {
+                self.push_json_error(&e);
+                return;
+            }
+        };
+        self.check_plan_value(&value);
+        if self.diagnostics.is_empty()
+            && let Err(e) = serde_json::from_str::<crate::task::ExecutionPlan>(self.content)
+        {
+            self.push_json_error(&e);
+        }
+    }
+
+    fn push_json_error(&mut self, e: &serde_json::Error) {
+        let message = e.to_string();
+        // The position is reported separately
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => message,
+        };
+        self.push_at(
+            Severity::Error,
+            e.line().max(1),
+            e.column().max(1),
+            message,
+            None,
+        );
+    }
+
+    fn check_toml(&mut self) {
+        let table: toml::Table = match toml::from_str(self.content) {
+            Ok(table) => table,
+            Err(e) => {
+                self.push_toml_error(&e);
+                return;
+            }
+        };
+
+        if table.contains_key("task_specs") {
+            match serde_json::to_value(&table) {
+                Ok(value) => self.check_plan_value(&value),
+                Err(e) => self.push(Severity::Error, 0, e.to_string(), None),
+            }
+            if self.diagnostics.is_empty()
+                && let Err(e) = toml::from_str::<crate::task::ExecutionPlan>(self.content)
+            {
+                self.push_toml_error(&e);
+            }
+        } else if let Err(e) = toml::from_str::<crate::AgentConfig>(self.content) {
+            self.push_toml_error(&e);
+        }
+    }
+
+    fn push_toml_error(&mut self, e: &toml::de::Error) {
+        let offset = e.span().map_or(0, |span| span.start);
+        self.push(Severity::Error, offset, e.message().to_string(), None);
+    }
+
+    /// Checks on an execution plan that serde would report one at a time,
+    /// or not at all
+    fn check_plan_value(&mut self, plan: &Value) {
+        let Some(specs) = plan.get("task_specs").and_then(Value::as_array) else {
+            return;
+        };
+
+        let ids: HashMap<uuid::Uuid, &str> = specs
+            .iter()
+            .filter_map(|spec| spec.get("title").and_then(Value::as_str))
+            .map(|title| (plan_task_id(title), title))
+            .collect();
+
+        let mut from = 0;
+        for (index, spec) in specs.iter().enumerate() {
+            let title = spec.get("title").and_then(Value::as_str).unwrap_or("");
+            let start = match (!title.is_empty())
+                .then(|| find_from(self.content, title, from))
+                .flatten()
+            {
+                Some(offset) => offset,
+                None => from,
+            };
+            from = start;
+
+            if title.trim().is_empty() {
+                self.push(
+                    Severity::Error,
+                    start,
+                    format!("task {} has no title", index + 1),
+                    Some("every task needs a non-empty `title`".to_string()),
+                );
+            }
+
+            let metadata = spec.get("metadata");
+            if let Some(priority) = metadata.and_then(|m| m.get("priority")) {
+                self.check_variant(start, "priority", priority, PRIORITIES);
+            }
+            if let Some(complexity) = metadata
+                .and_then(|m| m.get("estimated_complexity"))
+                .filter(|c| !c.is_null())
+            {
+                self.check_variant(start, "complexity", complexity, COMPLEXITIES);
+            }
+
+            if let Some(file_refs) = metadata
+                .and_then(|m| m.get("file_refs"))
+                .and_then(Value::as_array)
+            {
+                for file_ref in file_refs {
+                    if let Some(path) = file_ref.get("path").and_then(Value::as_str) {
+                        self.check_file_exists(start, path, Severity::Warning);
+                    }
+                }
+            }
+
+            if let Some(sub_plan) = spec.get("plan").and_then(Value::as_str) {
+                self.check_file_exists(start, sub_plan, Severity::Error);
+            }
+
+            if let Some(dependencies) = spec.get("dependencies").and_then(Value::as_array) {
+                let own_id = plan_task_id(title);
+                for dependency in dependencies {
+                    self.check_dependency(start, dependency, own_id, &ids);
+                }
+            }
+        }
+    }
+
+    fn check_variant(&mut self, from: usize, kind: &str, value: &Value, allowed: &[&str]) {
+        let text = value.as_str().unwrap_or_default();
+        if allowed.contains(&text) {
+            return;
+        }
+        let offset = locate(self.content, &value_literal(value), from);
+        let help = match closest(text, allowed) {
+            Some(suggestion) => format!("did you mean `{}`?", suggestion),
+            None => format!("expected one of {}", allowed.join(", ")),
+        };
+        self.push(
+            Severity::Error,
+            offset,
+            format!("unknown {} `{}`", kind, value_literal(value)),
+            Some(help),
+        );
+    }
+
+    fn check_dependency(
+        &mut self,
+        from: usize,
+        dependency: &Value,
+        own_id: uuid::Uuid,
+        ids: &HashMap<uuid::Uuid, &str>,
+    ) {
+        let text = value_literal(dependency);
+        let offset = locate(self.content, &text, from);
+        let Ok(id) = uuid::Uuid::parse_str(&text) else {
+            // A title where the task's id belongs is the usual mistake
+            let help = ids
+                .values()
+                .find(|title| title.eq_ignore_ascii_case(text.trim()))
+                .map(|title| {
+                    format!(
+                        "to depend on \"{}\" use its id `{}`",
+                        title,
+                        plan_task_id(title)
+                    )
+                })
+                .unwrap_or_else(|| {
+                    "dependencies are task ids; a task's id is derived from its title".to_string()
+                });
+            self.push(
+                Severity::Error,
+                offset,
+                format!("dependency `{}` is not a task id", text),
+                Some(help),
+            );
+            return;
+        };
+
+        if id == own_id {
+            self.push(
+                Severity::Error,
+                offset,
+                "task depends on itself".to_string(),
+                Some("remove the dependency".to_string()),
+            );
+        } else if !ids.contains_key(&id) {
+            let help = ids
+                .iter()
+                .find(|(other, _)| **other != own_id)
+                .map(|(other, title)| {
+                    format!(
+                        "dependencies must name a task in this plan, e.g. `{}` for \"{}\"",
+                        other, title
+                    )
+                });
+            self.push(
+                Severity::Error,
+                offset,
+                format!("dependency `{}` does not match any task in this plan", id),
+                help,
+            );
+        }
+    }
+
+    /// Report a referenced path that does not exist, relative to the plan's
+    /// directory or the current directory
+    fn check_file_exists(&mut self, from: usize, reference: &str, severity: Severity) {
+        let resolved = TaskLoader::resolve_reference_path(reference, self.path)
+            .unwrap_or_else(|_| PathBuf::from(reference));
+        if resolved.exists() || Path::new(reference).exists() {
+            return;
+        }
+        let offset = locate(self.content, reference, from);
+        self.push(
+            severity,
+            offset,
+            format!("referenced file `{}` does not exist", reference),
+            similar_file(&resolved).map(|name| {
+                format!(
+                    "did you mean `{}`?",
+                    Path::new(reference).with_file_name(name).display()
+                )
+            }),
+        );
+    }
+
+    fn check_task_list(&mut self) {
+        let mut lines = self.content.split_inclusive('\n').peekable();
+        let mut offset = 0;
+
+        // Front matter, when the first line opens it
+        if lines.peek().map(|line| line.trim_end()) == Some("---") {
+            let mut cursor = offset + lines.next().map_or(0, str::len);
+            let mut priority_line = None;
+            let mut closed = false;
+            for line in lines.by_ref() {
+                let start = cursor;
+                cursor += line.len();
+                let trimmed = line.trim_end();
+                if trimmed == "---" || trimmed == "..." {
+                    closed = true;
+                    break;
+                }
+                if let Some((key, value)) = trimmed.split_once(':')
+                    && key.trim().eq_ignore_ascii_case("priority")
+                {
+                    priority_line = Some((start + key.len() + 1, value.trim().to_string()));
+                }
+            }
+            if closed {
+                offset = cursor;
+                if let Some((start, value)) = priority_line {
+                    let value = value.trim_matches(|c| c == '"' || c == '\'').to_string();
+                    if TaskLoader::parse_priority(&value).is_none() {
+                        let at = locate(self.content, &value, start);
+                        let help = match closest(&value, TASK_LIST_PRIORITIES) {
+                            Some(suggestion) => format!("did you mean `{}`?", suggestion),
+                            None => format!("expected one of {}", TASK_LIST_PRIORITIES.join(", ")),
+                        };
+                        self.push(
+                            Severity::Error,
+                            at,
+                            format!("unknown priority `{}`", value),
+                            Some(help),
+                        );
+                    }
+                }
+            } else {
+                // An unterminated block is ordinary content
+                lines = self.content.split_inclusive('\n').peekable();
+            }
+        }
+
+        for line in lines {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim();
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                continue;
+            }
+            if let Some((_, reference)) = trimmed.split_once(" -> ") {
+                let reference = reference.trim();
+                if !reference.is_empty() {
+                    self.check_file_exists(start, reference, Severity::Error);
+                }
+            }
+        }
+
+        if let Ok(document) = TaskLoader::parse_task_list_document(self.path)
+            && document.tasks.is_empty()
+        {
+            self.push(
+                Severity::Warning,
+                0,
+                "no tasks found".to_string(),
+                Some("list tasks as `- [ ] ...`, `* TODO ...` or `1. ...` lines".to_string()),
+            );
+        }
+    }
+}
+
+/// The value as it appears between quotes in the file
+fn value_literal(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn find_from(content: &str, needle: &str, from: usize) -> Option<usize> {
+    content
+        .get(from..)
+        .and_then(|rest| rest.find(needle))
+        .map(|index| from + index)
+}
+
+/// Offset of `needle` at or after `from`, or `from` if it is not there
+fn locate(content: &str, needle: &str, from: usize) -> usize {
+    find_from(content, needle, from)
+        .or_else(|| content.find(needle))
+        .unwrap_or(from)
+}
+
+/// 1-based line and column (in characters) of a byte offset
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// The allowed value closest to `value`, if it is close enough to be a typo
+fn closest<'a>(value: &str, allowed: &[&'a str]) -> Option<&'a str> {
+    let value = value.trim();
+    if let Some(exact) = allowed.iter().find(|a| a.eq_ignore_ascii_case(value)) {
+        return Some(exact);
+    }
+    let lowered = value.to_lowercase();
+    allowed
+        .iter()
+        .map(|candidate| {
+            (
+                edit_distance(&lowered, &candidate.to_lowercase()),
+                *candidate,
+            )
+        })
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Name of a file next to `missing` that it is a likely typo of
+fn similar_file(missing: &Path) -> Option<String> {
+    let name = missing.file_name()?.to_str()?;
+    let entries =
+        std::fs::read_dir(missing.parent().filter(|p| !p.as_os_str().is_empty())?).ok()?;
+    let names: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    let candidates: Vec<&str> = names.iter().map(String::as_str).collect();
+    closest(name, &candidates).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(name: &str, content: &str) -> (tempfile::TempDir, PlanValidation) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        let validation = validate_plan_file(&path).unwrap();
+        (dir, validation)
+    }
+
+    #[test]
+    fn test_execution_plan_reports_every_problem_with_position() {
+        let setup_id = plan_task_id("Set up database");
+        let plan = format!(
+            r#"{{
+  "setup_commands": [],
+  "execution_mode": "Sequential",
+  "metadata": {{"name": null, "description": null, "tags": [], "estimated_duration": null, "custom_metadata": {{}}}},
+  "task_specs": [
+    {{
+      "title": "Set up database",
+      "description": "Create the schema",
+      "metadata": {{"priority": "Hight", "estimated_complexity": null, "estimated_duration": null,
+        "repository_refs": [], "file_refs": [], "tags": [], "context_requirements": {{}}}},
+      "dependencies": []
+    }},
+    {{
+      "title": "Add login",
+      "description": "Login endpoint",
+      "metadata": {{"priority": "Normal", "estimated_complexity": "Epik", "estimated_duration": null,
+        "repository_refs": [], "file_refs": [], "tags": [], "context_requirements": {{}}}},
+      "dependencies": ["set up database", "{}", "00000000-0000-0000-0000-000000000000"]
+    }}
+  ]
+}}"#,
+            setup_id
+        );
+        let (_dir, validation) = validate("plan.json", &plan);
+        let messages: Vec<(usize, &str, Option<&str>)> = validation
+            .diagnostics
+            .iter()
+            .map(|d| (d.line, d.message.as_str(), d.help.as_deref()))
+            .collect();
+
+        assert_eq!(validation.error_count(), 4, "{}", validation.render());
+        assert_eq!(
+            messages[0],
+            (9, "unknown priority `Hight`", Some("did you mean `High`?"))
+        );
+        assert_eq!(
+            messages[1],
+            (
+                16,
+                "unknown complexity `Epik`",
+                Some("did you mean `Epic`?")
+            )
+        );
+        assert_eq!(messages[2].0, 18);
+        assert_eq!(
+            messages[2].2.unwrap(),
+            format!("to depend on \"Set up database\" use its id `{}`", setup_id)
+        );
+        assert!(messages[3].1.contains("does not match any task"));
+        assert_eq!(validation.diagnostics[0].column, 33);
+    }
+
+    #[test]
+    fn test_syntax_and_type_errors_are_located() {
+        let (_dir, validation) = validate("plan.toml", "task_specs = [\n  { title = \"x\" \n");
+        assert_eq!(validation.error_count(), 1);
+        assert_eq!(validation.diagnostics[0].line, 2);
+
+        let (_dir, validation) = validate("plan.json", "{\n  \"task_specs\": []\n}");
+        assert_eq!(validation.error_count(), 1);
+        assert!(
+            validation.diagnostics[0]
+                .message
+                .contains("missing field `setup_commands`")
+        );
+        assert!(!validation.diagnostics[0].message.contains(" at line "));
+    }
+
+    #[test]
+    fn test_task_list_front_matter_and_references() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("spec.md"), "details").unwrap();
+        let path = dir.path().join("tasks.md");
+        std::fs::write(
+            &path,
+            "---\npriority: hihg\n---\n# Tasks\n- [ ] Use spec -> spec.md\n- [ ] Use design -> spex.md\n",
+        )
+        .unwrap();
+
+        let validation = validate_plan_file(&path).unwrap();
+        assert_eq!(validation.error_count(), 2, "{}", validation.render());
+        let priority = &validation.diagnostics[0];
+        assert_eq!((priority.line, priority.column), (2, 11));
+        assert_eq!(priority.help.as_deref(), Some("did you mean `high`?"));
+        let reference = &validation.diagnostics[1];
+        assert_eq!((reference.line, reference.column), (6, 21));
+        assert!(reference.help.as_deref().unwrap().ends_with("spec.md`?"));
+        assert!(validation.render().ends_with("2 error(s), 0 warning(s)"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Terminal prompt for questions the model asks mid-task
+
+use crate::task::{QuestionAnswer, QuestionHandler, QuestionRequest};
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::io::{self, BufRead, Write};
+
+/// Asks model questions on stdin/stdout
+///
+/// An empty reply takes the question's default; with no default, or at end
+/// of input, the question is left unanswered.
+#[derive(Debug, Default, Clone)]
+pub struct TerminalQuestionHandler;
+
+impl TerminalQuestionHandler {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl QuestionHandler for TerminalQuestionHandler {
+    fn ask<'a>(
+        &'a self,
+        request: &'a QuestionRequest,
+    ) -> BoxFuture<'a, Result<Option<QuestionAnswer>>> {
+        let request = request.clone();
+        Box::pin(async move { Ok(tokio::task::spawn_blocking(move || prompt(&request)).await??) })
+    }
+}
+
+fn prompt(request: &QuestionRequest) -> io::Result<Option<QuestionAnswer>> {
+    let question = &request.question;
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "\n❓ Task '{}' asks:", request.task_title)?;
+    writeln!(stdout, "   {}", question.question)?;
+    for (index, option) in question.options.iter().enumerate() {
+        writeln!(stdout, "   {}) {}", index + 1, option)?;
+    }
+    match &question.default {
+        Some(default) => write!(stdout, "Answer [{}]: ", default)?,
+        None => write!(stdout, "Answer: ")?,
+    }
+    stdout.flush()?;
+    drop(stdout);
+
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let reply = line.trim();
+
+    // Accept an option number as shorthand for the option itself
+    let answer = match reply.parse::<usize>() {
+        Ok(number) if (1..=question.options.len()).contains(&number) => {
+            Some(question.options[number - 1].clone())
+        }
+        _ if reply.is_empty() => question.default.clone(),
+        _ => Some(reply.to_string()),
+    };
+
+    Ok(answer.map(|answer| QuestionAnswer {
+        answer,
+        answered_by: std::env::var("USER").unwrap_or_else(|_| "terminal".to_string()),
+    }))
+}
@@ -0,0 +1,358 @@
+//! Commands understood by the interactive prompt
+//!
+//! A line whose first word names one of [`COMMANDS`] runs that command; any
+//! other text becomes a task. Task ids may be shortened to any unique prefix,
+//! such as the eight characters `tasks` prints.
+//!
+//! [`editor`] builds the rustyline prompt: Up and Down recall entries kept in
+//! the workspace's `.aca/history`, and Tab runs [`complete`] on command names,
+//! provider names after `provider` and file paths after `plan`.
+
+use super::args::ProviderCliOption;
+use crate::llm::types::ProviderType;
+use clap::ValueEnum;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Editor, Helper};
+use std::path::{Path, PathBuf};
+
+/// Entries kept in the history file
+pub const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// Command usages with their help text, in the order `help` lists them
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("tasks", "Show this session's task tree with statuses"),
+    ("show <task-id>", "Show a task's details, result or error"),
+    (
+        "retry <task-id>",
+        "Run a failed, skipped or blocked task again",
+    ),
+    (
+        "cancel <task-id>",
+        "Skip an unfinished task and its unfinished subtasks",
+    ),
+    ("checkpoint <description>", "Save a session checkpoint"),
+    (
+        "budget",
+        "Show token and cost usage against the daily quota",
+    ),
+    (
+        "provider [name]",
+        "Show or switch the provider that plans and runs tasks",
+    ),
+    ("plan <file>", "Load a plan or task list and run it"),
+    (
+        "history",
+        "List recent entries; `!N` reruns entry N, `!!` the last one",
+    ),
+    ("status", "Show system status"),
+    ("help", "Show this help message"),
+    ("quit", "Exit the application"),
+    ("exit", "Exit the application"),
+];
+
+/// One line of interactive input
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplCommand {
+    Empty,
+    Quit,
+    Help,
+    Status,
+    Tasks,
+    /// Task commands hold the id or id prefix as typed
+    Show(String),
+    Retry(String),
+    Cancel(String),
+    Checkpoint(String),
+    Budget,
+    History,
+    /// `None` shows the current provider
+    Provider(Option<ProviderType>),
+    Plan(PathBuf),
+    /// Any other text is a task description
+    Task(String),
+}
+
+impl ReplCommand {
+    /// Parse a line, failing on a command with missing or invalid arguments
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, rest) = match line.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (line, ""),
+        };
+
+        let command = match name {
+            "" => Self::Empty,
+            "quit" | "exit" => Self::Quit,
+            "help" => Self::Help,
+            "status" => Self::Status,
+            "tasks" => Self::Tasks,
+            "budget" => Self::Budget,
+            "history" => Self::History,
+            "show" | "retry" | "cancel" if rest.is_empty() => {
+                return Err(format!("Usage: {} <task-id>", name));
+            }
+            // Anything but an id makes the line a task, e.g. "cancel the old job"
+            "show" | "retry" | "cancel" if !is_task_id(rest) => Self::Task(line.to_string()),
+            "show" => Self::Show(rest.to_string()),
+            "retry" => Self::Retry(rest.to_string()),
+            "cancel" => Self::Cancel(rest.to_string()),
+            "checkpoint" if rest.is_empty() => {
+                return Err("Usage: checkpoint <description>".to_string());
+            }
+            "checkpoint" => Self::Checkpoint(rest.to_string()),
+            "provider" if rest.is_empty() => Self::Provider(None),
+            "provider" => Self::Provider(Some(parse_provider(rest)?)),
+            "plan" if rest.is_empty() => return Err("Usage: plan <file>".to_string()),
+            "plan" => Self::Plan(PathBuf::from(rest)),
+            _ => Self::Task(line.to_string()),
+        };
+
+        // Arguments to argument-less commands make the line a task instead
+        match command {
+            Self::Quit | Self::Help | Self::Status | Self::Tasks | Self::Budget | Self::History
+                if !rest.is_empty() =>
+            {
+                Ok(Self::Task(line.to_string()))
+            }
+            command => Ok(command),
+        }
+    }
+}
+
+/// Whether `word` could be a task id or a prefix of one
+fn is_task_id(word: &str) -> bool {
+    word.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+/// Provider names accepted by `provider`, as on the command line
+pub fn provider_names() -> Vec<String> {
+    ProviderCliOption::value_variants()
+        .iter()
+        .filter_map(|variant| variant.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+fn parse_provider(name: &str) -> Result<ProviderType, String> {
+    ProviderCliOption::from_str(name, true)
+        .map(ProviderCliOption::into_provider_type)
+        .map_err(|_| {
+            format!(
+                "Unknown provider '{}' (expected one of: {})",
+                name,
+                provider_names().join(", ")
+            )
+        })
+}
+
+/// Completions for the last word of `line`, with the offset that word starts at
+///
+/// Paths after `plan` are resolved against `cwd`.
+pub fn complete(line: &str, cwd: &Path) -> (usize, Vec<String>) {
+    let indent = line.len() - line.trim_start().len();
+    let Some((name, arg)) = line.trim_start().split_once(' ') else {
+        let candidates = COMMANDS
+            .iter()
+            .filter_map(|(usage, _)| usage.split(' ').next())
+            .filter(|name| name.starts_with(line.trim_start()))
+            .map(str::to_string)
+            .collect();
+        return (indent, candidates);
+    };
+
+    let candidates = match name {
+        "provider" => provider_names()
+            .into_iter()
+            .filter(|provider| provider.starts_with(arg))
+            .collect(),
+        "plan" => complete_path(arg, cwd),
+        _ => Vec::new(),
+    };
+    (indent + name.len() + 1, candidates)
+}
+
+/// Line editor for the interactive prompt
+pub type ReplEditor = Editor<ReplHelper, FileHistory>;
+
+/// Completes commands at the prompt through [`complete`]
+pub struct ReplHelper;
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(&line[..pos], &std::env::current_dir()?))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Prompt editor with command completion and the workspace's saved history
+pub fn editor(workspace_root: &Path) -> rustyline::Result<ReplEditor> {
+    let config = Config::builder()
+        .max_history_size(MAX_HISTORY_ENTRIES)?
+        .history_ignore_dups(true)?
+        .auto_add_history(false)
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor = ReplEditor::with_config(config)?;
+    editor.set_helper(Some(ReplHelper));
+
+    let path = crate::env::history_file_path(workspace_root);
+    if path.exists() {
+        editor.load_history(&path)?;
+    }
+    Ok(editor)
+}
+
+/// Add an entry to the prompt's history and append it to `.aca/history`
+pub fn remember(
+    editor: &mut ReplEditor,
+    workspace_root: &Path,
+    entry: &str,
+) -> rustyline::Result<()> {
+    if !editor.add_history_entry(entry)? {
+        return Ok(());
+    }
+    let path = crate::env::history_file_path(workspace_root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    editor.append_history(&path)
+}
+
+fn complete_path(partial: &str, cwd: &Path) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(slash) => (&partial[..=slash], &partial[slash + 1..]),
+        None => ("", partial),
+    };
+    let Ok(entries) = std::fs::read_dir(cwd.join(dir)) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let suffix = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, suffix))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(ReplCommand::parse("  ").unwrap(), ReplCommand::Empty);
+        assert_eq!(ReplCommand::parse("tasks").unwrap(), ReplCommand::Tasks);
+        assert_eq!(
+            ReplCommand::parse("checkpoint before refactor").unwrap(),
+            ReplCommand::Checkpoint("before refactor".to_string())
+        );
+        assert_eq!(
+            ReplCommand::parse("provider openai-codex").unwrap(),
+            ReplCommand::Provider(Some(ProviderType::OpenAICodex))
+        );
+        assert_eq!(
+            ReplCommand::parse("provider").unwrap(),
+            ReplCommand::Provider(None)
+        );
+        assert_eq!(
+            ReplCommand::parse("plan plans/release.json").unwrap(),
+            ReplCommand::Plan(PathBuf::from("plans/release.json"))
+        );
+        assert_eq!(
+            ReplCommand::parse("show 1f2e3d4c").unwrap(),
+            ReplCommand::Show("1f2e3d4c".to_string())
+        );
+        assert_eq!(
+            ReplCommand::parse("retry 1F2E3D4C-0000").unwrap(),
+            ReplCommand::Retry("1F2E3D4C-0000".to_string())
+        );
+        assert_eq!(
+            ReplCommand::parse("cancel ab12").unwrap(),
+            ReplCommand::Cancel("ab12".to_string())
+        );
+        assert!(ReplCommand::parse("retry").is_err());
+        assert!(ReplCommand::parse("checkpoint").is_err());
+        assert!(ReplCommand::parse("provider gpt").is_err());
+
+        // Ordinary sentences that happen to start with a command name
+        assert_eq!(
+            ReplCommand::parse("budget report should round to cents").unwrap(),
+            ReplCommand::Task("budget report should round to cents".to_string())
+        );
+        assert_eq!(
+            ReplCommand::parse("show a toast after saving").unwrap(),
+            ReplCommand::Task("show a toast after saving".to_string())
+        );
+        assert_eq!(
+            ReplCommand::parse("Fix the login form").unwrap(),
+            ReplCommand::Task("Fix the login form".to_string())
+        );
+    }
+
+    #[test]
+    fn test_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("plans")).unwrap();
+        std::fs::write(dir.path().join("plans/release.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("plan.md"), "").unwrap();
+
+        let words = |candidates: &[&str]| candidates.iter().map(|c| c.to_string()).collect();
+        assert_eq!(complete("che", dir.path()), (0, words(&["checkpoint"])));
+        assert_eq!(
+            complete("provider lo", dir.path()),
+            (9, words(&["local-model"]))
+        );
+        assert_eq!(
+            complete("  plan pl", dir.path()),
+            (7, words(&["plan.md", "plans/"]))
+        );
+        assert_eq!(
+            complete("plan plans/r", dir.path()),
+            (5, words(&["plans/release.json"]))
+        );
+        assert!(complete("fix the", dir.path()).1.is_empty());
+    }
+
+    #[test]
+    fn test_history_persists_multiline_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut first = editor(dir.path()).unwrap();
+        remember(&mut first, dir.path(), "tasks").unwrap();
+        remember(&mut first, dir.path(), "tasks").unwrap();
+        remember(&mut first, dir.path(), "Add caching\n- keep it in memory").unwrap();
+
+        // A later session recalls the entries with Up, newlines intact
+        let second = editor(dir.path()).unwrap();
+        let entries: Vec<&String> = second.history().iter().collect();
+        assert_eq!(entries, vec!["tasks", "Add caching\n- keep it in memory"]);
+    }
+}
@@ -0,0 +1,177 @@
+//! Terminal feedback when interactive tasks finish
+//!
+//! After each task the [`Reporter`] prints a one-line summary (outcome,
+//! duration, files touched, tokens and cost). Tasks that ran long enough for
+//! the user to look away also ring the terminal bell and send an OSC 9
+//! desktop notification, which iTerm2, WezTerm, kitty and Windows Terminal
+//! surface even when the window is in the background.
+
+use crate::claude::types::TokenUsage;
+use crate::task::{Task, TaskStatus};
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration;
+
+/// Tasks shorter than this finish without a bell or notification
+pub const MIN_NOTIFY_DURATION: Duration = Duration::from_secs(10);
+
+/// What happened to one task, for the summary line
+#[derive(Debug, Clone)]
+pub struct TaskSummary {
+    pub title: String,
+    /// Final status name, or `failed` if the task never finished
+    pub status: String,
+    pub succeeded: bool,
+    pub duration: Duration,
+    /// Files created, changed or deleted, if they could be counted
+    pub files_touched: Option<usize>,
+    pub usage: Option<TokenUsage>,
+}
+
+impl TaskSummary {
+    pub fn from_task(task: &Task, duration: Duration, files_touched: Option<usize>) -> Self {
+        let usage = match &task.status {
+            TaskStatus::Completed {
+                result: crate::task::types::TaskResult::Success { output, .. },
+                ..
+            } => output
+                .get("token_usage")
+                .and_then(|usage| serde_json::from_value(usage.clone()).ok()),
+            _ => None,
+        };
+        Self {
+            title: task.title.clone(),
+            status: task.status.name().to_string(),
+            succeeded: matches!(task.status, TaskStatus::Completed { .. }),
+            duration,
+            files_touched,
+            usage,
+        }
+    }
+
+    /// Summary for a task whose processing errored before it was reported
+    pub fn failed(title: &str, duration: Duration, files_touched: Option<usize>) -> Self {
+        Self {
+            title: title.to_string(),
+            status: "failed".to_string(),
+            succeeded: false,
+            duration,
+            files_touched,
+            usage: None,
+        }
+    }
+
+    /// `✅ completed in 2m 05s · 3 files · 41200 tokens · $0.1840`
+    pub fn line(&self) -> String {
+        let mut parts = vec![format!(
+            "{} {} in {}",
+            if self.succeeded { "✅" } else { "❌" },
+            self.status,
+            format_duration(self.duration)
+        )];
+        if let Some(files) = self.files_touched {
+            parts.push(format!(
+                "{} file{}",
+                files,
+                if files == 1 { "" } else { "s" }
+            ));
+        }
+        if let Some(usage) = &self.usage {
+            parts.push(format!("{} tokens", usage.total_tokens));
+            parts.push(format!("${:.4}", usage.estimated_cost));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Prints task summaries and notifies the user about finished tasks
+#[derive(Debug, Clone)]
+pub struct Reporter {
+    bell: bool,
+}
+
+impl Reporter {
+    /// `bell` enables the bell and OSC 9 notification for long tasks
+    pub fn new(bell: bool) -> Self {
+        Self { bell }
+    }
+
+    /// Whether finishing this task should ring and notify
+    pub fn should_notify(&self, summary: &TaskSummary) -> bool {
+        self.bell && summary.duration >= MIN_NOTIFY_DURATION
+    }
+
+    /// Print the summary, plus the bell and notification when due
+    pub fn task_finished(&self, summary: &TaskSummary) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", summary.line())?;
+        if self.should_notify(summary) && stdout.is_terminal() {
+            stdout.write_all(notification(summary).as_bytes())?;
+        }
+        stdout.flush()
+    }
+}
+
+/// OSC 9 notification followed by a bell
+pub fn notification(summary: &TaskSummary) -> String {
+    let message = format!("aca: {} {}", summary.title, summary.status)
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    format!("\x1b]9;{}\x07\x07", message)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}.{}s", s, duration.subsec_millis() / 100),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(duration: Duration) -> TaskSummary {
+        TaskSummary {
+            title: "Add login".to_string(),
+            status: "completed".to_string(),
+            succeeded: true,
+            duration,
+            files_touched: Some(3),
+            usage: Some(TokenUsage {
+                input_tokens: 40_000,
+                output_tokens: 1_200,
+                total_tokens: 41_200,
+                estimated_cost: 0.184,
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_summary_line() {
+        assert_eq!(
+            summary(Duration::from_secs(125)).line(),
+            "✅ completed in 2m 05s · 3 files · 41200 tokens · $0.1840"
+        );
+        let failed = TaskSummary::failed("Add login", Duration::from_millis(2500), None);
+        assert_eq!(failed.line(), "❌ failed in 2.5s");
+    }
+
+    #[test]
+    fn test_notifications_for_long_tasks_only() {
+        let reporter = Reporter::new(true);
+        assert!(!reporter.should_notify(&summary(Duration::from_secs(3))));
+        assert!(reporter.should_notify(&summary(Duration::from_secs(30))));
+        assert!(!Reporter::new(false).should_notify(&summary(Duration::from_secs(30))));
+
+        let mut task = summary(Duration::from_secs(30));
+        task.title = "Fix\x1b[31m bug".to_string();
+        assert_eq!(
+            notification(&task),
+            "\x1b]9;aca: Fix[31m bug completed\x07\x07"
+        );
+    }
+}
@@ -4,11 +4,19 @@
 //! - Single file tasks (--task-file): Any UTF-8 file becomes a task
 //! - Task lists (--tasks): Files containing multiple task specifications
 //! - Reference resolution: Tasks can reference other files for context
+//! - Inline text (`aca run -`, --from-clipboard): saved under `.aca/inputs/`
+//!   and then parsed as a task list
+//!
+//! Task lists keep their markdown structure without an LLM: headings group
+//! the items below them into sections, checked items (`- [x]`, `* DONE`) are
+//! recorded as already completed, and YAML front matter sets the plan's name,
+//! description, tags and default task priority.
 
 use crate::task::{
-    ComplexityLevel, ContextRequirements, ExecutionPlan, FileImportance, FileRef, TaskMetadata,
-    TaskPriority, TaskSpec,
+    ComplexityLevel, ContextRequirements, ExecutionPlan, FileImportance, FileRef, PRECOMPLETED_TAG,
+    PlanLoader, TaskMetadata, TaskPriority, TaskSpec,
 };
+use futures::future::BoxFuture;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -44,6 +52,79 @@ pub enum TaskInput {
     TaskList(PathBuf),        // --tasks (any UTF-8 file)
     ConfigWithTasks(PathBuf), // --config (current TOML format)
     ExecutionPlan(PathBuf),   // --execution-plan (JSON or TOML execution plan)
+    Inline(InlineSource),     // `-` or --from-clipboard, saved before parsing
+}
+
+impl TaskInput {
+    /// File the tasks are read from; inline input reports its source name
+    pub fn path(&self) -> &std::path::Path {
+        match self {
+            TaskInput::SingleFile(path)
+            | TaskInput::TaskList(path)
+            | TaskInput::ConfigWithTasks(path)
+            | TaskInput::ExecutionPlan(path) => path,
+            TaskInput::Inline(source) => Path::new(source.name()),
+        }
+    }
+}
+
+/// Where task text comes from when there is no file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlineSource {
+    Stdin,
+    Clipboard,
+}
+
+/// Clipboard readers tried in order, first one that succeeds wins
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+impl InlineSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InlineSource::Stdin => "stdin",
+            InlineSource::Clipboard => "clipboard",
+        }
+    }
+
+    /// Read the task text from stdin or the system clipboard
+    pub fn read(&self) -> Result<String, FileError> {
+        match self {
+            InlineSource::Stdin => {
+                let mut text = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut text).map_err(|e| {
+                    FileError::IoError {
+                        path: PathBuf::from(self.name()),
+                        source: e,
+                    }
+                })?;
+                Ok(text)
+            }
+            InlineSource::Clipboard => CLIPBOARD_COMMANDS
+                .iter()
+                .find_map(|(program, args)| {
+                    let output = std::process::Command::new(program)
+                        .args(*args)
+                        .output()
+                        .ok()?;
+                    if !output.status.success() {
+                        return None;
+                    }
+                    String::from_utf8(output.stdout).ok()
+                })
+                .ok_or_else(|| {
+                    FileError::Parse(
+                        "Could not read the clipboard; install pbpaste, wl-paste, xclip or xsel"
+                            .to_string(),
+                    )
+                }),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -56,6 +137,42 @@ struct Utf8File {
 pub struct SimpleTask {
     pub description: String,
     pub reference_file: Option<PathBuf>,
+    /// Checked off in the task list (`- [x]`, `* DONE`)
+    pub completed: bool,
+    /// Headings the task is listed under, outermost first
+    pub section: Vec<String>,
+}
+
+/// Plan metadata from a task list's YAML front matter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Priority of every task in the list
+    pub priority: Option<TaskPriority>,
+    pub tags: Vec<String>,
+}
+
+/// A parsed task list file
+#[derive(Debug, Clone)]
+pub struct TaskListDocument {
+    pub front_matter: FrontMatter,
+    pub tasks: Vec<SimpleTask>,
+}
+
+/// How task input is turned into an execution plan
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Plan task lists with the intelligent LLM-based parser
+    pub use_intelligent: bool,
+    pub context_hints: Vec<String>,
+    pub provider_override: Option<crate::llm::types::ProviderType>,
+    pub model_override: Option<String>,
+    pub parse_timeout: Option<std::time::Duration>,
+    pub decomposition: crate::cli::DecompositionLimits,
+    /// Workspace the intelligent parser runs in and stores its analysis
+    /// under; the current directory when unset
+    pub workspace: Option<PathBuf>,
 }
 
 /// Task loader responsible for loading and parsing different task input formats
@@ -65,22 +182,13 @@ impl TaskLoader {
     /// Convert a TaskInput to an ExecutionPlan using intelligent or naive parser
     pub async fn task_input_to_execution_plan_with_options(
         input: &TaskInput,
-        use_intelligent: bool,
-        context_hints: Vec<String>,
-        provider_override: Option<crate::llm::types::ProviderType>,
-        model_override: Option<String>,
+        options: &ParseOptions,
     ) -> Result<ExecutionPlan, FileError> {
         match input {
             TaskInput::ExecutionPlan(path) => Self::load_execution_plan(path),
             _ => {
-                if use_intelligent {
-                    Self::task_input_to_execution_plan_intelligent(
-                        input,
-                        context_hints,
-                        provider_override,
-                        model_override,
-                    )
-                    .await
+                if options.use_intelligent {
+                    Self::task_input_to_execution_plan_intelligent(input, options).await
                 } else {
                     Self::task_input_to_execution_plan(input)
                 }
@@ -116,42 +224,70 @@ impl TaskLoader {
     }
 
     /// Convert a TaskInput to an ExecutionPlan using intelligent parser
+    ///
+    /// If the analysis times out or is cancelled with Ctrl-C, the naive
+    /// parser's plan is used instead; nothing has been persisted yet.
     async fn task_input_to_execution_plan_intelligent(
         input: &TaskInput,
-        context_hints: Vec<String>,
-        provider_override: Option<crate::llm::types::ProviderType>,
-        model_override: Option<String>,
+        options: &ParseOptions,
     ) -> Result<ExecutionPlan, FileError> {
-        use crate::cli::IntelligentTaskParser;
+        use crate::cli::intelligent_parser::DEFAULT_ANALYSIS_TIMEOUT;
+        use crate::cli::{IntelligentParserError, IntelligentTaskParser};
         use crate::llm::provider::LLMProviderFactory;
         use crate::llm::types::ProviderConfig;
 
         // Use default provider config (Claude Code CLI mode)
         // The provider will handle API key requirements based on its configured mode
         let mut provider_config = ProviderConfig::default();
-        if let Some(provider_type) = provider_override {
+        if let Some(provider_type) = options.provider_override.clone() {
             provider_config.provider_type = provider_type;
         }
-        if let Some(model) = model_override
+        if let Some(model) = &options.model_override
             && !model.trim().is_empty()
         {
-            provider_config.model = Some(model);
+            provider_config.model = Some(model.clone());
         }
 
-        let workspace = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-        let provider = LLMProviderFactory::create_provider(provider_config, workspace)
+        let workspace = match &options.workspace {
+            Some(workspace) => workspace.clone(),
+            None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        };
+        let provider = LLMProviderFactory::create_provider(provider_config, workspace.clone())
             .await
             .map_err(|e| FileError::Parse(format!("Failed to create LLM provider: {}", e)))?;
 
-        let parser = IntelligentTaskParser::new(provider);
+        let parser = IntelligentTaskParser::new(provider)
+            .with_timeout(options.parse_timeout.unwrap_or(DEFAULT_ANALYSIS_TIMEOUT))
+            .with_analysis_store(workspace)
+            .with_decomposition(options.decomposition.clone());
 
         // Parse based on input type
         match input {
-            TaskInput::SingleFile(path) | TaskInput::TaskList(path) => parser
-                .parse_file(path.clone(), context_hints)
-                .await
-                .map_err(|e| FileError::Parse(format!("Intelligent parsing failed: {}", e))),
-            TaskInput::ConfigWithTasks(_) => {
+            TaskInput::SingleFile(path) | TaskInput::TaskList(path) => {
+                let parsed = tokio::select! {
+                    parsed = parser.parse_file(path.clone(), options.context_hints.clone()) => parsed,
+                    _ = tokio::signal::ctrl_c() => Err(IntelligentParserError::Cancelled),
+                };
+                match parsed {
+                    Ok(plan) => Ok(plan),
+                    Err(
+                        e
+                        @ (IntelligentParserError::Timeout(_) | IntelligentParserError::Cancelled),
+                    ) => {
+                        warn!("Intelligent parsing of {:?} stopped: {}", path, e);
+                        eprintln!(
+                            "⚠️  {}; falling back to the naive parser. Retry with a longer --parse-timeout, or use --force-naive-parser to skip LLM analysis.",
+                            e
+                        );
+                        Self::task_input_to_execution_plan(input)
+                    }
+                    Err(e) => Err(FileError::Parse(format!(
+                        "Intelligent parsing failed: {}",
+                        e
+                    ))),
+                }
+            }
+            TaskInput::ConfigWithTasks(_) | TaskInput::Inline(_) => {
                 // For TOML configs, fall back to naive parsing
                 Self::task_input_to_execution_plan(input)
             }
@@ -163,6 +299,52 @@ impl TaskLoader {
         }
     }
 
+    /// Read inline task text and save it in the workspace as a task list
+    ///
+    /// File inputs are returned unchanged, so callers can always run this
+    /// before handing the input to the parsers.
+    pub fn materialize_inline(input: TaskInput, workspace: &Path) -> Result<TaskInput, FileError> {
+        match input {
+            TaskInput::Inline(source) => {
+                let text = source.read()?;
+                Self::save_inline_text(source, &text, workspace)
+            }
+            input => Ok(input),
+        }
+    }
+
+    /// Save task text under `.aca/inputs/` and return it as a task list input
+    pub fn save_inline_text(
+        source: InlineSource,
+        text: &str,
+        workspace: &Path,
+    ) -> Result<TaskInput, FileError> {
+        if text.trim().is_empty() {
+            return Err(FileError::ParseError {
+                path: PathBuf::from(source.name()),
+                reason: "No task text provided".to_string(),
+            });
+        }
+
+        let file_name = format!(
+            "{}-{}.md",
+            source.name(),
+            chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f")
+        );
+        let path = crate::env::inline_input_file_path(workspace, &file_name);
+        let io_error = |e| FileError::IoError {
+            path: path.clone(),
+            source: e,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        fs::write(&path, text).map_err(io_error)?;
+
+        debug!("Saved task text from {} to {:?}", source.name(), path);
+        Ok(TaskInput::TaskList(path))
+    }
+
     /// Load a UTF-8 file with proper error handling
     fn load_utf8_file<P: AsRef<Path>>(path: P) -> Result<Utf8File, FileError> {
         let path = path.as_ref().to_path_buf();
@@ -200,20 +382,157 @@ impl TaskLoader {
         Ok(SimpleTask {
             description: file.content,
             reference_file: None,
+            completed: false,
+            section: Vec::new(),
         })
     }
 
     /// Parse a task list file (--tasks)
     pub fn parse_task_list<P: AsRef<Path>>(path: P) -> Result<Vec<SimpleTask>, FileError> {
+        Ok(Self::parse_task_list_document(path)?.tasks)
+    }
+
+    /// Parse a task list file along with its front matter
+    pub fn parse_task_list_document<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<TaskListDocument, FileError> {
         let file = Self::load_utf8_file(path)?;
 
         debug!("Parsing task list from: {:?}", file.path);
 
-        let tasks = Self::parse_task_list_content(&file.content, &file.path)?;
+        let (front_matter, body) = Self::split_front_matter(&file.content);
+        let tasks = Self::parse_task_list_content(body, &file.path)?;
 
         debug!("Parsed {} tasks from {:?}", tasks.len(), file.path);
 
-        Ok(tasks)
+        Ok(TaskListDocument {
+            front_matter,
+            tasks,
+        })
+    }
+
+    /// Separate `---` delimited YAML front matter from the rest of the file
+    ///
+    /// Only the flat subset plans need is understood: `key: value` pairs and
+    /// lists written inline (`[a, b]`) or as `- item` lines. Unknown keys are
+    /// ignored. A block with any other line, such as prose between two
+    /// horizontal rules, is ordinary content.
+    fn split_front_matter(content: &str) -> (FrontMatter, &str) {
+        let mut front_matter = FrontMatter::default();
+        let mut lines = content.split_inclusive('\n');
+        if lines.next().map(str::trim_end) != Some("---") {
+            return (front_matter, content);
+        }
+
+        let mut offset = content.find('\n').map_or(content.len(), |i| i + 1);
+        let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+        let mut closed = false;
+        for line in lines {
+            offset += line.len();
+            let line = line.trim_end();
+            if line == "---" || line == "..." {
+                closed = true;
+                break;
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(item) = trimmed.strip_prefix("- ") {
+                // A list item belongs to the key before it
+                let Some((_, values)) = fields.last_mut() else {
+                    return (front_matter, content);
+                };
+                values.push(Self::yaml_scalar(item));
+            } else if let Some((key, value)) = trimmed.split_once(':')
+                && Self::is_yaml_key(key)
+                && (value.is_empty() || value.starts_with(char::is_whitespace))
+            {
+                let value = value.trim();
+                let values = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                    Some(list) => list
+                        .split(',')
+                        .map(Self::yaml_scalar)
+                        .filter(|v| !v.is_empty())
+                        .collect(),
+                    None if value.is_empty() => Vec::new(),
+                    None => vec![Self::yaml_scalar(value)],
+                };
+                fields.push((key.trim().to_lowercase(), values));
+            } else {
+                return (front_matter, content);
+            }
+        }
+        if !closed {
+            // An unterminated block is ordinary content, not front matter
+            return (front_matter, content);
+        }
+
+        for (key, values) in fields {
+            match key.as_str() {
+                "name" | "title" => front_matter.name = values.into_iter().next(),
+                "description" => front_matter.description = values.into_iter().next(),
+                "priority" => {
+                    front_matter.priority = values.first().and_then(|value| {
+                        let priority = Self::parse_priority(value);
+                        if priority.is_none() {
+                            warn!("Ignoring unknown front matter priority '{}'", value);
+                        }
+                        priority
+                    })
+                }
+                "tags" => front_matter.tags = values,
+                _ => debug!("Ignoring front matter key '{}'", key),
+            }
+        }
+        (front_matter, &content[offset.min(content.len())..])
+    }
+
+    /// Whether `key` is a plain front matter key such as `name` or `max-tasks`
+    fn is_yaml_key(key: &str) -> bool {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    }
+
+    /// A YAML scalar with surrounding quotes removed
+    fn yaml_scalar(value: &str) -> String {
+        let value = value.trim();
+        value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value)
+            .to_string()
+    }
+
+    pub(crate) fn parse_priority(value: &str) -> Option<TaskPriority> {
+        match value.to_lowercase().as_str() {
+            "critical" => Some(TaskPriority::Critical),
+            "high" => Some(TaskPriority::High),
+            "normal" | "medium" => Some(TaskPriority::Normal),
+            "low" => Some(TaskPriority::Low),
+            "background" => Some(TaskPriority::Background),
+            _ => None,
+        }
+    }
+
+    /// Markdown heading level and text, for `#` through `######` followed by a space
+    fn parse_heading(line: &str) -> Option<(usize, String)> {
+        let level = line.chars().take_while(|c| *c == '#').count();
+        let text = line[level..].strip_prefix(' ')?;
+        let text = text.trim().trim_end_matches('#').trim();
+        ((1..=6).contains(&level) && !text.is_empty()).then(|| (level, text.to_string()))
+    }
+
+    /// Markdown horizontal rule: three or more `-`, `*` or `_`, optionally
+    /// spaced out
+    fn is_horizontal_rule(line: &str) -> bool {
+        let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+        marks.len() >= 3
+            && ['-', '*', '_'].contains(&marks[0])
+            && marks.iter().all(|c| *c == marks[0])
     }
 
     /// Parse task list content - handles various text formats
@@ -222,21 +541,35 @@ impl TaskLoader {
         source_path: &Path,
     ) -> Result<Vec<SimpleTask>, FileError> {
         let mut tasks = Vec::new();
+        // Enclosing headings as (level, text), outermost first
+        let mut headings: Vec<(usize, String)> = Vec::new();
 
         for (line_num, line) in content.lines().enumerate() {
             let line = line.trim();
 
-            // Skip empty lines and comments
-            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            // Headings open a section for the items below them
+            if let Some((level, text)) = Self::parse_heading(line) {
+                headings.retain(|(outer, _)| *outer < level);
+                headings.push((level, text));
+                continue;
+            }
+
+            // Skip empty lines, comments and horizontal rules
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("//")
+                || Self::is_horizontal_rule(line)
+            {
                 continue;
             }
 
             // Handle various task list formats
-            let task = if let Some(task) = Self::parse_task_line(line, source_path)? {
+            let mut task = if let Some(task) = Self::parse_task_line(line, source_path)? {
                 task
             } else {
                 continue; // Skip unrecognized lines
             };
+            task.section = headings.iter().map(|(_, text)| text.clone()).collect();
 
             debug!(
                 "Parsed task from line {}: {}",
@@ -255,14 +588,18 @@ impl TaskLoader {
 
     /// Parse a single task line with optional reference
     fn parse_task_line(line: &str, source_path: &Path) -> Result<Option<SimpleTask>, FileError> {
+        let completed = Self::is_checked(line);
+
         // Handle reference syntax: "task description -> reference_file.md"
         if let Some((description, reference)) = line.split_once(" -> ") {
-            let description = description.trim().to_string();
+            let description = Self::extract_task_description(description);
             let reference_path = Self::resolve_reference_path(reference.trim(), source_path)?;
 
             Ok(Some(SimpleTask {
                 description,
                 reference_file: Some(reference_path),
+                completed,
+                section: Vec::new(),
             }))
         } else {
             // Handle various task list formats
@@ -274,19 +611,29 @@ impl TaskLoader {
                 Ok(Some(SimpleTask {
                     description,
                     reference_file: None,
+                    completed,
+                    section: Vec::new(),
                 }))
             }
         }
     }
 
+    /// Whether the line is a checked markdown item or an org-mode DONE entry
+    fn is_checked(line: &str) -> bool {
+        let line = line.trim();
+        ["- [x]", "- [X]", "* [x]", "* [X]", "* DONE "]
+            .iter()
+            .any(|marker| line.starts_with(marker))
+    }
+
     /// Extract task description from various formats (markdown, org-mode, etc.)
     fn extract_task_description(line: &str) -> String {
         let line = line.trim();
 
         // Markdown task list: "- [ ] task" or "- [x] task" or "* task"
-        if let Some(rest) = line
-            .strip_prefix("- [ ]")
-            .or_else(|| line.strip_prefix("- [x]"))
+        if let Some(rest) = ["- [ ]", "- [x]", "- [X]", "* [ ]", "* [x]", "* [X]"]
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker))
         {
             return rest.trim().to_string();
         }
@@ -317,7 +664,10 @@ impl TaskLoader {
     }
 
     /// Resolve reference file path relative to the source file
-    fn resolve_reference_path(reference: &str, source_path: &Path) -> Result<PathBuf, FileError> {
+    pub(crate) fn resolve_reference_path(
+        reference: &str,
+        source_path: &Path,
+    ) -> Result<PathBuf, FileError> {
         let reference_path = if Path::new(reference).is_absolute() {
             PathBuf::from(reference)
         } else {
@@ -373,6 +723,20 @@ impl TaskLoader {
             context_requirements.required_files.push(ref_path.clone());
         }
 
+        let mut tags = vec!["from-task-file".to_string()];
+        let mut description = simple_task.description.clone();
+        if !simple_task.section.is_empty() {
+            tags.push(format!("section:{}", simple_task.section.join("/")));
+            description = format!(
+                "Section: {}\n\n{}",
+                simple_task.section.join(" > "),
+                description
+            );
+        }
+        if simple_task.completed {
+            tags.push(PRECOMPLETED_TAG.to_string());
+        }
+
         TaskSpec {
             title: format!(
                 "Task: {}",
@@ -382,7 +746,7 @@ impl TaskLoader {
                     simple_task.description.clone()
                 }
             ),
-            description: simple_task.description,
+            description,
             dependencies: Vec::new(),
             metadata: TaskMetadata {
                 priority: TaskPriority::Normal,
@@ -402,9 +766,14 @@ impl TaskLoader {
                         }]
                     })
                     .unwrap_or_default(),
-                tags: vec!["from-task-file".to_string()],
+                tags,
                 context_requirements,
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
+            plan: None,
         }
     }
 
@@ -443,38 +812,61 @@ impl TaskLoader {
     pub fn task_list_to_execution_plan<P: AsRef<Path>>(
         path: P,
     ) -> Result<ExecutionPlan, FileError> {
-        let mut simple_tasks = Self::parse_task_list(path.as_ref())?;
+        let TaskListDocument {
+            front_matter,
+            tasks: mut simple_tasks,
+        } = Self::parse_task_list_document(path.as_ref())?;
 
         // Resolve references
         debug!("Resolving task references for execution plan...");
         Self::resolve_task_references(&mut simple_tasks)?;
 
+        let open_count = simple_tasks.iter().filter(|task| !task.completed).count();
+
         // Convert to TaskSpec instances
         let task_specs: Vec<TaskSpec> = simple_tasks
             .into_iter()
             .map(Self::simple_task_to_task_spec)
+            .map(|mut spec| {
+                if let Some(priority) = &front_matter.priority {
+                    spec.metadata.priority = priority.clone();
+                }
+                spec
+            })
             .collect();
 
-        let plan_name = format!(
-            "Task List: {}",
-            path.as_ref()
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown")
-        );
+        let plan_name = front_matter.name.unwrap_or_else(|| {
+            format!(
+                "Task List: {}",
+                path.as_ref()
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown")
+            )
+        });
 
         let task_count = task_specs.len();
+        let plan_description =
+            front_matter
+                .description
+                .unwrap_or_else(|| match task_count - open_count {
+                    0 => format!("Execution plan for {} tasks from task list", task_count),
+                    done => format!(
+                        "Execution plan for {} tasks from task list ({} already done)",
+                        task_count, done
+                    ),
+                });
         let estimated_duration =
-            chrono::Duration::from_std(std::time::Duration::from_secs(300 * task_count as u64))
+            chrono::Duration::from_std(std::time::Duration::from_secs(300 * open_count as u64))
                 .unwrap_or_else(|_| chrono::Duration::minutes(5));
 
+        let mut tags = vec!["task-list".to_string(), "auto-generated".to_string()];
+        tags.extend(front_matter.tags);
+
         let plan = ExecutionPlan::new()
             .with_tasks(task_specs)
-            .with_metadata(
-                plan_name,
-                format!("Execution plan for {} tasks from task list", task_count),
-            )
-            .with_tags(vec!["task-list".to_string(), "auto-generated".to_string()])
+            .with_metadata(plan_name, plan_description)
+            .with_tags(tags)
             .with_estimated_duration(estimated_duration)
             .with_sequential_execution();
 
@@ -502,10 +894,39 @@ impl TaskLoader {
                 })
             }
             TaskInput::ExecutionPlan(path) => Self::load_execution_plan(path),
+            TaskInput::Inline(source) => Err(FileError::ParseError {
+                path: PathBuf::from(source.name()),
+                reason: "Inline task text must be saved with materialize_inline first".to_string(),
+            }),
         }
     }
 }
 
+/// Parses the files named by tasks' `plan` field, the same way `aca run`
+/// parses its input
+///
+/// `.json` and `.toml` files are execution plans; anything else is a task
+/// list, parsed with the intelligent parser when
+/// [`ParseOptions::use_intelligent`] is set.
+#[derive(Debug, Clone, Default)]
+pub struct SubPlanParser {
+    pub options: ParseOptions,
+}
+
+impl PlanLoader for SubPlanParser {
+    fn load<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, anyhow::Result<ExecutionPlan>> {
+        Box::pin(async move {
+            let input = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json" | "toml") => TaskInput::ExecutionPlan(path.to_path_buf()),
+                _ => TaskInput::TaskList(path.to_path_buf()),
+            };
+            let plan = TaskLoader::task_input_to_execution_plan_with_options(&input, &self.options)
+                .await?;
+            Ok(plan.with_source(path))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -571,6 +992,129 @@ mod tests {
         assert!(tasks[1].reference_file.is_none());
     }
 
+    #[test]
+    fn test_headings_group_items_and_checked_items_are_done() {
+        let temp_file = NamedTempFile::new().unwrap();
+        fs::write(
+            &temp_file,
+            "Set up CI\n\
+             # Backend\n\
+             ## Auth\n\
+             - [x] Add login endpoint\n\
+             - [ ] Add logout endpoint\n\
+             ## Storage ##\n\
+             - [X] Pick a database\n\
+             # Docs\n\
+             * DONE Write README\n\
+             #not-a-heading\n",
+        )
+        .unwrap();
+
+        let tasks = TaskLoader::parse_task_list(temp_file.path()).unwrap();
+        let summary: Vec<(&str, bool, String)> = tasks
+            .iter()
+            .map(|t| (t.description.as_str(), t.completed, t.section.join("/")))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Set up CI", false, String::new()),
+                ("Add login endpoint", true, "Backend/Auth".to_string()),
+                ("Add logout endpoint", false, "Backend/Auth".to_string()),
+                ("Pick a database", true, "Backend/Storage".to_string()),
+                ("DONE Write README", true, "Docs".to_string()),
+            ]
+        );
+
+        let spec = TaskLoader::simple_task_to_task_spec(tasks[1].clone());
+        assert_eq!(
+            spec.description,
+            "Section: Backend > Auth\n\nAdd login endpoint"
+        );
+        assert!(
+            spec.metadata
+                .tags
+                .contains(&"section:Backend/Auth".to_string())
+        );
+        assert!(spec.metadata.tags.contains(&PRECOMPLETED_TAG.to_string()));
+    }
+
+    #[test]
+    fn test_front_matter_sets_plan_metadata() {
+        let temp_file = NamedTempFile::new().unwrap();
+        fs::write(
+            &temp_file,
+            "---\n\
+             name: \"Sprint 12\"\n\
+             priority: high\n\
+             tags: [backend, 'cost:team-a']\n\
+             owner: someone\n\
+             ---\n\
+             - [ ] Add rate limiting\n\
+             - [x] Add metrics\n",
+        )
+        .unwrap();
+
+        let plan = TaskLoader::task_list_to_execution_plan(temp_file.path()).unwrap();
+        assert_eq!(plan.metadata.name.as_deref(), Some("Sprint 12"));
+        assert_eq!(
+            plan.metadata.description.as_deref(),
+            Some("Execution plan for 2 tasks from task list (1 already done)")
+        );
+        assert!(plan.metadata.tags.contains(&"cost:team-a".to_string()));
+        assert!(plan.metadata.tags.contains(&"task-list".to_string()));
+        assert_eq!(plan.task_count(), 2);
+        assert!(
+            plan.task_specs
+                .iter()
+                .all(|spec| spec.metadata.priority == TaskPriority::High)
+        );
+
+        let (front_matter, body) = TaskLoader::split_front_matter("---\ntags:\n  - a\n  - b\n");
+        assert!(front_matter.tags.is_empty(), "unterminated front matter");
+        assert!(body.starts_with("---"));
+        let (front_matter, body) =
+            TaskLoader::split_front_matter("---\ntags:\n  - a\n  - b\n---\n- Task\n");
+        assert_eq!(front_matter.tags, vec!["a", "b"]);
+        assert_eq!(body, "- Task\n");
+    }
+
+    #[test]
+    fn test_leading_horizontal_rule_is_not_front_matter() {
+        let temp_file = NamedTempFile::new().unwrap();
+        fs::write(
+            &temp_file,
+            "---\n\
+             Release checklist: everything below ships today.\n\
+             - [ ] Update the changelog\n\
+             ---\n\
+             - [ ] Tag the release\n",
+        )
+        .unwrap();
+
+        // Nothing is swallowed as front matter, and the rules are not tasks
+        let tasks = TaskLoader::parse_task_list(temp_file.path()).unwrap();
+        let descriptions: Vec<&str> = tasks.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "Release checklist: everything below ships today.",
+                "Update the changelog",
+                "Tag the release"
+            ]
+        );
+
+        for content in [
+            "---\n- Orphan item\n---\n",
+            "---\nSee https://example.com\n---\n",
+            "---\nname:value\n---\n",
+        ] {
+            let (front_matter, body) = TaskLoader::split_front_matter(content);
+            assert_eq!(front_matter, FrontMatter::default(), "{}", content);
+            assert_eq!(body, content);
+        }
+    }
+
     #[test]
     fn test_extract_task_description_formats() {
         assert_eq!(
@@ -683,11 +1227,38 @@ mod tests {
         assert!(format!("{}", result.unwrap_err()).contains("structured config mode"));
     }
 
+    #[test]
+    fn test_save_inline_text_as_task_list() {
+        let workspace = tempfile::tempdir().unwrap();
+        let input = TaskLoader::save_inline_text(
+            InlineSource::Stdin,
+            "- Fix login redirect\n- Add rate limiting\n",
+            workspace.path(),
+        )
+        .unwrap();
+
+        let TaskInput::TaskList(path) = &input else {
+            panic!("Expected a task list input");
+        };
+        assert!(path.starts_with(crate::env::aca_dir_path(workspace.path())));
+        let plan = TaskLoader::task_input_to_execution_plan(&input).unwrap();
+        assert_eq!(plan.task_count(), 2);
+
+        assert!(
+            TaskLoader::save_inline_text(InlineSource::Clipboard, "  \n", workspace.path())
+                .is_err()
+        );
+        let unsaved = TaskInput::Inline(InlineSource::Stdin);
+        assert!(TaskLoader::task_input_to_execution_plan(&unsaved).is_err());
+    }
+
     #[test]
     fn test_simple_task_to_task_spec() {
         let simple_task = SimpleTask {
             description: "Test task description".to_string(),
             reference_file: Some(PathBuf::from("reference.md")),
+            completed: false,
+            section: Vec::new(),
         };
 
         let task_spec = TaskLoader::simple_task_to_task_spec(simple_task);
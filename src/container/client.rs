@@ -3,7 +3,11 @@
 //! Provides a simplified interface to the bollard Docker API with automatic
 //! connection handling, fallback strategies, and health checking.
 
+use crate::container::context::{
+    DEFAULT_CONTEXT, DockerContext, current_context, docker_config_dir, docker_contexts, start_hint,
+};
 use crate::container::{ContainerError, Result};
+use crate::executor::ContainerRuntime;
 use bollard::Docker;
 use std::sync::Arc;
 use tracing::{debug, info};
@@ -15,6 +19,10 @@ pub struct ContainerClientConfig {
     pub timeout: u64,
     /// Number of connection retries
     pub retries: u32,
+    /// Runtime to connect to
+    pub runtime: ContainerRuntime,
+    /// Docker context to use instead of the Docker CLI's current one
+    pub docker_context: Option<String>,
 }
 
 impl Default for ContainerClientConfig {
@@ -22,6 +30,8 @@ impl Default for ContainerClientConfig {
         Self {
             timeout: 120,
             retries: 3,
+            runtime: ContainerRuntime::Auto,
+            docker_context: None,
         }
     }
 }
@@ -55,79 +65,69 @@ impl ContainerClient {
     ///
     /// Returns error if connection to container runtime fails.
     pub async fn with_config(config: ContainerClientConfig) -> Result<Self> {
-        let docker = Self::connect().await?;
-
-        let client = Self {
+        let docker = Self::connect(&config).await?;
+        Ok(Self {
             docker: Arc::new(docker),
             config,
-        };
-
-        // Verify connection works
-        client.ping().await?;
-
-        Ok(client)
+        })
     }
 
-    /// Connect to Docker or Podman daemon.
+    /// Connect to the first reachable runtime `config.runtime` allows.
     ///
-    /// Tries multiple connection strategies in order:
-    /// 1. Local defaults (Unix socket or Windows named pipe)
-    /// 2. DOCKER_HOST environment variable
-    /// 3. Podman socket (if Docker fails)
-    async fn connect() -> Result<Docker> {
-        // Try local defaults first
+    /// Each candidate endpoint is pinged before it is used, so a missing
+    /// Docker socket falls through to Podman's instead of failing later. The
+    /// Docker context in use is tried first; one chosen in the configuration
+    /// is the only endpoint tried.
+    async fn connect(config: &ContainerClientConfig) -> Result<Docker> {
         debug!("Attempting to connect to container runtime...");
+        let env = |name: &str| std::env::var(name).ok();
+        let context = select_context(config, env)?;
+
+        if let (Some(context), Some(_)) = (&context, &config.docker_context) {
+            let endpoint = Endpoint::new(RuntimeType::Docker, &context.host);
+            return match endpoint.ping(config.timeout).await {
+                Some(docker) => Ok(docker),
+                None => Err(ContainerError::Other(format!(
+                    "Docker context '{}' at {} is not responding; {}",
+                    context.name,
+                    context.host,
+                    start_hint(context)
+                ))),
+            };
+        }
 
-        match Docker::connect_with_local_defaults() {
-            Ok(docker) => {
-                info!("Connected to container runtime via local defaults");
+        let endpoints = runtime_endpoints(
+            config.runtime,
+            env,
+            uid(),
+            context.as_ref().map(|context| context.host.as_str()),
+        );
+        for endpoint in &endpoints {
+            if let Some(docker) = endpoint.ping(config.timeout).await {
                 return Ok(docker);
             }
-            Err(e) => {
-                debug!("Local defaults failed: {}", e);
-            }
         }
 
-        // Try Unix socket for Podman
-        #[cfg(unix)]
-        {
-            use bollard::Docker;
-
-            // Try rootless Podman socket
-            if let Ok(home) = std::env::var("HOME") {
-                let podman_socket = format!("unix://{}/run/podman/podman.sock", home);
-                debug!("Trying Podman socket: {}", podman_socket);
-
-                match Docker::connect_with_socket(&podman_socket, 120, bollard::API_DEFAULT_VERSION)
-                {
-                    Ok(docker) => {
-                        info!("Connected to Podman via rootless socket");
-                        return Ok(docker);
-                    }
-                    Err(e) => {
-                        debug!("Podman rootless socket failed: {}", e);
-                    }
-                }
-            }
-
-            // Try system Podman socket
-            let system_socket = "unix:///run/podman/podman.sock";
-            debug!("Trying system Podman socket: {}", system_socket);
-
-            match Docker::connect_with_socket(system_socket, 120, bollard::API_DEFAULT_VERSION) {
-                Ok(docker) => {
-                    info!("Connected to Podman via system socket");
-                    return Ok(docker);
-                }
-                Err(e) => {
-                    debug!("Podman system socket failed: {}", e);
-                }
-            }
+        let tried: Vec<&str> = endpoints.iter().map(|e| e.address.as_str()).collect();
+        let mut message = format!(
+            "Failed to connect to Docker or Podman (tried {}). Please ensure Docker or Podman is installed and running; \
+             for rootless Podman, start its socket with `systemctl --user enable --now podman.socket`.",
+            tried.join(", ")
+        );
+        if let Some(context) = &context {
+            message.push_str(&format!(
+                " The current Docker context '{}' is not responding; {}.",
+                context.name,
+                start_hint(context)
+            ));
         }
-
-        Err(ContainerError::Other(
-            "Failed to connect to Docker or Podman. Please ensure Docker or Podman is installed and running.".to_string()
-        ))
+        if cfg!(target_os = "macos") && which::which("container").is_ok() {
+            message.push_str(
+                " Apple's `container` runtime does not serve the Docker API; \
+                 use Colima, Docker Desktop, OrbStack or Rancher Desktop.",
+            );
+        }
+        Err(ContainerError::Other(message))
     }
 
     /// Ping the container runtime to verify connectivity.
@@ -305,6 +305,174 @@ pub enum ContainerState {
     Dead,
 }
 
+/// A socket or host a container runtime may be listening on
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Endpoint {
+    runtime: RuntimeType,
+    address: String,
+}
+
+impl Endpoint {
+    fn new(runtime: RuntimeType, address: impl Into<String>) -> Self {
+        Self {
+            runtime,
+            address: address.into(),
+        }
+    }
+
+    fn connect(&self, timeout: u64) -> std::result::Result<Docker, bollard::errors::Error> {
+        if self.address.starts_with("unix://") || self.address.starts_with("npipe://") {
+            Docker::connect_with_socket(&self.address, timeout, bollard::API_DEFAULT_VERSION)
+        } else if self.address.starts_with("tcp://") || self.address.starts_with("http://") {
+            Docker::connect_with_http(&self.address, timeout, bollard::API_DEFAULT_VERSION)
+        } else {
+            // Other schemes only come from `DOCKER_HOST`, which bollard reads itself
+            Docker::connect_with_defaults()
+        }
+    }
+
+    /// A client for this endpoint if its runtime responds
+    async fn ping(&self, timeout: u64) -> Option<Docker> {
+        debug!("Trying {} at {}", self.runtime, self.address);
+        let docker = self
+            .connect(timeout)
+            .inspect_err(|e| debug!("{} unavailable: {}", self.address, e))
+            .ok()?;
+        match docker.ping().await {
+            Ok(_) => {
+                info!("Connected to {} at {}", self.runtime, self.address);
+                Some(docker)
+            }
+            Err(e) => {
+                debug!("{} did not respond: {}", self.address, e);
+                None
+            }
+        }
+    }
+}
+
+/// The Docker context to connect through: the configured one, else the
+/// Docker CLI's current one; `None` for the standard sockets
+///
+/// A configured context that does not exist is an error; Podman ignores
+/// Docker contexts.
+fn select_context(
+    config: &ContainerClientConfig,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Option<DockerContext>> {
+    if config.runtime == ContainerRuntime::Podman {
+        return Ok(None);
+    }
+    let Some(config_dir) = docker_config_dir(&env) else {
+        return Ok(None);
+    };
+    let contexts = docker_contexts(&config_dir);
+    match config.docker_context.as_deref() {
+        Some(DEFAULT_CONTEXT) => Ok(None),
+        Some(name) => match contexts.iter().find(|context| context.name == name) {
+            Some(context) => Ok(Some(context.clone())),
+            None => Err(ContainerError::ConfigError(format!(
+                "Docker context '{}' not found; available: {}",
+                name,
+                std::iter::once(DEFAULT_CONTEXT)
+                    .chain(contexts.iter().map(|context| context.name.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        },
+        None => Ok(current_context(&env, &config_dir)
+            .and_then(|name| contexts.into_iter().find(|context| context.name == name))),
+    }
+}
+
+/// Endpoints to try for `runtime`, in order
+///
+/// `env` looks up environment variables and `uid` is the current user's,
+/// used for the rootless Podman socket when `XDG_RUNTIME_DIR` is unset.
+/// `context_host` is the current Docker context's endpoint, tried after
+/// `DOCKER_HOST`, which overrides contexts as it does for the Docker CLI.
+fn runtime_endpoints(
+    runtime: ContainerRuntime,
+    env: impl Fn(&str) -> Option<String>,
+    uid: Option<u32>,
+    context_host: Option<&str>,
+) -> Vec<Endpoint> {
+    let mut endpoints = Vec::new();
+    if runtime != ContainerRuntime::Podman {
+        if let Some(host) = env("DOCKER_HOST").filter(|host| !host.is_empty()) {
+            endpoints.push(Endpoint::new(RuntimeType::Docker, host));
+        }
+        if let Some(host) = context_host {
+            endpoints.push(Endpoint::new(RuntimeType::Docker, host));
+        }
+        if cfg!(windows) {
+            endpoints.push(Endpoint::new(
+                RuntimeType::Docker,
+                "npipe:////./pipe/docker_engine",
+            ));
+        } else {
+            endpoints.push(Endpoint::new(
+                RuntimeType::Docker,
+                "unix:///var/run/docker.sock",
+            ));
+            // Docker Desktop, the macOS VMs' default profiles and rootless Docker
+            if let Some(home) = env("HOME") {
+                for socket in [
+                    ".docker/run/docker.sock",
+                    ".colima/default/docker.sock",
+                    ".orbstack/run/docker.sock",
+                    ".rd/docker.sock",
+                ] {
+                    endpoints.push(Endpoint::new(
+                        RuntimeType::Docker,
+                        format!("unix://{}/{}", home, socket),
+                    ));
+                }
+            }
+            if let Some(dir) = env("XDG_RUNTIME_DIR") {
+                endpoints.push(Endpoint::new(
+                    RuntimeType::Docker,
+                    format!("unix://{}/docker.sock", dir),
+                ));
+            }
+        }
+    }
+
+    if runtime != ContainerRuntime::Docker && !cfg!(windows) {
+        // Podman's remote connections are usually over ssh, which isn't supported
+        if let Some(host) = env("CONTAINER_HOST").filter(|host| host.starts_with("unix://")) {
+            endpoints.push(Endpoint::new(RuntimeType::Podman, host));
+        }
+        let runtime_dir = env("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .or_else(|| uid.map(|uid| format!("/run/user/{}", uid)));
+        if let Some(dir) = runtime_dir {
+            endpoints.push(Endpoint::new(
+                RuntimeType::Podman,
+                format!("unix://{}/podman/podman.sock", dir),
+            ));
+        }
+        endpoints.push(Endpoint::new(
+            RuntimeType::Podman,
+            "unix:///run/podman/podman.sock",
+        ));
+    }
+
+    endpoints.dedup_by(|a, b| a.address == b.address);
+    endpoints
+}
+
+/// The current user's id, owner of this process
+fn uid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::metadata("/proc/self").ok().map(|meta| meta.uid())
+    }
+    #[cfg(not(unix))]
+    None
+}
+
 /// Type of container runtime.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RuntimeType {
@@ -327,6 +495,128 @@ impl std::fmt::Display for RuntimeType {
 mod tests {
     use super::*;
 
+    fn addresses(endpoints: &[Endpoint]) -> Vec<&str> {
+        endpoints.iter().map(|e| e.address.as_str()).collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rootless_podman_socket_from_xdg_runtime_dir() {
+        let env = |name: &str| match name {
+            "XDG_RUNTIME_DIR" => Some("/run/user/1000".to_string()),
+            "HOME" => Some("/home/dev".to_string()),
+            _ => None,
+        };
+        let endpoints = runtime_endpoints(ContainerRuntime::Podman, env, Some(1000), None);
+        assert_eq!(
+            addresses(&endpoints),
+            [
+                "unix:///run/user/1000/podman/podman.sock",
+                "unix:///run/podman/podman.sock"
+            ]
+        );
+
+        // Without XDG_RUNTIME_DIR the socket is found by uid
+        let endpoints = runtime_endpoints(ContainerRuntime::Podman, |_| None, Some(1001), None);
+        assert_eq!(
+            endpoints[0].address,
+            "unix:///run/user/1001/podman/podman.sock"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_auto_tries_docker_before_podman() {
+        let env = |name: &str| match name {
+            "DOCKER_HOST" => Some("tcp://build-host:2375".to_string()),
+            "CONTAINER_HOST" => Some("ssh://core@vm/run/podman/podman.sock".to_string()),
+            _ => None,
+        };
+        let endpoints = runtime_endpoints(ContainerRuntime::Auto, env, None, None);
+        assert_eq!(
+            addresses(&endpoints),
+            [
+                "tcp://build-host:2375",
+                "unix:///var/run/docker.sock",
+                "unix:///run/podman/podman.sock"
+            ]
+        );
+
+        let endpoints = runtime_endpoints(ContainerRuntime::Docker, env, Some(1000), None);
+        assert!(endpoints.iter().all(|e| e.runtime == RuntimeType::Docker));
+    }
+
+    #[test]
+    fn test_select_context() {
+        let config_dir = tempfile::TempDir::new().unwrap();
+        let meta = config_dir.path().join("contexts/meta/abc");
+        std::fs::create_dir_all(&meta).unwrap();
+        std::fs::write(
+            meta.join("meta.json"),
+            r#"{"Name":"colima","Endpoints":{"docker":{"Host":"unix:///Users/dev/.colima/default/docker.sock"}}}"#,
+        )
+        .unwrap();
+        let dir = config_dir.path().to_string_lossy().to_string();
+        let env = |name: &str| (name == "DOCKER_CONFIG").then(|| dir.clone());
+        let config = |runtime, docker_context: Option<&str>| ContainerClientConfig {
+            runtime,
+            docker_context: docker_context.map(str::to_string),
+            ..Default::default()
+        };
+
+        // No current context: the standard sockets
+        assert_eq!(
+            select_context(&config(ContainerRuntime::Auto, None), env).unwrap(),
+            None
+        );
+
+        std::fs::write(
+            config_dir.path().join("config.json"),
+            r#"{"currentContext":"colima"}"#,
+        )
+        .unwrap();
+        let current = select_context(&config(ContainerRuntime::Auto, None), env).unwrap();
+        assert_eq!(current.unwrap().name, "colima");
+        assert_eq!(
+            select_context(&config(ContainerRuntime::Podman, None), env).unwrap(),
+            None
+        );
+        assert_eq!(
+            select_context(&config(ContainerRuntime::Docker, Some("default")), env).unwrap(),
+            None
+        );
+
+        let error = select_context(&config(ContainerRuntime::Auto, Some("orbstack")), env)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("'orbstack' not found"), "{}", error);
+        assert!(error.contains("default, colima"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_context_endpoint_follows_docker_host() {
+        let env = |name: &str| match name {
+            "DOCKER_HOST" => Some("tcp://build-host:2375".to_string()),
+            "HOME" => Some("/Users/dev".to_string()),
+            _ => None,
+        };
+        let endpoints = runtime_endpoints(
+            ContainerRuntime::Docker,
+            env,
+            None,
+            Some("unix:///Users/dev/.colima/default/docker.sock"),
+        );
+        assert_eq!(
+            addresses(&endpoints)[..3],
+            [
+                "tcp://build-host:2375",
+                "unix:///Users/dev/.colima/default/docker.sock",
+                "unix:///var/run/docker.sock",
+            ]
+        );
+    }
+
     #[tokio::test]
     #[ignore] // Requires Docker/Podman to be running
     async fn test_client_connection() {
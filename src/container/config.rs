@@ -4,6 +4,7 @@
 //! without manual Dockerfiles or config files.
 
 use crate::container::{ContainerError, Result};
+use crate::executor::devcontainer::DevContainerSetup;
 use bollard::service::{HostConfig, Mount, MountTypeEnum, PortBinding};
 use std::collections::HashMap;
 
@@ -198,6 +199,18 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Apply a dev container's workspace folder, mounts and container environment.
+    ///
+    /// The workspace is mounted where the dev container expects it rather than
+    /// at `/workspace`.
+    pub fn devcontainer(mut self, setup: &DevContainerSetup) -> Self {
+        self = self.working_dir(&setup.workspace_folder);
+        for bind in &setup.binds {
+            self = self.bind(bind);
+        }
+        self.envs(setup.container_env.clone())
+    }
+
     /// Set user to run as in the container.
     pub fn user<S: Into<String>>(mut self, user: S) -> Self {
         self.user = Some(user.into());
@@ -0,0 +1,194 @@
+//! Docker CLI contexts.
+//!
+//! On macOS the Docker daemon usually runs in a VM managed by Docker
+//! Desktop, Colima, Rancher Desktop or OrbStack, each listening on its own
+//! socket and registered as a Docker context. The contexts are read from
+//! the Docker CLI's configuration directory (`DOCKER_CONFIG`, else
+//! `~/.docker`) without running `docker`: `contexts/meta/*/meta.json` names
+//! each context and its endpoint, and `config.json` records the current one.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The context that means the standard Docker sockets
+pub const DEFAULT_CONTEXT: &str = "default";
+
+/// A named Docker daemon endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerContext {
+    pub name: String,
+    /// Daemon address, e.g. `unix:///Users/dev/.colima/default/docker.sock`
+    pub host: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContextMeta {
+    name: String,
+    #[serde(default)]
+    endpoints: HashMap<String, ContextEndpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContextEndpoint {
+    host: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CliConfig {
+    current_context: Option<String>,
+}
+
+/// The Docker CLI's configuration directory
+///
+/// `env` looks up environment variables.
+pub fn docker_config_dir(env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    env("DOCKER_CONFIG")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| Path::new(&home).join(".docker")))
+}
+
+/// Contexts with a Docker endpoint defined in `config_dir`, sorted by name
+pub fn docker_contexts(config_dir: &Path) -> Vec<DockerContext> {
+    let Ok(entries) = std::fs::read_dir(config_dir.join("contexts/meta")) else {
+        return Vec::new();
+    };
+    let mut contexts: Vec<DockerContext> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let content = std::fs::read_to_string(entry.path().join("meta.json")).ok()?;
+            let mut meta: ContextMeta = serde_json::from_str(&content).ok()?;
+            let host = meta.endpoints.remove("docker")?.host?;
+            Some(DockerContext {
+                name: meta.name,
+                host,
+            })
+        })
+        .collect();
+    contexts.sort_by(|a, b| a.name.cmp(&b.name));
+    contexts
+}
+
+/// The context the Docker CLI would use: `DOCKER_CONTEXT`, then the
+/// `currentContext` in `config.json`
+pub fn current_context(env: impl Fn(&str) -> Option<String>, config_dir: &Path) -> Option<String> {
+    env("DOCKER_CONTEXT")
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            let content = std::fs::read_to_string(config_dir.join("config.json")).ok()?;
+            serde_json::from_str::<CliConfig>(&content)
+                .ok()?
+                .current_context
+        })
+        .filter(|name| !name.is_empty())
+}
+
+/// How to start the daemon behind a context that does not respond
+pub fn start_hint(context: &DockerContext) -> String {
+    let name = context.name.as_str();
+    if let Some(profile) = name.strip_prefix("colima-") {
+        format!("start it with `colima start --profile {}`", profile)
+    } else if name == "colima" || context.host.contains("/.colima/") {
+        "start it with `colima start`".to_string()
+    } else if name == "rancher-desktop" || context.host.contains("/.rd/") {
+        "start Rancher Desktop".to_string()
+    } else if name == "orbstack" || context.host.contains("/.orbstack/") {
+        "start OrbStack".to_string()
+    } else if name.starts_with("desktop-") || context.host.contains("/.docker/run/") {
+        "start Docker Desktop".to_string()
+    } else {
+        "start its daemon, or choose another with `docker context ls`".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_context(config_dir: &Path, dir: &str, name: &str, host: &str) {
+        let meta = config_dir.join("contexts/meta").join(dir);
+        std::fs::create_dir_all(&meta).unwrap();
+        std::fs::write(
+            meta.join("meta.json"),
+            format!(
+                r#"{{"Name":"{}","Metadata":{{"Description":"{}"}},"Endpoints":{{"docker":{{"Host":"{}","SkipTLSVerify":false}}}}}}"#,
+                name, name, host
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_contexts_and_current_context() {
+        let config = TempDir::new().unwrap();
+        let dir = config.path();
+        write_context(
+            dir,
+            "a1",
+            "colima",
+            "unix:///Users/dev/.colima/default/docker.sock",
+        );
+        write_context(
+            dir,
+            "b2",
+            "desktop-linux",
+            "unix:///Users/dev/.docker/run/docker.sock",
+        );
+        std::fs::create_dir_all(dir.join("contexts/meta/c3")).unwrap();
+        std::fs::write(dir.join("contexts/meta/c3/meta.json"), "not json").unwrap();
+
+        let contexts = docker_contexts(dir);
+        assert_eq!(
+            contexts.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["colima", "desktop-linux"]
+        );
+        assert_eq!(
+            contexts[0].host,
+            "unix:///Users/dev/.colima/default/docker.sock"
+        );
+
+        assert_eq!(current_context(|_| None, dir), None);
+        std::fs::write(dir.join("config.json"), r#"{"currentContext": "colima"}"#).unwrap();
+        assert_eq!(current_context(|_| None, dir).as_deref(), Some("colima"));
+        let env = |name: &str| (name == "DOCKER_CONTEXT").then(|| "desktop-linux".to_string());
+        assert_eq!(current_context(env, dir).as_deref(), Some("desktop-linux"));
+
+        let env = |name: &str| (name == "HOME").then(|| "/Users/dev".to_string());
+        assert_eq!(
+            docker_config_dir(env),
+            Some(PathBuf::from("/Users/dev/.docker"))
+        );
+    }
+
+    #[test]
+    fn test_start_hints() {
+        let hint = |name: &str, host: &str| {
+            start_hint(&DockerContext {
+                name: name.to_string(),
+                host: host.to_string(),
+            })
+        };
+        assert_eq!(
+            hint("colima-arm", "unix:///Users/dev/.colima/arm/docker.sock"),
+            "start it with `colima start --profile arm`"
+        );
+        assert_eq!(
+            hint("colima", "unix:///Users/dev/.colima/default/docker.sock"),
+            "start it with `colima start`"
+        );
+        assert_eq!(
+            hint("rancher-desktop", "unix:///Users/dev/.rd/docker.sock"),
+            "start Rancher Desktop"
+        );
+        assert_eq!(
+            hint("desktop-linux", "unix:///Users/dev/.docker/run/docker.sock"),
+            "start Docker Desktop"
+        );
+        assert!(hint("remote", "tcp://10.0.0.5:2376").contains("docker context ls"));
+    }
+}
@@ -4,10 +4,13 @@
 //! including the ACA base image with Claude Code and development tools.
 
 use crate::container::{ContainerError, Result};
+use crate::executor::devcontainer::{DevContainerImage, DevContainerSetup, workspace_slug};
+use crate::session::integrity::{SHA256_PREFIX, content_hash};
 use bollard::Docker;
 use futures::stream::StreamExt;
-use std::path::Path;
-use tracing::{debug, info};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
 
 /// Default ACA base image name (Ubuntu 22.04 full, ~3-4 GB)
 pub const ACA_BASE_IMAGE: &str = "aca-dev:latest";
@@ -15,6 +18,30 @@ pub const ACA_BASE_IMAGE: &str = "aca-dev:latest";
 /// ACA Alpine base image name (Alpine 3.19 lightweight, ~800 MB-1 GB)
 pub const ACA_BASE_IMAGE_ALPINE: &str = "aca-dev:alpine";
 
+/// A workspace's own Dockerfile or Containerfile to run tasks in
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockerfileBuild {
+    pub dockerfile: PathBuf,
+    /// Directory sent to the build as its context
+    pub context: PathBuf,
+}
+
+impl DockerfileBuild {
+    /// Tag for the image built from `contents` of the Dockerfile
+    ///
+    /// The tag carries a hash of the contents, so an unchanged Dockerfile
+    /// reuses the image built before.
+    pub fn image_tag(workspace_root: &Path, contents: &[u8]) -> String {
+        let hash = content_hash(contents);
+        let hash = hash.strip_prefix(SHA256_PREFIX).unwrap_or(&hash);
+        format!(
+            "aca-workspace-{}:{}",
+            workspace_slug(workspace_root),
+            &hash[..12]
+        )
+    }
+}
+
 /// Image builder for creating custom container images.
 pub struct ImageBuilder {
     docker: Docker,
@@ -56,8 +83,6 @@ impl ImageBuilder {
         info!("Building ACA base image: {} using Docker CLI", image_tag);
 
         // Use Docker CLI for building
-        use tokio::process::Command;
-
         let output = Command::new("docker")
             .arg("build")
             .arg("-t")
@@ -223,6 +248,142 @@ impl ImageBuilder {
 
         self.build_aca_base_image(path, None).await
     }
+
+    /// Build or pull the image of a dev container configuration.
+    ///
+    /// Features are applied with the `devcontainer` CLI when it is on `PATH`,
+    /// which also builds the base image; without it they are skipped with a
+    /// warning. Dockerfiles are built with the Docker CLI.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the build or pull fails.
+    pub async fn ensure_devcontainer_image(
+        &self,
+        setup: &DevContainerSetup,
+        workspace_root: &Path,
+    ) -> Result<String> {
+        let tag = setup.image_tag(workspace_root);
+
+        if !setup.features.is_empty() {
+            if which::which("devcontainer").is_ok() {
+                info!("Building dev container image {} with features", tag);
+                let mut command = Command::new("devcontainer");
+                command
+                    .arg("build")
+                    .arg("--workspace-folder")
+                    .arg(workspace_root)
+                    .arg("--config")
+                    .arg(&setup.config_path)
+                    .arg("--image-name")
+                    .arg(&tag);
+                run_build(command, "devcontainer build").await?;
+                return Ok(tag);
+            }
+            warn!(
+                "Skipping dev container features (install the devcontainer CLI to apply them): {}",
+                setup.features.join(", ")
+            );
+        }
+
+        match &setup.image {
+            DevContainerImage::Image(image) => {
+                if !self.image_exists(image).await? {
+                    self.pull_image(image).await?;
+                }
+                Ok(image.clone())
+            }
+            DevContainerImage::Dockerfile {
+                dockerfile,
+                context,
+                args,
+                target,
+            } => {
+                info!(
+                    "Building dev container image {} from {}",
+                    tag,
+                    dockerfile.display()
+                );
+                let mut command = Command::new("docker");
+                command
+                    .arg("build")
+                    .arg("-t")
+                    .arg(&tag)
+                    .arg("-f")
+                    .arg(dockerfile);
+                for (key, value) in args {
+                    command.arg("--build-arg").arg(format!("{}={}", key, value));
+                }
+                if let Some(target) = target {
+                    command.arg("--target").arg(target);
+                }
+                command.arg(context);
+                run_build(command, "docker build").await?;
+                Ok(tag)
+            }
+        }
+    }
+
+    /// Build the image of a workspace Dockerfile, unless one built from the
+    /// same contents exists
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the Dockerfile cannot be read or the build fails.
+    pub async fn ensure_dockerfile_image(
+        &self,
+        build: &DockerfileBuild,
+        workspace_root: &Path,
+    ) -> Result<String> {
+        let contents = std::fs::read(&build.dockerfile).map_err(|e| {
+            ContainerError::ConfigError(format!(
+                "Cannot read {}: {}",
+                build.dockerfile.display(),
+                e
+            ))
+        })?;
+        let tag = DockerfileBuild::image_tag(workspace_root, &contents);
+        if self.image_exists(&tag).await? {
+            info!(
+                "Using cached image {} for {}",
+                tag,
+                build.dockerfile.display()
+            );
+            return Ok(tag);
+        }
+
+        info!(
+            "Building workspace image {} from {}",
+            tag,
+            build.dockerfile.display()
+        );
+        let mut command = Command::new("docker");
+        command
+            .arg("build")
+            .arg("-t")
+            .arg(&tag)
+            .arg("-f")
+            .arg(&build.dockerfile)
+            .arg(&build.context);
+        run_build(command, "docker build").await?;
+        Ok(tag)
+    }
+}
+
+/// Run an image build command, failing with its stderr
+async fn run_build(mut command: Command, name: &str) -> Result<()> {
+    let output = command
+        .output()
+        .await
+        .map_err(|e| ContainerError::Other(format!("Failed to run {}: {}", name, e)))?;
+    if !output.status.success() {
+        return Err(ContainerError::Other(format!(
+            "{} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(())
 }
 
 /// Image information.
@@ -242,6 +403,15 @@ pub struct ImageInfo {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_dockerfile_image_tag_follows_contents() {
+        let root = Path::new("/home/dev/My Project");
+        let tag = DockerfileBuild::image_tag(root, b"FROM rust:1.90\n");
+        assert!(tag.starts_with("aca-workspace-my-project:"));
+        assert_eq!(tag, DockerfileBuild::image_tag(root, b"FROM rust:1.90\n"));
+        assert_ne!(tag, DockerfileBuild::image_tag(root, b"FROM rust:1.91\n"));
+    }
+
     #[tokio::test]
     #[ignore] // Requires Docker
     async fn test_image_exists() {
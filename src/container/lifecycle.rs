@@ -5,6 +5,7 @@
 //! start and cleaned up when sessions end.
 
 use super::{ContainerConfig, ContainerError, ContainerOrchestrator, Result};
+use crate::executor::devcontainer::DevContainerSetup;
 use crate::session::metadata::{
     ContainerResourceLimits, ContainerStatus, SessionContainerInfo, SessionId,
 };
@@ -28,6 +29,8 @@ pub struct LifecycleConfig {
     pub cpu_quota: Option<i64>,
     /// Whether to auto-remove container on shutdown
     pub auto_remove: bool,
+    /// Dev container whose mounts and environment the container uses
+    pub devcontainer: Option<DevContainerSetup>,
 }
 
 impl Default for LifecycleConfig {
@@ -39,6 +42,7 @@ impl Default for LifecycleConfig {
             memory_bytes: None,
             cpu_quota: None,
             auto_remove: true,
+            devcontainer: None,
         }
     }
 }
@@ -146,16 +150,20 @@ impl ContainerLifecycleManager {
         let mut container_config = ContainerConfig::builder()
             .image(&self.config.image)
             .cmd(vec!["sleep", "infinity"])
-            .working_dir("/workspace")
             .label("aca.session.id", self.session_id.to_string())
             .label("aca.managed", "true");
 
-        // Mount workspace
-        if !self.config.workspace_path.as_os_str().is_empty() {
-            container_config = container_config.bind(format!(
-                "{}:/workspace:rw",
-                self.config.workspace_path.display()
-            ));
+        // Mount workspace, where the dev container expects it if there is one
+        if let Some(ref setup) = self.config.devcontainer {
+            container_config = container_config.devcontainer(setup);
+        } else {
+            container_config = container_config.working_dir("/workspace");
+            if !self.config.workspace_path.as_os_str().is_empty() {
+                container_config = container_config.bind(format!(
+                    "{}:/workspace:rw",
+                    self.config.workspace_path.display()
+                ));
+            }
         }
 
         // Mount .aca directory
@@ -419,6 +427,7 @@ mod tests {
             memory_bytes: Some(1024 * 1024 * 1024), // 1GB
             cpu_quota: Some(50000),
             auto_remove: false,
+            devcontainer: None,
         };
 
         assert_eq!(config.image, "ubuntu:22.04");
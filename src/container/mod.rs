@@ -13,6 +13,7 @@
 //! The container module is organized into several components:
 //!
 //! - `client`: Docker/Podman API client wrapper with connection management
+//! - `context`: Docker CLI context discovery for daemons running in a VM
 //! - `orchestrator`: High-level container lifecycle orchestration
 //! - `config`: Container configuration builders for programmatic setup
 //! - `executor`: Command execution within running containers
@@ -54,6 +55,7 @@
 
 mod client;
 mod config;
+mod context;
 mod executor;
 mod image;
 mod interactive;
@@ -65,8 +67,11 @@ mod volume;
 
 pub use client::{ContainerClient, ContainerClientConfig, ContainerState, RuntimeType};
 pub use config::{ContainerConfig, ContainerConfigBuilder};
+pub use context::{
+    DEFAULT_CONTEXT, DockerContext, current_context, docker_config_dir, docker_contexts, start_hint,
+};
 pub use executor::{ExecConfig, ExecOutput};
-pub use image::{ACA_BASE_IMAGE, ACA_BASE_IMAGE_ALPINE, ImageBuilder, ImageInfo};
+pub use image::{ACA_BASE_IMAGE, ACA_BASE_IMAGE_ALPINE, DockerfileBuild, ImageBuilder, ImageInfo};
 pub use interactive::{InteractiveSession, attach_to_container};
 pub use lifecycle::{ContainerLifecycleManager, LifecycleConfig};
 pub use monitor::{ContainerStats, ResourceMonitor};
@@ -4,8 +4,10 @@
 //! execution, monitoring, and cleanup.
 
 use crate::container::{
-    ContainerClient, ContainerConfig, ContainerError, ExecConfig, ExecOutput, Result,
+    ContainerClient, ContainerClientConfig, ContainerConfig, ContainerError, ExecConfig,
+    ExecOutput, Result,
 };
+use crate::executor::ContainerRuntime;
 use futures::stream::StreamExt;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
@@ -19,6 +21,10 @@ pub struct ContainerOrchestratorConfig {
     pub name_prefix: String,
     /// Default stop timeout in seconds
     pub stop_timeout: i64,
+    /// Runtime to connect to
+    pub runtime: ContainerRuntime,
+    /// Docker context to use instead of the Docker CLI's current one
+    pub docker_context: Option<String>,
 }
 
 impl Default for ContainerOrchestratorConfig {
@@ -27,6 +33,8 @@ impl Default for ContainerOrchestratorConfig {
             auto_pull: true,
             name_prefix: "aca".to_string(),
             stop_timeout: 10,
+            runtime: ContainerRuntime::Auto,
+            docker_context: None,
         }
     }
 }
@@ -56,7 +64,12 @@ impl ContainerOrchestrator {
     ///
     /// Returns error if connection to container runtime fails.
     pub async fn with_config(config: ContainerOrchestratorConfig) -> Result<Self> {
-        let client = ContainerClient::new().await?;
+        let client = ContainerClient::with_config(ContainerClientConfig {
+            runtime: config.runtime,
+            docker_context: config.docker_context.clone(),
+            ..Default::default()
+        })
+        .await?;
         Ok(Self { client, config })
     }
 
@@ -199,6 +212,34 @@ impl ContainerOrchestrator {
         Ok(())
     }
 
+    /// Pause (freeze) all processes in a container.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if container pause fails.
+    pub async fn pause_container(&self, container_id: &str) -> Result<()> {
+        debug!("Pausing container: {}", container_id);
+
+        self.client.docker().pause_container(container_id).await?;
+
+        info!("Paused container: {}", container_id);
+        Ok(())
+    }
+
+    /// Resume a paused container.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if container unpause fails.
+    pub async fn unpause_container(&self, container_id: &str) -> Result<()> {
+        debug!("Unpausing container: {}", container_id);
+
+        self.client.docker().unpause_container(container_id).await?;
+
+        info!("Unpaused container: {}", container_id);
+        Ok(())
+    }
+
     /// Stop a container.
     ///
     /// # Errors
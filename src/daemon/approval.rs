@@ -0,0 +1,174 @@
+use super::auth::{AuthContext, Scope};
+use super::tenant::TenantError;
+use crate::session::JournalEvent;
+use crate::task::ExecutionPlan;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Plan waiting for an admin's decision
+#[derive(Debug, Clone)]
+pub struct PendingApproval {
+    pub id: Uuid,
+    pub title: String,
+    pub requested_by: String,
+    pub requested_at: DateTime<Utc>,
+    pub plan: ExecutionPlan,
+}
+
+/// Result of submitting a plan to a tenant
+#[derive(Debug, Clone, PartialEq)]
+pub enum Submission {
+    /// The tenant requires approval; the plan is queued under this id
+    Queued { approval_id: Uuid },
+    /// The plan ran immediately
+    Completed { task_ids: Vec<Uuid> },
+}
+
+/// Plans awaiting approval for one tenant
+#[derive(Debug, Default)]
+pub struct ApprovalQueue {
+    pending: Mutex<HashMap<Uuid, PendingApproval>>,
+}
+
+impl ApprovalQueue {
+    fn push(&self, approval: PendingApproval) {
+        self.pending.lock().unwrap().insert(approval.id, approval);
+    }
+
+    fn take(&self, id: Uuid) -> Option<PendingApproval> {
+        self.pending.lock().unwrap().remove(&id)
+    }
+
+    fn restore(&self, approval: PendingApproval) {
+        self.push(approval);
+    }
+
+    /// Pending approvals, oldest first
+    pub fn list(&self) -> Vec<PendingApproval> {
+        let mut pending: Vec<_> = self.pending.lock().unwrap().values().cloned().collect();
+        pending.sort_by_key(|approval| approval.requested_at);
+        pending
+    }
+}
+
+impl AuthContext {
+    /// Submit a plan, queueing it if the tenant requires approval
+    pub async fn submit_plan(&self, plan: ExecutionPlan) -> Result<Submission, TenantError> {
+        self.require(Scope::Submit)?;
+
+        if !self.tenant.config().require_approval {
+            let task_ids = self.tenant.execute_plan(plan).await?;
+            return Ok(Submission::Completed { task_ids });
+        }
+
+        let approval = PendingApproval {
+            id: Uuid::new_v4(),
+            title: plan.metadata.name.clone().unwrap_or_else(|| plan.summary()),
+            requested_by: self.principal.clone(),
+            requested_at: Utc::now(),
+            plan,
+        };
+        let approval_id = approval.id;
+
+        self.tenant
+            .journal()
+            .record(JournalEvent::ApprovalRequested {
+                approval_id,
+                title: approval.title.clone(),
+                requested_by: self.principal.clone(),
+            })?;
+        self.tenant.approvals().push(approval);
+
+        Ok(Submission::Queued { approval_id })
+    }
+
+    /// Plans waiting for approval
+    pub fn pending_approvals(&self) -> Result<Vec<PendingApproval>, TenantError> {
+        self.require(Scope::Read)?;
+        Ok(self.tenant.approvals().list())
+    }
+
+    /// Approve a pending plan and run it
+    pub async fn approve(&self, approval_id: Uuid) -> Result<Vec<Uuid>, TenantError> {
+        self.require(Scope::Approve)?;
+        let approval = self.take_pending(approval_id)?;
+
+        // Keep the plan queued if it cannot be admitted right now
+        let slot = match self
+            .tenant
+            .check_budget()
+            .and_then(|()| self.tenant.try_acquire_slot())
+        {
+            Ok(slot) => slot,
+            Err(e) => {
+                self.tenant.approvals().restore(approval);
+                return Err(e);
+            }
+        };
+
+        self.tenant
+            .journal()
+            .record(JournalEvent::ApprovalGranted {
+                approval_id,
+                title: approval.title.clone(),
+                approved_by: self.principal.clone(),
+                role: self.role.as_str().to_string(),
+            })?;
+
+        self.tenant.execute_plan_in_slot(approval.plan, slot).await
+    }
+
+    /// Reject a pending plan
+    pub fn reject(&self, approval_id: Uuid, reason: Option<String>) -> Result<(), TenantError> {
+        self.require(Scope::Approve)?;
+        let approval = self.take_pending(approval_id)?;
+
+        self.tenant
+            .journal()
+            .record(JournalEvent::ApprovalRejected {
+                approval_id,
+                title: approval.title,
+                rejected_by: self.principal.clone(),
+                role: self.role.as_str().to_string(),
+                reason,
+            })?;
+        Ok(())
+    }
+
+    /// Withdraw a pending plan; only its submitter or an admin may do so
+    pub fn cancel_approval(
+        &self,
+        approval_id: Uuid,
+        reason: Option<String>,
+    ) -> Result<(), TenantError> {
+        self.require(Scope::Submit)?;
+        let approval = self.take_pending(approval_id)?;
+        if approval.requested_by != self.principal && !self.role.allows(Scope::Approve) {
+            self.tenant.approvals().restore(approval);
+            return Err(TenantError::Forbidden {
+                principal: self.principal.clone(),
+                role: self.role,
+                scope: Scope::Approve,
+            });
+        }
+
+        self.tenant
+            .journal()
+            .record(JournalEvent::ApprovalCancelled {
+                approval_id,
+                title: approval.title,
+                cancelled_by: self.principal.clone(),
+                reason,
+            })?;
+        Ok(())
+    }
+
+    fn take_pending(&self, approval_id: Uuid) -> Result<PendingApproval, TenantError> {
+        self.tenant
+            .approvals()
+            .take(approval_id)
+            .ok_or(TenantError::UnknownApproval(approval_id))
+    }
+}
@@ -0,0 +1,111 @@
+use super::tenant::{Tenant, TenantError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Role granted to an API token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantRole {
+    /// Full access including approving or rejecting plans
+    Admin,
+    /// May read state and submit plans, but not approve them
+    Operator,
+    /// Read-only access; the role of tokens that do not name one
+    #[default]
+    Viewer,
+}
+
+/// Operation a token is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Read,
+    Submit,
+    Approve,
+}
+
+impl TenantRole {
+    /// Whether the role grants a scope
+    pub fn allows(self, scope: Scope) -> bool {
+        match self {
+            TenantRole::Admin => true,
+            TenantRole::Operator => matches!(scope, Scope::Read | Scope::Submit),
+            TenantRole::Viewer => scope == Scope::Read,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TenantRole::Admin => "admin",
+            TenantRole::Operator => "operator",
+            TenantRole::Viewer => "viewer",
+        }
+    }
+}
+
+/// Additional named token for a tenant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenConfig {
+    /// Principal name recorded in the event journal
+    pub name: String,
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// Environment variable holding the token (preferred over `api_token`)
+    #[serde(default)]
+    pub api_token_env: Option<String>,
+    #[serde(default)]
+    pub role: TenantRole,
+}
+
+/// Authenticated caller: the tenant, who they are and what they may do
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub tenant: Arc<Tenant>,
+    pub principal: String,
+    pub role: TenantRole,
+}
+
+impl AuthContext {
+    /// Fail unless the caller's role grants `scope`
+    pub fn require(&self, scope: Scope) -> Result<(), TenantError> {
+        if self.role.allows(scope) {
+            Ok(())
+        } else {
+            Err(TenantError::Forbidden {
+                principal: self.principal.clone(),
+                role: self.role,
+                scope,
+            })
+        }
+    }
+}
+
+/// Constant-time token comparison
+pub(crate) fn tokens_equal(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_scopes() {
+        assert!(TenantRole::Admin.allows(Scope::Approve));
+        assert!(TenantRole::Operator.allows(Scope::Submit));
+        assert!(!TenantRole::Operator.allows(Scope::Approve));
+        assert!(TenantRole::Viewer.allows(Scope::Read));
+        assert!(!TenantRole::Viewer.allows(Scope::Submit));
+    }
+
+    #[test]
+    fn test_tokens_without_a_role_are_read_only() {
+        let token: ApiTokenConfig =
+            toml::from_str("name = \"dashboard\"\napi_token = \"secret\"\n").unwrap();
+        assert_eq!(token.role, TenantRole::Viewer);
+        assert!(!token.role.allows(Scope::Approve));
+    }
+}
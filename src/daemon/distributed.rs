@@ -0,0 +1,1150 @@
+//! Distributed execution of one plan across several worker processes.
+//!
+//! `aca coordinator` owns a plan's task list and leases tasks whose
+//! dependencies have completed to `aca worker` processes, which may run on
+//! other machines with their own executor and provider quota. Idle workers
+//! pull the next eligible task, so fast workers take over work that would
+//! otherwise queue behind slow ones.
+//!
+//! A lease expires unless the worker renews it, after which the task is
+//! leased again. Results submitted under an expired lease are rejected, so
+//! each task's outcome is recorded once. Failed tasks are retried up to
+//! `max_attempts` times; a task that fails for good fails its dependents.
+//! The coordinator writes a snapshot after every change, so a restarted
+//! coordinator resumes where it stopped.
+//!
+//! ## Protocol
+//!
+//! Newline-delimited JSON over TCP. Each [`WorkerRequest`] carries the
+//! shared token (when configured) and one [`WorkerMessage`]; the coordinator
+//! answers with one [`CoordinatorReply`].
+//!
+//! | Message | Reply | Purpose |
+//! |---------|-------|---------|
+//! | `join` | `joined` | Register; returns the plan's setup commands and renewal interval |
+//! | `lease` | `lease` | Take the next eligible task (`null` when none is ready) |
+//! | `renew` | `renewed` | Extend a lease while its task runs |
+//! | `complete` | `ack` | Submit a task's result |
+//!
+//! Workers advertise their [`ExecutorCapabilities`] when they join and are
+//! only leased tasks whose requirements they meet; a ready task no joined
+//! worker can run is reported once and waits for a capable worker.
+//!
+//! The coordinator distributes tasks, not workspace contents: workers need
+//! their own checkout and a way to share changes, such as each task pushing
+//! a branch.
+
+use super::auth::tokens_equal;
+use crate::executor::{ExecutorCapabilities, place};
+use crate::integration::AgentSystem;
+use crate::task::{ExecutionMode, ExecutionPlan, SetupCommand, TaskSpec, TaskStatus};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::{Mutex, oneshot};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Address `aca coordinator` listens on and `aca worker` connects to by default
+pub const DEFAULT_COORDINATOR_ADDR: &str = "127.0.0.1:7979";
+
+/// How long an idle worker waits before asking for work again
+const IDLE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long a finished coordinator keeps answering so idle workers learn it is done
+const FINISHED_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(12);
+
+/// Errors raised by the coordinator, its protocol and workers
+#[derive(Debug, thiserror::Error)]
+pub enum CoordinatorError {
+    /// Request carried a missing or wrong cluster token
+    #[error("Invalid cluster token")]
+    Unauthorized,
+
+    /// Lease is unknown, already completed or expired
+    #[error("Unknown or expired lease: {0}")]
+    StaleLease(Uuid),
+
+    /// Distributed configuration is invalid
+    #[error("Invalid distributed configuration: {0}")]
+    InvalidConfig(String),
+
+    /// Error reported by the coordinator to a worker
+    #[error("Coordinator error: {0}")]
+    Remote(String),
+
+    /// Reply does not match the request
+    #[error("Unexpected coordinator reply: {0}")]
+    UnexpectedReply(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Lease and retry settings shared by the coordinator and its workers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DistributedConfig {
+    /// Seconds a lease lasts without renewal before the task is leased again
+    pub lease_timeout_secs: u64,
+    /// Seconds between lease renewals sent by workers
+    pub renew_interval_secs: u64,
+    /// Attempts per task, counting expired leases, before it fails
+    pub max_attempts: u32,
+    /// Environment variable holding the token workers must present
+    pub token_env: Option<String>,
+}
+
+impl Default for DistributedConfig {
+    fn default() -> Self {
+        Self {
+            lease_timeout_secs: 600,
+            renew_interval_secs: 60,
+            max_attempts: 3,
+            token_env: None,
+        }
+    }
+}
+
+impl DistributedConfig {
+    /// Shared cluster token from `token_env`, if configured
+    pub fn token(&self) -> Result<Option<String>, CoordinatorError> {
+        self.token_env
+            .as_ref()
+            .map(|var| {
+                std::env::var(var).map_err(|_| {
+                    CoordinatorError::InvalidConfig(format!(
+                        "environment variable {} is not set",
+                        var
+                    ))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Where a task stands in the distributed run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WorkState {
+    Pending,
+    Leased {
+        worker: String,
+        lease_id: Uuid,
+        expires_at: DateTime<Utc>,
+    },
+    Completed {
+        worker: String,
+        completed_at: DateTime<Utc>,
+        files_changed: Vec<PathBuf>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// One plan task tracked by the coordinator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkItem {
+    pub id: Uuid,
+    pub spec: TaskSpec,
+    /// Items that must complete before this one is leased
+    pub depends_on: Vec<Uuid>,
+    pub state: WorkState,
+    /// Leases granted so far
+    pub attempts: u32,
+    /// Error of the most recent failed attempt
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// Task handed to a worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub lease_id: Uuid,
+    pub task_id: Uuid,
+    pub spec: TaskSpec,
+    pub attempt: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outcome of a leased task reported by a worker
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkResult {
+    pub success: bool,
+    pub files_changed: Vec<PathBuf>,
+    pub error: Option<String>,
+}
+
+impl WorkResult {
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            files_changed: Vec::new(),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Task counts by state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterStatus {
+    pub pending: usize,
+    pub leased: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+impl ClusterStatus {
+    /// Every task has completed or failed
+    pub fn is_finished(&self) -> bool {
+        self.pending == 0 && self.leased == 0
+    }
+}
+
+/// Request sent by a worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerRequest {
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub message: WorkerMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    Join {
+        worker: String,
+        /// Workers without capabilities only take unconstrained tasks
+        #[serde(default)]
+        capabilities: Option<ExecutorCapabilities>,
+    },
+    Lease {
+        worker: String,
+    },
+    Renew {
+        lease_id: Uuid,
+    },
+    Complete {
+        lease_id: Uuid,
+        result: WorkResult,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CoordinatorReply {
+    Joined {
+        setup_commands: Vec<SetupCommand>,
+        renew_interval_secs: u64,
+    },
+    /// `lease` is `None` when no task is ready; `finished` once none ever will be
+    Lease {
+        lease: Option<Box<Lease>>,
+        finished: bool,
+    },
+    Renewed {
+        expires_at: DateTime<Utc>,
+    },
+    Ack,
+    Error {
+        message: String,
+    },
+}
+
+/// Persisted coordinator state
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    plan_fingerprint: &'a str,
+    items: &'a [WorkItem],
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    plan_fingerprint: String,
+    items: Vec<WorkItem>,
+}
+
+/// Owns a plan's tasks and leases them to workers
+#[derive(Debug)]
+pub struct WorkCoordinator {
+    config: DistributedConfig,
+    setup_commands: Vec<SetupCommand>,
+    /// Cap on simultaneous leases from a parallel plan's `max_concurrent`
+    max_leases: Option<usize>,
+    fingerprint: String,
+    items: Vec<WorkItem>,
+    state_file: Option<PathBuf>,
+    /// Capabilities advertised by each joined worker
+    workers: HashMap<String, ExecutorCapabilities>,
+    /// Ready tasks already reported as unplaceable
+    unplaced: HashSet<Uuid>,
+}
+
+impl WorkCoordinator {
+    /// Track `plan`'s tasks; sequential plans lease one task at a time in order
+    pub fn new(plan: &ExecutionPlan, config: DistributedConfig) -> Self {
+        let sequential = matches!(plan.execution_mode, ExecutionMode::Sequential);
+        let max_leases = match plan.execution_mode {
+            ExecutionMode::Parallel { max_concurrent } => max_concurrent,
+            _ => None,
+        };
+
+        let mut previous = None;
+        let items = plan
+            .task_specs
+            .iter()
+            .map(|spec| {
+                let id = Uuid::new_v4();
+                let depends_on = match previous {
+                    Some(previous) if sequential => vec![previous],
+                    _ => Vec::new(),
+                };
+                previous = Some(id);
+                WorkItem {
+                    id,
+                    spec: spec.clone(),
+                    depends_on,
+                    state: WorkState::Pending,
+                    attempts: 0,
+                    last_error: None,
+                }
+            })
+            .collect();
+
+        let fingerprint = plan
+            .task_specs
+            .iter()
+            .map(crate::task::task_fingerprint)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Self {
+            config,
+            setup_commands: plan.setup_commands.clone(),
+            max_leases,
+            fingerprint,
+            items,
+            state_file: None,
+            workers: HashMap::new(),
+            unplaced: HashSet::new(),
+        }
+    }
+
+    /// Snapshot state to `path` after every change, resuming from it first
+    /// if it holds the same plan
+    pub fn with_state_file(mut self, path: PathBuf) -> Result<Self, CoordinatorError> {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                let snapshot: Snapshot = serde_json::from_str(&content)?;
+                if snapshot.plan_fingerprint == self.fingerprint {
+                    info!("Resuming coordinator state from {}", path.display());
+                    self.items = snapshot.items;
+                } else {
+                    warn!(
+                        "Replacing coordinator state for a different plan in {}",
+                        path.display()
+                    );
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.state_file = Some(path);
+        self.persist()?;
+        Ok(self)
+    }
+
+    pub fn items(&self) -> &[WorkItem] {
+        &self.items
+    }
+
+    /// Record the capabilities `worker` offers
+    pub fn register(&mut self, worker: &str, capabilities: Option<ExecutorCapabilities>) {
+        match capabilities {
+            Some(capabilities) => {
+                self.workers.insert(worker.to_string(), capabilities);
+            }
+            None => {
+                self.workers.remove(worker);
+            }
+        }
+        // Tasks may have become placeable
+        self.unplaced.clear();
+    }
+
+    pub fn status(&self) -> ClusterStatus {
+        let mut status = ClusterStatus::default();
+        for item in &self.items {
+            match item.state {
+                WorkState::Pending => status.pending += 1,
+                WorkState::Leased { .. } => status.leased += 1,
+                WorkState::Completed { .. } => status.completed += 1,
+                WorkState::Failed { .. } => status.failed += 1,
+            }
+        }
+        status
+    }
+
+    /// Lease the first eligible task `worker` can run
+    pub fn lease(
+        &mut self,
+        worker: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Lease>, CoordinatorError> {
+        self.reclaim_expired(now)?;
+
+        let leased = self.status().leased;
+        if self.max_leases.is_some_and(|max| leased >= max) {
+            return Ok(None);
+        }
+        let Some(index) = (0..self.items.len())
+            .find(|&index| self.is_eligible(index) && self.can_run(worker, index))
+        else {
+            self.report_unplaceable();
+            return Ok(None);
+        };
+
+        let lease_id = Uuid::new_v4();
+        let expires_at = now + self.lease_duration();
+        let item = &mut self.items[index];
+        item.attempts += 1;
+        item.state = WorkState::Leased {
+            worker: worker.to_string(),
+            lease_id,
+            expires_at,
+        };
+        info!(
+            "Leased '{}' to {} (attempt {})",
+            item.spec.title, worker, item.attempts
+        );
+        let lease = Lease {
+            lease_id,
+            task_id: item.id,
+            spec: item.spec.clone(),
+            attempt: item.attempts,
+            expires_at,
+        };
+
+        self.persist()?;
+        Ok(Some(lease))
+    }
+
+    /// Extend an active lease
+    pub fn renew(
+        &mut self,
+        lease_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, CoordinatorError> {
+        let index = self
+            .active_lease(lease_id, now)
+            .ok_or(CoordinatorError::StaleLease(lease_id))?;
+        let renewed = now + self.lease_duration();
+        if let WorkState::Leased { expires_at, .. } = &mut self.items[index].state {
+            *expires_at = renewed;
+        }
+        self.persist()?;
+        Ok(renewed)
+    }
+
+    /// Record the result of an active lease
+    pub fn complete(
+        &mut self,
+        lease_id: Uuid,
+        result: WorkResult,
+        now: DateTime<Utc>,
+    ) -> Result<(), CoordinatorError> {
+        let index = self
+            .active_lease(lease_id, now)
+            .ok_or(CoordinatorError::StaleLease(lease_id))?;
+        let WorkState::Leased { worker, .. } = self.items[index].state.clone() else {
+            return Err(CoordinatorError::StaleLease(lease_id));
+        };
+
+        if result.success {
+            let item = &mut self.items[index];
+            info!("'{}' completed by {}", item.spec.title, worker);
+            item.state = WorkState::Completed {
+                worker,
+                completed_at: now,
+                files_changed: result.files_changed,
+            };
+        } else {
+            let error = result
+                .error
+                .unwrap_or_else(|| "task failed without an error message".to_string());
+            warn!(
+                "'{}' failed on {}: {}",
+                self.items[index].spec.title, worker, error
+            );
+            self.retry_or_fail(index, error);
+        }
+
+        self.persist()?;
+        Ok(())
+    }
+
+    /// Return expired leases to the queue, or fail tasks out of attempts
+    pub fn reclaim_expired(&mut self, now: DateTime<Utc>) -> Result<(), CoordinatorError> {
+        let expired: Vec<usize> = (0..self.items.len())
+            .filter(|&index| {
+                matches!(&self.items[index].state,
+                    WorkState::Leased { expires_at, .. } if *expires_at <= now)
+            })
+            .collect();
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        for index in expired {
+            if let WorkState::Leased { worker, .. } = &self.items[index].state {
+                let error = format!("lease held by {} expired", worker);
+                warn!("'{}': {}", self.items[index].spec.title, error);
+                self.retry_or_fail(index, error);
+            }
+        }
+        self.persist()
+    }
+
+    /// Answer one worker message
+    pub fn handle(
+        &mut self,
+        message: WorkerMessage,
+        now: DateTime<Utc>,
+    ) -> Result<CoordinatorReply, CoordinatorError> {
+        Ok(match message {
+            WorkerMessage::Join {
+                worker,
+                capabilities,
+            } => {
+                info!("Worker {} joined", worker);
+                self.register(&worker, capabilities);
+                CoordinatorReply::Joined {
+                    setup_commands: self.setup_commands.clone(),
+                    renew_interval_secs: self.config.renew_interval_secs,
+                }
+            }
+            WorkerMessage::Lease { worker } => {
+                let lease = self.lease(&worker, now)?;
+                CoordinatorReply::Lease {
+                    lease: lease.map(Box::new),
+                    finished: self.status().is_finished(),
+                }
+            }
+            WorkerMessage::Renew { lease_id } => CoordinatorReply::Renewed {
+                expires_at: self.renew(lease_id, now)?,
+            },
+            WorkerMessage::Complete { lease_id, result } => {
+                self.complete(lease_id, result, now)?;
+                CoordinatorReply::Ack
+            }
+        })
+    }
+
+    fn lease_duration(&self) -> Duration {
+        Duration::seconds(self.config.lease_timeout_secs as i64)
+    }
+
+    fn is_eligible(&self, index: usize) -> bool {
+        let item = &self.items[index];
+        item.state == WorkState::Pending
+            && item.depends_on.iter().all(|dependency| {
+                self.items.iter().any(|other| {
+                    other.id == *dependency && matches!(other.state, WorkState::Completed { .. })
+                })
+            })
+    }
+
+    fn can_run(&self, worker: &str, index: usize) -> bool {
+        let requirements = &self.items[index].spec.metadata.requirements;
+        match self.workers.get(worker) {
+            Some(capabilities) => capabilities.satisfies(requirements),
+            None => requirements.is_empty(),
+        }
+    }
+
+    /// Warn once about each ready task that no joined worker can run
+    fn report_unplaceable(&mut self) {
+        let workers: Vec<ExecutorCapabilities> = self.workers.values().cloned().collect();
+        for index in 0..self.items.len() {
+            let item = &self.items[index];
+            if !self.is_eligible(index) || self.unplaced.contains(&item.id) {
+                continue;
+            }
+            if let Err(error) = place(&item.spec.title, &item.spec.metadata.requirements, &workers)
+            {
+                warn!("{}; waiting for a capable worker to join", error);
+                self.unplaced.insert(item.id);
+            }
+        }
+    }
+
+    fn active_lease(&self, lease_id: Uuid, now: DateTime<Utc>) -> Option<usize> {
+        self.items.iter().position(|item| {
+            matches!(&item.state,
+                WorkState::Leased { lease_id: id, expires_at, .. } if *id == lease_id && *expires_at > now)
+        })
+    }
+
+    fn retry_or_fail(&mut self, index: usize, error: String) {
+        let item = &mut self.items[index];
+        item.last_error = Some(error.clone());
+        if item.attempts < self.config.max_attempts {
+            item.state = WorkState::Pending;
+            return;
+        }
+
+        item.state = WorkState::Failed { error };
+        // Dependents can never run
+        let mut failed = vec![item.id];
+        while let Some(id) = failed.pop() {
+            let title = self
+                .items
+                .iter()
+                .find(|item| item.id == id)
+                .map(|item| item.spec.title.clone())
+                .unwrap_or_default();
+            for dependent in &mut self.items {
+                if dependent.state == WorkState::Pending && dependent.depends_on.contains(&id) {
+                    dependent.state = WorkState::Failed {
+                        error: format!("dependency '{}' failed", title),
+                    };
+                    failed.push(dependent.id);
+                }
+            }
+        }
+    }
+
+    fn persist(&self) -> Result<(), CoordinatorError> {
+        let Some(path) = &self.state_file else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Write then rename so a crash never leaves a partial snapshot
+        let snapshot = SnapshotRef {
+            plan_fingerprint: &self.fingerprint,
+            items: &self.items,
+        };
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(&snapshot)?)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+/// Serve workers until every task has completed or failed
+pub async fn serve(
+    coordinator: Arc<Mutex<WorkCoordinator>>,
+    listener: TcpListener,
+    token: Option<String>,
+) -> Result<ClusterStatus, CoordinatorError> {
+    let token = Arc::new(token);
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(1));
+    let mut finished_at = None;
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, address) = accepted?;
+                debug!("Worker connected from {}", address);
+                tokio::spawn(handle_connection(stream, coordinator.clone(), token.clone()));
+            }
+            _ = ticker.tick() => {
+                let mut coordinator = coordinator.lock().await;
+                // Reclaim leases of workers that went away
+                coordinator.reclaim_expired(Utc::now())?;
+                let status = coordinator.status();
+                if status.is_finished() {
+                    let since = *finished_at.get_or_insert_with(std::time::Instant::now);
+                    if since.elapsed() >= FINISHED_GRACE_PERIOD {
+                        return Ok(status);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    coordinator: Arc<Mutex<WorkCoordinator>>,
+    token: Arc<Option<String>>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let reply = match serde_json::from_str::<WorkerRequest>(&line) {
+            Ok(request) if !authorized(token.as_deref(), request.token.as_deref()) => {
+                CoordinatorReply::Error {
+                    message: CoordinatorError::Unauthorized.to_string(),
+                }
+            }
+            Ok(request) => coordinator
+                .lock()
+                .await
+                .handle(request.message, Utc::now())
+                .unwrap_or_else(|e| CoordinatorReply::Error {
+                    message: e.to_string(),
+                }),
+            Err(e) => CoordinatorReply::Error {
+                message: format!("Invalid request: {}", e),
+            },
+        };
+
+        let Ok(mut line) = serde_json::to_string(&reply) else {
+            break;
+        };
+        line.push('\n');
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Whether a worker presented the cluster token, when one is required
+fn authorized(expected: Option<&str>, presented: Option<&str>) -> bool {
+    expected.is_none_or(|expected| presented.is_some_and(|token| tokens_equal(token, expected)))
+}
+
+/// Connection from a worker to the coordinator
+pub struct WorkerClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    token: Option<String>,
+}
+
+impl WorkerClient {
+    pub async fn connect(
+        address: impl ToSocketAddrs,
+        token: Option<String>,
+    ) -> Result<Self, CoordinatorError> {
+        let (reader, writer) = TcpStream::connect(address).await?.into_split();
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+            token,
+        })
+    }
+
+    /// Send one message and wait for its reply
+    pub async fn request(
+        &mut self,
+        message: WorkerMessage,
+    ) -> Result<CoordinatorReply, CoordinatorError> {
+        let request = WorkerRequest {
+            token: self.token.clone(),
+            message,
+        };
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+
+        let Some(line) = self.lines.next_line().await? else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "coordinator closed the connection",
+            )
+            .into());
+        };
+        match serde_json::from_str(&line)? {
+            CoordinatorReply::Error { message } => Err(CoordinatorError::Remote(message)),
+            reply => Ok(reply),
+        }
+    }
+
+    /// Register with the coordinator; returns the plan's setup commands and
+    /// the lease renewal interval
+    pub async fn join(
+        &mut self,
+        worker: &str,
+        capabilities: ExecutorCapabilities,
+    ) -> Result<(Vec<SetupCommand>, std::time::Duration), CoordinatorError> {
+        match self
+            .request(WorkerMessage::Join {
+                worker: worker.to_string(),
+                capabilities: Some(capabilities),
+            })
+            .await?
+        {
+            CoordinatorReply::Joined {
+                setup_commands,
+                renew_interval_secs,
+            } => Ok((
+                setup_commands,
+                std::time::Duration::from_secs(renew_interval_secs.max(1)),
+            )),
+            other => Err(CoordinatorError::UnexpectedReply(format!("{:?}", other))),
+        }
+    }
+}
+
+/// Tasks a worker ran before the coordinator finished
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerSummary {
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// Run leased tasks on `agent` until the coordinator reports the plan finished
+pub async fn run_worker(
+    agent: &AgentSystem,
+    client: WorkerClient,
+    worker: &str,
+    renew_interval: std::time::Duration,
+) -> Result<WorkerSummary, CoordinatorError> {
+    let client = Arc::new(Mutex::new(client));
+    let mut summary = WorkerSummary::default();
+
+    loop {
+        let reply = client
+            .lock()
+            .await
+            .request(WorkerMessage::Lease {
+                worker: worker.to_string(),
+            })
+            .await?;
+        let lease = match reply {
+            CoordinatorReply::Lease {
+                lease: Some(lease), ..
+            } => lease,
+            CoordinatorReply::Lease {
+                lease: None,
+                finished: true,
+            } => break,
+            CoordinatorReply::Lease { lease: None, .. } => {
+                tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+                continue;
+            }
+            other => return Err(CoordinatorError::UnexpectedReply(format!("{:?}", other))),
+        };
+
+        info!("Running '{}' (attempt {})", lease.spec.title, lease.attempt);
+        let (stop, renewal) = spawn_renewal(client.clone(), lease.lease_id, renew_interval);
+        let title = lease.spec.title.clone();
+        let result = execute(agent, lease.spec).await;
+        let _ = stop.send(());
+        let _ = renewal.await;
+
+        if result.success {
+            summary.completed += 1;
+        } else {
+            summary.failed += 1;
+        }
+        match client
+            .lock()
+            .await
+            .request(WorkerMessage::Complete {
+                lease_id: lease.lease_id,
+                result,
+            })
+            .await
+        {
+            Ok(_) => {}
+            Err(CoordinatorError::Remote(message)) => {
+                warn!("Result for '{}' was not accepted: {}", title, message)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Renew a lease periodically until stopped; renewals are never cancelled
+/// mid-request so replies stay in step with requests
+fn spawn_renewal(
+    client: Arc<Mutex<WorkerClient>>,
+    lease_id: Uuid,
+    interval: std::time::Duration,
+) -> (oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+    let (stop, mut stopped) = oneshot::channel();
+    let handle = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                _ = ticker.tick() => {}
+            }
+            let renewed = client
+                .lock()
+                .await
+                .request(WorkerMessage::Renew { lease_id })
+                .await;
+            if let Err(e) = renewed {
+                warn!("Failed to renew lease {}: {}", lease_id, e);
+            }
+        }
+    });
+    (stop, handle)
+}
+
+/// Run one task on the worker's own agent system
+async fn execute(agent: &AgentSystem, spec: TaskSpec) -> WorkResult {
+    let task_manager = agent.task_manager();
+    let task_id = match task_manager.create_task(spec, None).await {
+        Ok(task_id) => task_id,
+        Err(e) => return WorkResult::failed(format!("{:#}", e)),
+    };
+    if let Err(e) = agent.process_task(task_id).await {
+        return WorkResult::failed(format!("{:#}", e));
+    }
+
+    match task_manager.get_task(task_id).await.map(|task| task.status) {
+        Ok(TaskStatus::Completed { result, .. }) => WorkResult {
+            success: true,
+            files_changed: changed_files(&result),
+            error: None,
+        },
+        Ok(TaskStatus::Failed { error, .. }) => WorkResult::failed(error.to_string()),
+        Ok(status) => WorkResult::failed(format!("task ended as {:?}", status)),
+        Err(e) => WorkResult::failed(format!("{:#}", e)),
+    }
+}
+
+fn changed_files(result: &crate::task::TaskResult) -> Vec<PathBuf> {
+    match result {
+        crate::task::TaskResult::Success {
+            files_created,
+            files_modified,
+            ..
+        } => files_created
+            .iter()
+            .chain(files_modified)
+            .cloned()
+            .collect(),
+        crate::task::TaskResult::Partial { files_modified, .. } => files_modified.clone(),
+    }
+}
+
+/// Default worker name: host name and process id
+pub fn default_worker_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|name| name.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "worker".to_string());
+    format!("{}-{}", host, std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(titles: &[&str], execution_mode: ExecutionMode) -> ExecutionPlan {
+        let mut plan = ExecutionPlan::new().with_execution_mode(execution_mode);
+        for title in titles {
+            plan = plan.with_task(TaskSpec {
+                title: title.to_string(),
+                description: format!("Do {}", title),
+                ..Default::default()
+            });
+        }
+        plan
+    }
+
+    fn success() -> WorkResult {
+        WorkResult {
+            success: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_workers_pull_eligible_tasks() {
+        let now = Utc::now();
+        let parallel = ExecutionMode::Parallel {
+            max_concurrent: Some(2),
+        };
+        let mut coordinator =
+            WorkCoordinator::new(&plan(&["a", "b", "c"], parallel), Default::default());
+
+        let first = coordinator.lease("w1", now).unwrap().unwrap();
+        let second = coordinator.lease("w2", now).unwrap().unwrap();
+        assert_eq!(first.spec.title, "a");
+        assert_eq!(second.spec.title, "b");
+        // max_concurrent caps simultaneous leases
+        assert!(coordinator.lease("w3", now).unwrap().is_none());
+
+        // The first worker to finish takes the remaining task
+        coordinator
+            .complete(second.lease_id, success(), now)
+            .unwrap();
+        let third = coordinator.lease("w2", now).unwrap().unwrap();
+        assert_eq!(third.spec.title, "c");
+
+        // A sequential plan leases one task at a time, in order
+        let mut sequential = WorkCoordinator::new(
+            &plan(&["a", "b"], ExecutionMode::Sequential),
+            Default::default(),
+        );
+        let lease = sequential.lease("w1", now).unwrap().unwrap();
+        assert!(sequential.lease("w2", now).unwrap().is_none());
+        sequential.complete(lease.lease_id, success(), now).unwrap();
+        assert_eq!(
+            sequential.lease("w2", now).unwrap().unwrap().spec.title,
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_expired_leases_and_failures() {
+        let now = Utc::now();
+        let config = DistributedConfig {
+            lease_timeout_secs: 60,
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let mut coordinator =
+            WorkCoordinator::new(&plan(&["a", "b"], ExecutionMode::Sequential), config);
+
+        // The lease expires and the task moves to another worker
+        let stale = coordinator.lease("w1", now).unwrap().unwrap();
+        let later = now + Duration::seconds(61);
+        let retried = coordinator.lease("w2", later).unwrap().unwrap();
+        assert_eq!(retried.task_id, stale.task_id);
+        assert_eq!(retried.attempt, 2);
+        assert!(matches!(
+            coordinator.complete(stale.lease_id, success(), later),
+            Err(CoordinatorError::StaleLease(_))
+        ));
+        assert!(coordinator.renew(retried.lease_id, later).is_ok());
+
+        // Out of attempts: the task and its dependent fail
+        coordinator
+            .complete(retried.lease_id, WorkResult::failed("boom"), later)
+            .unwrap();
+        let status = coordinator.status();
+        assert_eq!(status.failed, 2);
+        assert!(status.is_finished());
+        assert_eq!(
+            coordinator.items()[1].state,
+            WorkState::Failed {
+                error: "dependency 'a' failed".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_leases_match_worker_capabilities() {
+        let now = Utc::now();
+        let parallel = ExecutionMode::Parallel {
+            max_concurrent: None,
+        };
+        let mut tasks = plan(&["train", "lint"], parallel);
+        tasks.task_specs[0].metadata.requirements.gpu = true;
+        let mut coordinator = WorkCoordinator::new(&tasks, Default::default());
+
+        let cpu = ExecutorCapabilities::host(&crate::executor::PlacementConfig {
+            gpu: Some(false),
+            ..Default::default()
+        });
+        let gpu = ExecutorCapabilities {
+            gpu: true,
+            ..cpu.clone()
+        };
+        coordinator.register("cpu", Some(cpu));
+        coordinator.register("gpu", Some(gpu));
+
+        // The CPU worker skips the GPU task
+        let lint = coordinator.lease("cpu", now).unwrap().unwrap();
+        assert_eq!(lint.spec.title, "lint");
+        assert!(coordinator.lease("cpu", now).unwrap().is_none());
+        assert!(coordinator.unplaced.is_empty());
+
+        let train = coordinator.lease("gpu", now).unwrap().unwrap();
+        assert_eq!(train.spec.title, "train");
+
+        // Workers that advertise nothing only take unconstrained tasks
+        let mut coordinator = WorkCoordinator::new(&tasks, Default::default());
+        assert_eq!(
+            coordinator.lease("old", now).unwrap().unwrap().spec.title,
+            "lint"
+        );
+        assert!(coordinator.lease("old", now).unwrap().is_none());
+        assert_eq!(coordinator.unplaced.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_state_snapshot_and_protocol() {
+        let workspace = tempfile::tempdir().unwrap();
+        let state_file = crate::env::coordinator_state_file_path(workspace.path());
+        let parallel = ExecutionMode::Parallel {
+            max_concurrent: None,
+        };
+        let tasks = plan(&["a", "b"], parallel);
+
+        let coordinator = WorkCoordinator::new(&tasks, Default::default())
+            .with_state_file(state_file.clone())
+            .unwrap();
+        let coordinator = Arc::new(Mutex::new(coordinator));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(
+            coordinator.clone(),
+            listener,
+            Some("secret".to_string()),
+        ));
+
+        for token in [None, Some("secreT".to_string())] {
+            let mut intruder = WorkerClient::connect(address, token).await.unwrap();
+            assert!(matches!(
+                intruder
+                    .join("intruder", ExecutorCapabilities::host(&Default::default()))
+                    .await,
+                Err(CoordinatorError::Remote(_))
+            ));
+        }
+
+        let mut client = WorkerClient::connect(address, Some("secret".to_string()))
+            .await
+            .unwrap();
+        client
+            .join("w1", ExecutorCapabilities::host(&Default::default()))
+            .await
+            .unwrap();
+        let CoordinatorReply::Lease {
+            lease: Some(lease), ..
+        } = client
+            .request(WorkerMessage::Lease {
+                worker: "w1".to_string(),
+            })
+            .await
+            .unwrap()
+        else {
+            panic!("Expected a lease");
+        };
+        client
+            .request(WorkerMessage::Complete {
+                lease_id: lease.lease_id,
+                result: success(),
+            })
+            .await
+            .unwrap();
+        server.abort();
+
+        // A restarted coordinator resumes from the snapshot
+        let resumed = WorkCoordinator::new(&tasks, Default::default())
+            .with_state_file(state_file)
+            .unwrap();
+        assert_eq!(resumed.status().completed, 1);
+        assert_eq!(resumed.status().pending, 1);
+    }
+}
@@ -0,0 +1,474 @@
+//! Tonic implementation of the daemon's gRPC interface.
+//!
+//! [`DaemonService`] serves `proto/aca/daemon/v1/daemon.proto` on top of a
+//! [`TenantRegistry`]: each call authenticates its `authorization: Bearer`
+//! token and then runs the matching [`AuthContext`] operation, so roles,
+//! budgets, concurrency limits and the approval journal behave exactly as
+//! they do for any other front end. Built only with the `grpc` feature.
+
+use super::approval::Submission;
+use super::auth::{AuthContext, Scope};
+use super::tenant::{TenantError, TenantRegistry};
+use crate::session::{EventJournal, JournalEntry};
+use crate::task::{ExecutionPlan, TaskStatus};
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+/// Types generated from `daemon.proto`
+pub mod proto {
+    tonic::include_proto!("aca.daemon.v1");
+}
+
+use proto::daemon_server::{Daemon, DaemonServer};
+
+/// How often `StreamEvents` checks the journal for new entries
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// gRPC front end for a set of tenants
+#[derive(Debug, Clone)]
+pub struct DaemonService {
+    registry: Arc<TenantRegistry>,
+    poll_interval: Duration,
+}
+
+impl DaemonService {
+    pub fn new(registry: Arc<TenantRegistry>) -> Self {
+        Self {
+            registry,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Set how often event streams poll the journal
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Wrap the service for a tonic [`Server`](tonic::transport::Server)
+    pub fn into_server(self) -> DaemonServer<Self> {
+        DaemonServer::new(self)
+    }
+
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<AuthContext, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+        Ok(self.registry.authenticate(token.trim())?)
+    }
+}
+
+/// Serve the registry's tenants over gRPC until the server fails
+pub async fn serve(registry: Arc<TenantRegistry>, addr: SocketAddr) -> anyhow::Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(DaemonService::new(registry).into_server())
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+impl From<TenantError> for Status {
+    fn from(error: TenantError) -> Self {
+        let message = error.to_string();
+        match error {
+            TenantError::Unauthorized => Status::unauthenticated(message),
+            TenantError::Forbidden { .. } => Status::permission_denied(message),
+            TenantError::UnknownTenant(_) | TenantError::UnknownApproval(_) => {
+                Status::not_found(message)
+            }
+            TenantError::InvalidConfig(_) => Status::failed_precondition(message),
+            TenantError::BudgetExceeded { .. } | TenantError::ConcurrencyLimit { .. } => {
+                Status::resource_exhausted(message)
+            }
+            TenantError::Agent(_) => Status::internal(message),
+        }
+    }
+}
+
+fn parse_id(field: &str, value: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(value)
+        .map_err(|e| Status::invalid_argument(format!("Invalid {}: {}", field, e)))
+}
+
+fn timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    SystemTime::from(time).into()
+}
+
+fn task_ids(ids: Vec<Uuid>) -> Vec<String> {
+    ids.into_iter().map(|id| id.to_string()).collect()
+}
+
+/// Protobuf state and detail text for a task status
+fn task_state(status: &TaskStatus) -> (proto::TaskState, String) {
+    use proto::TaskState;
+    match status {
+        TaskStatus::Pending => (TaskState::Pending, String::new()),
+        TaskStatus::InProgress { .. } => (TaskState::InProgress, String::new()),
+        TaskStatus::Blocked { reason, .. } => (TaskState::Blocked, reason.to_string()),
+        TaskStatus::Completed { .. } => (TaskState::Completed, String::new()),
+        TaskStatus::Failed { error, .. } => (TaskState::Failed, error.to_string()),
+        TaskStatus::Skipped { reason, .. } => (TaskState::Skipped, reason.clone()),
+        TaskStatus::Paused { reason, .. } => (TaskState::Paused, reason.clone()),
+        TaskStatus::AwaitingInput { question, .. } => {
+            (TaskState::AwaitingInput, question.question.clone())
+        }
+    }
+}
+
+fn event(entry: &JournalEntry) -> Result<proto::Event, Status> {
+    let json = serde_json::to_value(entry).map_err(|e| Status::internal(e.to_string()))?;
+    Ok(proto::Event {
+        timestamp: Some(timestamp(entry.timestamp)),
+        r#type: json["type"].as_str().unwrap_or_default().to_string(),
+        event_json: json.to_string(),
+    })
+}
+
+/// Poll the journal and yield each entry newer than `since` once
+fn journal_events(
+    journal: EventJournal,
+    since: Option<DateTime<Utc>>,
+    poll_interval: Duration,
+) -> impl Stream<Item = Result<proto::Event, Status>> + Send + 'static {
+    let state = (journal, 0usize, VecDeque::<JournalEntry>::new());
+    futures::stream::unfold(state, move |(journal, mut seen, mut buffered)| async move {
+        loop {
+            if let Some(entry) = buffered.pop_front() {
+                return Some((event(&entry), (journal, seen, buffered)));
+            }
+            let entries = match journal.load() {
+                Ok(entries) => entries,
+                Err(e) => {
+                    let status = Status::internal(e.to_string());
+                    return Some((Err(status), (journal, seen, buffered)));
+                }
+            };
+            if entries.len() > seen {
+                buffered.extend(
+                    entries[seen..]
+                        .iter()
+                        .filter(|entry| since.is_none_or(|since| entry.timestamp > since))
+                        .cloned(),
+                );
+                seen = entries.len();
+            } else {
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+    })
+}
+
+#[tonic::async_trait]
+impl Daemon for DaemonService {
+    async fn submit_plan(
+        &self,
+        request: Request<proto::SubmitPlanRequest>,
+    ) -> Result<Response<proto::SubmitPlanResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        let plan: ExecutionPlan = serde_json::from_str(&request.get_ref().plan_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid plan: {}", e)))?;
+
+        let outcome = match auth.submit_plan(plan).await? {
+            Submission::Queued { approval_id } => {
+                proto::submit_plan_response::Outcome::ApprovalId(approval_id.to_string())
+            }
+            Submission::Completed { task_ids: ids } => {
+                proto::submit_plan_response::Outcome::Completed(proto::TaskIds {
+                    task_ids: task_ids(ids),
+                })
+            }
+        };
+        Ok(Response::new(proto::SubmitPlanResponse {
+            outcome: Some(outcome),
+        }))
+    }
+
+    type StreamEventsStream =
+        Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send + 'static>>;
+
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let auth = self.authenticate(&request)?;
+        auth.require(Scope::Read)?;
+        let since = request
+            .into_inner()
+            .since
+            .map(|since| {
+                SystemTime::try_from(since)
+                    .map(DateTime::<Utc>::from)
+                    .map_err(|e| Status::invalid_argument(format!("Invalid since: {}", e)))
+            })
+            .transpose()?;
+
+        let journal = auth.tenant.journal().clone();
+        Ok(Response::new(Box::pin(journal_events(
+            journal,
+            since,
+            self.poll_interval,
+        ))))
+    }
+
+    async fn get_task(
+        &self,
+        request: Request<proto::GetTaskRequest>,
+    ) -> Result<Response<proto::Task>, Status> {
+        let auth = self.authenticate(&request)?;
+        auth.require(Scope::Read)?;
+        let task_id = parse_id("task_id", &request.get_ref().task_id)?;
+
+        let agent = auth.tenant.agent().await?;
+        let task = agent
+            .task_manager()
+            .get_task(task_id)
+            .await
+            .map_err(|e| Status::not_found(e.to_string()))?;
+        let (state, detail) = task_state(&task.status);
+        let task_json =
+            serde_json::to_string(&task).map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(proto::Task {
+            id: task.id.to_string(),
+            title: task.title,
+            state: state.into(),
+            detail,
+            created_at: Some(timestamp(task.created_at)),
+            updated_at: Some(timestamp(task.updated_at)),
+            task_json,
+        }))
+    }
+
+    async fn cancel(
+        &self,
+        request: Request<proto::CancelRequest>,
+    ) -> Result<Response<proto::CancelResponse>, Status> {
+        use proto::cancel_request::Target;
+
+        let auth = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let reason = Some(request.reason).filter(|reason| !reason.is_empty());
+
+        match request.target {
+            Some(Target::ApprovalId(id)) => {
+                auth.cancel_approval(parse_id("approval_id", &id)?, reason)?;
+            }
+            Some(Target::TaskId(id)) => {
+                auth.require(Scope::Submit)?;
+                let task_id = parse_id("task_id", &id)?;
+                let agent = auth.tenant.agent().await?;
+                let reason = reason.unwrap_or_else(|| format!("Cancelled by {}", auth.principal));
+                agent
+                    .cancel_task(task_id, &reason)
+                    .await
+                    .map_err(|e| Status::failed_precondition(e.to_string()))?;
+            }
+            None => return Err(Status::invalid_argument("Missing cancel target")),
+        }
+        Ok(Response::new(proto::CancelResponse {}))
+    }
+
+    async fn approve(
+        &self,
+        request: Request<proto::ApproveRequest>,
+    ) -> Result<Response<proto::ApproveResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let approval_id = parse_id("approval_id", &request.approval_id)?;
+
+        let ids = if request.reject {
+            let reason = Some(request.reason).filter(|reason| !reason.is_empty());
+            auth.reject(approval_id, reason)?;
+            Vec::new()
+        } else {
+            auth.approve(approval_id).await?
+        };
+        Ok(Response::new(proto::ApproveResponse {
+            task_ids: task_ids(ids),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::{ApiTokenConfig, TenantConfig, TenantRole};
+    use futures::StreamExt;
+    use proto::daemon_client::DaemonClient;
+    use tempfile::TempDir;
+    use tonic::transport::Channel;
+
+    fn with_token<T>(message: T, token: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        request
+    }
+
+    async fn start(registry: TenantRegistry) -> DaemonClient<Channel> {
+        let incoming =
+            tonic::transport::server::TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = incoming.local_addr().unwrap();
+        let service =
+            DaemonService::new(Arc::new(registry)).with_poll_interval(Duration::from_millis(20));
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(service.into_server())
+                .serve_with_incoming(incoming),
+        );
+        DaemonClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_submit_stream_and_approve() {
+        let temp = TempDir::new().unwrap();
+        let mut registry = TenantRegistry::default();
+        registry
+            .register(TenantConfig {
+                id: "alpha".to_string(),
+                workspace: temp.path().to_path_buf(),
+                api_token: Some("owner-token".to_string()),
+                api_token_env: None,
+                tokens: vec![ApiTokenConfig {
+                    name: "dashboard".to_string(),
+                    api_token: Some("viewer-token".to_string()),
+                    api_token_env: None,
+                    role: TenantRole::Viewer,
+                }],
+                require_approval: true,
+                agent_config: None,
+                max_concurrent_tasks: 1,
+                budget_usd: None,
+            })
+            .unwrap();
+        let mut client = start(registry).await;
+
+        let plan_json = serde_json::to_string(&ExecutionPlan::new()).unwrap();
+        let bad = client
+            .submit_plan(with_token(
+                proto::SubmitPlanRequest {
+                    plan_json: plan_json.clone(),
+                },
+                "wrong-token",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(bad.code(), tonic::Code::Unauthenticated);
+
+        let response = client
+            .submit_plan(with_token(
+                proto::SubmitPlanRequest { plan_json },
+                "owner-token",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let Some(proto::submit_plan_response::Outcome::ApprovalId(approval_id)) = response.outcome
+        else {
+            panic!("plan should be queued for approval");
+        };
+
+        let mut events = client
+            .stream_events(with_token(
+                proto::StreamEventsRequest { since: None },
+                "viewer-token",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let requested = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(requested.r#type, "approval_requested");
+        assert!(requested.event_json.contains(&approval_id));
+
+        let forbidden = client
+            .approve(with_token(
+                proto::ApproveRequest {
+                    approval_id: approval_id.clone(),
+                    reject: false,
+                    reason: String::new(),
+                },
+                "viewer-token",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(forbidden.code(), tonic::Code::PermissionDenied);
+
+        let rejected = client
+            .approve(with_token(
+                proto::ApproveRequest {
+                    approval_id,
+                    reject: true,
+                    reason: "not now".to_string(),
+                },
+                "owner-token",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(rejected.task_ids.is_empty());
+
+        let decision = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(decision.r#type, "approval_rejected");
+
+        // A withdrawn plan can no longer be approved
+        let plan_json = serde_json::to_string(&ExecutionPlan::new()).unwrap();
+        let Some(proto::submit_plan_response::Outcome::ApprovalId(approval_id)) = client
+            .submit_plan(with_token(
+                proto::SubmitPlanRequest { plan_json },
+                "owner-token",
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .outcome
+        else {
+            panic!("plan should be queued for approval");
+        };
+        client
+            .cancel(with_token(
+                proto::CancelRequest {
+                    target: Some(proto::cancel_request::Target::ApprovalId(
+                        approval_id.clone(),
+                    )),
+                    reason: String::new(),
+                },
+                "owner-token",
+            ))
+            .await
+            .unwrap();
+        let gone = client
+            .approve(with_token(
+                proto::ApproveRequest {
+                    approval_id,
+                    reject: false,
+                    reason: String::new(),
+                },
+                "owner-token",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(gone.code(), tonic::Code::NotFound);
+    }
+}
@@ -0,0 +1,81 @@
+//! # Multi-Tenant Daemon Core
+//!
+//! Shared engine for running aca as a small team service: several registered
+//! project workspaces, each with its own [`AgentSystem`](crate::AgentSystem),
+//! API token, budget and concurrency limit.
+//!
+//! ## Core Components
+//!
+//! - **[`TenantRegistry`]**: Registered projects and token authentication
+//! - **[`Tenant`]**: One project with its lazily created agent system
+//! - **[`DaemonConfig`]**: TOML configuration listing the tenants
+//! - **[`AuthContext`]**: Authenticated caller with a [`TenantRole`]
+//! - **[`ApprovalQueue`]**: Plans waiting for an admin's decision
+//! - **[`WorkCoordinator`]**: Leases one plan's tasks to distributed workers
+//!
+//! ## Isolation
+//!
+//! Every tenant runs against its own workspace, so session state, checkpoints,
+//! logs and the cost ledger all live under that workspace's `.aca` directory.
+//! Registration rejects duplicate or nested workspaces so two tenants can never
+//! share session storage.
+//!
+//! A network front end authenticates each request with
+//! [`TenantRegistry::authenticate`] and then submits work through
+//! [`AuthContext::submit_plan`], which enforces the tenant's budget and
+//! concurrency limit.
+//!
+//! ## Roles and Approval
+//!
+//! Each token carries a role: admins may approve, operators may submit and
+//! viewers are read-only. Tenants with `require_approval` queue submitted
+//! plans until an admin approves them; every request, approval and rejection
+//! is recorded with the acting principal in the workspace event journal.
+//!
+//! ## gRPC Interface
+//!
+//! `proto/aca/daemon/v1/daemon.proto` defines the typed RPC surface for
+//! integrations (`SubmitPlan`, `StreamEvents`, `GetTask`, `Cancel`,
+//! `Approve`). Each RPC authenticates its bearer token through
+//! [`TenantRegistry::authenticate`] and maps onto the [`AuthContext`]
+//! operations above; plans and journal entries travel as JSON so the schema
+//! stays stable as [`ExecutionPlan`](crate::task::ExecutionPlan) evolves.
+//! With the `grpc` feature, [`grpc::DaemonService`] implements the service
+//! with tonic and [`grpc::serve`] runs it for a registry; `aca serve` listens
+//! on `--grpc-listen`, the config's `grpc_listen` or [`DEFAULT_GRPC_ADDR`].
+//!
+//! ## Distributed Workers
+//!
+//! For very large plans, `aca coordinator` serves one plan to any number of
+//! `aca worker` processes through [`WorkCoordinator`]. Workers lease tasks,
+//! renew the lease while they run and submit the result; see [`distributed`]
+//! for the protocol and lease rules.
+//!
+//! ## Example Configuration
+//!
+//! ```toml
+//! [[tenants]]
+//! id = "platform"
+//! workspace = "/srv/repos/platform"
+//! api_token_env = "ACA_TOKEN_PLATFORM"
+//! max_concurrent_tasks = 2
+//! budget_usd = 50.0
+//! require_approval = true
+//!
+//! [[tenants.tokens]]
+//! name = "dashboard"
+//! api_token_env = "ACA_TOKEN_PLATFORM_VIEWER"
+//! role = "viewer"
+//! ```
+
+pub mod approval;
+pub mod auth;
+pub mod distributed;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod tenant;
+
+pub use approval::*;
+pub use auth::*;
+pub use distributed::*;
+pub use tenant::*;
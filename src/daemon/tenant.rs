@@ -0,0 +1,623 @@
+use super::approval::ApprovalQueue;
+use super::auth::{ApiTokenConfig, AuthContext, Scope, TenantRole, tokens_equal};
+use crate::integration::{AgentConfig, AgentSystem};
+use crate::session::{CostLedger, EventJournal};
+use crate::task::ExecutionPlan;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{OnceCell, OwnedSemaphorePermit, Semaphore};
+use tracing::info;
+use uuid::Uuid;
+
+/// Errors raised by tenant registration, authentication and admission
+#[derive(Debug, thiserror::Error)]
+pub enum TenantError {
+    /// No tenant matches the presented API token
+    #[error("Invalid API token")]
+    Unauthorized,
+
+    /// Token is valid but its role does not grant the operation
+    #[error("'{principal}' ({}) may not {scope:?}", role.as_str())]
+    Forbidden {
+        principal: String,
+        role: TenantRole,
+        scope: Scope,
+    },
+
+    /// Tenant id is not registered
+    #[error("Unknown tenant: {0}")]
+    UnknownTenant(String),
+
+    /// No pending approval with this id
+    #[error("Unknown approval: {0}")]
+    UnknownApproval(Uuid),
+
+    /// Tenant configuration is invalid
+    #[error("Invalid tenant configuration: {0}")]
+    InvalidConfig(String),
+
+    /// Tenant has spent its budget
+    #[error("Tenant '{tenant}' exceeded its budget (${spent:.2} of ${budget:.2})")]
+    BudgetExceeded {
+        tenant: String,
+        spent: f64,
+        budget: f64,
+    },
+
+    /// Tenant is already running its maximum number of tasks
+    #[error("Tenant '{tenant}' is at its concurrency limit ({limit})")]
+    ConcurrencyLimit { tenant: String, limit: usize },
+
+    /// Agent system failure while serving a tenant
+    #[error(transparent)]
+    Agent(#[from] anyhow::Error),
+}
+
+/// Registration of one project workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Unique tenant identifier
+    pub id: String,
+    /// Project workspace; all session storage lives below it
+    pub workspace: PathBuf,
+    /// Owner API token (admin role)
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// Environment variable holding the owner token (preferred over `api_token`)
+    #[serde(default)]
+    pub api_token_env: Option<String>,
+    /// Additional named tokens with their roles
+    #[serde(default)]
+    pub tokens: Vec<ApiTokenConfig>,
+    /// Queue submitted plans until an admin approves them
+    #[serde(default)]
+    pub require_approval: bool,
+    /// Agent configuration file for this tenant (workspace is always overridden)
+    #[serde(default)]
+    pub agent_config: Option<PathBuf>,
+    /// Maximum plans running at once for this tenant
+    #[serde(default = "default_max_concurrent_tasks")]
+    pub max_concurrent_tasks: usize,
+    /// Total spend allowed for the workspace, from its cost ledger
+    #[serde(default)]
+    pub budget_usd: Option<f64>,
+}
+
+fn default_max_concurrent_tasks() -> usize {
+    1
+}
+
+/// Principal name used for the tenant's owner token
+pub const OWNER_PRINCIPAL: &str = "owner";
+
+/// Token with the principal and role it authenticates as
+#[derive(Debug, Clone)]
+struct ResolvedToken {
+    principal: String,
+    token: String,
+    role: TenantRole,
+}
+
+impl TenantConfig {
+    /// Resolve all of the tenant's tokens from the environment or inline values
+    fn resolve_tokens(&self) -> Result<Vec<ResolvedToken>, TenantError> {
+        let mut tokens = Vec::new();
+
+        if self.api_token.is_some() || self.api_token_env.is_some() {
+            tokens.push(ResolvedToken {
+                principal: OWNER_PRINCIPAL.to_string(),
+                token: resolve_token(
+                    &self.id,
+                    OWNER_PRINCIPAL,
+                    &self.api_token,
+                    &self.api_token_env,
+                )?,
+                role: TenantRole::Admin,
+            });
+        }
+
+        for token in &self.tokens {
+            tokens.push(ResolvedToken {
+                principal: token.name.clone(),
+                token: resolve_token(
+                    &self.id,
+                    &token.name,
+                    &token.api_token,
+                    &token.api_token_env,
+                )?,
+                role: token.role,
+            });
+        }
+
+        if tokens.is_empty() {
+            return Err(TenantError::InvalidConfig(format!(
+                "tenant '{}' has no API token",
+                self.id
+            )));
+        }
+        Ok(tokens)
+    }
+}
+
+fn resolve_token(
+    tenant: &str,
+    principal: &str,
+    inline: &Option<String>,
+    env: &Option<String>,
+) -> Result<String, TenantError> {
+    if let Some(var) = env {
+        return std::env::var(var).map_err(|_| {
+            TenantError::InvalidConfig(format!(
+                "tenant '{}' token '{}': environment variable {} is not set",
+                tenant, principal, var
+            ))
+        });
+    }
+
+    inline
+        .clone()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| {
+            TenantError::InvalidConfig(format!(
+                "tenant '{}' token '{}' is empty",
+                tenant, principal
+            ))
+        })
+}
+
+/// Address `aca serve` accepts gRPC clients on by default
+pub const DEFAULT_GRPC_ADDR: &str = "127.0.0.1:7980";
+
+/// Daemon configuration listing all tenants
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Address the gRPC interface listens on; [`DEFAULT_GRPC_ADDR`] when unset
+    #[serde(default)]
+    pub grpc_listen: Option<String>,
+    #[serde(default)]
+    pub tenants: Vec<TenantConfig>,
+}
+
+impl DaemonConfig {
+    /// Load configuration from a TOML file
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// A registered project served by the daemon
+pub struct Tenant {
+    config: TenantConfig,
+    tokens: Vec<ResolvedToken>,
+    slots: Arc<Semaphore>,
+    agent: OnceCell<Arc<AgentSystem>>,
+    approvals: ApprovalQueue,
+    journal: EventJournal,
+}
+
+impl std::fmt::Debug for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tenant")
+            .field("id", &self.config.id)
+            .field("workspace", &self.config.workspace)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Tenant {
+    fn new(config: TenantConfig) -> Result<Self, TenantError> {
+        let tokens = config.resolve_tokens()?;
+        let slots = Arc::new(Semaphore::new(config.max_concurrent_tasks.max(1)));
+        let journal = EventJournal::for_workspace(&config.workspace);
+        Ok(Self {
+            config,
+            tokens,
+            slots,
+            agent: OnceCell::new(),
+            approvals: ApprovalQueue::default(),
+            journal,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    pub fn workspace(&self) -> &Path {
+        &self.config.workspace
+    }
+
+    pub fn config(&self) -> &TenantConfig {
+        &self.config
+    }
+
+    pub fn journal(&self) -> &EventJournal {
+        &self.journal
+    }
+
+    pub fn approvals(&self) -> &ApprovalQueue {
+        &self.approvals
+    }
+
+    /// Total spend recorded in the tenant's workspace cost ledger
+    pub fn spent_usd(&self) -> Result<f64, TenantError> {
+        let entries = CostLedger::for_workspace(&self.config.workspace).load()?;
+        Ok(entries.iter().map(|entry| entry.cost).sum())
+    }
+
+    /// Fail if the tenant has exhausted its budget
+    pub fn check_budget(&self) -> Result<(), TenantError> {
+        let Some(budget) = self.config.budget_usd else {
+            return Ok(());
+        };
+
+        let spent = self.spent_usd()?;
+        if spent >= budget {
+            return Err(TenantError::BudgetExceeded {
+                tenant: self.config.id.clone(),
+                spent,
+                budget,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reserve a concurrency slot without waiting
+    pub fn try_acquire_slot(&self) -> Result<OwnedSemaphorePermit, TenantError> {
+        self.slots
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| TenantError::ConcurrencyLimit {
+                tenant: self.config.id.clone(),
+                limit: self.config.max_concurrent_tasks.max(1),
+            })
+    }
+
+    /// Agent configuration pinned to the tenant's workspace
+    pub fn agent_config(&self) -> Result<AgentConfig, TenantError> {
+        let mut config = match &self.config.agent_config {
+            Some(path) => AgentConfig::from_toml_file(path)?,
+            None => AgentConfig::default(),
+        };
+        config.workspace_path = self.config.workspace.clone();
+        Ok(config)
+    }
+
+    /// The tenant's agent system, created on first use
+    pub async fn agent(&self) -> Result<Arc<AgentSystem>, TenantError> {
+        let agent = self
+            .agent
+            .get_or_try_init(|| async {
+                info!("Starting agent system for tenant '{}'", self.config.id);
+                let agent = AgentSystem::new(self.agent_config()?).await?;
+                Ok::<_, TenantError>(Arc::new(agent))
+            })
+            .await?;
+        Ok(agent.clone())
+    }
+
+    /// Run a plan after enforcing the tenant's budget and concurrency limit
+    pub async fn execute_plan(&self, plan: ExecutionPlan) -> Result<Vec<Uuid>, TenantError> {
+        self.check_budget()?;
+        let slot = self.try_acquire_slot()?;
+        self.execute_plan_in_slot(plan, slot).await
+    }
+
+    /// Run a plan in a slot reserved with [`Self::try_acquire_slot`]
+    pub async fn execute_plan_in_slot(
+        &self,
+        plan: ExecutionPlan,
+        _slot: OwnedSemaphorePermit,
+    ) -> Result<Vec<Uuid>, TenantError> {
+        let agent = self.agent().await?;
+        Ok(agent.execute_plan(plan).await?)
+    }
+
+    /// Shut down the tenant's agent system if it was started
+    pub async fn shutdown(&self) -> Result<(), TenantError> {
+        if let Some(agent) = self.agent.get() {
+            agent.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Registered tenants keyed by id
+#[derive(Debug, Default)]
+pub struct TenantRegistry {
+    tenants: HashMap<String, Arc<Tenant>>,
+}
+
+impl TenantRegistry {
+    /// Build a registry, validating ids, tokens and workspace isolation
+    pub fn from_config(config: DaemonConfig) -> Result<Self, TenantError> {
+        let mut registry = Self::default();
+        for tenant in config.tenants {
+            registry.register(tenant)?;
+        }
+        Ok(registry)
+    }
+
+    /// Register one tenant
+    pub fn register(&mut self, config: TenantConfig) -> Result<Arc<Tenant>, TenantError> {
+        if config.id.trim().is_empty() {
+            return Err(TenantError::InvalidConfig(
+                "tenant id must not be empty".to_string(),
+            ));
+        }
+        if self.tenants.contains_key(&config.id) {
+            return Err(TenantError::InvalidConfig(format!(
+                "duplicate tenant id '{}'",
+                config.id
+            )));
+        }
+
+        for existing in self.tenants.values() {
+            if workspaces_overlap(existing.workspace(), &config.workspace) {
+                return Err(TenantError::InvalidConfig(format!(
+                    "tenant '{}' workspace {:?} overlaps tenant '{}' workspace {:?}",
+                    config.id,
+                    config.workspace,
+                    existing.id(),
+                    existing.workspace()
+                )));
+            }
+        }
+
+        let tenant = Arc::new(Tenant::new(config)?);
+        let mut known: Vec<&str> = self
+            .tenants
+            .values()
+            .flat_map(|existing| existing.tokens.iter().map(|t| t.token.as_str()))
+            .collect();
+        for resolved in &tenant.tokens {
+            if known
+                .iter()
+                .any(|token| tokens_equal(token, &resolved.token))
+            {
+                return Err(TenantError::InvalidConfig(format!(
+                    "tenant '{}' token '{}' is already in use",
+                    tenant.id(),
+                    resolved.principal
+                )));
+            }
+            known.push(&resolved.token);
+        }
+
+        self.tenants.insert(tenant.id().to_string(), tenant.clone());
+        Ok(tenant)
+    }
+
+    /// Resolve an API token to its tenant, principal and role
+    pub fn authenticate(&self, token: &str) -> Result<AuthContext, TenantError> {
+        for tenant in self.tenants.values() {
+            if let Some(resolved) = tenant
+                .tokens
+                .iter()
+                .find(|resolved| tokens_equal(&resolved.token, token))
+            {
+                return Ok(AuthContext {
+                    tenant: tenant.clone(),
+                    principal: resolved.principal.clone(),
+                    role: resolved.role,
+                });
+            }
+        }
+        Err(TenantError::Unauthorized)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Arc<Tenant>, TenantError> {
+        self.tenants
+            .get(id)
+            .cloned()
+            .ok_or_else(|| TenantError::UnknownTenant(id.to_string()))
+    }
+
+    pub fn tenants(&self) -> impl Iterator<Item = &Arc<Tenant>> {
+        self.tenants.values()
+    }
+
+    /// Shut down every started agent system
+    pub async fn shutdown(&self) -> Result<(), TenantError> {
+        for tenant in self.tenants.values() {
+            tenant.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+fn workspaces_overlap(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (a, b) = (normalize(a), normalize(b));
+    a.starts_with(&b) || b.starts_with(&a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::types::TokenUsage;
+    use crate::session::CostLedgerEntry;
+    use tempfile::TempDir;
+
+    fn tenant(id: &str, workspace: &Path, token: &str) -> TenantConfig {
+        TenantConfig {
+            id: id.to_string(),
+            workspace: workspace.to_path_buf(),
+            api_token: Some(token.to_string()),
+            api_token_env: None,
+            tokens: Vec::new(),
+            require_approval: false,
+            agent_config: None,
+            max_concurrent_tasks: 1,
+            budget_usd: None,
+        }
+    }
+
+    #[test]
+    fn test_authenticate_by_token() {
+        let temp = TempDir::new().unwrap();
+        let config = DaemonConfig {
+            tenants: vec![
+                tenant("alpha", &temp.path().join("alpha"), "token-a"),
+                tenant("beta", &temp.path().join("beta"), "token-b"),
+            ],
+            ..Default::default()
+        };
+        let registry = TenantRegistry::from_config(config).unwrap();
+
+        let auth = registry.authenticate("token-b").unwrap();
+        assert_eq!(auth.tenant.id(), "beta");
+        assert_eq!(auth.principal, OWNER_PRINCIPAL);
+        assert_eq!(auth.role, TenantRole::Admin);
+        assert!(matches!(
+            registry.authenticate("token-c"),
+            Err(TenantError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_overlapping_workspaces() {
+        let temp = TempDir::new().unwrap();
+        let mut registry = TenantRegistry::default();
+        registry
+            .register(tenant("outer", temp.path(), "token-a"))
+            .unwrap();
+
+        let nested = tenant("inner", &temp.path().join("nested"), "token-b");
+        assert!(matches!(
+            registry.register(nested),
+            Err(TenantError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let temp = TempDir::new().unwrap();
+        let mut registry = TenantRegistry::default();
+        let tenant = registry
+            .register(tenant("alpha", temp.path(), "token-a"))
+            .unwrap();
+
+        let slot = tenant.try_acquire_slot().unwrap();
+        assert!(matches!(
+            tenant.try_acquire_slot(),
+            Err(TenantError::ConcurrencyLimit { .. })
+        ));
+        drop(slot);
+        assert!(tenant.try_acquire_slot().is_ok());
+    }
+
+    #[test]
+    fn test_budget_from_cost_ledger() {
+        let temp = TempDir::new().unwrap();
+        let mut config = tenant("alpha", temp.path(), "token-a");
+        config.budget_usd = Some(1.0);
+        let mut registry = TenantRegistry::default();
+        let tenant = registry.register(config).unwrap();
+        assert!(tenant.check_budget().is_ok());
+
+        let usage = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 10,
+            total_tokens: 20,
+            estimated_cost: 1.5,
+            ..Default::default()
+        };
+        CostLedger::for_workspace(temp.path())
+            .append(&CostLedgerEntry::new(
+                "session".to_string(),
+                Uuid::new_v4(),
+                "task".to_string(),
+                Vec::new(),
+                &usage,
+            ))
+            .unwrap();
+
+        assert!(matches!(
+            tenant.check_budget(),
+            Err(TenantError::BudgetExceeded { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_approval_requires_admin_and_is_journaled() {
+        let temp = TempDir::new().unwrap();
+        let mut config = tenant("alpha", temp.path(), "owner-token");
+        config.require_approval = true;
+        config.tokens = vec![ApiTokenConfig {
+            name: "ci".to_string(),
+            api_token: Some("viewer-token".to_string()),
+            api_token_env: None,
+            role: TenantRole::Viewer,
+        }];
+        let mut registry = TenantRegistry::default();
+        registry.register(config).unwrap();
+
+        let owner = registry.authenticate("owner-token").unwrap();
+        let viewer = registry.authenticate("viewer-token").unwrap();
+
+        // Viewers cannot submit
+        assert!(matches!(
+            viewer.submit_plan(ExecutionPlan::new()).await,
+            Err(TenantError::Forbidden { .. })
+        ));
+
+        let crate::daemon::Submission::Queued { approval_id } =
+            owner.submit_plan(ExecutionPlan::new()).await.unwrap()
+        else {
+            panic!("plan should be queued for approval");
+        };
+        assert_eq!(viewer.pending_approvals().unwrap().len(), 1);
+
+        // Viewers can see but not decide
+        assert!(matches!(
+            viewer.reject(approval_id, None),
+            Err(TenantError::Forbidden { .. })
+        ));
+        owner
+            .reject(approval_id, Some("not today".to_string()))
+            .unwrap();
+        assert!(owner.pending_approvals().unwrap().is_empty());
+
+        let entries = owner.tenant.journal().load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            &entries[1].event,
+            crate::session::JournalEvent::ApprovalRejected { rejected_by, role, .. }
+                if rejected_by == OWNER_PRINCIPAL && role == "admin"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_approval_waits_for_a_free_slot() {
+        let temp = TempDir::new().unwrap();
+        let mut config = tenant("alpha", temp.path(), "owner-token");
+        config.require_approval = true;
+        let mut registry = TenantRegistry::default();
+        let tenant = registry.register(config).unwrap();
+        let owner = registry.authenticate("owner-token").unwrap();
+
+        let crate::daemon::Submission::Queued { approval_id } =
+            owner.submit_plan(ExecutionPlan::new()).await.unwrap()
+        else {
+            panic!("plan should be queued for approval");
+        };
+
+        // Another plan holds the only slot: the approval stays pending, unjournaled
+        let _running = tenant.try_acquire_slot().unwrap();
+        assert!(matches!(
+            owner.approve(approval_id).await,
+            Err(TenantError::ConcurrencyLimit { .. })
+        ));
+        assert_eq!(owner.pending_approvals().unwrap().len(), 1);
+        let entries = tenant.journal().load().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].event,
+            crate::session::JournalEvent::ApprovalRequested { .. }
+        ));
+    }
+}
@@ -9,6 +9,33 @@ pub const ACA_DIR_NAME: &str = ".aca";
 /// Configuration file name
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 
+/// Interactive prompt history file name within .aca
+pub const HISTORY_FILE_NAME: &str = "history";
+
+/// Layout version marker within .aca
+pub const LAYOUT_FILE_NAME: &str = "layout.json";
+
+/// Pre-migration backups directory name within .aca
+pub const BACKUPS_DIR_NAME: &str = "backups";
+
+/// Mid-run priority overrides file name within .aca
+pub const PRIORITIES_FILE_NAME: &str = "priorities.toml";
+
+/// Crash reports directory name within .aca
+pub const CRASH_REPORTS_DIR_NAME: &str = "crash-reports";
+
+/// Marker naming the session a crash left dirty, within the crash reports directory
+pub const DIRTY_SESSION_FILE_NAME: &str = "dirty-session.json";
+
+/// Signing keys directory name within .aca
+pub const KEYS_DIR_NAME: &str = "keys";
+
+/// Run report signing key (PKCS#8 Ed25519), with its public key beside it as `.pub`
+pub const REPORT_SIGNING_KEY_FILE_NAME: &str = "report-signing.pk8";
+
+/// Local mapping between pseudonymized identifiers and their originals
+pub const ANONYMIZATION_FILE_NAME: &str = "anonymization.json";
+
 /// Session-related directory and file names
 pub mod session {
     /// Sessions directory name within .aca
@@ -26,17 +53,105 @@ pub mod session {
     /// Logs directory name
     pub const LOGS_DIR_NAME: &str = "logs";
 
+    /// Artifacts directory name
+    pub const ARTIFACTS_DIR_NAME: &str = "artifacts";
+
     /// Claude interactions subdirectory name
     pub const CLAUDE_INTERACTIONS_DIR_NAME: &str = "claude_interactions";
 
     /// OpenAI interactions subdirectory name
     pub const OPENAI_INTERACTIONS_DIR_NAME: &str = "openai_interactions";
 
+    /// Per-task workspace diffs subdirectory name
+    pub const DIFFS_DIR_NAME: &str = "diffs";
+
+    /// Run report written to the artifacts directory when a run ends
+    pub const RUN_REPORT_FILE_NAME: &str = "run-report.json";
+
+    /// MCP server config handed to the Claude CLI
+    pub const MCP_CONFIG_FILE_NAME: &str = "mcp-config.json";
+
     /// Errors subdirectory name
     pub const ERRORS_DIR_NAME: &str = "errors";
 
     /// Temp directory name
     pub const TEMP_DIR_NAME: &str = "temp";
+
+    /// Heartbeat file name (liveness reporting for unattended runs)
+    pub const HEARTBEAT_FILE_NAME: &str = "heartbeat.json";
+}
+
+/// Usage accounting file names (shared across sessions)
+pub mod usage {
+    /// Usage directory name within .aca
+    pub const USAGE_DIR_NAME: &str = "usage";
+
+    /// Append-only ledger of per-task costs
+    pub const COST_LEDGER_FILE_NAME: &str = "cost_ledger.jsonl";
+
+    /// Estimated vs actual task duration log file name
+    pub const ESTIMATES_FILE_NAME: &str = "estimates.jsonl";
+}
+
+/// Event journal file names (shared across sessions)
+pub mod journal {
+    /// Journal directory name within .aca
+    pub const JOURNAL_DIR_NAME: &str = "journal";
+
+    /// Append-only event journal
+    pub const EVENTS_FILE_NAME: &str = "events.jsonl";
+}
+
+/// Plan execution records and saved task analyses (shared across sessions)
+///
+/// Records are named after the plan id (a UUID); analyses after the SHA-256
+/// of the analyzed content.
+pub mod plans {
+    /// Plans directory name within .aca
+    pub const PLANS_DIR_NAME: &str = "plans";
+
+    /// Generated plan awaiting review with `aca run --approve`
+    pub const APPROVAL_FILE_NAME: &str = "approval.toml";
+}
+
+/// Distributed plan execution state (`aca coordinator`)
+pub mod coordinator {
+    /// Coordinator directory name within .aca
+    pub const COORDINATOR_DIR_NAME: &str = "coordinator";
+
+    /// Snapshot of every task's lease and result
+    pub const STATE_FILE_NAME: &str = "state.json";
+}
+
+/// Task text piped in or pasted instead of read from a file
+pub mod inputs {
+    /// Inputs directory name within .aca
+    pub const INPUTS_DIR_NAME: &str = "inputs";
+}
+
+/// Background context included in every task's system prompt
+pub mod context {
+    /// Context directory name within .aca
+    pub const CONTEXT_DIR_NAME: &str = "context";
+
+    /// Summary of imported Claude Code sessions
+    pub const CLAUDE_HISTORY_FILE_NAME: &str = "claude-history.md";
+
+    /// Claude Code's transcript directory within the user's home
+    pub const CLAUDE_PROJECTS_DIR: &str = ".claude/projects";
+
+    /// Directory within .aca for `--dump-context` output (kept out of
+    /// `CONTEXT_DIR_NAME` so dumps are never imported as context)
+    pub const DUMPS_DIR_NAME: &str = "context_dumps";
+}
+
+/// Machine-wide state shared by all aca processes of a user
+pub mod cache {
+    /// Cache directory name within `$XDG_CACHE_HOME` or `~/.cache`
+    pub const CACHE_DIR_NAME: &str = "aca";
+
+    /// Shared provider rate-limit bucket
+    pub const RATE_LIMIT_FILE_NAME: &str = "rate_limit.json";
 }
 
 /// Test-related constants
@@ -56,11 +171,131 @@ pub fn aca_dir_path(workspace_root: &std::path::Path) -> PathBuf {
     workspace_root.join(ACA_DIR_NAME)
 }
 
+/// Build the layout version marker path from a workspace root
+pub fn layout_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(LAYOUT_FILE_NAME)
+}
+
+/// Build the pre-migration backups directory path from a workspace root
+pub fn backups_dir_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(BACKUPS_DIR_NAME)
+}
+
 /// Build the sessions directory path from a workspace root
 pub fn sessions_dir_path(workspace_root: &std::path::Path) -> PathBuf {
     aca_dir_path(workspace_root).join(session::SESSIONS_DIR_NAME)
 }
 
+/// Build the cross-session cost ledger path from a workspace root
+pub fn cost_ledger_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(usage::USAGE_DIR_NAME)
+        .join(usage::COST_LEDGER_FILE_NAME)
+}
+
+/// Build the estimate calibration ledger path from a workspace root
+pub fn estimate_ledger_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(usage::USAGE_DIR_NAME)
+        .join(usage::ESTIMATES_FILE_NAME)
+}
+
+/// Build the workspace event journal path from a workspace root
+pub fn event_journal_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(journal::JOURNAL_DIR_NAME)
+        .join(journal::EVENTS_FILE_NAME)
+}
+
+/// Build the plan records directory path from a workspace root
+pub fn plans_dir_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(plans::PLANS_DIR_NAME)
+}
+
+/// Build the path a generated plan is written to for review with
+/// `aca run --approve`
+pub fn plan_approval_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    plans_dir_path(workspace_root).join(plans::APPROVAL_FILE_NAME)
+}
+
+/// Build the record path for one plan file from a workspace root
+pub fn plan_record_file_path(workspace_root: &std::path::Path, plan_id: &str) -> PathBuf {
+    plans_dir_path(workspace_root).join(format!("{}.json", plan_id))
+}
+
+/// Build the path a task analysis of content with `content_hash` is saved to
+pub fn plan_analysis_file_path(workspace_root: &std::path::Path, content_hash: &str) -> PathBuf {
+    plans_dir_path(workspace_root).join(format!("{}.json", content_hash))
+}
+
+/// Build the coordinator state snapshot path from a workspace root
+pub fn coordinator_state_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(coordinator::COORDINATOR_DIR_NAME)
+        .join(coordinator::STATE_FILE_NAME)
+}
+
+/// Build the imported context directory path from a workspace root
+pub fn context_dir_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(context::CONTEXT_DIR_NAME)
+}
+
+/// Build the path a task's `--dump-context` output is written to
+pub fn context_dump_file_path(workspace_root: &std::path::Path, task_id: &str) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(context::DUMPS_DIR_NAME)
+        .join(format!("{}.md", task_id))
+}
+
+/// Build the imported Claude Code history path from a workspace root
+pub fn claude_history_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    context_dir_path(workspace_root).join(context::CLAUDE_HISTORY_FILE_NAME)
+}
+
+/// Build Claude Code's transcript directory path (`~/.claude/projects`)
+pub fn claude_projects_dir_path(home_dir: &std::path::Path) -> PathBuf {
+    home_dir.join(context::CLAUDE_PROJECTS_DIR)
+}
+
+/// Build the priority overrides path from a workspace root
+pub fn priorities_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(PRIORITIES_FILE_NAME)
+}
+
+/// Build the crash reports directory path from a workspace root
+pub fn crash_reports_dir_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(CRASH_REPORTS_DIR_NAME)
+}
+
+/// Build the report signing key path from a workspace root
+pub fn report_signing_key_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(KEYS_DIR_NAME)
+        .join(REPORT_SIGNING_KEY_FILE_NAME)
+}
+
+/// Build the pseudonym mapping path from a workspace root
+pub fn anonymization_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(ANONYMIZATION_FILE_NAME)
+}
+
+/// Build the dirty session marker path from a workspace root
+pub fn dirty_session_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    crash_reports_dir_path(workspace_root).join(DIRTY_SESSION_FILE_NAME)
+}
+
+/// Build the interactive prompt history path from a workspace root
+pub fn history_file_path(workspace_root: &std::path::Path) -> PathBuf {
+    aca_dir_path(workspace_root).join(HISTORY_FILE_NAME)
+}
+
+/// Build the path that saved inline task text is written to
+pub fn inline_input_file_path(workspace_root: &std::path::Path, file_name: &str) -> PathBuf {
+    aca_dir_path(workspace_root)
+        .join(inputs::INPUTS_DIR_NAME)
+        .join(file_name)
+}
+
 /// Build a specific session directory path
 pub fn session_dir_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
     sessions_dir_path(workspace_root).join(session_id)
@@ -81,6 +316,21 @@ pub fn session_logs_dir_path(workspace_root: &std::path::Path, session_id: &str)
     session_dir_path(workspace_root, session_id).join(session::LOGS_DIR_NAME)
 }
 
+/// Build the session artifacts directory path
+pub fn session_artifacts_dir_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
+    session_dir_path(workspace_root, session_id).join(session::ARTIFACTS_DIR_NAME)
+}
+
+/// Build the run report path in a session's artifacts
+pub fn run_report_file_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
+    session_artifacts_dir_path(workspace_root, session_id).join(session::RUN_REPORT_FILE_NAME)
+}
+
+/// Build the MCP server config path for a session
+pub fn mcp_config_file_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
+    session_dir_path(workspace_root, session_id).join(session::MCP_CONFIG_FILE_NAME)
+}
+
 /// Build the Claude interactions logs directory path
 pub fn claude_interactions_dir_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
     session_logs_dir_path(workspace_root, session_id).join(session::CLAUDE_INTERACTIONS_DIR_NAME)
@@ -91,11 +341,30 @@ pub fn openai_interactions_dir_path(workspace_root: &std::path::Path, session_id
     session_logs_dir_path(workspace_root, session_id).join(session::OPENAI_INTERACTIONS_DIR_NAME)
 }
 
+/// Build the per-task workspace diffs directory path
+pub fn task_diffs_dir_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
+    session_logs_dir_path(workspace_root, session_id).join(session::DIFFS_DIR_NAME)
+}
+
 /// Build the session state file path
 pub fn session_state_file_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
     session_meta_dir_path(workspace_root, session_id).join(session::SESSION_FILE_NAME)
 }
 
+/// Build the output log path of a plan's background service
+pub fn service_log_file_path(
+    workspace_root: &std::path::Path,
+    session_id: &str,
+    service_name: &str,
+) -> PathBuf {
+    session_logs_dir_path(workspace_root, session_id).join(format!("service-{}.log", service_name))
+}
+
+/// Build the heartbeat file path for a session
+pub fn heartbeat_file_path(workspace_root: &std::path::Path, session_id: &str) -> PathBuf {
+    session_dir_path(workspace_root, session_id).join(session::HEARTBEAT_FILE_NAME)
+}
+
 /// Build a checkpoint file path
 pub fn checkpoint_file_path(
     workspace_root: &std::path::Path,
@@ -115,6 +384,20 @@ pub fn user_config_file_path(home_dir: &std::path::Path) -> PathBuf {
     user_config_dir_path(home_dir).join(CONFIG_FILE_NAME)
 }
 
+/// Build the machine-wide cache directory path (`$XDG_CACHE_HOME/aca` or `~/.cache/aca`)
+pub fn user_cache_dir_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join(cache::CACHE_DIR_NAME))
+}
+
+/// Build the shared rate-limit bucket file path
+pub fn shared_rate_limit_file_path() -> Option<PathBuf> {
+    user_cache_dir_path().map(|dir| dir.join(cache::RATE_LIMIT_FILE_NAME))
+}
+
 /// Build local config file path in current directory
 pub fn local_config_file_path(current_dir: &std::path::Path) -> PathBuf {
     current_dir.join(ACA_DIR_NAME).join(CONFIG_FILE_NAME)
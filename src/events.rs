@@ -0,0 +1,135 @@
+//! Structured progress events from [`AgentSystem`](crate::AgentSystem).
+//!
+//! Embedders call [`AgentSystem::subscribe`](crate::AgentSystem::subscribe)
+//! for a stream of [`AgentEvent`]s instead of scraping logs. Events are
+//! broadcast: every receiver sees every event sent after it subscribed, and a
+//! receiver that falls more than [`EVENT_CAPACITY`] events behind skips the
+//! oldest ones and gets `RecvError::Lagged`. Sending never blocks the agent,
+//! with or without subscribers.
+
+use crate::llm::ProviderType;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Events kept for a receiver that has not caught up
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// Something the agent did, for progress displays
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A task began running on a provider
+    TaskStarted { task_id: Uuid, title: String },
+    /// A request was sent to a provider for a task
+    ProviderCallStarted {
+        task_id: Uuid,
+        provider: ProviderType,
+        /// The provider's default model when unset
+        model: Option<String>,
+    },
+    /// A provider request returned
+    ProviderCallFinished {
+        task_id: Uuid,
+        provider: ProviderType,
+        success: bool,
+        duration_ms: u64,
+    },
+    /// Tokens a completed provider turn used
+    TokensUsed {
+        task_id: Uuid,
+        input_tokens: u64,
+        output_tokens: u64,
+        estimated_cost: f64,
+    },
+    /// A session checkpoint was written
+    CheckpointCreated {
+        checkpoint_id: String,
+        description: String,
+    },
+    /// A plan setup command finished
+    SetupCommandRun {
+        name: String,
+        success: bool,
+        exit_code: i32,
+        duration_ms: u64,
+    },
+    /// A port was still listening after its plan finished
+    ListenerLeaked {
+        port: u16,
+        /// Name of the port, if it was allocated to the plan
+        name: Option<String>,
+    },
+}
+
+/// Broadcasts [`AgentEvent`]s to any number of receivers
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<AgentEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl EventBus {
+    /// Send `event` to the current receivers, if any
+    pub fn emit(&self, event: AgentEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_every_receiver_sees_events_sent_after_subscribing() {
+        let bus = EventBus::default();
+        bus.emit(AgentEvent::CheckpointCreated {
+            checkpoint_id: "before".to_string(),
+            description: "no receivers yet".to_string(),
+        });
+
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        let task_id = Uuid::new_v4();
+        bus.emit(AgentEvent::TaskStarted {
+            task_id,
+            title: "Add login".to_string(),
+        });
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(
+                receiver.recv().await.unwrap(),
+                AgentEvent::TaskStarted {
+                    task_id,
+                    title: "Add login".to_string(),
+                }
+            );
+            assert!(receiver.try_recv().is_err());
+        }
+    }
+
+    #[test]
+    fn test_events_serialize_with_type_tag() {
+        let event = AgentEvent::SetupCommandRun {
+            name: "install".to_string(),
+            success: true,
+            exit_code: 0,
+            duration_ms: 1200,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "setup_command_run");
+        assert_eq!(json["name"], "install");
+        assert_eq!(serde_json::from_value::<AgentEvent>(json).unwrap(), event);
+    }
+}
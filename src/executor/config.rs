@@ -4,10 +4,25 @@
 //! (host vs container).
 
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Default container image for isolated execution
 pub const DEFAULT_CONTAINER_IMAGE: &str = "alpine:latest";
 
+/// Which container runtime to connect to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerRuntime {
+    /// Docker if reachable, then Podman (default)
+    #[default]
+    Auto,
+    /// Docker only: `DOCKER_HOST` or the standard Docker sockets
+    Docker,
+    /// Podman only: `CONTAINER_HOST`, the rootless socket under
+    /// `XDG_RUNTIME_DIR`, then the system socket
+    Podman,
+}
+
 /// Runtime mode - where commands run
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -50,6 +65,20 @@ pub struct ContainerExecutionConfig {
     /// Override CPU quota (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cpu_quota: Option<i64>,
+
+    /// Run in the workspace's dev container (`devcontainer.json`) instead of `image`
+    #[serde(default)]
+    pub devcontainer: bool,
+
+    /// Workspace Dockerfile, e.g. `Dockerfile` or `.aca/Containerfile`, to
+    /// build and run in instead of `image`; relative to the workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<PathBuf>,
+
+    /// Build context for `dockerfile`, relative to the workspace; the
+    /// workspace itself when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_context: Option<PathBuf>,
 }
 
 fn default_image() -> String {
@@ -67,6 +96,9 @@ impl Default for ContainerExecutionConfig {
             resource_percentage: default_resource_percentage(),
             memory_limit_bytes: None,
             cpu_quota: None,
+            devcontainer: false,
+            dockerfile: None,
+            build_context: None,
         }
     }
 }
@@ -97,6 +129,18 @@ impl ContainerExecutionConfig {
         self.cpu_quota = Some(quota);
         self
     }
+
+    /// Use the workspace's dev container configuration
+    pub fn with_devcontainer(mut self) -> Self {
+        self.devcontainer = true;
+        self
+    }
+
+    /// Build the image from a workspace Dockerfile
+    pub fn with_dockerfile(mut self, dockerfile: impl Into<PathBuf>) -> Self {
+        self.dockerfile = Some(dockerfile.into());
+        self
+    }
 }
 
 #[cfg(test)]
@@ -132,6 +176,31 @@ mod tests {
         assert_eq!(config.cpu_quota, Some(100_000));
     }
 
+    #[test]
+    fn test_dockerfile_from_toml() {
+        let mode: RuntimeMode =
+            toml::from_str("type = \"container\"\ndockerfile = \".aca/Containerfile\"\n").unwrap();
+        let RuntimeMode::Container(config) = mode else {
+            panic!("expected container mode");
+        };
+        assert_eq!(config.dockerfile, Some(PathBuf::from(".aca/Containerfile")));
+        assert_eq!(config.image, DEFAULT_CONTAINER_IMAGE);
+        assert!(config.build_context.is_none());
+    }
+
+    #[test]
+    fn test_container_runtime_from_toml() {
+        #[derive(Deserialize)]
+        struct Settings {
+            #[serde(default)]
+            container_runtime: ContainerRuntime,
+        }
+        let settings: Settings = toml::from_str("container_runtime = \"podman\"").unwrap();
+        assert_eq!(settings.container_runtime, ContainerRuntime::Podman);
+        let settings: Settings = toml::from_str("").unwrap();
+        assert_eq!(settings.container_runtime, ContainerRuntime::Auto);
+    }
+
     #[test]
     fn test_resource_percentage_clamping() {
         let config = ContainerExecutionConfig::default().with_resource_percentage(1.5);
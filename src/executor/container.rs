@@ -1,19 +1,26 @@
 //! Container-based command execution.
 //!
 //! Executes commands inside Docker/Podman containers for isolated execution.
-//! Supports session-bound container lifecycle management.
+//! Supports session-bound container lifecycle management, and a
+//! [`ContainerPool`] that gives each task its own ephemeral container.
 
 use super::{ExecutionCommand, ExecutionResult, ExecutorError};
 use crate::container::{
-    ContainerConfig, ContainerLifecycleManager, ContainerOrchestrator, ExecConfig, LifecycleConfig,
+    ContainerConfig, ContainerLifecycleManager, ContainerOrchestrator, ContainerOrchestratorConfig,
+    DockerfileBuild, ExecConfig, ImageBuilder, LifecycleConfig,
 };
-use crate::executor::config::DEFAULT_CONTAINER_IMAGE;
+use crate::executor::config::{ContainerRuntime, DEFAULT_CONTAINER_IMAGE};
+use crate::executor::devcontainer::DevContainerSetup;
 use crate::session::metadata::{SessionContainerInfo, SessionId};
+use crate::workspace::resolve_within;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
 
 /// Container execution configuration
 #[derive(Debug, Clone)]
@@ -32,6 +39,16 @@ pub struct ContainerExecutorConfig {
     pub auto_remove: bool,
     /// Session ID to bind the container to (optional for legacy support)
     pub session_id: Option<SessionId>,
+    /// Dev container to run in; its image replaces `image`
+    pub devcontainer: Option<Box<DevContainerSetup>>,
+    /// Workspace Dockerfile to build the image from; it replaces `image`
+    pub dockerfile: Option<Box<DockerfileBuild>>,
+    /// Name for a container not bound to a session; `aca-session` when unset
+    pub container_name: Option<String>,
+    /// Runtime to connect to
+    pub runtime: ContainerRuntime,
+    /// Docker context to use instead of the Docker CLI's current one
+    pub docker_context: Option<String>,
 }
 
 impl Default for ContainerExecutorConfig {
@@ -44,6 +61,11 @@ impl Default for ContainerExecutorConfig {
             cpu_quota: None,
             auto_remove: true,
             session_id: None,
+            devcontainer: None,
+            dockerfile: None,
+            container_name: None,
+            runtime: ContainerRuntime::Auto,
+            docker_context: None,
         }
     }
 }
@@ -60,10 +82,12 @@ impl ContainerExecutorConfig {
 #[derive(Clone)]
 pub struct ContainerExecutor {
     orchestrator: Arc<ContainerOrchestrator>,
-    config: ContainerExecutorConfig,
+    config: Arc<ContainerExecutorConfig>,
     container_id: Arc<RwLock<Option<String>>>,
     /// Lifecycle manager for session-bound container management
     lifecycle_manager: Option<Arc<ContainerLifecycleManager>>,
+    /// Dev container `remoteEnv`, resolved against the running container
+    remote_env: Arc<RwLock<Option<BTreeMap<String, String>>>>,
 }
 
 impl ContainerExecutor {
@@ -72,12 +96,10 @@ impl ContainerExecutor {
     /// # Errors
     ///
     /// Returns an error if the container runtime is unavailable.
-    pub async fn new(config: ContainerExecutorConfig) -> Result<Self, ExecutorError> {
-        let orchestrator = ContainerOrchestrator::new()
-            .await
-            .map_err(|e| ExecutorError::ContainerUnavailable(e.to_string()))?;
-
-        let orchestrator = Arc::new(orchestrator);
+    pub async fn new(mut config: ContainerExecutorConfig) -> Result<Self, ExecutorError> {
+        confine_mounts(&mut config)?;
+        let orchestrator = connect(&config).await?;
+        resolve_image(&orchestrator, &mut config).await?;
 
         // Create lifecycle manager if session_id is provided
         let lifecycle_manager = if let Some(session_id) = config.session_id {
@@ -88,6 +110,7 @@ impl ContainerExecutor {
                 memory_bytes: config.memory_bytes,
                 cpu_quota: config.cpu_quota,
                 auto_remove: config.auto_remove,
+                devcontainer: config.devcontainer.as_deref().cloned(),
             };
 
             Some(Arc::new(ContainerLifecycleManager::with_orchestrator(
@@ -100,13 +123,22 @@ impl ContainerExecutor {
         };
 
         Ok(Self {
-            orchestrator,
-            config,
-            container_id: Arc::new(RwLock::new(None)),
             lifecycle_manager,
+            ..Self::unbound(orchestrator, config)
         })
     }
 
+    /// An executor without a lifecycle manager, using `config` as is
+    fn unbound(orchestrator: Arc<ContainerOrchestrator>, config: ContainerExecutorConfig) -> Self {
+        Self {
+            orchestrator,
+            config: Arc::new(config),
+            container_id: Arc::new(RwLock::new(None)),
+            lifecycle_manager: None,
+            remote_env: Arc::new(RwLock::new(None)),
+        }
+    }
+
     /// Create a new container executor with an existing lifecycle manager
     ///
     /// This is useful when the lifecycle manager is created externally
@@ -117,9 +149,10 @@ impl ContainerExecutor {
     ) -> Self {
         Self {
             orchestrator: lifecycle_manager.orchestrator().clone(),
-            config,
+            config: Arc::new(config),
             container_id: Arc::new(RwLock::new(None)),
             lifecycle_manager: Some(lifecycle_manager),
+            remote_env: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -150,15 +183,19 @@ impl ContainerExecutor {
         // Build container configuration
         let mut container_config = ContainerConfig::builder()
             .image(&self.config.image)
-            .cmd(vec!["sleep", "infinity"])
-            .working_dir("/workspace");
-
-        // Mount workspace
-        if !self.config.workspace_mount.as_os_str().is_empty() {
-            container_config = container_config.bind(format!(
-                "{}:/workspace:rw",
-                self.config.workspace_mount.display()
-            ));
+            .cmd(vec!["sleep", "infinity"]);
+
+        // Mount workspace, where the dev container expects it if there is one
+        if let Some(ref setup) = self.config.devcontainer {
+            container_config = container_config.devcontainer(setup);
+        } else {
+            container_config = container_config.working_dir("/workspace");
+            if !self.config.workspace_mount.as_os_str().is_empty() {
+                container_config = container_config.bind(format!(
+                    "{}:/workspace:rw",
+                    self.config.workspace_mount.display()
+                ));
+            }
         }
 
         // Mount .aca directory
@@ -181,7 +218,9 @@ impl ContainerExecutor {
             .map_err(|e| ExecutorError::Other(format!("Container config error: {}", e)))?;
 
         // Generate container name (use session ID if available, otherwise default)
-        let container_name = if let Some(session_id) = self.config.session_id {
+        let container_name = if let Some(ref name) = self.config.container_name {
+            name.clone()
+        } else if let Some(session_id) = self.config.session_id {
             ContainerLifecycleManager::container_name_for_session(&session_id)
         } else {
             "aca-session".to_string()
@@ -210,6 +249,39 @@ impl ContainerExecutor {
         self.container_id.read().await.clone()
     }
 
+    /// The dev container's `remoteEnv`, resolving `${containerEnv:...}`
+    /// references against the container's environment on first use
+    async fn remote_env(&self, container_id: &str) -> BTreeMap<String, String> {
+        let Some(ref setup) = self.config.devcontainer else {
+            return BTreeMap::new();
+        };
+        if let Some(ref env) = *self.remote_env.read().await {
+            return env.clone();
+        }
+
+        let mut container_env = HashMap::new();
+        if setup
+            .remote_env
+            .values()
+            .any(|value| value.contains("${containerEnv:"))
+        {
+            match self.orchestrator.exec(container_id, vec!["env"]).await {
+                Ok(output) => {
+                    container_env = output
+                        .stdout
+                        .lines()
+                        .filter_map(|line| line.split_once('='))
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect();
+                }
+                Err(e) => debug!("Could not read the container environment: {}", e),
+            }
+        }
+        let env = setup.resolved_remote_env(&container_env);
+        *self.remote_env.write().await = Some(env.clone());
+        env
+    }
+
     /// Get the lifecycle manager if available
     pub fn lifecycle_manager(&self) -> Option<&Arc<ContainerLifecycleManager>> {
         self.lifecycle_manager.as_ref()
@@ -252,6 +324,19 @@ impl ContainerExecutor {
             exec_config = exec_config.working_dir(dir.to_string_lossy().to_string());
         }
 
+        // Run as the dev container's user with its remoteEnv
+        if let Some(user) = self
+            .config
+            .devcontainer
+            .as_ref()
+            .and_then(|setup| setup.user.as_ref())
+        {
+            exec_config = exec_config.user(user);
+        }
+        for (key, value) in self.remote_env(&container_id).await {
+            exec_config = exec_config.env(key, value);
+        }
+
         // Add environment variables
         for (key, value) in &cmd.env {
             exec_config = exec_config.env(key, value);
@@ -302,6 +387,24 @@ impl ContainerExecutor {
         "container"
     }
 
+    /// Pause the session container; `false` if none has been started
+    pub async fn pause(&self) -> Result<bool, ExecutorError> {
+        let Some(container_id) = self.container_id().await else {
+            return Ok(false);
+        };
+        self.orchestrator.pause_container(&container_id).await?;
+        Ok(true)
+    }
+
+    /// Unpause the session container; `false` if none has been started
+    pub async fn unpause(&self) -> Result<bool, ExecutorError> {
+        let Some(container_id) = self.container_id().await else {
+            return Ok(false);
+        };
+        self.orchestrator.unpause_container(&container_id).await?;
+        Ok(true)
+    }
+
     pub async fn shutdown(&self) -> Result<(), ExecutorError> {
         // Use lifecycle manager if available
         if let Some(ref lifecycle) = self.lifecycle_manager {
@@ -327,6 +430,262 @@ impl ContainerExecutor {
     }
 }
 
+/// Resolve the workspace mount through its symlinks and refuse an `.aca`
+/// mount outside it
+fn confine_mounts(config: &mut ContainerExecutorConfig) -> std::io::Result<()> {
+    if config.workspace_mount.as_os_str().is_empty() {
+        return Ok(());
+    }
+    config.workspace_mount = config.workspace_mount.canonicalize()?;
+    if !config.aca_mount.as_os_str().is_empty() {
+        config.aca_mount = resolve_within(&config.workspace_mount, &config.aca_mount)?;
+    }
+    Ok(())
+}
+
+async fn connect(
+    config: &ContainerExecutorConfig,
+) -> Result<Arc<ContainerOrchestrator>, ExecutorError> {
+    ContainerOrchestrator::with_config(ContainerOrchestratorConfig {
+        runtime: config.runtime,
+        docker_context: config.docker_context.clone(),
+        ..Default::default()
+    })
+    .await
+    .map(Arc::new)
+    .map_err(|e| ExecutorError::ContainerUnavailable(e.to_string()))
+}
+
+/// Build or pull the dev container's or Dockerfile's image, if any, and use
+/// it as `image`
+async fn resolve_image(
+    orchestrator: &ContainerOrchestrator,
+    config: &mut ContainerExecutorConfig,
+) -> Result<(), ExecutorError> {
+    let builder = ImageBuilder::new(orchestrator.client().docker().clone());
+    if let Some(ref setup) = config.devcontainer {
+        config.image = builder
+            .ensure_devcontainer_image(setup, &config.workspace_mount)
+            .await?;
+        info!(
+            "Using dev container {} with image {}",
+            setup.config_path.display(),
+            config.image
+        );
+    } else if let Some(ref build) = config.dockerfile {
+        config.image = builder
+            .ensure_dockerfile_image(build, &config.workspace_mount)
+            .await?;
+        info!(
+            "Using {} with image {}",
+            build.dockerfile.display(),
+            config.image
+        );
+    }
+    Ok(())
+}
+
+/// Container pool settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerPoolConfig {
+    /// Give each task its own ephemeral container; when false, all tasks
+    /// share the session container
+    pub per_task: bool,
+    /// Containers kept started and unused, ready for the next task
+    pub warm: usize,
+}
+
+impl Default for ContainerPoolConfig {
+    fn default() -> Self {
+        Self {
+            per_task: true,
+            warm: 1,
+        }
+    }
+}
+
+/// Hands out a container per task so parallel tasks don't share a container
+///
+/// A task's container is created from the pool's base configuration, or
+/// taken from the containers started ahead of time, and removed when the
+/// task is [released](Self::release). Containers are never reused across
+/// tasks, so files written outside the mounted workspace and `.aca`
+/// directory, installed packages and caches don't leak between tasks. The
+/// bind-mounted workspace itself is still shared.
+#[derive(Clone)]
+pub struct ContainerPool {
+    orchestrator: Arc<ContainerOrchestrator>,
+    base: ContainerExecutorConfig,
+    config: ContainerPoolConfig,
+    /// Started containers not yet given to a task
+    warm: Arc<Mutex<Vec<ContainerExecutor>>>,
+    warming: Arc<Mutex<()>>,
+    leased: Arc<Mutex<HashMap<Uuid, ContainerExecutor>>>,
+    /// The one container all tasks use when not `per_task`
+    shared: Option<ContainerExecutor>,
+}
+
+impl ContainerPool {
+    /// Create a pool of containers configured like `base`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container runtime is unavailable or the dev
+    /// container's image cannot be built.
+    pub async fn new(
+        mut base: ContainerExecutorConfig,
+        config: ContainerPoolConfig,
+    ) -> Result<Self, ExecutorError> {
+        confine_mounts(&mut base)?;
+        let orchestrator = connect(&base).await?;
+        let shared = if config.per_task {
+            // Build the image once for every task container
+            resolve_image(&orchestrator, &mut base).await?;
+            None
+        } else {
+            Some(ContainerExecutor::new(base.clone()).await?)
+        };
+        Ok(Self {
+            orchestrator,
+            base,
+            config,
+            warm: Arc::new(Mutex::new(Vec::new())),
+            warming: Arc::new(Mutex::new(())),
+            leased: Arc::new(Mutex::new(HashMap::new())),
+            shared,
+        })
+    }
+
+    pub fn config(&self) -> &ContainerPoolConfig {
+        &self.config
+    }
+
+    /// A new, not yet started container for one task
+    fn task_executor(&self) -> ContainerExecutor {
+        let id = Uuid::new_v4().simple().to_string();
+        let name = match self.base.session_id {
+            Some(session_id) => format!(
+                "{}-task-{}",
+                ContainerLifecycleManager::container_name_for_session(&session_id),
+                &id[..12]
+            ),
+            None => format!("aca-task-{}", &id[..12]),
+        };
+        let config = ContainerExecutorConfig {
+            session_id: None,
+            container_name: Some(name),
+            ..self.base.clone()
+        };
+        ContainerExecutor::unbound(self.orchestrator.clone(), config)
+    }
+
+    /// Start containers until `warm` are ready for new tasks
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a container fails to start.
+    pub async fn warm_up(&self) -> Result<(), ExecutorError> {
+        if !self.config.per_task {
+            return Ok(());
+        }
+        // One warm-up at a time, without blocking tasks taking containers
+        let _warming = self.warming.lock().await;
+        while self.warm.lock().await.len() < self.config.warm {
+            let executor = self.task_executor();
+            executor.ensure_container().await?;
+            self.warm.lock().await.push(executor);
+        }
+        Ok(())
+    }
+
+    /// The container `task_id` runs commands in, starting with a warm one
+    ///
+    /// Calling this again for the same task returns the same container.
+    /// With `per_task` off every task gets the shared container.
+    pub async fn acquire(&self, task_id: Uuid) -> ContainerExecutor {
+        if let Some(ref shared) = self.shared {
+            return shared.clone();
+        }
+        let mut leased = self.leased.lock().await;
+        if let Some(executor) = leased.get(&task_id) {
+            return executor.clone();
+        }
+
+        let executor = match self.warm.lock().await.pop() {
+            Some(executor) => executor,
+            // Started on its first command
+            None => self.task_executor(),
+        };
+        debug!(
+            "Leased container {} to task {}",
+            executor
+                .config
+                .container_name
+                .as_deref()
+                .unwrap_or_default(),
+            task_id
+        );
+        leased.insert(task_id, executor.clone());
+
+        if self.config.warm > 0 {
+            let pool = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = pool.warm_up().await {
+                    warn!("Failed to start a warm container: {}", e);
+                }
+            });
+        }
+        executor
+    }
+
+    /// Remove the container of a finished task
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container cannot be stopped and removed.
+    pub async fn release(&self, task_id: Uuid) -> Result<(), ExecutorError> {
+        let Some(executor) = self.leased.lock().await.remove(&task_id) else {
+            return Ok(());
+        };
+        debug!("Removing container of task {}", task_id);
+        executor.shutdown().await
+    }
+
+    /// Tasks currently holding a container
+    pub async fn leased_tasks(&self) -> Vec<Uuid> {
+        self.leased.lock().await.keys().copied().collect()
+    }
+
+    /// Remove every container the pool started
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error met; the remaining containers are still removed.
+    pub async fn shutdown(&self) -> Result<(), ExecutorError> {
+        let mut executors: Vec<_> = self.warm.lock().await.drain(..).collect();
+        executors.extend(
+            self.leased
+                .lock()
+                .await
+                .drain()
+                .map(|(_, executor)| executor),
+        );
+        executors.extend(self.shared.clone());
+
+        let mut result = Ok(());
+        for executor in executors {
+            if let Err(e) = executor.shutdown().await {
+                warn!("Failed to remove pooled container: {}", e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -407,4 +766,73 @@ mod tests {
 
         executor.shutdown().await.unwrap();
     }
+
+    #[test]
+    fn test_mounts_are_confined_to_workspace() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        let mut config = ContainerExecutorConfig {
+            workspace_mount: workspace.path().join("."),
+            aca_mount: workspace.path().join(".aca"),
+            ..Default::default()
+        };
+        confine_mounts(&mut config).unwrap();
+        assert_eq!(config.workspace_mount, root);
+        assert_eq!(config.aca_mount, root.join(".aca"));
+
+        let mut config = ContainerExecutorConfig {
+            workspace_mount: workspace.path().to_path_buf(),
+            aca_mount: outside.path().join(".aca"),
+            ..Default::default()
+        };
+        let error = confine_mounts(&mut config).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_pool_config_defaults_to_per_task_containers() {
+        let config: ContainerPoolConfig = toml::from_str("warm = 3").unwrap();
+        assert!(config.per_task);
+        assert_eq!(config.warm, 3);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Docker/Podman
+    async fn test_pool_isolates_task_containers() {
+        if !should_run_container_tests() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let base = ContainerExecutorConfig {
+            image: "alpine:latest".to_string(),
+            workspace_mount: temp_dir.path().to_path_buf(),
+            aca_mount: temp_dir.path().join(".aca"),
+            ..Default::default()
+        };
+        let pool = ContainerPool::new(base, ContainerPoolConfig::default())
+            .await
+            .unwrap();
+        pool.warm_up().await.unwrap();
+
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let touch = ExecutionCommand::new("touch", vec!["/tmp/scratch".to_string()]);
+        pool.acquire(first).await.execute(touch).await.unwrap();
+        let check =
+            ExecutionCommand::new("test", vec!["-e".to_string(), "/tmp/scratch".to_string()]);
+        let result = pool
+            .acquire(second)
+            .await
+            .execute(check.clone())
+            .await
+            .unwrap();
+        assert_ne!(result.exit_code, 0);
+        let result = pool.acquire(first).await.execute(check).await.unwrap();
+        assert_eq!(result.exit_code, 0);
+
+        pool.release(first).await.unwrap();
+        assert_eq!(pool.leased_tasks().await, [second]);
+        pool.shutdown().await.unwrap();
+    }
 }
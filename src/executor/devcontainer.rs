@@ -0,0 +1,573 @@
+//! Using a repository's `devcontainer.json` as the task sandbox.
+//!
+//! Many repositories describe their development environment in
+//! `.devcontainer/devcontainer.json`. [`DevContainerSetup`] reads that file
+//! (JSON with comments) and resolves what the container executor needs to
+//! start the same environment: the image, or the Dockerfile to build it
+//! from, the features to add, extra mounts, `containerEnv`, `remoteEnv`, the
+//! user and the workspace folder.
+//!
+//! Only a subset of the specification is supported. Features need the
+//! `devcontainer` CLI to be installed and are skipped otherwise; Docker
+//! Compose configurations, lifecycle scripts and port forwarding are
+//! ignored. Host directories may only be mounted from inside the
+//! workspace; named volumes are not restricted.
+
+use crate::workspace::resolve_within;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Locations checked for a dev container configuration, in order
+pub const DEVCONTAINER_PATHS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+#[derive(Error, Debug)]
+pub enum DevContainerError {
+    #[error("No devcontainer.json found in {0}")]
+    NotFound(PathBuf),
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid devcontainer.json {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error(
+        "{0} sets neither an image nor a Dockerfile; Docker Compose dev containers are not supported"
+    )]
+    NoImage(PathBuf),
+    #[error("Refusing to mount {bind}: {source}")]
+    MountOutsideWorkspace {
+        bind: String,
+        source: std::io::Error,
+    },
+}
+
+/// The fields of `devcontainer.json` that are supported
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct DevContainerFile {
+    image: Option<String>,
+    build: Option<BuildSection>,
+    /// Legacy spelling of `build.dockerfile`
+    docker_file: Option<String>,
+    context: Option<String>,
+    features: serde_json::Map<String, Value>,
+    mounts: Vec<Value>,
+    workspace_folder: Option<String>,
+    workspace_mount: Option<String>,
+    container_env: HashMap<String, String>,
+    remote_env: HashMap<String, Option<String>>,
+    remote_user: Option<String>,
+    container_user: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BuildSection {
+    dockerfile: Option<String>,
+    context: Option<String>,
+    args: HashMap<String, String>,
+    target: Option<String>,
+}
+
+/// Where the dev container's image comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum DevContainerImage {
+    /// A published image
+    Image(String),
+    /// An image built from a Dockerfile
+    Dockerfile {
+        dockerfile: PathBuf,
+        context: PathBuf,
+        args: BTreeMap<String, String>,
+        target: Option<String>,
+    },
+}
+
+/// A dev container configuration resolved against a workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct DevContainerSetup {
+    /// The `devcontainer.json` this was read from
+    pub config_path: PathBuf,
+    pub image: DevContainerImage,
+    /// Feature references, e.g. `ghcr.io/devcontainers/features/node:1`
+    pub features: Vec<String>,
+    /// Where the workspace is mounted in the container
+    pub workspace_folder: String,
+    /// Binds in `source:target` form, including the workspace
+    pub binds: Vec<String>,
+    /// Environment of the container itself
+    pub container_env: BTreeMap<String, String>,
+    /// Environment of commands run in the container; values may still refer
+    /// to `${containerEnv:NAME}`, see [`expand_container_env`]
+    pub remote_env: BTreeMap<String, String>,
+    /// User commands run as (`remoteUser`, else `containerUser`)
+    pub user: Option<String>,
+}
+
+impl DevContainerSetup {
+    /// Path of the workspace's `devcontainer.json`, if it has one
+    pub fn find(workspace_root: &Path) -> Option<PathBuf> {
+        DEVCONTAINER_PATHS
+            .iter()
+            .map(|path| workspace_root.join(path))
+            .find(|path| path.is_file())
+    }
+
+    /// Read and resolve the workspace's dev container configuration
+    pub fn discover(workspace_root: &Path) -> Result<Self, DevContainerError> {
+        let path = Self::find(workspace_root)
+            .ok_or_else(|| DevContainerError::NotFound(workspace_root.to_path_buf()))?;
+        let content = std::fs::read_to_string(&path).map_err(|source| DevContainerError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let mut setup = Self::parse(&content, &path, workspace_root)?;
+        setup.confine_binds(workspace_root)?;
+        Ok(setup)
+    }
+
+    /// Resolve the configuration in `content`, read from `config_path`
+    pub fn parse(
+        content: &str,
+        config_path: &Path,
+        workspace_root: &Path,
+    ) -> Result<Self, DevContainerError> {
+        let file: DevContainerFile =
+            serde_json::from_str(&strip_jsonc(content)).map_err(|source| {
+                DevContainerError::Parse {
+                    path: config_path.to_path_buf(),
+                    source,
+                }
+            })?;
+        let config_dir = config_path.parent().unwrap_or(workspace_root);
+
+        let basename = workspace_root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "workspace".to_string());
+        let mut vars = Variables {
+            local_workspace: workspace_root.display().to_string(),
+            local_basename: basename.clone(),
+            container_workspace: String::new(),
+        };
+        let workspace_folder = file
+            .workspace_folder
+            .as_deref()
+            .map(|folder| vars.substitute(folder))
+            .unwrap_or_else(|| format!("/workspaces/{}", basename));
+        vars.container_workspace = workspace_folder.clone();
+
+        let build = file.build.unwrap_or_default();
+        let image = match (build.dockerfile.or(file.docker_file), file.image) {
+            (Some(dockerfile), _) => DevContainerImage::Dockerfile {
+                dockerfile: config_dir.join(vars.substitute(&dockerfile)),
+                context: config_dir.join(
+                    vars.substitute(build.context.or(file.context).as_deref().unwrap_or(".")),
+                ),
+                args: build
+                    .args
+                    .iter()
+                    .map(|(key, value)| (key.clone(), vars.substitute(value)))
+                    .collect(),
+                target: build.target,
+            },
+            (None, Some(image)) => DevContainerImage::Image(vars.substitute(&image)),
+            (None, None) => return Err(DevContainerError::NoImage(config_path.to_path_buf())),
+        };
+
+        let workspace_bind = match file.workspace_mount.as_deref() {
+            Some(mount) => parse_mount(&Value::String(vars.substitute(mount))),
+            None => Some(format!("{}:{}", vars.local_workspace, workspace_folder)),
+        };
+        let binds = workspace_bind
+            .into_iter()
+            .chain(file.mounts.iter().filter_map(|mount| match mount {
+                Value::String(mount) => parse_mount(&Value::String(vars.substitute(mount))),
+                Value::Object(_) => parse_mount(mount).map(|bind| vars.substitute(&bind)),
+                _ => None,
+            }))
+            .collect();
+
+        Ok(Self {
+            config_path: config_path.to_path_buf(),
+            image,
+            features: file.features.keys().cloned().collect(),
+            workspace_folder,
+            binds,
+            container_env: file
+                .container_env
+                .iter()
+                .map(|(key, value)| (key.clone(), vars.substitute(value)))
+                .collect(),
+            remote_env: file
+                .remote_env
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), vars.substitute(value.as_ref()?))))
+                .collect(),
+            user: file.remote_user.or(file.container_user),
+        })
+    }
+
+    /// Resolve the host directory of each bind through its symlinks,
+    /// refusing any outside `workspace_root`
+    ///
+    /// Named volumes have no host directory and are left as they are.
+    pub fn confine_binds(&mut self, workspace_root: &Path) -> Result<(), DevContainerError> {
+        for bind in &mut self.binds {
+            let Some((source, target)) = bind.split_once(':') else {
+                continue;
+            };
+            if !Path::new(source).is_absolute() {
+                continue;
+            }
+            let resolved = resolve_within(workspace_root, Path::new(source)).map_err(|source| {
+                DevContainerError::MountOutsideWorkspace {
+                    bind: bind.clone(),
+                    source,
+                }
+            })?;
+            *bind = format!("{}:{}", resolved.display(), target);
+        }
+        Ok(())
+    }
+
+    /// Tag for an image built from this configuration
+    pub fn image_tag(&self, workspace_root: &Path) -> String {
+        format!("aca-devcontainer-{}:latest", workspace_slug(workspace_root))
+    }
+
+    /// `remoteEnv` with references to the container's environment resolved
+    pub fn resolved_remote_env(
+        &self,
+        container_env: &HashMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        self.remote_env
+            .iter()
+            .map(|(key, value)| (key.clone(), expand_container_env(value, container_env)))
+            .collect()
+    }
+}
+
+/// The workspace directory's name, usable in an image name
+pub(crate) fn workspace_slug(workspace_root: &Path) -> String {
+    let name: String = workspace_root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+/// Values substituted into `devcontainer.json` strings
+struct Variables {
+    local_workspace: String,
+    local_basename: String,
+    container_workspace: String,
+}
+
+impl Variables {
+    /// Replace `${...}` variables known on the host
+    ///
+    /// `${containerEnv:...}` is left for [`expand_container_env`].
+    fn substitute(&self, value: &str) -> String {
+        replace_variables(value, |name| {
+            let (kind, rest) = name.split_once(':').unwrap_or((name, ""));
+            match kind {
+                "localWorkspaceFolder" => Some(self.local_workspace.clone()),
+                "localWorkspaceFolderBasename" => Some(self.local_basename.clone()),
+                "containerWorkspaceFolder" => Some(self.container_workspace.clone()),
+                "containerWorkspaceFolderBasename" => Some(
+                    self.container_workspace
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                ),
+                "localEnv" | "env" => {
+                    let (var, default) = rest.split_once(':').unwrap_or((rest, ""));
+                    Some(std::env::var(var).unwrap_or_else(|_| default.to_string()))
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Replace `${containerEnv:NAME}` with the container's value of `NAME`
+pub fn expand_container_env(value: &str, container_env: &HashMap<String, String>) -> String {
+    replace_variables(value, |name| {
+        let rest = name.strip_prefix("containerEnv:")?;
+        let (var, default) = rest.split_once(':').unwrap_or((rest, ""));
+        Some(
+            container_env
+                .get(var)
+                .cloned()
+                .unwrap_or_else(|| default.to_string()),
+        )
+    })
+}
+
+/// Replace each `${name}` for which `lookup` has a value
+fn replace_variables(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + len];
+        match lookup(name) {
+            Some(replacement) => result.push_str(&replacement),
+            None => result.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// A mount as a `source:target` bind, from the string form
+/// `source=...,target=...,type=bind` or the equivalent object
+fn parse_mount(mount: &Value) -> Option<String> {
+    let (source, target) = match mount {
+        Value::String(spec) => {
+            let mut source = None;
+            let mut target = None;
+            for part in spec.split(',') {
+                match part.split_once('=') {
+                    Some(("source" | "src", value)) => source = Some(value.to_string()),
+                    Some(("target" | "destination" | "dst", value)) => {
+                        target = Some(value.to_string())
+                    }
+                    _ => {}
+                }
+            }
+            (source?, target?)
+        }
+        Value::Object(fields) => (
+            fields.get("source")?.as_str()?.to_string(),
+            fields.get("target")?.as_str()?.to_string(),
+        ),
+        _ => return None,
+    };
+    Some(format!("{}:{}", source, target))
+}
+
+/// Remove comments and trailing commas so JSONC parses as JSON
+fn strip_jsonc(content: &str) -> String {
+    let mut without_comments = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            without_comments.push(c);
+            match c {
+                '\\' => without_comments.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        without_comments.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                without_comments.push(c);
+            }
+        }
+    }
+
+    // Commas are dropped in a second pass so comments between a trailing
+    // comma and the closing bracket no longer hide it
+    let mut out = String::with_capacity(without_comments.len());
+    let mut chars = without_comments.chars();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if c == ',' {
+            let next = chars
+                .clone()
+                .find(|c| !c.is_whitespace())
+                .unwrap_or_default();
+            if next == '}' || next == ']' {
+                continue;
+            }
+        }
+        in_string = c == '"';
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dockerfile_devcontainer() {
+        let content = r#"{
+            // Build from the repo's Dockerfile
+            "name": "api",
+            "build": {
+                "dockerfile": "Dockerfile",
+                "context": "..",
+                "args": { "VARIANT": "bookworm" },
+            },
+            "features": { "ghcr.io/devcontainers/features/node:1": {} },
+            "mounts": [
+                "source=${localWorkspaceFolder}/.cache,target=/cache,type=bind",
+                { "source": "api-target", "target": "/target", "type": "volume" }
+            ],
+            "containerEnv": { "CARGO_HOME": "/cache/cargo" },
+            "remoteEnv": { "PATH": "${containerEnv:PATH}:/cache/cargo/bin", "UNSET": null },
+            "remoteUser": "vscode", /* not root */
+        }"#;
+        let workspace = Path::new("/src/api");
+        let setup = DevContainerSetup::parse(
+            content,
+            &workspace.join(".devcontainer/devcontainer.json"),
+            workspace,
+        )
+        .unwrap();
+
+        assert_eq!(
+            setup.image,
+            DevContainerImage::Dockerfile {
+                dockerfile: PathBuf::from("/src/api/.devcontainer/Dockerfile"),
+                context: PathBuf::from("/src/api/.devcontainer/.."),
+                args: BTreeMap::from([("VARIANT".to_string(), "bookworm".to_string())]),
+                target: None,
+            }
+        );
+        assert_eq!(
+            setup.features,
+            vec!["ghcr.io/devcontainers/features/node:1"]
+        );
+        assert_eq!(setup.workspace_folder, "/workspaces/api");
+        assert_eq!(
+            setup.binds,
+            vec![
+                "/src/api:/workspaces/api",
+                "/src/api/.cache:/cache",
+                "api-target:/target"
+            ]
+        );
+        assert_eq!(setup.user.as_deref(), Some("vscode"));
+        assert_eq!(setup.image_tag(workspace), "aca-devcontainer-api:latest");
+
+        let env = HashMap::from([("PATH".to_string(), "/usr/bin".to_string())]);
+        assert_eq!(
+            setup.resolved_remote_env(&env),
+            BTreeMap::from([("PATH".to_string(), "/usr/bin:/cache/cargo/bin".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_parse_image_devcontainer() {
+        let content = r#"{
+            "image": "mcr.microsoft.com/devcontainers/rust:1",
+            "workspaceFolder": "/code",
+            "workspaceMount": "source=${localWorkspaceFolder},target=/code,type=bind"
+        }"#;
+        let workspace = Path::new("/src/api");
+        let setup =
+            DevContainerSetup::parse(content, &workspace.join(".devcontainer.json"), workspace)
+                .unwrap();
+        assert_eq!(
+            setup.image,
+            DevContainerImage::Image("mcr.microsoft.com/devcontainers/rust:1".to_string())
+        );
+        assert_eq!(setup.binds, vec!["/src/api:/code"]);
+        assert!(setup.user.is_none());
+
+        let compose = DevContainerSetup::parse(
+            r#"{"dockerComposeFile": "compose.yml", "service": "app"}"#,
+            &workspace.join(".devcontainer.json"),
+            workspace,
+        );
+        assert!(matches!(compose, Err(DevContainerError::NoImage(_))));
+    }
+
+    #[test]
+    fn test_binds_outside_workspace_are_refused() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join(".devcontainer")).unwrap();
+        std::fs::create_dir_all(root.join(".cache")).unwrap();
+        let config = |mounts: &str| {
+            std::fs::write(
+                root.join(".devcontainer/devcontainer.json"),
+                format!(r#"{{"image": "rust:1", "mounts": [{}]}}"#, mounts),
+            )
+            .unwrap();
+            DevContainerSetup::discover(&root)
+        };
+
+        let setup = config(
+            r#""source=${localWorkspaceFolder}/.cache,target=/cache,type=bind",
+               "source=cargo-registry,target=/usr/local/cargo/registry,type=volume""#,
+        )
+        .unwrap();
+        assert_eq!(
+            setup.binds[1..],
+            [
+                format!("{}:/cache", root.join(".cache").display()),
+                "cargo-registry:/usr/local/cargo/registry".to_string()
+            ]
+        );
+
+        let error = config(r#""source=/etc,target=/host-etc,type=bind""#).unwrap_err();
+        assert!(
+            matches!(error, DevContainerError::MountOutsideWorkspace { ref bind, .. } if bind == "/etc:/host-etc"),
+            "{}",
+            error
+        );
+        assert!(
+            config(r#""source=${localWorkspaceFolder}/../other,target=/other,type=bind""#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_strip_jsonc_keeps_strings() {
+        let stripped = strip_jsonc(r#"{"url": "http://example.com/*x*/", "a": [1, 2,],}"#);
+        assert_eq!(
+            stripped,
+            r#"{"url": "http://example.com/*x*/", "a": [1, 2]}"#
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Probing the environment commands will run in.
+//!
+//! At plan start a single cheap shell script runs through the active
+//! executor and reports the operating system, CPU and memory, the toolchains
+//! and binaries on `PATH`, and the container runtime. The resulting
+//! [`EnvironmentReport`] is kept in the session, added to task prompts so
+//! generated commands match the machine, and consulted by setup command
+//! conditions that require particular binaries.
+
+use super::{CommandExecutor, ExecutionCommand, ExecutorError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// Binaries whose presence is reported
+pub const PROBED_BINARIES: &[&str] = &[
+    "git", "make", "cargo", "rustc", "python3", "pip3", "node", "npm", "go", "java", "gcc",
+    "clang", "docker", "podman", "kubectl",
+];
+
+/// Toolchains whose version is reported, with the command printing it
+const TOOLCHAIN_VERSIONS: &[(&str, &str)] = &[
+    ("rustc", "rustc --version"),
+    ("cargo", "cargo --version"),
+    ("python3", "python3 --version"),
+    ("node", "node --version"),
+    ("go", "go version"),
+    ("gcc", "gcc --version"),
+    ("docker", "docker --version"),
+    ("podman", "podman --version"),
+];
+
+/// Upper bound on the probe script's run time
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// What the probes found about the execution environment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvironmentReport {
+    /// Kernel name as reported by `uname -s`, e.g. `Linux` or `Darwin`
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub kernel: Option<String>,
+    pub cpus: Option<u32>,
+    pub memory_mb: Option<u64>,
+    /// First line of each toolchain's version output
+    pub toolchains: BTreeMap<String, String>,
+    /// Probed binaries found on `PATH`
+    pub binaries: BTreeSet<String>,
+    /// Docker or Podman, when either is available
+    pub container_runtime: Option<String>,
+    /// Whether the commands themselves run inside a container
+    pub in_container: bool,
+    pub probed_at: Option<DateTime<Utc>>,
+}
+
+impl EnvironmentReport {
+    /// Run the probes through `executor`
+    pub async fn probe(executor: &CommandExecutor) -> Result<Self, ExecutorError> {
+        let mut command = ExecutionCommand::new("sh", vec!["-c".to_string(), probe_script()]);
+        command.timeout = Some(PROBE_TIMEOUT);
+        let result = executor.execute(command).await?;
+        let mut report = Self::parse(&result.stdout);
+        report.probed_at = Some(Utc::now());
+        Ok(report)
+    }
+
+    /// Build a report from the probe script's `key=value` lines
+    pub fn parse(output: &str) -> Self {
+        let mut report = Self::default();
+        for line in output.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match key {
+                "os" => report.os = Some(value.to_string()),
+                "arch" => report.arch = Some(value.to_string()),
+                "kernel" => report.kernel = Some(value.to_string()),
+                "cpus" => report.cpus = value.parse().ok(),
+                "memory_kb" => report.memory_mb = value.parse::<u64>().ok().map(|kb| kb / 1024),
+                "memory_bytes" => {
+                    report.memory_mb = value.parse::<u64>().ok().map(|b| b / (1024 * 1024))
+                }
+                "container" => report.in_container = true,
+                _ => {
+                    if let Some(binary) = key.strip_prefix("bin.") {
+                        report.binaries.insert(binary.to_string());
+                    } else if let Some(tool) = key.strip_prefix("version.") {
+                        report
+                            .toolchains
+                            .insert(tool.to_string(), value.to_string());
+                    }
+                }
+            }
+        }
+        report.container_runtime = ["docker", "podman"]
+            .into_iter()
+            .find(|runtime| report.has_binary(runtime))
+            .map(str::to_string);
+        report
+    }
+
+    pub fn has_binary(&self, name: &str) -> bool {
+        self.binaries.contains(name)
+    }
+
+    /// Binaries from `required` that the environment does not have
+    pub fn missing_binaries<'a>(&self, required: &'a [String]) -> Vec<&'a str> {
+        required
+            .iter()
+            .filter(|binary| !self.has_binary(binary))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Description of the environment for the system prompt
+    pub fn prompt_section(&self) -> String {
+        let mut lines = vec!["Execution environment (commands you run execute here):".to_string()];
+        let platform: Vec<&str> = [&self.os, &self.arch, &self.kernel]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if !platform.is_empty() {
+            lines.push(format!("- Platform: {}", platform.join(" ")));
+        }
+        match (self.cpus, self.memory_mb) {
+            (Some(cpus), Some(memory)) => {
+                lines.push(format!("- Resources: {} CPUs, {} MB memory", cpus, memory))
+            }
+            (Some(cpus), None) => lines.push(format!("- Resources: {} CPUs", cpus)),
+            (None, Some(memory)) => lines.push(format!("- Resources: {} MB memory", memory)),
+            (None, None) => {}
+        }
+        if self.in_container {
+            lines.push("- Running inside a container".to_string());
+        }
+        for (tool, version) in &self.toolchains {
+            lines.push(format!("- {}: {}", tool, version));
+        }
+        let missing: Vec<&str> = PROBED_BINARIES
+            .iter()
+            .copied()
+            .filter(|binary| !self.has_binary(binary))
+            .collect();
+        if !self.binaries.is_empty() {
+            let available: Vec<&str> = self.binaries.iter().map(String::as_str).collect();
+            lines.push(format!("- Available: {}", available.join(", ")));
+        }
+        if !missing.is_empty() {
+            lines.push(format!("- Not installed: {}", missing.join(", ")));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Shell script printing one `key=value` line per finding
+fn probe_script() -> String {
+    let mut script = String::from(
+        "echo \"os=$(uname -s 2>/dev/null)\"\n\
+         echo \"arch=$(uname -m 2>/dev/null)\"\n\
+         echo \"kernel=$(uname -r 2>/dev/null)\"\n\
+         echo \"cpus=$(nproc 2>/dev/null || getconf _NPROCESSORS_ONLN 2>/dev/null)\"\n\
+         [ -r /proc/meminfo ] && echo \"memory_kb=$(awk '/^MemTotal:/ {print $2}' /proc/meminfo)\"\n\
+         [ -r /proc/meminfo ] || echo \"memory_bytes=$(sysctl -n hw.memsize 2>/dev/null)\"\n\
+         { [ -f /.dockerenv ] || [ -f /run/.containerenv ]; } && echo \"container=yes\"\n",
+    );
+    script.push_str(&format!(
+        "for bin in {}; do command -v \"$bin\" >/dev/null 2>&1 && echo \"bin.$bin=yes\"; done\n",
+        PROBED_BINARIES.join(" ")
+    ));
+    for (tool, version) in TOOLCHAIN_VERSIONS {
+        script.push_str(&format!(
+            "command -v {tool} >/dev/null 2>&1 && echo \"version.{tool}=$({version} 2>&1 | head -n 1)\"\n"
+        ));
+    }
+    script.push_str("exit 0\n");
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::HostExecutor;
+
+    #[test]
+    fn test_parse_probe_output() {
+        let report = EnvironmentReport::parse(
+            "os=Linux\narch=x86_64\ncpus=8\nmemory_kb=16384000\ncontainer=yes\n\
+             bin.git=yes\nbin.podman=yes\nversion.rustc=rustc 1.90.0 (abc 2025-09-14)\n\
+             kernel=\nnot a probe line\n",
+        );
+        assert_eq!(report.os.as_deref(), Some("Linux"));
+        assert_eq!(report.kernel, None);
+        assert_eq!(report.cpus, Some(8));
+        assert_eq!(report.memory_mb, Some(16000));
+        assert!(report.in_container);
+        assert_eq!(report.container_runtime.as_deref(), Some("podman"));
+        assert_eq!(
+            report.missing_binaries(&["git".to_string(), "cargo".to_string()]),
+            vec!["cargo"]
+        );
+
+        let prompt = report.prompt_section();
+        assert!(prompt.contains("- Platform: Linux x86_64"));
+        assert!(prompt.contains("- rustc: rustc 1.90.0 (abc 2025-09-14)"));
+        assert!(prompt.contains("- Available: git, podman"));
+        assert!(prompt.contains("cargo"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_host() {
+        let executor = CommandExecutor::Host(HostExecutor::new());
+        let report = EnvironmentReport::probe(&executor).await.unwrap();
+        assert!(report.os.is_some());
+        assert!(report.probed_at.is_some());
+    }
+}
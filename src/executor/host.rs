@@ -1,31 +1,44 @@
 //! Native host command execution.
 //!
-//! Executes commands directly on the host system using `tokio::process::Command`.
+//! Executes commands directly on the host system using `tokio::process::Command`,
+//! optionally inside a Nix development shell.
 
+use super::nix::NixShell;
 use super::{ExecutionCommand, ExecutionResult, ExecutorError};
+use std::sync::{Arc, RwLock};
 use std::time::Instant;
 use tokio::process::Command;
 use tracing::debug;
 
 /// Executes commands directly on the host system
-#[derive(Debug, Clone)]
-pub struct HostExecutor;
+#[derive(Debug, Clone, Default)]
+pub struct HostExecutor {
+    /// Development shell every command is wrapped in, shared between clones
+    nix: Arc<RwLock<Option<NixShell>>>,
+}
 
 impl HostExecutor {
     /// Create a new host executor
     pub fn new() -> Self {
-        Self
+        Self::default()
     }
-}
 
-impl Default for HostExecutor {
-    fn default() -> Self {
-        Self::new()
+    /// Run commands inside `shell`, or directly again with `None`
+    pub fn set_nix_shell(&self, shell: Option<NixShell>) {
+        *self.nix.write().unwrap() = shell;
+    }
+
+    pub fn nix_shell(&self) -> Option<NixShell> {
+        self.nix.read().unwrap().clone()
     }
 }
 
 impl HostExecutor {
     pub async fn execute(&self, cmd: ExecutionCommand) -> Result<ExecutionResult, ExecutorError> {
+        let cmd = match self.nix_shell() {
+            Some(shell) => shell.wrap(cmd),
+            None => cmd,
+        };
         debug!("Executing command on host: {} {:?}", cmd.program, cmd.args);
 
         let start = Instant::now();
@@ -8,9 +8,16 @@
 //! - **[`CommandExecutor`]**: Unified enum executor supporting both host and container modes
 //! - **[`HostExecutor`]**: Direct host system command execution with process management
 //! - **[`ContainerExecutor`]**: Sandboxed execution within Docker/Podman containers
+//! - **[`ContainerPool`]**: One ephemeral container per task, with warm containers started ahead
 //! - **[`ExecutionCommand`]**: Command specification with arguments, environment, and timeouts
 //! - **[`ExecutionResult`]**: Execution outcome with stdout, stderr, exit code, and duration
 //! - **[`SystemResources`]**: System resource detection and allocation for containers
+//! - **[`ExecutorCapabilities`]**: What an executor offers, matched against task requirements by [`place`]
+//! - **[`DevContainerSetup`]**: A repository's `devcontainer.json` resolved for the session container
+//! - **[`EnvironmentReport`]**: OS, toolchains and binaries probed through an executor at plan start
+//! - **[`NixShell`]**: A flake's `nix develop` shell that host commands are wrapped in
+//! - **[`ServiceSpec`]**: A background process, such as a dev server, kept running during a plan
+//! - **[`PortAllocator`]**: Free ports handed to a plan's commands and prompts by name
 //!
 //! ## Key Features
 //!
@@ -105,6 +112,7 @@
 //!         memory_bytes: Some(512_000_000), // 512 MB
 //!         cpu_quota: Some(50_000),         // 50% CPU
 //!         auto_remove: true,
+//!         ..Default::default()
 //!     };
 //!
 //!     let executor = CommandExecutor::Container(
@@ -173,12 +181,57 @@ pub mod resources;
 #[cfg(feature = "containers")]
 pub mod container;
 
-pub use config::{ContainerExecutionConfig, RuntimeMode};
+/// Dev container configurations used as the sandbox.
+///
+/// Provides [`DevContainerSetup`], resolved from a repository's
+/// `devcontainer.json` into the image, mounts and environment of the
+/// session container.
+pub mod devcontainer;
+
+/// Probing of the execution environment.
+///
+/// Provides [`EnvironmentReport`], built at plan start from cheap probes of
+/// the OS, toolchains and binaries available to the active executor.
+pub mod environment;
+
+/// Nix flake development shells for host execution.
+///
+/// Provides [`NixConfig`], resolved into the [`NixShell`] that host
+/// commands and the agent CLI run inside for workspaces with a `flake.nix`.
+pub mod nix;
+
+/// Placement of tasks on capable executors.
+///
+/// Provides [`ExecutorCapabilities`] describing the host, containers and
+/// remote workers, and [`place`] for matching them against a task's
+/// declared requirements.
+pub mod placement;
+
+/// Background services kept running during a plan.
+///
+/// Provides [`ServiceSpec`], started through an executor as a background
+/// process group, and the [`ServiceRecord`] kept in the session so the
+/// service can be health-checked and stopped.
+pub mod service;
+
+/// Port allocation and leaked listener detection.
+///
+/// Provides [`PortAllocator`], which gives the names a plan declares free
+/// ports, and the listening-port probe run through an executor at teardown.
+pub mod ports;
+
+pub use config::{ContainerExecutionConfig, ContainerRuntime, RuntimeMode};
+pub use devcontainer::DevContainerSetup;
+pub use environment::EnvironmentReport;
 pub use host::HostExecutor;
+pub use nix::{NixConfig, NixMode, NixShell};
+pub use placement::{ExecutorCapabilities, PlacementConfig, PlacementError, place};
+pub use ports::{PortAllocator, PortConfig};
 pub use resources::{ResourceAllocation, SystemResources};
+pub use service::{ServiceRecord, ServiceSpec};
 
 #[cfg(feature = "containers")]
-pub use container::ContainerExecutor;
+pub use container::{ContainerExecutor, ContainerPool, ContainerPoolConfig};
 
 /// Result of command execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -351,6 +404,34 @@ impl CommandExecutor {
         }
     }
 
+    /// Freeze the sandbox so paused tasks stop making changes
+    ///
+    /// Returns `false` for host executors, which have no sandbox to pause.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container runtime fails to pause the container.
+    pub async fn pause(&self) -> Result<bool, ExecutorError> {
+        match self {
+            Self::Host(_) => Ok(false),
+            #[cfg(feature = "containers")]
+            Self::Container(executor) => executor.pause().await,
+        }
+    }
+
+    /// Thaw a sandbox frozen by [`pause`](Self::pause)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container runtime fails to unpause the container.
+    pub async fn unpause(&self) -> Result<bool, ExecutorError> {
+        match self {
+            Self::Host(_) => Ok(false),
+            #[cfg(feature = "containers")]
+            Self::Container(executor) => executor.unpause().await,
+        }
+    }
+
     /// Get container info if running in container mode
     ///
     /// Returns `None` for host executors or if no container has been created yet.
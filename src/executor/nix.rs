@@ -0,0 +1,177 @@
+//! Running host commands inside a Nix flake's development shell.
+//!
+//! For workspaces with a `flake.nix`, commands on the host (setup commands,
+//! verification commands and the agent CLI) can be wrapped in
+//! `nix develop --command`, so they see the toolchains the flake pins
+//! without needing a container.
+
+use super::ExecutionCommand;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File whose presence marks a workspace as a Nix flake
+pub const FLAKE_FILE: &str = "flake.nix";
+
+/// When to wrap host commands in `nix develop`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NixMode {
+    /// Wrap when the workspace has a `flake.nix` and `nix` is installed
+    #[default]
+    Auto,
+    /// Always wrap, failing commands if the shell cannot be entered
+    Always,
+    /// Never wrap
+    Never,
+}
+
+/// Nix development shell settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NixConfig {
+    pub mode: NixMode,
+    /// Flake reference for `nix develop`, e.g. `.#ci`; relative references
+    /// are resolved against the workspace, which is the default
+    pub flake: Option<String>,
+    /// Extra `nix develop` arguments, e.g. `--impure`
+    pub extra_args: Vec<String>,
+}
+
+impl NixConfig {
+    /// The shell commands in `workspace` should be wrapped in, if any
+    pub fn resolve(&self, workspace: &Path) -> Option<NixShell> {
+        let wrap = match self.mode {
+            NixMode::Never => false,
+            NixMode::Always => true,
+            NixMode::Auto => workspace.join(FLAKE_FILE).is_file() && which::which("nix").is_ok(),
+        };
+        wrap.then(|| NixShell {
+            flake: resolve_flake(self.flake.as_deref(), workspace),
+            extra_args: self.extra_args.clone(),
+        })
+    }
+}
+
+/// A `nix develop` shell that commands are run through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NixShell {
+    flake: String,
+    extra_args: Vec<String>,
+}
+
+impl NixShell {
+    pub fn flake(&self) -> &str {
+        &self.flake
+    }
+
+    /// Arguments to `nix` preceding the wrapped program
+    pub fn develop_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--extra-experimental-features".to_string(),
+            "nix-command flakes".to_string(),
+            "develop".to_string(),
+            self.flake.clone(),
+        ];
+        args.extend(self.extra_args.iter().cloned());
+        args.push("--command".to_string());
+        args
+    }
+
+    /// `command` run inside the shell
+    pub fn wrap(&self, mut command: ExecutionCommand) -> ExecutionCommand {
+        let mut args = self.develop_args();
+        args.push(std::mem::replace(&mut command.program, "nix".to_string()));
+        args.append(&mut command.args);
+        command.args = args;
+        command
+    }
+
+    /// A process builder for `program` inside the shell
+    pub fn command(&self, program: &str) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("nix");
+        command.args(self.develop_args()).arg(program);
+        command
+    }
+}
+
+/// Absolute flake reference, so commands may run from any directory
+fn resolve_flake(flake: Option<&str>, workspace: &Path) -> String {
+    match flake {
+        None => workspace.display().to_string(),
+        Some(flake) if flake.starts_with('.') => {
+            let (path, output) = flake.split_once('#').unwrap_or((flake, ""));
+            let path = workspace.join(path);
+            let path = path.display().to_string();
+            let path = path.trim_end_matches("/.");
+            if output.is_empty() {
+                path.to_string()
+            } else {
+                format!("{}#{}", path, output)
+            }
+        }
+        Some(flake) => flake.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_follows_mode_and_flake_file() {
+        let workspace = std::env::temp_dir().join(format!("aca-nix-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+
+        // No flake.nix, so auto mode does not wrap whether or not nix exists
+        assert_eq!(NixConfig::default().resolve(&workspace), None);
+        let never = NixConfig {
+            mode: NixMode::Never,
+            ..Default::default()
+        };
+        std::fs::write(workspace.join(FLAKE_FILE), "{ }").unwrap();
+        assert_eq!(never.resolve(&workspace), None);
+
+        let always = NixConfig {
+            mode: NixMode::Always,
+            flake: Some(".#ci".to_string()),
+            extra_args: vec!["--impure".to_string()],
+        };
+        let shell = always.resolve(&workspace).unwrap();
+        assert_eq!(shell.flake(), format!("{}#ci", workspace.display()));
+        assert_eq!(shell.develop_args()[4..], ["--impure", "--command"]);
+    }
+
+    #[test]
+    fn test_wrap_runs_command_inside_develop_shell() {
+        let shell = NixShell {
+            flake: "/src/app".to_string(),
+            extra_args: Vec::new(),
+        };
+        let command = ExecutionCommand::new("cargo", vec!["test".to_string()])
+            .with_working_dir("/src/app".into());
+        let wrapped = shell.wrap(command);
+        assert_eq!(wrapped.program, "nix");
+        assert_eq!(
+            wrapped.args,
+            [
+                "--extra-experimental-features",
+                "nix-command flakes",
+                "develop",
+                "/src/app",
+                "--command",
+                "cargo",
+                "test"
+            ]
+        );
+        assert_eq!(wrapped.working_dir, Some("/src/app".into()));
+
+        assert_eq!(
+            resolve_flake(Some("github:org/env"), Path::new("/w")),
+            "github:org/env"
+        );
+        assert_eq!(
+            resolve_flake(Some("./nix#dev"), Path::new("/w")),
+            "/w/./nix#dev"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Matching task requirements to executors.
+//!
+//! Tasks can declare what they need from the machine that runs them
+//! ([`TaskRequirements`]): a container runtime, a GPU, an operating system
+//! or free-form labels such as `arm64` or `large-disk`. Each executor —
+//! the host, a container image or a remote worker — describes what it
+//! offers as [`ExecutorCapabilities`]. [`place`] picks the first executor
+//! that satisfies a task and otherwise reports, per executor, what was
+//! missing.
+
+use crate::task::TaskRequirements;
+use serde::{Deserialize, Serialize};
+use which::which;
+
+use super::RuntimeMode;
+
+/// Overrides for detected executor capabilities
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlacementConfig {
+    /// Labels this executor offers, matched against task `labels`
+    pub labels: Vec<String>,
+    /// Whether a GPU is available; detected from `nvidia-smi` when unset
+    pub gpu: Option<bool>,
+    /// Whether Docker or Podman is available; detected from `PATH` when unset
+    pub docker: Option<bool>,
+}
+
+/// What an executor can offer to tasks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutorCapabilities {
+    /// Human-readable executor name used in placement errors
+    pub name: String,
+    /// Operating system as reported by `std::env::consts::OS`
+    pub os: String,
+    pub docker: bool,
+    pub gpu: bool,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl ExecutorCapabilities {
+    /// Capabilities of the executor selected by `mode`
+    pub fn for_runtime(mode: &RuntimeMode, config: &PlacementConfig) -> Self {
+        match mode {
+            RuntimeMode::Host => Self::host(config),
+            RuntimeMode::Container(container) => Self {
+                name: format!("container ({})", container.image),
+                os: "linux".to_string(),
+                docker: config.docker.unwrap_or(false),
+                gpu: config.gpu.unwrap_or(false),
+                labels: config.labels.clone(),
+            },
+        }
+    }
+
+    /// Capabilities of the host, detected unless overridden by `config`
+    pub fn host(config: &PlacementConfig) -> Self {
+        Self {
+            name: format!("host ({})", std::env::consts::OS),
+            os: std::env::consts::OS.to_string(),
+            docker: config
+                .docker
+                .unwrap_or_else(|| which("docker").is_ok() || which("podman").is_ok()),
+            gpu: config.gpu.unwrap_or_else(|| which("nvidia-smi").is_ok()),
+            labels: config.labels.clone(),
+        }
+    }
+
+    /// Requirements this executor does not meet, empty when it can run the task
+    pub fn unmet(&self, requirements: &TaskRequirements) -> Vec<String> {
+        let mut unmet = Vec::new();
+        if requirements.docker && !self.docker {
+            unmet.push("docker".to_string());
+        }
+        if requirements.gpu && !self.gpu {
+            unmet.push("gpu".to_string());
+        }
+        if let Some(os) = &requirements.os
+            && !os.eq_ignore_ascii_case(&self.os)
+        {
+            unmet.push(format!("os {}", os));
+        }
+        for label in &requirements.labels {
+            if !self.labels.contains(label) {
+                unmet.push(format!("label '{}'", label));
+            }
+        }
+        unmet
+    }
+
+    pub fn satisfies(&self, requirements: &TaskRequirements) -> bool {
+        self.unmet(requirements).is_empty()
+    }
+}
+
+/// No executor can run a task
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("No executor can run task '{task}': {}", describe(.rejections))]
+pub struct PlacementError {
+    pub task: String,
+    /// Each executor's name with the requirements it does not meet
+    pub rejections: Vec<(String, Vec<String>)>,
+}
+
+fn describe(rejections: &[(String, Vec<String>)]) -> String {
+    if rejections.is_empty() {
+        return "no executors are available".to_string();
+    }
+    rejections
+        .iter()
+        .map(|(name, unmet)| format!("{} lacks {}", name, unmet.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// First executor that satisfies the requirements of the task titled `task`
+pub fn place<'a>(
+    task: &str,
+    requirements: &TaskRequirements,
+    executors: &'a [ExecutorCapabilities],
+) -> Result<&'a ExecutorCapabilities, PlacementError> {
+    let mut rejections = Vec::new();
+    for executor in executors {
+        let unmet = executor.unmet(requirements);
+        if unmet.is_empty() {
+            return Ok(executor);
+        }
+        rejections.push((executor.name.clone(), unmet));
+    }
+    Err(PlacementError {
+        task: task.to_string(),
+        rejections,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executor(name: &str, os: &str, gpu: bool, labels: &[&str]) -> ExecutorCapabilities {
+        ExecutorCapabilities {
+            name: name.to_string(),
+            os: os.to_string(),
+            docker: false,
+            gpu,
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_place_picks_first_capable_executor() {
+        let executors = vec![
+            executor("host (linux)", "linux", false, &[]),
+            executor("gpu-box", "linux", true, &["cuda"]),
+        ];
+
+        let unconstrained = TaskRequirements::default();
+        assert_eq!(
+            place("Lint", &unconstrained, &executors).unwrap().name,
+            "host (linux)"
+        );
+
+        let gpu = TaskRequirements {
+            gpu: true,
+            labels: vec!["cuda".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            place("Train model", &gpu, &executors).unwrap().name,
+            "gpu-box"
+        );
+    }
+
+    #[test]
+    fn test_place_reports_unmet_requirements() {
+        let executors = vec![executor("host (linux)", "linux", false, &[])];
+        let requirements = TaskRequirements {
+            gpu: true,
+            os: Some("macos".to_string()),
+            ..Default::default()
+        };
+
+        let error = place("Train model", &requirements, &executors).unwrap_err();
+        assert_eq!(
+            error.rejections,
+            vec![(
+                "host (linux)".to_string(),
+                vec!["gpu".to_string(), "os macos".to_string()]
+            )]
+        );
+        assert_eq!(
+            error.to_string(),
+            "No executor can run task 'Train model': host (linux) lacks gpu, os macos"
+        );
+        assert!(place("Train model", &requirements, &[]).is_err());
+    }
+
+    #[test]
+    fn test_capabilities_respect_overrides() {
+        let config = PlacementConfig {
+            labels: vec!["arm64".to_string()],
+            gpu: Some(true),
+            docker: Some(false),
+        };
+        let host = ExecutorCapabilities::for_runtime(&RuntimeMode::Host, &config);
+        assert!(host.gpu && !host.docker);
+        assert_eq!(host.os, std::env::consts::OS);
+
+        let container = ExecutorCapabilities::for_runtime(
+            &RuntimeMode::Container(crate::executor::ContainerExecutionConfig::new("rust:1")),
+            &PlacementConfig::default(),
+        );
+        assert_eq!(container.name, "container (rust:1)");
+        assert_eq!(container.os, "linux");
+        assert!(!container.gpu && !container.docker);
+    }
+}
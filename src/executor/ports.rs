@@ -0,0 +1,275 @@
+//! Ports allocated to a plan, and listeners it leaves behind.
+//!
+//! A plan names the ports it needs, in `[ports] allocate` or a service's
+//! `ports`. Each name gets a port from the configured range that nothing
+//! listens on. The ports are set as environment variables for setup,
+//! verification and service commands. They are also listed in the task
+//! system prompt, so generated configuration uses them instead of guessing.
+//! `${NAME}` in a readiness URL is replaced by the port.
+//!
+//! Listening ports are read from `/proc/net/tcp` through the executor, so
+//! they are those of the session container in sandbox mode. Ports that
+//! listen when the plan ends but did not before it started are reported as
+//! leaked.
+//!
+//! ```toml
+//! [ports]
+//! allocate = ["API_PORT"]
+//! range_start = 20000
+//! range_end = 29999
+//! ```
+
+use super::{CommandExecutor, ExecutionCommand};
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+
+/// Ports a plan needs and where they are taken from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PortConfig {
+    /// Environment variable names that each get a free port
+    pub allocate: Vec<String>,
+    pub range_start: u16,
+    pub range_end: u16,
+}
+
+impl Default for PortConfig {
+    fn default() -> Self {
+        Self {
+            allocate: Vec::new(),
+            range_start: 20000,
+            range_end: 29999,
+        }
+    }
+}
+
+impl PortConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.range_start == 0 || self.range_start > self.range_end {
+            bail!("Invalid port range {}-{}", self.range_start, self.range_end);
+        }
+        validate_names(&self.allocate)
+    }
+}
+
+/// Check that `names` can be used as environment variables
+pub fn validate_names(names: &[String]) -> Result<()> {
+    for name in names {
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            bail!("Invalid port name '{}': use letters, digits and '_'", name);
+        }
+    }
+    Ok(())
+}
+
+/// Ports handed out to the running plan, by name
+#[derive(Debug)]
+pub struct PortAllocator {
+    config: PortConfig,
+    ports: Mutex<BTreeMap<String, u16>>,
+}
+
+impl PortAllocator {
+    pub fn new(config: PortConfig) -> Self {
+        Self {
+            config,
+            ports: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Names every plan gets a port for
+    pub fn configured_names(&self) -> &[String] {
+        &self.config.allocate
+    }
+
+    /// Give each of `names` a port for which `is_free` holds
+    ///
+    /// Names that already have a port keep it. The search starts at a
+    /// random point in the range, so runs sharing a machine rarely try the
+    /// same ports.
+    pub fn allocate(&self, names: &[String], is_free: impl Fn(u16) -> bool) -> Result<()> {
+        validate_names(names)?;
+        let (start, end) = (self.config.range_start, self.config.range_end);
+        let size = u32::from(end - start) + 1;
+        let mut ports = self.ports.lock().unwrap();
+        for name in names {
+            if ports.contains_key(name) {
+                continue;
+            }
+            let taken: BTreeSet<u16> = ports.values().copied().collect();
+            let offset = rand::random_range(0..size);
+            let port = (0..size)
+                .map(|i| start + ((offset + i) % size) as u16)
+                .find(|port| !taken.contains(port) && is_free(*port))
+                .ok_or_else(|| anyhow!("No free port in {}-{} for {}", start, end, name))?;
+            ports.insert(name.clone(), port);
+        }
+        Ok(())
+    }
+
+    pub fn ports(&self) -> BTreeMap<String, u16> {
+        self.ports.lock().unwrap().clone()
+    }
+
+    /// Allocated ports as environment variables
+    pub fn env(&self) -> HashMap<String, String> {
+        self.ports
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, port)| (name.clone(), port.to_string()))
+            .collect()
+    }
+
+    /// Replace `${NAME}` with the port allocated to `NAME`
+    pub fn expand(&self, text: &str) -> String {
+        let mut expanded = text.to_string();
+        for (name, port) in self.ports.lock().unwrap().iter() {
+            expanded = expanded.replace(&format!("${{{}}}", name), &port.to_string());
+        }
+        expanded
+    }
+
+    /// Description of the allocated ports for the task system prompt
+    pub fn prompt_section(&self) -> Option<String> {
+        let ports = self.ports.lock().unwrap();
+        if ports.is_empty() {
+            return None;
+        }
+        let mut lines = vec![
+            "Ports reserved for this run (also set as environment variables; \
+             use them instead of choosing ports):"
+                .to_string(),
+        ];
+        lines.extend(
+            ports
+                .iter()
+                .map(|(name, port)| format!("- {}={}", name, port)),
+        );
+        Some(lines.join("\n"))
+    }
+
+    /// Give up all allocated ports, returning them
+    pub fn release(&self) -> BTreeMap<String, u16> {
+        std::mem::take(&mut *self.ports.lock().unwrap())
+    }
+}
+
+/// Ports something listens on where `executor` runs commands
+///
+/// `None` where `/proc/net/tcp` cannot be read, as on macOS.
+pub async fn listening_ports(executor: &CommandExecutor) -> Option<BTreeSet<u16>> {
+    let command = ExecutionCommand::new(
+        "sh",
+        vec![
+            "-c".to_string(),
+            "cat /proc/net/tcp /proc/net/tcp6 2>/dev/null; true".to_string(),
+        ],
+    );
+    let result = executor.execute(command).await.ok()?;
+    if result.stdout.trim().is_empty() {
+        return None;
+    }
+    Some(parse_listening(&result.stdout))
+}
+
+/// Whether a listener could be opened on `port` on this machine
+pub fn bindable(port: u16) -> bool {
+    std::net::TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// Local ports of the sockets in `LISTEN` state of a `/proc/net/tcp` table
+fn parse_listening(table: &str) -> BTreeSet<u16> {
+    const LISTEN: &str = "0A";
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let local = fields.nth(1)?;
+            let state = fields.nth(1)?;
+            if state != LISTEN {
+                return None;
+            }
+            u16::from_str_radix(local.rsplit(':').next()?, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_expand_and_prompt() {
+        let allocator = PortAllocator::new(PortConfig {
+            range_start: 20000,
+            range_end: 20002,
+            ..Default::default()
+        });
+        let names = vec!["API_PORT".to_string(), "WEB_PORT".to_string()];
+        allocator.allocate(&names, |port| port != 20001).unwrap();
+        let ports = allocator.ports();
+        assert_eq!(
+            ports.values().copied().collect::<BTreeSet<_>>(),
+            BTreeSet::from([20000, 20002])
+        );
+
+        // Allocated names keep their port, and no other is free
+        allocator.allocate(&names, |_| true).unwrap();
+        assert_eq!(allocator.ports(), ports);
+        assert!(
+            allocator
+                .allocate(&["DB_PORT".to_string()], |port| port != 20001)
+                .is_err()
+        );
+        assert!(
+            allocator
+                .allocate(&["db-port".to_string()], |_| true)
+                .is_err()
+        );
+
+        let api = ports["API_PORT"];
+        assert_eq!(
+            allocator.expand("http://localhost:${API_PORT}/health"),
+            format!("http://localhost:{}/health", api)
+        );
+        assert_eq!(allocator.env()["API_PORT"], api.to_string());
+        assert!(
+            allocator
+                .prompt_section()
+                .unwrap()
+                .contains(&format!("- API_PORT={}", api))
+        );
+        assert_eq!(allocator.release(), ports);
+        assert!(allocator.prompt_section().is_none());
+    }
+
+    #[test]
+    fn test_parse_listening() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue\n\
+            \x20  0: 0100007F:0BB8 00000000:0000 0A 00000000:00000000\n\
+            \x20  1: 0100007F:9C40 0100007F:0BB8 01 00000000:00000000\n\
+            \x20  0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 0\n";
+        assert_eq!(parse_listening(table), BTreeSet::from([3000, 8080]));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_listening_ports_sees_a_listener() {
+        let executor = CommandExecutor::Host(super::super::HostExecutor::new());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!bindable(port));
+        // Only where the table exists, as on Linux
+        if let Some(ports) = listening_ports(&executor).await {
+            assert!(ports.contains(&port));
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Background services kept running while a plan executes.
+//!
+//! Some tasks need a process running beside them, such as a dev server that
+//! verification tests against. A plan declares these as services. Each is
+//! started through the executor as a background job, in its own process
+//! group where `setsid` exists, so it runs on the host or in the session container like any other
+//! command. Tasks start once its readiness probe passes, and it is checked
+//! before each task and restarted if it died. The whole process group is
+//! stopped when the plan ends. Started services are recorded in the session
+//! metadata, so ones a crashed run left behind are stopped when its session
+//! is recovered.
+//!
+//! ```toml
+//! [[services]]
+//! name = "web"
+//! command = "npm run dev"
+//! working_dir = "frontend"
+//! env = { PORT = "3000" }
+//! ready = { url = "http://localhost:3000" }
+//! ```
+
+use super::{CommandExecutor, ExecutionCommand};
+use crate::task::WaitCondition;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A process a plan keeps running while its tasks execute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceSpec {
+    /// Name used in logs; letters, digits, `-` and `_`
+    pub name: String,
+    /// Shell command running the service in the foreground
+    pub command: String,
+    /// Directory the command runs in, relative to the workspace
+    pub working_dir: Option<PathBuf>,
+    pub env: BTreeMap<String, String>,
+    /// Condition that holds once the service accepts work
+    pub ready: Option<WaitCondition>,
+    /// How long the service has to become ready
+    pub ready_timeout_secs: u64,
+    /// How long the service has to exit after `SIGTERM` before it is killed
+    pub stop_timeout_secs: u64,
+    /// Leave the service running when the plan ends
+    pub keep_running: bool,
+    /// Names of ports allocated for the service, as environment variables
+    pub ports: Vec<String>,
+}
+
+impl Default for ServiceSpec {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            working_dir: None,
+            env: BTreeMap::new(),
+            ready: None,
+            ready_timeout_secs: 120,
+            stop_timeout_secs: 10,
+            keep_running: false,
+            ports: Vec::new(),
+        }
+    }
+}
+
+impl ServiceSpec {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "Invalid service name '{}': use letters, digits, '-' and '_'",
+                self.name
+            );
+        }
+        if self.command.trim().is_empty() {
+            bail!("Service '{}' has no command", self.name);
+        }
+        super::ports::validate_names(&self.ports)
+    }
+
+    /// Script starting the service in the background
+    ///
+    /// Where `setsid` is available the service leads a process group of its
+    /// own, so stopping the group also stops anything the command started.
+    /// Prints `group` or `process`, then the service's pid.
+    fn start_script(&self, log_path: &Path) -> String {
+        let mut script = String::from(
+            "if command -v setsid >/dev/null 2>&1; then launch=setsid; echo group; \
+             else launch=; echo process; fi\n(",
+        );
+        if let Some(dir) = &self.working_dir {
+            script.push_str(&format!("cd {} && ", shell_quote(&dir.to_string_lossy())));
+        }
+        script.push_str(&format!(
+            "exec $launch sh -c {}) > {} 2>&1 < /dev/null &\necho $!\n",
+            shell_quote(&self.command),
+            shell_quote(&log_path.to_string_lossy())
+        ));
+        script
+    }
+}
+
+/// A started service, as recorded in the session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceRecord {
+    pub name: String,
+    /// Process id of the service
+    pub pid: u32,
+    /// Whether the service leads its own process group
+    #[serde(default)]
+    pub process_group: bool,
+    pub started_at: DateTime<Utc>,
+    /// Output of the service, relative to the workspace
+    pub log_path: PathBuf,
+    pub stop_timeout_secs: u64,
+    pub keep_running: bool,
+    /// Names of the ports allocated for the service
+    #[serde(default)]
+    pub ports: Vec<String>,
+}
+
+/// Start `spec` in the background, its output going to `log_path`
+///
+/// Commands run in the workspace root, and `log_path` is relative to it.
+/// Returns as soon as the service is launched; readiness is the caller's
+/// to check.
+pub async fn start(
+    executor: &CommandExecutor,
+    spec: &ServiceSpec,
+    workspace_root: &Path,
+    log_path: &Path,
+) -> Result<ServiceRecord> {
+    spec.validate()?;
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(workspace_root.join(parent))?;
+    }
+    let mut command = shell(spec.start_script(log_path));
+    command.env = spec.env.clone().into_iter().collect();
+    if !executor.is_container_executor() {
+        command = command.with_working_dir(workspace_root.to_path_buf());
+    }
+    let result = executor
+        .execute(command)
+        .await
+        .with_context(|| format!("Failed to start service '{}'", spec.name))?;
+    let mut lines = result.stdout.lines();
+    let process_group = lines.next() == Some("group");
+    let pid = lines
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .parse()
+        .with_context(|| format!("Service '{}' did not start: {}", spec.name, result.stderr))?;
+    Ok(ServiceRecord {
+        name: spec.name.clone(),
+        pid,
+        process_group,
+        started_at: Utc::now(),
+        log_path: log_path.to_path_buf(),
+        stop_timeout_secs: spec.stop_timeout_secs,
+        keep_running: spec.keep_running,
+        ports: spec.ports.clone(),
+    })
+}
+
+impl ServiceRecord {
+    /// `kill` operand addressing the service's processes
+    fn kill_target(&self) -> String {
+        if self.process_group {
+            format!("-{}", self.pid)
+        } else {
+            self.pid.to_string()
+        }
+    }
+
+    /// Command succeeding while a process of the service is alive
+    ///
+    /// Exited processes nobody reaped yet do not count.
+    fn alive_check(&self) -> String {
+        format!(
+            "ps -e -o {}= -o stat= | awk -v id={} '$1 == id && $2 !~ /^Z/ {{ alive = 1 }} END {{ exit !alive }}'",
+            if self.process_group { "pgid" } else { "pid" },
+            self.pid
+        )
+    }
+}
+
+/// Whether a process of the service is still alive
+pub async fn is_running(executor: &CommandExecutor, record: &ServiceRecord) -> bool {
+    executor
+        .execute(shell(record.alive_check()))
+        .await
+        .is_ok_and(|result| result.success())
+}
+
+/// Stop the service and the processes it started
+///
+/// They get `SIGTERM`, then `SIGKILL` if still running after the record's
+/// stop timeout. A service that already exited is not an error.
+pub async fn stop(executor: &CommandExecutor, record: &ServiceRecord) -> Result<()> {
+    let script = format!(
+        "kill -TERM {target} 2>/dev/null || exit 0\n\
+         i=0\n\
+         while {alive} && [ $i -lt {ticks} ]; do sleep 0.1; i=$((i+1)); done\n\
+         kill -KILL {target} 2>/dev/null || true\n",
+        target = record.kill_target(),
+        alive = record.alive_check(),
+        ticks = record.stop_timeout_secs * 10
+    );
+    let result = executor
+        .execute(shell(script))
+        .await
+        .with_context(|| format!("Failed to stop service '{}'", record.name))?;
+    if !result.success() {
+        bail!(
+            "Failed to stop service '{}': {}",
+            record.name,
+            result.stderr
+        );
+    }
+    Ok(())
+}
+
+fn shell(script: String) -> ExecutionCommand {
+    ExecutionCommand::new("sh", vec!["-c".to_string(), script])
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::executor::HostExecutor;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_spec_from_toml_and_validation() {
+        let spec: ServiceSpec = toml::from_str(
+            r#"
+            name = "web"
+            command = "npm run dev"
+            ready = { url = "http://localhost:3000" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            spec.ready,
+            Some(WaitCondition::Url("http://localhost:3000".to_string()))
+        );
+        assert_eq!(spec.ready_timeout_secs, 120);
+        assert!(spec.validate().is_ok());
+
+        let unnamed = ServiceSpec {
+            name: "my web".to_string(),
+            ..spec
+        };
+        assert!(unnamed.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_start_and_stop_process_group() {
+        let workspace = TempDir::new().unwrap();
+        std::fs::create_dir(workspace.path().join("app")).unwrap();
+        let executor = CommandExecutor::Host(HostExecutor::new());
+        let spec = ServiceSpec {
+            name: "web".to_string(),
+            // A child process that must be stopped with the service
+            command: "echo \"up on $PORT in $(basename $PWD)\"; sleep 60 & wait".to_string(),
+            working_dir: Some(PathBuf::from("app")),
+            env: BTreeMap::from([("PORT".to_string(), "3000".to_string())]),
+            stop_timeout_secs: 2,
+            ..Default::default()
+        };
+
+        let log_path = PathBuf::from("logs/service-web.log");
+        let record = start(&executor, &spec, workspace.path(), &log_path)
+            .await
+            .unwrap();
+        assert!(is_running(&executor, &record).await);
+        let log = workspace.path().join(&log_path);
+        for _ in 0..50 {
+            if std::fs::read_to_string(&log).is_ok_and(|log| !log.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "up on 3000 in app\n"
+        );
+
+        stop(&executor, &record).await.unwrap();
+        assert!(!is_running(&executor, &record).await);
+        // Stopping again is harmless
+        stop(&executor, &record).await.unwrap();
+    }
+}
@@ -80,14 +80,61 @@
 //! }
 //! ```
 
-use crate::claude::{ClaudeCodeInterface, ClaudeConfig};
-use crate::session::{SessionInitOptions, SessionManager, SessionManagerConfig};
+mod task_phases;
+
+use crate::claude::token_breakdown::estimate_tokens;
+use crate::claude::{ClaudeCodeInterface, ClaudeConfig, ContextBudgetConfig};
+use crate::daemon::DistributedConfig;
+use crate::events::{AgentEvent, EventBus};
+use crate::executor::{
+    EnvironmentReport, ExecutorCapabilities, PlacementConfig, PortAllocator, PortConfig,
+    ServiceSpec,
+};
+use crate::llm::instructions::{InstructionsConfig, InstructionsManager};
+use crate::llm::offline::{
+    HealthProbe, OFFLINE_REASON, OfflineConfig, OfflineMode, QueuedOffline, is_connectivity_error,
+};
+use crate::llm::{
+    AnonymizationConfig, BudgetAction, BudgetConfig, BudgetExceeded, BudgetManager, ProviderRouter,
+    ProviderType, Pseudonymizer, Route, RoutingConfig,
+};
+use crate::mcp::{McpConfig, McpServers};
+use crate::policy::{
+    PolicyConfig, PolicyEffect, PolicyEngine, PolicyFacts, PolicyGate, PolicyStage, PolicyViolation,
+};
+use crate::session::{
+    CostLedger, CostLedgerEntry, DirtySession, EventJournal, HeartbeatConfig, HeartbeatMonitor,
+    InteractionRecord, JournalEvent, LogRetention, LogRetentionConfig, MigrationManager,
+    MigrationOutcome, PayloadBytes, PromptSizeConfig, RemoteSyncConfig, ReportSigner,
+    ReportSigningConfig, RunReport, SessionInitOptions, SessionManager, SessionManagerConfig,
+    crash,
+};
 use crate::task::{
-    ErrorHandler, ErrorStrategy, OutputCondition, SetupCommand, SetupResult, TaskManager,
-    TaskManagerConfig, TaskSpec, TaskStatus,
+    AwaitingAnswer, BugFixReport, BugFixStage, CompletedPlanTask, CompletedTaskPolicy,
+    CompletionGateConfig, CompletionReport, ErrorHandler, ErrorStrategy, EstimateLedger,
+    EstimateSample, Finding, GateAction, GateDecision, HostSuspender, IDEMPOTENCY_TAG_PREFIX,
+    INTERRUPTED_REASON, MAX_FEEDBACK_CHARS, MAX_GATE_ATTEMPTS, MAX_QUICK_OUTPUT_CHARS,
+    OutputCondition, PRECOMPLETED_TAG, PackageCheck, PlanLoader, PlanRecord, PlanTaskChange,
+    PreemptionController, PriorityFile, PriorityOverrides, QUESTION_PROTOCOL_PROMPT,
+    QuestionAnswer, QuestionConfig, QuestionHandler, QuestionRequest, QuickReport,
+    QuickVerification, ReconciledTask, RefactorGuardConfig, RollupConfig, RollupSummary,
+    SUMMARY_PROTOCOL_PROMPT, ScheduleConfig, ScheduleDecision, SetupCommand, SetupResult, Signal,
+    SpotCheckConfig, StopFlag, SubPlanStack, SystemPackages, TASK_KEY_TAG_PREFIX,
+    TEST_COMMAND_BLOCK_END, TEST_COMMAND_BLOCK_START, TaskManager, TaskManagerConfig, TaskSpec,
+    TaskStatus, TaskSuspender, TestImpact, ToolPermissions, UserQuestion, VerificationConfig,
+    VerificationGroup, WAITING_REASON_PREFIX, WaitCondition, WaitConfig, WatchConfig,
+    affected_tests, bug_fix_task_spec, group_fix_task_spec, is_verify_point, output_tail,
+    parse_test_command, prompt_with_answer, quick_task_spec, resolve_sub_plan_path, review_prompt,
+    tagged_idempotency_key, task_fingerprint, task_key,
+};
+use crate::workspace::{
+    FileStat, SnapshotManager, TaskDiff, TextFileConfig, WorkspaceChanges, diff_stat,
 };
 use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::{info, warn};
 use uuid::Uuid;
@@ -95,9 +142,58 @@ use uuid::Uuid;
 /// Integrated agent system that combines task management, session persistence, and Claude integration
 pub struct AgentSystem {
     task_manager: Arc<TaskManager>,
-    session_manager: Arc<SessionManager>,
+    session_manager: SessionManager,
     claude_interface: Arc<ClaudeCodeInterface>,
     executor: crate::executor::CommandExecutor,
+    capabilities: ExecutorCapabilities,
+    instructions: InstructionsManager,
+    heartbeat: Arc<HeartbeatMonitor>,
+    log_retention: Arc<LogRetention>,
+    #[cfg(feature = "remote-sync")]
+    remote_sync: Option<crate::session::RemoteSync>,
+    schedule: ScheduleConfig,
+    cost_ledger: CostLedger,
+    estimate_ledger: EstimateLedger,
+    priority_file: PriorityFile,
+    preemption: PreemptionController,
+    max_concurrent_tasks: usize,
+    /// Held while session state or a checkpoint is written, so concurrently
+    /// running tasks never save at the same time
+    persistence_lock: tokio::sync::Mutex<()>,
+    questions: QuestionConfig,
+    refactor_guard: RefactorGuardConfig,
+    completion_gates: CompletionGateConfig,
+    budget: BudgetManager,
+    question_handler: std::sync::RwLock<Option<Arc<dyn QuestionHandler>>>,
+    plan_loader: std::sync::RwLock<Arc<dyn PlanLoader>>,
+    journal: EventJournal,
+    nix: crate::executor::NixConfig,
+    offline: OfflineMode,
+    router: ProviderRouter,
+    verification: VerificationConfig,
+    spot_check: SpotCheckConfig,
+    policy: Arc<PolicyGate>,
+    snapshots: SnapshotManager,
+    report_signer: Option<ReportSigner>,
+    anonymizer: Option<Pseudonymizer>,
+    prompt_size: PromptSizeConfig,
+    rollup: RollupConfig,
+    wait: WaitConfig,
+    /// Set when the run is interrupted; no new tasks start once it is
+    stop: StopFlag,
+    /// Tasks of the plan being executed, to save the unstarted ones when
+    /// the run is interrupted
+    plan_specs: std::sync::Mutex<Vec<TaskSpec>>,
+    /// Background services of the running plan; held while they are checked
+    services: tokio::sync::Mutex<Vec<ServiceSpec>>,
+    /// Ports allocated to the running plan
+    ports: PortAllocator,
+    events: EventBus,
+    /// Package declarations already found installed in this session
+    satisfied_packages: std::sync::Mutex<HashSet<SystemPackages>>,
+    /// Findings from completion gates tripped in this run
+    findings: std::sync::Mutex<Vec<Finding>>,
+    workspace_path: std::path::PathBuf,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,9 +203,100 @@ pub struct AgentConfig {
     pub task_config: TaskManagerConfig,
     pub claude_config: ClaudeConfig,
     pub setup_commands: Vec<SetupCommand>,
+    /// Background services kept running while the plan executes
+    #[serde(default)]
+    pub services: Vec<ServiceSpec>,
     /// Execution mode (host or container)
     #[serde(default)]
     pub execution_mode: crate::executor::RuntimeMode,
+    /// Docker, Podman or whichever is found first, for container execution
+    #[serde(default)]
+    pub container_runtime: crate::executor::ContainerRuntime,
+    /// Docker CLI context to connect to, e.g. `colima`; the current context
+    /// when unset
+    #[serde(default)]
+    pub docker_context: Option<String>,
+    /// Provider system prompt and instructions file handling
+    #[serde(default)]
+    pub instructions: InstructionsConfig,
+    /// Heartbeat reporting and stall detection for unattended runs
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    /// Allowed execution windows and provider quota headroom
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    /// Structured questions from the model to the user
+    #[serde(default)]
+    pub questions: QuestionConfig,
+    /// Verify-and-fix loop run by `aca watch`
+    #[serde(default)]
+    pub watch: WatchConfig,
+    /// Behavior lock around tasks tagged as refactors
+    #[serde(default)]
+    pub refactor_guard: RefactorGuardConfig,
+    /// Review and approval gates on self-reported confidence and risks
+    #[serde(default)]
+    pub completion_gates: CompletionGateConfig,
+    /// Estimated cost limits per session and per task
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    /// Age, size and compression limits for session logs
+    #[serde(default)]
+    pub log_retention: LogRetentionConfig,
+    /// Backup of checkpoints and run records to S3, GCS or a local directory
+    #[serde(default)]
+    pub remote_sync: RemoteSyncConfig,
+    /// Lease settings for `aca coordinator` and `aca worker`
+    #[serde(default)]
+    pub distributed: DistributedConfig,
+    /// Labels and capability overrides matched against task requirements
+    #[serde(default)]
+    pub placement: PlacementConfig,
+    /// Caps on history, pinned and imported context in task prompts
+    #[serde(default)]
+    pub context_budget: ContextBudgetConfig,
+    /// Nix flake development shell host commands run in
+    #[serde(default)]
+    pub nix: crate::executor::NixConfig,
+    /// Queueing of provider tasks while no provider is reachable
+    #[serde(default)]
+    pub offline: OfflineConfig,
+    /// Provider and model choice per task
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Verification of plan tasks in groups, with bisection on failure
+    #[serde(default)]
+    pub verification: VerificationConfig,
+    /// Mutation-style checks that critical tasks' tests cover their changes
+    #[serde(default)]
+    pub spot_check: SpotCheckConfig,
+    /// Organizational rules that deny tasks or hold them for approval
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    /// Sign the run report and ledgers when a run ends
+    #[serde(default)]
+    pub report_signing: ReportSigningConfig,
+    /// Pseudonymize configured names in prompts sent to cloud providers (experimental)
+    #[serde(default)]
+    pub anonymization: AnonymizationConfig,
+    /// MCP tool servers the Claude CLI is started with
+    #[serde(default)]
+    pub mcp: McpConfig,
+    /// Warn when a single prompt is larger than a configured size
+    #[serde(default)]
+    pub prompt_size: PromptSizeConfig,
+    /// Roll-up summaries of parents completed by their subtasks
+    #[serde(default)]
+    pub rollup: RollupConfig,
+    /// Polling of the external state tasks wait for
+    #[serde(default)]
+    pub wait: WaitConfig,
+    /// Ports allocated to every plan and the range they come from
+    #[serde(default)]
+    pub ports: PortConfig,
+    /// Which files diffs and pinned context read as text
+    #[serde(default)]
+    pub text_files: TextFileConfig,
 }
 
 impl AgentConfig {
@@ -119,116 +306,60 @@ impl AgentConfig {
         self.claude_config.show_subprocess_output = show_output;
         self
     }
+
+    /// Write the prompt sent for the task with this title or id prefix
+    /// (from `--dump-context`)
+    pub fn with_context_dump(mut self, task: Option<String>) -> Self {
+        self.claude_config.dump_context = task;
+        self
+    }
 }
 
 impl AgentSystem {
     pub async fn new(config: AgentConfig) -> Result<Self> {
-        // Extract workspace path and execution mode before moving config
-        let workspace_path = config.workspace_path.clone();
+        Self::migrate_workspace(&config.workspace_path)?;
+
+        // Continue the session a crash left dirty instead of starting over
+        if config.session_config.enable_crash_recovery
+            && let Some(dirty) = DirtySession::take(&config.workspace_path)
+        {
+            match dirty.latest_checkpoint(&config.workspace_path) {
+                Some(checkpoint_id) => {
+                    warn!(
+                        "Session {} crashed at {} (report: {}); resuming from checkpoint {}",
+                        dirty.session_id,
+                        dirty.crashed_at,
+                        dirty.crash_report.display(),
+                        checkpoint_id
+                    );
+                    return Self::new_from_checkpoint(config, &checkpoint_id).await;
+                }
+                None => warn!(
+                    "Session {} crashed at {} before its first checkpoint; starting a new session",
+                    dirty.session_id, dirty.crashed_at
+                ),
+            }
+        }
+
         let execution_mode = Some(config.execution_mode.clone());
 
         // Initialize session manager with execution mode
         let init_options = SessionInitOptions {
             execution_mode,
+            task_manager_config: config.task_config.clone(),
             ..Default::default()
         };
-        let session_manager = Arc::new(
-            SessionManager::new(config.workspace_path, config.session_config, init_options).await?,
-        );
-
-        // Get session ID for container naming
-        let session_id = session_manager.session_id();
-
-        // Initialize task manager
-        let task_manager = Arc::new(TaskManager::new(config.task_config.clone()));
-
-        // Initialize executor based on execution mode
-        let executor = match &config.execution_mode {
-            crate::executor::RuntimeMode::Host => {
-                info!("Using host executor");
-                crate::executor::CommandExecutor::Host(crate::executor::HostExecutor::new())
-            }
-            crate::executor::RuntimeMode::Container(container_config) => {
-                #[cfg(feature = "containers")]
-                {
-                    use crate::executor::container::ContainerExecutorConfig;
-                    use crate::executor::{ContainerExecutor, SystemResources};
-
-                    info!(
-                        "Initializing container executor with image: {} for session {}",
-                        container_config.image, session_id
-                    );
-
-                    // Only detect system resources if needed
-                    let (memory_bytes, cpu_quota) = match (
-                        container_config.memory_limit_bytes,
-                        container_config.cpu_quota,
-                    ) {
-                        (Some(mem), Some(cpu)) => {
-                            // Both limits explicitly provided, skip resource detection
-                            (Some(mem), Some(cpu))
-                        }
-                        _ => {
-                            // At least one limit missing, detect resources and calculate
-                            let resources = SystemResources::detect().map_err(|e| {
-                                anyhow::anyhow!("Failed to detect system resources: {}", e)
-                            })?;
-                            let allocation =
-                                resources.allocate_percentage(container_config.resource_percentage);
-                            (
-                                container_config
-                                    .memory_limit_bytes
-                                    .or(Some(allocation.memory_bytes)),
-                                container_config.cpu_quota.or(Some(allocation.cpu_quota)),
-                            )
-                        }
-                    };
-
-                    // Create executor config with session ID for lifecycle binding
-                    let exec_config = ContainerExecutorConfig {
-                        image: container_config.image.clone(),
-                        workspace_mount: workspace_path.clone(),
-                        aca_mount: crate::env::aca_dir_path(&workspace_path),
-                        memory_bytes,
-                        cpu_quota,
-                        auto_remove: true,
-                        session_id: Some(session_id),
-                    };
-
-                    let container_executor = ContainerExecutor::new(exec_config)
-                        .await
-                        .map_err(|e| {
-                            anyhow::anyhow!(
-                                "Container runtime unavailable. --use-containers requires Docker or Podman: {}",
-                                e
-                            )
-                        })?;
-
-                    crate::executor::CommandExecutor::Container(container_executor)
-                }
-
-                #[cfg(not(feature = "containers"))]
-                {
-                    return Err(anyhow::anyhow!(
-                        "Container support not compiled. Enable 'containers' feature."
-                    ));
-                }
-            }
-        };
-
-        // Initialize Claude interface
-        let claude_interface = Arc::new(
-            ClaudeCodeInterface::new(config.claude_config, workspace_path)
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to initialize Claude interface: {}", e))?,
-        );
+        let session_manager = SessionManager::new(
+            config.workspace_path.clone(),
+            config.session_config.clone(),
+            init_options,
+        )
+        .await?;
+
+        // Share the session's task manager so checkpoints record the tasks
+        let task_manager = session_manager.task_manager().clone();
 
-        let system = Self {
-            task_manager,
-            session_manager,
-            claude_interface,
-            executor,
-        };
+        let system = Self::assemble(config.clone(), session_manager, task_manager).await?;
 
         // Execute setup commands using the initialized executor
         if !config.setup_commands.is_empty() {
@@ -272,12 +403,11 @@ impl AgentSystem {
         config: AgentConfig,
         mut session_init: SessionInitOptions,
     ) -> Result<Self> {
-        // Extract workspace path and execution mode before moving config
-        let workspace_path = config.workspace_path.clone();
+        Self::migrate_workspace(&config.workspace_path)?;
         let execution_mode = Some(config.execution_mode.clone());
 
         // Update session init options with workspace and execution mode from config
-        session_init.workspace_root = workspace_path.clone();
+        session_init.workspace_root = config.workspace_path.clone();
         if session_init.execution_mode.is_none() {
             session_init.execution_mode = execution_mode;
         }
@@ -288,21 +418,95 @@ impl AgentSystem {
         );
 
         // Initialize session manager with custom init options
-        let session_manager = Arc::new(
-            SessionManager::new(
-                config.workspace_path.clone(),
-                config.session_config.clone(),
-                session_init,
-            )
-            .await?,
-        );
-
-        // Get session ID for container naming
-        let session_id = session_manager.session_id();
+        let session_manager = SessionManager::new(
+            config.workspace_path.clone(),
+            config.session_config.clone(),
+            session_init,
+        )
+        .await?;
 
         // Initialize task manager - will be populated by session restore if applicable
         let task_manager = session_manager.task_manager().clone();
 
+        let system = Self::assemble(config.clone(), session_manager, task_manager).await?;
+
+        // Continue the restored session's conversations and usage statistics
+        let conversation = system.session_manager.conversation_state().await;
+        if !conversation.is_empty() {
+            info!(
+                "Restored {} conversation context(s) from the previous session",
+                conversation.contexts.len()
+            );
+            system
+                .claude_interface
+                .restore_conversation_state(conversation)
+                .await;
+        }
+
+        // Services still recorded were left behind by a crash
+        system.stop_stale_services().await;
+
+        // Execute setup commands using the initialized executor
+        if !config.setup_commands.is_empty() {
+            info!("Executing setup commands with configured executor...");
+            system
+                .execute_setup_commands(&config.setup_commands)
+                .await?;
+        }
+
+        Ok(system)
+    }
+
+    /// Create an agent system that continues the session saved in a checkpoint
+    ///
+    /// The task tree, conversation contexts and usage statistics are restored,
+    /// so pending tasks pick up where the previous run stopped.
+    pub async fn new_from_checkpoint(config: AgentConfig, checkpoint_id: &str) -> Result<Self> {
+        let session_init = SessionInitOptions {
+            name: "Resumed Session".to_string(),
+            description: Some(format!("Session resumed from checkpoint {}", checkpoint_id)),
+            enable_auto_save: true,
+            restore_from_checkpoint: Some(checkpoint_id.to_string()),
+            ..Default::default()
+        };
+        Self::with_session_init(config, session_init).await
+    }
+
+    /// Upgrade the workspace's `.aca` data before any session touches it
+    fn migrate_workspace(workspace_path: &std::path::Path) -> Result<()> {
+        match MigrationManager::new(workspace_path).run()? {
+            MigrationOutcome::Migrated {
+                from,
+                to,
+                backup: Some(backup),
+            } => info!(
+                "Migrated .aca layout {} -> {} (backup in {})",
+                from,
+                to,
+                backup.display()
+            ),
+            MigrationOutcome::Migrated { from, to, .. } => {
+                info!("Migrated .aca layout {} -> {}", from, to)
+            }
+            MigrationOutcome::Fresh | MigrationOutcome::UpToDate => {}
+        }
+        Ok(())
+    }
+
+    /// Build the executor, instructions and Claude interface shared by all constructors
+    async fn assemble(
+        config: AgentConfig,
+        session_manager: SessionManager,
+        task_manager: Arc<TaskManager>,
+    ) -> Result<Self> {
+        let workspace_path = config.workspace_path.clone();
+        config.schedule.validate()?;
+        config.ports.validate()?;
+
+        // Get session ID for container naming
+        let session_id = session_manager.session_id();
+        crash::set_session(&workspace_path, &session_id.to_string(), &config);
+
         // Initialize executor based on execution mode
         let executor = match &config.execution_mode {
             crate::executor::RuntimeMode::Host => {
@@ -345,6 +549,35 @@ impl AgentSystem {
                         }
                     };
 
+                    // Resolve the workspace's devcontainer.json when asked to use it
+                    let devcontainer = if container_config.devcontainer {
+                        Some(Box::new(
+                            crate::executor::DevContainerSetup::discover(&workspace_path)
+                                .context("Failed to load the dev container configuration")?,
+                        ))
+                    } else {
+                        None
+                    };
+
+                    // Build the workspace's own Dockerfile when pointed at one
+                    let dockerfile = match &container_config.dockerfile {
+                        Some(_) if container_config.devcontainer => {
+                            return Err(anyhow::anyhow!(
+                                "Set either a dockerfile or devcontainer for container execution, not both"
+                            ));
+                        }
+                        Some(dockerfile) => Some(Box::new(crate::container::DockerfileBuild {
+                            dockerfile: workspace_path.join(dockerfile),
+                            context: workspace_path.join(
+                                container_config
+                                    .build_context
+                                    .as_deref()
+                                    .unwrap_or(std::path::Path::new("")),
+                            ),
+                        })),
+                        None => None,
+                    };
+
                     // Create executor config with session ID for lifecycle binding
                     let exec_config = ContainerExecutorConfig {
                         image: container_config.image.clone(),
@@ -354,18 +587,37 @@ impl AgentSystem {
                         cpu_quota,
                         auto_remove: true,
                         session_id: Some(session_id),
+                        devcontainer,
+                        dockerfile,
+                        container_name: None,
+                        runtime: config.container_runtime,
+                        docker_context: config.docker_context.clone(),
                     };
 
-                    let container_executor = ContainerExecutor::new(exec_config)
-                        .await
-                        .map_err(|e| {
-                            anyhow::anyhow!(
-                                "Container runtime unavailable. --use-containers requires Docker or Podman: {}",
+                    match ContainerExecutor::new(exec_config).await {
+                        Ok(container_executor) => {
+                            crate::executor::CommandExecutor::Container(container_executor)
+                        }
+                        // Without a runtime, run unisolated rather than not at all,
+                        // unless a context was chosen to run in
+                        Err(crate::executor::ExecutorError::ContainerUnavailable(e))
+                            if config.docker_context.is_none() =>
+                        {
+                            warn!(
+                                "No container runtime found, running commands on the host instead: {}",
                                 e
+                            );
+                            crate::executor::CommandExecutor::Host(
+                                crate::executor::HostExecutor::new(),
                             )
-                        })?;
-
-                    crate::executor::CommandExecutor::Container(container_executor)
+                        }
+                        Err(e) => {
+                            return Err(anyhow::anyhow!(
+                                "Failed to start container execution: {}",
+                                e
+                            ));
+                        }
+                    }
                 }
 
                 #[cfg(not(feature = "containers"))]
@@ -376,58 +628,453 @@ impl AgentSystem {
                 }
             }
         };
+        let capabilities = if executor.is_container_executor() {
+            ExecutorCapabilities::for_runtime(&config.execution_mode, &config.placement)
+        } else {
+            ExecutorCapabilities::for_runtime(
+                &crate::executor::RuntimeMode::Host,
+                &config.placement,
+            )
+        };
+
+        // Resolve provider instructions before handing the config to Claude
+        let mut instructions = InstructionsManager::new(
+            config.instructions.clone(),
+            ProviderType::ClaudeCode,
+            workspace_path.clone(),
+        )
+        .with_text_files(config.text_files.clone());
+        let mut claude_config = config.claude_config;
+        if claude_config.system_prompt.is_none() {
+            claude_config.system_prompt = Some(instructions.system_prompt());
+        }
+
+        // Fit pinned and imported context into the budget, keeping room for
+        // the system prompt and for history, which is fitted per request
+        let mut fixed_tokens = claude_config
+            .system_prompt
+            .as_deref()
+            .map(estimate_tokens)
+            .unwrap_or(0)
+            + config.context_budget.history_reserve();
+        if config.questions.enabled {
+            fixed_tokens += estimate_tokens(QUESTION_PROTOCOL_PROMPT);
+        }
+        if config.completion_gates.enabled {
+            fixed_tokens += estimate_tokens(SUMMARY_PROTOCOL_PROMPT);
+        }
+        let context = instructions.apply_budget(&config.context_budget, fixed_tokens);
+
+        if let Some(context) = instructions.imported_context() {
+            let prompt = claude_config.system_prompt.get_or_insert_with(String::new);
+            if !prompt.is_empty() {
+                prompt.push_str("\n\n");
+            }
+            prompt.push_str(&context);
+        }
+        if config.questions.enabled {
+            let prompt = claude_config.system_prompt.get_or_insert_with(String::new);
+            if !prompt.is_empty() {
+                prompt.push_str("\n\n");
+            }
+            prompt.push_str(QUESTION_PROTOCOL_PROMPT);
+        }
+        if config.completion_gates.enabled {
+            let prompt = claude_config.system_prompt.get_or_insert_with(String::new);
+            if !prompt.is_empty() {
+                prompt.push_str("\n\n");
+            }
+            prompt.push_str(SUMMARY_PROTOCOL_PROMPT);
+        }
+
+        // Check the MCP servers before handing them to the Claude CLI
+        let mcp = McpServers::start(
+            &config.mcp,
+            crate::env::mcp_config_file_path(&config.workspace_path, &session_id.to_string()),
+        )
+        .await?;
+
+        // Liveness reporting for unattended runs
+        let heartbeat = Arc::new(HeartbeatMonitor::new(
+            config.heartbeat,
+            session_id.to_string(),
+            crate::env::heartbeat_file_path(&config.workspace_path, &session_id.to_string()),
+        ));
+        heartbeat.start();
 
         // Initialize Claude interface
         let claude_interface = Arc::new(
-            ClaudeCodeInterface::new(config.claude_config, workspace_path)
+            ClaudeCodeInterface::new(claude_config, workspace_path)
                 .await
-                .map_err(|e| anyhow::anyhow!("Failed to initialize Claude interface: {}", e))?,
+                .map_err(|e| anyhow::anyhow!("Failed to initialize Claude interface: {}", e))?
+                .with_context(context)
+                .with_context_budget(config.context_budget.clone())
+                .with_mcp(mcp)
+                .with_heartbeat(heartbeat.clone()),
         );
 
+        // Check new tasks against the organization's policy rules
+        let policy = Arc::new(PolicyGate::new(
+            PolicyEngine::new(&config.policy)?,
+            EventJournal::for_workspace(&config.workspace_path),
+        ));
+        task_manager.set_policy(policy.clone());
+
+        // Load or create the report signing key up front so a bad key fails the run early
+        let report_signer = if config.report_signing.enabled {
+            Some(ReportSigner::load_or_create(
+                &config.report_signing.key_path(&config.workspace_path),
+            )?)
+        } else {
+            None
+        };
+        let anonymizer = Pseudonymizer::new(&config.anonymization, &config.workspace_path)?;
+
+        // Let the task manager freeze a paused task's subprocess and sandbox
+        task_manager.set_suspender(Arc::new(AgentSuspender {
+            host: HostSuspender::new(claude_interface.process_registry()),
+            executor: executor.clone(),
+        }));
+
+        // Keep session logs within the configured age and size limits
+        let log_retention = Arc::new(LogRetention::new(
+            config.log_retention,
+            config.workspace_path.clone(),
+        ));
+        log_retention.start(session_manager.clone());
+
+        // Unblock failed tasks once their retry delay has passed; tasks
+        // queued offline wait for the provider instead
+        task_manager.keep_blocked(OFFLINE_REASON);
+        task_manager.start_maintenance_loop();
+
+        // Mirror checkpoints to remote storage so ephemeral runners can resume
+        #[cfg(feature = "remote-sync")]
+        let remote_sync = crate::session::RemoteSync::from_config(
+            &config.remote_sync,
+            config.workspace_path.clone(),
+        )
+        .context("Failed to configure remote sync")?;
+        #[cfg(not(feature = "remote-sync"))]
+        if config.remote_sync.is_enabled() {
+            return Err(anyhow::anyhow!(
+                "Remote sync support not compiled. Enable 'remote-sync' feature."
+            ));
+        }
+
         let system = Self {
             task_manager,
             session_manager,
             claude_interface,
             executor,
+            capabilities,
+            instructions,
+            heartbeat,
+            log_retention,
+            #[cfg(feature = "remote-sync")]
+            remote_sync,
+            schedule: config.schedule,
+            cost_ledger: CostLedger::for_workspace(&config.workspace_path),
+            estimate_ledger: EstimateLedger::for_workspace(&config.workspace_path),
+            priority_file: PriorityFile::for_workspace(&config.workspace_path),
+            preemption: PreemptionController::new(config.task_config.max_concurrent_tasks as usize),
+            max_concurrent_tasks: (config.task_config.max_concurrent_tasks as usize).max(1),
+            persistence_lock: tokio::sync::Mutex::new(()),
+            questions: config.questions,
+            refactor_guard: config.refactor_guard,
+            completion_gates: config.completion_gates,
+            budget: BudgetManager::new(config.budget),
+            question_handler: std::sync::RwLock::new(None),
+            plan_loader: std::sync::RwLock::new(Arc::new(crate::cli::SubPlanParser::default())),
+            journal: EventJournal::for_workspace(&config.workspace_path),
+            nix: config.nix,
+            offline: OfflineMode::new(config.offline),
+            router: ProviderRouter::new(config.routing, config.workspace_path.clone()),
+            snapshots: SnapshotManager::new(
+                &config.workspace_path,
+                &config.verification.ignore,
+                crate::env::task_diffs_dir_path(&config.workspace_path, &session_id.to_string()),
+            )
+            .with_text_files(config.text_files.clone()),
+            verification: config.verification,
+            spot_check: config.spot_check,
+            policy,
+            report_signer,
+            anonymizer,
+            prompt_size: config.prompt_size,
+            rollup: config.rollup,
+            wait: config.wait,
+            ports: PortAllocator::new(config.ports),
+            stop: StopFlag::default(),
+            plan_specs: std::sync::Mutex::new(Vec::new()),
+            services: tokio::sync::Mutex::new(Vec::new()),
+            events: EventBus::default(),
+            satisfied_packages: std::sync::Mutex::new(HashSet::new()),
+            findings: std::sync::Mutex::new(Vec::new()),
+            workspace_path: config.workspace_path,
         };
+        system.apply_nix(&system.nix);
+        Ok(system)
+    }
 
-        // Execute setup commands using the initialized executor
-        if !config.setup_commands.is_empty() {
-            info!("Executing setup commands with configured executor...");
-            system
-                .execute_setup_commands(&config.setup_commands)
-                .await?;
+    /// Run host commands and the agent CLI in the Nix shell `config` resolves to
+    fn apply_nix(&self, config: &crate::executor::NixConfig) {
+        let crate::executor::CommandExecutor::Host(host) = &self.executor else {
+            if config.mode == crate::executor::NixMode::Always {
+                warn!("Nix development shells only apply to host execution, ignoring");
+            }
+            return;
+        };
+        let shell = config.resolve(&self.workspace_path);
+        if let Some(shell) = &shell {
+            info!(
+                "Running host commands in the Nix shell of {}",
+                shell.flake()
+            );
         }
-
-        Ok(system)
+        host.set_nix_shell(shell.clone());
+        self.claude_interface.set_nix_shell(shell);
     }
 
     /// Process a single task with Claude integration and full persistence
+    ///
+    /// A task left waiting for an answer to its question returns an
+    /// [`AwaitingAnswer`] error, like a task queued offline returns
+    /// [`QueuedOffline`].
     pub async fn process_task(&self, task_id: Uuid) -> Result<()> {
         // Get task from task manager
-        let task = self.task_manager.get_task(task_id).await?;
+        let mut task = self.task_manager.get_task(task_id).await?;
 
         tracing::info!("Processing task: {} - {}", task.id, task.title);
 
+        // A task resumed while awaiting input needs its answer before running
+        if let TaskStatus::AwaitingInput { question, .. } = &task.status {
+            let question = question.clone();
+            match self.ask_question(&task, &question).await? {
+                Some(answer) => {
+                    task.description = prompt_with_answer(&task.description, &question, &answer)
+                }
+                None => return Err(AwaitingAnswer { task_id }.into()),
+            }
+        }
+
+        // Refuse tasks this executor cannot provide for before starting them
+        if let Err(error) = crate::executor::place(
+            &task.title,
+            &task.metadata.requirements,
+            std::slice::from_ref(&self.capabilities),
+        ) {
+            self.task_manager
+                .update_task_status(
+                    task_id,
+                    TaskStatus::Failed {
+                        failed_at: chrono::Utc::now(),
+                        error: crate::task::types::TaskError::Other {
+                            message: error.to_string(),
+                            source: None,
+                        },
+                        retry_count: 0,
+                    },
+                )
+                .await?;
+            self.save_session_state().await?;
+            return Err(error.into());
+        }
+
+        // Without a reachable provider the task waits instead of failing
+        if self.offline.is_offline() {
+            return self.queue_offline(task_id).await;
+        }
+
+        // External state the task depends on, such as a dev server, must be ready
+        if !task.metadata.wait_for.is_empty() {
+            self.wait_for_conditions(&task).await?;
+        }
+
+        // Wait for a concurrency slot, preempting background work if critical
+        let mut slot = self
+            .preemption
+            .acquire(task_id, task.metadata.priority.clone())
+            .await;
+        // Only once the slot is held, so the snapshots leave out changes the
+        // tasks it waited for made
+        let mut snapshots = self.take_snapshots(&task).await?;
+
         // Update task status to in progress
+        let started_at = chrono::Utc::now();
         self.task_manager
             .update_task_status(
                 task_id,
                 TaskStatus::InProgress {
-                    started_at: chrono::Utc::now(),
+                    started_at,
                     estimated_completion: None,
                 },
             )
             .await?;
+        self.events.emit(AgentEvent::TaskStarted {
+            task_id,
+            title: task.title.clone(),
+        });
 
         // Save current state
         self.save_session_state().await?;
 
-        // Process with Claude
-        let result = self.claude_interface.process_task(&task).await;
+        // Process on the routed provider, restarting the task if it stalls or
+        // is preempted
+        let route = self.router.route(&task.metadata);
+        let mut stall_restarts = 0;
+        let mut questions_asked = 0;
+        let mut reviewed = false;
+        let mut gate_decisions = Vec::new();
+        let result = loop {
+            // Each turn, including the first, must fit the cost budget
+            if let Err(exceeded) = self.budget.check(task_id) {
+                self.heartbeat.task_finished(task_id);
+                return self.stop_over_budget(task_id, exceeded).await;
+            }
+            // ...and the policy rules, now that its provider and cost are known
+            let facts = PolicyFacts::for_task(&task.title, &task.description, &task.metadata)
+                .with_route(&route)
+                .with_costs(self.budget.task_spent(task_id), self.budget.session_spent());
+            if let Err(violation) = self
+                .policy
+                .check(PolicyStage::Execute, Some(task_id), &facts)
+            {
+                self.heartbeat.task_finished(task_id);
+                return self.stop_for_policy(task_id, violation).await;
+            }
+            self.heartbeat.task_started(task.id, &task.title);
+            let preempted = tokio::select! {
+                result = self.run_routed_turn(&task, &route) => {
+                    self.record_interactions().await;
+                    let Some((turn, question)) = result
+                        .as_ref()
+                        .ok()
+                        .and_then(|turn| Some((turn, self.pending_question(turn, questions_asked)?)))
+                    else {
+                        // A low-confidence or risky turn gets one review pass
+                        if let Ok(turn) = &result
+                            && !reviewed
+                            && let Some(decision) = self.completion_gate(turn, false)
+                            && decision.action == GateAction::Review
+                        {
+                            self.record_task_cost(turn);
+                            reviewed = true;
+                            info!("Reviewing task {}: {}", task_id, decision.reason());
+                            task.description = review_prompt(&task.description, &decision);
+                            gate_decisions.push(decision);
+                            continue;
+                        }
+                        break result;
+                    };
+                    self.record_task_cost(turn);
+                    questions_asked += 1;
+
+                    self.heartbeat.task_finished(task_id);
+                    let Some(answer) = self.ask_question(&task, &question).await? else {
+                        drop(slot);
+                        return Err(AwaitingAnswer { task_id }.into());
+                    };
+                    task.description = prompt_with_answer(&task.description, &question, &answer);
+
+                    self.task_manager
+                        .update_task_status(
+                            task_id,
+                            TaskStatus::InProgress {
+                                started_at: chrono::Utc::now(),
+                                estimated_completion: None,
+                            },
+                        )
+                        .await?;
+                    self.save_session_state().await?;
+                    false
+                }
+                // The turn's subprocess is still registered until this resolves
+                _ = async {
+                    slot.preempted().await;
+                    self.kill_task_processes(task_id).await;
+                } => true,
+                _ = async {
+                    self.heartbeat.wait_for_stall(task_id).await;
+                    self.kill_task_processes(task_id).await;
+                } => {
+                    warn!("Task {} stalled with no activity, checkpointing", task_id);
+                    self.save_session_checkpoint(&format!("stall_detected_{}", task_id))
+                        .await?;
+
+                    if stall_restarts >= self.heartbeat.max_stall_restarts() {
+                        break Err(crate::claude::ClaudeError::Unknown(format!(
+                            "Task stalled after {} restart(s)",
+                            stall_restarts
+                        )));
+                    }
+
+                    stall_restarts += 1;
+                    self.heartbeat.record_stall_restart();
+                    info!(
+                        "Restarting stalled task {} (attempt {}/{})",
+                        task_id,
+                        stall_restarts,
+                        self.heartbeat.max_stall_restarts()
+                    );
+                    false
+                }
+            };
+
+            if preempted {
+                slot = self.pause_preempted(&task, slot).await?;
+            }
+        };
+        drop(slot);
+        self.heartbeat.task_finished(task_id);
+
+        let task_diff = self.record_changes(task_id, &mut snapshots);
+
+        match result {
+            Ok(mut completed_task) => {
+                self.record_task_cost(&completed_task);
+                let elapsed = chrono::Utc::now() - started_at;
+                self.record_task_estimate(&completed_task, elapsed);
+                attach_duration(&mut completed_task, elapsed);
+                self.verify_completed(&mut completed_task, &snapshots, task_diff.as_ref())
+                    .await?;
+
+                // Tasks the model is unsure of, or that made risky changes,
+                // wait for approval instead of completing
+                if let Some(report) = completed_task.response().and_then(CompletionReport::parse) {
+                    attach_completion_report(&mut completed_task, &report);
+                }
+                let decision = self.completion_gate(&completed_task, reviewed);
+                gate_decisions.extend(decision.clone());
+                self.record_gate_findings(
+                    &task.title,
+                    &gate_decisions,
+                    task_diff.as_ref().map(|diff| &diff.changes),
+                );
+                if let Some(decision) = decision
+                    && decision.action == GateAction::Approval
+                {
+                    let reason = decision.reason();
+                    warn!("Task {} needs approval: {}", task_id, reason);
+                    self.task_manager
+                        .update_task_status(
+                            task_id,
+                            TaskStatus::Blocked {
+                                reason: format!("Awaiting approval: {}", reason).into(),
+                                blocked_at: chrono::Utc::now(),
+                                retry_after: None,
+                            },
+                        )
+                        .await?;
+                    self.save_session_state().await?;
+                    self.update_heartbeat_progress().await;
+                    return Err(anyhow::anyhow!(
+                        "Task {} awaits approval: {}",
+                        task_id,
+                        reason
+                    ));
+                }
 
-        match result {
-            Ok(completed_task) => {
                 // Update task status to completed
                 self.task_manager
                     .update_task_status(task_id, completed_task.status)
@@ -435,28 +1082,47 @@ impl AgentSystem {
 
                 // Save session state
                 self.save_session_state().await?;
+                self.update_heartbeat_progress().await;
 
                 tracing::info!("Task completed successfully: {}", task_id);
                 Ok(())
             }
             Err(e) => {
+                if self.offline.enabled()
+                    && is_connectivity_error(&e.to_string())
+                    && !self.offline.refresh().await
+                {
+                    return self.queue_offline(task_id).await;
+                }
+
                 // Mark task as failed
+                let error = match &e {
+                    crate::claude::ClaudeError::Timeout { timeout_secs } => {
+                        crate::task::types::TaskError::TimeoutError {
+                            operation: "Claude CLI request".to_string(),
+                            timeout_duration: chrono::Duration::seconds(*timeout_secs as i64),
+                            elapsed_time: chrono::Utc::now() - started_at,
+                        }
+                    }
+                    _ => crate::task::types::TaskError::ClaudeError {
+                        message: format!("Failed: {}", e),
+                        error_code: None,
+                        retry_possible: true,
+                    },
+                };
                 self.task_manager
                     .update_task_status(
                         task_id,
                         TaskStatus::Failed {
                             failed_at: chrono::Utc::now(),
-                            error: crate::task::types::TaskError::ClaudeError {
-                                message: format!("Failed: {}", e),
-                                error_code: None,
-                                retry_possible: true,
-                            },
+                            error,
                             retry_count: 0,
                         },
                     )
                     .await?;
 
                 self.save_session_state().await?;
+                self.update_heartbeat_progress().await;
 
                 tracing::error!("Task failed: {} - {}", task_id, e);
                 Err(anyhow::anyhow!("Task processing failed: {}", e))
@@ -464,6 +1130,571 @@ impl AgentSystem {
         }
     }
 
+    /// Poll a task's wait conditions, failing it if one is not met in time
+    ///
+    /// The task shows as blocked on the condition while it waits.
+    async fn wait_for_conditions(&self, task: &crate::task::Task) -> Result<()> {
+        let started = std::time::Instant::now();
+        let deadline = started + self.wait.timeout();
+        for condition in &task.metadata.wait_for {
+            self.task_manager
+                .update_task_status(
+                    task.id,
+                    TaskStatus::Blocked {
+                        reason: format!("{}{}", WAITING_REASON_PREFIX, condition).into(),
+                        blocked_at: chrono::Utc::now(),
+                        retry_after: None,
+                    },
+                )
+                .await?;
+            self.save_session_state().await?;
+            info!("Task {} waiting for {}", task.id, condition);
+
+            let met = self
+                .wait
+                .poll(deadline, || self.wait_condition_met(condition))
+                .await;
+            if !met {
+                let error = crate::task::types::TaskError::TimeoutError {
+                    operation: format!("waiting for {}", condition),
+                    timeout_duration: chrono::Duration::seconds(self.wait.timeout_secs as i64),
+                    elapsed_time: chrono::Duration::from_std(started.elapsed()).unwrap_or_default(),
+                };
+                let message = error.to_string();
+                self.task_manager
+                    .update_task_status(
+                        task.id,
+                        TaskStatus::Failed {
+                            failed_at: chrono::Utc::now(),
+                            error,
+                            retry_count: 0,
+                        },
+                    )
+                    .await?;
+                self.save_session_state().await?;
+                anyhow::bail!("Task {} failed: {}", task.id, message);
+            }
+        }
+        Ok(())
+    }
+
+    async fn wait_condition_met(&self, condition: &WaitCondition) -> bool {
+        match condition {
+            WaitCondition::Url(url) => {
+                crate::task::wait::url_responds(&self.ports.expand(url)).await
+            }
+            WaitCondition::File(path) => crate::task::wait::file_exists(&self.workspace_path, path),
+            WaitCondition::Command(command) => self
+                .run_verification(command)
+                .await
+                .is_ok_and(|result| result.success()),
+        }
+    }
+
+    /// Register the handler that answers model questions
+    ///
+    /// Without one, a task that asks a question is left awaiting input.
+    pub fn set_question_handler(&self, handler: Arc<dyn QuestionHandler>) {
+        *self.question_handler.write().unwrap() = Some(handler);
+    }
+
+    /// Register the parser used for tasks that expand into a sub-plan
+    ///
+    /// Defaults to naive parsing of task lists.
+    pub fn set_plan_loader(&self, loader: Arc<dyn PlanLoader>) {
+        *self.plan_loader.write().unwrap() = loader;
+    }
+
+    /// Question asked in a finished turn, if the task may still ask one
+    fn pending_question(&self, turn: &crate::task::Task, asked: u32) -> Option<UserQuestion> {
+        if !self.questions.enabled {
+            return None;
+        }
+        let question = UserQuestion::parse(turn.response()?)?;
+
+        if asked >= self.questions.max_questions_per_task {
+            warn!(
+                "Task {} asked more than {} question(s), ignoring: {}",
+                turn.id, self.questions.max_questions_per_task, question.question
+            );
+            return None;
+        }
+        Some(question)
+    }
+
+    /// Gate that applies to a finished turn's completion report, if any
+    fn completion_gate(&self, turn: &crate::task::Task, reviewed: bool) -> Option<GateDecision> {
+        let report = turn.response().and_then(CompletionReport::parse);
+        self.completion_gates.evaluate(report.as_ref(), reviewed)
+    }
+
+    /// Keep the gates a finished task tripped as findings for SARIF export
+    fn record_gate_findings(
+        &self,
+        task_title: &str,
+        decisions: &[GateDecision],
+        changes: Option<&WorkspaceChanges>,
+    ) {
+        let mut findings = self.findings.lock().unwrap();
+        for decision in decisions {
+            findings.extend(decision.findings(task_title, changes));
+        }
+    }
+
+    /// Findings from the completion gates tripped so far in this run
+    pub fn findings(&self) -> Vec<Finding> {
+        self.findings.lock().unwrap().clone()
+    }
+
+    /// Mark the task awaiting input and get an answer from the question handler
+    ///
+    /// Returns `None` when nobody answered; the task then stays
+    /// [`TaskStatus::AwaitingInput`] and is asked again when it is resumed.
+    async fn ask_question(
+        &self,
+        task: &crate::task::Task,
+        question: &UserQuestion,
+    ) -> Result<Option<QuestionAnswer>> {
+        let request = QuestionRequest {
+            id: Uuid::new_v4(),
+            task_id: task.id,
+            task_title: task.title.clone(),
+            question: question.clone(),
+            asked_at: chrono::Utc::now(),
+        };
+
+        info!("Task {} asks: {}", task.id, question.question);
+        self.task_manager
+            .update_task_status(
+                task.id,
+                TaskStatus::AwaitingInput {
+                    question: question.clone(),
+                    asked_at: request.asked_at,
+                },
+            )
+            .await?;
+        self.save_session_state().await?;
+        self.journal.record(JournalEvent::QuestionAsked {
+            question_id: request.id,
+            task_id: task.id,
+            task_title: task.title.clone(),
+            question: question.question.clone(),
+        })?;
+
+        let handler = self.question_handler.read().unwrap().clone();
+        let answer = match handler {
+            Some(handler) => match handler.ask(&request).await {
+                Ok(answer) => answer,
+                Err(e) => {
+                    self.task_manager
+                        .update_task_status(
+                            task.id,
+                            TaskStatus::Failed {
+                                failed_at: chrono::Utc::now(),
+                                error: crate::task::types::TaskError::Other {
+                                    message: format!("Question not answered: {}", e),
+                                    source: Some(question.question.clone()),
+                                },
+                                retry_count: 0,
+                            },
+                        )
+                        .await?;
+                    self.save_session_state().await?;
+                    return Err(e.context(format!("Task {} could not get an answer", task.id)));
+                }
+            },
+            None => None,
+        };
+        let Some(answer) = answer else {
+            warn!("Task {} is awaiting input: {}", task.id, question.question);
+            return Ok(None);
+        };
+
+        self.journal.record(JournalEvent::QuestionAnswered {
+            question_id: request.id,
+            task_id: task.id,
+            question: question.question.clone(),
+            answer: answer.answer.clone(),
+            answered_by: answer.answered_by.clone(),
+        })?;
+        Ok(Some(answer))
+    }
+
+    /// Move the interface's new interaction records into the session state
+    async fn record_interactions(&self) {
+        for interaction in self.claude_interface.take_interactions().await {
+            self.record_interaction(interaction).await;
+        }
+        self.session_manager
+            .record_conversation_state(self.claude_interface.conversation_state().await)
+            .await;
+    }
+
+    /// Persist one provider call, warning if its prompt was oversized
+    async fn record_interaction(&self, interaction: InteractionRecord) {
+        if self.prompt_size.is_oversized(&interaction) {
+            warn!(
+                "Prompt for task {} sent to {} was {} KB, over the {} KB warning size; \
+                 check for bloated context",
+                interaction.task_id,
+                interaction.provider,
+                interaction.payload.sent / 1024,
+                self.prompt_size.warn_bytes / 1024
+            );
+        }
+        self.session_manager.record_interaction(interaction).await;
+    }
+
+    /// Record a call to a routed provider, which keeps no records of its own
+    async fn record_routed_interaction(
+        &self,
+        task_id: Uuid,
+        route: &Route,
+        started_at: chrono::DateTime<chrono::Utc>,
+        bytes_sent: u64,
+        result: &std::result::Result<crate::task::Task, crate::claude::ClaudeError>,
+    ) {
+        let output = match result {
+            Ok(crate::task::Task {
+                status:
+                    TaskStatus::Completed {
+                        result: crate::task::types::TaskResult::Success { output, .. },
+                        ..
+                    },
+                ..
+            }) => Some(output),
+            _ => None,
+        };
+        let usage = output.and_then(|output| {
+            serde_json::from_value::<crate::claude::TokenUsage>(output["token_usage"].clone()).ok()
+        });
+        let text = |key: &str| {
+            output
+                .and_then(|output| output[key].as_str())
+                .map(str::to_string)
+        };
+        let interaction = InteractionRecord {
+            request_id: task_id,
+            task_id,
+            provider: text("provider").unwrap_or_else(|| format!("{:?}", route.provider)),
+            model: text("model_used")
+                .or_else(|| route.model.clone())
+                .unwrap_or_default(),
+            started_at,
+            duration_ms: (chrono::Utc::now() - started_at).num_milliseconds().max(0) as u64,
+            input_tokens: usage.as_ref().map_or(0, |usage| usage.input_tokens),
+            output_tokens: usage.as_ref().map_or(0, |usage| usage.output_tokens),
+            estimated_cost: usage.as_ref().map_or(0.0, |usage| usage.estimated_cost),
+            exit_code: None,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            log_files: Vec::new(),
+            payload: PayloadBytes {
+                sent: bytes_sent,
+                received: text("response").map_or(0, |response| response.len() as u64),
+            },
+        };
+        self.record_interaction(interaction).await;
+    }
+
+    /// Append the task's usage to the workspace cost ledger
+    fn record_task_cost(&self, task: &crate::task::Task) {
+        let TaskStatus::Completed {
+            result: crate::task::types::TaskResult::Success { output, .. },
+            ..
+        } = &task.status
+        else {
+            return;
+        };
+        let Some(usage) = output.get("token_usage").and_then(|usage| {
+            serde_json::from_value::<crate::claude::TokenUsage>(usage.clone()).ok()
+        }) else {
+            return;
+        };
+
+        self.events.emit(AgentEvent::TokensUsed {
+            task_id: task.id,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            estimated_cost: usage.estimated_cost,
+        });
+        self.budget.record(task.id, usage.estimated_cost);
+        let entry = CostLedgerEntry::new(
+            self.session_manager.session_id().to_string(),
+            task.id,
+            task.title.clone(),
+            task.metadata.cost_tags(),
+            &usage,
+        );
+        if let Err(e) = self.cost_ledger.append(&entry) {
+            warn!("Failed to record task cost: {}", e);
+        }
+    }
+
+    /// Pause or abort a task whose session or task budget is spent
+    async fn stop_over_budget(&self, task_id: Uuid, exceeded: BudgetExceeded) -> Result<()> {
+        warn!("Stopping task {}: {}", task_id, exceeded);
+        match self.budget.config().on_exceeded {
+            BudgetAction::Pause => {
+                self.task_manager
+                    .block_over_budget(task_id, &exceeded)
+                    .await?
+            }
+            BudgetAction::Abort => {
+                self.task_manager
+                    .update_task_status(
+                        task_id,
+                        TaskStatus::Failed {
+                            failed_at: chrono::Utc::now(),
+                            error: crate::task::types::TaskError::ResourceError {
+                                resource_type: "cost".to_string(),
+                                limit_exceeded: format!("${:.2}", exceeded.limit),
+                                current_usage: format!("${:.2}", exceeded.spent),
+                            },
+                            retry_count: 0,
+                        },
+                    )
+                    .await?
+            }
+        }
+        self.save_session_state().await?;
+        self.update_heartbeat_progress().await;
+        Err(exceeded.into())
+    }
+
+    /// Stop a task a policy rule matched: fail it, or block it for approval
+    async fn stop_for_policy(&self, task_id: Uuid, violation: PolicyViolation) -> Result<()> {
+        let status = match violation.effect {
+            PolicyEffect::Deny => TaskStatus::Failed {
+                failed_at: chrono::Utc::now(),
+                error: crate::task::types::TaskError::Other {
+                    message: violation.to_string(),
+                    source: None,
+                },
+                retry_count: 0,
+            },
+            PolicyEffect::RequireApproval => TaskStatus::Blocked {
+                reason: violation.to_string().into(),
+                blocked_at: chrono::Utc::now(),
+                retry_after: None,
+            },
+        };
+        self.task_manager
+            .update_task_status(task_id, status)
+            .await?;
+        self.save_session_state().await?;
+        self.update_heartbeat_progress().await;
+        Err(violation.into())
+    }
+
+    /// Run one turn of a task on the provider and model it is routed to
+    async fn run_routed_turn(
+        &self,
+        task: &crate::task::Task,
+        route: &Route,
+    ) -> std::result::Result<crate::task::Task, crate::claude::ClaudeError> {
+        self.events.emit(AgentEvent::ProviderCallStarted {
+            task_id: task.id,
+            provider: route.provider.clone(),
+            model: route.model.clone(),
+        });
+        let started = std::time::Instant::now();
+        // Local models see the real names; cloud providers get placeholders
+        let anonymizer = self
+            .anonymizer
+            .as_ref()
+            .filter(|_| route.provider != ProviderType::LocalModel);
+        let original = task;
+        let outgoing = anonymizer.map(|anonymizer| anonymizer.pseudonymize_task(task));
+        let task = outgoing.as_ref().unwrap_or(task);
+        let system_message =
+            self.claude_interface
+                .system_message()
+                .map(|message| match anonymizer {
+                    Some(anonymizer) => anonymizer.pseudonymize(&message),
+                    None => message,
+                });
+        let result = if route.is_claude_code() {
+            self.claude_interface
+                .process_task_with_system_message(task, route.model.as_deref(), system_message)
+                .await
+        } else {
+            let logs_dir = crate::env::session_logs_dir_path(
+                &self.workspace_path,
+                &self.session_manager.session_id().to_string(),
+            );
+            let bytes_sent =
+                task.description.len() + system_message.as_ref().map_or(0, String::len);
+            let started_at = chrono::Utc::now();
+            let result = self
+                .router
+                .process_task(task, route, system_message, &logs_dir)
+                .await
+                .map_err(|e| crate::claude::ClaudeError::Unknown(e.to_string()));
+            self.record_routed_interaction(task.id, route, started_at, bytes_sent as u64, &result)
+                .await;
+            result
+        };
+        let result = result.map(|mut completed| {
+            if let Some(anonymizer) = anonymizer {
+                anonymizer.restore_task(original, &mut completed);
+            }
+            completed
+        });
+        self.events.emit(AgentEvent::ProviderCallFinished {
+            task_id: task.id,
+            provider: route.provider.clone(),
+            success: result.is_ok(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        result
+    }
+
+    /// Leave a task blocked until a provider is reachable again
+    async fn queue_offline(&self, task_id: Uuid) -> Result<()> {
+        info!("Queueing task {} until a provider is reachable", task_id);
+        self.task_manager
+            .update_task_status(
+                task_id,
+                TaskStatus::Blocked {
+                    reason: OFFLINE_REASON.into(),
+                    blocked_at: chrono::Utc::now(),
+                    retry_after: Some(self.offline.retry_after()),
+                },
+            )
+            .await?;
+        self.save_session_state().await?;
+        self.update_heartbeat_progress().await;
+        Err(QueuedOffline { task_id }.into())
+    }
+
+    /// Kill the provider subprocess running a task and the tools it started
+    ///
+    /// Dropping the turn only kills the CLI itself, not its process group.
+    async fn kill_task_processes(&self, task_id: Uuid) {
+        let Some(pid) = self.claude_interface.process_registry().pid(task_id) else {
+            return;
+        };
+        if let Err(e) = crate::task::kill_process_group(pid).await {
+            warn!("Failed to kill the processes of task {}: {}", task_id, e);
+        }
+    }
+
+    /// Run tasks without a provider preference on `provider` from now on;
+    /// `None` goes back to the configured routing
+    pub fn set_session_provider(&self, provider: Option<ProviderType>) {
+        self.router.set_session_provider(provider);
+    }
+
+    /// Provider chosen with [`set_session_provider`](Self::set_session_provider)
+    pub fn session_provider(&self) -> Option<ProviderType> {
+        self.router.session_provider()
+    }
+
+    /// Replace the probe deciding whether a provider is reachable
+    ///
+    /// Defaults to checking the Claude Code CLI and its API endpoint.
+    pub fn set_health_probe(&self, probe: Arc<dyn HealthProbe>) {
+        self.offline.set_probe(probe);
+    }
+
+    /// Run the tasks queued while no provider was reachable
+    ///
+    /// Waits up to the configured time for a provider to return, then runs
+    /// the queued tasks whose dependencies are complete, in dependency order.
+    /// Returns the ids of the tasks that completed; if the provider does not
+    /// return, the tasks stay blocked for a later run.
+    pub async fn resume_offline_tasks(&self) -> Result<Vec<Uuid>> {
+        let mut queued = self
+            .task_manager
+            .get_tasks_by_status(
+                |status| matches!(status, TaskStatus::Blocked { reason, .. } if reason.text() == Some(OFFLINE_REASON)),
+            )
+            .await?;
+        if queued.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if !self.offline.wait_until_online().await {
+            self.save_session_checkpoint("offline_queue").await?;
+            warn!(
+                "No provider reachable; {} task(s) stay queued until the next run",
+                queued.len()
+            );
+            return Ok(Vec::new());
+        }
+
+        info!("Running {} task(s) queued while offline", queued.len());
+        let mut completed = Vec::new();
+        while !queued.is_empty() {
+            // Dependencies missing from the tree completed in an earlier run
+            let mut ready = None;
+            'queued: for (index, task_id) in queued.iter().enumerate() {
+                let task = self.task_manager.get_task(*task_id).await?;
+                for dependency in &task.dependencies {
+                    if let Ok(dependency) = self.task_manager.get_task(*dependency).await
+                        && !matches!(dependency.status, TaskStatus::Completed { .. })
+                    {
+                        continue 'queued;
+                    }
+                }
+                ready = Some(index);
+                break;
+            }
+            let Some(index) = ready else {
+                for task_id in &queued {
+                    warn!(
+                        "Not running queued task {}: a task it depends on did not complete",
+                        task_id
+                    );
+                }
+                break;
+            };
+
+            let task_id = queued.remove(index);
+            match self.process_task(task_id).await {
+                Ok(()) => completed.push(task_id),
+                Err(e) if e.downcast_ref::<QueuedOffline>().is_some() => {
+                    warn!("Provider unreachable again; leaving queued tasks blocked");
+                    break;
+                }
+                Err(e) if e.downcast_ref::<AwaitingAnswer>().is_some() => {
+                    info!("Queued task {} is awaiting input", task_id)
+                }
+                Err(e) => tracing::error!("Queued task {} failed: {}", task_id, e),
+            }
+        }
+        Ok(completed)
+    }
+
+    /// Append the task's estimated and actual duration to the calibration ledger
+    fn record_task_estimate(&self, task: &crate::task::Task, elapsed: chrono::TimeDelta) {
+        let Some(sample) = EstimateSample::from_task(task, elapsed) else {
+            return;
+        };
+        if let Err(e) = self.estimate_ledger.append(&sample) {
+            warn!("Failed to record task duration: {}", e);
+        }
+    }
+
+    /// Spend per cost tag for tasks processed by this agent
+    pub async fn cost_report(&self) -> Vec<crate::claude::usage_tracker::TagUsage> {
+        self.claude_interface.tag_usage().await
+    }
+
+    /// Usage of this run, including prompt cache reads, writes and savings
+    pub async fn usage_totals(&self) -> crate::claude::usage_tracker::TotalUsage {
+        self.claude_interface.total_usage().await
+    }
+
+    /// Refresh heartbeat progress counters from the task manager
+    async fn update_heartbeat_progress(&self) {
+        if let Ok(stats) = self.task_manager.get_statistics().await {
+            self.heartbeat
+                .set_progress(stats.completed_tasks, stats.total_tasks);
+        }
+    }
+
     /// Create and process a new task
     pub async fn create_and_process_task(&self, title: &str, description: &str) -> Result<Uuid> {
         // Create task spec
@@ -487,7 +1718,12 @@ impl AgentSystem {
                     environment_vars: std::collections::HashMap::new(),
                     claude_context_keys: Vec::new(),
                 },
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
+            plan: None,
         };
 
         // Create task in task manager
@@ -503,9 +1739,10 @@ impl AgentSystem {
     }
 
     /// Execute a complete execution plan with setup commands and tasks
-    pub async fn execute_plan(&self, plan: crate::task::ExecutionPlan) -> Result<Vec<uuid::Uuid>> {
-        use tracing::{error, info, warn};
-
+    pub async fn execute_plan(
+        &self,
+        mut plan: crate::task::ExecutionPlan,
+    ) -> Result<Vec<uuid::Uuid>> {
         info!("Executing plan: {}", plan.summary());
 
         // Validate the execution plan
@@ -516,8 +1753,59 @@ impl AgentSystem {
             ));
         }
 
-        let mut task_ids = Vec::new();
+        // Fail before setup commands run if any task cannot be placed
+        for task_spec in &plan.task_specs {
+            crate::executor::place(
+                &task_spec.title,
+                &task_spec.metadata.requirements,
+                std::slice::from_ref(&self.capabilities),
+            )?;
+        }
+
+        // Plan-level cost tags apply to every task in the plan
+        let plan_cost_tags: Vec<&String> = plan
+            .metadata
+            .tags
+            .iter()
+            .filter(|tag| tag.starts_with(crate::task::COST_TAG_PREFIX))
+            .collect();
+        for task_spec in &mut plan.task_specs {
+            for tag in &plan_cost_tags {
+                if !task_spec.metadata.tags.contains(tag) {
+                    task_spec.metadata.tags.push((*tag).clone());
+                }
+            }
+        }
+
+        // Skip tasks an earlier run of the same plan file already completed
+        let plan_record = self.apply_plan_idempotency(&mut plan).await?;
+        if !plan.has_tasks() && !plan.has_setup_commands() {
+            info!("All tasks in plan were already completed; nothing to run");
+            return Ok(Vec::new());
+        }
+        *self.plan_specs.lock().unwrap() = plan.task_specs.clone();
+
+        // Write provider instructions file (CLAUDE.md / AGENTS.md) if configured
+        self.instructions.prepare()?;
+
+        // A plan may choose its own Nix shell; probes must already run in it
+        self.apply_nix(plan.nix.as_ref().unwrap_or(&self.nix));
+
+        // Describe the environment to prompts and setup command conditions
+        self.probe_environment().await;
+
+        // Reserve the ports the plan's commands and prompts refer to
+        let listening = self.allocate_ports(&plan).await?;
+        let result = self.set_up_and_run_plan(plan, plan_record).await;
+        self.release_ports(listening).await;
+        result
+    }
 
+    async fn set_up_and_run_plan(
+        &self,
+        plan: crate::task::ExecutionPlan,
+        plan_record: Option<PlanRecord>,
+    ) -> Result<Vec<uuid::Uuid>> {
         // Phase 1: Execute setup commands
         if plan.has_setup_commands() {
             info!("Executing {} setup commands...", plan.setup_command_count());
@@ -525,35 +1813,195 @@ impl AgentSystem {
             info!("Setup commands completed successfully");
         }
 
-        // Phase 2: Process tasks based on execution mode
-        if plan.has_tasks() {
-            info!(
-                "Processing {} tasks with mode: {:?}",
-                plan.task_count(),
-                plan.execution_mode
-            );
+        // Phase 2: Start background services, then run the tasks
+        self.start_services(&plan.services).await?;
+        let result = self.run_plan_tasks(plan, plan_record).await;
+        self.stop_services().await;
+        result
+    }
 
-            let total_tasks = plan.task_count();
+    /// Allocate the ports `plan` names and describe them to prompts
+    ///
+    /// Returns the ports already listening, to tell leaked listeners from
+    /// them at teardown.
+    async fn allocate_ports(
+        &self,
+        plan: &crate::task::ExecutionPlan,
+    ) -> Result<Option<std::collections::BTreeSet<u16>>> {
+        let mut names: Vec<String> = self.ports.configured_names().to_vec();
+        names.extend(plan.ports.iter().cloned());
+        names.extend(
+            plan.services
+                .iter()
+                .flat_map(|service| service.ports.iter().cloned()),
+        );
 
-            match plan.execution_mode {
-                crate::task::ExecutionMode::Sequential => {
-                    // Execute tasks one by one
-                    for (index, task_spec) in plan.task_specs.into_iter().enumerate() {
-                        let task_num = index + 1;
+        let listening = crate::executor::ports::listening_ports(&self.executor).await;
+        if names.is_empty() {
+            return Ok(listening);
+        }
+        let on_host = !self.executor.is_container_executor();
+        self.ports.allocate(&names, |port| {
+            !listening
+                .as_ref()
+                .is_some_and(|listening| listening.contains(&port))
+                && (!on_host || crate::executor::ports::bindable(port))
+        })?;
+        for (name, port) in self.ports.ports() {
+            info!("Allocated port {} as {}", port, name);
+        }
+        self.describe_environment().await;
+        Ok(listening)
+    }
 
-                        info!(
-                            "Processing task {}/{}: {}",
-                            task_num, total_tasks, task_spec.title
-                        );
+    /// Report listeners the plan left behind and give up its ports
+    ///
+    /// Ports listening now that were not when the plan started are leaked,
+    /// except those of services left running on purpose. Where listening
+    /// ports cannot be read, the allocated ports are checked on the host.
+    async fn release_ports(&self, listening_before: Option<std::collections::BTreeSet<u16>>) {
+        let allocated = self.ports.release();
+        let kept: Vec<String> = self
+            .session_manager
+            .services()
+            .await
+            .into_iter()
+            .filter(|record| record.keep_running)
+            .flat_map(|record| record.ports)
+            .collect();
+        let name_of = |port: u16| {
+            allocated
+                .iter()
+                .find(|(_, allocated)| **allocated == port)
+                .map(|(name, _)| name.clone())
+        };
 
-                        match self.create_and_process_task_spec(task_spec).await {
-                            Ok(task_id) => {
-                                info!(
-                                    "Task {}/{} completed successfully: {}",
-                                    task_num, total_tasks, task_id
-                                );
-                                task_ids.push(task_id);
-                            }
+        let listening_after = crate::executor::ports::listening_ports(&self.executor).await;
+        let leaked: Vec<u16> = match (listening_before, listening_after) {
+            (Some(before), Some(after)) => after.difference(&before).copied().collect(),
+            _ if !self.executor.is_container_executor() => allocated
+                .values()
+                .copied()
+                .filter(|port| !crate::executor::ports::bindable(*port))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for port in leaked {
+            let name = name_of(port);
+            if name.as_ref().is_some_and(|name| kept.contains(name)) {
+                continue;
+            }
+            warn!(
+                "Port {}{} is still listening after the plan finished; a process it started was left running",
+                port,
+                name.as_ref()
+                    .map(|name| format!(" ({})", name))
+                    .unwrap_or_default()
+            );
+            self.events.emit(AgentEvent::ListenerLeaked { port, name });
+        }
+
+        if !allocated.is_empty() {
+            self.describe_environment().await;
+        }
+    }
+
+    /// Describe the environment and the reserved ports in task system messages
+    async fn describe_environment(&self) {
+        let sections: Vec<String> = self
+            .session_manager
+            .environment_report()
+            .await
+            .map(|report| report.prompt_section())
+            .into_iter()
+            .chain(self.ports.prompt_section())
+            .collect();
+        if !sections.is_empty() {
+            self.claude_interface.set_environment(sections.join("\n\n"));
+        }
+    }
+
+    /// Run the tasks of `plan`, whose setup is done
+    async fn run_plan_tasks(
+        &self,
+        mut plan: crate::task::ExecutionPlan,
+        mut plan_record: Option<PlanRecord>,
+    ) -> Result<Vec<Uuid>> {
+        use tracing::{error, info, warn};
+
+        let mut task_ids = Vec::new();
+
+        // Find out up front whether provider tasks can run or must be queued
+        if plan.task_specs.iter().any(needs_provider) {
+            self.offline.refresh().await;
+        }
+
+        // Process tasks based on execution mode
+        if plan.has_tasks() {
+            info!(
+                "Processing {} tasks with mode: {:?}",
+                plan.task_count(),
+                plan.execution_mode
+            );
+
+            let total_tasks = plan.task_count();
+            let source = plan.source.clone();
+            let mut remaining = std::mem::take(&mut plan.task_specs);
+            let mut overrides = self.load_priority_overrides();
+
+            // Verify tasks in groups rather than after each one
+            let verification = plan
+                .verification
+                .clone()
+                .unwrap_or_else(|| self.verification.clone());
+            let mut group = self.start_verification_group(&verification);
+
+            match plan.execution_mode {
+                crate::task::ExecutionMode::Sequential => {
+                    // Execute tasks one by one
+                    let mut task_num = 0;
+                    while !remaining.is_empty() && !self.stop_requested() {
+                        self.wait_for_schedule_window().await?;
+                        let Some(task_spec) =
+                            self.next_plan_task(&mut remaining, &mut overrides).await?
+                        else {
+                            break;
+                        };
+                        task_num += 1;
+
+                        info!(
+                            "Processing task {}/{}: {}",
+                            task_num, total_tasks, task_spec.title
+                        );
+                        let title = task_spec.title.clone();
+                        let verify_point = is_verify_point(&task_spec);
+
+                        match self
+                            .create_and_process_plan_task(
+                                task_spec,
+                                source.as_deref(),
+                                &mut plan_record,
+                            )
+                            .await
+                        {
+                            Ok(task_id) => {
+                                info!(
+                                    "Task {}/{} completed successfully: {}",
+                                    task_num, total_tasks, task_id
+                                );
+                                task_ids.push(task_id);
+                                if let Some(group) = group.as_mut() {
+                                    group.record(Some(task_id), &title)?;
+                                    if verification.is_due(verify_point, group.len()) {
+                                        task_ids.extend(
+                                            self.verify_group(&verification, group, false).await?,
+                                        );
+                                    }
+                                }
+                            }
+                            Err(e) if held_task(&e).is_some() => {
+                                info!("Task {}/{} held: {}", task_num, total_tasks, e);
+                            }
                             Err(e) => {
                                 error!("Task {}/{} failed: {}", task_num, total_tasks, e);
                                 // For sequential mode, we can choose to continue or fail
@@ -563,17 +2011,20 @@ impl AgentSystem {
                         }
                     }
                 }
-                crate::task::ExecutionMode::Parallel { max_concurrent: _ } => {
-                    warn!(
-                        "Parallel execution mode not yet fully implemented, falling back to sequential"
-                    );
-                    // TODO: Implement parallel execution with semaphore for max_concurrent
-                    // For now, fall back to sequential execution
-                    for task_spec in plan.task_specs {
-                        match self.create_and_process_task_spec(task_spec).await {
-                            Ok(task_id) => task_ids.push(task_id),
-                            Err(e) => warn!("Task failed in parallel mode: {}", e),
-                        }
+                crate::task::ExecutionMode::Parallel { max_concurrent } => {
+                    task_ids = self
+                        .execute_parallel_plan_tasks(
+                            remaining,
+                            max_concurrent,
+                            source.as_deref(),
+                            &mut plan_record,
+                            &mut overrides,
+                        )
+                        .await?;
+                    // Concurrent tasks' edits interleave, so the plan is
+                    // verified once as a whole
+                    if let Some(group) = group.as_mut() {
+                        group.record(None, "parallel plan tasks")?;
                     }
                 }
                 crate::task::ExecutionMode::Intelligent => {
@@ -582,16 +2033,45 @@ impl AgentSystem {
                     );
                     // TODO: Implement intelligent scheduling based on task metadata
                     // For now, fall back to sequential execution
-                    for task_spec in plan.task_specs {
-                        match self.create_and_process_task_spec(task_spec).await {
+                    while !remaining.is_empty() && !self.stop_requested() {
+                        self.wait_for_schedule_window().await?;
+                        let Some(task_spec) =
+                            self.next_plan_task(&mut remaining, &mut overrides).await?
+                        else {
+                            break;
+                        };
+                        match self
+                            .create_and_process_plan_task(
+                                task_spec,
+                                source.as_deref(),
+                                &mut plan_record,
+                            )
+                            .await
+                        {
                             Ok(task_id) => task_ids.push(task_id),
                             Err(e) => warn!("Task failed in intelligent mode: {}", e),
                         }
                     }
                 }
             }
+
+            if self.stop_requested() {
+                info!("Run interrupted after {} task(s)", task_ids.len());
+                return Ok(task_ids);
+            }
+
+            // The plan ends with the full command, also after groups that
+            // only ran their affected tests
+            if let Some(group) = group.as_mut()
+                && (!group.is_empty() || (verification.affected_only && !group.baseline_verified()))
+            {
+                task_ids.extend(self.verify_group(&verification, group, true).await?);
+            }
         }
 
+        // Tasks queued while offline run once a provider is back
+        task_ids.extend(self.resume_offline_tasks().await?);
+
         // Save session state with checkpoint after plan execution
         self.save_session_checkpoint("plan_execution_complete")
             .await?;
@@ -603,406 +2083,3352 @@ impl AgentSystem {
         Ok(task_ids)
     }
 
-    /// Create and process a task from a TaskSpec (internal helper)
-    async fn create_and_process_task_spec(
+    /// Snapshot the workspace for batched verification, when a command is set
+    fn start_verification_group(&self, config: &VerificationConfig) -> Option<VerificationGroup> {
+        config.command.as_ref()?;
+        match VerificationGroup::start(&self.workspace_path, &config.ignore) {
+            Ok(group) => Some(group),
+            Err(e) => {
+                warn!(
+                    "Plan will not be verified, workspace snapshot failed: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Verify the tasks recorded in `group` and start the next group
+    ///
+    /// Unless `full`, the command is narrowed to the tests affected by the
+    /// group's changes when `affected_only` is set. When verification fails,
+    /// the group is bisected to find the first task after which it fails,
+    /// and a fix task is run as that task's subtask. Returns the ids of the
+    /// fix tasks created.
+    async fn verify_group(
         &self,
-        task_spec: crate::task::TaskSpec,
-    ) -> Result<uuid::Uuid> {
-        // Create task in the task manager
-        let task_id = self.task_manager.create_task(task_spec, None).await?;
+        config: &VerificationConfig,
+        group: &mut VerificationGroup,
+        full: bool,
+    ) -> Result<Vec<Uuid>> {
+        let Some(full_command) = config.command.as_deref() else {
+            return Ok(Vec::new());
+        };
+        let impact = if full || !config.affected_only {
+            TestImpact::Full
+        } else {
+            affected_tests(full_command, &self.workspace_path, &group.changed_files())
+        };
+        let command = match &impact {
+            TestImpact::Full => full_command,
+            TestImpact::Affected(command) => command.as_str(),
+            TestImpact::Unaffected => {
+                info!(
+                    "No tests affected by the last {} task(s), skipping verification",
+                    group.len()
+                );
+                group.reset(group.baseline_verified())?;
+                return Ok(Vec::new());
+            }
+        };
 
-        // Process the task
-        self.process_task(task_id).await?;
+        info!("Verifying {} task(s) with `{}`", group.len(), command);
+        let result = self.run_verification(command).await?;
+        if result.success() {
+            info!("Verification passed");
+            // Only the full command makes a trusted baseline for bisection
+            group.reset(impact == TestImpact::Full)?;
+            return Ok(Vec::new());
+        }
+        warn!("Verification failed (exit code {})", result.exit_code);
+
+        let culprit = if config.bisect {
+            group
+                .bisect(|count| async move {
+                    tracing::debug!("Verifying the state after {} task(s)", count);
+                    Ok(self.run_verification(command).await?.success())
+                })
+                .await?
+        } else if group.len() == 1 {
+            Some(0)
+        } else {
+            None
+        };
+        let culprit = culprit
+            .map(|index| &group.tasks()[index])
+            .filter(|task| task.task_id.is_some());
+        match culprit {
+            Some(task) => warn!("Verification first fails after task '{}'", task.title),
+            None => warn!("Verification failure not attributed to a single task"),
+        }
 
-        Ok(task_id)
+        let spec = group_fix_task_spec(command, &result, culprit, group, config.max_output_chars);
+        let fix_id = self
+            .task_manager
+            .create_task(spec, culprit.and_then(|task| task.task_id))
+            .await?;
+        if let Err(e) = self.process_task(fix_id).await {
+            warn!("Verification fix task {} failed: {}", fix_id, e);
+        }
+        group.reset(false)?;
+        Ok(vec![fix_id])
     }
 
-    /// Convert AgentConfig to ExecutionPlan (for structured config mode)
-    pub fn agent_config_to_execution_plan(config: &AgentConfig) -> crate::task::ExecutionPlan {
-        use tracing::info;
+    /// Run plan tasks concurrently, starting each once its dependencies are done
+    ///
+    /// At most `max_concurrent` tasks run at a time, capped by the task
+    /// manager's `max_concurrent_tasks`. A dependency on another task of the
+    /// plan is met once that task is completed in the task tree, so tasks
+    /// depending on one that failed are left unrun. Results are recorded one
+    /// at a time as tasks finish.
+    async fn execute_parallel_plan_tasks(
+        &self,
+        mut remaining: Vec<TaskSpec>,
+        max_concurrent: Option<usize>,
+        source: Option<&std::path::Path>,
+        plan_record: &mut Option<PlanRecord>,
+        overrides: &mut PriorityOverrides,
+    ) -> Result<Vec<Uuid>> {
+        let limit = max_concurrent
+            .unwrap_or(self.max_concurrent_tasks)
+            .clamp(1, self.max_concurrent_tasks);
+        let total_tasks = remaining.len();
+        info!("Running up to {} tasks concurrently", limit);
+
+        // Plan ids of the tasks in this run, mapped to tree ids as they finish
+        let plan_ids: HashSet<Uuid> = remaining
+            .iter()
+            .map(|spec| crate::task::plan_task_id(&spec.title))
+            .collect();
+        let mut tree_ids: HashMap<Uuid, Uuid> = HashMap::new();
 
-        info!(
-            "Converting AgentConfig to ExecutionPlan with {} setup commands",
-            config.setup_commands.len()
-        );
+        let mut task_ids = Vec::new();
+        let mut running = FuturesUnordered::new();
+        let mut started = 0;
+        let mut held = false;
+        loop {
+            // Start ready tasks while slots are free
+            let mut ready_but_held = false;
+            while running.len() < limit && !remaining.is_empty() && !self.stop_requested() {
+                if !running.is_empty() && !self.schedule_allows_start().await? {
+                    break;
+                }
+                self.wait_for_schedule_window().await?;
+                self.reload_priority_overrides(overrides);
 
-        let plan_name = format!(
-            "Structured Config: {}",
-            config
-                .workspace_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("workspace")
-        );
+                let mut ready = Vec::with_capacity(remaining.len());
+                for spec in &remaining {
+                    ready.push(self.plan_dependencies_met(spec, &plan_ids, &tree_ids).await);
+                }
+                let Some(index) = overrides.next_index_where(&remaining, |index| ready[index])
+                else {
+                    ready_but_held = ready.contains(&true);
+                    break;
+                };
+                if held {
+                    info!("Hold lifted, resuming execution");
+                    held = false;
+                }
 
-        let plan = crate::task::ExecutionPlan::new()
-            .with_setup_commands(config.setup_commands.clone())
-            .with_metadata(
-                plan_name,
-                "Execution plan generated from structured TOML configuration",
-            )
-            .with_tags(vec![
-                "structured-config".to_string(),
-                "auto-generated".to_string(),
-            ])
-            .with_sequential_execution();
+                let mut task_spec = remaining.remove(index);
+                if let Some(priority) = overrides
+                    .get(&task_spec)
+                    .and_then(|entry| entry.priority.clone())
+                {
+                    task_spec.metadata.priority = priority;
+                }
+                // Map plan dependencies to the tree tasks that satisfied them
+                for dependency in &mut task_spec.dependencies {
+                    if let Some(task_id) = tree_ids.get(dependency) {
+                        *dependency = *task_id;
+                    }
+                }
 
-        info!("Created execution plan: {}", plan.summary());
-        plan
-    }
+                started += 1;
+                info!(
+                    "Starting task {}/{}: {}",
+                    started, total_tasks, task_spec.title
+                );
+                let plan_id = crate::task::plan_task_id(&task_spec.title);
+                let identity = plan_task_identity(&task_spec);
+                let title = task_spec.title.clone();
+                running.push(async move {
+                    let result = self.run_plan_task(task_spec, source).await;
+                    (plan_id, identity, title, result)
+                });
+            }
 
-    /// Get system status
-    pub async fn get_system_status(&self) -> Result<SystemStatus> {
-        let task_stats = self.task_manager.get_statistics().await?;
-        let claude_status = self.claude_interface.get_interface_status().await;
-        let session_stats = self.session_manager.get_session_statistics().await?;
+            if running.is_empty() {
+                if remaining.is_empty() || self.stop_requested() {
+                    break;
+                }
+                if !ready_but_held && self.offline.is_offline() {
+                    self.queue_plan_specs(remaining, &mut tree_ids, true)
+                        .await?;
+                    break;
+                }
+                if !ready_but_held {
+                    for spec in &remaining {
+                        warn!(
+                            "Not running '{}': a task it depends on did not complete",
+                            spec.title
+                        );
+                    }
+                    break;
+                }
+                if !held {
+                    info!(
+                        "All ready task(s) are on hold in {:?}, waiting",
+                        self.priority_file.path()
+                    );
+                    self.save_session_checkpoint("priority_hold").await?;
+                    held = true;
+                }
+                tokio::time::sleep(self.schedule.poll_interval()).await;
+                continue;
+            }
 
-        Ok(SystemStatus {
-            task_stats: task_stats.clone(),
-            claude_status: claude_status.clone(),
-            session_stats,
-            is_healthy: claude_status.is_healthy && task_stats.total_tasks > 0,
-        })
-    }
+            let Some((plan_id, identity, title, result)) = running.next().await else {
+                continue;
+            };
+            match result {
+                Ok(task_id) => {
+                    info!("Task completed successfully: {} ({})", title, task_id);
+                    tree_ids.insert(plan_id, task_id);
+                    self.record_plan_task(plan_record, identity, task_id);
+                    task_ids.push(task_id);
+                }
+                Err(e) => match held_task(&e) {
+                    Some(task_id) => {
+                        info!("Task '{}' held: {}", title, e);
+                        tree_ids.insert(plan_id, task_id);
+                    }
+                    None => tracing::error!("Task '{}' failed: {}", title, e),
+                },
+            }
+        }
 
-    /// Save current session state
-    async fn save_session_state(&self) -> Result<()> {
-        // Only save session state, don't create checkpoint
-        // Checkpoints are created at major milestones (plan completion, shutdown)
-        self.session_manager.save_session().await?;
-        Ok(())
+        Ok(task_ids)
     }
 
-    /// Save session state and create a checkpoint
-    async fn save_session_checkpoint(&self, description: &str) -> Result<()> {
-        self.session_manager.save_session().await?;
-        self.session_manager
-            .create_checkpoint(description.to_string())
-            .await?;
-        Ok(())
+    /// Add plan tasks that did not start to the tree, to run later
+    ///
+    /// Tasks are added once all their plan dependencies are in the tree, so
+    /// each depends on the tree tasks it will wait for when resumed; other
+    /// dependencies completed in an earlier run and are dropped. When
+    /// `offline`, tasks are queued until a provider is reachable, otherwise
+    /// they are left pending for `aca resume`.
+    async fn queue_plan_specs(
+        &self,
+        mut remaining: Vec<TaskSpec>,
+        tree_ids: &mut HashMap<Uuid, Uuid>,
+        offline: bool,
+    ) -> Result<()> {
+        let plan_ids: HashSet<Uuid> = remaining
+            .iter()
+            .map(|spec| crate::task::plan_task_id(&spec.title))
+            .collect();
+        while let Some(index) = remaining.iter().position(|spec| {
+            spec.dependencies.iter().all(|dependency| {
+                !plan_ids.contains(dependency) || tree_ids.contains_key(dependency)
+            })
+        }) {
+            let mut task_spec = remaining.remove(index);
+            let plan_id = crate::task::plan_task_id(&task_spec.title);
+            let mut dependencies = Vec::new();
+            for dependency in std::mem::take(&mut task_spec.dependencies) {
+                if let Some(task_id) = tree_ids.get(&dependency) {
+                    dependencies.push(*task_id);
+                } else if self.task_manager.get_task(dependency).await.is_ok() {
+                    dependencies.push(dependency);
+                }
+            }
+            task_spec.dependencies = dependencies;
+            let task_id = if offline {
+                info!(
+                    "Queueing '{}' until a provider is reachable",
+                    task_spec.title
+                );
+                let task_id = self.task_manager.create_task(task_spec, None).await?;
+                self.task_manager
+                    .update_task_status(
+                        task_id,
+                        TaskStatus::Blocked {
+                            reason: OFFLINE_REASON.into(),
+                            blocked_at: chrono::Utc::now(),
+                            retry_after: Some(self.offline.retry_after()),
+                        },
+                    )
+                    .await?;
+                task_id
+            } else {
+                info!("Saving '{}' to run with `aca resume`", task_spec.title);
+                self.task_manager.create_task(task_spec, None).await?
+            };
+            tree_ids.insert(plan_id, task_id);
+        }
+        for spec in &remaining {
+            warn!(
+                "Not running '{}': a task it depends on did not complete",
+                spec.title
+            );
+        }
+        self.save_session_state().await
     }
 
-    /// Graceful shutdown
-    pub async fn shutdown(&self) -> Result<()> {
-        tracing::info!("Shutting down agent system...");
-
-        // Save final state with checkpoint
-        self.save_session_checkpoint("agent_shutdown").await?;
+    /// Whether every task `spec` depends on has completed
+    ///
+    /// Dependencies on tasks of the running plan wait for them to finish;
+    /// other dependencies are looked up in the task tree, and ones found in
+    /// neither were completed by an earlier run of the plan.
+    async fn plan_dependencies_met(
+        &self,
+        spec: &TaskSpec,
+        plan_ids: &HashSet<Uuid>,
+        tree_ids: &HashMap<Uuid, Uuid>,
+    ) -> bool {
+        for dependency in &spec.dependencies {
+            let task_id = match tree_ids.get(dependency) {
+                Some(task_id) => *task_id,
+                None if plan_ids.contains(dependency) => return false,
+                None => *dependency,
+            };
+            if let Ok(task) = self.task_manager.get_task(task_id).await
+                && !matches!(task.status, TaskStatus::Completed { .. })
+            {
+                return false;
+            }
+        }
+        true
+    }
 
-        // Graceful shutdown of session manager (this also creates a session_shutdown checkpoint)
-        self.session_manager.shutdown().await?;
+    /// Whether the schedule allows starting a task right now
+    async fn schedule_allows_start(&self) -> Result<bool> {
+        if !self.schedule.is_constrained() {
+            return Ok(true);
+        }
+        let tokens_used = self.claude_interface.tokens_used_today().await;
+        Ok(matches!(
+            self.schedule.check(tokens_used)?,
+            ScheduleDecision::Run
+        ))
+    }
 
-        // Shutdown executor (cleanup containers if any)
-        self.executor
-            .shutdown()
-            .await
-            .map_err(|e| anyhow::anyhow!("Executor shutdown failed: {}", e))?;
+    /// Block until the schedule allows starting another task
+    ///
+    /// Checkpoints once when the run pauses so an interrupted wait can be
+    /// resumed, then re-checks the constraints every poll interval.
+    async fn wait_for_schedule_window(&self) -> Result<()> {
+        if !self.schedule.is_constrained() {
+            return Ok(());
+        }
 
-        // Clear container info in session metadata since container is now removed
-        #[cfg(feature = "containers")]
-        if self.executor.is_container_executor() {
-            self.session_manager.clear_container_info().await;
+        let mut paused = false;
+        loop {
+            let tokens_used = self.claude_interface.tokens_used_today().await;
+            match self.schedule.check(tokens_used)? {
+                ScheduleDecision::Run => {
+                    if paused {
+                        info!("Schedule constraints satisfied, resuming execution");
+                    }
+                    return Ok(());
+                }
+                ScheduleDecision::Pause { reason } => {
+                    if !paused {
+                        info!("Pausing execution: {}", reason);
+                        self.save_session_checkpoint("schedule_pause").await?;
+                        paused = true;
+                    }
+                    tokio::time::sleep(self.schedule.poll_interval()).await;
+                }
+            }
         }
+    }
 
-        tracing::info!("Agent system shutdown complete");
-        Ok(())
+    /// Priority overrides in effect when a plan starts
+    fn load_priority_overrides(&self) -> PriorityOverrides {
+        match self.priority_file.load() {
+            Ok(overrides) => {
+                if !overrides.is_empty() {
+                    info!(
+                        "Applying priority overrides from {:?}",
+                        self.priority_file.path()
+                    );
+                }
+                overrides
+            }
+            Err(e) => {
+                warn!("Ignoring priority overrides: {:#}", e);
+                PriorityOverrides::default()
+            }
+        }
     }
 
-    // ============================================================================
-    // Setup Command Execution
-    // ============================================================================
+    /// Take the next plan task to run, honoring `.aca/priorities.toml`
+    ///
+    /// Re-reads the override file before every pick and journals each entry
+    /// that changed since the last read. Waits while every remaining task is
+    /// held.
+    async fn next_plan_task(
+        &self,
+        remaining: &mut Vec<TaskSpec>,
+        overrides: &mut PriorityOverrides,
+    ) -> Result<Option<TaskSpec>> {
+        let mut held = false;
+        loop {
+            if remaining.is_empty() {
+                return Ok(None);
+            }
+            self.reload_priority_overrides(overrides);
 
-    /// Execute all setup commands with error handling
-    async fn execute_setup_commands(&self, commands: &[SetupCommand]) -> Result<()> {
-        if commands.is_empty() {
-            return Ok(());
+            if let Some(index) = overrides.next_index(remaining) {
+                if held {
+                    info!("Hold lifted, resuming execution");
+                }
+                let mut task_spec = remaining.remove(index);
+                if let Some(priority) = overrides
+                    .get(&task_spec)
+                    .and_then(|entry| entry.priority.clone())
+                {
+                    task_spec.metadata.priority = priority;
+                }
+                return Ok(Some(task_spec));
+            }
+
+            if !held {
+                info!(
+                    "All {} remaining task(s) are on hold in {:?}, waiting",
+                    remaining.len(),
+                    self.priority_file.path()
+                );
+                self.save_session_checkpoint("priority_hold").await?;
+                held = true;
+            }
+            tokio::time::sleep(self.schedule.poll_interval()).await;
         }
+    }
 
-        info!("Running {} setup commands...", commands.len());
+    /// Re-read the override file, journaling what a human changed
+    fn reload_priority_overrides(&self, overrides: &mut PriorityOverrides) {
+        let current = match self.priority_file.load() {
+            Ok(current) => current,
+            Err(e) => {
+                warn!("Keeping previous priority overrides: {:#}", e);
+                return;
+            }
+        };
 
-        for (index, cmd) in commands.iter().enumerate() {
+        for change in current.changes_since(overrides) {
             info!(
-                "Executing setup command {}/{}: {}",
-                index + 1,
-                commands.len(),
-                cmd.name
+                "Priority override for '{}' changed: {:?} -> {:?}",
+                change.task_key, change.before, change.after
             );
+            if let Err(e) = self.journal.record(JournalEvent::PriorityOverrideChanged {
+                task_key: change.task_key,
+                before: change.before,
+                after: change.after,
+            }) {
+                warn!("Failed to journal priority change: {}", e);
+            }
+        }
+        *overrides = current;
+    }
 
-            let result = self.execute_shell_command(cmd).await?;
+    /// Reconcile plan tasks with the last run of the same plan file
+    ///
+    /// Only plans loaded from a file have stable task identities. New and
+    /// edited tasks run; unchanged tasks that already completed, in an earlier
+    /// run or in this session, are kept unless the plan asks to rerun them.
+    /// Tasks removed from the file are archived.
+    async fn apply_plan_idempotency(
+        &self,
+        plan: &mut crate::task::ExecutionPlan,
+    ) -> Result<Option<PlanRecord>> {
+        let Some(source) = plan.source.clone() else {
+            return Ok(None);
+        };
+        let source = source.canonicalize().unwrap_or(source);
+        let mut record = PlanRecord::load(&self.workspace_path, &source)?;
+
+        let mut completed_in_session = std::collections::HashSet::new();
+        let mut open_in_session = Vec::new();
+        for task_id in self
+            .task_manager
+            .get_tasks_by_status(|status| !matches!(status, TaskStatus::Skipped { .. }))
+            .await?
+        {
+            let task = self.task_manager.get_task(task_id).await?;
+            let Some(key) = tagged_idempotency_key(&task.metadata.tags) else {
+                continue;
+            };
+            if matches!(task.status, TaskStatus::Completed { .. }) {
+                completed_in_session.insert(key);
+            } else if !task.is_terminal() {
+                open_in_session.push((task_id, key));
+            }
+        }
 
-            if !result.success {
-                info!(
-                    "Setup command '{}' failed with exit code: {}",
-                    cmd.name, result.exit_code
-                );
+        let reconciliation = record.reconcile(std::mem::take(&mut plan.task_specs));
+        info!(
+            "Plan reconciliation: {} new, {} changed, {} unchanged, {} removed",
+            reconciliation.count(PlanTaskChange::New),
+            reconciliation.count(PlanTaskChange::Changed),
+            reconciliation.count(PlanTaskChange::Unchanged),
+            reconciliation.removed.len()
+        );
 
-                if let Some(handler) = &cmd.error_handler {
-                    if let Err(e) = self.handle_command_error(cmd, &result, handler).await {
-                        if cmd.required {
-                            return Err(anyhow::anyhow!(
-                                "Required setup command '{}' failed: {}",
-                                cmd.name,
-                                e
-                            ));
-                        } else {
-                            warn!("Optional setup command '{}' failed: {}", cmd.name, e);
-                        }
-                    }
-                } else if cmd.required {
-                    return Err(anyhow::anyhow!(
-                        "Required setup command '{}' failed: {}",
-                        cmd.name,
-                        result.stderr
-                    ));
-                } else {
-                    warn!(
-                        "Optional setup command '{}' failed: {}",
-                        cmd.name, result.stderr
+        // Tasks removed from the file must not run again in this session
+        for (task_id, key) in open_in_session {
+            if reconciliation.removed.contains(&key) {
+                self.task_manager
+                    .update_task_status(
+                        task_id,
+                        TaskStatus::Skipped {
+                            reason: "Removed from plan file".to_string(),
+                            skipped_at: chrono::Utc::now(),
+                        },
+                    )
+                    .await?;
+            }
+        }
+        record.apply(&reconciliation)?;
+
+        for task in reconciliation.tasks {
+            let ReconciledTask {
+                mut spec,
+                key,
+                id,
+                change,
+                ..
+            } = task;
+            if plan.completed_task_policy == CompletedTaskPolicy::Skip
+                && change == PlanTaskChange::Unchanged
+            {
+                if let Some(done) = record.completed_task(id) {
+                    info!(
+                        "Skipping '{}': already completed at {} (session {})",
+                        spec.title, done.completed_at, done.session_id
                     );
+                    continue;
+                }
+                if completed_in_session.contains(&id) {
+                    info!(
+                        "Skipping '{}': already completed in this session",
+                        spec.title
+                    );
+                    continue;
                 }
-            } else {
-                info!("Setup command '{}' completed successfully", cmd.name);
             }
+
+            spec.metadata.tags.retain(|tag| {
+                !tag.starts_with(TASK_KEY_TAG_PREFIX) && !tag.starts_with(IDEMPOTENCY_TAG_PREFIX)
+            });
+            spec.metadata
+                .tags
+                .push(format!("{}{}", TASK_KEY_TAG_PREFIX, key));
+            spec.metadata
+                .tags
+                .push(format!("{}{}", IDEMPOTENCY_TAG_PREFIX, id));
+            plan.task_specs.push(spec);
         }
 
-        info!("All setup commands completed successfully");
-        Ok(())
+        Ok(Some(record))
     }
 
-    /// Execute a single shell command
-    async fn execute_shell_command(&self, cmd: &SetupCommand) -> Result<SetupResult> {
-        use crate::executor::ExecutionCommand;
-        use std::collections::HashMap;
+    /// Run one plan task and record its completion for later reruns
+    async fn create_and_process_plan_task(
+        &self,
+        task_spec: crate::task::TaskSpec,
+        plan_source: Option<&std::path::Path>,
+        plan_record: &mut Option<PlanRecord>,
+    ) -> Result<uuid::Uuid> {
+        let identity = plan_task_identity(&task_spec);
+        let task_id = self.run_plan_task(task_spec, plan_source).await?;
+        self.record_plan_task(plan_record, identity, task_id);
+        Ok(task_id)
+    }
 
-        // Convert SetupCommand to ExecutionCommand
-        let exec_cmd = ExecutionCommand {
-            program: cmd.command.clone(),
-            args: cmd.args.clone(),
-            working_dir: cmd.working_dir.clone(),
-            env: HashMap::new(), // SetupCommand doesn't have env vars yet
-            stdin: None,
-            timeout: cmd
-                .timeout
-                .map(|d| d.to_std().unwrap_or(std::time::Duration::from_secs(300))),
+    /// Run one plan task: record it if already done, expand its sub-plan, or
+    /// process it
+    async fn run_plan_task(
+        &self,
+        task_spec: crate::task::TaskSpec,
+        plan_source: Option<&std::path::Path>,
+    ) -> Result<uuid::Uuid> {
+        if task_spec
+            .metadata
+            .tags
+            .iter()
+            .any(|tag| tag == PRECOMPLETED_TAG)
+        {
+            self.record_precompleted_task(task_spec, None).await
+        } else if let Some(sub_plan) = task_spec.plan.clone() {
+            let path = resolve_sub_plan_path(&sub_plan, plan_source, &self.workspace_path);
+            let task_id = self.task_manager.create_task(task_spec, None).await?;
+            self.expand_sub_plan(task_id, path, SubPlanStack::default())
+                .await?;
+            Ok(task_id)
+        } else {
+            self.check_services().await?;
+            self.create_and_process_task_spec(task_spec).await
+        }
+    }
+
+    /// Record a finished plan task so later runs of the plan skip it
+    fn record_plan_task(
+        &self,
+        plan_record: &mut Option<PlanRecord>,
+        identity: Option<PlanTaskIdentity>,
+        task_id: Uuid,
+    ) {
+        let (Some(record), Some(identity)) = (plan_record.as_mut(), identity) else {
+            return;
         };
+        let completed = CompletedPlanTask {
+            key: identity.key,
+            title: identity.title,
+            task_id,
+            session_id: self.session_manager.session_id().to_string(),
+            completed_at: chrono::Utc::now(),
+            fingerprint: Some(identity.fingerprint),
+        };
+        if let Err(e) = record.record_completed(identity.id, completed) {
+            warn!("Failed to record completed plan task: {}", e);
+        }
+    }
 
-        // Execute through the executor abstraction
-        let result = self
-            .executor
-            .execute(exec_cmd)
-            .await
-            .map_err(|e| anyhow::anyhow!("Command execution failed: {}", e))?;
+    /// Add a task the plan file already marks as done, without running it
+    async fn record_precompleted_task(
+        &self,
+        task_spec: crate::task::TaskSpec,
+        parent_id: Option<Uuid>,
+    ) -> Result<Uuid> {
+        info!("'{}' is already marked done in the plan", task_spec.title);
+        let task_id = self.task_manager.create_task(task_spec, parent_id).await?;
+        self.task_manager
+            .complete_task(
+                task_id,
+                crate::task::types::TaskResult::Success {
+                    output: serde_json::json!({"message": "Marked done in the plan file"}),
+                    files_created: Vec::new(),
+                    files_modified: Vec::new(),
+                    build_artifacts: Vec::new(),
+                },
+            )
+            .await?;
+        Ok(task_id)
+    }
 
-        // Convert ExecutionResult to SetupResult
-        Ok(SetupResult {
-            command_id: cmd.id,
-            success: result.exit_code == 0,
-            exit_code: result.exit_code,
-            stdout: result.stdout,
-            stderr: result.stderr,
-            duration: chrono::Duration::from_std(result.duration).unwrap_or_default(),
+    /// Run the sub-plan at `path` as the children of `task_id`
+    ///
+    /// All children are added before any runs, so the task is not completed
+    /// early by the first child to finish. The task fails if the sub-plan
+    /// cannot be loaded or any of its tasks fails.
+    fn expand_sub_plan(
+        &self,
+        task_id: Uuid,
+        path: std::path::PathBuf,
+        stack: SubPlanStack,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.task_manager
+                .update_task_status(
+                    task_id,
+                    TaskStatus::InProgress {
+                        started_at: chrono::Utc::now(),
+                        estimated_completion: None,
+                    },
+                )
+                .await?;
+
+            let loaded = match self.load_sub_plan(&path, &stack).await {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    self.fail_sub_plan_task(task_id, &path, e.to_string())
+                        .await?;
+                    return Err(e);
+                }
+            };
+            let (sub_plan, stack) = loaded;
+            info!(
+                "Expanding {} into {} tasks",
+                path.display(),
+                sub_plan.task_count()
+            );
+
+            if !sub_plan.services.is_empty() || !sub_plan.ports.is_empty() {
+                warn!(
+                    "Ignoring services and ports declared in sub-plan {}; declare them in the top-level plan",
+                    path.display()
+                );
+            }
+            if sub_plan.has_setup_commands()
+                && let Err(e) = self.execute_setup_commands(&sub_plan.setup_commands).await
+            {
+                self.fail_sub_plan_task(task_id, &path, e.to_string())
+                    .await?;
+                return Err(e);
+            }
+
+            let mut children = Vec::new();
+            for spec in sub_plan.task_specs {
+                let nested = spec.plan.clone();
+                let precompleted = spec.metadata.tags.iter().any(|tag| tag == PRECOMPLETED_TAG);
+                if precompleted {
+                    self.record_precompleted_task(spec, Some(task_id)).await?;
+                    continue;
+                }
+                let child_id = self.task_manager.create_task(spec, Some(task_id)).await?;
+                children.push((child_id, nested));
+            }
+
+            let mut failures = Vec::new();
+            let mut awaiting = None;
+            for (child_id, nested) in children {
+                let result = match nested {
+                    Some(nested) => {
+                        let nested =
+                            resolve_sub_plan_path(&nested, Some(&path), &self.workspace_path);
+                        self.expand_sub_plan(child_id, nested, stack.clone()).await
+                    }
+                    None => self.process_task(child_id).await,
+                };
+                match result {
+                    Ok(()) => {}
+                    // The parent completes once the question is answered
+                    Err(e) if e.downcast_ref::<AwaitingAnswer>().is_some() => awaiting = Some(e),
+                    Err(e) => {
+                        warn!("Sub-plan task {} failed: {}", child_id, e);
+                        failures.push(child_id);
+                    }
+                }
+            }
+
+            if !failures.is_empty() {
+                let message = format!(
+                    "{} of the tasks in sub-plan {} failed",
+                    failures.len(),
+                    path.display()
+                );
+                self.fail_sub_plan_task(task_id, &path, message.clone())
+                    .await?;
+                anyhow::bail!(message);
+            }
+            if let Some(e) = awaiting {
+                self.save_session_checkpoint("sub_plan_awaiting_input")
+                    .await?;
+                return Err(e);
+            }
+
+            let task = self.task_manager.get_task(task_id).await?;
+            if !matches!(task.status, TaskStatus::Completed { .. }) {
+                self.complete_with_rollup(task_id).await?;
+            }
+            self.save_session_checkpoint("sub_plan_complete").await?;
+            Ok(())
         })
     }
 
-    /// Handle command execution errors
-    async fn handle_command_error(
+    /// Complete a parent with the roll-up of its children
+    async fn complete_with_rollup(&self, task_id: Uuid) -> Result<()> {
+        let mut rollup = self.task_manager.rollup(task_id).await?;
+        if self.rollup.narrative {
+            rollup.narrative = self.narrate_rollup(task_id, &rollup).await;
+        }
+        info!("Task {}: {}", task_id, rollup.headline());
+        self.task_manager
+            .complete_task(task_id, rollup.into_result())
+            .await
+    }
+
+    /// The model's prose summary of a roll-up, `None` if it could not be had
+    ///
+    /// Runs on the parent's route with read-only tools; its cost is billed
+    /// to the parent.
+    async fn narrate_rollup(&self, task_id: Uuid, rollup: &RollupSummary) -> Option<String> {
+        let mut task = self.task_manager.get_task(task_id).await.ok()?;
+        task.description = rollup.narrative_prompt(&task);
+        task.metadata.tool_permissions = ToolPermissions {
+            allowed_tools: Some(vec![
+                "Read".to_string(),
+                "Grep".to_string(),
+                "Glob".to_string(),
+            ]),
+            disallowed_tools: ["Write", "Edit", "MultiEdit", "Bash"]
+                .map(str::to_string)
+                .to_vec(),
+            permission_mode: Some(crate::claude::PermissionMode::Plan),
+        };
+        let route = self.router.route(&task.metadata);
+        match self.run_routed_turn(&task, &route).await {
+            Ok(narrated) => {
+                self.record_task_cost(&narrated);
+                narrated
+                    .response()
+                    .map(str::trim)
+                    .filter(|narrative| !narrative.is_empty())
+                    .map(str::to_string)
+            }
+            Err(e) => {
+                warn!("Failed to summarize the subtasks of {}: {}", task_id, e);
+                None
+            }
+        }
+    }
+
+    /// Parse a sub-plan file, checking it does not include itself
+    async fn load_sub_plan(
         &self,
-        cmd: &SetupCommand,
-        result: &SetupResult,
-        handler: &ErrorHandler,
+        path: &std::path::Path,
+        stack: &SubPlanStack,
+    ) -> Result<(crate::task::ExecutionPlan, SubPlanStack)> {
+        let stack = stack.enter(path)?;
+        let loader = self.plan_loader.read().unwrap().clone();
+        let plan = loader
+            .load(path)
+            .await
+            .with_context(|| format!("Failed to load sub-plan {}", path.display()))?;
+        plan.validate()
+            .map_err(|e| anyhow::anyhow!("Invalid sub-plan {}: {}", path.display(), e))?;
+        Ok((plan, stack))
+    }
+
+    async fn fail_sub_plan_task(
+        &self,
+        task_id: Uuid,
+        path: &std::path::Path,
+        message: String,
     ) -> Result<()> {
-        match &handler.strategy {
-            ErrorStrategy::Skip => {
-                warn!("Skipping failed command: {}", cmd.name);
-                Ok(())
+        self.task_manager
+            .fail_task(
+                task_id,
+                crate::task::types::TaskError::Other {
+                    message,
+                    source: Some(path.display().to_string()),
+                },
+            )
+            .await
+    }
+
+    /// Turn a bug report into a failing test, a fix and a confirmation
+    ///
+    /// Each step is a task followed by a gate on the test command: it must
+    /// fail after the reproduce step and pass after the fix and confirm steps.
+    /// A step that misses its gate is retried once with the test output.
+    pub async fn fix_bug(&self, bug: &str, test_command: Option<String>) -> Result<BugFixReport> {
+        let mut feedback: Option<String> = None;
+        let mut reproduced = None;
+
+        for attempt in 1..=MAX_GATE_ATTEMPTS {
+            info!(
+                "Reproducing bug (attempt {}/{})",
+                attempt, MAX_GATE_ATTEMPTS
+            );
+            let spec = bug_fix_task_spec(
+                BugFixStage::Reproduce,
+                bug,
+                test_command.as_deref(),
+                feedback.as_deref(),
+            );
+            let task_id = self.create_and_process_task_spec(spec).await?;
+            let task = self.task_manager.get_task(task_id).await?;
+
+            let Some(command) = test_command
+                .clone()
+                .or_else(|| task.response().and_then(parse_test_command))
+            else {
+                feedback = Some(format!(
+                    "No test command was reported. End the response with {}<command>{}.",
+                    TEST_COMMAND_BLOCK_START, TEST_COMMAND_BLOCK_END
+                ));
+                continue;
+            };
+
+            let result = self.run_verification(&command).await?;
+            if result.success() {
+                warn!("Reproducing test passes, so it does not expose the bug");
+                feedback = Some(format!(
+                    "`{}` passed, so the test does not reproduce the bug. Make it fail for \
+                     the reported reason.",
+                    command
+                ));
+                continue;
             }
-            ErrorStrategy::Retry {
-                max_attempts,
-                delay,
-            } => {
-                info!(
-                    "Retrying command '{}' (max {} attempts)",
-                    cmd.name, max_attempts
-                );
-                self.retry_command(cmd, *max_attempts, *delay).await
+
+            info!("Bug reproduced: `{}` fails", command);
+            reproduced = Some((task_id, command, output_tail(&result, MAX_FEEDBACK_CHARS).0));
+            break;
+        }
+
+        let Some((reproduce_task, test_command, failing_output)) = reproduced else {
+            return Err(anyhow::anyhow!(
+                "Could not reproduce the bug with a failing test after {} attempt(s): {}",
+                MAX_GATE_ATTEMPTS,
+                feedback.unwrap_or_default()
+            ));
+        };
+
+        let fix_task = self
+            .run_gated_bug_fix_step(
+                BugFixStage::Fix,
+                bug,
+                &test_command,
+                Some(format!("Current test output:\n{}", failing_output)),
+            )
+            .await?;
+        let confirm_task = self
+            .run_gated_bug_fix_step(BugFixStage::Confirm, bug, &test_command, None)
+            .await?;
+
+        Ok(BugFixReport {
+            test_command,
+            reproduce_task,
+            fix_task,
+            confirm_task,
+            failing_output,
+        })
+    }
+
+    /// Run a one-line request as a single task, then `verify_command`
+    ///
+    /// Fails when the task does; a failing verification is reported in the
+    /// result rather than as an error.
+    pub async fn quick(&self, request: &str, verify_command: Option<&str>) -> Result<QuickReport> {
+        let task_id = self
+            .create_and_process_task_spec(quick_task_spec(request, verify_command))
+            .await?;
+        let task = self.task_manager.get_task(task_id).await?;
+        if let TaskStatus::Failed { error, .. } = &task.status {
+            return Err(anyhow::anyhow!("Task '{}' failed: {}", task.title, error));
+        }
+
+        // Line counts from the task's diff, else just the changed files
+        let task_diff = match &task.status {
+            TaskStatus::Completed {
+                result: crate::task::types::TaskResult::Success { output, .. },
+                ..
+            } => output
+                .get("workspace_diff")
+                .and_then(|diff| serde_json::from_value::<TaskDiff>(diff.clone()).ok()),
+            _ => None,
+        };
+        let files = match &task_diff {
+            Some(TaskDiff {
+                diff_path: Some(path),
+                ..
+            }) => std::fs::read_to_string(path)
+                .map(|diff| diff_stat(&diff))
+                .unwrap_or_default(),
+            Some(task_diff) => task_diff
+                .changes
+                .all()
+                .map(|path| FileStat {
+                    path: path.clone(),
+                    added: 0,
+                    removed: 0,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let verification = match verify_command {
+            Some(command) => {
+                let result = self.run_verification(command).await?;
+                Some(QuickVerification {
+                    command: command.to_string(),
+                    passed: result.success(),
+                    output: output_tail(&result, MAX_QUICK_OUTPUT_CHARS).0,
+                })
             }
-            ErrorStrategy::Backup {
-                condition,
-                backup_command,
-                backup_args,
-            } => {
-                if Self::should_run_backup(result, condition) {
-                    info!("Running backup command for: {}", cmd.name);
-                    self.execute_backup_command(backup_command, backup_args, &cmd.working_dir)
-                        .await
-                } else {
-                    Err(anyhow::anyhow!(
-                        "Backup condition not met for: {}",
-                        cmd.name
-                    ))
-                }
+            None => None,
+        };
+        Ok(QuickReport {
+            task_id,
+            files,
+            verification,
+        })
+    }
+
+    /// Run a fix or confirm step until the test command passes
+    async fn run_gated_bug_fix_step(
+        &self,
+        stage: BugFixStage,
+        bug: &str,
+        test_command: &str,
+        mut feedback: Option<String>,
+    ) -> Result<Uuid> {
+        for attempt in 1..=MAX_GATE_ATTEMPTS {
+            info!(
+                "Bug fix {} step (attempt {}/{})",
+                stage.name(),
+                attempt,
+                MAX_GATE_ATTEMPTS
+            );
+            let spec = bug_fix_task_spec(stage, bug, Some(test_command), feedback.as_deref());
+            let task_id = self.create_and_process_task_spec(spec).await?;
+
+            let result = self.run_verification(test_command).await?;
+            if result.success() == stage.expects_passing_test() {
+                return Ok(task_id);
             }
+
+            warn!(
+                "Bug fix {} gate failed: `{}` exited with {}",
+                stage.name(),
+                test_command,
+                result.exit_code
+            );
+            feedback = Some(format!(
+                "After your changes `{}` still fails:\n{}",
+                test_command,
+                output_tail(&result, MAX_FEEDBACK_CHARS).0
+            ));
+        }
+
+        Err(anyhow::anyhow!(
+            "Bug fix {} step did not get `{}` passing after {} attempt(s)",
+            stage.name(),
+            test_command,
+            MAX_GATE_ATTEMPTS
+        ))
+    }
+
+    /// Create and process a task from a TaskSpec (internal helper)
+    async fn create_and_process_task_spec(
+        &self,
+        task_spec: crate::task::TaskSpec,
+    ) -> Result<uuid::Uuid> {
+        // Create task in the task manager
+        let task_id = self.task_manager.create_task(task_spec, None).await?;
+
+        // Process the task
+        self.process_task(task_id).await?;
+
+        Ok(task_id)
+    }
+
+    /// Convert AgentConfig to ExecutionPlan (for structured config mode)
+    pub fn agent_config_to_execution_plan(config: &AgentConfig) -> crate::task::ExecutionPlan {
+        use tracing::info;
+
+        info!(
+            "Converting AgentConfig to ExecutionPlan with {} setup commands",
+            config.setup_commands.len()
+        );
+
+        let plan_name = format!(
+            "Structured Config: {}",
+            config
+                .workspace_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("workspace")
+        );
+
+        let plan = crate::task::ExecutionPlan::new()
+            .with_setup_commands(config.setup_commands.clone())
+            .with_services(config.services.clone())
+            .with_metadata(
+                plan_name,
+                "Execution plan generated from structured TOML configuration",
+            )
+            .with_tags(vec![
+                "structured-config".to_string(),
+                "auto-generated".to_string(),
+            ])
+            .with_sequential_execution();
+
+        info!("Created execution plan: {}", plan.summary());
+        plan
+    }
+
+    /// Get system status
+    pub async fn get_system_status(&self) -> Result<SystemStatus> {
+        let task_stats = self.task_manager.get_statistics().await?;
+        let claude_status = self.claude_interface.get_interface_status().await;
+        let session_stats = self.session_manager.get_session_statistics().await?;
+
+        Ok(SystemStatus {
+            task_stats: task_stats.clone(),
+            claude_status: claude_status.clone(),
+            session_stats,
+            is_healthy: claude_status.is_healthy && task_stats.total_tasks > 0,
+        })
+    }
+
+    /// Save current session state
+    async fn save_session_state(&self) -> Result<()> {
+        // Only save session state, don't create checkpoint
+        // Checkpoints are created at major milestones (plan completion, shutdown)
+        let _guard = self.persistence_lock.lock().await;
+        self.session_manager.save_session().await?;
+        Ok(())
+    }
+
+    /// Save session state and create a checkpoint
+    async fn save_session_checkpoint(&self, description: &str) -> Result<()> {
+        let _guard = self.persistence_lock.lock().await;
+        self.session_manager.save_session().await?;
+        let checkpoint = self
+            .session_manager
+            .create_checkpoint(description.to_string())
+            .await?;
+        self.events.emit(AgentEvent::CheckpointCreated {
+            checkpoint_id: checkpoint.id,
+            description: checkpoint.description,
+        });
+        self.sync_to_remote().await;
+        Ok(())
+    }
+
+    /// Upload new checkpoints and run records to the configured remote
+    ///
+    /// Failures are logged rather than returned so an unreachable remote
+    /// never stops a run.
+    async fn sync_to_remote(&self) {
+        #[cfg(feature = "remote-sync")]
+        if let Some(remote_sync) = &self.remote_sync {
+            let session_id = self.session_manager.session_id().to_string();
+            if let Err(e) = remote_sync.sync_session(&session_id).await {
+                warn!("Remote sync failed: {:#}", e);
+            }
+        }
+    }
+
+    /// Pause a running task, suspending its subprocess, and persist the pause
+    ///
+    /// Its concurrency slot goes to other tasks until it is resumed.
+    pub async fn pause_task(&self, task_id: Uuid, reason: &str) -> Result<()> {
+        self.task_manager
+            .pause_task(task_id, reason.to_string())
+            .await?;
+        self.heartbeat.set_paused(task_id, true);
+        self.preemption.release(task_id);
+        self.save_session_checkpoint(&format!("paused_{}", task_id))
+            .await
+    }
+
+    /// Resume a task paused with [`pause_task`](Self::pause_task) once a
+    /// concurrency slot is free
+    pub async fn resume_task(&self, task_id: Uuid) -> Result<()> {
+        self.preemption.reclaim(task_id).await;
+        self.task_manager.resume_task(task_id).await?;
+        self.heartbeat.set_paused(task_id, false);
+        self.save_session_state().await
+    }
+
+    /// Revert the file changes a finished task made and reset it to pending
+    ///
+    /// The workspace is left untouched if any file the task changed has been
+    /// changed again since, or while any task is running. Files changed while
+    /// tasks overlapped it are kept. Returns the changes undone.
+    pub async fn rollback_task(&self, task_id: Uuid) -> Result<WorkspaceChanges> {
+        let task = self.task_manager.get_task(task_id).await?;
+        if task.is_running() {
+            return Err(anyhow::anyhow!(
+                "Task {} is still running and cannot be rolled back",
+                task_id
+            ));
+        }
+
+        let undone = self.snapshots.rollback(&task_id.to_string())?;
+        info!(
+            "Rolled back task {}: {} files restored",
+            task_id,
+            undone.all().count()
+        );
+        self.task_manager
+            .update_task_status(task_id, TaskStatus::Pending)
+            .await?;
+        if let Err(e) = self.journal.record(JournalEvent::TaskRolledBack {
+            task_id,
+            task_title: task.title,
+            files: undone.all().cloned().collect(),
+        }) {
+            warn!("Failed to journal rollback of task {}: {}", task_id, e);
+        }
+        self.save_session_checkpoint(&format!("rolled_back_{}", task_id))
+            .await?;
+        Ok(undone)
+    }
+
+    /// All tasks in tree order, each with its depth below its root
+    pub async fn task_tree(&self) -> Result<Vec<(usize, crate::task::Task)>> {
+        let mut tasks = HashMap::new();
+        for task_id in self.task_manager.get_tasks_by_status(|_| true).await? {
+            tasks.insert(task_id, self.task_manager.get_task(task_id).await?);
+        }
+
+        let mut roots: Vec<_> = tasks
+            .values()
+            .filter(|task| {
+                task.parent_id
+                    .is_none_or(|parent| !tasks.contains_key(&parent))
+            })
+            .map(|task| (task.created_at, task.id))
+            .collect();
+        roots.sort();
+
+        let mut pending: Vec<(usize, Uuid)> =
+            roots.into_iter().rev().map(|(_, id)| (0, id)).collect();
+        let mut listing = Vec::with_capacity(tasks.len());
+        while let Some((depth, task_id)) = pending.pop() {
+            let Some(task) = tasks.remove(&task_id) else {
+                continue;
+            };
+            pending.extend(task.children.iter().rev().map(|&child| (depth + 1, child)));
+            listing.push((depth, task));
+        }
+        Ok(listing)
+    }
+
+    /// The task whose id is `id` or, for a short id, starts with it
+    pub async fn find_task(&self, id: &str) -> Result<crate::task::Task> {
+        if let Ok(task_id) = Uuid::parse_str(id) {
+            return self.task_manager.get_task(task_id).await;
+        }
+
+        let prefix = id.to_lowercase();
+        let matches: Vec<Uuid> = self
+            .task_manager
+            .get_tasks_by_status(|_| true)
+            .await?
+            .into_iter()
+            .filter(|task_id| task_id.to_string().starts_with(&prefix))
+            .collect();
+        match matches.as_slice() {
+            [task_id] => self.task_manager.get_task(*task_id).await,
+            [] => Err(anyhow::anyhow!("No task id starts with '{}'", id)),
+            _ => Err(anyhow::anyhow!(
+                "'{}' matches {} tasks; give more of the id",
+                id,
+                matches.len()
+            )),
+        }
+    }
+
+    /// Run a failed, skipped or blocked task again
+    pub async fn retry_task(&self, task_id: Uuid) -> Result<()> {
+        let task = self.task_manager.get_task(task_id).await?;
+        if !matches!(
+            task.status,
+            TaskStatus::Failed { .. } | TaskStatus::Skipped { .. } | TaskStatus::Blocked { .. }
+        ) {
+            return Err(anyhow::anyhow!(
+                "Task {} is {} and cannot be retried",
+                task_id,
+                task.status.name()
+            ));
+        }
+
+        info!("Retrying task {}: {}", task_id, task.title);
+        self.task_manager
+            .update_task_status(task_id, TaskStatus::Pending)
+            .await?;
+        self.process_task(task_id).await
+    }
+
+    /// Skip an unfinished task and its unfinished subtasks, returning the
+    /// ids of the tasks skipped
+    pub async fn cancel_task(&self, task_id: Uuid, reason: &str) -> Result<Vec<Uuid>> {
+        let task = self.task_manager.get_task(task_id).await?;
+        if task.is_terminal() {
+            return Err(anyhow::anyhow!(
+                "Task {} is already {}",
+                task_id,
+                task.status.name()
+            ));
+        }
+
+        let mut cancelled = Vec::new();
+        let mut pending = vec![task];
+        while let Some(task) = pending.pop() {
+            for &child in &task.children {
+                pending.push(self.task_manager.get_task(child).await?);
+            }
+            if task.is_terminal() {
+                continue;
+            }
+            self.task_manager
+                .update_task_status(
+                    task.id,
+                    TaskStatus::Skipped {
+                        reason: reason.to_string(),
+                        skipped_at: chrono::Utc::now(),
+                    },
+                )
+                .await?;
+            cancelled.push(task.id);
+        }
+
+        info!("Cancelled {} task(s): {}", cancelled.len(), reason);
+        self.save_session_state().await?;
+        Ok(cancelled)
+    }
+
+    /// Stop starting new tasks; tasks already running carry on
+    pub fn request_stop(&self) {
+        self.stop.request();
+    }
+
+    /// Whether the run was interrupted
+    pub fn stop_requested(&self) -> bool {
+        self.stop.is_requested()
+    }
+
+    /// Execute `plan`, stopping cleanly on Ctrl-C or `SIGTERM`
+    ///
+    /// After the first Ctrl-C no new tasks start, and the plan runs until
+    /// the tasks already running finish. A second Ctrl-C, or `SIGTERM`,
+    /// kills the running provider processes instead. Either way the run is
+    /// saved for `aca resume` (see [`save_interrupted`](Self::save_interrupted)).
+    /// Returns `None` when interrupted.
+    pub async fn execute_plan_interruptible(
+        &self,
+        plan: crate::task::ExecutionPlan,
+    ) -> Result<Option<Vec<Uuid>>> {
+        let started_at = chrono::Utc::now();
+        let mut work = Box::pin(self.execute_plan(plan));
+        let signal = tokio::select! {
+            result = &mut work => return result.map(Some),
+            signal = crate::task::interrupt::next_signal() => signal?,
+        };
+        self.request_stop();
+        if signal == Signal::Interrupt {
+            warn!(
+                "Interrupted: waiting for running tasks to finish (Ctrl-C again to stop them now)"
+            );
+            tokio::select! {
+                result = &mut work => {
+                    if let Err(e) = result {
+                        warn!("Plan failed after the interruption: {}", e);
+                    }
+                }
+                signal = crate::task::interrupt::next_signal() => {
+                    signal?;
+                    warn!("Stopping running tasks");
+                    self.kill_running_providers().await;
+                }
+            }
+        } else {
+            warn!("Terminated: stopping running tasks");
+            self.kill_running_providers().await;
+        }
+        drop(work);
+        self.save_interrupted(started_at).await?;
+        Ok(None)
+    }
+
+    /// Kill the provider process groups of tasks in progress
+    async fn kill_running_providers(&self) {
+        let processes = self.claude_interface.process_registry();
+        let Ok(running) = self
+            .task_manager
+            .get_tasks_by_status(|status| matches!(status, TaskStatus::InProgress { .. }))
+            .await
+        else {
+            return;
+        };
+        for task_id in running {
+            if let Some(pid) = processes.pid(task_id)
+                && let Err(e) = crate::task::suspend::kill_process_group(pid).await
+            {
+                warn!("Failed to stop the provider of task {}: {}", task_id, e);
+            }
+        }
+    }
+
+    /// Save an interrupted run so `aca resume` resumes where it stopped
+    ///
+    /// Tasks still in progress or waiting on their conditions are paused,
+    /// tasks of the plan started at `started_at` that never made it into
+    /// the tree are added as pending, and an `interrupted` checkpoint is
+    /// saved. Returns the ids of the paused tasks.
+    pub async fn save_interrupted(
+        &self,
+        started_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Uuid>> {
+        let interrupted = self
+            .task_manager
+            .get_tasks_by_status(|status| match status {
+                TaskStatus::InProgress { .. } => true,
+                TaskStatus::Blocked { reason, .. } => reason
+                    .text()
+                    .is_some_and(|reason| reason.starts_with(WAITING_REASON_PREFIX)),
+                _ => false,
+            })
+            .await?;
+        for task_id in &interrupted {
+            self.task_manager
+                .update_task_status(
+                    *task_id,
+                    TaskStatus::Paused {
+                        reason: INTERRUPTED_REASON.to_string(),
+                        paused_at: chrono::Utc::now(),
+                    },
+                )
+                .await?;
+        }
+
+        // Plan tasks are known by title; ones already in the tree started
+        let specs = std::mem::take(&mut *self.plan_specs.lock().unwrap());
+        let titles: HashSet<&str> = specs.iter().map(|spec| spec.title.as_str()).collect();
+        let mut tree_ids = HashMap::new();
+        for task_id in self.task_manager.get_tasks_by_status(|_| true).await? {
+            let task = self.task_manager.get_task(task_id).await?;
+            if task.created_at >= started_at && titles.contains(task.title.as_str()) {
+                tree_ids.insert(crate::task::plan_task_id(&task.title), task_id);
+            }
+        }
+        let unstarted: Vec<TaskSpec> = specs
+            .into_iter()
+            .filter(|spec| !tree_ids.contains_key(&crate::task::plan_task_id(&spec.title)))
+            .collect();
+        let unstarted_count = unstarted.len();
+        self.queue_plan_specs(unstarted, &mut tree_ids, false)
+            .await?;
+        self.stop_services().await;
+
+        self.save_session_checkpoint("interrupted").await?;
+        info!(
+            "Run interrupted: {} task(s) paused, {} not started; resume with `aca resume`",
+            interrupted.len(),
+            unstarted_count
+        );
+        Ok(interrupted)
+    }
+
+    /// Graceful shutdown
+    pub async fn shutdown(&self) -> Result<()> {
+        tracing::info!("Shutting down agent system...");
+
+        // Final log pruning, before the checkpoint records the surviving logs
+        self.log_retention.stop(&self.session_manager).await;
+        self.task_manager.stop_maintenance_loop();
+
+        // Services of a plan that did not finish are not left behind
+        self.stop_services().await;
+
+        // Save final state with checkpoint
+        self.save_session_checkpoint("agent_shutdown").await?;
+
+        // Publish a final heartbeat and stop the writer
+        self.heartbeat.stop().await;
+
+        // Restore or keep the generated instructions file
+        if let Err(e) = self.instructions.cleanup() {
+            warn!("Failed to clean up instructions file: {}", e);
+        }
+
+        // Graceful shutdown of session manager (this also creates a session_shutdown checkpoint)
+        self.session_manager.shutdown().await?;
+
+        // Write the run report, signing it and the ledgers so they can be audited later
+        if let Err(e) = self.write_run_report(self.report_signer.as_ref()) {
+            warn!("Failed to write the run report: {}", e);
+        }
+
+        // Shutdown executor (cleanup containers if any)
+        self.executor
+            .shutdown()
+            .await
+            .map_err(|e| anyhow::anyhow!("Executor shutdown failed: {}", e))?;
+
+        // Clear container info in session metadata since container is now removed
+        #[cfg(feature = "containers")]
+        if self.executor.is_container_executor() {
+            self.session_manager.clear_container_info().await;
+        }
+
+        tracing::info!("Agent system shutdown complete");
+        Ok(())
+    }
+
+    /// Write the run report to the session artifacts and, with a `signer`,
+    /// sign it, the cost ledger and the event journal
+    fn write_run_report(&self, signer: Option<&ReportSigner>) -> Result<()> {
+        let session_id = self.session_manager.session_id().to_string();
+        let state =
+            crate::session::run_report::find_session(&self.workspace_path, Some(&session_id))?;
+        let report_path = crate::env::run_report_file_path(&self.workspace_path, &session_id);
+        if let Some(parent) = report_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &report_path,
+            serde_json::to_string_pretty(&RunReport::from_session(&state))?,
+        )?;
+        let Some(signer) = signer else {
+            return Ok(());
+        };
+
+        let ledgers = [
+            crate::env::cost_ledger_file_path(&self.workspace_path),
+            crate::env::event_journal_file_path(&self.workspace_path),
+        ];
+        for path in
+            std::iter::once(report_path).chain(ledgers.into_iter().filter(|path| path.exists()))
+        {
+            signer.sign_file(&path)?;
+            info!("Signed {:?}", path);
+        }
+        Ok(())
+    }
+
+    // ============================================================================
+    // Setup Command Execution
+    // ============================================================================
+
+    /// Start the plan's background services and wait until they are ready
+    ///
+    /// If one fails to start or become ready, the ones already started are
+    /// stopped again and the plan fails.
+    async fn start_services(&self, specs: &[ServiceSpec]) -> Result<()> {
+        if specs.is_empty() {
+            return Ok(());
+        }
+        let mut services = self.services.lock().await;
+        for spec in specs {
+            if let Err(e) = self.start_service(spec).await {
+                drop(services);
+                self.stop_services().await;
+                return Err(e);
+            }
+            services.push(spec.clone());
+        }
+        drop(services);
+        self.save_session_checkpoint("services_started").await
+    }
+
+    /// Start one service and wait for its readiness probe
+    async fn start_service(&self, spec: &ServiceSpec) -> Result<()> {
+        let log_path = crate::env::service_log_file_path(
+            &self.workspace_path,
+            &self.session_manager.session_id().to_string(),
+            &spec.name,
+        );
+        let log_path = log_path
+            .strip_prefix(&self.workspace_path)
+            .unwrap_or(&log_path);
+        info!("Starting service '{}': {}", spec.name, spec.command);
+        // Allocated ports are in the environment, below the service's own
+        let mut env: std::collections::BTreeMap<String, String> =
+            self.ports.env().into_iter().collect();
+        env.extend(spec.env.clone());
+        let spec = &ServiceSpec {
+            env,
+            ..spec.clone()
+        };
+        let record =
+            crate::executor::service::start(&self.executor, spec, &self.workspace_path, log_path)
+                .await?;
+        self.session_manager.record_service(record.clone()).await;
+
+        if let Some(ready) = &spec.ready {
+            let deadline =
+                std::time::Instant::now() + std::time::Duration::from_secs(spec.ready_timeout_secs);
+            let exited = std::sync::atomic::AtomicBool::new(false);
+            let met = self
+                .wait
+                .poll(deadline, || async {
+                    if self.wait_condition_met(ready).await {
+                        return true;
+                    }
+                    let gone = !crate::executor::service::is_running(&self.executor, &record).await;
+                    exited.store(gone, std::sync::atomic::Ordering::SeqCst);
+                    gone
+                })
+                .await;
+            if exited.load(std::sync::atomic::Ordering::SeqCst) {
+                anyhow::bail!(
+                    "Service '{}' exited before {} (log: {})",
+                    spec.name,
+                    ready,
+                    log_path.display()
+                );
+            }
+            if !met {
+                anyhow::bail!(
+                    "Service '{}' was not ready within {}s: still waiting for {} (log: {})",
+                    spec.name,
+                    spec.ready_timeout_secs,
+                    ready,
+                    log_path.display()
+                );
+            }
+        }
+        info!("Service '{}' is ready (pid {})", spec.name, record.pid);
+        Ok(())
+    }
+
+    /// Restart services of the running plan that have died
+    async fn check_services(&self) -> Result<()> {
+        let services = self.services.lock().await;
+        let records = self.session_manager.services().await;
+        for spec in services.iter() {
+            let Some(record) = records.iter().find(|record| record.name == spec.name) else {
+                continue;
+            };
+            if crate::executor::service::is_running(&self.executor, record).await {
+                continue;
+            }
+            warn!(
+                "Service '{}' exited (log: {}); restarting it",
+                spec.name,
+                record.log_path.display()
+            );
+            crate::executor::service::stop(&self.executor, record).await?;
+            self.start_service(spec)
+                .await
+                .with_context(|| format!("Service '{}' is down", spec.name))?;
+        }
+        Ok(())
+    }
+
+    /// Stop the running plan's services, leaving those set to keep running
+    async fn stop_services(&self) {
+        self.services.lock().await.clear();
+        for record in self.session_manager.services().await {
+            if record.keep_running {
+                info!(
+                    "Leaving service '{}' running (pid {})",
+                    record.name, record.pid
+                );
+            } else {
+                self.stop_service(&record).await;
+            }
+            self.session_manager.remove_service(&record.name).await;
+        }
+    }
+
+    /// Stop every service recorded in the session, left behind by a run that
+    /// did not end cleanly
+    async fn stop_stale_services(&self) {
+        for record in self.session_manager.services().await {
+            warn!(
+                "Stopping service '{}' (pid {}) left running by an earlier run",
+                record.name, record.pid
+            );
+            self.stop_service(&record).await;
+            self.session_manager.remove_service(&record.name).await;
+        }
+    }
+
+    async fn stop_service(&self, record: &crate::executor::ServiceRecord) {
+        info!("Stopping service '{}'", record.name);
+        if let Err(e) = crate::executor::service::stop(&self.executor, record).await {
+            warn!("{}", e);
+        }
+    }
+
+    /// Execute all setup commands with error handling
+    async fn execute_setup_commands(&self, commands: &[SetupCommand]) -> Result<()> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+
+        info!("Running {} setup commands...", commands.len());
+
+        for (index, cmd) in commands.iter().enumerate() {
+            info!(
+                "Executing setup command {}/{}: {}",
+                index + 1,
+                commands.len(),
+                cmd.name
+            );
+
+            let result = self.execute_shell_command(cmd).await?;
+            self.events.emit(AgentEvent::SetupCommandRun {
+                name: cmd.name.clone(),
+                success: result.success,
+                exit_code: result.exit_code,
+                duration_ms: result.duration.num_milliseconds().max(0) as u64,
+            });
+
+            if !result.success {
+                info!(
+                    "Setup command '{}' failed with exit code: {}",
+                    cmd.name, result.exit_code
+                );
+
+                if let Some(handler) = &cmd.error_handler {
+                    if let Err(e) = self.handle_command_error(cmd, &result, handler).await {
+                        if cmd.required {
+                            return Err(anyhow::anyhow!(
+                                "Required setup command '{}' failed: {}",
+                                cmd.name,
+                                e
+                            ));
+                        } else {
+                            warn!("Optional setup command '{}' failed: {}", cmd.name, e);
+                        }
+                    }
+                } else if cmd.required {
+                    return Err(anyhow::anyhow!(
+                        "Required setup command '{}' failed: {}",
+                        cmd.name,
+                        result.stderr
+                    ));
+                } else {
+                    warn!(
+                        "Optional setup command '{}' failed: {}",
+                        cmd.name, result.stderr
+                    );
+                }
+            } else {
+                info!("Setup command '{}' completed successfully", cmd.name);
+            }
+        }
+
+        info!("All setup commands completed successfully");
+        Ok(())
+    }
+
+    /// Execute a single shell command
+    async fn execute_shell_command(&self, cmd: &SetupCommand) -> Result<SetupResult> {
+        use crate::executor::ExecutionCommand;
+
+        if let Some(packages) = &cmd.packages {
+            return self.ensure_packages(cmd, packages).await;
+        }
+
+        // Convert SetupCommand to ExecutionCommand
+        let exec_cmd = ExecutionCommand {
+            program: cmd.command.clone(),
+            args: cmd.args.clone(),
+            working_dir: cmd.working_dir.clone(),
+            env: self.ports.env(),
+            stdin: None,
+            timeout: cmd
+                .timeout
+                .map(|d| d.to_std().unwrap_or(std::time::Duration::from_secs(300))),
+        };
+
+        // Execute through the executor abstraction
+        let result = self
+            .executor
+            .execute(exec_cmd)
+            .await
+            .map_err(|e| anyhow::anyhow!("Command execution failed: {}", e))?;
+
+        // Convert ExecutionResult to SetupResult
+        Ok(SetupResult {
+            command_id: cmd.id,
+            success: result.exit_code == 0,
+            exit_code: result.exit_code,
+            stdout: result.stdout,
+            stderr: result.stderr,
+            duration: chrono::Duration::from_std(result.duration).unwrap_or_default(),
+        })
+    }
+
+    /// Make sure the system packages a setup command declares are installed
+    ///
+    /// Missing packages are installed inside a container; on the host the
+    /// command fails with the install command to run by hand. Declarations
+    /// already satisfied in this session are not checked again.
+    async fn ensure_packages(
+        &self,
+        cmd: &SetupCommand,
+        packages: &SystemPackages,
+    ) -> Result<SetupResult> {
+        let started = std::time::Instant::now();
+        let result = |exit_code: i32, stdout: String, stderr: String| SetupResult {
+            command_id: cmd.id,
+            success: exit_code == 0,
+            exit_code,
+            stdout,
+            stderr,
+            duration: chrono::Duration::from_std(started.elapsed()).unwrap_or_default(),
+        };
+        if self.satisfied_packages.lock().unwrap().contains(packages) {
+            return Ok(result(
+                0,
+                "System packages already verified in this session".to_string(),
+                String::new(),
+            ));
+        }
+
+        let check = self.run_setup_script(cmd, packages.check_script()).await?;
+        let check = PackageCheck::parse(&check.stdout);
+        let Some(manager) = check.manager else {
+            return Ok(result(
+                1,
+                String::new(),
+                format!(
+                    "No supported package manager (apt-get, apk, dnf or brew) found; install {} manually",
+                    packages.packages.join(", ")
+                ),
+            ));
+        };
+
+        if !check.missing.is_empty() {
+            if !self.executor.is_container_executor() {
+                return Ok(result(
+                    1,
+                    String::new(),
+                    format!(
+                        "Missing system packages: {}. Install them with:\n  {}",
+                        check.missing.join(", "),
+                        manager.install_hint(&check.missing)
+                    ),
+                ));
+            }
+
+            info!(
+                "Installing system packages with {}: {}",
+                manager.name(),
+                check.missing.join(", ")
+            );
+            let install = self
+                .run_setup_script(cmd, SystemPackages::install_script(manager, &check.missing))
+                .await?;
+            if install.exit_code != 0 {
+                return Ok(result(install.exit_code, install.stdout, install.stderr));
+            }
+        }
+
+        self.satisfied_packages
+            .lock()
+            .unwrap()
+            .insert(packages.clone());
+        Ok(result(
+            0,
+            format!(
+                "System packages installed with {}: {}",
+                manager.name(),
+                packages.for_manager(manager).join(", ")
+            ),
+            String::new(),
+        ))
+    }
+
+    /// Run a script for a setup command through `sh -c` on the executor
+    async fn run_setup_script(
+        &self,
+        cmd: &SetupCommand,
+        script: String,
+    ) -> Result<crate::executor::ExecutionResult> {
+        let mut exec_cmd =
+            crate::executor::ExecutionCommand::new("sh", vec!["-c".to_string(), script]);
+        exec_cmd.working_dir = cmd.working_dir.clone();
+        exec_cmd.timeout = cmd
+            .timeout
+            .map(|d| d.to_std().unwrap_or(std::time::Duration::from_secs(300)));
+        self.executor
+            .execute(exec_cmd)
+            .await
+            .map_err(|e| anyhow::anyhow!("Command execution failed: {}", e))
+    }
+
+    /// Run a verification command (e.g. `cargo test`) in the workspace
+    ///
+    /// The command goes through `sh -c` on the active executor, so it runs in
+    /// the session container in container mode.
+    pub async fn run_verification(
+        &self,
+        command: &str,
+    ) -> Result<crate::executor::ExecutionResult> {
+        let mut exec_cmd = crate::executor::ExecutionCommand::new(
+            "sh",
+            vec!["-c".to_string(), command.to_string()],
+        );
+        if !self.executor.is_container_executor() {
+            exec_cmd = exec_cmd.with_working_dir(self.workspace_path.clone());
+        }
+        exec_cmd.env = self.ports.env();
+
+        self.executor
+            .execute(exec_cmd)
+            .await
+            .map_err(|e| anyhow::anyhow!("Verification command failed to run: {}", e))
+    }
+
+    /// Handle command execution errors
+    async fn handle_command_error(
+        &self,
+        cmd: &SetupCommand,
+        result: &SetupResult,
+        handler: &ErrorHandler,
+    ) -> Result<()> {
+        match &handler.strategy {
+            ErrorStrategy::Skip => {
+                warn!("Skipping failed command: {}", cmd.name);
+                Ok(())
+            }
+            ErrorStrategy::Retry {
+                max_attempts,
+                delay,
+            } => {
+                info!(
+                    "Retrying command '{}' (max {} attempts)",
+                    cmd.name, max_attempts
+                );
+                self.retry_command(cmd, *max_attempts, *delay).await
+            }
+            ErrorStrategy::Backup {
+                condition,
+                backup_command,
+                backup_args,
+            } => {
+                let environment = if condition.requires.is_empty() {
+                    None
+                } else {
+                    self.environment_report().await
+                };
+                if Self::should_run_backup(result, condition, environment.as_ref()) {
+                    info!("Running backup command for: {}", cmd.name);
+                    self.execute_backup_command(backup_command, backup_args, &cmd.working_dir)
+                        .await
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Backup condition not met for: {}",
+                        cmd.name
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Retry a command with delay
+    async fn retry_command(
+        &self,
+        cmd: &SetupCommand,
+        max_attempts: u32,
+        delay: chrono::Duration,
+    ) -> Result<()> {
+        for attempt in 1..=max_attempts {
+            info!(
+                "Retry attempt {}/{} for command: {}",
+                attempt, max_attempts, cmd.name
+            );
+
+            if let Ok(delay_std) = delay.to_std() {
+                tokio::time::sleep(delay_std).await;
+            }
+
+            let result = self.execute_shell_command(cmd).await?;
+            if result.success {
+                info!(
+                    "Command '{}' succeeded on retry attempt {}",
+                    cmd.name, attempt
+                );
+                return Ok(());
+            }
+
+            if attempt == max_attempts {
+                return Err(anyhow::anyhow!(
+                    "Command '{}' failed after {} attempts. Last error: {}",
+                    cmd.name,
+                    max_attempts,
+                    result.stderr
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute a backup command
+    async fn execute_backup_command(
+        &self,
+        backup_command: &str,
+        backup_args: &[String],
+        working_dir: &Option<std::path::PathBuf>,
+    ) -> Result<()> {
+        use crate::executor::ExecutionCommand;
+        use std::collections::HashMap;
+
+        // Use the executor to run the backup command
+        let exec_cmd = ExecutionCommand {
+            program: backup_command.to_string(),
+            args: backup_args.to_vec(),
+            working_dir: working_dir.clone(),
+            env: HashMap::new(),
+            stdin: None,
+            timeout: None,
+        };
+
+        let result = self
+            .executor
+            .execute(exec_cmd)
+            .await
+            .map_err(|e| anyhow::anyhow!("Backup command execution failed: {}", e))?;
+
+        if result.exit_code == 0 {
+            info!("Backup command executed successfully");
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Backup command failed: {}", result.stderr))
+        }
+    }
+
+    /// Probe the execution environment and share the report with the session
+    /// and the Claude interface
+    ///
+    /// A failed probe is logged; tasks then run without an environment report.
+    async fn probe_environment(&self) -> Option<EnvironmentReport> {
+        match EnvironmentReport::probe(&self.executor).await {
+            Ok(report) => {
+                info!(
+                    "Environment: {} {}, {} of {} probed binaries available",
+                    report.os.as_deref().unwrap_or("unknown OS"),
+                    report.arch.as_deref().unwrap_or("unknown architecture"),
+                    report.binaries.len(),
+                    crate::executor::environment::PROBED_BINARIES.len()
+                );
+                self.session_manager
+                    .set_environment_report(report.clone())
+                    .await;
+                self.describe_environment().await;
+                Some(report)
+            }
+            Err(e) => {
+                warn!("Failed to probe the execution environment: {}", e);
+                None
+            }
+        }
+    }
+
+    /// The session's environment report, probing now if no plan has yet
+    async fn environment_report(&self) -> Option<EnvironmentReport> {
+        match self.session_manager.environment_report().await {
+            Some(report) => Some(report),
+            None => self.probe_environment().await,
+        }
+    }
+
+    /// Check if backup should be triggered based on output analysis
+    ///
+    /// Binaries the condition requires are checked against `environment`;
+    /// without a report they cannot be checked and the backup is skipped.
+    fn should_run_backup(
+        result: &SetupResult,
+        condition: &OutputCondition,
+        environment: Option<&EnvironmentReport>,
+    ) -> bool {
+        if !condition.requires.is_empty() {
+            let Some(environment) = environment else {
+                return false;
+            };
+            let missing = environment.missing_binaries(&condition.requires);
+            if !missing.is_empty() {
+                info!("Backup needs {} which is not available", missing.join(", "));
+                return false;
+            }
+        }
+
+        // Check exit code range first
+        if let Some((min, max)) = condition.exit_code_range
+            && (result.exit_code < min || result.exit_code > max)
+        {
+            return false;
+        }
+
+        // Check for required text in output
+        if let Some(must_contain) = &condition.contains {
+            let output = if condition.check_stdout {
+                &result.stdout
+            } else {
+                &result.stderr
+            };
+            if !output.contains(must_contain) {
+                return false;
+            }
+        }
+
+        // Check for forbidden text in output
+        if let Some(must_not_contain) = &condition.not_contains {
+            let output = if condition.check_stdout {
+                &result.stdout
+            } else {
+                &result.stderr
+            };
+            if output.contains(must_not_contain) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Getters for individual components
+    pub fn task_manager(&self) -> Arc<TaskManager> {
+        self.task_manager.clone()
+    }
+
+    /// Handle to the session, shareable with other surfaces
+    pub fn session_manager(&self) -> SessionManager {
+        self.session_manager.clone()
+    }
+
+    /// Receive the events this system emits from now on
+    ///
+    /// A receiver that falls more than [`crate::events::EVENT_CAPACITY`]
+    /// events behind gets `RecvError::Lagged` and skips the oldest ones.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<AgentEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn claude_interface(&self) -> Arc<ClaudeCodeInterface> {
+        self.claude_interface.clone()
+    }
+
+    /// Sync container info with session metadata
+    ///
+    /// This should be called after the container is first created (e.g., after setup commands)
+    /// to store container metadata in the session for persistence and recovery.
+    #[cfg(feature = "containers")]
+    pub async fn sync_container_info(&self) {
+        if let Some(container_info) = self.executor.container_info().await {
+            self.session_manager
+                .set_container_info(container_info)
+                .await;
+            info!("Container info synced with session metadata");
+        }
+    }
+}
+
+/// What a plan record needs to know about a plan task once it finishes
+struct PlanTaskIdentity {
+    id: Uuid,
+    key: String,
+    title: String,
+    fingerprint: String,
+}
+
+/// Identity of a plan task loaded from a file, captured before it runs
+fn plan_task_identity(task_spec: &TaskSpec) -> Option<PlanTaskIdentity> {
+    tagged_idempotency_key(&task_spec.metadata.tags).map(|id| PlanTaskIdentity {
+        id,
+        key: task_key(task_spec),
+        title: task_spec.title.clone(),
+        fingerprint: task_fingerprint(task_spec),
+    })
+}
+
+/// Whether running a plan task calls the provider, rather than recording it
+/// as done or expanding its sub-plan
+fn needs_provider(task_spec: &TaskSpec) -> bool {
+    task_spec.plan.is_none()
+        && !task_spec
+            .metadata
+            .tags
+            .iter()
+            .any(|tag| tag == PRECOMPLETED_TAG)
+}
+
+/// The task an error left held rather than failed: queued until a provider
+/// is reachable, or awaiting an answer
+fn held_task(error: &anyhow::Error) -> Option<Uuid> {
+    error
+        .downcast_ref::<QueuedOffline>()
+        .map(|queued| queued.task_id)
+        .or_else(|| {
+            error
+                .downcast_ref::<AwaitingAnswer>()
+                .map(|awaiting| awaiting.task_id)
+        })
+}
+
+fn attach_task_diff(task: &mut crate::task::Task, diff: &TaskDiff) {
+    if let TaskStatus::Completed {
+        result:
+            crate::task::types::TaskResult::Success {
+                output,
+                files_created,
+                files_modified,
+                ..
+            },
+        ..
+    } = &mut task.status
+    {
+        files_created.clone_from(&diff.changes.created);
+        files_modified.clone_from(&diff.changes.modified);
+        if let (Some(output), Ok(diff)) = (output.as_object_mut(), serde_json::to_value(diff)) {
+            output.insert("workspace_diff".to_string(), diff);
+        }
+    }
+}
+
+fn attach_duration(task: &mut crate::task::Task, elapsed: chrono::Duration) {
+    if let TaskStatus::Completed {
+        result: crate::task::types::TaskResult::Success { output, .. },
+        ..
+    } = &mut task.status
+        && let Some(output) = output.as_object_mut()
+    {
+        output.insert(
+            crate::task::rollup::DURATION_KEY.to_string(),
+            serde_json::json!(elapsed.num_milliseconds().max(0)),
+        );
+    }
+}
+
+fn attach_completion_report(task: &mut crate::task::Task, report: &CompletionReport) {
+    if let TaskStatus::Completed {
+        result: crate::task::types::TaskResult::Success { output, .. },
+        ..
+    } = &mut task.status
+        && let (Some(output), Ok(report)) = (output.as_object_mut(), serde_json::to_value(report))
+    {
+        output.insert("completion_report".to_string(), report);
+    }
+}
+
+/// Suspends the provider process and, in container mode, the sandbox
+struct AgentSuspender {
+    host: HostSuspender,
+    executor: crate::executor::CommandExecutor,
+}
+
+impl TaskSuspender for AgentSuspender {
+    fn suspend(&self, task_id: Uuid) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(async move {
+            let process = self.host.suspend(task_id).await?;
+            let sandbox = self.executor.pause().await?;
+            Ok(process || sandbox)
+        })
+    }
+
+    fn resume(&self, task_id: Uuid) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(async move {
+            let sandbox = self.executor.unpause().await?;
+            let process = self.host.resume(task_id).await?;
+            Ok(process || sandbox)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{ErrorHandler, OutputCondition};
+    use chrono::Duration;
+    use std::path::PathBuf;
+
+    // Helper function to create a test AgentSystem with minimal configuration
+    async fn create_test_agent_system() -> AgentSystem {
+        let temp_dir = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let config = AgentConfig {
+            workspace_path: temp_dir.clone(),
+            session_config: crate::session::SessionManagerConfig::default(),
+            task_config: crate::task::TaskManagerConfig::default(),
+            claude_config: crate::claude::ClaudeConfig::default(),
+            setup_commands: vec![],
+            services: vec![],
+            execution_mode: crate::executor::RuntimeMode::Host,
+            container_runtime: crate::executor::ContainerRuntime::Auto,
+            docker_context: None,
+            instructions: InstructionsConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            schedule: ScheduleConfig::default(),
+            questions: QuestionConfig::default(),
+            watch: WatchConfig::default(),
+            refactor_guard: RefactorGuardConfig::default(),
+            completion_gates: CompletionGateConfig::default(),
+            budget: BudgetConfig::default(),
+            log_retention: LogRetentionConfig::default(),
+            remote_sync: RemoteSyncConfig::default(),
+            distributed: DistributedConfig::default(),
+            placement: PlacementConfig::default(),
+            context_budget: ContextBudgetConfig::default(),
+            nix: crate::executor::NixConfig::default(),
+            offline: OfflineConfig::default(),
+            routing: RoutingConfig::default(),
+            verification: VerificationConfig::default(),
+            spot_check: SpotCheckConfig::default(),
+            policy: PolicyConfig::default(),
+            report_signing: ReportSigningConfig::default(),
+            anonymization: AnonymizationConfig::default(),
+            mcp: McpConfig::default(),
+            prompt_size: PromptSizeConfig::default(),
+            rollup: RollupConfig::default(),
+            wait: WaitConfig::default(),
+            ports: PortConfig::default(),
+            text_files: TextFileConfig::default(),
+        };
+
+        AgentSystem::new(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_precompleted_plan_tasks_are_not_run() {
+        let agent = create_test_agent_system().await;
+        let mut spec = TaskSpec {
+            title: "Add metrics".to_string(),
+            description: "Already done by hand".to_string(),
+            ..Default::default()
+        };
+        spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+
+        let task_ids = agent
+            .execute_plan(crate::task::ExecutionPlan::new().with_task(spec))
+            .await
+            .unwrap();
+        assert_eq!(task_ids.len(), 1);
+
+        let task = agent.task_manager().get_task(task_ids[0]).await.unwrap();
+        assert!(matches!(task.status, TaskStatus::Completed { .. }));
+        assert!(task.execution_history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_operator_task_tree_lookup_and_cancel() {
+        let agent = create_test_agent_system().await;
+        let task_manager = agent.task_manager();
+        let spec = |title: &str| TaskSpec {
+            title: title.to_string(),
+            description: title.to_string(),
+            ..Default::default()
+        };
+        let epic = task_manager.create_task(spec("Epic"), None).await.unwrap();
+        let first = task_manager
+            .create_task(spec("First step"), Some(epic))
+            .await
+            .unwrap();
+        let second = task_manager
+            .create_task(spec("Second step"), Some(epic))
+            .await
+            .unwrap();
+        let other = task_manager.create_task(spec("Other"), None).await.unwrap();
+        task_manager
+            .complete_task(
+                first,
+                crate::task::types::TaskResult::Success {
+                    output: serde_json::json!({}),
+                    files_created: Vec::new(),
+                    files_modified: Vec::new(),
+                    build_artifacts: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let tree: Vec<_> = agent
+            .task_tree()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(depth, task)| (depth, task.title))
+            .collect();
+        assert_eq!(
+            tree,
+            [
+                (0, "Epic".to_string()),
+                (1, "First step".to_string()),
+                (1, "Second step".to_string()),
+                (0, "Other".to_string()),
+            ]
+        );
+
+        let short_id = &second.to_string()[..8];
+        assert_eq!(agent.find_task(short_id).await.unwrap().id, second);
+        assert!(agent.find_task("zz").await.is_err());
+
+        // The completed subtask keeps its status
+        let mut cancelled = agent.cancel_task(epic, "Cancelled by user").await.unwrap();
+        cancelled.sort();
+        let mut expected = vec![epic, second];
+        expected.sort();
+        assert_eq!(cancelled, expected);
+        let first = task_manager.get_task(first).await.unwrap();
+        assert!(matches!(first.status, TaskStatus::Completed { .. }));
+        assert!(agent.cancel_task(second, "again").await.is_err());
+        assert!(agent.retry_task(other).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plan_verifies_at_verify_points_and_plan_end() {
+        let agent = create_test_agent_system().await;
+        let mut plan = crate::task::ExecutionPlan::new().with_verification(VerificationConfig {
+            command: Some("echo verified >> verify.log".to_string()),
+            every: 0,
+            ..Default::default()
+        });
+        for title in ["Add schema", "Add endpoint", "Write docs"] {
+            let mut spec = TaskSpec {
+                title: title.to_string(),
+                description: "Already done by hand".to_string(),
+                ..Default::default()
+            };
+            spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+            if title == "Add schema" {
+                spec.metadata
+                    .tags
+                    .push(crate::task::VERIFY_POINT_TAG.to_string());
+            }
+            plan = plan.with_task(spec);
+        }
+
+        let task_ids = agent.execute_plan(plan).await.unwrap();
+        assert_eq!(task_ids.len(), 3);
+        let log = std::fs::read_to_string(agent.workspace_path.join("verify.log")).unwrap();
+        assert_eq!(log.lines().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_affected_only_verification_skips_untested_groups() {
+        let agent = create_test_agent_system().await;
+        let mut plan = crate::task::ExecutionPlan::new().with_verification(VerificationConfig {
+            command: Some("echo verified >> verify.log".to_string()),
+            every: 1,
+            affected_only: true,
+            ..Default::default()
+        });
+        for title in ["Add schema", "Add endpoint"] {
+            let mut spec = TaskSpec {
+                title: title.to_string(),
+                description: "Already done by hand".to_string(),
+                ..Default::default()
+            };
+            spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+            plan = plan.with_task(spec);
+        }
+
+        // The tasks change no files, so only the plan-end full run happens
+        agent.execute_plan(plan).await.unwrap();
+        let log = std::fs::read_to_string(agent.workspace_path.join("verify.log")).unwrap();
+        assert_eq!(log.lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_setup_and_checkpoint_events() {
+        let agent = create_test_agent_system().await;
+        let mut events = agent.subscribe();
+        let mut spec = TaskSpec {
+            title: "Write docs".to_string(),
+            description: "Already done by hand".to_string(),
+            ..Default::default()
+        };
+        spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+        let plan = crate::task::ExecutionPlan::new()
+            .with_setup_command(SetupCommand::new("check", "true"))
+            .with_task(spec);
+        agent.execute_plan(plan).await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert!(matches!(
+            &received[0],
+            AgentEvent::SetupCommandRun { name, success: true, exit_code: 0, .. } if name == "check"
+        ));
+        assert!(received.iter().any(|event| matches!(
+            event,
+            AgentEvent::CheckpointCreated { description, .. }
+                if description == "plan_execution_complete"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_plan_ports_reach_commands_and_leaks_are_reported() {
+        let agent = create_test_agent_system().await;
+        let mut events = agent.subscribe();
+        let mut spec = TaskSpec {
+            title: "Add endpoint".to_string(),
+            description: "Already done by hand".to_string(),
+            ..Default::default()
+        };
+        spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+        let plan = crate::task::ExecutionPlan {
+            ports: vec!["API_PORT".to_string()],
+            ..crate::task::ExecutionPlan::new()
+                .with_verification(VerificationConfig {
+                    command: Some("echo $API_PORT > port.txt".to_string()),
+                    every: 0,
+                    ..Default::default()
+                })
+                .with_task(spec)
+        };
+        agent.execute_plan(plan).await.unwrap();
+        let port: u16 = std::fs::read_to_string(agent.workspace_path.join("port.txt"))
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        assert!((20000..=29999).contains(&port));
+        assert!(agent.ports.ports().is_empty());
+
+        // A listener opened during a plan is reported when it ends
+        let plan = crate::task::ExecutionPlan {
+            ports: vec!["API_PORT".to_string()],
+            ..crate::task::ExecutionPlan::new()
+        };
+        let listening = agent.allocate_ports(&plan).await.unwrap();
+        let port = agent.ports.ports()["API_PORT"];
+        assert!(
+            agent
+                .claude_interface
+                .system_message()
+                .unwrap()
+                .contains(&format!("- API_PORT={}", port))
+        );
+        let _listener = std::net::TcpListener::bind(("0.0.0.0", port)).unwrap();
+        agent.release_ports(listening).await;
+
+        let mut leaked = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::ListenerLeaked { port, name } = event {
+                leaked.push((port, name));
+            }
+        }
+        // Tests running alongside may open listeners of their own
+        assert!(leaked.contains(&(port, Some("API_PORT".to_string()))));
+    }
+
+    #[tokio::test]
+    async fn test_priority_overrides_reorder_plan_tasks() {
+        let agent = create_test_agent_system().await;
+        let priorities = crate::env::priorities_file_path(&agent.workspace_path);
+        std::fs::create_dir_all(priorities.parent().unwrap()).unwrap();
+        std::fs::write(&priorities, "\"add metrics\" = { priority = \"high\" }\n").unwrap();
+
+        let mut plan = crate::task::ExecutionPlan::new();
+        for title in ["Write docs", "Add metrics"] {
+            let mut spec = TaskSpec {
+                title: title.to_string(),
+                description: "Already done by hand".to_string(),
+                ..Default::default()
+            };
+            spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+            plan = plan.with_task(spec);
+        }
+
+        let task_ids = agent.execute_plan(plan).await.unwrap();
+        let first = agent.task_manager().get_task(task_ids[0]).await.unwrap();
+        assert_eq!(first.title, "Add metrics");
+        assert_eq!(first.metadata.priority, crate::task::TaskPriority::High);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_plan_waits_for_dependencies() {
+        let agent = create_test_agent_system().await;
+        let priorities = crate::env::priorities_file_path(&agent.workspace_path);
+        std::fs::create_dir_all(priorities.parent().unwrap()).unwrap();
+        std::fs::write(&priorities, "\"write docs\" = { priority = \"high\" }\n").unwrap();
+
+        let mut plan = crate::task::ExecutionPlan::new().with_parallel_execution(Some(4));
+        for (title, dependencies) in [
+            ("Write docs", vec![crate::task::plan_task_id("Add metrics")]),
+            ("Add metrics", Vec::new()),
+        ] {
+            let mut spec = TaskSpec {
+                title: title.to_string(),
+                description: "Already done by hand".to_string(),
+                dependencies,
+                ..Default::default()
+            };
+            spec.metadata.tags.push(PRECOMPLETED_TAG.to_string());
+            plan = plan.with_task(spec);
+        }
+
+        // The override cannot start docs before the metrics they depend on
+        let task_ids = agent.execute_plan(plan).await.unwrap();
+        assert_eq!(task_ids.len(), 2);
+        let first = agent.task_manager().get_task(task_ids[0]).await.unwrap();
+        let second = agent.task_manager().get_task(task_ids[1]).await.unwrap();
+        assert_eq!(first.title, "Add metrics");
+        assert_eq!(second.title, "Write docs");
+        assert_eq!(second.dependencies, vec![first.id]);
+    }
+
+    #[tokio::test]
+    async fn test_task_over_budget_is_blocked() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let agent = AgentSystem::new(AgentConfig {
+            workspace_path: workspace,
+            budget: BudgetConfig {
+                max_session_cost_usd: Some(0.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let task_id = agent
+            .task_manager()
+            .create_task(
+                TaskSpec {
+                    title: "Add metrics".to_string(),
+                    description: "Add request metrics".to_string(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(agent.process_task(task_id).await.is_err());
+
+        let task = agent.task_manager().get_task(task_id).await.unwrap();
+        let TaskStatus::Blocked {
+            reason: crate::task::BlockReason::BudgetExceeded { scope, limit, .. },
+            ..
+        } = task.status
+        else {
+            panic!(
+                "expected a task blocked by its budget, got {:?}",
+                task.status
+            );
+        };
+        assert_eq!(scope, crate::llm::BudgetScope::Session);
+        assert_eq!(limit, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_unmet_wait_condition_fails_task() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let agent = AgentSystem::new(AgentConfig {
+            workspace_path: workspace,
+            wait: WaitConfig {
+                timeout_secs: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let mut spec = TaskSpec {
+            title: "Run smoke tests".to_string(),
+            description: "Run the smoke tests against the dev server".to_string(),
+            ..Default::default()
+        };
+        spec.metadata.wait_for = vec![WaitCondition::File("server.pid".into())];
+        let task_id = agent.task_manager().create_task(spec, None).await.unwrap();
+        assert!(agent.process_task(task_id).await.is_err());
+
+        let task = agent.task_manager().get_task(task_id).await.unwrap();
+        let TaskStatus::Failed { error, .. } = task.status else {
+            panic!("expected a failed task, got {:?}", task.status);
+        };
+        assert_eq!(error.kind(), "timeout_error");
+        assert!(error.to_string().contains("server.pid to exist"));
+    }
+
+    #[tokio::test]
+    async fn test_services_start_ready_and_stop() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let agent = AgentSystem::new(AgentConfig {
+            workspace_path: workspace.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let web = ServiceSpec {
+            name: "web".to_string(),
+            command: "sleep 0.2; touch web.ready; exec sleep 60".to_string(),
+            ready: Some(WaitCondition::File("web.ready".into())),
+            ready_timeout_secs: 10,
+            stop_timeout_secs: 1,
+            ..Default::default()
+        };
+        agent
+            .start_services(std::slice::from_ref(&web))
+            .await
+            .unwrap();
+        let records = agent.session_manager.services().await;
+        assert_eq!(records.len(), 1);
+        assert!(crate::executor::service::is_running(&agent.executor, &records[0]).await);
+        agent.check_services().await.unwrap();
+
+        agent.stop_services().await;
+        assert!(agent.session_manager.services().await.is_empty());
+        assert!(!crate::executor::service::is_running(&agent.executor, &records[0]).await);
+
+        // A service that exits before it is ready fails the plan
+        let broken = ServiceSpec {
+            name: "broken".to_string(),
+            command: "exit 1".to_string(),
+            ready: Some(WaitCondition::File("never".into())),
+            ..Default::default()
+        };
+        let error = agent.start_services(&[web, broken]).await.unwrap_err();
+        assert!(error.to_string().contains("Service 'broken' exited"));
+        assert!(agent.session_manager.services().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_record_the_runs_tasks() {
+        let agent = create_test_agent_system().await;
+        let task_id = agent
+            .task_manager()
+            .create_task(
+                TaskSpec {
+                    title: "Add metrics".to_string(),
+                    description: "Export request counts".to_string(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        agent.save_session_checkpoint("manual").await.unwrap();
+        let checkpoint = agent
+            .session_manager()
+            .list_checkpoints(false)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|checkpoint| checkpoint.description == "manual")
+            .unwrap();
+        // The checkpoint holds the task tree the agent ran with
+        assert_eq!(checkpoint.task_count, 1);
+        assert!(
+            agent
+                .session_manager()
+                .task_manager()
+                .get_task(task_id)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_run_is_saved_for_continue() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let config = AgentConfig {
+            workspace_path: workspace,
+            ..Default::default()
+        };
+        let agent = AgentSystem::new(config.clone()).await.unwrap();
+        let spec = |title: &str, dependencies: Vec<Uuid>| TaskSpec {
+            title: title.to_string(),
+            description: title.to_string(),
+            dependencies,
+            ..Default::default()
+        };
+        let schema_id = crate::task::plan_task_id("Add schema");
+
+        // "Add schema" was running, the other two had not started
+        let started_at = chrono::Utc::now();
+        *agent.plan_specs.lock().unwrap() = vec![
+            spec("Add schema", Vec::new()),
+            spec("Add endpoint", vec![schema_id]),
+            spec(
+                "Write docs",
+                vec![crate::task::plan_task_id("Done earlier")],
+            ),
+        ];
+        let running = agent
+            .task_manager()
+            .create_task(spec("Add schema", Vec::new()), None)
+            .await
+            .unwrap();
+        agent
+            .task_manager()
+            .update_task_status(
+                running,
+                TaskStatus::InProgress {
+                    started_at: chrono::Utc::now(),
+                    estimated_completion: None,
+                },
+            )
+            .await
+            .unwrap();
+        agent.request_stop();
+        assert!(agent.stop_requested());
+
+        assert_eq!(
+            agent.save_interrupted(started_at).await.unwrap(),
+            vec![running]
+        );
+        let task = agent.task_manager().get_task(running).await.unwrap();
+        assert!(
+            matches!(task.status, TaskStatus::Paused { ref reason, .. } if reason == INTERRUPTED_REASON)
+        );
+
+        let mut saved = HashMap::new();
+        for task_id in agent
+            .task_manager()
+            .get_tasks_by_status(|_| true)
+            .await
+            .unwrap()
+        {
+            let task = agent.task_manager().get_task(task_id).await.unwrap();
+            saved.insert(task.title.clone(), task);
         }
+        assert_eq!(saved.len(), 3);
+        // Dependencies point at the tree task, or are dropped if an earlier run completed them
+        assert_eq!(saved["Add endpoint"].dependencies, vec![running]);
+        assert!(saved["Write docs"].dependencies.is_empty());
+        let eligible = agent.task_manager().get_eligible_tasks().await.unwrap();
+        assert!(eligible.contains(&saved["Write docs"].id));
+        assert!(!eligible.contains(&saved["Add endpoint"].id));
+
+        // `aca resume` finds the paused and unstarted tasks in the checkpoint
+        let checkpoint = agent
+            .session_manager()
+            .list_checkpoints(false)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|checkpoint| checkpoint.description == "interrupted")
+            .unwrap();
+        assert_eq!(checkpoint.task_count, 3);
+        agent.shutdown().await.unwrap();
+        let resumed = AgentSystem::new_from_checkpoint(config, &checkpoint.id)
+            .await
+            .unwrap();
+        let task = resumed.task_manager().get_task(running).await.unwrap();
+        assert!(
+            matches!(task.status, TaskStatus::Paused { ref reason, .. } if reason == INTERRUPTED_REASON)
+        );
+        let pending = resumed
+            .task_manager()
+            .get_tasks_by_status(|status| matches!(status, TaskStatus::Pending))
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.contains(&saved["Add endpoint"].id));
+        assert!(pending.contains(&saved["Write docs"].id));
     }
 
-    /// Retry a command with delay
-    async fn retry_command(
-        &self,
-        cmd: &SetupCommand,
-        max_attempts: u32,
-        delay: chrono::Duration,
-    ) -> Result<()> {
-        for attempt in 1..=max_attempts {
-            info!(
-                "Retry attempt {}/{} for command: {}",
-                attempt, max_attempts, cmd.name
-            );
+    struct UnreachableProbe;
 
-            if let Ok(delay_std) = delay.to_std() {
-                tokio::time::sleep(delay_std).await;
-            }
+    impl HealthProbe for UnreachableProbe {
+        fn check(&self) -> BoxFuture<'_, Result<(), String>> {
+            Box::pin(async { Err("connection refused".to_string()) })
+        }
+    }
 
-            let result = self.execute_shell_command(cmd).await?;
-            if result.success {
-                info!(
-                    "Command '{}' succeeded on retry attempt {}",
-                    cmd.name, attempt
-                );
-                return Ok(());
-            }
+    /// Local model that answers every request with the same response
+    struct ScriptedModel {
+        response: String,
+    }
 
-            if attempt == max_attempts {
-                return Err(anyhow::anyhow!(
-                    "Command '{}' failed after {} attempts. Last error: {}",
-                    cmd.name,
-                    max_attempts,
-                    result.stderr
-                ));
-            }
+    impl crate::llm::LLMProvider for ScriptedModel {
+        fn execute_request<'a>(
+            &'a self,
+            request: crate::llm::types::LLMRequest,
+            _logger: &'a crate::llm::provider_logger::ProviderLogger,
+        ) -> BoxFuture<'a, Result<crate::llm::types::LLMResponse, crate::llm::types::LLMError>>
+        {
+            Box::pin(async move {
+                Ok(crate::llm::types::LLMResponse {
+                    request_id: request.id,
+                    content: self.response.clone(),
+                    model_used: "scripted".to_string(),
+                    token_usage: Default::default(),
+                    execution_time: std::time::Duration::ZERO,
+                    provider_metadata: HashMap::new(),
+                })
+            })
         }
-        Ok(())
-    }
 
-    /// Execute a backup command
-    async fn execute_backup_command(
-        &self,
-        backup_command: &str,
-        backup_args: &[String],
-        working_dir: &Option<std::path::PathBuf>,
-    ) -> Result<()> {
-        use crate::executor::ExecutionCommand;
-        use std::collections::HashMap;
+        fn get_capabilities(
+            &self,
+        ) -> BoxFuture<
+            '_,
+            Result<crate::llm::types::ProviderCapabilities, crate::llm::types::LLMError>,
+        > {
+            Box::pin(async {
+                Err(crate::llm::types::LLMError::ProviderUnavailable(
+                    "scripted".to_string(),
+                ))
+            })
+        }
 
-        // Use the executor to run the backup command
-        let exec_cmd = ExecutionCommand {
-            program: backup_command.to_string(),
-            args: backup_args.to_vec(),
-            working_dir: working_dir.clone(),
-            env: HashMap::new(),
-            stdin: None,
-            timeout: None,
-        };
+        fn get_status(
+            &self,
+        ) -> BoxFuture<'_, Result<crate::llm::types::ProviderStatus, crate::llm::types::LLMError>>
+        {
+            Box::pin(async {
+                Err(crate::llm::types::LLMError::ProviderUnavailable(
+                    "scripted".to_string(),
+                ))
+            })
+        }
 
-        let result = self
-            .executor
-            .execute(exec_cmd)
-            .await
-            .map_err(|e| anyhow::anyhow!("Backup command execution failed: {}", e))?;
+        fn health_check(&self) -> BoxFuture<'_, Result<(), crate::llm::types::LLMError>> {
+            Box::pin(async { Ok(()) })
+        }
 
-        if result.exit_code == 0 {
-            info!("Backup command executed successfully");
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Backup command failed: {}", result.stderr))
+        fn provider_name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>, crate::llm::types::LLMError>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn estimate_tokens(&self, text: &str) -> u64 {
+            text.len() as u64 / 4
         }
     }
 
-    /// Check if backup should be triggered based on output analysis
-    fn should_run_backup(result: &SetupResult, condition: &OutputCondition) -> bool {
-        // Check exit code range first
-        if let Some((min, max)) = condition.exit_code_range
-            && (result.exit_code < min || result.exit_code > max)
+    /// Local model whose first request never answers, recording every prompt
+    ///
+    /// Like the CLI, the stuck request runs in a registered process group
+    /// with a tool of its own, whose pid is recorded in `tools`.
+    #[derive(Default)]
+    struct StuckOnceModel {
+        prompts: std::sync::Mutex<Vec<String>>,
+        processes: Arc<crate::task::ProcessRegistry>,
+        tools: std::sync::Mutex<Vec<u32>>,
+    }
+
+    impl crate::llm::LLMProvider for StuckOnceModel {
+        fn execute_request<'a>(
+            &'a self,
+            request: crate::llm::types::LLMRequest,
+            logger: &'a crate::llm::provider_logger::ProviderLogger,
+        ) -> BoxFuture<'a, Result<crate::llm::types::LLMResponse, crate::llm::types::LLMError>>
         {
-            return false;
+            let first = {
+                let mut prompts = self.prompts.lock().unwrap();
+                prompts.push(request.prompt.clone());
+                prompts.len() == 1
+            };
+            Box::pin(async move {
+                if first {
+                    use tokio::io::AsyncBufReadExt;
+
+                    let mut child = tokio::process::Command::new("sh")
+                        .args(["-c", "sleep 30 & echo $!; wait"])
+                        .process_group(0)
+                        .stdout(std::process::Stdio::piped())
+                        .kill_on_drop(true)
+                        .spawn()
+                        .unwrap();
+                    let _registration = self.processes.register(request.id, child.id().unwrap());
+                    let mut lines = tokio::io::BufReader::new(child.stdout.take().unwrap()).lines();
+                    let tool = lines.next_line().await.unwrap().unwrap();
+                    self.tools.lock().unwrap().push(tool.parse().unwrap());
+                    std::future::pending::<()>().await;
+                }
+                ScriptedModel {
+                    response: "Done.\n<aca-summary>{\"confidence\": 0.9}</aca-summary>".to_string(),
+                }
+                .execute_request(request, logger)
+                .await
+            })
         }
 
-        // Check for required text in output
-        if let Some(must_contain) = &condition.contains {
-            let output = if condition.check_stdout {
-                &result.stdout
-            } else {
-                &result.stderr
-            };
-            if !output.contains(must_contain) {
-                return false;
-            }
+        fn get_capabilities(
+            &self,
+        ) -> BoxFuture<
+            '_,
+            Result<crate::llm::types::ProviderCapabilities, crate::llm::types::LLMError>,
+        > {
+            Box::pin(async {
+                Err(crate::llm::types::LLMError::ProviderUnavailable(
+                    "scripted".to_string(),
+                ))
+            })
         }
 
-        // Check for forbidden text in output
-        if let Some(must_not_contain) = &condition.not_contains {
-            let output = if condition.check_stdout {
-                &result.stdout
-            } else {
-                &result.stderr
-            };
-            if output.contains(must_not_contain) {
-                return false;
+        fn get_status(
+            &self,
+        ) -> BoxFuture<'_, Result<crate::llm::types::ProviderStatus, crate::llm::types::LLMError>>
+        {
+            Box::pin(async {
+                Err(crate::llm::types::LLMError::ProviderUnavailable(
+                    "scripted".to_string(),
+                ))
+            })
+        }
+
+        fn health_check(&self) -> BoxFuture<'_, Result<(), crate::llm::types::LLMError>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>, crate::llm::types::LLMError>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn estimate_tokens(&self, text: &str) -> u64 {
+            text.len() as u64 / 4
+        }
+    }
+
+    /// A plan task routed to a [`ScriptedModel`]
+    fn local_task(title: &str) -> TaskSpec {
+        let mut spec = TaskSpec {
+            title: title.to_string(),
+            description: title.to_string(),
+            ..Default::default()
+        };
+        spec.metadata.provider_preference.provider = Some(ProviderType::LocalModel);
+        spec
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_plan_task_is_not_recorded() {
+        for parallel in [false, true] {
+            let agent = create_test_agent_system().await;
+            agent
+                .router
+                .set_provider(
+                    ProviderType::LocalModel,
+                    Arc::new(ScriptedModel {
+                        response:
+                            "<aca-question>{\"question\": \"Which database?\"}</aca-question>"
+                                .to_string(),
+                    }),
+                )
+                .await;
+            let source = agent.workspace_path.join("tasks.md");
+            std::fs::write(&source, "- [ ] Add storage\n").unwrap();
+            let mut plan = crate::task::ExecutionPlan::new()
+                .with_source(&source)
+                .with_task(local_task("Add storage"));
+            if parallel {
+                plan = plan.with_parallel_execution(Some(2));
             }
+
+            // Nobody answers, so the task waits instead of completing
+            assert!(agent.execute_plan(plan).await.unwrap().is_empty());
+            let awaiting = agent
+                .task_manager()
+                .get_tasks_by_status(|status| matches!(status, TaskStatus::AwaitingInput { .. }))
+                .await
+                .unwrap();
+            assert_eq!(awaiting.len(), 1);
+            let record =
+                PlanRecord::load(&agent.workspace_path, &source.canonicalize().unwrap()).unwrap();
+            assert!(record.completed.is_empty(), "parallel: {}", parallel);
         }
+    }
 
-        true
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_critical_task_preempts_and_resumes_background_task() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let agent = AgentSystem::new(AgentConfig {
+            workspace_path: workspace,
+            task_config: crate::task::TaskManagerConfig {
+                max_concurrent_tasks: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let model = Arc::new(StuckOnceModel {
+            processes: agent.claude_interface.process_registry(),
+            ..Default::default()
+        });
+        agent
+            .router
+            .set_provider(ProviderType::LocalModel, model.clone())
+            .await;
+        let mut background = local_task("Reindex search");
+        background.metadata.priority = crate::task::TaskPriority::Background;
+        let background = agent
+            .task_manager()
+            .create_task(background, None)
+            .await
+            .unwrap();
+        let mut critical = local_task("Fix outage");
+        critical.metadata.priority = crate::task::TaskPriority::Critical;
+        let critical = agent
+            .task_manager()
+            .create_task(critical, None)
+            .await
+            .unwrap();
+
+        // The background task holds the only slot until the critical one arrives
+        let (background_result, critical_result) =
+            tokio::join!(agent.process_task(background), async {
+                while model.tools.lock().unwrap().is_empty() {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+                agent.process_task(critical).await
+            });
+        background_result.unwrap();
+        critical_result.unwrap();
+
+        // It was paused for the critical task, then resumed and completed
+        let prompts = model.prompts.lock().unwrap().clone();
+        assert_eq!(prompts.len(), 3);
+        assert!(prompts[0].contains("Reindex search"));
+        assert!(prompts[1].contains("Fix outage"));
+        assert!(prompts[2].contains("Reindex search"));
+        // Preempting killed the tool the stuck turn had started
+        let tool = model.tools.lock().unwrap()[0];
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", tool)).unwrap_or_default();
+        assert!(
+            stat.is_empty() || stat[stat.rfind(')').unwrap() + 2..].starts_with('Z'),
+            "the preempted turn's tool kept running"
+        );
+        for task_id in [background, critical] {
+            let task = agent.task_manager().get_task(task_id).await.unwrap();
+            assert!(matches!(task.status, TaskStatus::Completed { .. }));
+        }
+        let checkpoints = agent
+            .session_manager()
+            .list_checkpoints(false)
+            .await
+            .unwrap();
+        assert!(
+            checkpoints
+                .iter()
+                .any(|checkpoint| checkpoint.description == format!("preempted_{}", background))
+        );
     }
 
-    // Getters for individual components
-    pub fn task_manager(&self) -> Arc<TaskManager> {
-        self.task_manager.clone()
+    #[tokio::test]
+    async fn test_session_provider_runs_later_tasks() {
+        let agent = create_test_agent_system().await;
+        agent
+            .router
+            .set_provider(
+                ProviderType::LocalModel,
+                Arc::new(ScriptedModel {
+                    response: "Done.\n<aca-summary>{\"confidence\": 0.9}</aca-summary>".to_string(),
+                }),
+            )
+            .await;
+
+        agent.set_session_provider(Some(ProviderType::LocalModel));
+        let task_id = agent
+            .create_and_process_task("User Task", "Add metrics")
+            .await
+            .unwrap();
+        let task = agent.task_manager().get_task(task_id).await.unwrap();
+        let TaskStatus::Completed {
+            result: crate::task::types::TaskResult::Success { output, .. },
+            ..
+        } = task.status
+        else {
+            panic!("task did not complete: {:?}", task.status);
+        };
+        assert_eq!(output["provider"], "scripted");
     }
 
-    pub fn session_manager(&self) -> Arc<SessionManager> {
-        self.session_manager.clone()
+    #[tokio::test]
+    async fn test_snapshots_leave_out_changes_made_while_waiting_for_a_slot() {
+        let agent = create_test_agent_system().await;
+        agent
+            .router
+            .set_provider(
+                ProviderType::LocalModel,
+                Arc::new(ScriptedModel {
+                    response: "Done.\n<aca-summary>{\"confidence\": 0.9}</aca-summary>".to_string(),
+                }),
+            )
+            .await;
+        let task_id = agent
+            .task_manager()
+            .create_task(local_task("Add metrics"), None)
+            .await
+            .unwrap();
+
+        // Other tasks hold every slot and change the workspace while it waits
+        let mut held = Vec::new();
+        for _ in 0..agent.max_concurrent_tasks {
+            held.push(
+                agent
+                    .preemption
+                    .acquire(Uuid::new_v4(), crate::task::TaskPriority::Normal)
+                    .await,
+            );
+        }
+        let (result, _) = tokio::join!(agent.process_task(task_id), async {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            std::fs::write(agent.workspace_path.join("other.txt"), "other").unwrap();
+            drop(held);
+        });
+        result.unwrap();
+
+        let task = agent.task_manager().get_task(task_id).await.unwrap();
+        let TaskStatus::Completed {
+            result: crate::task::types::TaskResult::Success { files_created, .. },
+            ..
+        } = task.status
+        else {
+            panic!("task did not complete: {:?}", task.status);
+        };
+        assert!(files_created.is_empty(), "{:?}", files_created);
     }
 
-    pub fn claude_interface(&self) -> Arc<ClaudeCodeInterface> {
-        self.claude_interface.clone()
+    #[tokio::test]
+    async fn test_gated_task_is_reported_as_a_finding() {
+        let agent = create_test_agent_system().await;
+        agent
+            .router
+            .set_provider(
+                ProviderType::LocalModel,
+                Arc::new(ScriptedModel {
+                    response: "Done.\n<aca-summary>{\"confidence\": 0.9, \"risks\": [\"auth\"]}</aca-summary>"
+                        .to_string(),
+                }),
+            )
+            .await;
+        let task_id = agent
+            .task_manager()
+            .create_task(local_task("Add login"), None)
+            .await
+            .unwrap();
+
+        // Auth changes wait for approval by default
+        assert!(agent.process_task(task_id).await.is_err());
+        let findings = agent.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].category, "Auth change");
+        assert_eq!(findings[0].severity, crate::task::FindingSeverity::Warning);
+        assert!(findings[0].message.contains("Add login"));
     }
 
-    /// Sync container info with session metadata
-    ///
-    /// This should be called after the container is first created (e.g., after setup commands)
-    /// to store container metadata in the session for persistence and recovery.
-    #[cfg(feature = "containers")]
-    pub async fn sync_container_info(&self) {
-        if let Some(container_info) = self.executor.container_info().await {
-            self.session_manager
-                .set_container_info(container_info)
-                .await;
-            info!("Container info synced with session metadata");
+    #[tokio::test]
+    async fn test_plan_tasks_are_queued_while_offline() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let agent = AgentSystem::new(AgentConfig {
+            workspace_path: workspace,
+            offline: OfflineConfig {
+                max_wait_minutes: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        agent.set_health_probe(Arc::new(UnreachableProbe));
+
+        let mut plan = crate::task::ExecutionPlan::new().with_parallel_execution(Some(2));
+        for (title, dependencies) in [
+            ("Add metrics", Vec::new()),
+            ("Write docs", vec![crate::task::plan_task_id("Add metrics")]),
+        ] {
+            plan = plan.with_task(TaskSpec {
+                title: title.to_string(),
+                description: format!("{} for the API", title),
+                dependencies,
+                ..Default::default()
+            });
+        }
+        assert!(agent.execute_plan(plan).await.unwrap().is_empty());
+
+        // Both tasks wait for the provider, the docs still behind the metrics
+        let queued = agent
+            .task_manager()
+            .get_tasks_by_status(
+                |status| matches!(status, TaskStatus::Blocked { reason, .. } if reason.text() == Some(OFFLINE_REASON)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(queued.len(), 2);
+        let mut tasks = Vec::new();
+        for task_id in queued {
+            tasks.push(agent.task_manager().get_task(task_id).await.unwrap());
         }
+        let metrics = tasks
+            .iter()
+            .find(|task| task.title == "Add metrics")
+            .unwrap();
+        let docs = tasks
+            .iter()
+            .find(|task| task.title == "Write docs")
+            .unwrap();
+        assert_eq!(docs.dependencies, vec![metrics.id]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::task::{ErrorHandler, OutputCondition};
-    use chrono::Duration;
-    use std::path::PathBuf;
+    #[tokio::test]
+    async fn test_sub_plan_task_expands_into_children() {
+        let agent = create_test_agent_system().await;
+        let sub_plan = agent.workspace_path.join("payments/tasks.md");
+        std::fs::create_dir_all(sub_plan.parent().unwrap()).unwrap();
+        std::fs::write(&sub_plan, "- [x] Add refunds\n- [x] Add invoices\n").unwrap();
 
-    // Helper function to create a test AgentSystem with minimal configuration
-    async fn create_test_agent_system() -> AgentSystem {
-        let temp_dir = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
-        std::fs::create_dir_all(&temp_dir).unwrap();
+        let plan = crate::task::ExecutionPlan::new().with_task(TaskSpec {
+            title: "Payments subsystem".to_string(),
+            plan: Some(PathBuf::from("payments/tasks.md")),
+            ..Default::default()
+        });
+
+        let task_ids = agent.execute_plan(plan).await.unwrap();
+        let parent = agent.task_manager().get_task(task_ids[0]).await.unwrap();
+        assert!(matches!(parent.status, TaskStatus::Completed { .. }));
+        assert_eq!(parent.children.len(), 2);
+        let child = agent
+            .task_manager()
+            .get_task(parent.children[0])
+            .await
+            .unwrap();
+        assert!(child.title.ends_with("Add refunds"));
+        assert_eq!(child.parent_id, Some(parent.id));
+    }
 
+    #[tokio::test]
+    async fn test_resume_restores_tasks_and_conversation() {
+        let workspace = std::env::temp_dir().join(format!("aca-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
         let config = AgentConfig {
-            workspace_path: temp_dir.clone(),
-            session_config: crate::session::SessionManagerConfig::default(),
-            task_config: crate::task::TaskManagerConfig::default(),
-            claude_config: crate::claude::ClaudeConfig::default(),
-            setup_commands: vec![],
-            execution_mode: crate::executor::RuntimeMode::Host,
+            workspace_path: workspace,
+            ..Default::default()
+        };
+        let agent = AgentSystem::with_session_init(config.clone(), SessionInitOptions::default())
+            .await
+            .unwrap();
+
+        let task_id = agent
+            .task_manager()
+            .create_task(
+                TaskSpec {
+                    title: "Add metrics".to_string(),
+                    description: "Add request metrics".to_string(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let session_id = uuid::Uuid::new_v4();
+        let message = crate::claude::types::ClaudeMessage {
+            id: uuid::Uuid::new_v4(),
+            role: crate::claude::types::MessageRole::Assistant,
+            content: "Added the metrics middleware".to_string(),
+            timestamp: chrono::Utc::now(),
+            token_count: Some(6),
+            metadata: std::collections::HashMap::new(),
         };
+        agent
+            .claude_interface()
+            .restore_conversation_state(crate::session::ConversationState {
+                contexts: vec![crate::claude::types::ConversationContext {
+                    session_id,
+                    messages: vec![message],
+                    total_tokens: 6,
+                    last_activity: chrono::Utc::now(),
+                    context_summary: None,
+                }],
+                ..Default::default()
+            })
+            .await;
+        agent.record_interactions().await;
+        let checkpoint = agent
+            .session_manager()
+            .create_checkpoint("Before resume".to_string())
+            .await
+            .unwrap();
 
-        AgentSystem::new(config).await.unwrap()
+        let resumed = AgentSystem::new_from_checkpoint(config, &checkpoint.id)
+            .await
+            .unwrap();
+        assert!(resumed.task_manager().get_task(task_id).await.is_ok());
+        let conversation = resumed.claude_interface().conversation_state().await;
+        assert_eq!(conversation.contexts.len(), 1);
+        assert_eq!(conversation.contexts[0].session_id, session_id);
+        assert_eq!(
+            conversation.contexts[0].messages[0].content,
+            "Added the metrics middleware"
+        );
     }
 
     #[tokio::test]
@@ -1076,10 +5502,10 @@ mod tests {
         };
 
         let condition = OutputCondition::stderr_contains("command not found");
-        assert!(AgentSystem::should_run_backup(&result, &condition));
+        assert!(AgentSystem::should_run_backup(&result, &condition, None));
 
         let condition = OutputCondition::stderr_contains("different error");
-        assert!(!AgentSystem::should_run_backup(&result, &condition));
+        assert!(!AgentSystem::should_run_backup(&result, &condition, None));
     }
 
     #[tokio::test]
@@ -1097,13 +5523,40 @@ mod tests {
             exit_code_range: Some((1, 10)),
             ..Default::default()
         };
-        assert!(AgentSystem::should_run_backup(&result, &condition));
+        assert!(AgentSystem::should_run_backup(&result, &condition, None));
 
         let condition = OutputCondition {
             exit_code_range: Some((1, 3)),
             ..Default::default()
         };
-        assert!(!AgentSystem::should_run_backup(&result, &condition));
+        assert!(!AgentSystem::should_run_backup(&result, &condition, None));
+    }
+
+    #[tokio::test]
+    async fn test_should_run_backup_requires_binaries() {
+        let result = SetupResult {
+            command_id: uuid::Uuid::new_v4(),
+            success: false,
+            exit_code: 1,
+            stdout: String::new(),
+            stderr: String::new(),
+            duration: Duration::milliseconds(100),
+        };
+        let condition = OutputCondition::exit_code_range(1, 1).requiring("apt-get");
+
+        let mut environment = EnvironmentReport::parse("os=Linux\nbin.git=yes\n");
+        assert!(!AgentSystem::should_run_backup(&result, &condition, None));
+        assert!(!AgentSystem::should_run_backup(
+            &result,
+            &condition,
+            Some(&environment)
+        ));
+        environment.binaries.insert("apt-get".to_string());
+        assert!(AgentSystem::should_run_backup(
+            &result,
+            &condition,
+            Some(&environment)
+        ));
     }
 
     #[tokio::test]
@@ -1147,6 +5600,24 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_missing_packages_on_host_fail_with_instructions() {
+        let agent = create_test_agent_system().await;
+        let cmd = SetupCommand::packages(
+            "system packages",
+            SystemPackages::new(&["aca-test-package-that-does-not-exist"]),
+        );
+
+        let result = agent.execute_shell_command(&cmd).await.unwrap();
+        assert!(!result.success);
+        assert!(
+            result
+                .stderr
+                .contains("aca-test-package-that-does-not-exist")
+        );
+        assert!(agent.execute_setup_commands(&[cmd]).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_backup_command_execution() {
         let agent = create_test_agent_system().await;
@@ -1342,7 +5813,37 @@ impl Default for AgentConfig {
             task_config: TaskManagerConfig::default(),
             claude_config: ClaudeConfig::default(),
             setup_commands: Vec::new(), // No setup commands by default
+            services: Vec::new(),
             execution_mode: crate::executor::RuntimeMode::Host,
+            container_runtime: crate::executor::ContainerRuntime::Auto,
+            docker_context: None,
+            instructions: InstructionsConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            schedule: ScheduleConfig::default(),
+            questions: QuestionConfig::default(),
+            watch: WatchConfig::default(),
+            refactor_guard: RefactorGuardConfig::default(),
+            completion_gates: CompletionGateConfig::default(),
+            budget: BudgetConfig::default(),
+            log_retention: LogRetentionConfig::default(),
+            remote_sync: RemoteSyncConfig::default(),
+            distributed: DistributedConfig::default(),
+            placement: PlacementConfig::default(),
+            context_budget: ContextBudgetConfig::default(),
+            nix: crate::executor::NixConfig::default(),
+            offline: OfflineConfig::default(),
+            routing: RoutingConfig::default(),
+            verification: VerificationConfig::default(),
+            spot_check: SpotCheckConfig::default(),
+            policy: PolicyConfig::default(),
+            report_signing: ReportSigningConfig::default(),
+            anonymization: AnonymizationConfig::default(),
+            mcp: McpConfig::default(),
+            prompt_size: PromptSizeConfig::default(),
+            rollup: RollupConfig::default(),
+            wait: WaitConfig::default(),
+            ports: PortConfig::default(),
+            text_files: TextFileConfig::default(),
         }
     }
 }
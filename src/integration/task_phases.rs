@@ -0,0 +1,285 @@
+//! Phases of [`AgentSystem::process_task`] around a task's turns.
+//!
+//! Once a task holds a concurrency slot its snapshots are taken: the
+//! refactor guard's behavior baseline, the spot check's copy of the
+//! workspace and the hashes its diff is recorded against. After it
+//! completes they are compared with the workspace it left. A task preempted
+//! by a critical one is paused here until it gets a slot back.
+
+use super::{AgentSystem, attach_task_diff};
+use crate::task::{
+    BehaviorComparison, BehaviorSnapshot, GoldenOutput, SlotGuard, SpotCheck, SpotCheckOutcome,
+    SpotCheckReport, Task, TaskStatus, TestSnapshot,
+};
+use crate::workspace::{TaskDiff, TaskSnapshot};
+use anyhow::Result;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// What a task's result is checked against, recorded before it runs
+#[derive(Debug)]
+pub(super) struct TaskSnapshots {
+    /// Behavior before a refactor
+    baseline: Option<BehaviorSnapshot>,
+    spot_check: Option<SpotCheck>,
+    /// Hashes the task's changes are recorded against
+    workspace: Option<TaskSnapshot>,
+}
+
+impl AgentSystem {
+    /// Take the snapshots a task is checked against
+    ///
+    /// Called with the task's slot held, so changes made by the tasks it
+    /// waited for are not attributed to it.
+    pub(super) async fn take_snapshots(&self, task: &Task) -> Result<TaskSnapshots> {
+        // Record behavior before a refactor so changes can block completion
+        let baseline = if self.refactor_guard.applies_to(&task.metadata.tags) {
+            info!("Recording behavior baseline for refactor task {}", task.id);
+            Some(self.capture_behavior().await?)
+        } else {
+            None
+        };
+
+        // Snapshot the workspace to check afterwards that the tests cover the change
+        let spot_check = match self.spot_check_command() {
+            Some(_) if self.spot_check.applies_to(&task.metadata.tags) => {
+                match SpotCheck::start(&self.workspace_path, &self.verification.ignore) {
+                    Ok(check) => Some(check),
+                    Err(e) => {
+                        warn!("Skipping spot check of task {}: {}", task.id, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // Hash the workspace to record which files the task changes
+        let workspace = match self.snapshots.start() {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                warn!("Skipping workspace snapshot of task {}: {}", task.id, e);
+                None
+            }
+        };
+
+        Ok(TaskSnapshots {
+            baseline,
+            spot_check,
+            workspace,
+        })
+    }
+
+    /// Record the files a finished task changed, restoring pseudonymized
+    /// names the provider wrote back first
+    pub(super) fn record_changes(
+        &self,
+        task_id: Uuid,
+        snapshots: &mut TaskSnapshots,
+    ) -> Option<TaskDiff> {
+        let before = snapshots.workspace.take()?;
+        if let Some(anonymizer) = &self.anonymizer {
+            let restored = self
+                .snapshots
+                .capture()
+                .map_err(anyhow::Error::from)
+                .and_then(|now| {
+                    let changes = before.changes(&now);
+                    anonymizer.restore_files(
+                        &self.workspace_path,
+                        changes.created.iter().chain(&changes.modified),
+                    )
+                });
+            match restored {
+                Ok(files) if !files.is_empty() => {
+                    info!("Restored pseudonymized names in {} file(s)", files.len())
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to restore pseudonymized names: {}", e),
+            }
+        }
+
+        let task_diff = self
+            .snapshots
+            .finish(&task_id.to_string(), &before)
+            .inspect_err(|e| warn!("Failed to record task {}'s changes: {}", task_id, e))
+            .ok()?;
+        // Without git-lfs, committing an LFS file stores it in Git itself
+        if !task_diff.lfs_files.is_empty() && which::which("git-lfs").is_err() {
+            warn!(
+                "Task {} changed files stored in Git LFS, but git-lfs is not installed: {}",
+                task_id,
+                task_diff
+                    .lfs_files
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        Some(task_diff)
+    }
+
+    /// Check a completed task against its snapshots, adding the results to
+    /// its output
+    ///
+    /// A refactor that changed behavior blocks the task and fails.
+    pub(super) async fn verify_completed(
+        &self,
+        completed_task: &mut Task,
+        snapshots: &TaskSnapshots,
+        task_diff: Option<&TaskDiff>,
+    ) -> Result<()> {
+        let task_id = completed_task.id;
+        if let Some(task_diff) = task_diff {
+            attach_task_diff(completed_task, task_diff);
+        }
+
+        if let Some(baseline) = &snapshots.baseline {
+            let comparison = baseline.compare(&self.capture_behavior().await?);
+            attach_behavior_check(completed_task, &comparison);
+            if !comparison.is_clean() {
+                let summary = comparison.summary();
+                warn!("Refactor task {} changed behavior: {}", task_id, summary);
+                self.task_manager
+                    .update_task_status(
+                        task_id,
+                        TaskStatus::Blocked {
+                            reason: format!("Refactor changed behavior. {}", summary).into(),
+                            blocked_at: chrono::Utc::now(),
+                            retry_after: None,
+                        },
+                    )
+                    .await?;
+                self.save_session_state().await?;
+                self.update_heartbeat_progress().await;
+                return Err(anyhow::anyhow!(
+                    "Refactor task {} changed behavior: {}",
+                    task_id,
+                    summary
+                ));
+            }
+            info!("Refactor task {} preserved behavior", task_id);
+        }
+
+        if let Some(check) = &snapshots.spot_check
+            && let Some(command) = self.spot_check_command()
+        {
+            let report = check
+                .run(&self.spot_check, command, || async {
+                    Ok(self.run_verification(command).await?.success())
+                })
+                .await?;
+            match &report.outcome {
+                SpotCheckOutcome::Covered => {
+                    info!("Spot check: task {}'s tests cover its change", task_id)
+                }
+                SpotCheckOutcome::NotCovered => warn!(
+                    "Spot check: `{}` still passes with task {}'s change reverted",
+                    command, task_id
+                ),
+                SpotCheckOutcome::FailsWithChange => warn!(
+                    "Spot check: `{}` fails with task {}'s change applied",
+                    command, task_id
+                ),
+                SpotCheckOutcome::Skipped { reason } => {
+                    info!("Spot check of task {} skipped: {}", task_id, reason)
+                }
+            }
+            attach_spot_check(completed_task, &report);
+        }
+        Ok(())
+    }
+
+    /// Pause a task a critical one preempted until it gets a slot again,
+    /// returning the new slot
+    pub(super) async fn pause_preempted(&self, task: &Task, slot: SlotGuard) -> Result<SlotGuard> {
+        warn!("Task {} preempted by a critical task, pausing", task.id);
+        self.heartbeat.task_finished(task.id);
+        self.task_manager
+            .update_task_status(
+                task.id,
+                TaskStatus::Paused {
+                    reason: "Preempted by a critical task".to_string(),
+                    paused_at: chrono::Utc::now(),
+                },
+            )
+            .await?;
+        self.save_session_checkpoint(&format!("preempted_{}", task.id))
+            .await?;
+
+        drop(slot);
+        let slot = self
+            .preemption
+            .acquire(task.id, task.metadata.priority.clone())
+            .await;
+
+        info!("Resuming preempted task {}", task.id);
+        self.task_manager
+            .update_task_status(
+                task.id,
+                TaskStatus::InProgress {
+                    started_at: chrono::Utc::now(),
+                    estimated_completion: None,
+                },
+            )
+            .await?;
+        self.save_session_state().await?;
+        Ok(slot)
+    }
+
+    /// Test command spot checks run: their own, else the verification command
+    fn spot_check_command(&self) -> Option<&str> {
+        self.spot_check
+            .test_command
+            .as_deref()
+            .or(self.verification.command.as_deref())
+    }
+
+    /// Run the refactor guard's commands and record their behavior
+    async fn capture_behavior(&self) -> Result<BehaviorSnapshot> {
+        let tests = match &self.refactor_guard.test_command {
+            Some(command) => Some(TestSnapshot::from_result(
+                command,
+                &self.run_verification(command).await?,
+            )),
+            None => None,
+        };
+
+        let mut golden = Vec::new();
+        for command in &self.refactor_guard.golden_commands {
+            let result = self.run_verification(command).await?;
+            golden.push(GoldenOutput::from_result(command, &result));
+        }
+
+        Ok(BehaviorSnapshot {
+            captured_at: chrono::Utc::now(),
+            tests,
+            golden,
+        })
+    }
+}
+
+/// Add a refactor's behavior comparison to the completed task's output
+fn attach_behavior_check(task: &mut Task, comparison: &BehaviorComparison) {
+    if let TaskStatus::Completed {
+        result: crate::task::types::TaskResult::Success { output, .. },
+        ..
+    } = &mut task.status
+        && let (Some(output), Ok(check)) =
+            (output.as_object_mut(), serde_json::to_value(comparison))
+    {
+        output.insert("behavior_check".to_string(), check);
+    }
+}
+
+fn attach_spot_check(task: &mut Task, report: &SpotCheckReport) {
+    if let TaskStatus::Completed {
+        result: crate::task::types::TaskResult::Success { output, .. },
+        ..
+    } = &mut task.status
+        && let (Some(output), Ok(report)) = (output.as_object_mut(), serde_json::to_value(report))
+    {
+        output.insert("spot_check".to_string(), report);
+    }
+}
@@ -14,6 +14,7 @@
 //! - **[`task`]**: Hierarchical task management with intelligent scheduling
 //! - **[`session`]**: Complete session lifecycle management with atomic persistence
 //! - **[`integration`]**: High-level system orchestration and agent coordination
+//! - **[`daemon`]**: Multi-tenant project registry for serving several workspaces
 //!
 //! ## Features
 //!
@@ -99,6 +100,34 @@ pub mod llm;
 /// coordinated task processing and system-wide status monitoring.
 pub mod integration;
 
+/// Structured progress events.
+///
+/// Typed events an `AgentSystem` broadcasts as it runs tasks, calls
+/// providers and writes checkpoints, for embedders building their own UIs.
+pub mod events;
+
+/// Organizational guardrails: configured rules that deny tasks or hold them
+/// for approval before they are created or run.
+pub mod policy;
+
+/// MCP tool servers.
+///
+/// Checks the configured Model Context Protocol servers at startup and
+/// hands them to the Claude CLI, so tasks can use project-specific tools.
+pub mod mcp;
+
+/// Workspace snapshots.
+///
+/// Hashes the workspace before and after each task to record the files it
+/// created, modified and deleted, with a unified diff per task.
+pub mod workspace;
+
+/// Multi-tenant daemon core.
+///
+/// Registers several project workspaces with per-project API tokens,
+/// budgets, concurrency limits and isolated session storage.
+pub mod daemon;
+
 /// Environment constants and path utilities.
 ///
 /// Centralizes all hardcoded paths and directory names used throughout
@@ -141,6 +170,18 @@ pub use llm::{LLMProvider, LLMRequest, LLMResponse, ProviderConfig, ProviderType
 // Re-export integration types
 pub use integration::{AgentConfig, AgentSystem, SystemStatus};
 
+// Re-export event types
+pub use events::{AgentEvent, EventBus};
+
+// Re-export policy types
+pub use policy::{PolicyConfig, PolicyEffect, PolicyEngine, PolicyGate, PolicyRule, PolicyStage};
+
+// Re-export MCP types
+pub use mcp::{McpConfig, McpServerConfig, McpServers};
+
+// Re-export workspace snapshot types
+pub use workspace::{SnapshotManager, TaskDiff, WorkspaceChanges, WorkspaceSnapshot};
+
 // Re-export container types (only with containers feature)
 #[cfg(feature = "containers")]
 pub use container::{
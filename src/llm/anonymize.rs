@@ -0,0 +1,333 @@
+//! Reversible pseudonymization of prompts sent to cloud providers.
+//!
+//! Experimental. Configured identifiers (project and product names, say) and
+//! anything matching the configured patterns (internal hostnames) are replaced
+//! with placeholders like `aca_anon_3` before a prompt leaves the machine.
+//! Placeholders in the response, and in files the task wrote, are turned back
+//! into the originals. The mapping stays local in `.aca/anonymization.json`,
+//! so a name gets the same placeholder in every run.
+//!
+//! Only text aca sends is rewritten: agentic CLIs such as Claude Code still
+//! read workspace files directly.
+
+use crate::task::{Task, TaskResult, TaskStatus};
+use crate::workspace::resolve_within;
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Prefix of every placeholder
+const PLACEHOLDER_PREFIX: &str = "aca_anon_";
+
+/// Prompt pseudonymization settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnonymizationConfig {
+    /// Pseudonymize prompts for cloud providers; local models are left alone
+    pub enabled: bool,
+    /// Names replaced wherever they appear as a whole word
+    pub identifiers: Vec<String>,
+    /// Regular expressions whose matches are replaced, e.g. `[a-z0-9.-]+\.corp\.example\.com`
+    pub patterns: Vec<String>,
+}
+
+/// Persisted mapping of original text to placeholder
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Mapping {
+    pseudonyms: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    mapping: Mapping,
+    originals: HashMap<String, String>,
+}
+
+/// Replaces configured names with placeholders and back
+#[derive(Debug)]
+pub struct Pseudonymizer {
+    matcher: Regex,
+    placeholder: Regex,
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+impl Pseudonymizer {
+    /// Build from `config`, loading the workspace mapping
+    ///
+    /// `None` when disabled or when there is nothing to replace.
+    pub fn new(config: &AnonymizationConfig, workspace_root: &Path) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let mut identifiers: Vec<&String> = config
+            .identifiers
+            .iter()
+            .filter(|identifier| !identifier.is_empty())
+            .collect();
+        // Longest first, so a name wins over any name it contains
+        identifiers.sort_by_key(|identifier| std::cmp::Reverse(identifier.len()));
+        let mut alternatives: Vec<String> = identifiers
+            .into_iter()
+            .map(|identifier| word_bounded(identifier))
+            .collect();
+        for pattern in &config.patterns {
+            Regex::new(pattern)
+                .with_context(|| format!("Invalid anonymization pattern '{}'", pattern))?;
+            alternatives.push(format!("(?:{})", pattern));
+        }
+        if alternatives.is_empty() {
+            return Ok(None);
+        }
+
+        let path = crate::env::anonymization_file_path(workspace_root);
+        let mapping: Mapping = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Malformed anonymization mapping {:?}", path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Mapping::default(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+        let originals = mapping
+            .pseudonyms
+            .iter()
+            .map(|(original, placeholder)| (placeholder.clone(), original.clone()))
+            .collect();
+
+        Ok(Some(Self {
+            matcher: Regex::new(&alternatives.join("|"))?,
+            placeholder: Regex::new(&format!(r"\b{}\d+\b", PLACEHOLDER_PREFIX))?,
+            path,
+            state: Mutex::new(State { mapping, originals }),
+        }))
+    }
+
+    /// Replace configured names in `text` with their placeholders
+    pub fn pseudonymize(&self, text: &str) -> String {
+        let mut state = self.state.lock().unwrap();
+        let mut added = false;
+        let result = self.matcher.replace_all(text, |caps: &Captures| {
+            let original = &caps[0];
+            if let Some(placeholder) = state.mapping.pseudonyms.get(original) {
+                return placeholder.clone();
+            }
+            let placeholder = format!(
+                "{}{}",
+                PLACEHOLDER_PREFIX,
+                state.mapping.pseudonyms.len() + 1
+            );
+            state
+                .mapping
+                .pseudonyms
+                .insert(original.to_string(), placeholder.clone());
+            state
+                .originals
+                .insert(placeholder.clone(), original.to_string());
+            added = true;
+            placeholder
+        });
+        if added && let Err(e) = self.save(&state.mapping) {
+            tracing::warn!("Failed to save anonymization mapping: {}", e);
+        }
+        result.into_owned()
+    }
+
+    /// Put the originals back in place of known placeholders in `text`
+    pub fn restore(&self, text: &str) -> String {
+        let state = self.state.lock().unwrap();
+        self.placeholder
+            .replace_all(text, |caps: &Captures| {
+                state
+                    .originals
+                    .get(&caps[0])
+                    .cloned()
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// Restore every string in `value`
+    pub fn restore_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => *text = self.restore(text),
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(|item| self.restore_json(item))
+            }
+            serde_json::Value::Object(fields) => {
+                fields.values_mut().for_each(|item| self.restore_json(item))
+            }
+            _ => {}
+        }
+    }
+
+    /// A copy of `task` as it should be described to the provider
+    pub fn pseudonymize_task(&self, task: &Task) -> Task {
+        let mut outgoing = task.clone();
+        outgoing.title = self.pseudonymize(&task.title);
+        outgoing.description = self.pseudonymize(&task.description);
+        outgoing
+    }
+
+    /// Undo [`pseudonymize_task`](Self::pseudonymize_task) on the provider's result
+    pub fn restore_task(&self, original: &Task, result: &mut Task) {
+        result.title = original.title.clone();
+        result.description = original.description.clone();
+        if let TaskStatus::Completed {
+            result: task_result,
+            ..
+        } = &mut result.status
+        {
+            match task_result {
+                TaskResult::Success { output, .. } => self.restore_json(output),
+                TaskResult::Partial {
+                    completed_work,
+                    remaining_work,
+                    ..
+                } => {
+                    self.restore_json(completed_work);
+                    for spec in remaining_work {
+                        spec.title = self.restore(&spec.title);
+                        spec.description = self.restore(&spec.description);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Restore placeholders the provider wrote into workspace files
+    ///
+    /// `paths` are relative to `root`; files that are not UTF-8 are skipped.
+    /// Returns the files that were rewritten.
+    pub fn restore_files<'a>(
+        &self,
+        root: &Path,
+        paths: impl IntoIterator<Item = &'a PathBuf>,
+    ) -> Result<Vec<PathBuf>> {
+        let mut restored = Vec::new();
+        for path in paths {
+            let full_path = match resolve_within(root, path) {
+                Ok(full_path) => full_path,
+                Err(e) => {
+                    warn!("Not restoring names in {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            let Ok(text) = std::fs::read_to_string(&full_path) else {
+                continue;
+            };
+            if !text.contains(PLACEHOLDER_PREFIX) {
+                continue;
+            }
+            let replaced = self.restore(&text);
+            if replaced != text {
+                std::fs::write(&full_path, replaced)
+                    .with_context(|| format!("Failed to restore {:?}", full_path))?;
+                restored.push(path.clone());
+            }
+        }
+        Ok(restored)
+    }
+
+    fn save(&self, mapping: &Mapping) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(mapping)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+/// `identifier` as a regex, bounded where it starts or ends with a word character
+fn word_bounded(identifier: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut pattern = String::new();
+    if is_word(identifier.chars().next()) {
+        pattern.push_str(r"\b");
+    }
+    pattern.push_str(&regex::escape(identifier));
+    if is_word(identifier.chars().last()) {
+        pattern.push_str(r"\b");
+    }
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config() -> AnonymizationConfig {
+        AnonymizationConfig {
+            enabled: true,
+            identifiers: vec!["Falcon".to_string(), "FalconPay".to_string()],
+            patterns: vec![r"[a-z0-9-]+\.corp\.example\.com".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_stable_mapping() {
+        let workspace = TempDir::new().unwrap();
+        let pseudonymizer = Pseudonymizer::new(&config(), workspace.path())
+            .unwrap()
+            .unwrap();
+
+        let prompt = "Move FalconPay from build01.corp.example.com into Falcon; keep Falconry.";
+        let outgoing = pseudonymizer.pseudonymize(prompt);
+        assert_eq!(
+            outgoing,
+            "Move aca_anon_1 from aca_anon_2 into aca_anon_3; keep Falconry."
+        );
+        assert_eq!(pseudonymizer.restore(&outgoing), prompt);
+
+        // Placeholders the mapping does not know are left alone
+        assert_eq!(pseudonymizer.restore("aca_anon_99"), "aca_anon_99");
+
+        // A later run reuses the saved placeholders
+        let reloaded = Pseudonymizer::new(&config(), workspace.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(reloaded.pseudonymize("Falcon"), "aca_anon_3");
+        let mut response = serde_json::json!({"response": ["Updated aca_anon_1"]});
+        reloaded.restore_json(&mut response);
+        assert_eq!(response["response"][0], "Updated FalconPay");
+    }
+
+    #[test]
+    fn test_restore_files_and_disabled_config() {
+        let workspace = TempDir::new().unwrap();
+        assert!(
+            Pseudonymizer::new(&AnonymizationConfig::default(), workspace.path())
+                .unwrap()
+                .is_none()
+        );
+
+        let pseudonymizer = Pseudonymizer::new(&config(), workspace.path())
+            .unwrap()
+            .unwrap();
+        let placeholder = pseudonymizer.pseudonymize("Falcon");
+        std::fs::write(
+            workspace.path().join("main.rs"),
+            format!("// {} service\n", placeholder),
+        )
+        .unwrap();
+        std::fs::write(workspace.path().join("other.rs"), "// untouched\n").unwrap();
+
+        let paths = vec![PathBuf::from("main.rs"), PathBuf::from("other.rs")];
+        let restored = pseudonymizer
+            .restore_files(workspace.path(), &paths)
+            .unwrap();
+        assert_eq!(restored, vec![PathBuf::from("main.rs")]);
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("main.rs")).unwrap(),
+            "// Falcon service\n"
+        );
+    }
+}
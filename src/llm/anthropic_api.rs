@@ -0,0 +1,329 @@
+//! Anthropic Messages API requests with prompt caching.
+//!
+//! Used by [`AnthropicApiProvider`](super::AnthropicApiProvider), directly or
+//! through [`ClaudeProvider`](super::ClaudeProvider) in API mode. The
+//! system prompt — which carries the provider instructions and any pinned
+//! or imported context — is the stable prefix shared by every request, so
+//! it is sent as a `cache_control` block once it is long enough for the
+//! API to cache. Later requests then read it from the prompt cache at a
+//! fraction of the input price; cache reads and writes are reported in
+//! [`TokenUsage`] together with the realized savings.
+//!
+//! Streamed responses arrive as server-sent events; [`StreamAccumulator`]
+//! folds them back into the shape of a non-streaming response body.
+
+use super::types::{LLMError, LLMRequest, TokenUsage};
+use crate::claude::token_breakdown::estimate_tokens;
+use crate::claude::usage_tracker::{cache_cost_and_savings, estimate_cost};
+use serde_json::{Value, json};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+pub const API_VERSION: &str = "2023-06-01";
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-5";
+pub const DEFAULT_MAX_TOKENS: u64 = 4096;
+
+/// Shortest prefix, in estimated tokens, the API will cache
+pub const MIN_CACHEABLE_TOKENS: u64 = 1024;
+
+/// Resolve the CLI-style aliases used in provider configs to API model ids
+pub fn api_model(model: Option<&str>) -> String {
+    match model {
+        None | Some("claude-sonnet") | Some("sonnet") => DEFAULT_MODEL.to_string(),
+        Some("claude-haiku") | Some("haiku") => "claude-haiku-4-5".to_string(),
+        Some("claude-opus") | Some("opus") => "claude-opus-4-1".to_string(),
+        Some(model) => model.to_string(),
+    }
+}
+
+/// Request body for `POST /v1/messages`
+///
+/// With `prompt_caching`, a system prompt of at least
+/// [`MIN_CACHEABLE_TOKENS`] is marked as a cache breakpoint.
+pub fn messages_body(request: &LLMRequest, model: &str, prompt_caching: bool) -> Value {
+    let mut body = json!({
+        "model": model,
+        "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        "messages": [{ "role": "user", "content": request.prompt }],
+    });
+
+    if let Some(system) = request.system_message.as_deref().filter(|s| !s.is_empty()) {
+        let mut block = json!({ "type": "text", "text": system });
+        if prompt_caching && estimate_tokens(system) >= MIN_CACHEABLE_TOKENS {
+            block["cache_control"] = json!({ "type": "ephemeral" });
+        }
+        body["system"] = json!([block]);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    body
+}
+
+/// Text and usage from a `/v1/messages` response body
+pub fn parse_messages_response(body: &Value) -> Result<(String, TokenUsage), LLMError> {
+    let content = body
+        .get("content")
+        .and_then(|content| content.as_array())
+        .ok_or_else(|| {
+            LLMError::ProviderSpecific(format!("Unexpected Anthropic API response: {}", body))
+        })?
+        .iter()
+        .filter_map(|block| block.get("text").and_then(|text| text.as_str()))
+        .collect::<Vec<_>>()
+        .join("");
+
+    let usage = body.get("usage").cloned().unwrap_or_default();
+    let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+    let input_tokens = field("input_tokens");
+    let output_tokens = field("output_tokens");
+    let cache_read_tokens = field("cache_read_input_tokens");
+    let cache_creation_tokens = field("cache_creation_input_tokens");
+    let (cache_cost, cache_savings) =
+        cache_cost_and_savings(cache_read_tokens, cache_creation_tokens);
+
+    Ok((
+        content,
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+            estimated_cost: estimate_cost(input_tokens, output_tokens) + cache_cost,
+            cache_read_tokens,
+            cache_creation_tokens,
+            cache_savings,
+        },
+    ))
+}
+
+/// Map an API error status and body to an [`LLMError`]
+pub fn api_error(status: u16, body: &str) -> LLMError {
+    let message = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|json| json["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string());
+    if matches!(status, 400 | 413)
+        && crate::claude::is_context_overflow(&message)
+        && let Some((current, max)) = crate::claude::downscope::overflow_token_counts(&message)
+    {
+        return LLMError::ContextTooLarge { current, max };
+    }
+    match status {
+        401 | 403 => LLMError::Authentication(message),
+        400 | 404 | 422 => LLMError::InvalidRequest(message),
+        413 => LLMError::InvalidRequest(format!("Request too large: {}", message)),
+        429 => LLMError::RateLimit {
+            message,
+            reset_time: None,
+        },
+        500..=599 => LLMError::ProviderUnavailable(message),
+        _ => LLMError::ProviderSpecific(format!("HTTP {}: {}", status, message)),
+    }
+}
+
+/// Map an `error` event received mid-stream to an [`LLMError`]
+fn stream_error(data: &Value) -> LLMError {
+    let message = data["error"]["message"]
+        .as_str()
+        .unwrap_or("Anthropic API stream failed")
+        .to_string();
+    match data["error"]["type"].as_str() {
+        Some("rate_limit_error") => LLMError::RateLimit {
+            message,
+            reset_time: None,
+        },
+        Some("overloaded_error") | Some("api_error") => LLMError::ProviderUnavailable(message),
+        _ => LLMError::ProviderSpecific(message),
+    }
+}
+
+/// Builds a response body from the events of a streamed `/v1/messages` call
+#[derive(Debug, Default)]
+pub struct StreamAccumulator {
+    /// Bytes of an event that has not been terminated yet
+    pending: Vec<u8>,
+    model: Option<String>,
+    content: String,
+    stop_reason: Option<String>,
+    usage: serde_json::Map<String, Value>,
+}
+
+impl StreamAccumulator {
+    /// Feed a chunk of the response body; returns the text deltas of the
+    /// events it completed
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>, LLMError> {
+        self.pending.extend_from_slice(chunk);
+        let mut deltas = Vec::new();
+        while let Some(end) = self.pending.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = self.pending.drain(..end + 2).collect();
+            let event = String::from_utf8_lossy(&event).replace('\r', "");
+            for data in event.lines().filter_map(|line| line.strip_prefix("data:")) {
+                let data: Value = serde_json::from_str(data.trim()).map_err(|e| {
+                    LLMError::ProviderSpecific(format!("Invalid Anthropic API event: {}", e))
+                })?;
+                if let Some(text) = self.apply(&data)? {
+                    deltas.push(text);
+                }
+            }
+        }
+        Ok(deltas)
+    }
+
+    fn apply(&mut self, data: &Value) -> Result<Option<String>, LLMError> {
+        match data["type"].as_str() {
+            Some("message_start") => {
+                let message = &data["message"];
+                self.model = message["model"].as_str().map(str::to_string);
+                self.merge_usage(&message["usage"]);
+            }
+            Some("content_block_delta") if data["delta"]["type"] == "text_delta" => {
+                let text = data["delta"]["text"].as_str().unwrap_or_default();
+                self.content.push_str(text);
+                return Ok(Some(text.to_string()));
+            }
+            Some("message_delta") => {
+                if let Some(reason) = data["delta"]["stop_reason"].as_str() {
+                    self.stop_reason = Some(reason.to_string());
+                }
+                self.merge_usage(&data["usage"]);
+            }
+            Some("error") => return Err(stream_error(data)),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn merge_usage(&mut self, usage: &Value) {
+        if let Some(usage) = usage.as_object() {
+            for (key, value) in usage {
+                self.usage.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    /// The collected message, shaped like a non-streaming response body
+    pub fn finish(self) -> Value {
+        json!({
+            "model": self.model,
+            "content": [{ "type": "text", "text": self.content }],
+            "stop_reason": self.stop_reason,
+            "usage": self.usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_system_prompt_is_cacheable() {
+        let request = LLMRequest {
+            prompt: "Fix the bug".to_string(),
+            system_message: Some("Follow the project conventions. ".repeat(200)),
+            ..Default::default()
+        };
+
+        let body = messages_body(&request, DEFAULT_MODEL, true);
+        assert_eq!(body["system"][0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(body["messages"][0]["content"], "Fix the bug");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+
+        // Disabled, or too short for the API to cache
+        let body = messages_body(&request, DEFAULT_MODEL, false);
+        assert!(body["system"][0].get("cache_control").is_none());
+        let short = LLMRequest {
+            system_message: Some("Be brief.".to_string()),
+            ..request
+        };
+        let body = messages_body(&short, DEFAULT_MODEL, true);
+        assert!(body["system"][0].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn test_response_usage_includes_cache_traffic() {
+        let body = json!({
+            "content": [{ "type": "text", "text": "Done." }],
+            "usage": {
+                "input_tokens": 20,
+                "output_tokens": 10,
+                "cache_read_input_tokens": 100_000,
+                "cache_creation_input_tokens": 0
+            }
+        });
+        let (content, usage) = parse_messages_response(&body).unwrap();
+        assert_eq!(content, "Done.");
+        assert_eq!(usage.total_tokens, 30);
+        assert_eq!(usage.cache_read_tokens, 100_000);
+        // 100k cached tokens at $0.30/M instead of $3/M
+        assert!((usage.cache_savings - 0.27).abs() < 1e-9);
+        assert!((usage.estimated_cost - (0.00006 + 0.00015 + 0.03)).abs() < 1e-9);
+
+        assert!(parse_messages_response(&json!({ "error": "oops" })).is_err());
+    }
+
+    #[test]
+    fn test_api_errors_and_model_aliases() {
+        let body = r#"{"type":"error","error":{"type":"rate_limit_error","message":"Slow down"}}"#;
+        assert!(
+            matches!(api_error(429, body), LLMError::RateLimit { message, .. } if message == "Slow down")
+        );
+        assert!(matches!(
+            api_error(401, "denied"),
+            LLMError::Authentication(_)
+        ));
+        let overflow = r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#;
+        assert!(matches!(
+            api_error(400, overflow),
+            LLMError::ContextTooLarge {
+                current: 210000,
+                max: 200000
+            }
+        ));
+        assert_eq!(api_model(Some("claude-sonnet")), DEFAULT_MODEL);
+        assert_eq!(
+            api_model(Some("claude-3-5-haiku-latest")),
+            "claude-3-5-haiku-latest"
+        );
+    }
+
+    #[test]
+    fn test_stream_events_assemble_response() {
+        let events = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"model\":\"claude-sonnet-4-5\",",
+            "\"usage\":{\"input_tokens\":25,\"output_tokens\":1,\"cache_read_input_tokens\":0}}}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,",
+            "\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,",
+            "\"delta\":{\"type\":\"text_delta\",\"text\":\", world\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},",
+            "\"usage\":{\"output_tokens\":12}}\n\n",
+        );
+
+        // Chunk boundaries fall in the middle of events
+        let mut stream = StreamAccumulator::default();
+        let mut deltas = Vec::new();
+        for chunk in events.as_bytes().chunks(37) {
+            deltas.extend(stream.push(chunk).unwrap());
+        }
+        assert_eq!(deltas, vec!["Hello", ", world"]);
+
+        let body = stream.finish();
+        assert_eq!(body["model"], "claude-sonnet-4-5");
+        assert_eq!(body["stop_reason"], "end_turn");
+        let (content, usage) = parse_messages_response(&body).unwrap();
+        assert_eq!(content, "Hello, world");
+        assert_eq!(usage.input_tokens, 25);
+        assert_eq!(usage.output_tokens, 12);
+
+        let mut failing = StreamAccumulator::default();
+        let error = failing.push(
+            b"event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+        );
+        assert!(matches!(error, Err(LLMError::ProviderUnavailable(m)) if m == "Overloaded"));
+    }
+}
@@ -0,0 +1,375 @@
+//! Native Anthropic Messages API provider
+//!
+//! Implements [`LLMProvider`] over HTTP, without the `claude` CLI, so tasks
+//! can run on machines (such as CI runners) where Claude Code is not
+//! installed. Selected with `--provider anthropic` or
+//! [`ProviderType::Anthropic`](super::ProviderType::Anthropic).
+//!
+//! ## Configuration
+//!
+//! - `api_key`, or the `ANTHROPIC_API_KEY` environment variable
+//! - `base_url`, or `ANTHROPIC_BASE_URL` (default `https://api.anthropic.com`)
+//! - `model`: an API model id or a CLI-style alias such as `claude-sonnet`
+//! - `rate_limits`: enforced with the same [`RateLimiter`] as the CLI path
+//! - `additional_config["streaming"]`: stream responses (default `false`)
+//! - `additional_config["prompt_caching"]`: mark the system prompt for
+//!   prompt caching (default `true`)
+
+use crate::claude::RateLimiter;
+use crate::llm::anthropic_api::{self, StreamAccumulator};
+use crate::llm::provider::LLMProvider;
+use crate::llm::provider_logger::{LogContext, ProviderLogger};
+use crate::llm::types::{
+    LLMError, LLMRequest, LLMResponse, ProviderCapabilities, ProviderConfig, ProviderStatus,
+    RateLimitStatus,
+};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Receives the text deltas of a streamed response
+type TextSink<'a> = Box<dyn FnMut(&str) + Send + 'a>;
+
+/// Direct Anthropic Messages API implementation of [`LLMProvider`]
+pub struct AnthropicApiProvider {
+    config: ProviderConfig,
+    api_key: String,
+    base_url: String,
+    http: reqwest::Client,
+    rate_limiter: RateLimiter,
+}
+
+impl AnthropicApiProvider {
+    pub fn new(config: ProviderConfig) -> Result<Self, LLMError> {
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| {
+                LLMError::Authentication(
+                    "The Anthropic provider needs an API key. Set ANTHROPIC_API_KEY or the provider's api_key.".to_string(),
+                )
+            })?;
+        let base_url = config
+            .base_url
+            .clone()
+            .or_else(|| std::env::var("ANTHROPIC_BASE_URL").ok())
+            .unwrap_or_else(|| anthropic_api::DEFAULT_BASE_URL.to_string());
+        let rate_limiter = RateLimiter::new(crate::claude::RateLimitConfig {
+            max_tokens_per_minute: config.rate_limits.max_tokens_per_minute,
+            max_requests_per_minute: config.rate_limits.max_requests_per_minute,
+            burst_allowance: config.rate_limits.burst_allowance,
+            backoff_multiplier: 2.0,
+            max_backoff_delay: Duration::from_secs(600),
+        });
+
+        Ok(Self {
+            config,
+            api_key,
+            base_url,
+            http: reqwest::Client::new(),
+            rate_limiter,
+        })
+    }
+
+    /// Whether responses are streamed (`additional_config["streaming"]`)
+    fn streaming(&self) -> bool {
+        self.config
+            .additional_config
+            .get("streaming")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Whether the stable prompt prefix is marked as cacheable
+    /// (`additional_config["prompt_caching"]`, on by default)
+    fn prompt_caching(&self) -> bool {
+        self.config
+            .additional_config
+            .get("prompt_caching")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+
+    fn model_for(&self, request: &LLMRequest) -> String {
+        anthropic_api::api_model(
+            request
+                .model_preference
+                .as_deref()
+                .or(self.config.model.as_deref()),
+        )
+    }
+
+    /// Execute a request with streaming, passing each text delta to `on_text`
+    /// as it arrives
+    pub async fn execute_streaming_request(
+        &self,
+        request: LLMRequest,
+        logger: &ProviderLogger,
+        on_text: impl FnMut(&str) + Send,
+    ) -> Result<LLMResponse, LLMError> {
+        self.execute(request, logger, Some(Box::new(on_text))).await
+    }
+
+    async fn execute(
+        &self,
+        request: LLMRequest,
+        logger: &ProviderLogger,
+        on_text: Option<TextSink<'_>>,
+    ) -> Result<LLMResponse, LLMError> {
+        let start_time = Instant::now();
+        let model = self.model_for(&request);
+        let ctx = LogContext::new(request.id, &model);
+        logger.save_prompt(&ctx, &request.prompt).await.ok();
+
+        self.rate_limiter
+            .acquire_permit(&crate::claude::TaskRequest {
+                id: request.id,
+                task_type: "llm_request".to_string(),
+                description: String::new(),
+                context: HashMap::new(),
+                priority: crate::claude::TaskPriority::Medium,
+                estimated_tokens: request.max_tokens,
+                system_message: None,
+                tool_permissions: Default::default(),
+            })
+            .await
+            .map_err(|e| match e {
+                crate::claude::ClaudeError::RateLimit {
+                    message,
+                    reset_time,
+                } => LLMError::RateLimit {
+                    message,
+                    reset_time: Some(reset_time),
+                },
+                other => LLMError::ProviderSpecific(other.to_string()),
+            })?;
+
+        let result = self.send(&request, &model, on_text).await;
+        match &result {
+            Ok(_) => self.rate_limiter.record_success().await,
+            Err(e) => {
+                self.rate_limiter.record_failure().await;
+                logger.log_error(&ctx, &e.to_string()).await.ok();
+            }
+        }
+        let json = result?;
+        let (content, token_usage) = anthropic_api::parse_messages_response(&json)?;
+
+        let execution_time = start_time.elapsed();
+        logger
+            .log_completion(
+                &ctx,
+                token_usage.input_tokens,
+                token_usage.output_tokens,
+                token_usage.total_tokens,
+                token_usage.estimated_cost,
+                execution_time.as_secs_f64(),
+            )
+            .await
+            .ok();
+
+        let stop_reason = json["stop_reason"].as_str().unwrap_or_default();
+        if stop_reason == "max_tokens" {
+            tracing::warn!(
+                "Anthropic API response for request {} was cut off at max_tokens",
+                request.id
+            );
+        }
+
+        let mut provider_metadata = HashMap::new();
+        provider_metadata.insert("mode".to_string(), serde_json::json!("API"));
+        provider_metadata.insert("stop_reason".to_string(), serde_json::json!(stop_reason));
+        Ok(LLMResponse {
+            request_id: request.id,
+            content,
+            model_used: json["model"].as_str().unwrap_or(&model).to_string(),
+            token_usage,
+            execution_time,
+            provider_metadata,
+        })
+    }
+
+    /// Send the request; a streamed response is assembled into the same body
+    /// shape as a non-streaming one
+    async fn send(
+        &self,
+        request: &LLMRequest,
+        model: &str,
+        mut on_text: Option<TextSink<'_>>,
+    ) -> Result<Value, LLMError> {
+        let stream = on_text.is_some() || self.streaming();
+        let mut body = anthropic_api::messages_body(request, model, self.prompt_caching());
+        if stream {
+            body["stream"] = serde_json::json!(true);
+        }
+
+        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
+        let mut response = self
+            .http
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", anthropic_api::API_VERSION)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() || !stream {
+            let text = response
+                .text()
+                .await
+                .map_err(|e| LLMError::Network(e.to_string()))?;
+            if !status.is_success() {
+                return Err(anthropic_api::api_error(status.as_u16(), &text));
+            }
+            return serde_json::from_str(&text).map_err(|e| {
+                LLMError::ProviderSpecific(format!("Invalid Anthropic API response: {}", e))
+            });
+        }
+
+        let mut accumulator = StreamAccumulator::default();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?
+        {
+            for text in accumulator.push(&chunk)? {
+                if let Some(on_text) = on_text.as_mut() {
+                    on_text(&text);
+                }
+            }
+        }
+        Ok(accumulator.finish())
+    }
+}
+
+impl LLMProvider for AnthropicApiProvider {
+    fn execute_request<'a>(
+        &'a self,
+        request: LLMRequest,
+        logger: &'a ProviderLogger,
+    ) -> BoxFuture<'a, Result<LLMResponse, LLMError>> {
+        Box::pin(self.execute(request, logger, None))
+    }
+
+    fn get_capabilities(&self) -> BoxFuture<'_, Result<ProviderCapabilities, LLMError>> {
+        Box::pin(async move {
+            Ok(ProviderCapabilities {
+                supports_streaming: true,
+                supports_function_calling: true,
+                supports_vision: true,
+                max_context_tokens: 200000,
+                available_models: self.list_models().await?,
+            })
+        })
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<ProviderStatus, LLMError>> {
+        Box::pin(async move {
+            let limiter = self.rate_limiter.get_status().await;
+            Ok(ProviderStatus {
+                is_healthy: limiter.failure_count < 5,
+                last_check: Utc::now(),
+                error_count: limiter.failure_count,
+                average_response_time: Duration::from_millis(500),
+                rate_limit_status: RateLimitStatus {
+                    requests_remaining: limiter.available_requests as u64,
+                    tokens_remaining: limiter.available_tokens,
+                    reset_time: limiter.last_failure,
+                },
+            })
+        })
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), LLMError>> {
+        Box::pin(async move {
+            let status = self.get_status().await?;
+            if status.is_healthy {
+                Ok(())
+            } else {
+                Err(LLMError::ProviderUnavailable(
+                    "Anthropic API provider has failed repeatedly".to_string(),
+                ))
+            }
+        })
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>, LLMError>> {
+        Box::pin(async move {
+            Ok(vec![
+                anthropic_api::api_model(Some("claude-sonnet")),
+                anthropic_api::api_model(Some("claude-haiku")),
+                anthropic_api::api_model(Some("claude-opus")),
+            ])
+        })
+    }
+
+    fn estimate_tokens(&self, text: &str) -> u64 {
+        crate::claude::token_breakdown::estimate_tokens(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(api_key: Option<&str>) -> ProviderConfig {
+        ProviderConfig {
+            provider_type: crate::llm::ProviderType::Anthropic,
+            api_key: api_key.map(str::to_string),
+            base_url: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_factory_creates_api_provider() {
+        let provider = crate::llm::provider::LLMProviderFactory::create_provider(
+            config(Some("sk-ant-test")),
+            std::path::PathBuf::from("."),
+        )
+        .await
+        .unwrap();
+        assert_eq!(provider.provider_name(), "anthropic");
+        assert!(
+            provider
+                .get_capabilities()
+                .await
+                .unwrap()
+                .supports_streaming
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_is_applied_before_sending() {
+        let mut config = config(Some("sk-ant-test"));
+        config.rate_limits.max_requests_per_minute = 0;
+        let provider = AnthropicApiProvider::new(config).unwrap();
+        let logs = tempfile::tempdir().unwrap();
+        let logger = crate::llm::provider_logger::ProviderLoggerBuilder::new("anthropic")
+            .logs_dir(logs.path().to_path_buf())
+            .build()
+            .await
+            .unwrap();
+
+        let result = provider
+            .execute_request(
+                LLMRequest {
+                    prompt: "Hello".to_string(),
+                    ..Default::default()
+                },
+                &logger,
+            )
+            .await;
+        assert!(matches!(result, Err(LLMError::RateLimit { .. })));
+    }
+}
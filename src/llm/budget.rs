@@ -0,0 +1,195 @@
+//! Cost budgets for sessions and individual tasks.
+//!
+//! [`BudgetManager`] adds up the estimated cost of every provider turn, per
+//! task and for the whole session, and tells the engine when a configured
+//! [`BudgetConfig`] limit has been reached. Costs come from the token usage
+//! each provider reports, so the same limits apply whichever provider runs
+//! the task. A task over budget is paused (left blocked until the budget is
+//! raised) or aborted, depending on [`BudgetAction`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Prefix of the blocked reason of a task stopped by its budget
+pub const BUDGET_EXCEEDED_REASON: &str = "Budget exceeded";
+
+/// What happens to a task once a budget is spent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// Leave the task blocked so it can resume under a larger budget
+    #[default]
+    Pause,
+    /// Fail the task
+    Abort,
+}
+
+/// Spending limits in US dollars of estimated cost
+///
+/// No limits are set by default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Cap on the estimated cost of one session
+    pub max_session_cost_usd: Option<f64>,
+    /// Cap on the estimated cost of any one task, across its turns
+    pub max_task_cost_usd: Option<f64>,
+    pub on_exceeded: BudgetAction,
+}
+
+/// Which limit was reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetScope {
+    Session,
+    Task,
+}
+
+/// A limit that has been reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub scope: BudgetScope,
+    pub spent: f64,
+    pub limit: f64,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = match self.scope {
+            BudgetScope::Session => "the session",
+            BudgetScope::Task => "the task",
+        };
+        write!(
+            f,
+            "{}: {} spent ${:.2} of its ${:.2} budget",
+            BUDGET_EXCEEDED_REASON, scope, self.spent, self.limit
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+#[derive(Debug, Default)]
+struct Spend {
+    session: f64,
+    tasks: HashMap<Uuid, f64>,
+}
+
+/// Running totals checked against a [`BudgetConfig`]
+#[derive(Debug, Default)]
+pub struct BudgetManager {
+    config: BudgetConfig,
+    spend: Mutex<Spend>,
+}
+
+impl BudgetManager {
+    pub fn new(config: BudgetConfig) -> Self {
+        Self {
+            config,
+            spend: Mutex::new(Spend::default()),
+        }
+    }
+
+    pub fn config(&self) -> &BudgetConfig {
+        &self.config
+    }
+
+    /// Add the cost of one of the task's turns
+    pub fn record(&self, task_id: Uuid, cost: f64) {
+        if !cost.is_finite() || cost <= 0.0 {
+            return;
+        }
+        let mut spend = self.spend.lock().unwrap();
+        spend.session += cost;
+        *spend.tasks.entry(task_id).or_default() += cost;
+    }
+
+    pub fn session_spent(&self) -> f64 {
+        self.spend.lock().unwrap().session
+    }
+
+    pub fn task_spent(&self, task_id: Uuid) -> f64 {
+        self.spend
+            .lock()
+            .unwrap()
+            .tasks
+            .get(&task_id)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Whether the task may start another turn
+    ///
+    /// The session limit is checked before the task's own.
+    pub fn check(&self, task_id: Uuid) -> Result<(), BudgetExceeded> {
+        let spend = self.spend.lock().unwrap();
+        if let Some(limit) = self.config.max_session_cost_usd
+            && spend.session >= limit
+        {
+            return Err(BudgetExceeded {
+                scope: BudgetScope::Session,
+                spent: spend.session,
+                limit,
+            });
+        }
+        let task = spend.tasks.get(&task_id).copied().unwrap_or(0.0);
+        if let Some(limit) = self.config.max_task_cost_usd
+            && task >= limit
+        {
+            return Err(BudgetExceeded {
+                scope: BudgetScope::Task,
+                spent: task,
+                limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_and_session_limits() {
+        let budget = BudgetManager::new(BudgetConfig {
+            max_session_cost_usd: Some(2.0),
+            max_task_cost_usd: Some(1.0),
+            ..Default::default()
+        });
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+
+        budget.record(first, 0.6);
+        assert!(budget.check(first).is_ok());
+        budget.record(first, 0.5);
+        let exceeded = budget.check(first).unwrap_err();
+        assert_eq!(exceeded.scope, BudgetScope::Task);
+        assert_eq!(
+            exceeded.to_string(),
+            "Budget exceeded: the task spent $1.10 of its $1.00 budget"
+        );
+
+        assert!(budget.check(second).is_ok());
+        budget.record(second, 0.9);
+        assert_eq!(
+            budget.check(Uuid::new_v4()).unwrap_err().scope,
+            BudgetScope::Session
+        );
+        assert!((budget.session_spent() - 2.0).abs() < 1e-9);
+        assert!((budget.task_spent(second) - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_no_limits_by_default() {
+        let budget = BudgetManager::default();
+        let task = Uuid::new_v4();
+        budget.record(task, 1000.0);
+        budget.record(task, f64::NAN);
+        assert!(budget.check(task).is_ok());
+        assert_eq!(budget.task_spent(task), 1000.0);
+    }
+}
@@ -11,8 +11,11 @@
 //! - Best for development and testing
 //!
 //! ### API Mode
-//! - Direct integration with Anthropic API
+//! - Direct integration with the Anthropic Messages API, delegated to
+//!   [`AnthropicApiProvider`]
 //! - Requires `ANTHROPIC_API_KEY` environment variable or config
+//! - Marks the system prompt for prompt caching (disable with
+//!   `additional_config["prompt_caching"] = false`)
 //! - Suitable for production deployments
 //! - Enables advanced features and higher rate limits
 //!
@@ -54,6 +57,7 @@
 //! ```
 
 use crate::claude::ClaudeCodeInterface;
+use crate::llm::anthropic_provider::AnthropicApiProvider;
 use crate::llm::provider::LLMProvider;
 use crate::llm::types::{
     ClaudeProviderMode, LLMError, LLMRequest, LLMResponse, ProviderCapabilities, ProviderConfig,
@@ -71,9 +75,9 @@ use std::time::Duration;
 /// Mode is automatically determined from configuration or environment variables.
 pub struct ClaudeProvider {
     claude_interface: ClaudeCodeInterface,
-    #[allow(dead_code)]
-    config: ProviderConfig,
     mode: ClaudeProviderMode,
+    /// Handles requests in API mode
+    api: Option<AnthropicApiProvider>,
 }
 
 impl ClaudeProvider {
@@ -88,6 +92,11 @@ impl ClaudeProvider {
             ));
         }
 
+        let api = match mode {
+            ClaudeProviderMode::API => Some(AnthropicApiProvider::new(config.clone())?),
+            ClaudeProviderMode::CLI => None,
+        };
+
         // Convert ProviderConfig to ClaudeConfig
         let claude_config = crate::claude::ClaudeConfig {
             api_key: config.api_key.clone(),
@@ -109,6 +118,7 @@ impl ClaudeProvider {
                 compression_threshold: 0.8,
                 max_history_length: 100,
                 relevance_threshold: 0.3,
+                max_relevant_turns: 8,
             },
             usage_tracking: crate::claude::UsageTrackingConfig {
                 track_tokens: true,
@@ -124,6 +134,15 @@ impl ClaudeProvider {
                 enable_fallback_models: true,
             },
             show_subprocess_output: false, // Controlled via CLI --verbose flag
+            system_prompt: None,           // Supplied per request via LLMRequest
+            shared_rate_limit: crate::claude::SharedRateLimitConfig::default(),
+            dump_context: None,
+            allowed_tools: crate::claude::DEFAULT_ALLOWED_TOOLS
+                .iter()
+                .map(|tool| tool.to_string())
+                .collect(),
+            permission_mode: crate::claude::PermissionMode::default(),
+            request_timeout_secs: crate::claude::ClaudeConfig::default().request_timeout_secs,
         };
 
         let claude_interface = ClaudeCodeInterface::new(claude_config, workspace_root)
@@ -134,8 +153,8 @@ impl ClaudeProvider {
 
         Ok(Self {
             claude_interface,
-            config,
             mode,
+            api,
         })
     }
 
@@ -186,6 +205,10 @@ impl LLMProvider for ClaudeProvider {
         logger: &'a crate::llm::provider_logger::ProviderLogger,
     ) -> BoxFuture<'a, Result<LLMResponse, LLMError>> {
         Box::pin(async move {
+            if let Some(api) = &self.api {
+                return api.execute_request(request, logger).await;
+            }
+
             // Convert LLMRequest to Claude TaskRequest
             let claude_request = crate::claude::TaskRequest {
                 id: request.id,
@@ -195,6 +218,7 @@ impl LLMProvider for ClaudeProvider {
                 priority: crate::claude::TaskPriority::Medium,
                 estimated_tokens: request.max_tokens,
                 system_message: request.system_message,
+                tool_permissions: Default::default(),
             };
 
             // Execute via Claude interface with logger for audit trail
@@ -220,6 +244,7 @@ impl LLMProvider for ClaudeProvider {
                         LLMError::ContextTooLarge { current, max }
                     }
                     crate::claude::ClaudeError::NetworkTimeout(msg) => LLMError::Network(msg),
+                    crate::claude::ClaudeError::Timeout { .. } => LLMError::Network(e.to_string()),
                     _ => LLMError::ProviderSpecific(format!("Claude error: {}", e)),
                 })?;
 
@@ -243,6 +268,9 @@ impl LLMProvider for ClaudeProvider {
                     output_tokens: claude_response.token_usage.output_tokens,
                     total_tokens: claude_response.token_usage.total_tokens,
                     estimated_cost: claude_response.token_usage.estimated_cost,
+                    cache_read_tokens: claude_response.token_usage.cache_read_tokens,
+                    cache_creation_tokens: claude_response.token_usage.cache_creation_tokens,
+                    cache_savings: claude_response.token_usage.cache_savings,
                 },
                 execution_time: claude_response.execution_time,
                 provider_metadata,
@@ -0,0 +1,599 @@
+//! Provider-specific system prompts and workspace instructions files.
+//!
+//! Each agent CLI reads project instructions from its own file: Claude Code
+//! reads `CLAUDE.md` while Codex (and most local agents) read `AGENTS.md`.
+//! This module generates a provider-appropriate instructions section from the
+//! default system prompt plus any pinned context files, writes it into the
+//! workspace before a run, and restores or keeps the file afterwards.
+//!
+//! The generated content is wrapped in marker comments so an existing,
+//! user-maintained instructions file is never clobbered: only the managed
+//! section is replaced on each run.
+//!
+//! Pinned and imported files are decoded with a [`TextFileConfig`], so
+//! UTF-16 and Latin-1 files are included as text while binary and oversized
+//! ones are skipped with a warning.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use aca::llm::instructions::{InstructionsConfig, InstructionsManager};
+//! use aca::llm::ProviderType;
+//! use std::path::PathBuf;
+//!
+//! # fn example() -> anyhow::Result<()> {
+//! let config = InstructionsConfig {
+//!     write_instructions_file: true,
+//!     ..Default::default()
+//! };
+//! let manager = InstructionsManager::new(config, ProviderType::ClaudeCode, PathBuf::from("."));
+//! manager.prepare()?;
+//! // ... run tasks ...
+//! manager.cleanup()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::claude::context_budget::{ContextBudgetConfig, ContextPiece};
+use crate::claude::token_breakdown::ContextSource;
+use crate::llm::types::ProviderType;
+use crate::workspace::{LfsAttributes, TextFileConfig, resolve_within};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Marker opening the aca-managed section of an instructions file
+pub const MANAGED_SECTION_BEGIN: &str = "<!-- aca:instructions:begin -->";
+
+/// Marker closing the aca-managed section of an instructions file
+pub const MANAGED_SECTION_END: &str = "<!-- aca:instructions:end -->";
+
+/// What to do with the generated instructions file once the run finishes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum InstructionsCleanup {
+    /// Restore the file to its pre-run state (delete it if aca created it)
+    #[default]
+    Restore,
+    /// Leave the generated file in the workspace
+    Keep,
+}
+
+/// Configuration for provider system prompts and instructions files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstructionsConfig {
+    /// Override for the provider's default system prompt
+    pub system_prompt: Option<String>,
+    /// Generate a provider-specific instructions file before each run
+    pub write_instructions_file: bool,
+    /// Files whose contents are pinned into the instructions file
+    pub pinned_context: Vec<PathBuf>,
+    /// Cleanup behaviour after the run
+    pub cleanup: InstructionsCleanup,
+}
+
+impl Default for InstructionsConfig {
+    fn default() -> Self {
+        Self {
+            system_prompt: None,
+            write_instructions_file: false,
+            pinned_context: Vec::new(),
+            cleanup: InstructionsCleanup::Restore,
+        }
+    }
+}
+
+/// File name the provider's agent reads project instructions from
+pub fn instructions_file_name(provider: &ProviderType) -> &'static str {
+    match provider {
+        ProviderType::ClaudeCode | ProviderType::Anthropic => "CLAUDE.md",
+        ProviderType::OpenAICodex
+        | ProviderType::OpenAIApi
+        | ProviderType::LocalModel
+        | ProviderType::Custom(_) => "AGENTS.md",
+    }
+}
+
+/// Built-in system prompt for a provider
+pub fn default_system_prompt(provider: &ProviderType) -> &'static str {
+    match provider {
+        ProviderType::ClaudeCode | ProviderType::Anthropic => {
+            "You are running unattended as part of an automated coding pipeline. \
+             Work directly in the current workspace, keep changes focused on the task, \
+             follow the existing conventions of the codebase, and finish with a short \
+             summary of what you changed."
+        }
+        ProviderType::OpenAICodex
+        | ProviderType::OpenAIApi
+        | ProviderType::LocalModel
+        | ProviderType::Custom(_) => {
+            "You are an autonomous coding agent executing a single task without human \
+             supervision. Edit files in the current workspace, keep the change minimal \
+             and consistent with surrounding code, and end with a brief summary."
+        }
+    }
+}
+
+/// Writes and restores the instructions file for a single provider
+#[derive(Debug)]
+pub struct InstructionsManager {
+    config: InstructionsConfig,
+    provider: ProviderType,
+    workspace_root: PathBuf,
+    /// Content of the file before `prepare` ran (`Some(None)` if it did not exist)
+    original: Mutex<Option<Option<String>>>,
+    /// Pinned and imported context after [`Self::apply_budget`]
+    budgeted: Option<Vec<ContextPiece>>,
+    text_files: TextFileConfig,
+}
+
+impl InstructionsManager {
+    pub fn new(
+        config: InstructionsConfig,
+        provider: ProviderType,
+        workspace_root: PathBuf,
+    ) -> Self {
+        Self {
+            config,
+            provider,
+            workspace_root,
+            original: Mutex::new(None),
+            budgeted: None,
+            text_files: TextFileConfig::default(),
+        }
+    }
+
+    /// Decide which pinned and imported files are read as text with
+    /// `text_files`
+    pub fn with_text_files(mut self, text_files: TextFileConfig) -> Self {
+        self.text_files = text_files;
+        self
+    }
+
+    /// Contents of a context file, or `None` with a warning if it cannot
+    /// be read as text
+    fn read_context(&self, kind: &str, path: &Path) -> Option<String> {
+        if path
+            .strip_prefix(&self.workspace_root)
+            .is_ok_and(|relative| LfsAttributes::load(&self.workspace_root).tracks(relative))
+        {
+            warn!("Skipping {} context {:?}: stored in Git LFS", kind, path);
+            return None;
+        }
+        match self.text_files.read(path) {
+            Ok(Some(decoded)) => Some(decoded.text),
+            Ok(None) => {
+                warn!(
+                    "Skipping {} context {:?}: binary, a Git LFS pointer or larger than {} bytes",
+                    kind, path, self.text_files.max_text_bytes
+                );
+                None
+            }
+            Err(e) => {
+                warn!("Skipping {} context {:?}: {}", kind, path, e);
+                None
+            }
+        }
+    }
+
+    /// Effective system prompt (configured override or provider default)
+    pub fn system_prompt(&self) -> String {
+        self.config
+            .system_prompt
+            .clone()
+            .unwrap_or_else(|| default_system_prompt(&self.provider).to_string())
+    }
+
+    /// Markdown files under `.aca/context`, e.g. imported Claude Code history
+    pub fn imported_context(&self) -> Option<String> {
+        if let Some(budgeted) = &self.budgeted {
+            return budgeted
+                .iter()
+                .find(|piece| piece.source == ContextSource::Imported)
+                .map(|piece| piece.content.clone());
+        }
+
+        let dir = crate::env::context_dir_path(&self.workspace_root);
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("md"))
+            .collect();
+        paths.sort();
+
+        let sections: Vec<String> = paths
+            .iter()
+            .filter_map(|path| self.read_context("imported", path))
+            .filter(|content| !content.trim().is_empty())
+            .map(|content| content.trim_end().to_string())
+            .collect();
+        (!sections.is_empty()).then(|| sections.join("\n\n"))
+    }
+
+    /// Readable pinned files with their contents, in configured order
+    pub fn pinned_context(&self) -> Vec<(PathBuf, String)> {
+        if let Some(budgeted) = &self.budgeted {
+            return budgeted
+                .iter()
+                .filter_map(|piece| match &piece.source {
+                    ContextSource::Pinned(path) => Some((path.clone(), piece.content.clone())),
+                    _ => None,
+                })
+                .collect();
+        }
+
+        self.config
+            .pinned_context
+            .iter()
+            .filter_map(|path| {
+                // Absolute paths are the user's choice; relative ones stay
+                // in the workspace
+                let resolved = if path.is_absolute() {
+                    path.clone()
+                } else {
+                    match resolve_within(&self.workspace_root, path) {
+                        Ok(resolved) => resolved,
+                        Err(e) => {
+                            warn!("Skipping pinned context {:?}: {}", path, e);
+                            return None;
+                        }
+                    }
+                };
+                self.read_context("pinned", &resolved)
+                    .map(|content| (path.clone(), content))
+            })
+            .collect()
+    }
+
+    /// Fit pinned and imported context into `budget`, leaving `fixed_tokens`
+    /// for the rest of the prompt, and return what will be sent
+    ///
+    /// Pinned files only count when the instructions file is written.
+    pub fn apply_budget(
+        &mut self,
+        budget: &ContextBudgetConfig,
+        fixed_tokens: u64,
+    ) -> Vec<ContextPiece> {
+        let mut pieces = Vec::new();
+        if self.config.write_instructions_file {
+            pieces.extend(
+                self.pinned_context()
+                    .into_iter()
+                    .map(|(path, content)| ContextPiece {
+                        source: ContextSource::Pinned(path),
+                        content,
+                    }),
+            );
+        }
+        if let Some(content) = self.imported_context() {
+            pieces.push(ContextPiece {
+                source: ContextSource::Imported,
+                content,
+            });
+        }
+
+        let fitted = budget.fit(fixed_tokens, pieces);
+        self.budgeted = Some(fitted.clone());
+        fitted
+    }
+
+    /// Path of the instructions file in the workspace
+    pub fn instructions_path(&self) -> PathBuf {
+        self.workspace_root
+            .join(instructions_file_name(&self.provider))
+    }
+
+    /// [`Self::instructions_path`] resolved through symlinks, refusing one
+    /// that leads outside the workspace
+    fn writable_instructions_path(&self) -> Result<PathBuf> {
+        resolve_within(
+            &self.workspace_root,
+            Path::new(instructions_file_name(&self.provider)),
+        )
+        .context("Refusing to write the instructions file")
+    }
+
+    /// Render the managed section (system prompt plus pinned context)
+    pub fn render_section(&self) -> String {
+        let mut section = String::new();
+        section.push_str(MANAGED_SECTION_BEGIN);
+        section.push_str("\n## Automation Instructions (generated by aca)\n\n");
+        section.push_str(&self.system_prompt());
+        section.push('\n');
+
+        for (path, content) in self.pinned_context() {
+            section.push_str(&format!("\n### Pinned: {}\n\n", path.display()));
+            section.push_str(content.trim_end());
+            section.push('\n');
+        }
+
+        if let Some(context) = self.imported_context() {
+            section.push('\n');
+            section.push_str(&context);
+            section.push('\n');
+        }
+
+        section.push_str(MANAGED_SECTION_END);
+        section.push('\n');
+        section
+    }
+
+    /// Generate or update the instructions file before a run
+    pub fn prepare(&self) -> Result<()> {
+        if !self.config.write_instructions_file {
+            return Ok(());
+        }
+
+        let path = self.writable_instructions_path()?;
+        let existing = read_optional(&path)?;
+
+        let updated = match &existing {
+            Some(content) => replace_managed_section(content, &self.render_section()),
+            None => self.render_section(),
+        };
+
+        std::fs::write(&path, updated)
+            .with_context(|| format!("Failed to write instructions file {:?}", path))?;
+
+        let mut original = self.original.lock().unwrap();
+        if original.is_none() {
+            *original = Some(existing);
+        }
+
+        info!("Prepared instructions file: {:?}", path);
+        Ok(())
+    }
+
+    /// Restore or keep the instructions file according to configuration
+    pub fn cleanup(&self) -> Result<()> {
+        let Some(original) = self.original.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        if self.config.cleanup == InstructionsCleanup::Keep {
+            return Ok(());
+        }
+
+        let path = self.writable_instructions_path()?;
+        match original {
+            Some(content) => std::fs::write(&path, content)
+                .with_context(|| format!("Failed to restore instructions file {:?}", path))?,
+            None => {
+                if path.exists() {
+                    std::fs::remove_file(&path).with_context(|| {
+                        format!("Failed to remove instructions file {:?}", path)
+                    })?;
+                }
+            }
+        }
+
+        info!("Restored instructions file: {:?}", path);
+        Ok(())
+    }
+}
+
+/// Replace the managed section in `content`, appending it if absent
+fn replace_managed_section(content: &str, section: &str) -> String {
+    if let Some(start) = content.find(MANAGED_SECTION_BEGIN)
+        && let Some(end_offset) = content[start..].find(MANAGED_SECTION_END)
+    {
+        let mut end = start + end_offset + MANAGED_SECTION_END.len();
+        if content[end..].starts_with('\n') {
+            end += 1;
+        }
+        return format!("{}{}{}", &content[..start], section, &content[end..]);
+    }
+
+    let separator = if content.is_empty() || content.ends_with("\n\n") {
+        ""
+    } else if content.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{}{}{}", content, separator, section)
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn enabled_config() -> InstructionsConfig {
+        InstructionsConfig {
+            write_instructions_file: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_instructions_file_name_per_provider() {
+        assert_eq!(
+            instructions_file_name(&ProviderType::ClaudeCode),
+            "CLAUDE.md"
+        );
+        assert_eq!(
+            instructions_file_name(&ProviderType::OpenAICodex),
+            "AGENTS.md"
+        );
+        assert_eq!(
+            instructions_file_name(&ProviderType::LocalModel),
+            "AGENTS.md"
+        );
+    }
+
+    #[test]
+    fn test_system_prompt_override() {
+        let config = InstructionsConfig {
+            system_prompt: Some("Custom prompt".to_string()),
+            ..Default::default()
+        };
+        let manager = InstructionsManager::new(config, ProviderType::ClaudeCode, PathBuf::new());
+        assert_eq!(manager.system_prompt(), "Custom prompt");
+    }
+
+    #[test]
+    fn test_imported_context() {
+        let temp = TempDir::new().unwrap();
+        let manager = InstructionsManager::new(
+            enabled_config(),
+            ProviderType::ClaudeCode,
+            temp.path().to_path_buf(),
+        );
+        assert_eq!(manager.imported_context(), None);
+
+        let dir = crate::env::context_dir_path(temp.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("claude-history.md"), "# Earlier sessions\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        assert_eq!(
+            manager.imported_context().as_deref(),
+            Some("# Earlier sessions")
+        );
+        assert!(manager.render_section().contains("# Earlier sessions"));
+    }
+
+    #[test]
+    fn test_pinned_context_decodes_and_skips_binary() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("LEGACY.md"), b"Caf\xE9 rules").unwrap();
+        std::fs::write(temp.path().join("WIDE.md"), b"\xFF\xFEo\x00k\x00").unwrap();
+        std::fs::write(temp.path().join("blob.md"), b"\x00\x01\x02").unwrap();
+        std::fs::write(
+            temp.path().join("MODEL.md"),
+            "version https://git-lfs.github.com/spec/v1\noid sha256:ab\nsize 9\n",
+        )
+        .unwrap();
+        std::fs::write(temp.path().join(".gitattributes"), "*.csv filter=lfs\n").unwrap();
+        std::fs::write(temp.path().join("users.csv"), "id,name\n").unwrap();
+        let config = InstructionsConfig {
+            pinned_context: ["LEGACY.md", "WIDE.md", "blob.md", "MODEL.md", "users.csv"]
+                .iter()
+                .map(PathBuf::from)
+                .collect(),
+            ..enabled_config()
+        };
+        let manager =
+            InstructionsManager::new(config, ProviderType::ClaudeCode, temp.path().to_path_buf());
+
+        assert_eq!(
+            manager.pinned_context(),
+            vec![
+                (PathBuf::from("LEGACY.md"), "Café rules".to_string()),
+                (PathBuf::from("WIDE.md"), "ok".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_instructions_file_symlinked_outside_is_refused() {
+        let temp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("CLAUDE.md"),
+            temp.path().join("CLAUDE.md"),
+        )
+        .unwrap();
+        let manager = InstructionsManager::new(
+            enabled_config(),
+            ProviderType::ClaudeCode,
+            temp.path().to_path_buf(),
+        );
+
+        let error = format!("{:#}", manager.prepare().unwrap_err());
+        assert!(error.contains("dangling symlink"), "{}", error);
+        assert!(!outside.path().join("CLAUDE.md").exists());
+    }
+
+    #[test]
+    fn test_prepare_and_restore_new_file() {
+        let temp = TempDir::new().unwrap();
+        let manager = InstructionsManager::new(
+            enabled_config(),
+            ProviderType::OpenAICodex,
+            temp.path().to_path_buf(),
+        );
+
+        manager.prepare().unwrap();
+        let path = temp.path().join("AGENTS.md");
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains(MANAGED_SECTION_BEGIN));
+
+        manager.cleanup().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_prepare_preserves_user_content() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("CLAUDE.md");
+        std::fs::write(&path, "# Project notes\n").unwrap();
+        std::fs::write(temp.path().join("STYLE.md"), "Use tabs.").unwrap();
+
+        let config = InstructionsConfig {
+            pinned_context: vec![PathBuf::from("STYLE.md")],
+            cleanup: InstructionsCleanup::Keep,
+            ..enabled_config()
+        };
+        let manager =
+            InstructionsManager::new(config, ProviderType::ClaudeCode, temp.path().to_path_buf());
+
+        // Running twice must not duplicate the managed section
+        manager.prepare().unwrap();
+        manager.prepare().unwrap();
+        manager.cleanup().unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Project notes\n"));
+        assert!(content.contains("Use tabs."));
+        assert_eq!(content.matches(MANAGED_SECTION_BEGIN).count(), 1);
+    }
+
+    #[test]
+    fn test_apply_budget_truncates_pinned_and_imported() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("STYLE.md"), "a".repeat(2000)).unwrap();
+        let dir = crate::env::context_dir_path(temp.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("claude-history.md"), "b".repeat(2000)).unwrap();
+
+        let config = InstructionsConfig {
+            pinned_context: vec![PathBuf::from("STYLE.md")],
+            ..enabled_config()
+        };
+        let mut manager =
+            InstructionsManager::new(config, ProviderType::ClaudeCode, temp.path().to_path_buf());
+        let budget = ContextBudgetConfig {
+            max_input_tokens: Some(1000),
+            pinned_percent: Some(20),
+            ..Default::default()
+        };
+
+        // 600 fixed tokens leave 400: pinned is capped at 200, imported
+        // gets the remaining 200
+        let fitted = manager.apply_budget(&budget, 600);
+        let tokens: Vec<u64> = fitted.iter().map(|piece| piece.tokens().tokens).collect();
+        assert_eq!(tokens, vec![200, 200]);
+
+        let (_, pinned) = &manager.pinned_context()[0];
+        assert!(pinned.ends_with(crate::claude::context_budget::TRUNCATION_MARKER));
+        assert!(manager.imported_context().unwrap().len() <= 800);
+        assert!(
+            manager
+                .render_section()
+                .contains(crate::claude::context_budget::TRUNCATION_MARKER)
+        );
+    }
+}
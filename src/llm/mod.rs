@@ -7,8 +7,16 @@
 //!
 //! - **[`LLMProvider`]**: Universal trait for all LLM provider implementations
 //! - **`ClaudeProvider`**: Claude-specific implementation with Claude Code integration
+//! - **`anthropic_api`**: Messages API requests with prompt caching for API mode
+//! - **`AnthropicApiProvider`**: Direct Messages API provider with streaming, no CLI required
+//! - **`OllamaProvider`**: Local models served by Ollama
+//! - **`OpenAIApiProvider`**: Chat Completions / Responses API provider for OpenAI, Azure and OpenRouter
 //! - **`LLMProviderFactory`**: Factory for creating provider instances
 //! - **Provider Types**: Request/response types and configuration structures
+//! - **[`OfflineMode`]**: Provider health probes, queueing tasks while no provider is reachable
+//! - **[`ProviderRouter`]**: Per-task provider and model selection by preference, complexity and tags
+//! - **[`execute_structured`]**: Schema-validated JSON responses with retry on invalid output
+//! - **[`Pseudonymizer`]**: Reversible placeholders for configured names in prompts sent to cloud providers
 //!
 //! ## Key Features
 //!
@@ -18,8 +26,11 @@
 //!   - **API Mode**: Direct Anthropic API access, requires API key
 //!   - Configure via `CLAUDE_MODE` env var or `additional_config["mode"]`
 //! - **OpenAI Codex CLI**: Local Codex command-line integration
-//! - **Anthropic API**: Direct Anthropic API integration (via API mode)
-//! - **Local Models**: Ollama, LocalAI, and other local inference (planned)
+//! - **OpenAI API**: Chat Completions or Responses API over HTTP
+//!   (`OpenAIApiProvider`), also for Azure OpenAI and OpenRouter
+//! - **Anthropic API**: Direct Anthropic API integration (`AnthropicApiProvider`,
+//!   also used by Claude's API mode)
+//! - **Local Models**: Ollama (`OllamaProvider`), selected with `--provider local`
 //! - **Custom Providers**: Extensible architecture for custom implementations
 //!
 //! ### 🔀 Unified Interface
@@ -160,37 +171,105 @@
 //! }
 //! ```
 
+/// Reversible pseudonymization of prompts for cloud providers.
+///
+/// Replaces configured names and hostnames with placeholders kept in a local
+/// mapping, and restores them in responses and the files a task wrote.
+pub mod anonymize;
+
+/// Anthropic Messages API requests for Claude API mode.
+///
+/// Builds request bodies with the system prompt marked for prompt caching
+/// and reads cache reads, writes and savings from the response usage.
+pub mod anthropic_api;
+
+/// Native Anthropic Messages API provider.
+///
+/// Talks to the API over HTTP, streaming or not, under the configured rate
+/// limits; for machines where the `claude` CLI is not installed.
+pub mod anthropic_provider;
+
+/// Cost budgets for sessions and tasks.
+///
+/// Adds up the estimated cost of each provider turn and reports when a
+/// session or task limit from `AgentConfig` has been spent.
+pub mod budget;
+
 /// Claude-specific LLM provider implementation.
 ///
 /// Integrates with the Claude Code interface to provide full Claude
 /// functionality including context management, rate limiting, and error recovery.
 pub mod claude_provider;
 
+/// Offline mode for runs without a reachable provider.
+///
+/// Probes provider health and queues tasks that need the model as blocked
+/// until it is reachable again.
+pub mod offline;
+
 /// OpenAI Codex LLM provider implementation.
 ///
 /// Provides access to OpenAI's Codex-style completions with smart rate limiting.
 pub mod openai_provider;
 
+/// Native OpenAI API provider.
+///
+/// Calls the Chat Completions or Responses API at a configurable base URL,
+/// returns the model's tool calls and estimates cost per model.
+pub mod openai_api_provider;
+
+/// Local model provider backed by Ollama.
+///
+/// Chats with models served by a local `ollama serve` and lists the models
+/// it has pulled; no API key or rate limits.
+pub mod ollama_provider;
+
 /// Core LLM provider trait and factory.
 ///
 /// Defines the universal [`LLMProvider`] trait that all provider implementations
 /// must satisfy, plus the factory for creating provider instances.
 pub mod provider;
 
+/// Per-task provider routing.
+///
+/// Picks the provider and model for each task from its preference or the
+/// configured rules, and runs tasks routed away from the Claude Code CLI.
+pub mod router;
+
 /// Provider-agnostic types and configuration.
 ///
 /// Common data types, request/response structures, error types,
 /// and configuration options used across all LLM providers.
 pub mod types;
 
+/// Provider-specific system prompts and workspace instructions files.
+///
+/// Generates `CLAUDE.md` / `AGENTS.md` content from the default prompt and
+/// pinned context, and restores the workspace after the run.
+pub mod instructions;
+
 /// Standardized logging abstraction for provider interactions.
 ///
 /// Provides unified logging interface for consistent audit trails across all providers.
 pub mod provider_logger;
 
+/// Schema validation and retries for structured responses.
+///
+/// Extracts JSON from model output, validates it against the request's
+/// JSON Schema and re-asks the model with the errors when it does not match.
+pub mod structured;
+
+pub use anonymize::{AnonymizationConfig, Pseudonymizer};
+pub use anthropic_provider::AnthropicApiProvider;
+pub use budget::{BudgetAction, BudgetConfig, BudgetExceeded, BudgetManager, BudgetScope};
 pub use claude_provider::ClaudeProvider;
+pub use offline::{HealthProbe, OfflineConfig, OfflineMode, QueuedOffline};
+pub use ollama_provider::OllamaProvider;
+pub use openai_api_provider::OpenAIApiProvider;
 pub use openai_provider::OpenAIProvider;
 pub use provider::LLMProvider;
+pub use router::{ProviderRouter, Route, RoutingConfig, RoutingRule};
+pub use structured::{DEFAULT_STRUCTURED_RETRIES, StructuredResponse, execute_structured};
 pub use types::*;
 
 // Re-export ClaudeProviderMode for convenience
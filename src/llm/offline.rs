@@ -0,0 +1,293 @@
+//! Offline mode for runs without a reachable provider.
+//!
+//! [`OfflineMode`] probes the provider through a [`HealthProbe`] when a
+//! plan starts and whenever a provider call fails with what looks like a
+//! connectivity error. While it is offline the engine still accepts plans
+//! and does the deterministic work (setup and verification commands,
+//! validation, reports), but tasks that need the model are left
+//! `Blocked("offline")` instead of failing. Once a probe succeeds again the
+//! queued tasks run, in dependency order.
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Blocked reason of a task queued until a provider is reachable
+pub const OFFLINE_REASON: &str = "offline";
+
+/// Time a probe waits for the provider's endpoint to accept a connection
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Error messages that point at the provider being unreachable rather than
+/// at the request
+const CONNECTIVITY_PHRASES: &[&str] = &[
+    "failed to spawn",
+    "failed to execute claude command",
+    "no such file or directory",
+    "connection refused",
+    "connection reset",
+    "network",
+    "timed out",
+    "dns error",
+    "could not resolve",
+    "service unavailable",
+    "not reachable",
+];
+
+/// Offline mode settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OfflineConfig {
+    /// Queue provider tasks instead of failing them when no provider is
+    /// reachable
+    pub enabled: bool,
+    /// Seconds between provider probes while offline
+    pub probe_interval_secs: u64,
+    /// How long a plan run waits for the provider to return before leaving
+    /// the queued tasks blocked; 0 stops waiting at once
+    pub max_wait_minutes: u64,
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            probe_interval_secs: 60,
+            max_wait_minutes: 30,
+        }
+    }
+}
+
+/// Checks whether the provider tasks run through can be reached
+pub trait HealthProbe: Send + Sync {
+    /// `Err` describes why the provider is unreachable
+    fn check(&self) -> BoxFuture<'_, Result<(), String>>;
+}
+
+/// Probe for the Claude Code CLI: the binary is installed and the API
+/// endpoint (`ANTHROPIC_BASE_URL`, or `api.anthropic.com`) accepts connections
+#[derive(Debug, Clone, Default)]
+pub struct ClaudeCliProbe;
+
+impl HealthProbe for ClaudeCliProbe {
+    fn check(&self) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async {
+            which::which("claude").map_err(|_| "the `claude` CLI is not installed".to_string())?;
+            let endpoint = api_endpoint(std::env::var("ANTHROPIC_BASE_URL").ok().as_deref());
+            match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(&endpoint))
+                .await
+            {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(format!("{} is not reachable: {}", endpoint, e)),
+                Err(_) => Err(format!(
+                    "{} did not answer within {}s",
+                    endpoint,
+                    PROBE_TIMEOUT.as_secs()
+                )),
+            }
+        })
+    }
+}
+
+/// `host:port` of the API behind `base_url`
+fn api_endpoint(base_url: Option<&str>) -> String {
+    base_url
+        .and_then(|url| reqwest::Url::parse(url).ok())
+        .and_then(|url| {
+            Some(format!(
+                "{}:{}",
+                url.host_str()?,
+                url.port_or_known_default()?
+            ))
+        })
+        .unwrap_or_else(|| "api.anthropic.com:443".to_string())
+}
+
+/// Whether a provider error message looks like the provider is unreachable
+pub fn is_connectivity_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    CONNECTIVITY_PHRASES
+        .iter()
+        .any(|phrase| message.contains(phrase))
+}
+
+/// A task left blocked until a provider is reachable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Task {task_id} is queued until a provider is reachable")]
+pub struct QueuedOffline {
+    pub task_id: Uuid,
+}
+
+/// Whether the provider is reachable, as of the last probe
+pub struct OfflineMode {
+    config: OfflineConfig,
+    probe: RwLock<Arc<dyn HealthProbe>>,
+    offline: AtomicBool,
+}
+
+impl OfflineMode {
+    pub fn new(config: OfflineConfig) -> Self {
+        Self {
+            config,
+            probe: RwLock::new(Arc::new(ClaudeCliProbe)),
+            offline: AtomicBool::new(false),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Replace the probe, e.g. for a provider other than the Claude CLI
+    pub fn set_probe(&self, probe: Arc<dyn HealthProbe>) {
+        *self.probe.write().unwrap() = probe;
+    }
+
+    /// Whether the last probe failed
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    fn probe_interval(&self) -> Duration {
+        Duration::from_secs(self.config.probe_interval_secs)
+    }
+
+    /// When a queued task should next be tried
+    pub fn retry_after(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now()
+            + chrono::Duration::seconds(self.config.probe_interval_secs.min(i64::MAX as u64) as i64)
+    }
+
+    /// Probe the provider, returning whether it is reachable
+    ///
+    /// Always reachable when offline mode is disabled, so tasks fail as
+    /// before.
+    pub async fn refresh(&self) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let probe = self.probe.read().unwrap().clone();
+        let result = probe.check().await;
+        let was_offline = self.offline.swap(result.is_err(), Ordering::Relaxed);
+        match (&result, was_offline) {
+            (Err(reason), false) => warn!(
+                "No provider reachable ({}); running offline, provider tasks are queued",
+                reason
+            ),
+            (Ok(()), true) => info!("Provider reachable again, leaving offline mode"),
+            _ => {}
+        }
+        result.is_ok()
+    }
+
+    /// Wait, probing every interval, until the provider is reachable or
+    /// `max_wait_minutes` pass; returns whether it is reachable
+    pub async fn wait_until_online(&self) -> bool {
+        if !self.is_offline() || self.refresh().await {
+            return true;
+        }
+        let max_wait = Duration::from_secs(self.config.max_wait_minutes * 60);
+        if max_wait.is_zero() {
+            return false;
+        }
+        info!(
+            "Waiting up to {} minute(s) for a provider to become reachable",
+            self.config.max_wait_minutes
+        );
+        let started = Instant::now();
+        while started.elapsed() < max_wait {
+            tokio::time::sleep(
+                self.probe_interval()
+                    .min(max_wait.saturating_sub(started.elapsed())),
+            )
+            .await;
+            if self.refresh().await {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    /// Fails until it has been checked `failures` times
+    struct FlakyProbe {
+        failures: u32,
+        checks: AtomicU32,
+    }
+
+    impl HealthProbe for FlakyProbe {
+        fn check(&self) -> BoxFuture<'_, Result<(), String>> {
+            Box::pin(async move {
+                if self.checks.fetch_add(1, Ordering::Relaxed) < self.failures {
+                    Err("connection refused".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    fn offline_mode(failures: u32, max_wait_minutes: u64) -> OfflineMode {
+        let mode = OfflineMode::new(OfflineConfig {
+            enabled: true,
+            probe_interval_secs: 0,
+            max_wait_minutes,
+        });
+        mode.set_probe(Arc::new(FlakyProbe {
+            failures,
+            checks: AtomicU32::new(0),
+        }));
+        mode
+    }
+
+    #[tokio::test]
+    async fn test_probe_failures_switch_to_offline_until_reachable() {
+        let mode = offline_mode(3, 1);
+        assert!(!mode.is_offline());
+        assert!(!mode.refresh().await);
+        assert!(mode.is_offline());
+
+        // Two more failed probes, then the provider is back
+        assert!(mode.wait_until_online().await);
+        assert!(!mode.is_offline());
+
+        let mode = offline_mode(u32::MAX, 0);
+        assert!(!mode.refresh().await);
+        assert!(!mode.wait_until_online().await);
+
+        let disabled = OfflineMode::new(OfflineConfig {
+            enabled: false,
+            ..Default::default()
+        });
+        disabled.set_probe(Arc::new(FlakyProbe {
+            failures: u32::MAX,
+            checks: AtomicU32::new(0),
+        }));
+        assert!(disabled.refresh().await);
+        assert!(!disabled.is_offline());
+    }
+
+    #[test]
+    fn test_connectivity_errors_and_endpoints() {
+        assert!(is_connectivity_error(
+            "Failed to execute claude command: No such file or directory (os error 2)"
+        ));
+        assert!(is_connectivity_error("Network timeout: request timed out"));
+        assert!(!is_connectivity_error("Invalid request: prompt is empty"));
+
+        assert_eq!(api_endpoint(None), "api.anthropic.com:443");
+        assert_eq!(
+            api_endpoint(Some("http://proxy.internal:8080/anthropic")),
+            "proxy.internal:8080"
+        );
+    }
+}
@@ -0,0 +1,474 @@
+//! Local model provider backed by Ollama
+//!
+//! Implements [`LLMProvider`] over Ollama's HTTP API (`/api/chat` and
+//! `/api/tags`), so tasks can run against models served on the local
+//! machine. Selected with `--provider local-model` (alias `local`) or
+//! [`ProviderType::LocalModel`](super::ProviderType::LocalModel).
+//!
+//! ## Configuration
+//!
+//! - `base_url`, or the `OLLAMA_HOST` environment variable (default
+//!   `http://localhost:11434`)
+//! - `model`, or `OLLAMA_MODEL` (default `llama3.1`); the Claude aliases the
+//!   default provider configuration names are ignored
+//! - `additional_config["context_window"]`: the model's context size in
+//!   tokens, reported in the provider's capabilities (default 8192)
+//!
+//! Local models cost nothing, so responses report an estimated cost of 0.
+
+use crate::llm::provider::LLMProvider;
+use crate::llm::provider_logger::{LogContext, ProviderLogger};
+use crate::llm::types::{
+    LLMError, LLMRequest, LLMResponse, ProviderCapabilities, ProviderConfig, ProviderStatus,
+    RateLimitStatus, TokenUsage,
+};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// Where Ollama listens unless configured otherwise
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Model used when none is configured
+pub const DEFAULT_OLLAMA_MODEL: &str = "llama3.1";
+
+const DEFAULT_CONTEXT_WINDOW: u64 = 8192;
+
+/// Time allowed for listing models and health checks
+const METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Ollama implementation of [`LLMProvider`]
+pub struct OllamaProvider {
+    config: ProviderConfig,
+    base_url: String,
+    http: reqwest::Client,
+    /// Consecutive failed requests, reset by a success
+    failures: AtomicU32,
+}
+
+impl OllamaProvider {
+    pub fn new(config: ProviderConfig) -> Result<Self, LLMError> {
+        let base_url = config
+            .base_url
+            .clone()
+            .or_else(|| std::env::var("OLLAMA_HOST").ok())
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| normalize_base_url(&url))
+            .unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string());
+
+        Ok(Self {
+            config,
+            base_url,
+            http: reqwest::Client::new(),
+            failures: AtomicU32::new(0),
+        })
+    }
+
+    fn model_for(&self, request: &LLMRequest) -> String {
+        [
+            request.model_preference.clone(),
+            self.config.model.clone(),
+            std::env::var("OLLAMA_MODEL").ok(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|model| model.trim().to_string())
+        .find(|model| !model.is_empty() && !model.starts_with("claude"))
+        .unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string())
+    }
+
+    fn context_window(&self) -> u64 {
+        self.config
+            .additional_config
+            .get("context_window")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn execute(
+        &self,
+        request: LLMRequest,
+        logger: &ProviderLogger,
+    ) -> Result<LLMResponse, LLMError> {
+        let start_time = Instant::now();
+        let model = self.model_for(&request);
+        let ctx = LogContext::new(request.id, &model);
+        logger.save_prompt(&ctx, &request.prompt).await.ok();
+
+        let result = self.send(&request, &model).await;
+        match &result {
+            Ok(_) => self.failures.store(0, Ordering::Relaxed),
+            Err(e) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                logger.log_error(&ctx, &e.to_string()).await.ok();
+            }
+        }
+        let json = result?;
+        let (content, token_usage) = parse_chat_response(&json)?;
+
+        let execution_time = start_time.elapsed();
+        logger
+            .log_completion(
+                &ctx,
+                token_usage.input_tokens,
+                token_usage.output_tokens,
+                token_usage.total_tokens,
+                token_usage.estimated_cost,
+                execution_time.as_secs_f64(),
+            )
+            .await
+            .ok();
+
+        let mut provider_metadata = HashMap::new();
+        provider_metadata.insert("mode".to_string(), json!("local"));
+        if let Some(done_reason) = json.get("done_reason") {
+            provider_metadata.insert("done_reason".to_string(), done_reason.clone());
+        }
+        Ok(LLMResponse {
+            request_id: request.id,
+            content,
+            model_used: json["model"].as_str().unwrap_or(&model).to_string(),
+            token_usage,
+            execution_time,
+            provider_metadata,
+        })
+    }
+
+    async fn send(&self, request: &LLMRequest, model: &str) -> Result<Value, LLMError> {
+        let response = self
+            .http
+            .post(self.url("/api/chat"))
+            .json(&chat_body(request, model))
+            .send()
+            .await
+            .map_err(|e| unreachable_error(&self.base_url, &e))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<Value>(&text)
+                .ok()
+                .and_then(|body| body["error"].as_str().map(str::to_string))
+                .unwrap_or(text);
+            return Err(if status.as_u16() == 404 {
+                LLMError::ModelUnavailable(format!(
+                    "{} (pull it with `ollama pull {}`)",
+                    message, model
+                ))
+            } else {
+                LLMError::ProviderSpecific(format!("Ollama returned {}: {}", status, message))
+            });
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| LLMError::ProviderSpecific(format!("Invalid Ollama response: {}", e)))
+    }
+
+    /// Names of the models Ollama has pulled
+    async fn fetch_models(&self) -> Result<Vec<String>, LLMError> {
+        let response = self
+            .http
+            .get(self.url("/api/tags"))
+            .timeout(METADATA_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| unreachable_error(&self.base_url, &e))?;
+        if !response.status().is_success() {
+            return Err(LLMError::ProviderUnavailable(format!(
+                "Ollama at {} returned {} for /api/tags",
+                self.base_url,
+                response.status()
+            )));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| LLMError::ProviderSpecific(format!("Invalid Ollama response: {}", e)))?;
+        Ok(body["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model["name"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// `OLLAMA_HOST` is often a bare `host:port`
+fn normalize_base_url(url: &str) -> String {
+    let url = url.trim();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    }
+}
+
+fn unreachable_error(base_url: &str, e: &reqwest::Error) -> LLMError {
+    if e.is_connect() || e.is_timeout() {
+        LLMError::ProviderUnavailable(format!(
+            "Ollama is not reachable at {} (is `ollama serve` running?): {}",
+            base_url, e
+        ))
+    } else {
+        LLMError::Network(e.to_string())
+    }
+}
+
+/// Non-streaming `/api/chat` request body
+fn chat_body(request: &LLMRequest, model: &str) -> Value {
+    let mut messages = Vec::new();
+    if let Some(system) = request.system_message.as_deref().filter(|s| !s.is_empty()) {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.push(json!({ "role": "user", "content": request.prompt }));
+
+    let mut options = serde_json::Map::new();
+    if let Some(max_tokens) = request.max_tokens {
+        options.insert("num_predict".to_string(), json!(max_tokens));
+    }
+    if let Some(temperature) = request.temperature {
+        options.insert("temperature".to_string(), json!(temperature));
+    }
+
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "stream": false,
+        "options": options,
+    });
+    // Ollama constrains the output to the schema itself
+    if let Some(schema) = &request.response_schema {
+        body["format"] = schema.clone();
+    }
+    body
+}
+
+/// Text and usage from an `/api/chat` response body
+fn parse_chat_response(body: &Value) -> Result<(String, TokenUsage), LLMError> {
+    let content = body["message"]["content"].as_str().ok_or_else(|| {
+        LLMError::ProviderSpecific(format!("Unexpected Ollama response: {}", body))
+    })?;
+    let input_tokens = body["prompt_eval_count"].as_u64().unwrap_or(0);
+    let output_tokens = body["eval_count"].as_u64().unwrap_or(0);
+    Ok((
+        content.to_string(),
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+            estimated_cost: 0.0,
+            ..Default::default()
+        },
+    ))
+}
+
+impl LLMProvider for OllamaProvider {
+    fn execute_request<'a>(
+        &'a self,
+        request: LLMRequest,
+        logger: &'a ProviderLogger,
+    ) -> BoxFuture<'a, Result<LLMResponse, LLMError>> {
+        Box::pin(self.execute(request, logger))
+    }
+
+    fn get_capabilities(&self) -> BoxFuture<'_, Result<ProviderCapabilities, LLMError>> {
+        Box::pin(async move {
+            Ok(ProviderCapabilities {
+                supports_streaming: false,
+                supports_function_calling: false,
+                supports_vision: false,
+                max_context_tokens: self.context_window(),
+                available_models: self.list_models().await?,
+            })
+        })
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<ProviderStatus, LLMError>> {
+        Box::pin(async move {
+            let started = Instant::now();
+            let reachable = self.fetch_models().await.is_ok();
+            let failures = self.failures.load(Ordering::Relaxed);
+            Ok(ProviderStatus {
+                is_healthy: reachable && failures < 5,
+                last_check: Utc::now(),
+                error_count: failures,
+                average_response_time: started.elapsed(),
+                // Local inference has no rate limits
+                rate_limit_status: RateLimitStatus {
+                    requests_remaining: u64::MAX,
+                    tokens_remaining: u64::MAX,
+                    reset_time: None,
+                },
+            })
+        })
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), LLMError>> {
+        Box::pin(async move {
+            let models = self.fetch_models().await?;
+            let model = self.model_for(&LLMRequest::default());
+            // `llama3.1` is served as `llama3.1:latest`
+            let pulled = models
+                .iter()
+                .any(|name| *name == model || name.strip_suffix(":latest") == Some(&model));
+            if pulled {
+                Ok(())
+            } else {
+                Err(LLMError::ModelUnavailable(format!(
+                    "Ollama has not pulled '{}' (run `ollama pull {}`)",
+                    model, model
+                )))
+            }
+        })
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>, LLMError>> {
+        Box::pin(self.fetch_models())
+    }
+
+    fn estimate_tokens(&self, text: &str) -> u64 {
+        crate::claude::token_breakdown::estimate_tokens(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one HTTP request per response body, in order
+    async fn serve(bodies: Vec<(u16, Value)>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for (status, body) in bodies {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 64 * 1024];
+                let _ = stream.read(&mut buffer).await.unwrap();
+                let body = body.to_string();
+                let response = format!(
+                    "HTTP/1.1 {} OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    fn config(base_url: String, model: Option<&str>) -> ProviderConfig {
+        ProviderConfig {
+            provider_type: crate::llm::ProviderType::LocalModel,
+            base_url: Some(base_url),
+            model: model.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_request_and_model_listing() {
+        let tags =
+            json!({ "models": [{ "name": "qwen2.5-coder:7b" }, { "name": "llama3.1:latest" }] });
+        let chat = json!({
+            "model": "qwen2.5-coder:7b",
+            "message": { "role": "assistant", "content": "fn main() {}" },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 5
+        });
+        let base_url = serve(vec![(200, tags), (200, chat)]).await;
+        let provider = crate::llm::provider::LLMProviderFactory::create_provider(
+            config(base_url, Some("qwen2.5-coder:7b")),
+            std::path::PathBuf::from("."),
+        )
+        .await
+        .unwrap();
+        assert_eq!(provider.provider_name(), "ollama");
+        assert_eq!(
+            provider.list_models().await.unwrap(),
+            vec!["qwen2.5-coder:7b", "llama3.1:latest"]
+        );
+
+        let logs = tempfile::tempdir().unwrap();
+        let logger = crate::llm::provider_logger::ProviderLoggerBuilder::new("ollama")
+            .logs_dir(logs.path().to_path_buf())
+            .build()
+            .await
+            .unwrap();
+        let response = provider
+            .execute_request(
+                LLMRequest {
+                    prompt: "Write main".to_string(),
+                    ..Default::default()
+                },
+                &logger,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.content, "fn main() {}");
+        assert_eq!(response.token_usage.total_tokens, 17);
+        assert_eq!(response.token_usage.estimated_cost, 0.0);
+        assert!(provider.estimate_tokens("fn main() {}") > 0);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_requires_a_pulled_model() {
+        let tags = json!({ "models": [{ "name": "llama3.1:latest" }] });
+        let base_url = serve(vec![(200, tags.clone()), (200, tags)]).await;
+
+        // The default provider config names a Claude alias, which is ignored
+        let provider =
+            OllamaProvider::new(config(base_url.clone(), Some("claude-sonnet"))).unwrap();
+        assert!(provider.health_check().await.is_ok());
+
+        let provider = OllamaProvider::new(config(base_url, Some("mistral"))).unwrap();
+        assert!(matches!(
+            provider.health_check().await,
+            Err(LLMError::ModelUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_chat_body_and_base_url() {
+        let body = chat_body(
+            &LLMRequest {
+                prompt: "Hi".to_string(),
+                system_message: Some("Be brief".to_string()),
+                max_tokens: Some(256),
+                response_schema: Some(json!({ "type": "object" })),
+                ..Default::default()
+            },
+            "llama3.1",
+        );
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "Hi");
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert_eq!(body["format"]["type"], "object");
+        assert_eq!(body["stream"], false);
+
+        assert_eq!(normalize_base_url("0.0.0.0:11434"), "http://0.0.0.0:11434");
+        assert_eq!(
+            normalize_base_url("https://ollama.internal"),
+            "https://ollama.internal"
+        );
+    }
+}
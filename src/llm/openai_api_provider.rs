@@ -0,0 +1,848 @@
+//! Native OpenAI API provider
+//!
+//! Implements [`LLMProvider`] over HTTP against the Chat Completions or
+//! Responses API, without the Codex CLI. Any service speaking the same
+//! protocol works through `base_url`: Azure OpenAI deployments, OpenRouter,
+//! vLLM and similar gateways. Selected with `--provider openai-api` (alias
+//! `openai`) or [`ProviderType::OpenAIApi`](super::ProviderType::OpenAIApi).
+//!
+//! ## Configuration
+//!
+//! - `api_key`, or the `OPENAI_API_KEY` environment variable
+//! - `base_url`, or `OPENAI_BASE_URL` (default `https://api.openai.com/v1`)
+//! - `model`, or `OPENAI_MODEL` (default `gpt-5`); the Claude aliases the
+//!   default provider configuration names are ignored
+//! - `rate_limits`: enforced with the same [`RateLimiter`] as the Claude paths
+//! - `additional_config["api"]`: `"chat_completions"` (default) or `"responses"`
+//! - `additional_config["api_version"]`: Azure's `api-version`; when set the
+//!   key is sent in the `api-key` header instead of as a bearer token
+//! - `additional_config["tools"]`: function tools in Chat Completions form;
+//!   the calls the model makes are returned in the response metadata
+//! - `additional_config["pricing"]`: `{ input, cached_input, output }` in
+//!   dollars per million tokens, for models missing from the built-in table
+//!
+//! Costs are estimated per model like the Claude usage tracker does,
+//! counting prompt tokens served from OpenAI's automatic prompt cache at
+//! the cached input price.
+
+use crate::claude::RateLimiter;
+use crate::llm::provider::LLMProvider;
+use crate::llm::provider_logger::{LogContext, ProviderLogger};
+use crate::llm::types::{
+    LLMError, LLMRequest, LLMResponse, ProviderCapabilities, ProviderConfig, ProviderStatus,
+    RateLimitStatus, TokenUsage,
+};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+
+/// Model used when none is configured
+pub const DEFAULT_OPENAI_MODEL: &str = "gpt-5";
+
+/// Time allowed for listing models
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Dollars per million tokens: model prefix, input, cached input, output.
+/// More specific prefixes come first.
+const MODEL_PRICES: &[(&str, f64, f64, f64)] = &[
+    ("gpt-5-nano", 0.05, 0.005, 0.40),
+    ("gpt-5-mini", 0.25, 0.025, 2.00),
+    ("gpt-5", 1.25, 0.125, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.025, 0.40),
+    ("gpt-4.1-mini", 0.40, 0.10, 1.60),
+    ("gpt-4.1", 2.00, 0.50, 8.00),
+    ("gpt-4o-mini", 0.15, 0.075, 0.60),
+    ("gpt-4o", 2.50, 1.25, 10.00),
+    ("o4-mini", 1.10, 0.275, 4.40),
+    ("o3", 2.00, 0.50, 8.00),
+];
+
+/// Which OpenAI endpoint requests go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenAIApi {
+    /// `POST /chat/completions`
+    ChatCompletions,
+    /// `POST /responses`
+    Responses,
+}
+
+/// Token prices for a model, in dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub cached_input: f64,
+    pub output: f64,
+}
+
+impl ModelPricing {
+    /// Built-in prices for `model`, matched by prefix; a router prefix such
+    /// as `openai/` is ignored
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.rsplit('/').next().unwrap_or(model);
+        MODEL_PRICES
+            .iter()
+            .find(|(prefix, ..)| model.starts_with(prefix))
+            .map(|&(_, input, cached_input, output)| Self {
+                input,
+                cached_input,
+                output,
+            })
+    }
+
+    /// Estimated cost of uncached input, cached input and output tokens
+    pub fn cost(&self, input_tokens: u64, cached_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input
+            + cached_tokens as f64 * self.cached_input
+            + output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+
+    /// What cached input saved over paying the full input price
+    pub fn cache_savings(&self, cached_tokens: u64) -> f64 {
+        cached_tokens as f64 * (self.input - self.cached_input) / 1_000_000.0
+    }
+}
+
+/// A function call the model asked for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// Parsed arguments, or the raw string when they are not valid JSON
+    pub arguments: Value,
+}
+
+/// Direct OpenAI API implementation of [`LLMProvider`]
+pub struct OpenAIApiProvider {
+    config: ProviderConfig,
+    api_key: String,
+    base_url: String,
+    api: OpenAIApi,
+    http: reqwest::Client,
+    rate_limiter: RateLimiter,
+}
+
+impl OpenAIApiProvider {
+    pub fn new(config: ProviderConfig) -> Result<Self, LLMError> {
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| {
+                LLMError::Authentication(
+                    "The OpenAI API provider needs an API key. Set OPENAI_API_KEY or the provider's api_key.".to_string(),
+                )
+            })?;
+        let base_url = config
+            .base_url
+            .clone()
+            .or_else(|| std::env::var("OPENAI_BASE_URL").ok())
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_OPENAI_URL.to_string());
+        let api = match config.additional_config.get("api").and_then(|v| v.as_str()) {
+            None | Some("chat_completions") => OpenAIApi::ChatCompletions,
+            Some("responses") => OpenAIApi::Responses,
+            Some(other) => {
+                return Err(LLMError::InvalidRequest(format!(
+                    "Unknown OpenAI API '{}' (expected chat_completions or responses)",
+                    other
+                )));
+            }
+        };
+        let rate_limiter = RateLimiter::new(crate::claude::RateLimitConfig {
+            max_tokens_per_minute: config.rate_limits.max_tokens_per_minute,
+            max_requests_per_minute: config.rate_limits.max_requests_per_minute,
+            burst_allowance: config.rate_limits.burst_allowance,
+            backoff_multiplier: 2.0,
+            max_backoff_delay: Duration::from_secs(600),
+        });
+
+        Ok(Self {
+            config,
+            api_key,
+            base_url,
+            api,
+            http: reqwest::Client::new(),
+            rate_limiter,
+        })
+    }
+
+    fn model_for(&self, request: &LLMRequest) -> String {
+        [
+            request.model_preference.clone(),
+            self.config.model.clone(),
+            std::env::var("OPENAI_MODEL").ok(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|model| model.trim().to_string())
+        .find(|model| !model.is_empty() && !model.starts_with("claude"))
+        .unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string())
+    }
+
+    /// Configured prices, falling back to the built-in table
+    fn pricing(&self, model: &str) -> Option<ModelPricing> {
+        self.config
+            .additional_config
+            .get("pricing")
+            .and_then(|pricing| serde_json::from_value(pricing.clone()).ok())
+            .or_else(|| ModelPricing::for_model(model))
+    }
+
+    /// Azure's `api-version` (`additional_config["api_version"]`)
+    fn api_version(&self) -> Option<&str> {
+        self.config
+            .additional_config
+            .get("api_version")
+            .and_then(|v| v.as_str())
+    }
+
+    fn tools(&self) -> Option<&[Value]> {
+        self.config
+            .additional_config
+            .get("tools")
+            .and_then(|v| v.as_array())
+            .map(Vec::as_slice)
+            .filter(|tools| !tools.is_empty())
+    }
+
+    fn request_builder(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), path);
+        let builder = self.http.request(method, url);
+        match self.api_version() {
+            Some(version) => builder
+                .query(&[("api-version", version)])
+                .header("api-key", &self.api_key),
+            None => builder.bearer_auth(&self.api_key),
+        }
+    }
+
+    async fn execute(
+        &self,
+        request: LLMRequest,
+        logger: &ProviderLogger,
+    ) -> Result<LLMResponse, LLMError> {
+        let start_time = Instant::now();
+        let model = self.model_for(&request);
+        let ctx = LogContext::new(request.id, &model);
+        logger.save_prompt(&ctx, &request.prompt).await.ok();
+
+        self.rate_limiter
+            .acquire_permit(&crate::claude::TaskRequest {
+                id: request.id,
+                task_type: "llm_request".to_string(),
+                description: String::new(),
+                context: HashMap::new(),
+                priority: crate::claude::TaskPriority::Medium,
+                estimated_tokens: request.max_tokens,
+                system_message: None,
+                tool_permissions: Default::default(),
+            })
+            .await
+            .map_err(|e| match e {
+                crate::claude::ClaudeError::RateLimit {
+                    message,
+                    reset_time,
+                } => LLMError::RateLimit {
+                    message,
+                    reset_time: Some(reset_time),
+                },
+                other => LLMError::ProviderSpecific(other.to_string()),
+            })?;
+
+        let result = self.send(&request, &model).await;
+        match &result {
+            Ok(_) => self.rate_limiter.record_success().await,
+            Err(e) => {
+                self.rate_limiter.record_failure().await;
+                logger.log_error(&ctx, &e.to_string()).await.ok();
+            }
+        }
+        let json = result?;
+        let parsed = match self.api {
+            OpenAIApi::ChatCompletions => parse_chat_response(&json)?,
+            OpenAIApi::Responses => parse_responses_response(&json)?,
+        };
+        let model_used = json["model"].as_str().unwrap_or(&model).to_string();
+        let token_usage = parsed.token_usage(self.pricing(&model_used).as_ref());
+
+        let execution_time = start_time.elapsed();
+        logger
+            .log_completion(
+                &ctx,
+                token_usage.input_tokens,
+                token_usage.output_tokens,
+                token_usage.total_tokens,
+                token_usage.estimated_cost,
+                execution_time.as_secs_f64(),
+            )
+            .await
+            .ok();
+
+        if parsed.finish_reason == "length" || parsed.finish_reason == "max_output_tokens" {
+            tracing::warn!(
+                "OpenAI API response for request {} was cut off at the token limit",
+                request.id
+            );
+        }
+
+        let mut provider_metadata = HashMap::new();
+        provider_metadata.insert("mode".to_string(), json!("API"));
+        provider_metadata.insert("finish_reason".to_string(), json!(parsed.finish_reason));
+        if !parsed.tool_calls.is_empty() {
+            provider_metadata.insert("tool_calls".to_string(), json!(parsed.tool_calls));
+        }
+        Ok(LLMResponse {
+            request_id: request.id,
+            content: parsed.content,
+            model_used,
+            token_usage,
+            execution_time,
+            provider_metadata,
+        })
+    }
+
+    async fn send(&self, request: &LLMRequest, model: &str) -> Result<Value, LLMError> {
+        let (path, body) = match self.api {
+            OpenAIApi::ChatCompletions => {
+                ("/chat/completions", chat_body(request, model, self.tools()))
+            }
+            OpenAIApi::Responses => ("/responses", responses_body(request, model, self.tools())),
+        };
+        let response = self
+            .request_builder(reqwest::Method::POST, path)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), &text));
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| LLMError::ProviderSpecific(format!("Invalid OpenAI API response: {}", e)))
+    }
+
+    /// Model ids the endpoint serves
+    async fn fetch_models(&self) -> Result<Vec<String>, LLMError> {
+        let response = self
+            .request_builder(reqwest::Method::GET, "/models")
+            .timeout(METADATA_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LLMError::Network(e.to_string()))?;
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), &text));
+        }
+        let body: Value = serde_json::from_str(&text).map_err(|e| {
+            LLMError::ProviderSpecific(format!("Invalid OpenAI API response: {}", e))
+        })?;
+        Ok(body["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// Request body for `POST /chat/completions`
+fn chat_body(request: &LLMRequest, model: &str, tools: Option<&[Value]>) -> Value {
+    let mut messages = Vec::new();
+    if let Some(system) = request.system_message.as_deref().filter(|s| !s.is_empty()) {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.push(json!({ "role": "user", "content": request.prompt }));
+
+    let mut body = json!({ "model": model, "messages": messages });
+    if let Some(max_tokens) = request.max_tokens {
+        body["max_completion_tokens"] = json!(max_tokens);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(tools) = tools {
+        body["tools"] = json!(tools);
+    }
+    if let Some(schema) = &request.response_schema {
+        body["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": { "name": "response", "schema": schema },
+        });
+    }
+    body
+}
+
+/// Request body for `POST /responses`
+fn responses_body(request: &LLMRequest, model: &str, tools: Option<&[Value]>) -> Value {
+    let mut body = json!({ "model": model, "input": request.prompt });
+    if let Some(system) = request.system_message.as_deref().filter(|s| !s.is_empty()) {
+        body["instructions"] = json!(system);
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        body["max_output_tokens"] = json!(max_tokens);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(tools) = tools {
+        // The Responses API takes the function fields at the top level
+        let tools: Vec<Value> = tools
+            .iter()
+            .map(|tool| match tool.get("function") {
+                Some(Value::Object(function)) => {
+                    let mut flat = function.clone();
+                    flat.insert("type".to_string(), json!("function"));
+                    Value::Object(flat)
+                }
+                _ => tool.clone(),
+            })
+            .collect();
+        body["tools"] = json!(tools);
+    }
+    if let Some(schema) = &request.response_schema {
+        body["text"] = json!({
+            "format": { "type": "json_schema", "name": "response", "schema": schema },
+        });
+    }
+    body
+}
+
+/// What a response body holds, before pricing
+#[derive(Debug, Default)]
+struct ParsedResponse {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: String,
+    /// Prompt tokens, including cached ones
+    prompt_tokens: u64,
+    cached_tokens: u64,
+    output_tokens: u64,
+}
+
+impl ParsedResponse {
+    fn token_usage(&self, pricing: Option<&ModelPricing>) -> TokenUsage {
+        let input_tokens = self.prompt_tokens.saturating_sub(self.cached_tokens);
+        TokenUsage {
+            input_tokens,
+            output_tokens: self.output_tokens,
+            total_tokens: self.prompt_tokens + self.output_tokens,
+            estimated_cost: pricing
+                .map(|p| p.cost(input_tokens, self.cached_tokens, self.output_tokens))
+                .unwrap_or(0.0),
+            cache_read_tokens: self.cached_tokens,
+            cache_creation_tokens: 0,
+            cache_savings: pricing
+                .map(|p| p.cache_savings(self.cached_tokens))
+                .unwrap_or(0.0),
+        }
+    }
+}
+
+/// Text, tool calls and usage from a `/chat/completions` response body
+fn parse_chat_response(body: &Value) -> Result<ParsedResponse, LLMError> {
+    let choice = body["choices"].get(0).ok_or_else(|| {
+        LLMError::ProviderSpecific(format!("Unexpected OpenAI API response: {}", body))
+    })?;
+    let message = &choice["message"];
+    let tool_calls = message["tool_calls"]
+        .as_array()
+        .map(|calls| {
+            calls
+                .iter()
+                .map(|call| {
+                    tool_call(
+                        &call["id"],
+                        &call["function"]["name"],
+                        &call["function"]["arguments"],
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let usage = &body["usage"];
+    Ok(ParsedResponse {
+        content: message["content"].as_str().unwrap_or_default().to_string(),
+        tool_calls,
+        finish_reason: choice["finish_reason"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        prompt_tokens: usage["prompt_tokens"].as_u64().unwrap_or(0),
+        cached_tokens: usage["prompt_tokens_details"]["cached_tokens"]
+            .as_u64()
+            .unwrap_or(0),
+        output_tokens: usage["completion_tokens"].as_u64().unwrap_or(0),
+    })
+}
+
+/// Text, tool calls and usage from a `/responses` response body
+fn parse_responses_response(body: &Value) -> Result<ParsedResponse, LLMError> {
+    let output = body["output"].as_array().ok_or_else(|| {
+        LLMError::ProviderSpecific(format!("Unexpected OpenAI API response: {}", body))
+    })?;
+    let mut parsed = ParsedResponse::default();
+    for item in output {
+        match item["type"].as_str() {
+            Some("message") => {
+                for part in item["content"].as_array().into_iter().flatten() {
+                    if let Some(text) = part["text"].as_str() {
+                        parsed.content.push_str(text);
+                    }
+                }
+            }
+            Some("function_call") => parsed.tool_calls.push(tool_call(
+                &item["call_id"],
+                &item["name"],
+                &item["arguments"],
+            )),
+            _ => {}
+        }
+    }
+
+    parsed.finish_reason = match body["status"].as_str() {
+        Some("incomplete") => body["incomplete_details"]["reason"]
+            .as_str()
+            .unwrap_or("incomplete")
+            .to_string(),
+        status => status.unwrap_or_default().to_string(),
+    };
+    let usage = &body["usage"];
+    parsed.prompt_tokens = usage["input_tokens"].as_u64().unwrap_or(0);
+    parsed.cached_tokens = usage["input_tokens_details"]["cached_tokens"]
+        .as_u64()
+        .unwrap_or(0);
+    parsed.output_tokens = usage["output_tokens"].as_u64().unwrap_or(0);
+    Ok(parsed)
+}
+
+/// Arguments arrive as a JSON-encoded string
+fn tool_call(id: &Value, name: &Value, arguments: &Value) -> ToolCall {
+    let arguments = match arguments {
+        Value::String(raw) => serde_json::from_str(raw).unwrap_or_else(|_| json!(raw)),
+        other => other.clone(),
+    };
+    ToolCall {
+        id: id.as_str().unwrap_or_default().to_string(),
+        name: name.as_str().unwrap_or_default().to_string(),
+        arguments,
+    }
+}
+
+/// Map an API error status and body to an [`LLMError`]
+fn api_error(status: u16, body: &str) -> LLMError {
+    let error = serde_json::from_str::<Value>(body)
+        .ok()
+        .map(|json| json["error"].clone())
+        .unwrap_or_default();
+    let message = error["message"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| body.to_string());
+    if matches!(status, 400 | 413)
+        && (error["code"] == "context_length_exceeded"
+            || crate::claude::is_context_overflow(&message))
+        && let Some((first, second)) = crate::claude::downscope::overflow_token_counts(&message)
+    {
+        // OpenAI states the maximum before the prompt size
+        return LLMError::ContextTooLarge {
+            current: first.max(second),
+            max: first.min(second),
+        };
+    }
+    match status {
+        401 | 403 => LLMError::Authentication(message),
+        404 if error["code"] == "model_not_found" => LLMError::ModelUnavailable(message),
+        400 | 404 | 422 => LLMError::InvalidRequest(message),
+        413 => LLMError::InvalidRequest(format!("Request too large: {}", message)),
+        429 => LLMError::RateLimit {
+            message,
+            reset_time: None,
+        },
+        500..=599 => LLMError::ProviderUnavailable(message),
+        _ => LLMError::ProviderSpecific(format!("HTTP {}: {}", status, message)),
+    }
+}
+
+impl LLMProvider for OpenAIApiProvider {
+    fn execute_request<'a>(
+        &'a self,
+        request: LLMRequest,
+        logger: &'a ProviderLogger,
+    ) -> BoxFuture<'a, Result<LLMResponse, LLMError>> {
+        Box::pin(self.execute(request, logger))
+    }
+
+    fn get_capabilities(&self) -> BoxFuture<'_, Result<ProviderCapabilities, LLMError>> {
+        Box::pin(async move {
+            Ok(ProviderCapabilities {
+                supports_streaming: false,
+                supports_function_calling: true,
+                supports_vision: true,
+                max_context_tokens: 128000,
+                available_models: self.list_models().await?,
+            })
+        })
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<ProviderStatus, LLMError>> {
+        Box::pin(async move {
+            let limiter = self.rate_limiter.get_status().await;
+            Ok(ProviderStatus {
+                is_healthy: limiter.failure_count < 5,
+                last_check: Utc::now(),
+                error_count: limiter.failure_count,
+                average_response_time: Duration::from_millis(500),
+                rate_limit_status: RateLimitStatus {
+                    requests_remaining: limiter.available_requests as u64,
+                    tokens_remaining: limiter.available_tokens,
+                    reset_time: limiter.last_failure,
+                },
+            })
+        })
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), LLMError>> {
+        Box::pin(async move {
+            let status = self.get_status().await?;
+            if status.is_healthy {
+                Ok(())
+            } else {
+                Err(LLMError::ProviderUnavailable(
+                    "OpenAI API provider has failed repeatedly".to_string(),
+                ))
+            }
+        })
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "openai-api"
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>, LLMError>> {
+        Box::pin(self.fetch_models())
+    }
+
+    fn estimate_tokens(&self, text: &str) -> u64 {
+        crate::claude::token_breakdown::estimate_tokens(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one HTTP request with `body`, returning the request received
+    async fn serve_once(body: Value) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 64 * 1024];
+            let read = stream.read(&mut buffer).await.unwrap();
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buffer[..read]).into_owned()
+        });
+        (format!("http://{}/v1", address), handle)
+    }
+
+    fn config(base_url: String, additional: Value) -> ProviderConfig {
+        ProviderConfig {
+            provider_type: crate::llm::ProviderType::OpenAIApi,
+            api_key: Some("sk-test".to_string()),
+            base_url: Some(base_url),
+            model: Some("gpt-4.1".to_string()),
+            additional_config: serde_json::from_value(additional).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    async fn logger() -> (tempfile::TempDir, ProviderLogger) {
+        let logs = tempfile::tempdir().unwrap();
+        let logger = crate::llm::provider_logger::ProviderLoggerBuilder::new("openai-api")
+            .logs_dir(logs.path().to_path_buf())
+            .build()
+            .await
+            .unwrap();
+        (logs, logger)
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_with_tool_calls_and_cost() {
+        let (base_url, received) = serve_once(json!({
+            "model": "gpt-4.1-2025-04-14",
+            "choices": [{
+                "finish_reason": "tool_calls",
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "run_tests", "arguments": "{\"filter\":\"parser\"}" }
+                    }]
+                }
+            }],
+            "usage": {
+                "prompt_tokens": 1000,
+                "completion_tokens": 100,
+                "prompt_tokens_details": { "cached_tokens": 400 }
+            }
+        }))
+        .await;
+        let tools =
+            json!([{ "type": "function", "function": { "name": "run_tests", "parameters": {} } }]);
+        let provider = crate::llm::provider::LLMProviderFactory::create_provider(
+            config(base_url, json!({ "tools": tools })),
+            std::path::PathBuf::from("."),
+        )
+        .await
+        .unwrap();
+        assert_eq!(provider.provider_name(), "openai-api");
+
+        let (_logs, logger) = logger().await;
+        let response = provider
+            .execute_request(
+                LLMRequest {
+                    prompt: "Run the parser tests".to_string(),
+                    ..Default::default()
+                },
+                &logger,
+            )
+            .await
+            .unwrap();
+        let request = received.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(request.contains("authorization: Bearer sk-test"));
+
+        assert_eq!(response.content, "");
+        assert_eq!(response.model_used, "gpt-4.1-2025-04-14");
+        assert_eq!(
+            response.provider_metadata["tool_calls"],
+            json!([{ "id": "call_1", "name": "run_tests", "arguments": { "filter": "parser" } }])
+        );
+        let usage = &response.token_usage;
+        assert_eq!((usage.input_tokens, usage.cache_read_tokens), (600, 400));
+        assert_eq!(usage.total_tokens, 1100);
+        // 600 * $2 + 400 * $0.50 + 100 * $8 per million
+        assert!((usage.estimated_cost - 0.0022).abs() < 1e-9);
+        assert!((usage.cache_savings - 0.0006).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_responses_api_on_azure() {
+        let (base_url, received) = serve_once(json!({
+            "model": "gpt-5-mini",
+            "status": "completed",
+            "output": [
+                { "type": "reasoning", "summary": [] },
+                { "type": "message", "content": [{ "type": "output_text", "text": "Done." }] }
+            ],
+            "usage": { "input_tokens": 50, "output_tokens": 10 }
+        }))
+        .await;
+        let provider = OpenAIApiProvider::new(config(
+            base_url,
+            json!({ "api": "responses", "api_version": "2025-04-01-preview" }),
+        ))
+        .unwrap();
+        let (_logs, logger) = logger().await;
+        let response = provider
+            .execute_request(
+                LLMRequest {
+                    prompt: "Summarize".to_string(),
+                    system_message: Some("Be brief".to_string()),
+                    ..Default::default()
+                },
+                &logger,
+            )
+            .await
+            .unwrap();
+        let request = received.await.unwrap();
+        assert!(request.starts_with("POST /v1/responses?api-version=2025-04-01-preview"));
+        assert!(request.contains("api-key: sk-test"));
+        assert!(request.contains("\"instructions\":\"Be brief\""));
+
+        assert_eq!(response.content, "Done.");
+        assert_eq!(response.provider_metadata["finish_reason"], "completed");
+        assert!(!response.provider_metadata.contains_key("tool_calls"));
+        assert!(response.token_usage.estimated_cost > 0.0);
+    }
+
+    #[test]
+    fn test_pricing_errors_and_request_bodies() {
+        let pricing = ModelPricing::for_model("openai/gpt-5-mini-2025-08-07").unwrap();
+        assert_eq!(pricing.output, 2.00);
+        assert_eq!(ModelPricing::for_model("gpt-5").unwrap().input, 1.25);
+        assert_eq!(ModelPricing::for_model("llama-3-70b"), None);
+
+        assert!(matches!(
+            api_error(
+                400,
+                r#"{"error":{"message":"This model's maximum context length is 128000 tokens. However, your messages resulted in 130500 tokens.","code":"context_length_exceeded"}}"#
+            ),
+            LLMError::ContextTooLarge {
+                current: 130500,
+                max: 128000
+            }
+        ));
+        assert!(matches!(
+            api_error(401, r#"{"error":{"message":"Incorrect API key"}}"#),
+            LLMError::Authentication(message) if message == "Incorrect API key"
+        ));
+        assert!(matches!(
+            api_error(429, "slow down"),
+            LLMError::RateLimit { .. }
+        ));
+
+        let request = LLMRequest {
+            prompt: "Hi".to_string(),
+            max_tokens: Some(256),
+            response_schema: Some(json!({ "type": "object" })),
+            ..Default::default()
+        };
+        let chat = chat_body(&request, "gpt-4.1", None);
+        assert_eq!(chat["max_completion_tokens"], 256);
+        assert_eq!(
+            chat["response_format"]["json_schema"]["schema"]["type"],
+            "object"
+        );
+
+        let tools =
+            [json!({ "type": "function", "function": { "name": "edit", "parameters": {} } })];
+        let responses = responses_body(&request, "gpt-5", Some(&tools));
+        assert_eq!(responses["max_output_tokens"], 256);
+        assert_eq!(
+            responses["tools"][0],
+            json!({ "type": "function", "name": "edit", "parameters": {} })
+        );
+        assert_eq!(responses["text"]["format"]["type"], "json_schema");
+    }
+}
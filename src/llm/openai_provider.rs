@@ -204,6 +204,7 @@ impl LLMProvider for OpenAIProvider {
                     output_tokens: response.token_usage.completion_tokens,
                     total_tokens: response.token_usage.total_tokens,
                     estimated_cost: response.token_usage.estimated_cost,
+                    ..Default::default()
                 },
                 execution_time: response.execution_time,
                 provider_metadata,
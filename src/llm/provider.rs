@@ -59,18 +59,15 @@ impl LLMProviderFactory {
             crate::llm::types::ProviderType::OpenAICodex => Ok(Arc::new(
                 crate::llm::openai_provider::OpenAIProvider::new(config, workspace_root).await?,
             )),
-            crate::llm::types::ProviderType::Anthropic => {
-                // TODO: Implement direct Anthropic API provider
-                Err(LLMError::ProviderUnavailable(
-                    "Anthropic provider not yet implemented".to_string(),
-                ))
-            }
-            crate::llm::types::ProviderType::LocalModel => {
-                // TODO: Implement local model provider (e.g., Ollama)
-                Err(LLMError::ProviderUnavailable(
-                    "Local model provider not yet implemented".to_string(),
-                ))
-            }
+            crate::llm::types::ProviderType::OpenAIApi => Ok(Arc::new(
+                crate::llm::openai_api_provider::OpenAIApiProvider::new(config)?,
+            )),
+            crate::llm::types::ProviderType::Anthropic => Ok(Arc::new(
+                crate::llm::anthropic_provider::AnthropicApiProvider::new(config)?,
+            )),
+            crate::llm::types::ProviderType::LocalModel => Ok(Arc::new(
+                crate::llm::ollama_provider::OllamaProvider::new(config)?,
+            )),
             crate::llm::types::ProviderType::Custom(name) => Err(LLMError::ProviderUnavailable(
                 format!("Custom provider '{}' not implemented", name),
             )),
@@ -7,8 +7,9 @@
 //! ## Features
 //!
 //! - **Standardized file naming**: `{provider}-{timestamp}-{request-id}.*`
-//! - **Complete audit trail**: 5 files per execution
+//! - **Complete audit trail**: 6 files per execution
 //!   - `.log`: Human-readable summary with timestamps
+//!   - `.prompt.md`: Prompt as sent, including replayed history
 //!   - `.stdout.json`: Full JSON/JSONL output (no truncation)
 //!   - `.stderr.txt`: Error output
 //!   - `.command.sh`: Reproducible command for debugging
@@ -66,6 +67,14 @@ use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
+/// Extensions of the files written for each request
+pub const LOG_EXTENSION: &str = "log";
+pub const PROMPT_EXTENSION: &str = "prompt.md";
+pub const STDOUT_EXTENSION: &str = "stdout.json";
+pub const STDERR_EXTENSION: &str = "stderr.txt";
+pub const COMMAND_EXTENSION: &str = "command.sh";
+pub const TOOLS_EXTENSION: &str = "tools.json";
+
 /// Configuration for provider logging behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderLoggerConfig {
@@ -182,27 +191,32 @@ impl ProviderLogger {
 
     /// Get path to the main log file.
     fn log_file_path(&self, ctx: &LogContext) -> PathBuf {
-        self.base_path(ctx).with_extension("log")
+        self.base_path(ctx).with_extension(LOG_EXTENSION)
+    }
+
+    /// Get path to the prompt file.
+    fn prompt_file_path(&self, ctx: &LogContext) -> PathBuf {
+        self.base_path(ctx).with_extension(PROMPT_EXTENSION)
     }
 
     /// Get path to the stdout file.
     fn stdout_file_path(&self, ctx: &LogContext) -> PathBuf {
-        self.base_path(ctx).with_extension("stdout.json")
+        self.base_path(ctx).with_extension(STDOUT_EXTENSION)
     }
 
     /// Get path to the stderr file.
     fn stderr_file_path(&self, ctx: &LogContext) -> PathBuf {
-        self.base_path(ctx).with_extension("stderr.txt")
+        self.base_path(ctx).with_extension(STDERR_EXTENSION)
     }
 
     /// Get path to the command script file.
     fn command_file_path(&self, ctx: &LogContext) -> PathBuf {
-        self.base_path(ctx).with_extension("command.sh")
+        self.base_path(ctx).with_extension(COMMAND_EXTENSION)
     }
 
     /// Get path to the tools file.
     fn tools_file_path(&self, ctx: &LogContext) -> PathBuf {
-        self.base_path(ctx).with_extension("tools.json")
+        self.base_path(ctx).with_extension(TOOLS_EXTENSION)
     }
 
     /// Log command execution start.
@@ -268,6 +282,15 @@ impl ProviderLogger {
         Ok(())
     }
 
+    /// Save the prompt sent to the provider.
+    pub async fn save_prompt(&self, ctx: &LogContext, prompt: &str) -> Result<(), std::io::Error> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        fs::write(self.prompt_file_path(ctx), prompt).await
+    }
+
     /// Save full stdout output.
     pub async fn save_stdout(&self, ctx: &LogContext, stdout: &[u8]) -> Result<(), std::io::Error> {
         if !self.config.enabled || stdout.is_empty() {
@@ -412,6 +435,26 @@ impl ProviderLogger {
         }
     }
 
+    /// Provider name used in log file names.
+    pub fn provider_name(&self) -> &str {
+        &self.provider_name
+    }
+
+    /// Files written so far for a request.
+    pub fn request_files(&self, ctx: &LogContext) -> Vec<PathBuf> {
+        [
+            self.log_file_path(ctx),
+            self.prompt_file_path(ctx),
+            self.stdout_file_path(ctx),
+            self.stderr_file_path(ctx),
+            self.command_file_path(ctx),
+            self.tools_file_path(ctx),
+        ]
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect()
+    }
+
     /// Check if logging is enabled.
     pub fn is_enabled(&self) -> bool {
         self.config.enabled
@@ -0,0 +1,303 @@
+//! Per-task provider and model selection.
+//!
+//! [`ProviderRouter`] decides which provider and model run a task. A task's
+//! own [`ProviderPreference`] wins; then a provider chosen for the session,
+//! such as with `provider <name>` at the interactive prompt; otherwise the
+//! first matching [`RoutingRule`] applies, e.g. a cheap model for trivial
+//! tasks and Opus for epics. Tasks nothing matches run on the Claude Code CLI
+//! as before. Routes
+//! to other providers are executed through their [`LLMProvider`], created
+//! once and reused.
+
+use crate::llm::provider::{LLMProvider, LLMProviderFactory};
+use crate::llm::provider_logger::{ProviderLogger, ProviderLoggerConfig};
+use crate::llm::types::{LLMError, LLMRequest, ProviderConfig, ProviderType};
+use crate::task::{ComplexityLevel, ProviderPreference, Task, TaskMetadata, TaskStatus};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+/// Provider routing settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// Rules tried in order for tasks without a provider preference
+    pub rules: Vec<RoutingRule>,
+}
+
+/// Provider and model for tasks matching a complexity level or tag
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingRule {
+    /// Complexity levels the rule applies to; empty matches any
+    pub complexity: Vec<ComplexityLevel>,
+    /// Tags of which a task must carry at least one; empty matches any
+    pub tags: Vec<String>,
+    /// Provider to use; the Claude Code CLI when unset
+    pub provider: Option<ProviderType>,
+    pub model: Option<String>,
+}
+
+impl RoutingRule {
+    pub fn matches(&self, metadata: &TaskMetadata) -> bool {
+        let complexity = self.complexity.is_empty()
+            || metadata
+                .estimated_complexity
+                .as_ref()
+                .is_some_and(|level| self.complexity.contains(level));
+        let tags = self.tags.is_empty() || metadata.tags.iter().any(|tag| self.tags.contains(tag));
+        complexity && tags
+    }
+}
+
+/// Where a task runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub provider: ProviderType,
+    /// Model for the provider; its default when unset
+    pub model: Option<String>,
+}
+
+impl Route {
+    fn new(provider: Option<ProviderType>, model: Option<String>) -> Self {
+        Self {
+            provider: provider.unwrap_or(ProviderType::ClaudeCode),
+            model,
+        }
+    }
+
+    /// Whether the task runs on the Claude Code CLI interface
+    pub fn is_claude_code(&self) -> bool {
+        self.provider == ProviderType::ClaudeCode
+    }
+}
+
+impl Default for Route {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+/// Picks the provider and model for each task and runs routed tasks
+pub struct ProviderRouter {
+    config: RoutingConfig,
+    workspace_root: PathBuf,
+    providers: Mutex<Vec<(ProviderType, Arc<dyn LLMProvider>)>>,
+    session_provider: RwLock<Option<ProviderType>>,
+}
+
+impl ProviderRouter {
+    pub fn new(config: RoutingConfig, workspace_root: PathBuf) -> Self {
+        Self {
+            config,
+            workspace_root,
+            providers: Mutex::new(Vec::new()),
+            session_provider: RwLock::new(None),
+        }
+    }
+
+    /// Route for a task: its own preference, else the session's provider,
+    /// else the first matching rule, else the Claude Code CLI with its
+    /// default model
+    pub fn route(&self, metadata: &TaskMetadata) -> Route {
+        let ProviderPreference { provider, model } = &metadata.provider_preference;
+        if !metadata.provider_preference.is_empty() {
+            return Route::new(provider.clone(), model.clone());
+        }
+        if let Some(provider) = self.session_provider() {
+            return Route::new(Some(provider), None);
+        }
+        self.config
+            .rules
+            .iter()
+            .find(|rule| rule.matches(metadata))
+            .map(|rule| Route::new(rule.provider.clone(), rule.model.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Run tasks without a provider preference on `provider`; `None` goes
+    /// back to the routing rules
+    pub fn set_session_provider(&self, provider: Option<ProviderType>) {
+        *self.session_provider.write().unwrap() = provider;
+    }
+
+    /// Provider chosen with [`set_session_provider`](Self::set_session_provider)
+    pub fn session_provider(&self) -> Option<ProviderType> {
+        self.session_provider.read().unwrap().clone()
+    }
+
+    /// The provider instance for `provider_type`, created on first use
+    pub async fn provider(
+        &self,
+        provider_type: &ProviderType,
+    ) -> Result<Arc<dyn LLMProvider>, LLMError> {
+        let mut providers = self.providers.lock().await;
+        if let Some((_, provider)) = providers.iter().find(|(kind, _)| kind == provider_type) {
+            return Ok(provider.clone());
+        }
+        debug!("Creating {:?} provider for routed tasks", provider_type);
+        let config = ProviderConfig {
+            provider_type: provider_type.clone(),
+            model: None,
+            ..Default::default()
+        };
+        let provider =
+            LLMProviderFactory::create_provider(config, self.workspace_root.clone()).await?;
+        providers.push((provider_type.clone(), provider.clone()));
+        Ok(provider)
+    }
+
+    /// Run routes to `provider_type` on `provider` instead of creating one
+    pub async fn set_provider(&self, provider_type: ProviderType, provider: Arc<dyn LLMProvider>) {
+        let mut providers = self.providers.lock().await;
+        providers.retain(|(kind, _)| *kind != provider_type);
+        providers.push((provider_type, provider));
+    }
+
+    /// Run `task` on the route's provider, returning it completed with the
+    /// response in the same shape the Claude Code interface produces
+    ///
+    /// Provider logs go to `<logs_dir>/<provider>_interactions`.
+    pub async fn process_task(
+        &self,
+        task: &Task,
+        route: &Route,
+        system_message: Option<String>,
+        logs_dir: &Path,
+    ) -> Result<Task, LLMError> {
+        let provider = self.provider(&route.provider).await?;
+        info!(
+            "Routing task {} to {} ({})",
+            task.id,
+            provider.provider_name(),
+            route.model.as_deref().unwrap_or("default model")
+        );
+
+        let logger = ProviderLogger::new(
+            provider.provider_name(),
+            ProviderLoggerConfig::default(),
+            logs_dir.join(format!("{}_interactions", provider.provider_name())),
+        )
+        .await
+        .map_err(|e| LLMError::ProviderSpecific(format!("Failed to create logger: {}", e)))?;
+
+        let request = LLMRequest {
+            id: task.id,
+            prompt: task.description.clone(),
+            model_preference: route.model.clone(),
+            system_message,
+            ..Default::default()
+        };
+        let response = provider.execute_request(request, &logger).await?;
+
+        let mut completed = task.clone();
+        completed.status = TaskStatus::Completed {
+            completed_at: Utc::now(),
+            result: crate::task::types::TaskResult::Success {
+                output: serde_json::json!({
+                    "response": response.content,
+                    "token_usage": response.token_usage,
+                    "model_used": response.model_used,
+                    "provider": provider.provider_name(),
+                }),
+                files_created: Vec::new(),
+                files_modified: Vec::new(),
+                build_artifacts: Vec::new(),
+            },
+        };
+        completed.updated_at = Utc::now();
+        Ok(completed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(complexity: ComplexityLevel, tags: &[&str]) -> TaskMetadata {
+        TaskMetadata {
+            estimated_complexity: Some(complexity),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn router() -> ProviderRouter {
+        ProviderRouter::new(
+            RoutingConfig {
+                rules: vec![
+                    RoutingRule {
+                        complexity: vec![ComplexityLevel::Trivial],
+                        provider: Some(ProviderType::OpenAIApi),
+                        model: Some("gpt-5-mini".to_string()),
+                        ..Default::default()
+                    },
+                    RoutingRule {
+                        complexity: vec![ComplexityLevel::Epic],
+                        tags: vec!["architecture".to_string()],
+                        model: Some("opus".to_string()),
+                        ..Default::default()
+                    },
+                ],
+            },
+            std::env::temp_dir(),
+        )
+    }
+
+    #[test]
+    fn test_rules_route_by_complexity_and_tags() {
+        let router = router();
+        assert_eq!(
+            router.route(&metadata(ComplexityLevel::Trivial, &[])),
+            Route {
+                provider: ProviderType::OpenAIApi,
+                model: Some("gpt-5-mini".to_string()),
+            }
+        );
+
+        // Epic tasks go to Opus only when tagged for it
+        let epic = router.route(&metadata(ComplexityLevel::Epic, &["architecture"]));
+        assert!(epic.is_claude_code());
+        assert_eq!(epic.model.as_deref(), Some("opus"));
+        assert_eq!(
+            router.route(&metadata(ComplexityLevel::Epic, &["docs"])),
+            Route::default()
+        );
+    }
+
+    #[test]
+    fn test_task_preference_overrides_rules() {
+        let router = router();
+        let mut trivial = metadata(ComplexityLevel::Trivial, &[]);
+        trivial.provider_preference = ProviderPreference {
+            provider: None,
+            model: Some("haiku".to_string()),
+        };
+        assert_eq!(
+            router.route(&trivial),
+            Route {
+                provider: ProviderType::ClaudeCode,
+                model: Some("haiku".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_session_provider_overrides_rules() {
+        let router = router();
+        router.set_session_provider(Some(ProviderType::LocalModel));
+        let trivial = metadata(ComplexityLevel::Trivial, &[]);
+        assert_eq!(router.route(&trivial).provider, ProviderType::LocalModel);
+
+        // A task's own preference still wins
+        let mut pinned = trivial.clone();
+        pinned.provider_preference.provider = Some(ProviderType::Anthropic);
+        assert_eq!(router.route(&pinned).provider, ProviderType::Anthropic);
+
+        router.set_session_provider(None);
+        assert_eq!(router.route(&trivial).provider, ProviderType::OpenAIApi);
+    }
+}
@@ -0,0 +1,370 @@
+//! Schema-validated responses for requests that expect structured output.
+//!
+//! Task parsing, reviews and summaries ask the model for JSON. Models wrap
+//! JSON in markdown fences, add prose around it, double-encode it or get
+//! the shape wrong. [`execute_structured`] extracts the JSON, validates it
+//! against the request's [`LLMRequest::response_schema`] and any extra
+//! checks, and on failure re-asks the model with the validation errors,
+//! up to a retry limit.
+
+use super::provider::LLMProvider;
+use super::provider_logger::ProviderLogger;
+use super::types::{LLMError, LLMRequest, LLMResponse};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Retries after the first attempt when a response fails validation
+pub const DEFAULT_STRUCTURED_RETRIES: u32 = 2;
+
+/// Longest response excerpt quoted in errors
+const EXCERPT_CHARS: usize = 200;
+
+/// A validated structured response
+#[derive(Debug, Clone)]
+pub struct StructuredResponse<T> {
+    pub value: T,
+    /// The accepted response, with token usage summed over all attempts
+    pub response: LLMResponse,
+    /// Requests made, including the accepted one
+    pub attempts: u32,
+}
+
+/// Pull a JSON value out of a model response
+///
+/// Accepts bare JSON, JSON inside a ```` ```json ```` fence, JSON preceded
+/// or followed by prose, and JSON encoded as a JSON string.
+pub fn extract_json(content: &str) -> Result<Value, String> {
+    let value = match serde_json::from_str(content.trim()) {
+        Ok(value) => value,
+        Err(e) => {
+            let candidate = if let Some(start) = content.find("```json") {
+                let body = &content[start + 7..];
+                body.find("```").map_or(body, |end| &body[..end])
+            } else if let Some(start) = content.find(['{', '[']) {
+                &content[start..]
+            } else {
+                return Err(format!("response is not valid JSON: {}", e));
+            };
+
+            // Read the first value and ignore whatever trails it
+            serde_json::Deserializer::from_str(candidate)
+                .into_iter::<Value>()
+                .next()
+                .unwrap_or(Err(e))
+                .map_err(|e| format!("response is not valid JSON: {}", e))?
+        }
+    };
+
+    match value {
+        Value::String(encoded) => serde_json::from_str(&encoded)
+            .map_err(|e| format!("response is a string, not a JSON object: {}", e)),
+        value => Ok(value),
+    }
+}
+
+/// Check `value` against a JSON Schema, returning one message per violation
+pub fn validate_schema(value: &Value, schema: &Value) -> Result<(), Vec<String>> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| vec![format!("response schema is invalid: {}", e)])?;
+    let errors: Vec<String> = validator
+        .iter_errors(value)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            if path.is_empty() {
+                error.to_string()
+            } else {
+                format!("{}: {}", path, error)
+            }
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parse and validate a response body: schema, then type, then `check`
+pub fn parse_structured<T: DeserializeOwned>(
+    content: &str,
+    schema: Option<&Value>,
+    check: impl Fn(&T) -> Result<(), String>,
+) -> Result<T, Vec<String>> {
+    let value = extract_json(content).map_err(|e| vec![e])?;
+    if let Some(schema) = schema {
+        validate_schema(&value, schema)?;
+    }
+    let parsed: T = serde_json::from_value(value).map_err(|e| vec![e.to_string()])?;
+    check(&parsed).map_err(|e| vec![e])?;
+    Ok(parsed)
+}
+
+/// Execute `request`, re-asking with the validation errors until the
+/// response is accepted or `max_retries` retries are used up
+///
+/// `check` adds validation the schema cannot express, such as indices
+/// that must refer to other entries.
+pub async fn execute_structured<T: DeserializeOwned>(
+    provider: &dyn LLMProvider,
+    request: LLMRequest,
+    logger: &ProviderLogger,
+    max_retries: u32,
+    check: impl Fn(&T) -> Result<(), String>,
+) -> Result<StructuredResponse<T>, LLMError> {
+    let mut attempt_request = request.clone();
+    let mut usage: Option<super::types::TokenUsage> = None;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let mut response = provider
+            .execute_request(attempt_request.clone(), logger)
+            .await?;
+        let total = match usage.take() {
+            Some(mut total) => {
+                total.input_tokens += response.token_usage.input_tokens;
+                total.output_tokens += response.token_usage.output_tokens;
+                total.total_tokens += response.token_usage.total_tokens;
+                total.estimated_cost += response.token_usage.estimated_cost;
+                total.cache_read_tokens += response.token_usage.cache_read_tokens;
+                total.cache_creation_tokens += response.token_usage.cache_creation_tokens;
+                total.cache_savings += response.token_usage.cache_savings;
+                total
+            }
+            None => response.token_usage.clone(),
+        };
+
+        match parse_structured(&response.content, request.response_schema.as_ref(), &check) {
+            Ok(value) => {
+                debug!("Structured response accepted after {} attempt(s)", attempts);
+                response.token_usage = total;
+                return Ok(StructuredResponse {
+                    value,
+                    response,
+                    attempts,
+                });
+            }
+            Err(errors) if attempts <= max_retries => {
+                warn!(
+                    "Structured response rejected (attempt {}): {}",
+                    attempts,
+                    errors.join("; ")
+                );
+                usage = Some(total);
+                attempt_request = LLMRequest {
+                    id: Uuid::new_v4(),
+                    prompt: retry_prompt(&request.prompt, &errors),
+                    ..request.clone()
+                };
+            }
+            Err(errors) => {
+                return Err(LLMError::InvalidResponse {
+                    attempts,
+                    message: format!(
+                        "{}. Content: {}",
+                        errors.join("; "),
+                        response
+                            .content
+                            .chars()
+                            .take(EXCERPT_CHARS)
+                            .collect::<String>()
+                    ),
+                });
+            }
+        }
+    }
+}
+
+/// The original prompt followed by what was wrong with the last answer
+fn retry_prompt(prompt: &str, errors: &[String]) -> String {
+    let mut retry = prompt.to_string();
+    retry.push_str("\n\nYour previous response was rejected:\n");
+    for error in errors {
+        retry.push_str("- ");
+        retry.push_str(error);
+        retry.push('\n');
+    }
+    retry.push_str("Respond again with only JSON that fixes these problems.\n");
+    retry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ProviderCapabilities, ProviderStatus, TokenUsage};
+    use futures::future::BoxFuture;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Returns the queued responses in order and records the prompts
+    struct ScriptedProvider {
+        responses: Mutex<Vec<String>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: &[&str]) -> Self {
+            Self {
+                responses: Mutex::new(responses.iter().rev().map(|r| r.to_string()).collect()),
+                prompts: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl LLMProvider for ScriptedProvider {
+        fn execute_request<'a>(
+            &'a self,
+            request: LLMRequest,
+            _logger: &'a ProviderLogger,
+        ) -> BoxFuture<'a, Result<LLMResponse, LLMError>> {
+            self.prompts.lock().unwrap().push(request.prompt);
+            let content = self.responses.lock().unwrap().pop().unwrap_or_default();
+            Box::pin(async move {
+                Ok(LLMResponse {
+                    request_id: request.id,
+                    content,
+                    model_used: "scripted".to_string(),
+                    token_usage: TokenUsage {
+                        input_tokens: 10,
+                        output_tokens: 5,
+                        total_tokens: 15,
+                        estimated_cost: 0.5,
+                        ..Default::default()
+                    },
+                    execution_time: std::time::Duration::ZERO,
+                    provider_metadata: HashMap::new(),
+                })
+            })
+        }
+
+        fn get_capabilities(&self) -> BoxFuture<'_, Result<ProviderCapabilities, LLMError>> {
+            Box::pin(async { Err(LLMError::ProviderUnavailable("scripted".to_string())) })
+        }
+
+        fn get_status(&self) -> BoxFuture<'_, Result<ProviderStatus, LLMError>> {
+            Box::pin(async { Err(LLMError::ProviderUnavailable("scripted".to_string())) })
+        }
+
+        fn health_check(&self) -> BoxFuture<'_, Result<(), LLMError>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>, LLMError>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn estimate_tokens(&self, text: &str) -> u64 {
+            text.len() as u64 / 4
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Summary {
+        title: String,
+        score: u32,
+    }
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["title", "score"],
+            "properties": {
+                "title": { "type": "string" },
+                "score": { "type": "integer", "minimum": 0, "maximum": 10 }
+            }
+        })
+    }
+
+    async fn logger() -> (tempfile::TempDir, ProviderLogger) {
+        let dir = tempfile::tempdir().unwrap();
+        let logger =
+            ProviderLogger::new("structured", Default::default(), dir.path().to_path_buf())
+                .await
+                .unwrap();
+        (dir, logger)
+    }
+
+    #[test]
+    fn test_extract_json_variants() {
+        let expected = json!({"a": 1});
+        assert_eq!(extract_json(r#"{"a": 1}"#).unwrap(), expected);
+        assert_eq!(
+            extract_json("Here you go:\n```json\n{\"a\": 1}\n```\nDone.").unwrap(),
+            expected
+        );
+        assert_eq!(
+            extract_json("Sure! {\"a\": 1} Let me know.").unwrap(),
+            expected
+        );
+        assert_eq!(extract_json(r#""{\"a\": 1}""#).unwrap(), expected);
+        assert!(extract_json("no json here").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retries_with_validation_feedback() {
+        let provider = ScriptedProvider::new(&[
+            r#"{"title": "Report"}"#,
+            r#"{"title": "Report", "score": 11}"#,
+            r#"{"title": "Report", "score": 7}"#,
+        ]);
+        let (_dir, logger) = logger().await;
+        let request = LLMRequest {
+            prompt: "Summarize".to_string(),
+            response_schema: Some(schema()),
+            ..Default::default()
+        };
+
+        let result: StructuredResponse<Summary> =
+            execute_structured(&provider, request, &logger, 2, |_| Ok(()))
+                .await
+                .unwrap();
+        assert_eq!(result.value.title, "Report");
+        assert_eq!(result.value.score, 7);
+        assert_eq!(result.attempts, 3);
+        assert_eq!(result.response.token_usage.total_tokens, 45);
+
+        let prompts = provider.prompts.lock().unwrap();
+        assert_eq!(prompts[0], "Summarize");
+        assert!(prompts[1].starts_with("Summarize\n\nYour previous response was rejected:"));
+        assert!(prompts[1].contains("\"score\" is a required property"));
+        assert!(prompts[2].contains("/score"));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_retries_and_runs_checks() {
+        let provider = ScriptedProvider::new(&[
+            r#"{"title": "", "score": 1}"#,
+            r#"{"title": "", "score": 1}"#,
+        ]);
+        let (_dir, logger) = logger().await;
+        let request = LLMRequest {
+            response_schema: Some(schema()),
+            ..Default::default()
+        };
+
+        let error = execute_structured::<Summary>(&provider, request, &logger, 1, |summary| {
+            if summary.title.is_empty() {
+                Err("title must not be empty".to_string())
+            } else {
+                Ok(())
+            }
+        })
+        .await
+        .unwrap_err();
+        match error {
+            LLMError::InvalidResponse { attempts, message } => {
+                assert_eq!(attempts, 2);
+                assert!(message.starts_with("title must not be empty"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -14,6 +14,9 @@ pub struct LLMRequest {
     pub temperature: Option<f32>,
     pub model_preference: Option<String>,
     pub system_message: Option<String>,
+    /// JSON Schema the response must satisfy; see [`crate::llm::structured`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
 }
 
 /// Generic LLM response from any provider
@@ -28,12 +31,22 @@ pub struct LLMResponse {
 }
 
 /// Token usage statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenUsage {
+    /// Prompt tokens not served from or written to the prompt cache
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub total_tokens: u64,
     pub estimated_cost: f64,
+    /// Prompt tokens read from the provider's prompt cache
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
+    /// Cost avoided by cache reads, net of the cache write premium
+    #[serde(default)]
+    pub cache_savings: f64,
 }
 
 /// Provider-specific configuration
@@ -49,10 +62,12 @@ pub struct ProviderConfig {
 }
 
 /// Supported LLM providers
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProviderType {
     ClaudeCode,
     OpenAICodex,
+    /// OpenAI Chat Completions or Responses API, or a compatible endpoint
+    OpenAIApi,
     Anthropic,
     LocalModel,
     Custom(String),
@@ -126,6 +141,8 @@ pub enum LLMError {
     Network(String),
     #[error("Provider-specific error: {0}")]
     ProviderSpecific(String),
+    #[error("Invalid structured response after {attempts} attempt(s): {message}")]
+    InvalidResponse { attempts: u32, message: String },
 }
 
 impl Default for LLMRequest {
@@ -138,6 +155,7 @@ impl Default for LLMRequest {
             temperature: None,
             model_preference: None,
             system_message: None,
+            response_schema: None,
         }
     }
 }
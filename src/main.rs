@@ -1,31 +1,55 @@
 use aca::cli::{
-    Args, BatchConfig, ConfigDiscovery, ExecutionMode, InteractiveConfig, TaskInput, TaskLoader,
-    args::ResumeConfig,
+    Args, BatchConfig, ConfigDiscovery, ConfigInitConfig, ContextImportConfig,
+    CoordinatorModeConfig, ExecutionMode, FixBugConfig, IdeServer, IdeServerConfig, InlineSource,
+    InputBuffer, InteractiveConfig, LogsPruneConfig, ParseOptions, PlanDiffConfig, PlanDiffFormat,
+    PlanValidateConfig, PrDescriptionConfig, QuickConfig, ReplCommand, ReplayConfig, ReplayFormat,
+    ReportVerifyConfig, Reporter, ServeModeConfig, SessionRestoreConfig, StatsConfig, StatusConfig,
+    SubPlanParser, TaskInput, TaskLoader, TaskSummary, TaskWhyConfig, TerminalQuestionHandler,
+    TranscriptConfig, TranscriptFormat, UsageConfig, WatchModeConfig, WhyFormat, WorkerModeConfig,
+    args::{Commands, ResumeConfig},
+    recall, repl,
 };
+use aca::daemon::{DaemonConfig, TenantRegistry, WorkCoordinator, WorkState, WorkerClient};
 use aca::env;
+use aca::executor::ExecutorCapabilities;
+use aca::llm::offline::{OFFLINE_REASON, is_connectivity_error};
 use aca::session::persistence::PersistenceConfig;
 use aca::session::recovery::RecoveryConfig;
-use aca::session::{SessionInitOptions, SessionManager, SessionManagerConfig};
-use aca::task::ExecutionPlan;
+use aca::session::{LogTail, SessionInitOptions, SessionManager, SessionManagerConfig, crash};
 use aca::task::TaskStatus;
 use aca::task::manager::TaskManagerConfig;
+use aca::task::{
+    AnswerFile, Calibration, CompletedTaskPolicy, ExecutionPlan, FixThrottle, GraphFormat,
+    PlanSimulator, ScheduleDecision, SchedulerConfig, TaskExplanation, TaskManager, TaskScheduler,
+    WaitConditions, WorkspaceSnapshot, fix_task_spec, parse_deadline, plan_task_id,
+};
 use aca::{AgentConfig, AgentSystem};
-use std::io::{self, Write};
+use rustyline::error::ReadlineError;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
-use tracing::{error, info};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use tracing_subscriber::fmt::writer::MakeWriterExt;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter("automatic_coding_agent=info")
-        .init();
-
-    info!("Starting Automatic Coding Agent");
-
     // Parse command line arguments
     let args = Args::parse();
 
+    // Initialize logging; the IDE server's stdout carries the protocol
+    // and every line is also kept for crash reports
+    let log_tail = LogTail::new();
+    let logging = tracing_subscriber::fmt().with_env_filter("automatic_coding_agent=info");
+    if matches!(args.command, Some(Commands::IdeServer { .. })) {
+        logging.with_writer(io::stderr.and(log_tail.clone())).init();
+    } else {
+        logging.with_writer(io::stdout.and(log_tail.clone())).init();
+    }
+    crash::install(Some(log_tail));
+
+    info!("Starting Automatic Coding Agent");
+
     // Execute based on mode
     let mode = match args.mode() {
         Ok(mode) => mode,
@@ -36,7 +60,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     match mode {
-        ExecutionMode::Batch(config) => run_batch_mode(config).await,
+        ExecutionMode::Batch(config) => run_batch_mode(*config).await,
         ExecutionMode::Interactive(config) => run_interactive_mode(config).await,
         ExecutionMode::Resume(config) => run_resume_mode(config).await,
         ExecutionMode::ListCheckpoints { all_sessions } => {
@@ -47,6 +71,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             ConfigDiscovery::show_discovery_info();
             Ok(())
         }
+        ExecutionMode::InitConfig(config) => init_config(config),
+        ExecutionMode::Status(config) => show_status(config),
+        ExecutionMode::ValidatePlan(config) => validate_plan(config),
+        ExecutionMode::DiffPlans(config) => diff_plans(config),
+        ExecutionMode::Usage(config) => show_usage(config),
+        ExecutionMode::Stats(config) => show_stats(config),
+        ExecutionMode::Watch(config) => run_watch_mode(config).await,
+        ExecutionMode::FixBug(config) => run_fix_bug_mode(config).await,
+        ExecutionMode::Quick(config) => run_quick_mode(config).await,
+        ExecutionMode::IdeServer(config) => run_ide_server_mode(config).await,
+        ExecutionMode::ImportClaudeContext(config) => import_claude_context(config),
+        ExecutionMode::PruneLogs(config) => prune_logs(config),
+        ExecutionMode::RestoreSession(config) => restore_session(config).await,
+        ExecutionMode::TaskTranscript(config) => show_task_transcript(config),
+        ExecutionMode::TaskWhy(config) => show_task_why(config),
+        ExecutionMode::PrDescription(config) => show_pr_description(config),
+        ExecutionMode::VerifyReport(config) => verify_report(config),
+        ExecutionMode::Replay(config) => show_replay(config),
+        ExecutionMode::Coordinator(config) => run_coordinator_mode(config).await,
+        ExecutionMode::Worker(config) => run_worker_mode(config).await,
+        ExecutionMode::Serve(config) => run_serve_mode(config).await,
     }
 }
 
@@ -66,6 +111,22 @@ async fn run_batch_mode(config: BatchConfig) -> Result<(), Box<dyn std::error::E
         default_config.to_agent_config(config.workspace_override.clone())
     };
 
+    // Save piped or pasted task text so it goes through the file pipeline
+    let mut config = config;
+    if let TaskInput::Inline(source) = config.task_input {
+        config.task_input =
+            TaskLoader::materialize_inline(config.task_input, &agent_config.workspace_path)?;
+        info!(
+            "Saved task text from {} to {:?}",
+            source.name(),
+            config.task_input.path()
+        );
+    }
+
+    if config.dry_run && !matches!(config.task_input, TaskInput::SingleFile(_)) {
+        report_plan_diagnostics(config.task_input.path())?;
+    }
+
     // Convert task input to execution plan
     let execution_plan = match &config.task_input {
         TaskInput::ConfigWithTasks(path) => {
@@ -95,20 +156,48 @@ async fn run_batch_mode(config: BatchConfig) -> Result<(), Box<dyn std::error::E
 
             if use_intelligent {
                 info!("Using intelligent LLM-based task parser");
-                TaskLoader::task_input_to_execution_plan_with_options(
+                match TaskLoader::task_input_to_execution_plan_with_options(
                     &config.task_input,
-                    true,
-                    config.context_hints.clone(),
-                    config.provider_override.clone(),
-                    config.model_override.clone(),
+                    &parse_options(&config, true, &agent_config.workspace_path),
                 )
-                .await?
+                .await
+                {
+                    Ok(plan) => plan,
+                    // Offline, the plan is still accepted with naive parsing
+                    Err(e)
+                        if agent_config.offline.enabled
+                            && is_connectivity_error(&e.to_string()) =>
+                    {
+                        warn!(
+                            "No provider reachable for parsing ({}), using naive parser",
+                            e
+                        );
+                        TaskLoader::task_input_to_execution_plan(&config.task_input)?
+                    }
+                    Err(e) => return Err(e.into()),
+                }
             } else {
                 TaskLoader::task_input_to_execution_plan(&config.task_input)?
             }
         }
     };
 
+    let execution_plan = if config.approve {
+        match approve_plan(execution_plan, &agent_config.workspace_path)? {
+            Some(plan) => plan,
+            None => {
+                println!("🛑 Plan not approved; nothing was run");
+                return Ok(());
+            }
+        }
+    } else {
+        execution_plan
+    };
+
+    let execution_plan = execution_plan
+        .with_source(config.task_input.path())
+        .with_completed_task_policy(completed_task_policy(&config));
+
     if config.verbose {
         println!("📁 Created execution plan: {}", execution_plan.summary());
         if let Some(ref name) = execution_plan.metadata.name {
@@ -136,13 +225,21 @@ async fn run_batch_mode(config: BatchConfig) -> Result<(), Box<dyn std::error::E
         }
     }
 
+    if config.dry_run || config.deadline.is_some() {
+        print_plan_simulation(&execution_plan, &agent_config, &config)?;
+    }
+
     // Dump execution plan if requested
     if let Some(ref dump_path) = config.dump_plan {
         dump_execution_plan(&execution_plan, dump_path)?;
         println!("📄 Execution plan dumped to: {}", dump_path.display());
-        if config.dry_run {
-            return Ok(());
-        }
+    }
+    if let Some(ref graph_path) = config.dump_graph {
+        dump_task_graph(&execution_plan, graph_path).await?;
+        println!("🕸️  Task graph dumped to: {}", graph_path.display());
+    }
+    if config.dry_run && (config.dump_plan.is_some() || config.dump_graph.is_some()) {
+        return Ok(());
     }
 
     if config.dry_run {
@@ -152,27 +249,53 @@ async fn run_batch_mode(config: BatchConfig) -> Result<(), Box<dyn std::error::E
 
     // Initialize agent system
     info!("Initializing agent system for batch execution...");
-    let mut agent_config = agent_config.with_subprocess_output(config.verbose);
+    let mut agent_config = agent_config
+        .with_subprocess_output(config.verbose)
+        .with_context_dump(config.dump_context.clone());
 
     // Apply container configuration if requested
-    if config.use_containers {
+    if config.use_devcontainer {
         use aca::executor::{ContainerExecutionConfig, RuntimeMode};
+        info!("Dev container execution requested");
+        agent_config.execution_mode =
+            RuntimeMode::Container(ContainerExecutionConfig::default().with_devcontainer());
+    } else if config.use_containers {
+        use aca::executor::{ContainerExecutionConfig, DevContainerSetup, RuntimeMode};
         info!(
             "Container execution requested with image: {}",
             config.container_image
         );
+        if let Some(path) = DevContainerSetup::find(&agent_config.workspace_path) {
+            info!(
+                "Found {}; pass --devcontainer to run tasks in it",
+                path.display()
+            );
+        }
         agent_config.execution_mode = RuntimeMode::Container(ContainerExecutionConfig::new(
             config.container_image.clone(),
         ));
     }
 
+    let workspace_path = agent_config.workspace_path.clone();
     let agent = AgentSystem::new(agent_config).await?;
+    attach_question_handler(&agent, &config)?;
+    attach_plan_loader(&agent, &config, &workspace_path);
 
     info!("Agent system initialized successfully!");
 
     // Execute the plan using the unified execution path
     info!("Executing plan with unified agent system...");
-    let task_ids = agent.execute_plan(execution_plan).await?;
+    let result = agent.execute_plan_interruptible(execution_plan).await;
+    // Failed runs are the ones CI most needs to report
+    if let Some(ref junit_path) = config.report_junit {
+        write_junit_report(&agent, junit_path).await?;
+    }
+    if let Some(ref sarif_path) = config.report_sarif {
+        write_sarif_report(&agent, &workspace_path, sarif_path)?;
+    }
+    let Some(task_ids) = result? else {
+        return shut_down_interrupted(&agent).await;
+    };
 
     if config.verbose {
         if !task_ids.is_empty() {
@@ -190,6 +313,7 @@ async fn run_batch_mode(config: BatchConfig) -> Result<(), Box<dyn std::error::E
             println!("ℹ️  No tasks were executed (setup-only plan)");
         }
     }
+    print_cost_report(&agent).await;
 
     // Graceful shutdown
     info!("Shutting down agent system...");
@@ -206,11 +330,13 @@ async fn run_structured_config_mode(
     info!("Running structured TOML configuration mode");
 
     // Load the agent config from TOML file
-    let agent_config = AgentConfig::from_toml_file(config_path)?;
+    let agent_config = AgentConfig::from_toml_file(&config_path)?;
 
     // Convert the agent config to execution plan
     info!("Converting structured configuration to execution plan...");
-    let execution_plan = AgentSystem::agent_config_to_execution_plan(&agent_config);
+    let execution_plan = AgentSystem::agent_config_to_execution_plan(&agent_config)
+        .with_source(&config_path)
+        .with_completed_task_policy(completed_task_policy(&config));
 
     if config.verbose {
         println!(
@@ -239,6 +365,10 @@ async fn run_structured_config_mode(
         }
     }
 
+    if config.dry_run || config.deadline.is_some() {
+        print_plan_simulation(&execution_plan, &agent_config, &config)?;
+    }
+
     if config.dry_run {
         println!(
             "🔍 Dry run mode - structured execution plan would be processed but won't actually run"
@@ -249,13 +379,18 @@ async fn run_structured_config_mode(
     // Initialize agent system
     info!("Initializing agent system for structured batch execution...");
     let agent_config = agent_config.with_subprocess_output(config.verbose);
+    let workspace_path = agent_config.workspace_path.clone();
     let agent = AgentSystem::new(agent_config).await?;
+    attach_question_handler(&agent, &config)?;
+    attach_plan_loader(&agent, &config, &workspace_path);
 
     info!("Agent system initialized successfully!");
 
     // Execute the plan using the unified execution path
     info!("Executing structured configuration plan...");
-    let task_ids = agent.execute_plan(execution_plan).await?;
+    let Some(task_ids) = agent.execute_plan_interruptible(execution_plan).await? else {
+        return shut_down_interrupted(&agent).await;
+    };
 
     if config.verbose {
         if !task_ids.is_empty() {
@@ -267,6 +402,7 @@ async fn run_structured_config_mode(
             println!("✅ Structured configuration setup completed successfully!");
         }
     }
+    print_cost_report(&agent).await;
 
     // Graceful shutdown
     info!("Shutting down agent system...");
@@ -275,261 +411,945 @@ async fn run_structured_config_mode(
     Ok(())
 }
 
-async fn run_interactive_mode(config: InteractiveConfig) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Running in interactive mode");
+/// Report an interrupted run and shut down, leaving it to `aca resume`
+async fn shut_down_interrupted(agent: &AgentSystem) -> Result<(), Box<dyn std::error::Error>> {
+    println!("⏸️  Run interrupted; resume it with `aca resume`");
+    print_cost_report(agent).await;
+    info!("Shutting down agent system...");
+    agent.shutdown().await?;
+    Ok(())
+}
 
-    // Discover configuration for interactive mode
-    let default_config = ConfigDiscovery::discover_config()?;
-    let agent_config = default_config.to_agent_config(config.workspace.clone());
+async fn run_ide_server_mode(config: IdeServerConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
+    let workspace = agent_config.workspace_path.clone();
+    let ignore = agent_config
+        .watch
+        .ignored_dirs(&agent_config.verification.ignore);
+
+    let agent = std::sync::Arc::new(AgentSystem::new(agent_config).await?);
+    info!("IDE server ready for {}", workspace.display());
+
+    let server = std::sync::Arc::new(IdeServer::new(agent.clone(), workspace, ignore));
+    server
+        .serve(
+            tokio::io::BufReader::new(tokio::io::stdin()),
+            tokio::io::stdout(),
+        )
+        .await?;
 
-    // Initialize the agent system
-    info!("Initializing agent system...");
-    let agent_config = agent_config.with_subprocess_output(config.verbose);
-    let agent = AgentSystem::new(agent_config).await?;
+    agent.shutdown().await?;
+    Ok(())
+}
 
-    info!("Agent system initialized successfully!");
+async fn run_watch_mode(config: WatchModeConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
 
-    if config.verbose {
-        println!("🤖 Interactive mode started. Type 'help' for commands.");
+    let mut watch = agent_config.watch.clone();
+    if config.skip_confirmation {
+        watch.confirm_fixes = false;
     }
+    let verify_command = config
+        .verify_command
+        .clone()
+        .or_else(|| watch.verify_command.clone())
+        .ok_or("No verification command: pass --verify or set watch.verify_command")?;
+    let workspace = agent_config.workspace_path.clone();
+    let ignore = watch.ignored_dirs(&agent_config.verification.ignore);
 
-    // Interactive CLI loop (preserved from original)
-    loop {
-        print!("\n> Enter a task description (or 'quit' to exit): ");
-        io::stdout().flush()?;
+    let agent_config = agent_config.with_subprocess_output(config.verbose);
+    let agent = AgentSystem::new(agent_config).await?;
+    attach_terminal_questions(&agent);
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input = input.trim();
+    println!(
+        "👀 Watching {} — running `{}` after changes (Ctrl+C to stop)",
+        workspace.display(),
+        verify_command
+    );
 
-        if input == "quit" || input == "exit" {
-            break;
+    let poll_interval = std::time::Duration::from_secs(watch.poll_interval_secs.max(1));
+    let settle = std::time::Duration::from_secs(watch.settle_secs);
+    let mut throttle = FixThrottle::new(&watch);
+    let mut verified = WorkspaceSnapshot::capture(&workspace, &ignore)?;
+    let mut latest = verified.clone();
+    let mut last_change: Option<std::time::Instant> = None;
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(poll_interval) => {}
         }
 
-        if input == "status" {
-            show_system_status(&agent).await?;
+        // Wait for edits to settle before verifying
+        let current = WorkspaceSnapshot::capture(&workspace, &ignore)?;
+        if current != latest {
+            latest = current;
+            last_change = Some(std::time::Instant::now());
             continue;
         }
-
-        if input == "help" {
-            show_interactive_help();
+        match last_change {
+            Some(changed_at) if changed_at.elapsed() >= settle => last_change = None,
+            _ => continue,
+        }
+        let changed = latest.changed_since(&verified);
+        verified = latest.clone();
+        if changed.is_empty() {
             continue;
         }
 
-        if input.is_empty() {
+        println!(
+            "🔎 {} file(s) changed, running `{}`...",
+            changed.len(),
+            verify_command
+        );
+        let result = agent.run_verification(&verify_command).await?;
+        if result.success() {
+            println!("✅ Verification passed");
             continue;
         }
+        println!("❌ Verification failed (exit code {})", result.exit_code);
 
-        // Create and process the task
-        info!("Creating task: {}", input);
-
-        match agent.create_and_process_task("User Task", input).await {
-            Ok(task_id) => {
-                info!("Task completed successfully! Task ID: {}", task_id);
-                println!("✅ Task completed: {}", task_id);
-            }
-            Err(e) => {
-                error!("Task failed: {}", e);
-                println!("❌ Task failed: {}", e);
+        if let Some(wait) = throttle.wait_time(std::time::Instant::now()) {
+            println!(
+                "⏳ Fix limit reached, next fix allowed in {}s",
+                wait.as_secs()
+            );
+            continue;
+        }
+        if watch.confirm_fixes {
+            print!("🛠️  Create a fix task for this failure? [y/N] ");
+            io::stdout().flush()?;
+            let mut answer = String::new();
+            io::stdin().read_line(&mut answer)?;
+            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                continue;
             }
         }
-    }
-
-    // Graceful shutdown
-    info!("Shutting down agent system...");
-    agent.shutdown().await?;
-
-    println!("Goodbye!");
-    Ok(())
-}
-
-fn show_interactive_help() {
-    println!("📖 Interactive Mode Commands:");
-    println!("  status  - Show system status");
-    println!("  help    - Show this help message");
-    println!("  quit    - Exit the application");
-    println!("  exit    - Exit the application");
-    println!("\n💡 Enter any other text to create and execute a task.");
-}
 
-async fn show_system_status(agent: &AgentSystem) -> Result<(), Box<dyn std::error::Error>> {
-    let status = agent.get_system_status().await?;
+        throttle.record(std::time::Instant::now());
+        let fix = fix_task_spec(&verify_command, &result, &changed, watch.max_output_chars);
+        if let Err(e) = agent
+            .execute_plan(ExecutionPlan::new().with_task(fix))
+            .await
+        {
+            error!("Fix task failed: {}", e);
+        }
 
-    println!("\n📊 System Status:");
-    println!(
-        "  Health: {}",
-        if status.is_healthy {
-            "✅ Healthy"
+        // The fix's own edits are not human edits; verify once and move on
+        let result = agent.run_verification(&verify_command).await?;
+        if result.success() {
+            println!("✅ Verification passes after the fix");
         } else {
-            "❌ Unhealthy"
+            println!(
+                "❌ Verification still fails after the fix (exit code {})",
+                result.exit_code
+            );
         }
-    );
-    println!("  Tasks: {} total", status.task_stats.total_tasks);
-    println!(
-        "  Claude: {} available tokens, {} requests",
-        status.claude_status.rate_limiter.available_tokens,
-        status.claude_status.rate_limiter.available_requests
-    );
-    println!(
-        "  Sessions: {} active, {} idle",
-        status.claude_status.session_stats.active_sessions,
-        status.claude_status.session_stats.idle_sessions
-    );
+        verified = WorkspaceSnapshot::capture(&workspace, &ignore)?;
+        latest = verified.clone();
+    }
 
+    print_cost_report(&agent).await;
+    agent.shutdown().await?;
     Ok(())
 }
 
-async fn run_resume_mode(config: ResumeConfig) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Running in resume mode");
-
-    let workspace = config
-        .workspace_override
-        .unwrap_or_else(|| std::env::current_dir().unwrap());
-
-    // Check if .aca directory structure exists
-    let _aca_dir = env::aca_dir_path(&workspace);
-    let sessions_dir = env::sessions_dir_path(&workspace);
-
-    if !sessions_dir.exists() {
-        eprintln!(
-            "Error: No session data found in directory: {}",
-            workspace.display()
-        );
-        eprintln!("Make sure you're in the correct workspace directory.");
-        std::process::exit(1);
-    }
-
-    // Determine which checkpoint to restore from
-    let checkpoint_id = if config.continue_latest {
-        match find_latest_checkpoint(&workspace).await {
-            Ok(id) => id,
-            Err(e) => {
-                eprintln!("Error: Failed to find latest checkpoint: {}", e);
-                std::process::exit(1);
-            }
-        }
-    } else if let Some(id) = config.checkpoint_id {
-        id
+async fn run_fix_bug_mode(config: FixBugConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
     } else {
-        eprintln!("Error: Must specify --resume <checkpoint-id> or --continue");
-        std::process::exit(1);
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
     };
 
-    if config.verbose {
-        println!("🔄 Resuming from checkpoint: {}", checkpoint_id);
-    }
-
-    // Discover and load configuration
-    let default_config = ConfigDiscovery::discover_config()?;
-    let agent_config = default_config.to_agent_config(Some(workspace.clone()));
-
-    // Initialize agent system with session restore from checkpoint
-    info!("Initializing agent system with checkpoint restore...");
-    let init_options = SessionInitOptions {
-        name: "Resumed Session".to_string(),
-        description: Some("Session resumed from checkpoint".to_string()),
-        workspace_root: workspace.clone(),
-        task_manager_config: TaskManagerConfig::default(),
-        persistence_config: PersistenceConfig::default(),
-        recovery_config: RecoveryConfig::default(),
-        enable_auto_save: true,
-        restore_from_checkpoint: Some(checkpoint_id.clone()),
-        execution_mode: None,
+    let description = if config.description == "-" {
+        InlineSource::Stdin.read()?
+    } else {
+        config.description.clone()
     };
-
-    let agent = AgentSystem::with_session_init(agent_config, init_options).await?;
-
-    if config.verbose {
-        println!("✅ Successfully resumed from checkpoint: {}", checkpoint_id);
-        println!("🤖 Agent system ready. Checking for incomplete tasks...");
+    if description.trim().is_empty() {
+        return Err("The bug description is empty".into());
     }
 
-    // Check for incomplete tasks and continue processing
-    let incomplete_tasks = find_incomplete_tasks(&agent).await?;
+    let agent_config = agent_config.with_subprocess_output(config.verbose);
+    let agent = AgentSystem::new(agent_config).await?;
+    attach_terminal_questions(&agent);
 
-    if !incomplete_tasks.is_empty() {
-        if config.verbose {
+    println!("🐞 Reproducing the bug with a failing test...");
+    let outcome = agent
+        .fix_bug(&description, config.test_command.clone())
+        .await;
+
+    match &outcome {
+        Ok(report) => {
             println!(
-                "🔄 Found {} incomplete tasks. Continuing processing...",
-                incomplete_tasks.len()
+                "✅ Bug fixed: `{}` failed before and passes now",
+                report.test_command
             );
+            if config.verbose {
+                println!("  🧪 Reproduce task: {}", report.reproduce_task);
+                println!("  🔧 Fix task: {}", report.fix_task);
+                println!("  🔍 Confirm task: {}", report.confirm_task);
+            }
         }
+        Err(e) => eprintln!("❌ Bug fix workflow stopped: {}", e),
+    }
+    print_cost_report(&agent).await;
+    agent.shutdown().await?;
 
-        let mut successful_tasks = 0;
-        let total_tasks = incomplete_tasks.len();
+    outcome.map(|_| ()).map_err(Into::into)
+}
 
-        for (task_num, task_id) in incomplete_tasks.iter().enumerate() {
-            if config.verbose {
-                println!(
-                    "🔄 Processing incomplete task {}/{}: {}",
-                    task_num + 1,
-                    total_tasks,
-                    task_id
-                );
-            }
+async fn run_quick_mode(config: QuickConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
 
-            match agent.process_task(*task_id).await {
-                Ok(()) => {
-                    info!(
-                        "Resumed task {}/{} completed successfully! Task ID: {}",
-                        task_num + 1,
-                        total_tasks,
-                        task_id
+    let request = if config.request == "-" {
+        InlineSource::Stdin.read()?
+    } else {
+        config.request.clone()
+    };
+    if request.trim().is_empty() {
+        return Err("The request is empty".into());
+    }
+    let verify_command = match config.skip_verify {
+        true => None,
+        false => config
+            .verify_command
+            .clone()
+            .or_else(|| agent_config.verification.command.clone())
+            .or_else(|| agent_config.watch.verify_command.clone()),
+    };
+
+    let agent_config = agent_config.with_subprocess_output(config.stream_output);
+    let agent = AgentSystem::new(agent_config).await?;
+    attach_terminal_questions(&agent);
+
+    let outcome = match agent.quick(&request, verify_command.as_deref()).await {
+        Ok(report) => {
+            print!("\n{}", report.render_diff_stat());
+            match &report.verification {
+                Some(verification) if verification.passed => {
+                    println!("✅ `{}` passed", verification.command);
+                    Ok(())
+                }
+                Some(verification) => {
+                    println!(
+                        "❌ `{}` failed:\n{}",
+                        verification.command, verification.output
                     );
-                    if config.verbose {
-                        println!(
-                            "✅ Task {}/{} completed: {}",
-                            task_num + 1,
-                            total_tasks,
-                            task_id
-                        );
-                    }
-                    successful_tasks += 1;
+                    Err(format!("`{}` failed", verification.command).into())
                 }
-                Err(e) => {
-                    error!("Failed to process resumed task {}: {}", task_id, e);
-                    if config.verbose {
-                        println!("❌ Task {}/{} failed: {}", task_num + 1, total_tasks, e);
-                    }
+                None if config.skip_verify => Ok(()),
+                None => {
+                    println!("ℹ️  Not verified: pass --verify or set verification.command");
+                    Ok(())
                 }
             }
         }
-
-        if successful_tasks == total_tasks {
-            println!(
-                "✅ All {} resumed tasks completed successfully!",
-                total_tasks
-            );
-        } else {
-            println!(
-                "⚠️  {}/{} resumed tasks completed successfully",
-                successful_tasks, total_tasks
-            );
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            Err(e.into())
         }
-    } else if config.verbose {
-        println!("ℹ️  No incomplete tasks found. Session restored successfully.");
-    }
-
-    // Graceful shutdown
-    info!("Shutting down agent system...");
+    };
     agent.shutdown().await?;
 
-    Ok(())
+    outcome
 }
 
-async fn list_available_checkpoints(_all_sessions: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let workspace = std::env::current_dir()?;
-    let _aca_dir = env::aca_dir_path(&workspace);
-    let sessions_dir = env::sessions_dir_path(&workspace);
+async fn run_coordinator_mode(
+    config: CoordinatorModeConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
 
-    // Check if .aca directory structure exists
-    if !sessions_dir.exists() {
-        println!("No session data found in current directory.");
-        println!("Make sure you're in a workspace that has been used with aca.");
-        return Ok(());
+    let plan = TaskLoader::load_execution_plan(&config.plan_file)?;
+    if !plan.has_tasks() {
+        return Err("The plan has no tasks to distribute".into());
     }
 
-    // Find the most recent session directory
+    let token = agent_config.distributed.token()?;
+    let coordinator = WorkCoordinator::new(&plan, agent_config.distributed.clone())
+        .with_state_file(env::coordinator_state_file_path(
+            &agent_config.workspace_path,
+        ))?;
+    let resumed = coordinator.status();
+    let listener = tokio::net::TcpListener::bind(&config.listen).await?;
+
+    println!(
+        "🛰️  Coordinating {} task(s) on {}",
+        plan.task_count(),
+        config.listen
+    );
+    if resumed.completed + resumed.failed > 0 {
+        println!(
+            "  ↩️  Resumed: {} completed, {} failed",
+            resumed.completed, resumed.failed
+        );
+    }
+    if token.is_none() {
+        println!(
+            "⚠️  No worker token configured: any client that can reach {} may lease tasks",
+            config.listen
+        );
+    }
+
+    let coordinator = Arc::new(tokio::sync::Mutex::new(coordinator));
+    let status = aca::daemon::serve(coordinator.clone(), listener, token).await?;
+
+    println!(
+        "✅ Plan finished: {} task(s) completed, {} failed",
+        status.completed, status.failed
+    );
+    for item in coordinator.lock().await.items() {
+        if let WorkState::Failed { error } = &item.state {
+            println!("  ❌ {}: {}", item.spec.title, error);
+        }
+    }
+
+    if status.failed > 0 {
+        return Err(format!("{} task(s) failed", status.failed).into());
+    }
+    Ok(())
+}
+
+async fn run_serve_mode(config: ServeModeConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let daemon_config = DaemonConfig::from_toml_file(&config.config_file).map_err(|e| {
+        format!(
+            "Failed to load daemon configuration {}: {}",
+            config.config_file.display(),
+            e
+        )
+    })?;
+    if daemon_config.tenants.is_empty() {
+        return Err(format!("No tenants in {}", config.config_file.display()).into());
+    }
+    let grpc_listen = config
+        .grpc_listen
+        .clone()
+        .or_else(|| daemon_config.grpc_listen.clone());
+    let registry = std::sync::Arc::new(TenantRegistry::from_config(daemon_config)?);
+
+    let mut tenants: Vec<_> = registry.tenants().collect();
+    tenants.sort_by(|a, b| a.id().cmp(b.id()));
+    println!("🏢 Serving {} tenant(s) (Ctrl+C to stop)", tenants.len());
+    for tenant in tenants {
+        println!("  • {} — {}", tenant.id(), tenant.workspace().display());
+    }
+
+    #[cfg(feature = "grpc")]
+    {
+        let addr: std::net::SocketAddr = grpc_listen
+            .as_deref()
+            .unwrap_or(aca::daemon::DEFAULT_GRPC_ADDR)
+            .parse()
+            .map_err(|e| format!("Invalid gRPC listen address: {}", e))?;
+        println!("📡 gRPC interface on {}", addr);
+        tokio::select! {
+            result = aca::daemon::grpc::serve(registry.clone(), addr) => result?,
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+    #[cfg(not(feature = "grpc"))]
+    {
+        if let Some(addr) = grpc_listen {
+            return Err(format!(
+                "Cannot serve gRPC on {}: aca was built without the `grpc` feature",
+                addr
+            )
+            .into());
+        }
+        println!("No network interface: aca was built without the `grpc` feature");
+        tokio::signal::ctrl_c().await?;
+    }
+
+    println!("Shutting down");
+    registry.shutdown().await?;
+    Ok(())
+}
+
+async fn run_worker_mode(config: WorkerModeConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let mut agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
+
+    let worker_id = config
+        .worker_id
+        .clone()
+        .unwrap_or_else(aca::daemon::default_worker_id);
+    let token = agent_config.distributed.token()?;
+    let mut client = WorkerClient::connect(&config.coordinator, token).await?;
+    let capabilities =
+        ExecutorCapabilities::for_runtime(&agent_config.execution_mode, &agent_config.placement);
+    let (setup_commands, renew_interval) = client.join(&worker_id, capabilities).await?;
+    println!(
+        "🛰️  Joined coordinator {} as {}",
+        config.coordinator, worker_id
+    );
+
+    // The plan's setup commands prepare this worker's checkout
+    agent_config.setup_commands = setup_commands;
+    let agent_config = agent_config.with_subprocess_output(config.verbose);
+    let agent = AgentSystem::new(agent_config).await?;
+
+    let outcome = aca::daemon::run_worker(&agent, client, &worker_id, renew_interval).await;
+    match &outcome {
+        Ok(summary) => println!(
+            "✅ Coordinator finished: this worker completed {} task(s), {} failed",
+            summary.completed, summary.failed
+        ),
+        Err(e) => eprintln!("❌ Worker stopped: {}", e),
+    }
+    print_cost_report(&agent).await;
+    agent.shutdown().await?;
+
+    outcome.map(|_| ()).map_err(Into::into)
+}
+
+async fn run_interactive_mode(config: InteractiveConfig) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Running in interactive mode");
+
+    // Discover configuration for interactive mode
+    let default_config = ConfigDiscovery::discover_config()?;
+    let agent_config = default_config.to_agent_config(config.workspace.clone());
+    let workspace = agent_config.workspace_path.clone();
+    let ignore = agent_config
+        .watch
+        .ignored_dirs(&agent_config.verification.ignore);
+    let reporter = Reporter::new(config.bell);
+
+    // Initialize the agent system
+    info!("Initializing agent system...");
+    let agent_config = agent_config.with_subprocess_output(config.verbose);
+    let agent = AgentSystem::new(agent_config.clone()).await?;
+
+    info!("Agent system initialized successfully!");
+
+    if config.verbose {
+        println!("🤖 Interactive mode started. Type 'help' for commands.");
+    }
+
+    let mut editor = repl::editor(&workspace)?;
+    let mut buffer = InputBuffer::default();
+
+    loop {
+        let prompt = if buffer.is_continuing() {
+            "... "
+        } else {
+            println!();
+            "> Enter a task description (or 'quit' to exit): "
+        };
+
+        // Ctrl-C drops the entry being typed instead of exiting
+        let line = match tokio::task::block_in_place(|| editor.readline(prompt)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                if buffer.cancel() {
+                    println!("^C (input cancelled)");
+                } else {
+                    println!("^C (type 'quit' or press Ctrl-D to exit)");
+                }
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let Some(input) = buffer.push(&line) else {
+            continue;
+        };
+
+        let entries: Vec<String> = editor.history().iter().cloned().collect();
+        let input = match recall(&entries, &input) {
+            Some(Ok(recalled)) => {
+                println!("{}", recalled);
+                recalled
+            }
+            Some(Err(e)) => {
+                println!("❌ {}", e);
+                continue;
+            }
+            None => input,
+        };
+
+        let command = match ReplCommand::parse(&input) {
+            Ok(command) => command,
+            Err(e) => {
+                println!("❌ {}", e);
+                continue;
+            }
+        };
+
+        if command != ReplCommand::Empty
+            && command != ReplCommand::History
+            && let Err(e) = repl::remember(&mut editor, &workspace, &input)
+        {
+            warn!("Could not save history: {}", e);
+        }
+
+        match command {
+            ReplCommand::Empty => {}
+            ReplCommand::Quit => break,
+            ReplCommand::Help => show_interactive_help(),
+            ReplCommand::History => show_history(&entries),
+            ReplCommand::Status => show_system_status(&agent).await?,
+            ReplCommand::Tasks => show_task_list(&agent).await?,
+            ReplCommand::Show(id) => match agent.find_task(&id).await {
+                Ok(task) => show_task(&agent, &task).await,
+                Err(e) => println!("❌ {}", e),
+            },
+            ReplCommand::Retry(id) => {
+                let task = match agent.find_task(&id).await {
+                    Ok(task) => task,
+                    Err(e) => {
+                        println!("❌ {}", e);
+                        continue;
+                    }
+                };
+                println!("🔁 Retrying {}", task.title);
+                let result = tokio::select! {
+                    result = agent.retry_task(task.id) => result,
+                    _ = tokio::signal::ctrl_c() => {
+                        println!("^C (task interrupted)");
+                        break;
+                    }
+                };
+                match result {
+                    Ok(()) => match agent.task_manager().get_task(task.id).await {
+                        Ok(task) => println!("✅ Task {}: {}", task.status.name(), task.id),
+                        Err(e) => println!("❌ {}", e),
+                    },
+                    Err(e) => println!("❌ Retry failed: {}", e),
+                }
+            }
+            ReplCommand::Cancel(id) => {
+                let result = match agent.find_task(&id).await {
+                    Ok(task) => agent.cancel_task(task.id, "Cancelled by user").await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(cancelled) => println!("🛑 Cancelled {} task(s)", cancelled.len()),
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+            ReplCommand::Checkpoint(description) => {
+                match agent.session_manager().create_checkpoint(description).await {
+                    Ok(checkpoint) => println!(
+                        "💾 Checkpoint {} saved ({} tasks)",
+                        checkpoint.id, checkpoint.task_count
+                    ),
+                    Err(e) => println!("❌ Checkpoint failed: {}", e),
+                }
+            }
+            ReplCommand::Budget => show_budget(&agent, &agent_config).await,
+            ReplCommand::Provider(None) => match agent.session_provider() {
+                Some(provider) => println!("🧠 Provider: {:?}", provider),
+                None => println!("🧠 Provider: configured routing (default ClaudeCode)"),
+            },
+            ReplCommand::Provider(Some(provider)) => {
+                println!("🧠 Tasks will now be planned and run with {:?}", provider);
+                agent.set_session_provider(Some(provider));
+            }
+            ReplCommand::Plan(path) => {
+                run_interactive_plan(
+                    &agent,
+                    &path,
+                    agent.session_provider(),
+                    &agent_config.workspace_path,
+                )
+                .await;
+            }
+            ReplCommand::Task(input) => {
+                // Create and process the task
+                info!("Creating task: {}", input);
+                let started = std::time::Instant::now();
+                let before = WorkspaceSnapshot::capture(&workspace, &ignore).ok();
+
+                // Ctrl-C while a task runs stops the session
+                let result = tokio::select! {
+                    result = agent.create_and_process_task("User Task", &input) => result,
+                    _ = tokio::signal::ctrl_c() => {
+                        println!("^C (task interrupted)");
+                        break;
+                    }
+                };
+                let task = match result {
+                    Ok(task_id) => {
+                        info!("Task completed successfully! Task ID: {}", task_id);
+                        println!("✅ Task completed: {}", task_id);
+                        agent.task_manager().get_task(task_id).await.ok()
+                    }
+                    Err(e) => {
+                        error!("Task failed: {}", e);
+                        println!("❌ Task failed: {}", e);
+                        None
+                    }
+                };
+
+                let files_touched = before.and_then(|before| {
+                    WorkspaceSnapshot::capture(&workspace, &ignore)
+                        .ok()
+                        .map(|after| after.changed_since(&before).len())
+                });
+                let summary = match task {
+                    Some(task) => TaskSummary::from_task(&task, started.elapsed(), files_touched),
+                    None => TaskSummary::failed("User Task", started.elapsed(), files_touched),
+                };
+                reporter.task_finished(&summary)?;
+            }
+        }
+    }
+
+    // Graceful shutdown
+    info!("Shutting down agent system...");
+    agent.shutdown().await?;
+
+    println!("Goodbye!");
+    Ok(())
+}
+
+fn show_interactive_help() {
+    println!("📖 Interactive Mode Commands:");
+    for (usage, help) in repl::COMMANDS {
+        println!("  {:<24} - {}", usage, help);
+    }
+    println!("\n💡 Enter any other text to create and execute a task.");
+    println!(
+        "   Tab completes commands, provider names and plan paths; Up and Down recall earlier entries."
+    );
+}
+
+/// Most recent entries, numbered for `!N`
+fn show_history(entries: &[String]) {
+    const SHOWN: usize = 20;
+    let start = entries.len().saturating_sub(SHOWN);
+    for (index, entry) in entries.iter().enumerate().skip(start) {
+        let mut lines = entry.lines();
+        let first = lines.next().unwrap_or_default();
+        let more = if lines.next().is_some() { " ..." } else { "" };
+        println!("  {:>4}  {}{}", index + 1, first, more);
+    }
+}
+
+async fn show_task_list(agent: &AgentSystem) -> Result<(), Box<dyn std::error::Error>> {
+    let tasks = agent.task_tree().await?;
+    if tasks.is_empty() {
+        println!("📋 No tasks yet");
+        return Ok(());
+    }
+    println!("\n📋 Tasks:");
+    for (depth, task) in tasks {
+        println!(
+            "  {:<14} {}  {}{}",
+            task.status.name(),
+            &task.id.to_string()[..8],
+            "  ".repeat(depth),
+            task.title
+        );
+    }
+    Ok(())
+}
+
+async fn show_task(agent: &AgentSystem, task: &aca::task::Task) {
+    println!("\n🔎 {}", task.title);
+    println!("  Id: {}", task.id);
+    println!("  Status: {}", task.status.name());
+    match &task.status {
+        TaskStatus::Blocked { reason, .. } => println!("  Reason: {}", reason),
+        TaskStatus::Skipped { reason, .. } | TaskStatus::Paused { reason, .. } => {
+            println!("  Reason: {}", reason)
+        }
+        TaskStatus::Failed {
+            error, retry_count, ..
+        } => println!("  Error (attempt {}): {}", retry_count, error),
+        TaskStatus::AwaitingInput { question, .. } => println!("  Question: {}", question.question),
+        _ => {}
+    }
+    if let Some(parent) = task.parent_id {
+        println!("  Parent: {}", parent);
+    }
+    if !task.children.is_empty() {
+        println!("  Subtasks: {}", task.children.len());
+    }
+    for dependency in &task.dependencies {
+        let status = match agent.task_manager().get_task(*dependency).await {
+            Ok(dependency) => dependency.status.name(),
+            Err(_) => "missing",
+        };
+        println!("  Depends on: {} ({})", dependency, status);
+    }
+    if !task.metadata.tags.is_empty() {
+        println!("  Tags: {}", task.metadata.tags.join(", "));
+    }
+    println!(
+        "  Created: {}  Updated: {}",
+        task.created_at.format("%Y-%m-%d %H:%M:%S"),
+        task.updated_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    println!("\n{}", task.description.trim());
+
+    if let TaskStatus::Completed {
+        result: aca::task::types::TaskResult::Success { output, .. },
+        ..
+    } = &task.status
+        && let Some(response) = output.get("response").and_then(|r| r.as_str())
+    {
+        println!("\n💬 {}", response.trim());
+    }
+}
+
+async fn show_budget(agent: &AgentSystem, agent_config: &AgentConfig) {
+    let used_today = agent.claude_interface().tokens_used_today().await;
+    let spent_today: f64 = aca::session::CostLedger::for_workspace(&agent_config.workspace_path)
+        .load()
+        .unwrap_or_default()
+        .iter()
+        .filter(|entry| entry.timestamp.date_naive() == chrono::Utc::now().date_naive())
+        .fold(0.0, |spent, entry| spent + entry.cost);
+
+    println!("\n💰 Budget:");
+    match agent_config.schedule.daily_token_quota {
+        Some(quota) => println!(
+            "  Tokens today: {} of {} ({} remaining)",
+            used_today,
+            quota,
+            quota.saturating_sub(used_today)
+        ),
+        None => println!("  Tokens today: {} (no daily quota set)", used_today),
+    }
+    println!("  Spent today: ${:.4}", spent_today);
+    for usage in agent.cost_report().await {
+        println!(
+            "  {}: {} tokens, ${:.4}",
+            usage.tag, usage.total_tokens, usage.total_cost
+        );
+    }
+}
+
+/// Load a plan file, or plan a task list, and run it
+async fn run_interactive_plan(
+    agent: &AgentSystem,
+    path: &Path,
+    provider: Option<aca::llm::types::ProviderType>,
+    workspace: &Path,
+) {
+    let plan = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json" | "toml") => TaskLoader::load_execution_plan(path),
+        _ => {
+            TaskLoader::task_input_to_execution_plan_with_options(
+                &TaskInput::TaskList(path.to_path_buf()),
+                &ParseOptions {
+                    use_intelligent: true,
+                    provider_override: provider,
+                    workspace: Some(workspace.to_path_buf()),
+                    ..Default::default()
+                },
+            )
+            .await
+        }
+    };
+    let plan = match plan {
+        Ok(plan) => plan.with_source(path),
+        Err(e) => {
+            println!("❌ Could not load {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    println!("📁 Running {}", plan.summary());
+    match agent.execute_plan(plan).await {
+        Ok(task_ids) => println!("✅ Plan finished: {} tasks", task_ids.len()),
+        Err(e) => println!("❌ Plan failed: {}", e),
+    }
+}
+
+async fn show_system_status(agent: &AgentSystem) -> Result<(), Box<dyn std::error::Error>> {
+    let status = agent.get_system_status().await?;
+
+    println!("\n📊 System Status:");
+    println!(
+        "  Health: {}",
+        if status.is_healthy {
+            "✅ Healthy"
+        } else {
+            "❌ Unhealthy"
+        }
+    );
+    println!("  Tasks: {} total", status.task_stats.total_tasks);
+    if status.task_stats.blocked_tasks > 0 {
+        println!(
+            "  Blocked: {} ({} over budget)",
+            status.task_stats.blocked_tasks, status.task_stats.over_budget_tasks
+        );
+    }
+    println!(
+        "  Claude: {} available tokens, {} requests",
+        status.claude_status.rate_limiter.available_tokens,
+        status.claude_status.rate_limiter.available_requests
+    );
+    println!(
+        "  Sessions: {} active, {} idle",
+        status.claude_status.session_stats.active_sessions,
+        status.claude_status.session_stats.idle_sessions
+    );
+
+    Ok(())
+}
+
+async fn run_resume_mode(config: ResumeConfig) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Running in resume mode");
+
+    let workspace = config
+        .workspace_override
+        .unwrap_or_else(|| std::env::current_dir().unwrap());
+
+    // Check if .aca directory structure exists
+    let _aca_dir = env::aca_dir_path(&workspace);
+    let sessions_dir = env::sessions_dir_path(&workspace);
+
+    if !sessions_dir.exists() {
+        eprintln!(
+            "Error: No session data found in directory: {}",
+            workspace.display()
+        );
+        eprintln!("Make sure you're in the correct workspace directory.");
+        std::process::exit(1);
+    }
+
+    // Determine which checkpoint to restore from
+    let checkpoint_id = if config.continue_latest {
+        match find_latest_checkpoint(&workspace).await {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("Error: Failed to find latest checkpoint: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(id) = config.checkpoint_id {
+        id
+    } else {
+        eprintln!("Error: Must specify a checkpoint id or resume the latest with `aca resume`");
+        std::process::exit(1);
+    };
+
+    if config.verbose {
+        println!("🔄 Resuming from checkpoint: {}", checkpoint_id);
+    }
+
+    // Discover and load configuration
+    let default_config = ConfigDiscovery::discover_config()?;
+    let agent_config = default_config.to_agent_config(Some(workspace.clone()));
+
+    // Initialize agent system with session restore from checkpoint
+    info!("Initializing agent system with checkpoint restore...");
+    let agent = AgentSystem::new_from_checkpoint(agent_config, &checkpoint_id).await?;
+    attach_terminal_questions(&agent);
+
+    if config.verbose {
+        println!("✅ Successfully resumed from checkpoint: {}", checkpoint_id);
+        println!("🤖 Agent system ready. Checking for incomplete tasks...");
+    }
+
+    // Tasks that become eligible as others complete, such as plan tasks an
+    // interrupted run never started, are picked up in later rounds
+    let mut attempted = HashSet::new();
+    let mut successful_tasks = 0;
+    loop {
+        let incomplete_tasks: Vec<uuid::Uuid> = find_incomplete_tasks(&agent)
+            .await?
+            .into_iter()
+            .filter(|task_id| attempted.insert(*task_id))
+            .collect();
+        if incomplete_tasks.is_empty() {
+            break;
+        }
+        if config.verbose {
+            println!(
+                "🔄 Found {} incomplete tasks. Continuing processing...",
+                incomplete_tasks.len()
+            );
+        }
+
+        for task_id in incomplete_tasks {
+            if config.verbose {
+                println!("🔄 Processing incomplete task: {}", task_id);
+            }
+
+            match agent.process_task(task_id).await {
+                Ok(()) => {
+                    info!("Resumed task completed successfully! Task ID: {}", task_id);
+                    if config.verbose {
+                        println!("✅ Task completed: {}", task_id);
+                    }
+                    successful_tasks += 1;
+                }
+                Err(e) => {
+                    error!("Failed to process resumed task {}: {}", task_id, e);
+                    if config.verbose {
+                        println!("❌ Task {} failed: {}", task_id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    let total_tasks = attempted.len();
+    if total_tasks == 0 {
+        if config.verbose {
+            println!("ℹ️  No incomplete tasks found. Session restored successfully.");
+        }
+    } else if successful_tasks == total_tasks {
+        println!(
+            "✅ All {} resumed tasks completed successfully!",
+            total_tasks
+        );
+    } else {
+        println!(
+            "⚠️  {}/{} resumed tasks completed successfully",
+            successful_tasks, total_tasks
+        );
+    }
+
+    // Graceful shutdown
+    info!("Shutting down agent system...");
+    agent.shutdown().await?;
+
+    Ok(())
+}
+
+async fn list_available_checkpoints(_all_sessions: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = std::env::current_dir()?;
+    let _aca_dir = env::aca_dir_path(&workspace);
+    let sessions_dir = env::sessions_dir_path(&workspace);
+
+    // Check if .aca directory structure exists
+    if !sessions_dir.exists() {
+        println!("No session data found in current directory.");
+        println!("Make sure you're in a workspace that has been used with aca.");
+        return Ok(());
+    }
+
+    // Find the most recent session directory
     let mut latest_session_dir = None;
     let mut latest_time = None;
 
@@ -578,14 +1398,14 @@ async fn list_available_checkpoints(_all_sessions: bool) -> Result<(), Box<dyn s
     // For CLI usage, default to showing all checkpoints across sessions
     // The --all-sessions flag is currently redundant but kept for explicit behavior
     let show_all = true; // CLI users expect to see all checkpoints by default
-    let checkpoints = temp_session.list_checkpoints(show_all).await?;
+    let checkpoints = temp_session.list_checkpoints_verified(show_all).await?;
 
     if checkpoints.is_empty() {
         println!("No checkpoints available in current workspace.");
     } else {
         println!("Available checkpoints in {}:", workspace.display());
         println!();
-        for checkpoint in checkpoints {
+        for (checkpoint, integrity) in checkpoints {
             println!(
                 "📌 {} ({})",
                 checkpoint.id,
@@ -595,10 +1415,13 @@ async fn list_available_checkpoints(_all_sessions: bool) -> Result<(), Box<dyn s
             if checkpoint.task_count > 0 {
                 println!("   Tasks: {} total", checkpoint.task_count);
             }
+            if let Some(integrity) = integrity.filter(|i| !i.is_usable()) {
+                println!("   ⚠️  Integrity: {} (cannot be restored)", integrity);
+            }
             println!();
         }
-        println!("Use --resume <checkpoint-id> to restore from a specific checkpoint");
-        println!("Use --continue to resume from the latest checkpoint");
+        println!("Use `aca resume <checkpoint-id>` to restore from a specific checkpoint");
+        println!("Use `aca resume` to resume from the latest checkpoint");
     }
 
     Ok(())
@@ -717,15 +1540,162 @@ fn dump_execution_plan(
     Ok(())
 }
 
+/// Write the plan's tasks and their dependencies as a diagram, in the
+/// format the extension of `path` names
+async fn dump_task_graph(
+    plan: &ExecutionPlan,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = GraphFormat::from_path(path).ok_or_else(|| {
+        format!(
+            "Unsupported graph format: {}. Use .dot, .gv, .mmd or .md extension",
+            path.display()
+        )
+    })?;
+
+    // Add tasks after the ones they depend on, so plan ids map to tree ids
+    let manager = TaskManager::new(TaskManagerConfig::default());
+    let mut remaining = plan.task_specs.clone();
+    let plan_ids: HashSet<uuid::Uuid> = remaining
+        .iter()
+        .map(|spec| plan_task_id(&spec.title))
+        .collect();
+    let mut tree_ids: HashMap<uuid::Uuid, uuid::Uuid> = HashMap::new();
+    while !remaining.is_empty() {
+        // A dependency cycle is broken at the first task left
+        let index = remaining
+            .iter()
+            .position(|spec| {
+                spec.dependencies.iter().all(|dependency| {
+                    !plan_ids.contains(dependency) || tree_ids.contains_key(dependency)
+                })
+            })
+            .unwrap_or(0);
+        let mut spec = remaining.remove(index);
+        let plan_id = plan_task_id(&spec.title);
+        spec.dependencies = spec
+            .dependencies
+            .iter()
+            .filter_map(|dependency| tree_ids.get(dependency).copied())
+            .collect();
+        tree_ids.insert(plan_id, manager.create_task(spec, None).await?);
+    }
+
+    std::fs::write(path, manager.export_graph(format).await?)?;
+    Ok(())
+}
+
+/// Whether tasks finished by an earlier run of the same file run again
+fn completed_task_policy(config: &BatchConfig) -> CompletedTaskPolicy {
+    if config.rerun_completed {
+        CompletedTaskPolicy::Rerun
+    } else {
+        CompletedTaskPolicy::Skip
+    }
+}
+
+/// Tokens the workspace's cost ledger records for today
+fn tokens_used_today(workspace: &Path) -> u64 {
+    let today = chrono::Utc::now().date_naive();
+    aca::session::CostLedger::for_workspace(workspace)
+        .load()
+        .unwrap_or_default()
+        .iter()
+        .filter(|entry| entry.timestamp.date_naive() == today)
+        .map(|entry| entry.total_tokens)
+        .sum()
+}
+
+/// Print the plan's estimated timeline and warn if it misses `--deadline`
+fn print_plan_simulation(
+    plan: &ExecutionPlan,
+    agent_config: &AgentConfig,
+    config: &BatchConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let schedule = &agent_config.schedule;
+    let now = schedule.now();
+    let deadline = config
+        .deadline
+        .as_deref()
+        .map(|value| parse_deadline(value, now))
+        .transpose()?;
+
+    // Seed the daily quota with what this workspace already spent today
+    let tokens_used_today = tokens_used_today(&agent_config.workspace_path);
+
+    // Stretch or shrink estimates by how long earlier tasks really took
+    let calibration = Calibration::for_workspace(&agent_config.workspace_path).unwrap_or_default();
+    let simulation = PlanSimulator::new(&agent_config.claude_config.rate_limits, schedule)
+        .with_calibration(&calibration)
+        .simulate(plan, now, tokens_used_today)?;
+    println!("⏱️  {}", simulation.report(deadline));
+    Ok(())
+}
+
+/// Answer model questions from `--answers`, or at the terminal if there is one
+fn attach_question_handler(
+    agent: &AgentSystem,
+    config: &BatchConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match &config.answers_file {
+        Some(path) => {
+            let answers = AnswerFile::load(path)?;
+            info!(
+                "Answering model questions from {} (unanswered: {:?})",
+                path.display(),
+                answers.default_policy()
+            );
+            agent.set_question_handler(std::sync::Arc::new(answers));
+        }
+        None => attach_terminal_questions(agent),
+    }
+    Ok(())
+}
+
+/// Parse sub-plan files with the parser `--use-intelligent-parser` and
+/// `--force-naive-parser` select for task lists
+fn attach_plan_loader(agent: &AgentSystem, config: &BatchConfig, workspace: &Path) {
+    agent.set_plan_loader(std::sync::Arc::new(SubPlanParser {
+        options: parse_options(config, !config.force_naive_parser, workspace),
+    }));
+}
+
+/// The parser options `aca run` was given, planning in `workspace`
+fn parse_options(config: &BatchConfig, use_intelligent: bool, workspace: &Path) -> ParseOptions {
+    ParseOptions {
+        use_intelligent,
+        context_hints: config.context_hints.clone(),
+        provider_override: config.provider_override.clone(),
+        model_override: config.model_override.clone(),
+        parse_timeout: config.parse_timeout,
+        decomposition: config.decomposition.clone(),
+        workspace: Some(workspace.to_path_buf()),
+    }
+}
+
+/// Answer model questions at the terminal when someone is there to reply
+fn attach_terminal_questions(agent: &AgentSystem) {
+    if io::stdin().is_terminal() {
+        agent.set_question_handler(std::sync::Arc::new(TerminalQuestionHandler::new()));
+    }
+}
+
 /// Find incomplete tasks that should be continued when resuming
 async fn find_incomplete_tasks(
     agent: &AgentSystem,
 ) -> Result<Vec<uuid::Uuid>, Box<dyn std::error::Error>> {
     let task_manager = agent.task_manager();
 
-    // Look for tasks that are in progress or eligible to be processed
+    // Look for tasks that are in progress, paused or eligible to be processed
     let in_progress_tasks = task_manager
-        .get_tasks_by_status(|status| matches!(status, TaskStatus::InProgress { .. }))
+        .get_tasks_by_status(|status| {
+            matches!(
+                status,
+                TaskStatus::InProgress { .. }
+                    | TaskStatus::Paused { .. }
+                    | TaskStatus::AwaitingInput { .. }
+            ) || matches!(status, TaskStatus::Blocked { reason, .. } if reason.text() == Some(OFFLINE_REASON))
+        })
         .await?;
 
     let eligible_tasks = task_manager.get_eligible_tasks().await?;
@@ -756,3 +1726,544 @@ async fn find_incomplete_tasks(
 
     Ok(incomplete_tasks)
 }
+
+/// Write every task in the session as a JUnit XML test case
+async fn write_junit_report(
+    agent: &AgentSystem,
+    path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let task_manager = agent.task_manager();
+    let mut tasks = Vec::new();
+    for task_id in task_manager.get_tasks_by_status(|_| true).await? {
+        tasks.push(task_manager.get_task(task_id).await?);
+    }
+    std::fs::write(path, aca::task::render_junit("aca", &tasks))?;
+    println!("🧪 JUnit report written to: {}", path.display());
+    Ok(())
+}
+
+/// Write the findings of this run's completion gates as a SARIF log
+fn write_sarif_report(
+    agent: &AgentSystem,
+    workspace: &std::path::Path,
+    path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let findings = agent.findings();
+    let sarif = aca::task::findings::to_sarif(&findings, workspace);
+    std::fs::write(path, serde_json::to_string_pretty(&sarif)?)?;
+    println!(
+        "🔎 {} finding(s) written as SARIF to: {}",
+        findings.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Print this run's prompt cache savings and spend per cost tag (each
+/// skipped when there is nothing to report)
+async fn print_cost_report(agent: &AgentSystem) {
+    let totals = agent.usage_totals().await;
+    if totals.cache_read_tokens + totals.cache_creation_tokens > 0 {
+        println!(
+            "\n💾 Prompt cache: {} tokens read, {} written, ${:.4} saved",
+            totals.cache_read_tokens, totals.cache_creation_tokens, totals.cache_savings
+        );
+    }
+
+    let report = agent.cost_report().await;
+    if report
+        .iter()
+        .all(|usage| usage.tag == aca::claude::usage_tracker::UNTAGGED_COST_TAG)
+    {
+        return;
+    }
+
+    println!("\n💰 Cost by tag:");
+    for usage in report {
+        println!(
+            "  {:<24} {:>4} tasks {:>10} tokens  ${:.4}",
+            usage.tag, usage.task_count, usage.total_tokens, usage.total_cost
+        );
+    }
+}
+
+fn import_claude_context(config: ContextImportConfig) -> Result<(), Box<dyn std::error::Error>> {
+    use aca::claude::{TranscriptImporter, transcript_import::render_summary};
+
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    // Claude Code keys transcripts by the absolute workspace path
+    let workspace = workspace.canonicalize().unwrap_or(workspace);
+    let projects_dir = match config.projects_dir {
+        Some(dir) => dir,
+        None => {
+            let home = std::env::var_os("HOME").ok_or("HOME is not set")?;
+            env::claude_projects_dir_path(Path::new(&home))
+        }
+    };
+
+    let importer = TranscriptImporter::new(projects_dir, workspace.clone());
+    let transcripts = importer.transcripts()?;
+    if transcripts.is_empty() {
+        println!(
+            "No Claude Code sessions found in {}",
+            importer.transcript_dir().display()
+        );
+        return Ok(());
+    }
+
+    let summary = render_summary(&transcripts, &workspace, config.max_sessions);
+    let path = env::claude_history_file_path(&workspace);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, summary)?;
+    println!(
+        "📥 Summarized recent Claude Code sessions ({} found) into {}",
+        transcripts.len(),
+        path.display()
+    );
+    println!("   Tasks in this workspace now include it in their system prompt.");
+    Ok(())
+}
+
+fn prune_logs(config: LogsPruneConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
+
+    // `log_retention.enabled` only controls automatic pruning
+    let retention =
+        aca::session::LogRetention::new(agent_config.log_retention, agent_config.workspace_path);
+    let report = match &config.session_id {
+        Some(session_id) => retention.enforce_session(session_id)?,
+        None => retention.enforce()?,
+    };
+
+    if report.is_empty() {
+        println!("🧹 Session logs are already within the retention limits");
+        return Ok(());
+    }
+    println!(
+        "🧹 Compressed {} and removed {} log file(s), freeing {:.1} MB",
+        report.compressed.len(),
+        report.removed.len(),
+        report.bytes_freed as f64 / (1024.0 * 1024.0)
+    );
+    Ok(())
+}
+
+async fn restore_session(config: SessionRestoreConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config = if let Some(ref config_override) = config.config_override {
+        AgentConfig::from_toml_file(config_override)?
+    } else {
+        let default_config = ConfigDiscovery::discover_config()?;
+        default_config.to_agent_config(config.workspace_override.clone())
+    };
+
+    let Some(url) = config.remote_url.or(agent_config.remote_sync.url) else {
+        eprintln!("Error: No remote configured. Set remote_sync.url or pass --url.");
+        std::process::exit(1);
+    };
+
+    #[cfg(feature = "remote-sync")]
+    {
+        let remote = aca::session::RemoteSync::new(&url, agent_config.workspace_path)?;
+        let restored = remote.restore(config.session_id.as_deref()).await?;
+        if restored.is_empty() {
+            println!("☁️  Nothing to restore from {}", url);
+            return Ok(());
+        }
+        println!("☁️  Restored {} file(s) from {}", restored.len(), url);
+        println!("Use 'aca checkpoint list' to see the restored checkpoints");
+        println!("Use 'aca checkpoint resume <checkpoint-id>' to continue the run");
+        Ok(())
+    }
+
+    #[cfg(not(feature = "remote-sync"))]
+    {
+        let _ = (url, config.session_id);
+        Err("Remote sync support not compiled. Enable 'remote-sync' feature.".into())
+    }
+}
+
+fn show_task_transcript(config: TranscriptConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let (state, task_id) = aca::session::transcript::find_task(&workspace, &config.task_ref)?;
+    let journal = aca::session::EventJournal::for_workspace(&workspace).load()?;
+    let transcript = aca::session::TaskTranscript::build(&state, task_id, &journal)?;
+
+    match config.format {
+        TranscriptFormat::Md => print!("{}", transcript.render_markdown()),
+        TranscriptFormat::Json => println!("{}", serde_json::to_string_pretty(&transcript)?),
+    }
+    Ok(())
+}
+
+fn validate_plan(config: PlanValidateConfig) -> Result<(), Box<dyn std::error::Error>> {
+    report_plan_diagnostics(&config.path)?;
+    println!("✅ {} is valid", config.path.display());
+    Ok(())
+}
+
+fn diff_plans(config: PlanDiffConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let old = TaskLoader::load_execution_plan(&config.old)?;
+    let new = TaskLoader::load_execution_plan(&config.new)?;
+    let diff = old.diff(&new);
+    match config.format {
+        PlanDiffFormat::Text => print!("{}", diff.render()),
+        PlanDiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    Ok(())
+}
+
+/// Let the user edit `plan` in a file and confirm the result
+///
+/// The file is opened in `$VISUAL` or `$EDITOR` when one is set. It is
+/// loaded back and checked after every edit, and the edited plan is
+/// returned once the user confirms it; `None` if they cancel.
+fn approve_plan(
+    plan: ExecutionPlan,
+    workspace: &Path,
+) -> Result<Option<ExecutionPlan>, Box<dyn std::error::Error>> {
+    if !io::stdin().is_terminal() {
+        return Err("--approve needs an interactive terminal".into());
+    }
+    let path = env::plan_approval_file_path(workspace);
+    aca::cli::write_plan_for_review(&plan, &path)?;
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty());
+
+    let prompt = |question: &str| -> io::Result<Option<String>> {
+        print!("{}", question);
+        io::stdout().flush()?;
+        let mut line = String::new();
+        Ok((io::stdin().read_line(&mut line)? > 0).then(|| line.trim().to_lowercase()))
+    };
+    loop {
+        match &editor {
+            Some(editor) => {
+                let mut words = editor.split_whitespace();
+                let program = words.next().unwrap_or_default();
+                let status = std::process::Command::new(program)
+                    .args(words)
+                    .arg(&path)
+                    .status()?;
+                if !status.success() {
+                    warn!("Editor '{}' exited with {}", editor, status);
+                }
+            }
+            None => {
+                println!("📝 Review and edit the plan in {}", path.display());
+                match prompt("Press Enter when done, or q to cancel > ")?.as_deref() {
+                    None | Some("q") => return Ok(None),
+                    _ => {}
+                }
+            }
+        }
+
+        match aca::cli::load_reviewed_plan(&path) {
+            Ok(plan) => {
+                println!("📋 Plan to run: {}", plan.summary());
+                for (i, spec) in plan.task_specs.iter().enumerate() {
+                    println!("  {}. {}", i + 1, spec.title);
+                }
+                match prompt("Run this plan? [y]es · [e]dit again · anything else cancels > ")?
+                    .as_deref()
+                {
+                    Some("y" | "yes") => return Ok(Some(plan)),
+                    Some("e" | "edit") => {}
+                    _ => return Ok(None),
+                }
+            }
+            Err(problems) => {
+                eprintln!("❌ The edited plan has problems:\n{}", problems);
+                match prompt("Press Enter to edit it again, or q to cancel > ")?.as_deref() {
+                    None | Some("q") => return Ok(None),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Print a plan file's diagnostics and fail if any is an error
+fn report_plan_diagnostics(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let validation = aca::cli::validate_plan_file(path)?;
+    if !validation.diagnostics.is_empty() {
+        eprintln!("{}", validation.render());
+    }
+    if validation.has_errors() {
+        return Err(format!(
+            "{} has {} error(s)",
+            path.display(),
+            validation.error_count()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn show_task_why(config: TaskWhyConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let agent_config = ConfigDiscovery::discover_config()?.to_agent_config(Some(workspace.clone()));
+    let (state, task_id) = aca::session::transcript::find_task(&workspace, &config.task_ref)?;
+
+    let schedule_pause = match agent_config.schedule.check(tokens_used_today(&workspace))? {
+        ScheduleDecision::Pause { reason } => Some(reason),
+        ScheduleDecision::Run => None,
+    };
+    let claude_config = &agent_config.claude_config;
+    let rate_limit_wait = aca::claude::SharedRateLimiter::from_config(
+        &claude_config.shared_rate_limit,
+        &claude_config.rate_limits,
+    )
+    .and_then(|limiter| limiter.exhausted_for(chrono::Utc::now()).ok().flatten());
+    let conditions = WaitConditions {
+        max_concurrent_tasks: agent_config.task_config.max_concurrent_tasks,
+        schedule_pause,
+        rate_limit_wait,
+    };
+
+    let scheduler = TaskScheduler::new(SchedulerConfig::default());
+    let explanation = TaskExplanation::build(&state.task_tree, task_id, &scheduler, &conditions)?;
+    match config.format {
+        WhyFormat::Text => println!("{}", explanation.render_text()),
+        WhyFormat::Json => println!("{}", serde_json::to_string_pretty(&explanation)?),
+    }
+    Ok(())
+}
+
+fn show_pr_description(config: PrDescriptionConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let state = aca::session::run_report::find_session(&workspace, config.session_ref.as_deref())?;
+    print!(
+        "{}",
+        aca::session::RunReport::from_session(&state).render_pr_description()
+    );
+    Ok(())
+}
+
+fn verify_report(config: ReportVerifyConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    // Pin the given key, or the workspace's own public key if it has one
+    let pinned_key_path = aca::env::report_signing_key_path(&workspace).with_extension("pub");
+    let trusted_key = match config.key {
+        Some(key) if std::path::Path::new(&key).is_file() => Some(std::fs::read_to_string(key)?),
+        Some(key) => Some(key),
+        None => std::fs::read_to_string(&pinned_key_path).ok(),
+    };
+    if trusted_key.is_none() {
+        println!(
+            "⚠️  No trusted key given or found at {:?}; any valid signature is accepted",
+            pinned_key_path
+        );
+    }
+
+    let check = aca::session::report_signing::verify_file(&config.path, trusted_key.as_deref())?;
+    if check.is_verified() {
+        println!("✅ {}: {}", config.path.display(), check);
+        Ok(())
+    } else {
+        Err(format!("{}: {}", config.path.display(), check).into())
+    }
+}
+
+fn show_replay(config: ReplayConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let state = aca::session::run_report::find_session(&workspace, Some(&config.session_ref))?;
+    let journal = aca::session::EventJournal::for_workspace(&workspace).load()?;
+    let replay = aca::session::SessionReplay::build(&state, &journal);
+
+    if config.format == ReplayFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&replay)?);
+        return Ok(());
+    }
+    println!(
+        "⏪ Replay of {} ({}): {} steps",
+        replay.session_name,
+        replay.session_id,
+        replay.steps.len()
+    );
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        for index in 0..replay.steps.len() {
+            println!("{}", replay.render_step(index));
+        }
+        return Ok(());
+    }
+
+    let mut index = 0;
+    while index < replay.steps.len() {
+        println!("{}", replay.render_step(index));
+        print!("[Enter] next · p previous · <number> go to step · q quit > ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            break;
+        }
+        match line.trim() {
+            "" | "n" => index += 1,
+            "p" => index = index.saturating_sub(1),
+            "q" => break,
+            other => match other.parse::<usize>() {
+                Ok(step) if (1..=replay.steps.len()).contains(&step) => index = step - 1,
+                _ => println!("Unknown command '{}'", other),
+            },
+        }
+    }
+    Ok(())
+}
+
+fn init_config(config: ConfigInitConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let path = if config.local {
+        ConfigDiscovery::create_default_local_config(&std::env::current_dir()?)?
+    } else {
+        ConfigDiscovery::create_default_user_config()?
+    };
+    println!("📄 Configuration file: {}", path.display());
+    Ok(())
+}
+
+fn show_status(config: StatusConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let sessions = match env::sessions_dir_path(&workspace).exists() {
+        true => aca::session::persistence::load_workspace_sessions(&workspace)?,
+        false => Vec::new(),
+    };
+    let Some(state) = sessions
+        .into_iter()
+        .max_by_key(|state| state.metadata.last_updated)
+    else {
+        println!("No sessions recorded in {}", workspace.display());
+        return Ok(());
+    };
+    let report = aca::session::RunReport::from_session(&state);
+
+    println!(
+        "📊 Session {} ({}), last updated {}",
+        report.session_name,
+        &report.session_id[..8],
+        state.metadata.last_updated.format("%Y-%m-%d %H:%M:%S")
+    );
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for task in &report.tasks {
+        match counts.iter_mut().find(|(status, _)| *status == task.status) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&task.status, 1)),
+        }
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(status, count)| format!("{} {}", count, status))
+        .collect();
+    match counts.as_slice() {
+        [] => println!("  Tasks: none"),
+        counts => println!("  Tasks: {} ({})", report.tasks.len(), counts.join(", ")),
+    }
+    if report.provider_calls > 0 {
+        println!(
+            "  Usage: {} tokens, ${:.2} over {} provider calls",
+            report.total_tokens, report.estimated_cost, report.provider_calls
+        );
+    }
+    let unfinished = report
+        .tasks
+        .iter()
+        .any(|task| !matches!(task.status.as_str(), "completed" | "failed" | "skipped"));
+    if unfinished {
+        println!("  Resume with `aca resume`");
+    }
+    Ok(())
+}
+
+fn show_stats(config: StatsConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let calibration = Calibration::for_workspace(&workspace)?;
+    println!("📊 Stats for {}:", workspace.display());
+    println!("{}", calibration.report());
+    Ok(())
+}
+
+fn show_usage(config: UsageConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = match config.workspace_override {
+        Some(workspace) => workspace,
+        None => std::env::current_dir()?,
+    };
+    let ledger = aca::session::CostLedger::for_workspace(&workspace);
+    let entries = ledger.load()?;
+
+    if entries.is_empty() {
+        println!("No usage recorded in {}", workspace.display());
+        return Ok(());
+    }
+
+    let total_tokens: u64 = entries.iter().map(|entry| entry.total_tokens).sum();
+    let total_cost: f64 = entries.iter().map(|entry| entry.cost).sum();
+    println!("📊 Usage for {}:", workspace.display());
+    println!(
+        "  {} tasks, {} tokens, ${:.4} estimated cost",
+        entries.len(),
+        total_tokens,
+        total_cost
+    );
+    let cache_read: u64 = entries.iter().map(|entry| entry.cache_read_tokens).sum();
+    let cache_written: u64 = entries
+        .iter()
+        .map(|entry| entry.cache_creation_tokens)
+        .sum();
+    if cache_read + cache_written > 0 {
+        let cache_savings: f64 = entries.iter().map(|entry| entry.cache_savings).sum();
+        println!(
+            "  prompt cache: {} tokens read, {} written, ${:.4} saved",
+            cache_read, cache_written, cache_savings
+        );
+    }
+
+    if config.by_tag {
+        println!();
+        println!(
+            "  {:<24} {:>6} {:>9} {:>12} {:>10}",
+            "TAG", "TASKS", "SESSIONS", "TOKENS", "COST"
+        );
+        for summary in aca::session::summarize_by_tag(&entries) {
+            println!(
+                "  {:<24} {:>6} {:>9} {:>12} {:>10.4}",
+                summary.tag,
+                summary.task_count,
+                summary.session_count,
+                summary.total_tokens,
+                summary.total_cost
+            );
+        }
+    }
+
+    Ok(())
+}
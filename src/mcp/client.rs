@@ -0,0 +1,124 @@
+//! Minimal MCP client over stdio, for probing servers at startup.
+
+use super::{McpServerConfig, McpTool};
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+/// Protocol revision sent in `initialize`
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Launch a stdio server, initialize it and list its tools
+///
+/// The server is killed when the listing is done or `timeout` passes.
+pub async fn list_tools(server: &McpServerConfig, timeout: Duration) -> Result<Vec<McpTool>> {
+    let command = server
+        .command
+        .as_deref()
+        .ok_or_else(|| anyhow!("MCP server '{}' has no command", server.name))?;
+    let mut child = Command::new(command)
+        .args(&server.args)
+        .envs(&server.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start MCP server '{}'", server.name))?;
+
+    let result = tokio::time::timeout(timeout, handshake(&mut child))
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "MCP server '{}' did not answer within {:?}",
+                server.name,
+                timeout
+            )
+        })?
+        .with_context(|| format!("MCP server '{}' failed to list its tools", server.name));
+    child.kill().await.ok();
+    result
+}
+
+async fn handshake(child: &mut Child) -> Result<Vec<McpTool>> {
+    let mut connection = Connection {
+        stdin: child.stdin.take().context("No stdin")?,
+        stdout: BufReader::new(child.stdout.take().context("No stdout")?).lines(),
+        next_id: 0,
+    };
+    connection
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "aca", "version": env!("CARGO_PKG_VERSION")},
+            }),
+        )
+        .await?;
+    connection
+        .send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+        .await?;
+
+    let mut tools = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({"cursor": cursor}),
+            None => json!({}),
+        };
+        let result = connection.request("tools/list", params).await?;
+        let page: Vec<McpTool> =
+            serde_json::from_value(result.get("tools").cloned().unwrap_or(json!([])))
+                .context("Malformed tools/list result")?;
+        tools.extend(page);
+        cursor = result
+            .get("nextCursor")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        if cursor.is_none() {
+            return Ok(tools);
+        }
+    }
+}
+
+/// Newline-delimited JSON-RPC over a server's stdin and stdout
+struct Connection {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+impl Connection {
+    async fn send(&mut self, message: Value) -> Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        self.stdin.write_all(&line).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    /// Send a request and wait for its response, skipping anything else
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .await?;
+        while let Some(line) = self.stdout.next_line().await? {
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                bail!("{} failed: {}", method, error);
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+        bail!("Server exited before answering {}", method)
+    }
+}
@@ -0,0 +1,297 @@
+//! MCP (Model Context Protocol) tool servers for tasks.
+//!
+//! Servers declared in configuration give the agent project-specific tools,
+//! such as database access or internal APIs. Stdio servers are launched once
+//! at startup to check that they answer and to list their tools; servers
+//! that fail are left out with a warning. The rest are written to an MCP
+//! config file that the Claude CLI is started with (`--mcp-config`), and
+//! their tools are allowed without prompting. Calls to them show up in the
+//! per-task tool logs.
+//!
+//! ```toml
+//! [[mcp.servers]]
+//! name = "db"
+//! command = "npx"
+//! args = ["-y", "@modelcontextprotocol/server-postgres", "postgresql://localhost/app"]
+//!
+//! [[mcp.servers]]
+//! name = "tickets"
+//! url = "https://mcp.internal.example.com/mcp"
+//! headers = { Authorization = "Bearer ${TICKETS_TOKEN}" }
+//! ```
+
+mod client;
+
+pub use client::list_tools;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Prefix of MCP tool names as the Claude CLI reports them: `mcp__<server>__<tool>`
+pub const MCP_TOOL_PREFIX: &str = "mcp__";
+
+/// MCP servers available to tasks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpConfig {
+    pub servers: Vec<McpServerConfig>,
+    /// Launch stdio servers at startup and drop those that do not answer
+    pub probe: bool,
+    /// How long a server has to list its tools when probed
+    pub probe_timeout_secs: u64,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            probe: true,
+            probe_timeout_secs: 15,
+        }
+    }
+}
+
+/// One MCP server, started from `command` or reached at `url`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpServerConfig {
+    /// Name the server's tools are prefixed with; letters, digits, `-` and `_`
+    pub name: String,
+    /// Command starting a stdio server
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// URL of a streamable HTTP server
+    pub url: Option<String>,
+    /// HTTP headers sent to `url`
+    pub headers: BTreeMap<String, String>,
+}
+
+impl McpServerConfig {
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty()
+            || self.name.contains("__")
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "Invalid MCP server name '{}': use letters, digits, '-' and single '_'",
+                self.name
+            );
+        }
+        match (&self.command, &self.url) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => bail!(
+                "MCP server '{}' needs exactly one of `command` or `url`",
+                self.name
+            ),
+        }
+    }
+
+    /// Entry for the server in the Claude CLI's `mcpServers` map
+    fn claude_entry(&self) -> serde_json::Value {
+        match &self.url {
+            Some(url) => json!({"type": "http", "url": url, "headers": self.headers}),
+            None => json!({
+                "type": "stdio",
+                "command": self.command,
+                "args": self.args,
+                "env": self.env,
+            }),
+        }
+    }
+}
+
+/// A tool a server offers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Server and tool of an MCP tool name such as `mcp__db__query`
+pub fn parse_tool_name(name: &str) -> Option<(&str, &str)> {
+    name.strip_prefix(MCP_TOOL_PREFIX)?.split_once("__")
+}
+
+/// The servers a run uses, written out for the Claude CLI
+#[derive(Debug, Clone)]
+pub struct McpServers {
+    config_path: PathBuf,
+    names: Vec<String>,
+}
+
+impl McpServers {
+    /// Check the configured servers and write the CLI config to `config_path`
+    ///
+    /// `None` when no servers are configured or none of them answered. An
+    /// invalid declaration is an error.
+    pub async fn start(config: &McpConfig, config_path: PathBuf) -> Result<Option<Self>> {
+        let mut seen = HashSet::new();
+        for server in &config.servers {
+            server.validate()?;
+            if !seen.insert(server.name.as_str()) {
+                bail!("MCP server '{}' is declared twice", server.name);
+            }
+        }
+
+        let mut entries = serde_json::Map::new();
+        for server in &config.servers {
+            if config.probe && server.command.is_some() {
+                let timeout = Duration::from_secs(config.probe_timeout_secs);
+                match list_tools(server, timeout).await {
+                    Ok(tools) => info!(
+                        "MCP server '{}' offers {} tool(s): {}",
+                        server.name,
+                        tools.len(),
+                        tools
+                            .iter()
+                            .map(|tool| tool.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    Err(e) => {
+                        warn!("Leaving out MCP server '{}': {:#}", server.name, e);
+                        continue;
+                    }
+                }
+            }
+            entries.insert(server.name.clone(), server.claude_entry());
+        }
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &config_path,
+            serde_json::to_string_pretty(&json!({ "mcpServers": entries }))?,
+        )
+        .with_context(|| format!("Failed to write MCP config {:?}", config_path))?;
+        Ok(Some(Self {
+            config_path,
+            names: entries.keys().cloned().collect(),
+        }))
+    }
+
+    /// File passed to the Claude CLI's `--mcp-config`
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    pub fn server_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// `--allowedTools` entries allowing every tool of every server
+    pub fn allowed_tools(&self) -> Vec<String> {
+        self.names
+            .iter()
+            .map(|name| format!("{}{}", MCP_TOOL_PREFIX, name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A stdio server answering `initialize` and `tools/list`
+    fn fake_server(name: &str) -> McpServerConfig {
+        let script = r#"while read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","capabilities":{"tools":{}},"serverInfo":{"name":"fake","version":"1"}}}' ;;
+    *'"tools/list"'*) echo '{"jsonrpc":"2.0","method":"notifications/message","params":{}}'; echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"query","description":"Run SQL","inputSchema":{"type":"object"}}]}}' ;;
+  esac
+done"#;
+        McpServerConfig {
+            name: name.to_string(),
+            command: Some("sh".to_string()),
+            args: vec!["-c".to_string(), script.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_lists_tools() {
+        let tools = list_tools(&fake_server("db"), Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(
+            tools,
+            vec![McpTool {
+                name: "query".to_string(),
+                description: Some("Run SQL".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_writes_config_and_drops_failing_servers() {
+        let dir = TempDir::new().unwrap();
+        let config_path = dir.path().join("mcp-config.json");
+        let config = McpConfig {
+            servers: vec![
+                fake_server("db"),
+                McpServerConfig {
+                    name: "broken".to_string(),
+                    command: Some("false".to_string()),
+                    ..Default::default()
+                },
+                McpServerConfig {
+                    name: "tickets".to_string(),
+                    url: Some("https://mcp.example.com/mcp".to_string()),
+                    ..Default::default()
+                },
+            ],
+            probe_timeout_secs: 10,
+            ..Default::default()
+        };
+
+        let servers = McpServers::start(&config, config_path.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(servers.allowed_tools(), vec!["mcp__db", "mcp__tickets"]);
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(written["mcpServers"]["db"]["command"], "sh");
+        assert_eq!(written["mcpServers"]["tickets"]["type"], "http");
+        assert!(written["mcpServers"].get("broken").is_none());
+
+        assert_eq!(parse_tool_name("mcp__db__query"), Some(("db", "query")));
+        assert_eq!(parse_tool_name("Bash"), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_declarations() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("mcp-config.json");
+        let mut config = McpConfig {
+            servers: vec![McpServerConfig {
+                name: "my__db".to_string(),
+                command: Some("db-mcp".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(McpServers::start(&config, path.clone()).await.is_err());
+
+        config.servers = vec![fake_server("db"), fake_server("db")];
+        assert!(McpServers::start(&config, path.clone()).await.is_err());
+
+        config.servers.clear();
+        assert!(McpServers::start(&config, path).await.unwrap().is_none());
+    }
+}
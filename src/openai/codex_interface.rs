@@ -55,6 +55,7 @@ impl OpenAICodexInterface {
             .map_err(|e| OpenAIError::Unknown(format!("Failed to log command start: {}", e)))?;
 
         let composed_prompt = self.compose_prompt(&request);
+        logger.save_prompt(&ctx, &composed_prompt).await.ok();
 
         // Save reproducible command script
         if logger.is_command_tracking_enabled() {
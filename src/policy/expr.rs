@@ -0,0 +1,464 @@
+//! The policy rule expression language.
+//!
+//! A rule's `when` is a boolean expression over a task's facts:
+//!
+//! ```text
+//! task.cost > 2.5
+//! task.files matches 'secrets/**' or task.files matches '**/.env'
+//! 'deploy' in task.tags and not 'approved' in task.tags
+//! task.provider not in ['ClaudeCode', 'Anthropic']
+//! ```
+//!
+//! Operands are fields, string literals in single or double quotes,
+//! numbers, `true`/`false` and `[...]` lists. Comparisons are `==`, `!=`,
+//! `<`, `<=`, `>`, `>=`, `in`, `not in`, `contains` and `matches` (a glob
+//! where `*` stays within a path segment and `**` spans segments), combined
+//! with `and`, `or`, `not` and parentheses. When the left side is a list,
+//! such as `task.tags`, a comparison holds if it holds for any element.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A value a field or literal evaluates to
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "'{}'", s),
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+/// Why an expression could not be evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EvalError {
+    /// A field the expression uses is not known at this point
+    Unknown(String),
+    /// Operands of the wrong type for an operator
+    Type(String),
+}
+
+/// A comparison operator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    NotIn,
+    Contains,
+    Matches,
+}
+
+/// A parsed expression
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Literal(Value),
+    Field(String),
+    List(Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Expr {
+    /// Parse `source`, accepting only the field names in `fields`
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            fields,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {} after the expression", token)),
+        }
+    }
+
+    /// Whether the expression holds for the field values in `facts`
+    pub fn matches(&self, facts: &HashMap<&str, Value>) -> Result<bool, EvalError> {
+        match self.eval(facts)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(EvalError::Type(format!("{} is not true or false", other))),
+        }
+    }
+
+    fn eval(&self, facts: &HashMap<&str, Value>) -> Result<Value, EvalError> {
+        Ok(match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Field(name) => facts
+                .get(name.as_str())
+                .cloned()
+                .ok_or_else(|| EvalError::Unknown(name.clone()))?,
+            Expr::List(items) => Value::List(
+                items
+                    .iter()
+                    .map(|item| item.eval(facts))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::Not(inner) => Value::Bool(!inner.matches(facts)?),
+            Expr::And(left, right) => Value::Bool(left.matches(facts)? && right.matches(facts)?),
+            Expr::Or(left, right) => Value::Bool(left.matches(facts)? || right.matches(facts)?),
+            Expr::Compare(left, op, right) => {
+                Value::Bool(compare(&left.eval(facts)?, *op, &right.eval(facts)?)?)
+            }
+        })
+    }
+}
+
+fn compare(left: &Value, op: Op, right: &Value) -> Result<bool, EvalError> {
+    // A list on the left holds if any element does; `!=` and `not in` hold
+    // only if no element matches
+    if let Value::List(items) = left
+        && !matches!(op, Op::Contains)
+    {
+        let positive = match op {
+            Op::Ne => Op::Eq,
+            Op::NotIn => Op::In,
+            other => other,
+        };
+        let mut any = false;
+        for item in items {
+            any |= compare(item, positive, right)?;
+        }
+        return Ok(if positive == op { any } else { !any });
+    }
+
+    match op {
+        Op::Eq => Ok(left == right),
+        Op::Ne => Ok(left != right),
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+            let (Value::Num(a), Value::Num(b)) = (left, right) else {
+                return Err(EvalError::Type(format!(
+                    "{} and {} are not both numbers",
+                    left, right
+                )));
+            };
+            Ok(match op {
+                Op::Lt => a < b,
+                Op::Le => a <= b,
+                Op::Gt => a > b,
+                _ => a >= b,
+            })
+        }
+        Op::In => contains(right, left),
+        Op::NotIn => Ok(!contains(right, left)?),
+        Op::Contains => contains(left, right),
+        Op::Matches => match (left, right) {
+            (Value::Str(text), Value::Str(pattern)) => Ok(glob(pattern).is_match(text)),
+            _ => Err(EvalError::Type(format!(
+                "{} matches {}: both sides must be strings",
+                left, right
+            ))),
+        },
+    }
+}
+
+/// Whether `haystack`, a list or string, contains `needle`
+fn contains(haystack: &Value, needle: &Value) -> Result<bool, EvalError> {
+    match (haystack, needle) {
+        (Value::List(items), needle) => Ok(items.contains(needle)),
+        (Value::Str(text), Value::Str(part)) => Ok(text.contains(part.as_str())),
+        _ => Err(EvalError::Type(format!(
+            "{} cannot contain {}",
+            haystack, needle
+        ))),
+    }
+}
+
+/// Regex for a path glob
+fn glob(pattern: &str) -> Regex {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directories at all
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).expect("escaped glob is a valid regex")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Str(s) => write!(f, "string '{}'", s),
+            Token::Num(n) => write!(f, "number {}", n),
+            Token::Symbol(s) => write!(f, "'{}'", s),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &["==", "!=", "<=", ">=", "<", ">", "(", ")", "[", "]", ","];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if c == '\'' || c == '"' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| format!("unterminated string starting at {}", rest))?;
+            tokens.push(Token::Str(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", &rest[..end]))?;
+            tokens.push(Token::Num(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    fields: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(name)) if name == word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        let left = self.operand()?;
+        let op = if self.symbol("==") {
+            Op::Eq
+        } else if self.symbol("!=") {
+            Op::Ne
+        } else if self.symbol("<=") {
+            Op::Le
+        } else if self.symbol(">=") {
+            Op::Ge
+        } else if self.symbol("<") {
+            Op::Lt
+        } else if self.symbol(">") {
+            Op::Gt
+        } else if self.keyword("in") {
+            Op::In
+        } else if self.keyword("contains") {
+            Op::Contains
+        } else if self.keyword("matches") {
+            Op::Matches
+        } else if matches!(self.tokens.get(self.pos..self.pos + 2),
+            Some([Token::Ident(not), Token::Ident(is_in)]) if not == "not" && is_in == "in")
+        {
+            self.pos += 2;
+            Op::NotIn
+        } else {
+            return Ok(left);
+        };
+        let right = self.operand()?;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+    }
+
+    fn operand(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::Str(s))),
+            Some(Token::Num(n)) => Ok(Expr::Literal(Value::Num(n))),
+            Some(Token::Ident(name)) if name == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Ident(name)) if name == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Ident(name)) if self.fields.contains(&name.as_str()) => {
+                Ok(Expr::Field(name))
+            }
+            Some(Token::Ident(name)) => Err(format!(
+                "unknown field '{}'; expected one of {}",
+                name,
+                self.fields.join(", ")
+            )),
+            Some(Token::Symbol("(")) => {
+                let expr = self.or()?;
+                if !self.symbol(")") {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::Symbol("[")) => {
+                let mut items = Vec::new();
+                if self.symbol("]") {
+                    return Ok(Expr::List(items));
+                }
+                loop {
+                    items.push(self.operand()?);
+                    if self.symbol("]") {
+                        return Ok(Expr::List(items));
+                    }
+                    if !self.symbol(",") {
+                        return Err("expected ',' or ']' in list".to_string());
+                    }
+                }
+            }
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("expression ends early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[&str] = &["task.tags", "task.cost", "task.files", "task.provider"];
+
+    fn facts() -> HashMap<&'static str, Value> {
+        HashMap::from([
+            (
+                "task.tags",
+                Value::List(vec![
+                    Value::Str("deploy".into()),
+                    Value::Str("backend".into()),
+                ]),
+            ),
+            ("task.cost", Value::Num(3.2)),
+            (
+                "task.files",
+                Value::List(vec![
+                    Value::Str("src/main.rs".into()),
+                    Value::Str("config/secrets/prod.env".into()),
+                ]),
+            ),
+        ])
+    }
+
+    fn holds(source: &str) -> Result<bool, EvalError> {
+        Expr::parse(source, FIELDS).unwrap().matches(&facts())
+    }
+
+    #[test]
+    fn test_operators_over_lists_and_numbers() {
+        assert_eq!(holds("task.cost > 2.5"), Ok(true));
+        assert_eq!(holds("task.cost <= 2.5"), Ok(false));
+        assert_eq!(
+            holds("'deploy' in task.tags and not 'approved' in task.tags"),
+            Ok(true)
+        );
+        assert_eq!(holds("task.tags contains 'frontend'"), Ok(false));
+        assert_eq!(holds("task.tags == 'backend'"), Ok(true));
+        assert_eq!(holds("task.tags not in ['deploy', 'backend']"), Ok(false));
+        assert_eq!(holds("task.files matches '**/secrets/**'"), Ok(true));
+        assert_eq!(holds("task.files matches 'src/*'"), Ok(true));
+        assert_eq!(holds("task.files matches '*.env'"), Ok(false));
+        assert_eq!(
+            holds("(task.cost < 1 or 'x' in task.tags) == false"),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_and_type_errors() {
+        assert_eq!(
+            holds("task.provider not in ['ClaudeCode']"),
+            Err(EvalError::Unknown("task.provider".to_string()))
+        );
+        assert!(matches!(holds("task.tags > 1"), Err(EvalError::Type(_))));
+        assert!(matches!(holds("task.cost"), Err(EvalError::Type(_))));
+
+        let error = Expr::parse("task.owner == 'me'", FIELDS).unwrap_err();
+        assert!(error.starts_with("unknown field 'task.owner'"), "{}", error);
+        assert!(Expr::parse("task.cost >", FIELDS).is_err());
+        assert!(Expr::parse("'open", FIELDS).is_err());
+        assert!(Expr::parse("task.cost > 1 task.cost", FIELDS).is_err());
+    }
+}
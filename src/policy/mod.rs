@@ -0,0 +1,446 @@
+//! Organizational guardrails checked before tasks are created and executed.
+//!
+//! Rules are declared in configuration and evaluated against facts about a
+//! task: its title, tags, priority and files, the provider and model it is
+//! routed to, and what it has cost so far. A matching rule either denies
+//! the task outright or holds it until someone approves it. Every match is
+//! written to the event journal as an audit trail.
+//!
+//! ```toml
+//! [[policy.rules]]
+//! name = "no-secrets"
+//! when = "task.files matches '**/secrets/**'"
+//! effect = "deny"
+//! message = "Tasks may not touch secrets"
+//!
+//! [[policy.rules]]
+//! name = "approved-providers"
+//! when = "task.provider not in ['ClaudeCode', 'Anthropic']"
+//!
+//! [[policy.rules]]
+//! name = "expensive-tasks"
+//! when = "task.cost > 5 and not 'approved' in task.tags"
+//! effect = "require_approval"
+//! ```
+
+mod expr;
+
+use crate::llm::{ProviderType, Route};
+use crate::session::{EventJournal, JournalEvent};
+use crate::task::TaskMetadata;
+use anyhow::{Context, Result};
+use expr::{EvalError, Expr, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Fields a rule's `when` expression may refer to
+pub const POLICY_FIELDS: &[&str] = &[
+    "stage",
+    "task.title",
+    "task.description",
+    "task.tags",
+    "task.priority",
+    "task.files",
+    "task.provider",
+    "task.model",
+    "task.cost",
+    "session.cost",
+];
+
+/// Policy rules checked against every task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    pub rules: Vec<PolicyRule>,
+}
+
+/// One named rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub name: String,
+    /// Expression over task facts that triggers the rule, see [`POLICY_FIELDS`]
+    pub when: String,
+    #[serde(default)]
+    pub effect: PolicyEffect,
+    /// Explanation shown to whoever hits the rule
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// What happens to a task that matches a rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEffect {
+    /// Refuse the task
+    #[default]
+    Deny,
+    /// Block the task until it is approved
+    RequireApproval,
+}
+
+/// Where in a task's life a policy is checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyStage {
+    /// Before the task is added to the tree
+    Create,
+    /// Before each turn on a provider
+    Execute,
+}
+
+impl PolicyStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyStage::Create => "create",
+            PolicyStage::Execute => "execute",
+        }
+    }
+}
+
+/// A rule a task matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyViolation {
+    pub rule: String,
+    pub effect: PolicyEffect,
+    pub message: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.effect {
+            PolicyEffect::Deny => write!(f, "Denied by policy '{}': {}", self.rule, self.message),
+            PolicyEffect::RequireApproval => write!(
+                f,
+                "Awaiting approval: policy '{}': {}",
+                self.rule, self.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+/// What is known about a task when a policy is checked
+///
+/// Facts left unset are unknown at that stage; a rule that needs one is
+/// skipped rather than treated as matching.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyFacts {
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub priority: String,
+    pub files: Vec<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub task_cost: Option<f64>,
+    pub session_cost: Option<f64>,
+}
+
+impl PolicyFacts {
+    /// Facts known before a task runs, including any provider it asks for
+    pub fn for_task(title: &str, description: &str, metadata: &TaskMetadata) -> Self {
+        Self {
+            title: title.to_string(),
+            description: description.to_string(),
+            tags: metadata.tags.clone(),
+            priority: format!("{:?}", metadata.priority).to_lowercase(),
+            files: metadata
+                .file_refs
+                .iter()
+                .map(|file| file.path.display().to_string())
+                .collect(),
+            provider: metadata
+                .provider_preference
+                .provider
+                .as_ref()
+                .map(provider_name),
+            model: metadata.provider_preference.model.clone(),
+            task_cost: None,
+            session_cost: None,
+        }
+    }
+
+    /// Add the provider and model the task was routed to
+    pub fn with_route(mut self, route: &Route) -> Self {
+        self.provider = Some(provider_name(&route.provider));
+        self.model = route.model.clone().or(self.model);
+        self
+    }
+
+    /// Add what the task and the session have spent so far
+    pub fn with_costs(mut self, task_cost: f64, session_cost: f64) -> Self {
+        self.task_cost = Some(task_cost);
+        self.session_cost = Some(session_cost);
+        self
+    }
+
+    fn values(&self, stage: PolicyStage) -> HashMap<&'static str, Value> {
+        let strings = |items: &[String]| {
+            Value::List(items.iter().cloned().map(Value::Str).collect::<Vec<_>>())
+        };
+        let mut values = HashMap::from([
+            ("stage", Value::Str(stage.as_str().to_string())),
+            ("task.title", Value::Str(self.title.clone())),
+            ("task.description", Value::Str(self.description.clone())),
+            ("task.tags", strings(&self.tags)),
+            ("task.priority", Value::Str(self.priority.clone())),
+            ("task.files", strings(&self.files)),
+        ]);
+        if let Some(provider) = &self.provider {
+            values.insert("task.provider", Value::Str(provider.clone()));
+        }
+        if let Some(model) = &self.model {
+            values.insert("task.model", Value::Str(model.clone()));
+        }
+        if let Some(cost) = self.task_cost {
+            values.insert("task.cost", Value::Num(cost));
+        }
+        if let Some(cost) = self.session_cost {
+            values.insert("session.cost", Value::Num(cost));
+        }
+        values
+    }
+}
+
+/// A provider as rules name it: the config spelling, or a custom provider's name
+fn provider_name(provider: &ProviderType) -> String {
+    match provider {
+        ProviderType::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Parsed policy rules
+#[derive(Debug, Clone, Default)]
+pub struct PolicyEngine {
+    rules: Vec<(PolicyRule, Expr)>,
+}
+
+impl PolicyEngine {
+    /// Parse every rule, failing on the first malformed one
+    pub fn new(config: &PolicyConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                Expr::parse(&rule.when, POLICY_FIELDS)
+                    .map(|expr| (rule.clone(), expr))
+                    .map_err(anyhow::Error::msg)
+                    .with_context(|| format!("Invalid policy rule '{}'", rule.name))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Rules the facts match at `stage`, denials first
+    pub fn evaluate(&self, stage: PolicyStage, facts: &PolicyFacts) -> Vec<PolicyViolation> {
+        let values = facts.values(stage);
+        let mut violations: Vec<PolicyViolation> = self
+            .rules
+            .iter()
+            .filter(|(rule, expr)| match expr.matches(&values) {
+                Ok(matched) => matched,
+                Err(EvalError::Unknown(field)) => {
+                    debug!(
+                        "Skipping policy '{}' at {}: {} is not known yet",
+                        rule.name,
+                        stage.as_str(),
+                        field
+                    );
+                    false
+                }
+                Err(EvalError::Type(reason)) => {
+                    warn!("Skipping policy '{}': {}", rule.name, reason);
+                    false
+                }
+            })
+            .map(|(rule, _)| PolicyViolation {
+                rule: rule.name.clone(),
+                effect: rule.effect,
+                message: rule
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| format!("matched {}", rule.when)),
+            })
+            .collect();
+        violations.sort_by_key(|violation| violation.effect != PolicyEffect::Deny);
+        violations
+    }
+}
+
+/// Checks tasks against the policy engine and journals every match
+#[derive(Debug, Clone)]
+pub struct PolicyGate {
+    engine: PolicyEngine,
+    journal: EventJournal,
+}
+
+impl PolicyGate {
+    pub fn new(engine: PolicyEngine, journal: EventJournal) -> Self {
+        Self { engine, journal }
+    }
+
+    /// The first rule that stops the task, if any
+    pub fn check(
+        &self,
+        stage: PolicyStage,
+        task_id: Option<Uuid>,
+        facts: &PolicyFacts,
+    ) -> std::result::Result<(), PolicyViolation> {
+        if self.engine.is_empty() {
+            return Ok(());
+        }
+        let violations = self.engine.evaluate(stage, facts);
+        for violation in &violations {
+            warn!("Task '{}': {}", facts.title, violation);
+            if let Err(e) = self.journal.record(JournalEvent::PolicyViolated {
+                stage,
+                rule: violation.rule.clone(),
+                effect: violation.effect,
+                task_id,
+                task_title: facts.title.clone(),
+                message: violation.message.clone(),
+            }) {
+                warn!("Failed to journal policy violation: {}", e);
+            }
+        }
+        match violations.into_iter().next() {
+            Some(violation) => Err(violation),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{FileImportance, FileRef};
+    use tempfile::TempDir;
+
+    fn rule(name: &str, when: &str, effect: PolicyEffect) -> PolicyRule {
+        PolicyRule {
+            name: name.to_string(),
+            when: when.to_string(),
+            effect,
+            message: None,
+        }
+    }
+
+    fn facts(tags: &[&str], files: &[&str]) -> PolicyFacts {
+        let metadata = TaskMetadata {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            file_refs: files
+                .iter()
+                .map(|path| FileRef {
+                    path: path.into(),
+                    repository: "main".to_string(),
+                    line_range: None,
+                    importance: FileImportance::Medium,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        PolicyFacts::for_task("Rotate keys", "Rotate the deploy keys", &metadata)
+    }
+
+    #[test]
+    fn test_rules_from_toml_and_evaluation_order() {
+        let config: PolicyConfig = toml::from_str(
+            r#"
+            [[rules]]
+            name = "expensive"
+            when = "task.cost > 5"
+            effect = "require_approval"
+
+            [[rules]]
+            name = "no-secrets"
+            when = "task.files matches '**/secrets/**'"
+            message = "Tasks may not touch secrets"
+
+            [[rules]]
+            name = "approved-providers"
+            when = "task.provider not in ['ClaudeCode', 'Anthropic']"
+            "#,
+        )
+        .unwrap();
+        let engine = PolicyEngine::new(&config).unwrap();
+
+        // Cost and provider are unknown at creation, so only the path rule applies
+        let task = facts(&[], &["config/secrets/prod.env"]);
+        let violations = engine.evaluate(PolicyStage::Create, &task);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "no-secrets");
+        assert_eq!(
+            violations[0].to_string(),
+            "Denied by policy 'no-secrets': Tasks may not touch secrets"
+        );
+
+        let route = Route {
+            provider: ProviderType::OpenAIApi,
+            model: None,
+        };
+        let task = facts(&[], &["src/lib.rs"])
+            .with_route(&route)
+            .with_costs(7.5, 20.0);
+        let names: Vec<_> = engine
+            .evaluate(PolicyStage::Execute, &task)
+            .into_iter()
+            .map(|violation| violation.rule)
+            .collect();
+        assert_eq!(names, vec!["approved-providers", "expensive"]);
+    }
+
+    #[test]
+    fn test_invalid_rule_is_rejected_at_startup() {
+        let config = PolicyConfig {
+            rules: vec![rule("typo", "task.tag contains 'x'", PolicyEffect::Deny)],
+        };
+        let error = format!("{:#}", PolicyEngine::new(&config).unwrap_err());
+        assert!(error.contains("Invalid policy rule 'typo'"), "{}", error);
+        assert!(error.contains("unknown field 'task.tag'"), "{}", error);
+    }
+
+    #[test]
+    fn test_gate_journals_violations() {
+        let workspace = TempDir::new().unwrap();
+        let journal = EventJournal::for_workspace(workspace.path());
+        let config = PolicyConfig {
+            rules: vec![rule(
+                "deploys-need-approval",
+                "'deploy' in task.tags and not 'approved' in task.tags",
+                PolicyEffect::RequireApproval,
+            )],
+        };
+        let gate = PolicyGate::new(PolicyEngine::new(&config).unwrap(), journal.clone());
+
+        let task_id = Uuid::new_v4();
+        let violation = gate
+            .check(PolicyStage::Create, Some(task_id), &facts(&["deploy"], &[]))
+            .unwrap_err();
+        assert_eq!(violation.effect, PolicyEffect::RequireApproval);
+        assert!(
+            gate.check(
+                PolicyStage::Create,
+                None,
+                &facts(&["deploy", "approved"], &[])
+            )
+            .is_ok()
+        );
+
+        let entries = journal.load().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].event,
+            JournalEvent::PolicyViolated { rule, task_id: Some(id), .. }
+                if rule == "deploys-need-approval" && *id == task_id
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+//! Cross-session cost ledger for chargeback reporting.
+//!
+//! Every processed task appends one JSON line to
+//! `.aca/usage/cost_ledger.jsonl` with its token usage, estimated cost and
+//! cost-center tags (task tags prefixed with `cost:`). The ledger outlives
+//! individual sessions, so `aca usage --by-tag` can break down spend per team
+//! across every run in the workspace.
+
+use crate::claude::types::TokenUsage;
+use crate::claude::usage_tracker::UNTAGGED_COST_TAG;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Usage and cost of a single processed task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostLedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub tags: Vec<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    pub cost: f64,
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
+    #[serde(default)]
+    pub cache_savings: f64,
+}
+
+impl CostLedgerEntry {
+    pub fn new(
+        session_id: String,
+        task_id: Uuid,
+        task_title: String,
+        tags: Vec<String>,
+        usage: &TokenUsage,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            task_id,
+            task_title,
+            tags,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            total_tokens: usage.total_tokens,
+            cost: usage.estimated_cost,
+            cache_read_tokens: usage.cache_read_tokens,
+            cache_creation_tokens: usage.cache_creation_tokens,
+            cache_savings: usage.cache_savings,
+        }
+    }
+}
+
+/// Aggregated spend for one cost tag
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagCostSummary {
+    pub tag: String,
+    pub task_count: u32,
+    pub session_count: u32,
+    pub total_tokens: u64,
+    pub total_cost: f64,
+}
+
+/// Append-only JSONL ledger stored in the workspace
+#[derive(Debug, Clone)]
+pub struct CostLedger {
+    path: PathBuf,
+}
+
+impl CostLedger {
+    /// Ledger for a workspace (`.aca/usage/cost_ledger.jsonl`)
+    pub fn for_workspace(workspace_root: &Path) -> Self {
+        Self::new(crate::env::cost_ledger_file_path(workspace_root))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one entry to the ledger
+    pub fn append(&self, entry: &CostLedgerEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create usage directory {:?}", parent))?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open cost ledger {:?}", self.path))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)
+            .with_context(|| format!("Failed to write cost ledger {:?}", self.path))?;
+        Ok(())
+    }
+
+    /// Read all entries, skipping malformed lines
+    pub fn load(&self) -> Result<Vec<CostLedgerEntry>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {:?}", self.path));
+            }
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!("Skipping malformed cost ledger line: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// Break down spend per cost tag, most expensive first
+///
+/// Entries with several tags count fully towards each of them; untagged
+/// entries are grouped under [`UNTAGGED_COST_TAG`].
+pub fn summarize_by_tag(entries: &[CostLedgerEntry]) -> Vec<TagCostSummary> {
+    let mut summaries: HashMap<String, (TagCostSummary, Vec<&str>)> = HashMap::new();
+
+    for entry in entries {
+        let untagged = [UNTAGGED_COST_TAG.to_string()];
+        let tags = if entry.tags.is_empty() {
+            &untagged[..]
+        } else {
+            &entry.tags[..]
+        };
+
+        for tag in tags {
+            let (summary, sessions) = summaries.entry(tag.clone()).or_insert_with(|| {
+                (
+                    TagCostSummary {
+                        tag: tag.clone(),
+                        ..Default::default()
+                    },
+                    Vec::new(),
+                )
+            });
+            summary.task_count += 1;
+            summary.total_tokens += entry.total_tokens;
+            summary.total_cost += entry.cost;
+            if !sessions.contains(&entry.session_id.as_str()) {
+                sessions.push(&entry.session_id);
+            }
+        }
+    }
+
+    let mut result: Vec<TagCostSummary> = summaries
+        .into_values()
+        .map(|(mut summary, sessions)| {
+            summary.session_count = sessions.len() as u32;
+            summary
+        })
+        .collect();
+    result.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(session: &str, tags: &[&str], cost: f64) -> CostLedgerEntry {
+        CostLedgerEntry::new(
+            session.to_string(),
+            Uuid::new_v4(),
+            "task".to_string(),
+            tags.iter().map(|t| t.to_string()).collect(),
+            &TokenUsage {
+                input_tokens: 100,
+                output_tokens: 50,
+                total_tokens: 150,
+                estimated_cost: cost,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_ledger_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let ledger = CostLedger::for_workspace(temp.path());
+        assert!(ledger.load().unwrap().is_empty());
+
+        ledger.append(&entry("s1", &["platform"], 0.5)).unwrap();
+        ledger.append(&entry("s2", &[], 0.25)).unwrap();
+
+        let entries = ledger.load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tags, vec!["platform".to_string()]);
+    }
+
+    #[test]
+    fn test_summarize_by_tag() {
+        let entries = vec![
+            entry("s1", &["platform"], 1.0),
+            entry("s2", &["platform", "search"], 2.0),
+            entry("s2", &[], 0.5),
+        ];
+
+        let summary = summarize_by_tag(&entries);
+        assert_eq!(summary[0].tag, "platform");
+        assert_eq!(summary[0].task_count, 2);
+        assert_eq!(summary[0].session_count, 2);
+        assert!((summary[0].total_cost - 3.0).abs() < f64::EPSILON);
+        assert_eq!(summary[1].tag, "search");
+        assert_eq!(summary[2].tag, UNTAGGED_COST_TAG);
+    }
+}
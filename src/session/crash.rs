@@ -0,0 +1,382 @@
+//! Crash reports for panics.
+//!
+//! [`install`] adds a panic hook that writes the panic message, a
+//! backtrace, the most recent log lines and the sanitized configuration to
+//! `.aca/crash-reports/<timestamp>.json`, tells the user how to file an
+//! issue and marks the running session dirty. The next agent started in the
+//! workspace takes the marker and recovers that session from its latest
+//! checkpoint.
+
+use crate::env;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Log lines kept for crash reports
+pub const LOG_TAIL_LINES: usize = 200;
+
+/// Config keys whose string values are left out of reports
+const SECRET_KEY_PARTS: &[&str] = &["key", "token", "secret", "password", "credential", "auth"];
+
+const REDACTED: &str = "[redacted]";
+
+/// What the panic hook knows about the running agent
+struct CrashContext {
+    workspace: Option<PathBuf>,
+    session_id: Option<String>,
+    config: Option<serde_json::Value>,
+    log_tail: Option<LogTail>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    workspace: None,
+    session_id: None,
+    config: None,
+    log_tail: None,
+});
+
+fn context() -> MutexGuard<'static, CrashContext> {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Install the panic hook, reporting `log_tail`'s lines with each crash
+///
+/// The previously installed hook still runs afterwards.
+pub fn install(log_tail: Option<LogTail>) {
+    context().log_tail = log_tail;
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::new(
+            panic_message(info.payload()),
+            info.location().map(|location| location.to_string()),
+        );
+        report.save_and_notify();
+        previous(info);
+    }));
+}
+
+/// Record the session and configuration that crash reports describe
+pub fn set_session(workspace: &Path, session_id: &str, config: &impl Serialize) {
+    let mut context = context();
+    context.workspace = Some(workspace.to_path_buf());
+    context.session_id = Some(session_id.to_string());
+    context.config = serde_json::to_value(config).ok().map(sanitize);
+}
+
+/// A panic, written to the workspace's crash reports directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub crashed_at: DateTime<Utc>,
+    pub version: String,
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    /// Most recent log lines, oldest first
+    pub log_tail: Vec<String>,
+    pub session_id: Option<String>,
+    /// Configuration with secrets redacted
+    pub config: Option<serde_json::Value>,
+}
+
+impl CrashReport {
+    /// Capture a report of a panic on the current thread
+    pub fn new(message: String, location: Option<String>) -> Self {
+        let context = context();
+        Self {
+            crashed_at: Utc::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            message,
+            location,
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            log_tail: context
+                .log_tail
+                .as_ref()
+                .map(LogTail::lines)
+                .unwrap_or_default(),
+            session_id: context.session_id.clone(),
+            config: context.config.clone(),
+        }
+    }
+
+    /// Write the report under `workspace` and mark its session dirty
+    pub fn save(&self, workspace: &Path) -> io::Result<PathBuf> {
+        let dir = env::crash_reports_dir_path(workspace);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "{}.json",
+            self.crashed_at.format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+
+        if let Some(session_id) = &self.session_id {
+            DirtySession {
+                session_id: session_id.clone(),
+                crash_report: path.clone(),
+                crashed_at: self.crashed_at,
+            }
+            .save(workspace)?;
+        }
+        Ok(path)
+    }
+
+    /// Save the report and tell the user what to do with it
+    fn save_and_notify(&self) {
+        let workspace = context()
+            .workspace
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        eprintln!("\naca crashed: {}", self.message);
+        match self.save(&workspace) {
+            Ok(path) => {
+                eprintln!("A crash report was saved to {}", path.display());
+                eprintln!(
+                    "Please file an issue at {}/issues/new and attach the report, after checking it for anything private.",
+                    env!("CARGO_PKG_REPOSITORY")
+                );
+                if let Some(session_id) = &self.session_id {
+                    eprintln!(
+                        "Session {} will be recovered from its latest checkpoint on the next run.",
+                        session_id
+                    );
+                }
+            }
+            Err(e) => eprintln!("Failed to write a crash report: {}", e),
+        }
+    }
+}
+
+/// Marker naming the session a crash interrupted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirtySession {
+    pub session_id: String,
+    pub crash_report: PathBuf,
+    pub crashed_at: DateTime<Utc>,
+}
+
+impl DirtySession {
+    fn save(&self, workspace: &Path) -> io::Result<()> {
+        std::fs::write(
+            env::dirty_session_file_path(workspace),
+            serde_json::to_vec_pretty(self)?,
+        )
+    }
+
+    /// Remove and return the marker a crash left in `workspace`, if any
+    pub fn take(workspace: &Path) -> Option<Self> {
+        let path = env::dirty_session_file_path(workspace);
+        let content = std::fs::read(&path).ok()?;
+        if let Err(e) = std::fs::remove_file(&path) {
+            tracing::warn!("Failed to remove dirty session marker {:?}: {}", path, e);
+        }
+        serde_json::from_slice(&content).ok()
+    }
+
+    /// Id of the dirty session's newest checkpoint
+    pub fn latest_checkpoint(&self, workspace: &Path) -> Option<String> {
+        std::fs::read_dir(env::session_checkpoints_dir_path(
+            workspace,
+            &self.session_id,
+        ))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .max_by_key(|path| {
+            path.metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+    }
+}
+
+/// The most recent log lines, fed by the logging subscriber
+#[derive(Debug, Clone, Default)]
+pub struct LogTail {
+    buffer: Arc<Mutex<TailBuffer>>,
+}
+
+#[derive(Debug, Default)]
+struct TailBuffer {
+    lines: VecDeque<String>,
+    /// Text written since the last newline
+    partial: String,
+}
+
+impl LogTail {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kept lines, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        let mut lines: Vec<String> = buffer.lines.iter().cloned().collect();
+        if !buffer.partial.trim().is_empty() {
+            lines.push(strip_ansi(&buffer.partial));
+        }
+        lines
+    }
+
+    fn push(&self, text: &str) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.partial.push_str(text);
+        while let Some(end) = buffer.partial.find('\n') {
+            let line: String = buffer.partial.drain(..=end).collect();
+            let line = strip_ansi(line.trim_end());
+            if line.trim().is_empty() {
+                continue;
+            }
+            if buffer.lines.len() == LOG_TAIL_LINES {
+                buffer.lines.pop_front();
+            }
+            buffer.lines.push_back(line);
+        }
+    }
+}
+
+impl io::Write for &LogTail {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.push(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogTail {
+    type Writer = &'a LogTail;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+/// Replace secret-looking string values in a serialized config
+pub fn sanitize(value: serde_json::Value) -> serde_json::Value {
+    redact(value, false)
+}
+
+fn redact(value: serde_json::Value, secret: bool) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::String(_) if secret => Value::String(REDACTED.to_string()),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|item| redact(item, secret)).collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let lowercase = key.to_lowercase();
+                    let secret =
+                        secret || SECRET_KEY_PARTS.iter().any(|part| lowercase.contains(part));
+                    (key, redact(value, secret))
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+/// Remove terminal color escape sequences
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip to the end of the sequence, e.g. `ESC[2m`
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_sanitize_redacts_secrets() {
+        let config = serde_json::json!({
+            "claude_config": { "api_key": "sk-123", "max_tokens_per_minute": 40000 },
+            "remote_sync": { "credentials": { "access": "AKIA", "region": "eu" } },
+            "workspace_path": "/src/app"
+        });
+        let sanitized = sanitize(config);
+        assert_eq!(sanitized["claude_config"]["api_key"], REDACTED);
+        assert_eq!(sanitized["claude_config"]["max_tokens_per_minute"], 40000);
+        assert_eq!(sanitized["remote_sync"]["credentials"]["region"], REDACTED);
+        assert_eq!(sanitized["workspace_path"], "/src/app");
+    }
+
+    #[test]
+    fn test_log_tail_keeps_recent_plain_lines() {
+        let tail = LogTail::new();
+        for index in 0..LOG_TAIL_LINES + 5 {
+            writeln!(
+                &tail,
+                "\u{1b}[2m2025-01-01\u{1b}[0m \u{1b}[32m INFO\u{1b}[0m line {}",
+                index
+            )
+            .unwrap();
+        }
+        let lines = tail.lines();
+        assert_eq!(lines.len(), LOG_TAIL_LINES);
+        assert_eq!(lines[0], "2025-01-01  INFO line 5");
+    }
+
+    #[test]
+    fn test_report_marks_session_dirty() {
+        let workspace = std::env::temp_dir().join(format!("aca-crash-{}", uuid::Uuid::new_v4()));
+        let checkpoints = env::session_checkpoints_dir_path(&workspace, "session-1");
+        std::fs::create_dir_all(&checkpoints).unwrap();
+        std::fs::write(checkpoints.join("checkpoint_a.json"), "{}").unwrap();
+
+        let mut report = CrashReport::new("boom".to_string(), Some("src/main.rs:1:1".to_string()));
+        report.session_id = Some("session-1".to_string());
+        let path = report.save(&workspace).unwrap();
+        let saved: CrashReport = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved.message, "boom");
+        assert!(!saved.backtrace.is_empty());
+
+        let dirty = DirtySession::take(&workspace).unwrap();
+        assert_eq!(dirty.session_id, "session-1");
+        assert_eq!(dirty.crash_report, path);
+        assert_eq!(
+            dirty.latest_checkpoint(&workspace).as_deref(),
+            Some("checkpoint_a")
+        );
+        assert_eq!(DirtySession::take(&workspace), None);
+    }
+}
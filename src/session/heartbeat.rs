@@ -0,0 +1,420 @@
+//! Heartbeat and liveness reporting for unattended runs.
+//!
+//! When enabled, the [`HeartbeatMonitor`] periodically writes a small JSON
+//! status file (timestamp, current task and progress) into the session
+//! directory and optionally POSTs the same payload to a ping URL, so external
+//! supervisors can tell a slow run from a hung one.
+//!
+//! The monitor also tracks the time since each running task's last recorded
+//! activity. Tasks running in parallel are tracked separately, so one busy
+//! task does not hide a hung sibling. If a stall timeout is configured,
+//! [`HeartbeatMonitor::wait_for_stall`] resolves once the given task has
+//! been silent for that long, allowing the caller to checkpoint and restart
+//! it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Configuration for heartbeat writing and stall detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartbeatConfig {
+    /// Enable periodic heartbeat reporting
+    pub enabled: bool,
+    /// Seconds between heartbeats
+    pub interval_secs: u64,
+    /// Heartbeat file location (defaults to `heartbeat.json` in the session directory)
+    pub file_path: Option<PathBuf>,
+    /// Optional URL that receives each heartbeat as a JSON POST
+    pub ping_url: Option<String>,
+    /// Silence window after which a running task is considered stalled
+    pub stall_timeout_secs: Option<u64>,
+    /// Maximum number of times a stalled task is restarted before failing it
+    pub max_stall_restarts: u32,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 30,
+            file_path: None,
+            ping_url: None,
+            stall_timeout_secs: None,
+            max_stall_restarts: 1,
+        }
+    }
+}
+
+/// Task being processed, as reported in the heartbeat
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HeartbeatTask {
+    pub id: Uuid,
+    pub title: String,
+    pub started_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    /// The task is deliberately paused, so silence is not a stall
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// Payload written to the heartbeat file and sent to the ping URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatStatus {
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    /// Tasks being processed, oldest first
+    pub running_tasks: Vec<HeartbeatTask>,
+    pub completed_tasks: u32,
+    pub total_tasks: u32,
+    pub stall_restarts: u32,
+}
+
+#[derive(Debug)]
+struct HeartbeatState {
+    last_activity: DateTime<Utc>,
+    running_tasks: HashMap<Uuid, HeartbeatTask>,
+    completed_tasks: u32,
+    total_tasks: u32,
+    stall_restarts: u32,
+}
+
+/// Tracks run liveness and publishes heartbeats
+#[derive(Debug)]
+pub struct HeartbeatMonitor {
+    config: HeartbeatConfig,
+    session_id: String,
+    file_path: PathBuf,
+    state: Mutex<HeartbeatState>,
+    client: reqwest::Client,
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl HeartbeatMonitor {
+    /// Create a monitor; `default_file_path` is used unless the config overrides it
+    pub fn new(config: HeartbeatConfig, session_id: String, default_file_path: PathBuf) -> Self {
+        let file_path = config.file_path.clone().unwrap_or(default_file_path);
+        Self {
+            config,
+            session_id,
+            file_path,
+            state: Mutex::new(HeartbeatState {
+                last_activity: Utc::now(),
+                running_tasks: HashMap::new(),
+                completed_tasks: 0,
+                total_tasks: 0,
+                stall_restarts: 0,
+            }),
+            client: reqwest::Client::new(),
+            writer: Mutex::new(None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Stall timeout, if stall detection is configured
+    pub fn stall_timeout(&self) -> Option<Duration> {
+        self.config.stall_timeout_secs.map(Duration::from_secs)
+    }
+
+    pub fn max_stall_restarts(&self) -> u32 {
+        self.config.max_stall_restarts
+    }
+
+    /// Record that a running task is making progress
+    pub fn record_activity(&self, id: Uuid) {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        state.last_activity = now;
+        if let Some(task) = state.running_tasks.get_mut(&id) {
+            task.last_activity = now;
+        }
+    }
+
+    /// Mark a task as being processed, or restart its silence window
+    pub fn task_started(&self, id: Uuid, title: &str) {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        state.last_activity = now;
+        state
+            .running_tasks
+            .entry(id)
+            .and_modify(|task| task.last_activity = now)
+            .or_insert_with(|| HeartbeatTask {
+                id,
+                title: title.to_string(),
+                started_at: now,
+                last_activity: now,
+                paused: false,
+            });
+    }
+
+    /// Stop tracking a task
+    pub fn task_finished(&self, id: Uuid) {
+        let mut state = self.state.lock().unwrap();
+        state.last_activity = Utc::now();
+        state.running_tasks.remove(&id);
+    }
+
+    /// Suspend or restart stall detection while a running task is paused
+    pub fn set_paused(&self, id: Uuid, paused: bool) {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        state.last_activity = now;
+        if let Some(task) = state.running_tasks.get_mut(&id) {
+            task.last_activity = now;
+            task.paused = paused;
+        }
+    }
+
+    /// Update overall progress counters
+    pub fn set_progress(&self, completed_tasks: u32, total_tasks: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.completed_tasks = completed_tasks;
+        state.total_tasks = total_tasks;
+    }
+
+    /// Count a stall-triggered restart
+    pub fn record_stall_restart(&self) {
+        self.state.lock().unwrap().stall_restarts += 1;
+    }
+
+    /// Snapshot of the current liveness state
+    pub fn status(&self) -> HeartbeatStatus {
+        let state = self.state.lock().unwrap();
+        let mut running_tasks: Vec<_> = state.running_tasks.values().cloned().collect();
+        running_tasks.sort_by_key(|task| task.started_at);
+        HeartbeatStatus {
+            session_id: self.session_id.clone(),
+            timestamp: Utc::now(),
+            last_activity: state.last_activity,
+            running_tasks,
+            completed_tasks: state.completed_tasks,
+            total_tasks: state.total_tasks,
+            stall_restarts: state.stall_restarts,
+        }
+    }
+
+    /// Whether a task is running and has been silent for longer than the stall timeout
+    pub fn is_stalled(&self, id: Uuid) -> bool {
+        self.silence_remaining(id) == Some(Duration::ZERO)
+    }
+
+    /// Time left before a running task counts as stalled
+    fn silence_remaining(&self, id: Uuid) -> Option<Duration> {
+        let timeout = self.stall_timeout()?;
+        let state = self.state.lock().unwrap();
+        let task = state.running_tasks.get(&id)?;
+        if task.paused {
+            return None;
+        }
+        let silent_for = (Utc::now() - task.last_activity)
+            .to_std()
+            .unwrap_or(Duration::ZERO);
+        Some(timeout.saturating_sub(silent_for))
+    }
+
+    /// Resolve once the task stalls; never resolves without a stall timeout
+    pub async fn wait_for_stall(&self, id: Uuid) {
+        let Some(timeout) = self.stall_timeout() else {
+            return std::future::pending().await;
+        };
+
+        loop {
+            match self.silence_remaining(id) {
+                Some(Duration::ZERO) => return,
+                Some(remaining) => tokio::time::sleep(remaining).await,
+                // Not running or paused; check again after a full window
+                None => tokio::time::sleep(timeout).await,
+            }
+        }
+    }
+
+    /// Write the heartbeat file and send the ping, if configured
+    pub async fn beat(&self) -> anyhow::Result<()> {
+        let status = self.status();
+
+        if let Some(parent) = self.file_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Write to a temp file first so readers never see a partial heartbeat
+        let tmp_path = self.file_path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(&status)?).await?;
+        tokio::fs::rename(&tmp_path, &self.file_path).await?;
+
+        if let Some(url) = &self.config.ping_url
+            && let Err(e) = self
+                .client
+                .post(url)
+                .json(&status)
+                .timeout(Duration::from_secs(10))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+        {
+            warn!("Heartbeat ping to {} failed: {}", url, e);
+        }
+
+        debug!("Heartbeat written to {:?}", self.file_path);
+        Ok(())
+    }
+
+    /// Spawn the periodic heartbeat writer (no-op when disabled or already running)
+    pub fn start(self: &std::sync::Arc<Self>) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut writer = self.writer.lock().unwrap();
+        if writer.is_some() {
+            return;
+        }
+
+        let monitor = self.clone();
+        let interval = Duration::from_secs(self.config.interval_secs.max(1));
+        info!(
+            "Starting heartbeat every {}s at {:?}",
+            interval.as_secs(),
+            self.file_path
+        );
+        *writer = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = monitor.beat().await {
+                    warn!("Failed to write heartbeat: {}", e);
+                }
+            }
+        }));
+    }
+
+    /// Stop the writer and publish a final heartbeat
+    pub async fn stop(&self) {
+        let handle = self.writer.lock().unwrap().take();
+        if let Some(handle) = handle {
+            handle.abort();
+            if let Err(e) = self.beat().await {
+                warn!("Failed to write final heartbeat: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn monitor(config: HeartbeatConfig, dir: &TempDir) -> HeartbeatMonitor {
+        HeartbeatMonitor::new(
+            config,
+            "test-session".to_string(),
+            dir.path().join("heartbeat.json"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_beat_writes_status_file() {
+        let temp = TempDir::new().unwrap();
+        let monitor = monitor(HeartbeatConfig::default(), &temp);
+        let task_id = Uuid::new_v4();
+        monitor.task_started(task_id, "Refactor parser");
+        monitor.set_progress(2, 5);
+
+        monitor.beat().await.unwrap();
+
+        let content = std::fs::read_to_string(monitor.file_path()).unwrap();
+        let status: HeartbeatStatus = serde_json::from_str(&content).unwrap();
+        assert_eq!(status.session_id, "test-session");
+        assert_eq!(status.running_tasks[0].id, task_id);
+        assert_eq!(status.completed_tasks, 2);
+        assert_eq!(status.total_tasks, 5);
+    }
+
+    #[tokio::test]
+    async fn test_stall_detection() {
+        let temp = TempDir::new().unwrap();
+        let config = HeartbeatConfig {
+            stall_timeout_secs: Some(0),
+            ..Default::default()
+        };
+        let monitor = monitor(config, &temp);
+
+        let task_id = Uuid::new_v4();
+
+        // Idle between tasks never counts as a stall
+        assert!(!monitor.is_stalled(task_id));
+
+        monitor.task_started(task_id, "Hung task");
+        assert!(monitor.is_stalled(task_id));
+        tokio::time::timeout(Duration::from_secs(1), monitor.wait_for_stall(task_id))
+            .await
+            .expect("stall should be detected");
+
+        // A paused task is silent on purpose
+        monitor.set_paused(task_id, true);
+        assert!(!monitor.is_stalled(task_id));
+        monitor.set_paused(task_id, false);
+        assert!(monitor.is_stalled(task_id));
+
+        monitor.task_finished(task_id);
+        assert!(!monitor.is_stalled(task_id));
+    }
+
+    #[test]
+    fn test_no_stall_without_timeout() {
+        let temp = TempDir::new().unwrap();
+        let monitor = monitor(HeartbeatConfig::default(), &temp);
+        let task_id = Uuid::new_v4();
+        monitor.task_started(task_id, "Long task");
+        assert!(!monitor.is_stalled(task_id));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_tasks_are_tracked_separately() {
+        let temp = TempDir::new().unwrap();
+        let config = HeartbeatConfig {
+            stall_timeout_secs: Some(1),
+            ..Default::default()
+        };
+        let monitor = monitor(config, &temp);
+        let (busy, hung) = (Uuid::new_v4(), Uuid::new_v4());
+        monitor.task_started(busy, "Busy task");
+        monitor.task_started(hung, "Hung task");
+
+        // The busy task's output does not hide its silent sibling
+        let keep_busy = async {
+            loop {
+                monitor.record_activity(busy);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        tokio::select! {
+            _ = monitor.wait_for_stall(hung) => {}
+            _ = monitor.wait_for_stall(busy) => panic!("the busy task was reported as stalled"),
+            _ = keep_busy => unreachable!(),
+            _ = tokio::time::sleep(Duration::from_secs(5)) => panic!("the hung task was not detected"),
+        }
+        assert!(!monitor.is_stalled(busy));
+
+        // Finishing one task leaves the other running
+        monitor.task_finished(hung);
+        let status = monitor.status();
+        assert_eq!(status.running_tasks.len(), 1);
+        assert_eq!(status.running_tasks[0].id, busy);
+    }
+}
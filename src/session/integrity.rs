@@ -0,0 +1,197 @@
+use ring::{digest, hmac};
+use std::fmt;
+
+/// Prefix of SHA-256 content hashes; checksums from older releases have none
+pub const SHA256_PREFIX: &str = "sha256:";
+
+/// Key used to sign checkpoint hashes with HMAC-SHA256
+#[derive(Clone)]
+pub struct SigningKey(hmac::Key);
+
+impl SigningKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self(hmac::Key::new(hmac::HMAC_SHA256, secret))
+    }
+
+    /// Hex signature of a content hash
+    pub fn sign(&self, content_hash: &str) -> String {
+        to_hex(hmac::sign(&self.0, content_hash.as_bytes()).as_ref())
+    }
+
+    pub fn verify(&self, content_hash: &str, signature: &str) -> bool {
+        from_hex(signature)
+            .is_some_and(|tag| hmac::verify(&self.0, content_hash.as_bytes(), &tag).is_ok())
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SigningKey(..)")
+    }
+}
+
+/// Outcome of checking a checkpoint against its recorded hash and signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointIntegrity {
+    Verified,
+    /// No hash was recorded, as for checkpoints written by older releases
+    Unverified,
+    /// The content no longer matches the hash recorded at creation
+    Corrupted {
+        expected: String,
+        actual: String,
+    },
+    /// Signing is configured but the signature is missing or invalid
+    BadSignature,
+}
+
+impl CheckpointIntegrity {
+    /// Whether the checkpoint may be restored
+    pub fn is_usable(&self) -> bool {
+        matches!(self, Self::Verified | Self::Unverified)
+    }
+}
+
+impl fmt::Display for CheckpointIntegrity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verified => f.write_str("verified"),
+            Self::Unverified => f.write_str("no recorded hash"),
+            Self::Corrupted { expected, actual } => {
+                write!(
+                    f,
+                    "content hash mismatch (expected {}, got {})",
+                    expected, actual
+                )
+            }
+            Self::BadSignature => f.write_str("missing or invalid signature"),
+        }
+    }
+}
+
+/// `sha256:<hex>` hash of `data`
+pub fn content_hash(data: &[u8]) -> String {
+    format!(
+        "{}{}",
+        SHA256_PREFIX,
+        to_hex(digest::digest(&digest::SHA256, data).as_ref())
+    )
+}
+
+/// Check `data` against the hash and signature recorded for it
+///
+/// Unprefixed hashes are the checksums written by older releases. With a
+/// signing key, anything without a valid signature is rejected.
+pub fn verify(
+    data: &[u8],
+    expected_hash: Option<&str>,
+    signature: Option<&str>,
+    key: Option<&SigningKey>,
+) -> CheckpointIntegrity {
+    let Some(expected) = expected_hash.map(str::trim) else {
+        return match key {
+            Some(_) => CheckpointIntegrity::BadSignature,
+            None => CheckpointIntegrity::Unverified,
+        };
+    };
+
+    let actual = if expected.starts_with(SHA256_PREFIX) {
+        content_hash(data)
+    } else {
+        legacy_checksum(data)
+    };
+    if actual != expected {
+        return CheckpointIntegrity::Corrupted {
+            expected: expected.to_string(),
+            actual,
+        };
+    }
+
+    match key {
+        Some(key) if !signature.is_some_and(|signature| key.verify(expected, signature.trim())) => {
+            CheckpointIntegrity::BadSignature
+        }
+        _ => CheckpointIntegrity::Verified,
+    }
+}
+
+/// Checksum format written before content hashes were SHA-256
+fn legacy_checksum(data: &[u8]) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_verification() {
+        let data = br#"{"task_tree": {}}"#;
+        let hash = content_hash(data);
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash.len(), SHA256_PREFIX.len() + 64);
+
+        assert_eq!(
+            verify(data, Some(&hash), None, None),
+            CheckpointIntegrity::Verified
+        );
+        assert_eq!(
+            verify(data, Some(&legacy_checksum(data)), None, None),
+            CheckpointIntegrity::Verified
+        );
+        assert_eq!(
+            verify(data, None, None, None),
+            CheckpointIntegrity::Unverified
+        );
+
+        let corrupted = verify(br#"{"task_tree": {]"#, Some(&hash), None, None);
+        assert!(matches!(corrupted, CheckpointIntegrity::Corrupted { .. }));
+        assert!(!corrupted.is_usable());
+    }
+
+    #[test]
+    fn test_signatures() {
+        let data = b"checkpoint";
+        let hash = content_hash(data);
+        let key = SigningKey::new(b"secret");
+        let signature = key.sign(&hash);
+
+        assert_eq!(
+            verify(data, Some(&hash), Some(&signature), Some(&key)),
+            CheckpointIntegrity::Verified
+        );
+        let other = SigningKey::new(b"other");
+        assert_eq!(
+            verify(data, Some(&hash), Some(&signature), Some(&other)),
+            CheckpointIntegrity::BadSignature
+        );
+        assert_eq!(
+            verify(data, Some(&hash), None, Some(&key)),
+            CheckpointIntegrity::BadSignature
+        );
+        assert_eq!(
+            verify(data, None, None, Some(&key)),
+            CheckpointIntegrity::BadSignature
+        );
+        assert!(!key.verify(&hash, "not hex"));
+    }
+}
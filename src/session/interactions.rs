@@ -0,0 +1,189 @@
+use crate::task::types::TaskId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// One provider call made for a task, as persisted in the session state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionRecord {
+    /// Request identifier shared with the interaction's log file names
+    pub request_id: Uuid,
+    pub task_id: TaskId,
+    pub provider: String,
+    pub model: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub estimated_cost: f64,
+    /// Exit code of the provider process, `None` if it never ran or was killed
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Log, output and command files written for this interaction
+    pub log_files: Vec<PathBuf>,
+    /// Size of the prompt sent, with its system message and history, and of
+    /// the output received
+    #[serde(default)]
+    pub payload: PayloadBytes,
+}
+
+/// Bytes sent to and received from a provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayloadBytes {
+    pub sent: u64,
+    pub received: u64,
+}
+
+impl std::ops::AddAssign for PayloadBytes {
+    fn add_assign(&mut self, other: Self) {
+        self.sent += other.sent;
+        self.received += other.received;
+    }
+}
+
+/// Warnings about oversized prompts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptSizeConfig {
+    /// Warn when a single prompt is larger than this many bytes; 0 never warns
+    pub warn_bytes: u64,
+}
+
+impl Default for PromptSizeConfig {
+    fn default() -> Self {
+        Self {
+            warn_bytes: 400_000,
+        }
+    }
+}
+
+impl PromptSizeConfig {
+    /// Whether `interaction` sent a prompt over the limit
+    pub fn is_oversized(&self, interaction: &InteractionRecord) -> bool {
+        self.warn_bytes > 0 && interaction.payload.sent > self.warn_bytes
+    }
+}
+
+impl InteractionRecord {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Interactions made for `task_id`, oldest first
+pub fn for_task(interactions: &[InteractionRecord], task_id: TaskId) -> Vec<&InteractionRecord> {
+    interactions
+        .iter()
+        .filter(|interaction| interaction.task_id == task_id)
+        .collect()
+}
+
+/// The `count` interactions that sent the largest prompts, largest first
+pub fn largest_prompts(
+    interactions: &[InteractionRecord],
+    count: usize,
+) -> Vec<&InteractionRecord> {
+    let mut largest: Vec<&InteractionRecord> = interactions.iter().collect();
+    largest.sort_by_key(|interaction| std::cmp::Reverse(interaction.payload.sent));
+    largest.truncate(count);
+    largest
+}
+
+/// Remove interactions that started before `cutoff` and return them
+///
+/// Retention policies use the returned records to delete their log files.
+pub fn prune_before(
+    interactions: &mut Vec<InteractionRecord>,
+    cutoff: DateTime<Utc>,
+) -> Vec<InteractionRecord> {
+    let (expired, kept) = std::mem::take(interactions)
+        .into_iter()
+        .partition(|interaction| interaction.started_at < cutoff);
+    *interactions = kept;
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn record(task_id: TaskId, started_at: DateTime<Utc>) -> InteractionRecord {
+        InteractionRecord {
+            request_id: Uuid::new_v4(),
+            task_id,
+            provider: "claude".to_string(),
+            model: "sonnet".to_string(),
+            started_at,
+            duration_ms: 1500,
+            input_tokens: 100,
+            output_tokens: 20,
+            estimated_cost: 0.01,
+            exit_code: Some(0),
+            success: true,
+            error: None,
+            log_files: vec![PathBuf::from("claude-1.log")],
+            payload: PayloadBytes {
+                sent: 4000,
+                received: 800,
+            },
+        }
+    }
+
+    #[test]
+    fn test_interactions_for_task() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Utc::now();
+        let interactions = vec![record(first, now), record(second, now), record(first, now)];
+
+        let found = for_task(&interactions, first);
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|interaction| interaction.task_id == first));
+        assert_eq!(found[0].total_tokens(), 120);
+    }
+
+    #[test]
+    fn test_prune_before() {
+        let task_id = Uuid::new_v4();
+        let now = Utc::now();
+        let mut interactions = vec![
+            record(task_id, now - Duration::days(10)),
+            record(task_id, now),
+        ];
+
+        let expired = prune_before(&mut interactions, now - Duration::days(7));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(interactions.len(), 1);
+        assert_eq!(interactions[0].started_at, now);
+    }
+
+    #[test]
+    fn test_largest_prompts_and_size_warning() {
+        let now = Utc::now();
+        let mut interactions: Vec<InteractionRecord> =
+            (0..4).map(|_| record(Uuid::new_v4(), now)).collect();
+        interactions[1].payload.sent = 900_000;
+        interactions[3].payload.sent = 20_000;
+        // Records saved before sizes were tracked load with zero sizes
+        let mut legacy = serde_json::to_value(&interactions[0]).unwrap();
+        legacy.as_object_mut().unwrap().remove("payload");
+        interactions[2] = serde_json::from_value(legacy).unwrap();
+
+        let largest = largest_prompts(&interactions, 2);
+        assert_eq!(largest[0].payload.sent, 900_000);
+        assert_eq!(largest[1].payload.sent, 20_000);
+
+        let config = PromptSizeConfig::default();
+        assert!(config.is_oversized(&interactions[1]));
+        assert!(!config.is_oversized(&interactions[3]));
+        assert!(!PromptSizeConfig { warn_bytes: 0 }.is_oversized(&interactions[1]));
+
+        let mut total = PayloadBytes::default();
+        for interaction in &interactions {
+            total += interaction.payload;
+        }
+        assert_eq!(total.received, 2400);
+    }
+}
@@ -0,0 +1,195 @@
+//! Workspace event journal.
+//!
+//! Append-only JSONL record (`.aca/journal/events.jsonl`) of notable
+//! decisions made outside a single session, such as who approved or rejected
+//! a submitted plan, how a model's question was answered or how a task was
+//! re-prioritized mid-run. Each line is one [`JournalEntry`].
+
+use crate::policy::{PolicyEffect, PolicyStage};
+use crate::session::StorageCategory;
+use crate::task::PriorityOverride;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Events recorded in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEvent {
+    /// A plan was submitted and is waiting for approval
+    ApprovalRequested {
+        approval_id: Uuid,
+        title: String,
+        requested_by: String,
+    },
+    /// A pending plan was approved
+    ApprovalGranted {
+        approval_id: Uuid,
+        title: String,
+        approved_by: String,
+        role: String,
+    },
+    /// A pending plan was rejected
+    ApprovalRejected {
+        approval_id: Uuid,
+        title: String,
+        rejected_by: String,
+        role: String,
+        reason: Option<String>,
+    },
+    /// A pending plan was withdrawn before anyone decided on it
+    ApprovalCancelled {
+        approval_id: Uuid,
+        title: String,
+        cancelled_by: String,
+        reason: Option<String>,
+    },
+    /// The model asked the user a question mid-task
+    QuestionAsked {
+        question_id: Uuid,
+        task_id: Uuid,
+        task_title: String,
+        question: String,
+    },
+    /// A question was answered and the task continued
+    QuestionAnswered {
+        question_id: Uuid,
+        task_id: Uuid,
+        question: String,
+        answer: String,
+        answered_by: String,
+    },
+    /// A human changed a task's entry in `.aca/priorities.toml` mid-run
+    PriorityOverrideChanged {
+        task_key: String,
+        before: Option<PriorityOverride>,
+        after: Option<PriorityOverride>,
+    },
+    /// Files were removed to bring a session under its storage quota
+    StorageQuotaEnforced {
+        session_id: String,
+        category: StorageCategory,
+        removed: Vec<PathBuf>,
+        bytes_freed: u64,
+    },
+    /// A task matched a policy rule and was denied or held for approval
+    PolicyViolated {
+        stage: PolicyStage,
+        rule: String,
+        effect: PolicyEffect,
+        task_id: Option<Uuid>,
+        task_title: String,
+        message: String,
+    },
+    /// A task's file changes were reverted and the task reset to pending
+    TaskRolledBack {
+        task_id: Uuid,
+        task_title: String,
+        files: Vec<PathBuf>,
+    },
+}
+
+/// One timestamped journal line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: JournalEvent,
+}
+
+/// Append-only event journal stored in the workspace
+#[derive(Debug, Clone)]
+pub struct EventJournal {
+    path: PathBuf,
+}
+
+impl EventJournal {
+    /// Journal for a workspace (`.aca/journal/events.jsonl`)
+    pub fn for_workspace(workspace_root: &Path) -> Self {
+        Self::new(crate::env::event_journal_file_path(workspace_root))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event with the current timestamp
+    pub fn record(&self, event: JournalEvent) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create journal directory {:?}", parent))?;
+        }
+
+        let entry = JournalEntry {
+            timestamp: Utc::now(),
+            event,
+        };
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open event journal {:?}", self.path))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+            .with_context(|| format!("Failed to write event journal {:?}", self.path))?;
+        Ok(())
+    }
+
+    /// Read all entries, skipping malformed lines
+    pub fn load(&self) -> Result<Vec<JournalEntry>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {:?}", self.path));
+            }
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!("Skipping malformed journal line: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let journal = EventJournal::for_workspace(temp.path());
+        let approval_id = Uuid::new_v4();
+
+        journal
+            .record(JournalEvent::ApprovalGranted {
+                approval_id,
+                title: "Deploy".to_string(),
+                approved_by: "alice".to_string(),
+                role: "admin".to_string(),
+            })
+            .unwrap();
+
+        let entries = journal.load().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].event,
+            JournalEvent::ApprovalGranted { approved_by, .. } if approved_by == "alice"
+        ));
+    }
+}
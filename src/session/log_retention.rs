@@ -0,0 +1,367 @@
+//! Retention and rotation for session log directories.
+//!
+//! Provider interaction logs (`stdout.json` in particular, with stream-json
+//! output) grow without bound across sessions. [`LogRetention`] walks
+//! `.aca/sessions/*/logs`, gzips files older than `compress_after_days`,
+//! deletes files older than `max_age_days`, and then deletes the oldest files
+//! until the total size fits under `max_total_size_mb`. It runs periodically
+//! while an agent is active, once more on shutdown, and on demand through
+//! `aca logs prune`.
+
+use crate::env;
+use crate::session::SessionManager;
+use chrono::{DateTime, Duration, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Extension appended to compressed log files
+pub const COMPRESSED_EXTENSION: &str = "gz";
+
+/// Configuration for log retention
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogRetentionConfig {
+    /// Enforce retention periodically and on shutdown
+    pub enabled: bool,
+    /// Delete log files older than this many days
+    pub max_age_days: Option<u32>,
+    /// Delete the oldest log files while all sessions' logs exceed this size
+    pub max_total_size_mb: Option<u64>,
+    /// Gzip log files older than this many days
+    pub compress_after_days: Option<u32>,
+    /// Minutes between enforcement runs while an agent is active
+    pub interval_minutes: u32,
+}
+
+impl Default for LogRetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: Some(30),
+            max_total_size_mb: Some(2048),
+            compress_after_days: Some(2),
+            interval_minutes: 60,
+        }
+    }
+}
+
+/// What one enforcement run changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionReport {
+    /// Original and compressed path of each gzipped file
+    pub compressed: Vec<(PathBuf, PathBuf)>,
+    pub removed: Vec<PathBuf>,
+    /// Bytes reclaimed by compression and removal
+    pub bytes_freed: u64,
+}
+
+impl RetentionReport {
+    pub fn is_empty(&self) -> bool {
+        self.compressed.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug)]
+struct LogFile {
+    path: PathBuf,
+    size: u64,
+    modified: DateTime<Utc>,
+}
+
+/// Applies [`LogRetentionConfig`] to a workspace's session logs
+#[derive(Debug)]
+pub struct LogRetention {
+    config: LogRetentionConfig,
+    workspace_root: PathBuf,
+    enforcer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LogRetention {
+    pub fn new(config: LogRetentionConfig, workspace_root: PathBuf) -> Self {
+        Self {
+            config,
+            workspace_root,
+            enforcer: Mutex::new(None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Enforce retention across every session's logs
+    pub fn enforce(&self) -> io::Result<RetentionReport> {
+        let sessions_dir = env::sessions_dir_path(&self.workspace_root);
+        let log_dirs = match std::fs::read_dir(&sessions_dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path().join(env::session::LOGS_DIR_NAME))
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        self.prune(&log_dirs, Utc::now())
+    }
+
+    /// Enforce retention on one session's logs
+    ///
+    /// The size limit then applies to that session's logs alone.
+    pub fn enforce_session(&self, session_id: &str) -> io::Result<RetentionReport> {
+        let log_dir = env::session_logs_dir_path(&self.workspace_root, session_id);
+        self.prune(&[log_dir], Utc::now())
+    }
+
+    fn prune(&self, log_dirs: &[PathBuf], now: DateTime<Utc>) -> io::Result<RetentionReport> {
+        let mut files = Vec::new();
+        for dir in log_dirs {
+            collect_files(dir, &mut files)?;
+        }
+        let mut report = RetentionReport::default();
+
+        if let Some(days) = self.config.max_age_days {
+            let cutoff = now - Duration::days(days.into());
+            files.retain(|file| {
+                if file.modified >= cutoff {
+                    return true;
+                }
+                match std::fs::remove_file(&file.path) {
+                    Ok(()) => {
+                        report.bytes_freed += file.size;
+                        report.removed.push(file.path.clone());
+                    }
+                    Err(e) => warn!("Failed to remove {}: {}", file.path.display(), e),
+                }
+                false
+            });
+        }
+
+        if let Some(days) = self.config.compress_after_days {
+            let cutoff = now - Duration::days(days.into());
+            for file in files.iter_mut() {
+                if file.modified >= cutoff || is_compressed(&file.path) {
+                    continue;
+                }
+                match compress(&file.path, file.modified) {
+                    Ok((compressed, size)) => {
+                        report.bytes_freed += file.size.saturating_sub(size);
+                        report
+                            .compressed
+                            .push((file.path.clone(), compressed.clone()));
+                        file.path = compressed;
+                        file.size = size;
+                    }
+                    Err(e) => warn!("Failed to compress {}: {}", file.path.display(), e),
+                }
+            }
+        }
+
+        if let Some(max_mb) = self.config.max_total_size_mb {
+            let limit = max_mb * 1024 * 1024;
+            let mut total: u64 = files.iter().map(|file| file.size).sum();
+            files.sort_by_key(|file| file.modified);
+            for file in &files {
+                if total <= limit {
+                    break;
+                }
+                match std::fs::remove_file(&file.path) {
+                    Ok(()) => {
+                        total -= file.size;
+                        report.bytes_freed += file.size;
+                        report.removed.push(file.path.clone());
+                    }
+                    Err(e) => warn!("Failed to remove {}: {}", file.path.display(), e),
+                }
+            }
+        }
+
+        if !report.is_empty() {
+            info!(
+                "Log retention compressed {} and removed {} file(s), freeing {} bytes",
+                report.compressed.len(),
+                report.removed.len(),
+                report.bytes_freed
+            );
+        }
+        Ok(report)
+    }
+
+    /// Enforce retention and point interaction records at the surviving files
+    pub async fn enforce_for(self: &Arc<Self>, session_manager: &SessionManager) {
+        let retention = self.clone();
+        match tokio::task::spawn_blocking(move || retention.enforce()).await {
+            Ok(Ok(report)) => session_manager.apply_log_retention(&report).await,
+            Ok(Err(e)) => warn!("Log retention failed: {}", e),
+            Err(e) => warn!("Log retention task failed: {}", e),
+        }
+    }
+
+    /// Spawn periodic enforcement (no-op when disabled or already running)
+    pub fn start(self: &Arc<Self>, session_manager: SessionManager) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut enforcer = self.enforcer.lock().unwrap();
+        if enforcer.is_some() {
+            return;
+        }
+
+        let retention = self.clone();
+        let interval =
+            std::time::Duration::from_secs(u64::from(self.config.interval_minutes.max(1)) * 60);
+        debug!("Enforcing log retention every {}s", interval.as_secs());
+        *enforcer = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                retention.enforce_for(&session_manager).await;
+            }
+        }));
+    }
+
+    /// Stop periodic enforcement and run it one last time
+    pub async fn stop(self: &Arc<Self>, session_manager: &SessionManager) {
+        let handle = self.enforcer.lock().unwrap().take();
+        if let Some(handle) = handle {
+            handle.abort();
+            self.enforce_for(session_manager).await;
+        }
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<LogFile>) -> io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries.flatten() {
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else {
+            files.push(LogFile {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified()?.into(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some(COMPRESSED_EXTENSION)
+}
+
+/// Gzip `path` to `<path>.gz`, keeping its modification time so age-based
+/// retention still applies; returns the new path and size
+fn compress(path: &Path, modified: DateTime<Utc>) -> io::Result<(PathBuf, u64)> {
+    let mut compressed = path.as_os_str().to_owned();
+    compressed.push(".");
+    compressed.push(COMPRESSED_EXTENSION);
+    let compressed = PathBuf::from(compressed);
+
+    let mut encoder = GzEncoder::new(File::create(&compressed)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    let file = encoder.finish()?;
+    file.set_modified(modified.into())?;
+    let size = file.metadata()?.len();
+
+    std::fs::remove_file(path)?;
+    Ok((compressed, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn write_log(dir: &Path, name: &str, contents: &str, age_days: i64) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        let modified = Utc::now() - Duration::days(age_days);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified.into())
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_compress_and_expire_by_age() {
+        let workspace = tempfile::tempdir().unwrap();
+        let logs = env::claude_interactions_dir_path(workspace.path(), "s1");
+        let fresh = write_log(&logs, "fresh.log", "new", 0);
+        let old = write_log(&logs, "old.stdout.json", &"{}\n".repeat(1000), 5);
+        let expired = write_log(&logs, "expired.log", "ancient", 40);
+
+        let retention = LogRetention::new(
+            LogRetentionConfig {
+                max_total_size_mb: None,
+                ..Default::default()
+            },
+            workspace.path().to_path_buf(),
+        );
+        let report = retention.enforce().unwrap();
+
+        assert_eq!(report.removed, vec![expired.clone()]);
+        let compressed = logs.join("old.stdout.json.gz");
+        assert_eq!(report.compressed, vec![(old.clone(), compressed.clone())]);
+        assert!(fresh.exists() && !old.exists() && !expired.exists());
+
+        let mut contents = String::new();
+        GzDecoder::new(File::open(&compressed).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "{}\n".repeat(1000));
+
+        // Compressed files keep their age and are not compressed twice
+        assert!(retention.enforce().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_size_limit_removes_oldest_first() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mb = "x".repeat(1024 * 1024);
+        let oldest = write_log(
+            &env::session_logs_dir_path(workspace.path(), "s1"),
+            "a.log",
+            &mb,
+            3,
+        );
+        let newer = write_log(
+            &env::claude_interactions_dir_path(workspace.path(), "s2"),
+            "b.log",
+            &mb,
+            1,
+        );
+
+        let config = LogRetentionConfig {
+            max_age_days: None,
+            compress_after_days: None,
+            max_total_size_mb: Some(1),
+            ..Default::default()
+        };
+        let retention = LogRetention::new(config, workspace.path().to_path_buf());
+
+        // Each session alone fits
+        assert!(retention.enforce_session("s1").unwrap().is_empty());
+
+        let report = retention.enforce().unwrap();
+        assert_eq!(report.removed, vec![oldest]);
+        assert_eq!(report.bytes_freed, 1024 * 1024);
+        assert!(newer.exists());
+    }
+}
@@ -1,5 +1,10 @@
+use crate::session::integrity::{CheckpointIntegrity, SigningKey};
+use crate::session::interactions::{self, InteractionRecord};
+use crate::session::journal::{EventJournal, JournalEvent};
+use crate::session::log_retention::RetentionReport;
 use crate::session::metadata::*;
 use crate::session::persistence::*;
+use crate::session::quota::{QuotaReport, StorageCategory, StorageQuotaConfig};
 use crate::session::recovery::*;
 use crate::task::manager::{TaskManager, TaskManagerConfig};
 use crate::task::tree::TaskTree;
@@ -9,19 +14,31 @@ use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Mutex, RwLock, watch};
 use tokio::time::{Duration as TokioDuration, interval};
 use tracing::{debug, error, info, warn};
 
 /// Central session manager that coordinates persistence, recovery, and task management
+///
+/// The manager is a cheap handle: clones share the same session, so the
+/// engine, API, TUI and background loops can each hold one. Status queries
+/// read a snapshot republished after every save, checkpoint and restore and
+/// never wait on those operations.
+#[derive(Clone)]
 pub struct SessionManager {
     session_id: SessionId,
     metadata: Arc<RwLock<SessionMetadata>>,
     task_manager: Arc<TaskManager>,
     persistence: Arc<PersistenceManager>,
     recovery: Arc<RecoveryManager>,
-    config: SessionManagerConfig,
-    auto_save_enabled: Arc<Mutex<bool>>,
+    config: Arc<SessionManagerConfig>,
+    auto_save_enabled: Arc<AtomicBool>,
+    interactions: Arc<RwLock<Vec<InteractionRecord>>>,
+    conversation: Arc<RwLock<ConversationState>>,
+    /// Serializes saves and checkpoints from concurrent handles
+    save_lock: Arc<Mutex<()>>,
+    status: Arc<watch::Sender<SessionStatus>>,
 }
 
 /// Configuration for session manager
@@ -35,6 +52,15 @@ pub struct SessionManagerConfig {
     pub enable_crash_recovery: bool,
     pub validate_on_save: bool,
     pub compress_checkpoints: bool,
+    /// Environment variable holding a secret used to sign checkpoints
+    ///
+    /// When set, checkpoints without a valid signature are refused on restore.
+    #[serde(default)]
+    pub checkpoint_signing_key_env: Option<String>,
+    /// Size caps on each session's logs, artifacts and checkpoints, in place
+    /// of the persistence defaults
+    #[serde(default)]
+    pub storage_quota: Option<StorageQuotaConfig>,
 }
 
 /// Session initialization options
@@ -56,6 +82,7 @@ pub struct SessionInitOptions {
 pub struct SessionStatus {
     pub id: SessionId,
     pub name: String,
+    pub started_at: DateTime<Utc>,
     pub uptime: Duration,
     pub total_tasks: u32,
     pub completed_tasks: u32,
@@ -91,16 +118,63 @@ impl SessionManager {
         metadata.container_info.clone()
     }
 
+    /// Store the environment report in session metadata
+    pub async fn set_environment_report(&self, report: crate::executor::EnvironmentReport) {
+        self.metadata.write().await.set_environment(report);
+    }
+
+    /// Environment report of the latest plan, if one was probed
+    pub async fn environment_report(&self) -> Option<crate::executor::EnvironmentReport> {
+        self.metadata.read().await.environment.clone()
+    }
+
+    /// Record a started background service, replacing one of the same name
+    pub async fn record_service(&self, record: crate::executor::ServiceRecord) {
+        let mut metadata = self.metadata.write().await;
+        metadata
+            .services
+            .retain(|service| service.name != record.name);
+        metadata.services.push(record);
+        metadata.last_updated = Utc::now();
+    }
+
+    /// Forget a stopped background service
+    pub async fn remove_service(&self, name: &str) {
+        let mut metadata = self.metadata.write().await;
+        metadata.services.retain(|service| service.name != name);
+        metadata.last_updated = Utc::now();
+    }
+
+    /// Background services recorded as running
+    pub async fn services(&self) -> Vec<crate::executor::ServiceRecord> {
+        self.metadata.read().await.services.clone()
+    }
+
     /// Create a new session manager
     pub async fn new(
         session_dir: PathBuf,
         config: SessionManagerConfig,
-        init_options: SessionInitOptions,
+        mut init_options: SessionInitOptions,
     ) -> Result<Self> {
         info!("Creating new session: {}", init_options.name);
 
         let session_id = SessionId::new_v4();
 
+        if let Some(var) = &config.checkpoint_signing_key_env
+            && init_options.persistence_config.signing_key.is_none()
+        {
+            let secret = std::env::var(var).with_context(|| {
+                format!(
+                    "Checkpoint signing is configured but environment variable {} is not set",
+                    var
+                )
+            })?;
+            init_options.persistence_config.signing_key = Some(SigningKey::new(secret.as_bytes()));
+        }
+        if let Some(quotas) = &config.storage_quota {
+            init_options.persistence_config.quotas = quotas.clone();
+        }
+
         // Initialize persistence and recovery managers
         let persistence = Arc::new(
             PersistenceManager::new(
@@ -127,6 +201,20 @@ impl SessionManager {
         let mut session_metadata =
             SessionMetadata::new(init_options.name, init_options.workspace_root);
         session_metadata.execution_mode = init_options.execution_mode;
+        let status = SessionStatus {
+            id: session_id,
+            name: session_metadata.name.clone(),
+            started_at: session_metadata.created_at,
+            uptime: Duration::zero(),
+            total_tasks: 0,
+            completed_tasks: 0,
+            failed_tasks: 0,
+            active_tasks: 0,
+            last_checkpoint: None,
+            last_save: None,
+            memory_usage_mb: 0,
+            is_auto_save_active: init_options.enable_auto_save,
+        };
         let metadata = Arc::new(RwLock::new(session_metadata));
 
         let session_manager = Self {
@@ -135,8 +223,12 @@ impl SessionManager {
             task_manager,
             persistence,
             recovery,
-            config: config.clone(),
-            auto_save_enabled: Arc::new(Mutex::new(init_options.enable_auto_save)),
+            config: Arc::new(config.clone()),
+            auto_save_enabled: Arc::new(AtomicBool::new(init_options.enable_auto_save)),
+            interactions: Arc::new(RwLock::new(Vec::new())),
+            conversation: Arc::new(RwLock::new(ConversationState::default())),
+            save_lock: Arc::new(Mutex::new(())),
+            status: Arc::new(watch::channel(status).0),
         };
 
         // Attempt recovery if requested
@@ -168,46 +260,127 @@ impl SessionManager {
         Ok(session_manager)
     }
 
+    /// Record a provider call; it is persisted with the next save
+    pub async fn record_interaction(&self, interaction: InteractionRecord) {
+        self.interactions.write().await.push(interaction);
+    }
+
+    /// Keep the provider's conversation state for the next save or checkpoint
+    pub async fn record_conversation_state(&self, state: ConversationState) {
+        *self.conversation.write().await = state;
+    }
+
+    /// Provider conversation state, as restored from a checkpoint or last recorded
+    pub async fn conversation_state(&self) -> ConversationState {
+        self.conversation.read().await.clone()
+    }
+
+    /// Provider calls recorded in this session, oldest first
+    pub async fn interactions(&self) -> Vec<InteractionRecord> {
+        self.interactions.read().await.clone()
+    }
+
+    /// Provider calls recorded for `task_id`, oldest first
+    pub async fn interactions_for_task(&self, task_id: TaskId) -> Vec<InteractionRecord> {
+        let interactions = self.interactions.read().await;
+        interactions::for_task(&interactions, task_id)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Forget interactions older than `cutoff`, returning them so their logs can be removed
+    pub async fn prune_interactions_before(&self, cutoff: DateTime<Utc>) -> Vec<InteractionRecord> {
+        interactions::prune_before(&mut *self.interactions.write().await, cutoff)
+    }
+
+    /// Point interaction records at compressed logs and drop removed ones
+    pub async fn apply_log_retention(&self, report: &RetentionReport) {
+        if report.is_empty() {
+            return;
+        }
+        for interaction in self.interactions.write().await.iter_mut() {
+            interaction
+                .log_files
+                .retain(|path| !report.removed.contains(path));
+            for path in interaction.log_files.iter_mut() {
+                if let Some((_, compressed)) =
+                    report.compressed.iter().find(|(from, _)| from == path)
+                {
+                    *path = compressed.clone();
+                }
+            }
+        }
+    }
+
     /// Get the task manager instance
     pub fn task_manager(&self) -> &Arc<TaskManager> {
         &self.task_manager
     }
 
-    /// Get current session status
+    /// Get current session status, reading the live task tree
+    ///
+    /// Monitoring should prefer [`status`](Self::status), which does not
+    /// take any lock.
     pub async fn get_status(&self) -> Result<SessionStatus> {
+        let count = async |status_filter: fn(&TaskStatus) -> bool| -> Result<u32> {
+            Ok(self
+                .task_manager
+                .get_tasks_by_status(status_filter)
+                .await?
+                .len() as u32)
+        };
+        let mut status = self.status();
+        status.total_tasks = count(|_| true).await?;
+        status.completed_tasks =
+            count(|status| matches!(status, TaskStatus::Completed { .. })).await?;
+        status.failed_tasks = count(|status| matches!(status, TaskStatus::Failed { .. })).await?;
+        status.active_tasks =
+            count(|status| matches!(status, TaskStatus::InProgress { .. })).await?;
+
         let metadata = self.metadata.read().await;
-        let uptime = Utc::now().signed_duration_since(metadata.created_at);
+        status.name = metadata.name.clone();
+        status.last_checkpoint = metadata.latest_checkpoint().map(|c| c.created_at);
+        Ok(status)
+    }
 
-        // Get task statistics from task manager
-        let _statistics = self.task_manager.get_statistics().await?;
-
-        let active_tasks = self
-            .task_manager
-            .get_tasks_by_status(|status| matches!(status, TaskStatus::InProgress { .. }))
-            .await?
-            .len() as u32;
-
-        let auto_save_active = *self.auto_save_enabled.lock().await;
-
-        Ok(SessionStatus {
-            id: self.session_id,
-            name: metadata.name.clone(),
-            uptime,
-            total_tasks: metadata.total_tasks,
-            completed_tasks: metadata.completed_tasks,
-            failed_tasks: metadata.failed_tasks,
-            active_tasks,
-            last_checkpoint: metadata.latest_checkpoint().map(|c| c.created_at),
-            last_save: Some(metadata.last_updated),
-            memory_usage_mb: 0, // Would be calculated from actual usage
-            is_auto_save_active: auto_save_active,
-        })
+    /// Status as of the last save, checkpoint or restore, without waiting
+    /// on any of them
+    pub fn status(&self) -> SessionStatus {
+        let mut status = self.status.borrow().clone();
+        status.uptime = Utc::now().signed_duration_since(status.started_at);
+        status.is_auto_save_active = self.auto_save_enabled.load(Ordering::Relaxed);
+        status
+    }
+
+    /// Receiver notified whenever the status snapshot changes
+    pub fn subscribe_status(&self) -> watch::Receiver<SessionStatus> {
+        self.status.subscribe()
+    }
+
+    /// Republish the status snapshot from a captured state
+    fn publish_status(&self, state: &SessionState, saved: bool) {
+        let statistics = &state.task_tree.metadata.statistics;
+        self.status.send_modify(|status| {
+            status.name = state.metadata.name.clone();
+            status.started_at = state.metadata.created_at;
+            status.total_tasks = statistics.total_tasks;
+            status.completed_tasks = statistics.completed_tasks;
+            status.failed_tasks = statistics.failed_tasks;
+            status.active_tasks = statistics.in_progress_tasks;
+            status.last_checkpoint = state.metadata.latest_checkpoint().map(|c| c.created_at);
+            if saved {
+                status.last_save = Some(Utc::now());
+            }
+            status.is_auto_save_active = self.auto_save_enabled.load(Ordering::Relaxed);
+        });
     }
 
     /// Save current session state
     pub async fn save_session(&self) -> Result<PersistenceResult> {
         debug!("Saving session state");
 
+        let _saving = self.save_lock.lock().await;
         let session_state = self.capture_session_state().await?;
 
         // Validate before saving if configured
@@ -223,12 +396,14 @@ impl SessionManager {
         }
 
         let result = self.persistence.save_session_state(&session_state).await?;
+        self.enforce_storage_quotas().await;
 
         // Update metadata
         {
             let mut metadata = self.metadata.write().await;
             metadata.last_updated = Utc::now();
         }
+        self.publish_status(&session_state, true);
 
         info!("Session saved successfully: {} bytes", result.bytes_written);
         Ok(result)
@@ -238,12 +413,8 @@ impl SessionManager {
     pub async fn create_checkpoint(&self, description: String) -> Result<CheckpointInfo> {
         info!("Creating manual checkpoint: {}", description);
 
-        let session_state = self.capture_session_state().await?;
-
         let checkpoint_info = self
-            .persistence
-            .create_checkpoint(
-                &session_state,
+            .checkpoint_with_trigger(
                 description,
                 CheckpointTrigger::Manual {
                     reason: "User requested".to_string(),
@@ -251,19 +422,41 @@ impl SessionManager {
             )
             .await?;
 
+        info!("Checkpoint created: {}", checkpoint_info.id);
+        Ok(checkpoint_info)
+    }
+
+    async fn checkpoint_with_trigger(
+        &self,
+        description: String,
+        trigger: CheckpointTrigger,
+    ) -> Result<CheckpointInfo> {
+        let _saving = self.save_lock.lock().await;
+        let mut session_state = self.capture_session_state().await?;
+
+        let checkpoint_info = self
+            .persistence
+            .create_checkpoint(&session_state, description, trigger)
+            .await?;
+
         // Update metadata
         {
             let mut metadata = self.metadata.write().await;
             metadata.add_checkpoint(checkpoint_info.clone());
         }
+        session_state
+            .metadata
+            .add_checkpoint(checkpoint_info.clone());
+        self.publish_status(&session_state, false);
+        self.enforce_storage_quotas().await;
 
-        info!("Checkpoint created: {}", checkpoint_info.id);
         Ok(checkpoint_info)
     }
 
     /// Restore from a specific checkpoint
     pub async fn restore_from_checkpoint(&self, checkpoint_id: &str) -> Result<()> {
         info!("Restoring session from checkpoint: {}", checkpoint_id);
+        let _saving = self.save_lock.lock().await;
 
         // Create emergency backup before restoration
         if self.recovery.config.create_recovery_checkpoint {
@@ -275,6 +468,9 @@ impl SessionManager {
         }
 
         let recovery_result = self.recovery.recover_from_checkpoint(checkpoint_id).await?;
+        for warning in &recovery_result.warnings {
+            warn!("{}", warning);
+        }
 
         if recovery_result.success {
             if let Some(state) = recovery_result.recovered_state {
@@ -320,6 +516,33 @@ impl SessionManager {
         }
     }
 
+    /// List checkpoints with the result of checking each against its recorded hash
+    ///
+    /// The integrity is `None` when the checkpoint file no longer exists.
+    pub async fn list_checkpoints_verified(
+        &self,
+        include_all_sessions: bool,
+    ) -> Result<Vec<(CheckpointInfo, Option<CheckpointIntegrity>)>> {
+        let workspace_root = self.metadata.read().await.workspace_root.clone();
+        let key = self.persistence.config.signing_key.as_ref();
+
+        let mut verified = Vec::new();
+        for checkpoint in self.list_checkpoints(include_all_sessions).await? {
+            let integrity = match find_checkpoint_file(&workspace_root, &checkpoint.id) {
+                Some(path) => Some(verify_checkpoint_file(&path, Some(&checkpoint), key).await?),
+                None => None,
+            };
+            if let Some(integrity) = integrity.as_ref().filter(|i| !i.is_usable()) {
+                warn!(
+                    "Checkpoint {} failed integrity check: {}",
+                    checkpoint.id, integrity
+                );
+            }
+            verified.push((checkpoint, integrity));
+        }
+        Ok(verified)
+    }
+
     /// Create a checkpoint in the latest session of the workspace
     /// This is useful for CLI operations that want to add checkpoints to existing sessions
     pub async fn create_checkpoint_in_latest_session_of_workspace(
@@ -336,40 +559,20 @@ impl SessionManager {
         let interval_duration =
             TokioDuration::from_secs(self.config.auto_save_interval_minutes as u64 * 60);
 
-        let persistence = self.persistence.clone();
-        let metadata = self.metadata.clone();
-        let task_manager = self.task_manager.clone();
-        let auto_save_enabled = self.auto_save_enabled.clone();
-        let validate_on_save = self.config.validate_on_save;
-
+        let manager = self.clone();
         tokio::spawn(async move {
             let mut interval_timer = interval(interval_duration);
 
             loop {
                 interval_timer.tick().await;
 
-                let is_enabled = *auto_save_enabled.lock().await;
-                if !is_enabled {
+                if !manager.auto_save_enabled.load(Ordering::Relaxed) {
                     continue;
                 }
 
-                match Self::capture_session_state_static(&metadata, &task_manager).await {
-                    Ok(session_state) => {
-                        // Validate if configured
-                        if validate_on_save {
-                            // Note: We can't access recovery manager in static context
-                            // In a real implementation, we'd pass validation logic here
-                        }
-
-                        if let Err(e) = persistence.save_session_state(&session_state).await {
-                            error!("Auto-save failed: {}", e);
-                        } else {
-                            debug!("Auto-save completed successfully");
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to capture session state for auto-save: {}", e);
-                    }
+                match manager.save_session().await {
+                    Ok(_) => debug!("Auto-save completed successfully"),
+                    Err(e) => error!("Auto-save failed: {}", e),
                 }
             }
         });
@@ -386,43 +589,25 @@ impl SessionManager {
         let interval_duration =
             TokioDuration::from_secs(self.config.auto_checkpoint_interval_minutes as u64 * 60);
 
-        let persistence = self.persistence.clone();
-        let metadata = self.metadata.clone();
-        let task_manager = self.task_manager.clone();
-
+        let manager = self.clone();
         tokio::spawn(async move {
             let mut interval_timer = interval(interval_duration);
 
             loop {
                 interval_timer.tick().await;
 
-                match Self::capture_session_state_static(&metadata, &task_manager).await {
-                    Ok(session_state) => {
-                        let description = format!(
-                            "Automatic checkpoint at {}",
-                            Utc::now().format("%Y-%m-%d %H:%M:%S")
-                        );
-
-                        if let Err(e) = persistence
-                            .create_checkpoint(
-                                &session_state,
-                                description,
-                                CheckpointTrigger::Automatic {
-                                    trigger: AutoTrigger::TimeInterval {
-                                        minutes: interval_duration.as_secs() as u32 / 60,
-                                    },
-                                },
-                            )
-                            .await
-                        {
-                            error!("Auto-checkpoint failed: {}", e);
-                        } else {
-                            debug!("Auto-checkpoint created successfully");
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to capture session state for auto-checkpoint: {}", e);
-                    }
+                let description = format!(
+                    "Automatic checkpoint at {}",
+                    Utc::now().format("%Y-%m-%d %H:%M:%S")
+                );
+                let trigger = CheckpointTrigger::Automatic {
+                    trigger: AutoTrigger::TimeInterval {
+                        minutes: interval_duration.as_secs() as u32 / 60,
+                    },
+                };
+                match manager.checkpoint_with_trigger(description, trigger).await {
+                    Ok(_) => debug!("Auto-checkpoint created successfully"),
+                    Err(e) => error!("Auto-checkpoint failed: {}", e),
                 }
             }
         });
@@ -436,8 +621,9 @@ impl SessionManager {
 
     /// Enable or disable auto-save
     pub async fn set_auto_save_enabled(&self, enabled: bool) -> Result<()> {
-        let mut auto_save_enabled = self.auto_save_enabled.lock().await;
-        *auto_save_enabled = enabled;
+        self.auto_save_enabled.store(enabled, Ordering::Relaxed);
+        self.status
+            .send_modify(|status| status.is_auto_save_active = enabled);
 
         info!("Auto-save {}", if enabled { "enabled" } else { "disabled" });
         Ok(())
@@ -445,21 +631,14 @@ impl SessionManager {
 
     /// Capture current session state
     async fn capture_session_state(&self) -> Result<SessionState> {
-        Self::capture_session_state_static(&self.metadata, &self.task_manager).await
-    }
-
-    /// Static helper for capturing session state (used in spawned tasks)
-    async fn capture_session_state_static(
-        metadata: &Arc<RwLock<SessionMetadata>>,
-        task_manager: &Arc<TaskManager>,
-    ) -> Result<SessionState> {
         // Get current task tree state
-        let task_tree_json = task_manager.export_to_json().await?;
-        let task_tree: TaskTree = serde_json::from_str(&task_tree_json)?;
+        let task_tree_json = self.task_manager.export_to_json().await?;
+        let mut task_tree: TaskTree = serde_json::from_str(&task_tree_json)?;
+        task_tree.rebuild_statistics();
 
         // Get current metadata
         let current_metadata = {
-            let metadata_lock = metadata.read().await;
+            let metadata_lock = self.metadata.read().await;
             metadata_lock.clone()
         };
 
@@ -474,6 +653,8 @@ impl SessionManager {
             task_tree,
             execution_context,
             file_system_state,
+            interactions: self.interactions.read().await.clone(),
+            conversation: self.conversation.read().await.clone(),
         })
     }
 
@@ -484,12 +665,15 @@ impl SessionManager {
         // Import task tree
         let task_tree_json = serde_json::to_string(&state.task_tree)?;
         self.task_manager.import_from_json(&task_tree_json).await?;
+        self.publish_status(&state, false);
 
         // Update metadata (includes container_info if present)
         {
             let mut metadata = self.metadata.write().await;
             *metadata = state.metadata;
         }
+        *self.interactions.write().await = state.interactions;
+        *self.conversation.write().await = state.conversation;
 
         // Note: Container reconnection is handled separately by AgentSystem.
         // If state.metadata.container_info is present, the AgentSystem's
@@ -505,6 +689,59 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Remove the session's lowest-value files while it exceeds its storage
+    /// quotas, journaling what was removed
+    pub async fn enforce_storage_quotas(&self) -> QuotaReport {
+        let persistence = &self.persistence;
+        let report = match persistence.enforce_quotas().await {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("{:#}", e);
+                return QuotaReport::default();
+            }
+        };
+        if report.is_empty() {
+            return report;
+        }
+
+        // Forget the removed logs and checkpoints
+        for interaction in self.interactions.write().await.iter_mut() {
+            interaction
+                .log_files
+                .retain(|path| !report.removed.iter().any(|item| &item.path == path));
+        }
+        let removed_checkpoints: Vec<&str> = report.removed_checkpoints().collect();
+        self.metadata
+            .write()
+            .await
+            .checkpoints
+            .retain(|checkpoint| !removed_checkpoints.contains(&checkpoint.id.as_str()));
+
+        let journal = EventJournal::for_workspace(persistence.workspace_root());
+        for category in StorageCategory::ALL {
+            let (removed, bytes_freed) = report.for_category(category);
+            if removed.is_empty() {
+                continue;
+            }
+            warn!(
+                "Session {} exceeded its {} quota; removed {} file(s), freeing {} bytes",
+                persistence.session_id(),
+                category,
+                removed.len(),
+                bytes_freed
+            );
+            if let Err(e) = journal.record(JournalEvent::StorageQuotaEnforced {
+                session_id: persistence.session_id().to_string(),
+                category,
+                removed,
+                bytes_freed,
+            }) {
+                warn!("Failed to journal storage quota enforcement: {}", e);
+            }
+        }
+        report
+    }
+
     /// Cleanup old checkpoints
     pub async fn cleanup_old_checkpoints(&self) -> Result<u32> {
         let cleaned_count = self.persistence.cleanup_old_checkpoints().await?;
@@ -701,6 +938,8 @@ impl SessionManager {
             trigger_reason: CheckpointTrigger::Manual {
                 reason: "User requested manual checkpoint".to_string(),
             },
+            content_hash: None,
+            signature: None,
         };
 
         // Find the most recent checkpoint file in the session to update
@@ -776,11 +1015,11 @@ impl SessionManager {
             }
         });
 
-        // Write the checkpoint file
-        std::fs::write(
-            &checkpoint_file,
-            serde_json::to_string_pretty(&session_data)?,
-        )?;
+        // Write the checkpoint file with its content hash alongside
+        let content = serde_json::to_string_pretty(&session_data)?;
+        std::fs::write(&checkpoint_file, &content)?;
+        let content_hash = crate::session::integrity::content_hash(content.as_bytes());
+        std::fs::write(checkpoint_file.with_extension("checksum"), &content_hash)?;
 
         info!(
             "Manual checkpoint created: {} in session: {:?}",
@@ -788,7 +1027,10 @@ impl SessionManager {
             session_dir.file_name()
         );
 
-        Ok(checkpoint_info)
+        Ok(CheckpointInfo {
+            content_hash: Some(content_hash),
+            ..checkpoint_info
+        })
     }
 
     /// Get session directory path for audit trail coordination
@@ -812,6 +1054,8 @@ impl Default for SessionManagerConfig {
             enable_crash_recovery: true,
             validate_on_save: true,
             compress_checkpoints: false, // Disabled for initial implementation
+            checkpoint_signing_key_env: None,
+            storage_quota: None,
         }
     }
 }
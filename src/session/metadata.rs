@@ -101,6 +101,12 @@ pub struct SessionMetadata {
     /// Container information if running in container mode
     #[serde(default)]
     pub container_info: Option<SessionContainerInfo>,
+    /// Environment probed at the start of the latest plan
+    #[serde(default)]
+    pub environment: Option<crate::executor::EnvironmentReport>,
+    /// Background services started for the running plan
+    #[serde(default)]
+    pub services: Vec<crate::executor::ServiceRecord>,
 }
 
 /// Session version information for compatibility tracking
@@ -123,6 +129,12 @@ pub struct CheckpointInfo {
     pub size_bytes: u64,
     pub is_automatic: bool,
     pub trigger_reason: CheckpointTrigger,
+    /// `sha256:` hash of the checkpoint file, recorded at creation
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// HMAC-SHA256 of `content_hash` when checkpoint signing is configured
+    #[serde(default)]
+    pub signature: Option<String>,
 }
 
 /// Reasons for creating a checkpoint
@@ -184,6 +196,8 @@ impl SessionMetadata {
             custom_properties: HashMap::new(),
             execution_mode: None,
             container_info: None,
+            environment: None,
+            services: Vec::new(),
         }
     }
 
@@ -193,6 +207,12 @@ impl SessionMetadata {
         self.last_updated = Utc::now();
     }
 
+    /// Record the environment report of the latest plan
+    pub fn set_environment(&mut self, environment: crate::executor::EnvironmentReport) {
+        self.environment = Some(environment);
+        self.last_updated = Utc::now();
+    }
+
     /// Clear container information (e.g., when container is removed)
     pub fn clear_container_info(&mut self) {
         if let Some(ref mut info) = self.container_info {
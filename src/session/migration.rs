@@ -0,0 +1,290 @@
+use crate::env;
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// `.aca` layout version written by this build
+///
+/// Bump it together with a new entry in [`MIGRATIONS`] whenever paths in
+/// [`crate::env`] or on-disk file formats change incompatibly.
+pub const CURRENT_LAYOUT_VERSION: u32 = 2;
+
+/// Layout version of `.aca` directories created before versioning existed
+pub const UNVERSIONED_LAYOUT_VERSION: u32 = 1;
+
+/// Contents of `.aca/layout.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutVersion {
+    pub version: u32,
+    /// aca release that last wrote the layout
+    pub written_by: String,
+}
+
+impl LayoutVersion {
+    pub fn current() -> Self {
+        Self {
+            version: CURRENT_LAYOUT_VERSION,
+            written_by: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// One step from layout `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    /// Whether the step rewrites existing files, which requires a backup
+    pub modifies_data: bool,
+    pub apply: fn(&Path) -> Result<()>,
+}
+
+/// Every migration, ordered by `from`
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "Record the layout version of an unversioned .aca directory",
+    modifies_data: false,
+    apply: |_| Ok(()),
+}];
+
+/// What [`MigrationManager::run`] did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationOutcome {
+    /// No `.aca` directory yet; it was created at the current version
+    Fresh,
+    UpToDate,
+    Migrated {
+        from: u32,
+        to: u32,
+        /// Copy of `.aca` taken before files were rewritten
+        backup: Option<PathBuf>,
+    },
+}
+
+/// Brings a workspace's `.aca` directory up to [`CURRENT_LAYOUT_VERSION`]
+#[derive(Debug, Clone)]
+pub struct MigrationManager {
+    workspace_root: PathBuf,
+}
+
+impl MigrationManager {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+        }
+    }
+
+    /// Layout recorded in `.aca/layout.json`, `None` when there is no `.aca`
+    pub fn detect(&self) -> Result<Option<LayoutVersion>> {
+        if !env::aca_dir_path(&self.workspace_root).exists() {
+            return Ok(None);
+        }
+        let path = env::layout_file_path(&self.workspace_root);
+        if !path.exists() {
+            return Ok(Some(LayoutVersion {
+                version: UNVERSIONED_LAYOUT_VERSION,
+                written_by: "unknown".to_string(),
+            }));
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let layout = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(layout))
+    }
+
+    /// Migrate to the current layout, refusing layouts from newer releases
+    pub fn run(&self) -> Result<MigrationOutcome> {
+        self.run_migrations(MIGRATIONS, CURRENT_LAYOUT_VERSION)
+    }
+
+    fn run_migrations(&self, migrations: &[Migration], target: u32) -> Result<MigrationOutcome> {
+        let aca_dir = env::aca_dir_path(&self.workspace_root);
+        let Some(layout) = self.detect()? else {
+            std::fs::create_dir_all(&aca_dir)
+                .with_context(|| format!("Failed to create {}", aca_dir.display()))?;
+            self.write_version(target)?;
+            return Ok(MigrationOutcome::Fresh);
+        };
+
+        if layout.version > target {
+            bail!(
+                "{} was written by aca {} (layout version {}), but this aca {} only \
+                 understands layout version {} or older. Upgrade aca to use this workspace, \
+                 or move {} aside to start with fresh sessions.",
+                aca_dir.display(),
+                layout.written_by,
+                layout.version,
+                env!("CARGO_PKG_VERSION"),
+                target,
+                aca_dir.display()
+            );
+        }
+        if layout.version == target {
+            return Ok(MigrationOutcome::UpToDate);
+        }
+
+        let pending: Vec<&Migration> = (layout.version..target)
+            .map(|from| {
+                migrations
+                    .iter()
+                    .find(|migration| migration.from == from)
+                    .with_context(|| format!("No migration from layout version {}", from))
+            })
+            .collect::<Result<_>>()?;
+
+        let backup = if pending.iter().any(|migration| migration.modifies_data) {
+            Some(self.backup(layout.version)?)
+        } else {
+            None
+        };
+
+        for migration in pending {
+            info!(
+                "Migrating .aca layout {} -> {}: {}",
+                migration.from,
+                migration.from + 1,
+                migration.description
+            );
+            if let Err(e) = (migration.apply)(&self.workspace_root) {
+                if let Some(backup) = &backup {
+                    warn!(
+                        "Migration failed; the pre-migration copy is in {}",
+                        backup.display()
+                    );
+                }
+                return Err(e.context(format!(
+                    "Failed to migrate .aca layout from version {}",
+                    migration.from
+                )));
+            }
+            self.write_version(migration.from + 1)?;
+        }
+
+        Ok(MigrationOutcome::Migrated {
+            from: layout.version,
+            to: target,
+            backup,
+        })
+    }
+
+    /// Copy `.aca` (without earlier backups) to a timestamped backup directory
+    fn backup(&self, version: u32) -> Result<PathBuf> {
+        let aca_dir = env::aca_dir_path(&self.workspace_root);
+        let backups_dir = env::backups_dir_path(&self.workspace_root);
+        let backup = backups_dir.join(format!(
+            "layout-v{}-{}",
+            version,
+            Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        copy_dir(&aca_dir, &backup, &backups_dir)
+            .with_context(|| format!("Failed to back up {}", aca_dir.display()))?;
+        info!("Backed up {} to {}", aca_dir.display(), backup.display());
+        Ok(backup)
+    }
+
+    fn write_version(&self, version: u32) -> Result<()> {
+        let layout = LayoutVersion {
+            version,
+            ..LayoutVersion::current()
+        };
+        let path = env::layout_file_path(&self.workspace_root);
+        std::fs::write(&path, serde_json::to_string_pretty(&layout)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path, skip: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path == skip {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &target, skip)?;
+        } else {
+            std::fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_unversioned_and_current_layouts() {
+        let workspace = tempfile::tempdir().unwrap();
+        let manager = MigrationManager::new(workspace.path());
+        assert_eq!(manager.run().unwrap(), MigrationOutcome::Fresh);
+        assert_eq!(manager.detect().unwrap(), Some(LayoutVersion::current()));
+        assert_eq!(manager.run().unwrap(), MigrationOutcome::UpToDate);
+
+        // A directory from before layout versioning
+        let old = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(env::sessions_dir_path(old.path())).unwrap();
+        let manager = MigrationManager::new(old.path());
+        assert_eq!(
+            manager.run().unwrap(),
+            MigrationOutcome::Migrated {
+                from: 1,
+                to: CURRENT_LAYOUT_VERSION,
+                backup: None
+            }
+        );
+        assert_eq!(manager.detect().unwrap(), Some(LayoutVersion::current()));
+    }
+
+    #[test]
+    fn test_refuses_future_layout() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(env::aca_dir_path(workspace.path())).unwrap();
+        std::fs::write(
+            env::layout_file_path(workspace.path()),
+            r#"{"version": 99, "written_by": "9.0.0"}"#,
+        )
+        .unwrap();
+
+        let error = MigrationManager::new(workspace.path())
+            .run()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("aca 9.0.0 (layout version 99)"));
+        assert!(error.contains("Upgrade aca"));
+    }
+
+    #[test]
+    fn test_data_migration_backs_up_first() {
+        let workspace = tempfile::tempdir().unwrap();
+        let ledger = env::cost_ledger_file_path(workspace.path());
+        std::fs::create_dir_all(ledger.parent().unwrap()).unwrap();
+        std::fs::write(&ledger, "old\n").unwrap();
+
+        let migrations = [Migration {
+            from: 1,
+            description: "rewrite the ledger",
+            modifies_data: true,
+            apply: |root| Ok(std::fs::write(env::cost_ledger_file_path(root), "new\n")?),
+        }];
+        let manager = MigrationManager::new(workspace.path());
+        let MigrationOutcome::Migrated {
+            backup: Some(backup),
+            ..
+        } = manager.run_migrations(&migrations, 2).unwrap()
+        else {
+            panic!("Expected a backed-up migration");
+        };
+
+        assert_eq!(std::fs::read_to_string(&ledger).unwrap(), "new\n");
+        let backed_up = backup
+            .join(env::usage::USAGE_DIR_NAME)
+            .join(env::usage::COST_LEDGER_FILE_NAME);
+        assert_eq!(std::fs::read_to_string(backed_up).unwrap(), "old\n");
+        assert_eq!(manager.detect().unwrap().unwrap().version, 2);
+    }
+}
@@ -9,7 +9,16 @@
 //! - **[`SessionManager`]**: Central orchestrator for session lifecycle
 //! - **`PersistenceManager`**: Atomic file operations with transaction support
 //! - **`RecoveryManager`**: State validation and corruption recovery
+//! - **[`CheckpointIntegrity`]**: Hash and signature checks before a checkpoint is restored
 //! - **[`SessionMetadata`]**: Version tracking and performance metrics
+//! - **[`InteractionRecord`]**: Per-task provider calls with their logs and usage
+//! - **[`RunReport`]**: A finished run's tasks, changed files and cost, rendered as a PR description
+//! - **[`TaskTranscript`]**: A task's prompts, responses and tool uses as markdown or JSON
+//! - **[`LogRetention`]**: Age, size and compression limits for session logs
+//! - **[`StorageQuotaConfig`]**: Per-session size caps on logs, artifacts and checkpoints
+//! - **[`MigrationManager`]**: `.aca` layout upgrades with backups
+//! - **[`CrashReport`]**: Panic details saved for bug reports, with the session marked dirty
+//! - **`RemoteSync`**: Checkpoint backup to S3, GCS or a local directory (`remote-sync` feature)
 //!
 //! ## Key Features
 //!
@@ -60,12 +69,59 @@
 //! }
 //! ```
 
+/// Crash reports and dirty session markers.
+///
+/// A panic hook saves the panic, a backtrace, the log tail and the
+/// sanitized config, and marks the session for recovery on the next start.
+pub mod crash;
+
+/// Cross-session cost ledger for chargeback reporting.
+///
+/// Records per-task usage with cost-center tags and aggregates spend
+/// across all sessions in a workspace.
+pub mod cost_ledger;
+
+/// Heartbeat and liveness reporting for unattended runs.
+///
+/// Periodically publishes run status to a file or ping URL and detects
+/// tasks that have gone silent.
+pub mod heartbeat;
+
+/// Content hashes and signatures for checkpoint files.
+///
+/// Detects checkpoints corrupted or modified after creation so restores can
+/// refuse them.
+pub mod integrity;
+
+/// Provider interaction records kept in the session state.
+///
+/// Links each task to the provider calls made for it, with their log files,
+/// model, token usage, duration and exit status.
+pub mod interactions;
+
+/// Workspace event journal.
+///
+/// Append-only record of cross-session decisions such as plan approvals.
+pub mod journal;
+
+/// Retention and rotation of session log directories.
+///
+/// Compresses, expires and size-caps provider interaction logs across
+/// sessions.
+pub mod log_retention;
+
 /// Central session lifecycle management and coordination.
 ///
 /// The [`SessionManager`] orchestrates all session operations including
 /// persistence, recovery, and state management.
 pub mod manager;
 
+/// `.aca` layout versioning and migration between releases.
+///
+/// Upgrades older workspace data in place after taking a backup, and
+/// refuses data written by a newer release.
+pub mod migration;
+
 /// Session metadata and configuration tracking.
 ///
 /// Handles version information, performance metrics, and session
@@ -78,6 +134,41 @@ pub mod metadata;
 /// and integrity validation for session state.
 pub mod persistence;
 
+/// Per-session disk quotas.
+///
+/// Caps the space a session's logs, artifacts and checkpoints take, removing
+/// the lowest-value files first.
+pub mod quota;
+
+/// Remote backup of checkpoints and run records.
+///
+/// Mirrors sessions to an object store so runs on ephemeral machines can be
+/// resumed elsewhere.
+pub mod remote_sync;
+
+/// Run summaries for pull request descriptions.
+///
+/// Collects task outcomes, edited files and cost from a saved session.
+pub mod run_report;
+
+/// Ed25519 signatures over run reports and ledgers.
+///
+/// Lets organizations prove a report or the signed part of a ledger was not
+/// altered after a run.
+pub mod report_signing;
+
+/// Conversation transcripts of individual tasks.
+///
+/// Renders a task's prompts, responses, tool uses, questions and errors
+/// from the session state and its logs, for attaching to PRs.
+pub mod transcript;
+
+/// Step-by-step replays of whole sessions.
+///
+/// Orders a session's task starts, provider calls, diffs, journal entries and
+/// task outcomes into one timeline for post-mortems.
+pub mod replay;
+
 /// State validation and corruption recovery.
 ///
 /// Handles integrity checking, automatic recovery from corruption,
@@ -87,7 +178,23 @@ pub mod recovery;
 #[cfg(test)]
 mod tests;
 
+pub use cost_ledger::{CostLedger, CostLedgerEntry, TagCostSummary, summarize_by_tag};
+pub use crash::{CrashReport, DirtySession, LogTail};
+pub use heartbeat::{HeartbeatConfig, HeartbeatMonitor, HeartbeatStatus};
+pub use integrity::{CheckpointIntegrity, SigningKey};
+pub use interactions::{InteractionRecord, PayloadBytes, PromptSizeConfig};
+pub use journal::{EventJournal, JournalEntry, JournalEvent};
+pub use log_retention::{LogRetention, LogRetentionConfig, RetentionReport};
 pub use manager::*;
 pub use metadata::*;
+pub use migration::{MigrationManager, MigrationOutcome};
 pub use persistence::*;
+pub use quota::{QuotaReport, StorageCategory, StorageQuotaConfig};
 pub use recovery::*;
+#[cfg(feature = "remote-sync")]
+pub use remote_sync::RemoteSync;
+pub use remote_sync::RemoteSyncConfig;
+pub use replay::{ReplayEvent, ReplayStep, SessionReplay, ToolDiff};
+pub use report_signing::{ReportSignature, ReportSigner, ReportSigningConfig, SignatureCheck};
+pub use run_report::{ChangedFile, PayloadReport, PromptSize, RunReport, TaskOutcome};
+pub use transcript::{TaskTranscript, TranscriptQuestion, TranscriptTurn};
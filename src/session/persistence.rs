@@ -1,6 +1,12 @@
+use crate::claude::types::ConversationContext;
+use crate::claude::usage_tracker::UsageData;
 use crate::env;
+use crate::session::integrity::{self, CheckpointIntegrity, SigningKey};
+use crate::session::interactions::{self, InteractionRecord};
 use crate::session::metadata::*;
+use crate::session::quota::{self, QuotaReport, StorageQuotaConfig};
 use crate::task::tree::TaskTree;
+use crate::task::types::TaskId;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -15,6 +21,33 @@ pub struct SessionState {
     pub task_tree: TaskTree,
     pub execution_context: ExecutionContext,
     pub file_system_state: FileSystemState,
+    /// Provider calls made for the session's tasks, oldest first
+    #[serde(default)]
+    pub interactions: Vec<InteractionRecord>,
+    /// Conversation history and usage statistics of the provider interface
+    #[serde(default)]
+    pub conversation: ConversationState,
+}
+
+/// Provider-side state a resumed session continues from
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversationState {
+    pub contexts: Vec<ConversationContext>,
+    pub usage: UsageData,
+}
+
+impl ConversationState {
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty() && self.usage.is_empty()
+    }
+}
+
+impl SessionState {
+    /// Provider calls made for `task_id`, oldest first
+    pub fn interactions_for_task(&self, task_id: TaskId) -> Vec<&InteractionRecord> {
+        interactions::for_task(&self.interactions, task_id)
+    }
 }
 
 /// Execution context state
@@ -72,6 +105,10 @@ pub struct PersistenceConfig {
     pub checksum_validation: bool,
     pub auto_cleanup: bool,
     pub max_checkpoint_age_hours: u32,
+    /// Sign checkpoint hashes so tampered checkpoints are refused
+    pub signing_key: Option<SigningKey>,
+    /// Size caps on the session's logs, artifacts and checkpoints
+    pub quotas: StorageQuotaConfig,
 }
 
 /// Result of a persistence operation
@@ -147,6 +184,14 @@ impl PersistenceManager {
         })
     }
 
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
     /// Save session state atomically
     pub async fn save_session_state(&self, state: &SessionState) -> Result<PersistenceResult> {
         let _start_time = std::time::Instant::now();
@@ -237,6 +282,16 @@ impl PersistenceManager {
 
         // Save checkpoint
         let persistence_result = self.save_to_file(state, &checkpoint_file).await?;
+        let signature = self
+            .config
+            .signing_key
+            .as_ref()
+            .map(|key| key.sign(&persistence_result.checksum));
+        if let Some(signature) = &signature {
+            async_fs::write(checkpoint_file.with_extension("signature"), signature)
+                .await
+                .context("Failed to write checkpoint signature")?;
+        }
 
         let checkpoint_info = CheckpointInfo {
             id: checkpoint_id,
@@ -246,6 +301,8 @@ impl PersistenceManager {
             size_bytes: persistence_result.bytes_written,
             is_automatic: matches!(trigger, CheckpointTrigger::Automatic { .. }),
             trigger_reason: trigger,
+            content_hash: Some(persistence_result.checksum),
+            signature,
         };
 
         info!(
@@ -258,12 +315,9 @@ impl PersistenceManager {
 
     /// Restore from a specific checkpoint
     pub async fn restore_from_checkpoint(&self, checkpoint_id: &str) -> Result<SessionState> {
-        let checkpoint_file =
-            env::checkpoint_file_path(&self.workspace_root, &self.session_id, checkpoint_id);
-
-        if !checkpoint_file.exists() {
-            return Err(anyhow::anyhow!("Checkpoint {} not found", checkpoint_id));
-        }
+        let checkpoint_file = self
+            .checkpoint_file(checkpoint_id)
+            .ok_or_else(|| anyhow::anyhow!("Checkpoint {} not found", checkpoint_id))?;
 
         info!("Restoring from checkpoint: {}", checkpoint_id);
 
@@ -272,6 +326,20 @@ impl PersistenceManager {
             .await
             .context("Failed to read checkpoint file")?;
 
+        if self.config.checksum_validation {
+            let integrity = self.check_integrity(&content, &checkpoint_file).await?;
+            if !integrity.is_usable() {
+                return Err(anyhow::anyhow!(
+                    "Checkpoint {} failed integrity check: {}",
+                    checkpoint_id,
+                    integrity
+                ));
+            }
+            if integrity == CheckpointIntegrity::Unverified {
+                warn!("Checkpoint {} has no recorded hash", checkpoint_id);
+            }
+        }
+
         let decompressed_content = if self.config.enable_compression {
             self.decompress_data(&content)?
         } else {
@@ -285,7 +353,36 @@ impl PersistenceManager {
         Ok(state)
     }
 
-    /// List available checkpoints
+    /// Check a checkpoint file against the hash and signature recorded with it
+    pub async fn verify_checkpoint(&self, checkpoint_id: &str) -> Result<CheckpointIntegrity> {
+        let checkpoint_file = self
+            .checkpoint_file(checkpoint_id)
+            .ok_or_else(|| anyhow::anyhow!("Checkpoint {} not found", checkpoint_id))?;
+        let content = async_fs::read(&checkpoint_file)
+            .await
+            .with_context(|| format!("Failed to read checkpoint {}", checkpoint_id))?;
+        self.check_integrity(&content, &checkpoint_file).await
+    }
+
+    /// Checkpoint file in this session, or else in another session of the
+    /// workspace, so a new session can resume from an earlier one
+    fn checkpoint_file(&self, checkpoint_id: &str) -> Option<PathBuf> {
+        let own = env::checkpoint_file_path(&self.workspace_root, &self.session_id, checkpoint_id);
+        if own.exists() {
+            return Some(own);
+        }
+        find_checkpoint_file(&self.workspace_root, checkpoint_id)
+    }
+
+    async fn check_integrity(
+        &self,
+        content: &[u8],
+        file_path: &Path,
+    ) -> Result<CheckpointIntegrity> {
+        check_file_integrity(content, file_path, None, self.config.signing_key.as_ref()).await
+    }
+
+    /// List available checkpoints, oldest first
     pub async fn list_checkpoints(&self) -> Result<Vec<String>> {
         let mut checkpoints = Vec::new();
 
@@ -299,6 +396,7 @@ impl PersistenceManager {
             .await
             .context("Failed to read checkpoints directory")?;
 
+        let mut found = Vec::new();
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
             if let Some(file_name) = path.file_name()
@@ -307,11 +405,14 @@ impl PersistenceManager {
                 && name_str.ends_with(".json")
             {
                 let checkpoint_id = name_str.strip_suffix(".json").unwrap();
-                checkpoints.push(checkpoint_id.to_string());
+                let modified = entry.metadata().await?.modified()?;
+                found.push((modified, checkpoint_id.to_string()));
             }
         }
 
-        checkpoints.sort();
+        // Checkpoint ids are random, so order by when they were written
+        found.sort();
+        checkpoints.extend(found.into_iter().map(|(_, checkpoint_id)| checkpoint_id));
         Ok(checkpoints)
     }
 
@@ -355,6 +456,17 @@ impl PersistenceManager {
         Ok(cleaned_count)
     }
 
+    /// Remove the session's lowest-value files while it exceeds its quotas
+    pub async fn enforce_quotas(&self) -> Result<QuotaReport> {
+        let quotas = self.config.quotas.clone();
+        let workspace_root = self.workspace_root.clone();
+        let session_id = self.session_id.clone();
+        tokio::task::spawn_blocking(move || quota::enforce(&quotas, &workspace_root, &session_id))
+            .await
+            .context("Storage quota task failed")?
+            .context("Failed to enforce storage quotas")
+    }
+
     /// Begin atomic transaction
     async fn begin_transaction(&self) -> Result<PersistenceTransaction> {
         let transaction_id = uuid::Uuid::new_v4().to_string();
@@ -526,31 +638,89 @@ impl PersistenceManager {
 
     /// Calculate checksum for data
     fn calculate_checksum(&self, data: &[u8]) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        data.hash(&mut hasher);
-        format!("{:x}", hasher.finish())
+        integrity::content_hash(data)
     }
 
     /// Validate checksum of loaded data
     async fn validate_checksum(&self, data: &[u8], file_path: &Path) -> Result<()> {
-        let checksum_file = file_path.with_extension("checksum");
+        let stored_checksum = read_sidecar(&file_path.with_extension("checksum")).await?;
 
-        if checksum_file.exists() {
-            let stored_checksum = async_fs::read_to_string(&checksum_file)
-                .await
-                .context("Failed to read checksum file")?;
+        if let CheckpointIntegrity::Corrupted { .. } =
+            integrity::verify(data, stored_checksum.as_deref(), None, None)
+        {
+            return Err(anyhow::anyhow!("Checksum validation failed"));
+        }
 
-            let calculated_checksum = self.calculate_checksum(data);
+        Ok(())
+    }
+}
 
-            if stored_checksum.trim() != calculated_checksum {
-                return Err(anyhow::anyhow!("Checksum validation failed"));
-            }
+/// Saved state of every session in `workspace_root`
+///
+/// Sessions whose state file is missing or unreadable are skipped.
+pub fn load_workspace_sessions(workspace_root: &Path) -> Result<Vec<SessionState>> {
+    let sessions_dir = env::sessions_dir_path(workspace_root);
+    let entries = std::fs::read_dir(&sessions_dir)
+        .with_context(|| format!("No sessions found in {}", sessions_dir.display()))?;
+
+    let mut states = Vec::new();
+    for entry in entries.flatten() {
+        let session_id = entry.file_name().to_string_lossy().to_string();
+        let state_file = env::session_state_file_path(workspace_root, &session_id);
+        let Ok(content) = std::fs::read(&state_file) else {
+            continue;
+        };
+        match serde_json::from_slice(&content) {
+            Ok(state) => states.push(state),
+            Err(e) => warn!("Skipping unreadable session {}: {}", session_id, e),
         }
+    }
+    Ok(states)
+}
 
-        Ok(())
+/// Check a checkpoint file against the hash and signature in `recorded`
+///
+/// Without a recorded hash, the checksum and signature files written next to
+/// the checkpoint are used instead.
+pub async fn verify_checkpoint_file(
+    path: &Path,
+    recorded: Option<&CheckpointInfo>,
+    key: Option<&SigningKey>,
+) -> Result<CheckpointIntegrity> {
+    let content = async_fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    check_file_integrity(&content, path, recorded, key).await
+}
+
+async fn check_file_integrity(
+    content: &[u8],
+    path: &Path,
+    recorded: Option<&CheckpointInfo>,
+    key: Option<&SigningKey>,
+) -> Result<CheckpointIntegrity> {
+    let expected = match recorded.and_then(|info| info.content_hash.clone()) {
+        Some(hash) => Some(hash),
+        None => read_sidecar(&path.with_extension("checksum")).await?,
+    };
+    let signature = match recorded.and_then(|info| info.signature.clone()) {
+        Some(signature) => Some(signature),
+        None => read_sidecar(&path.with_extension("signature")).await?,
+    };
+    Ok(integrity::verify(
+        content,
+        expected.as_deref(),
+        signature.as_deref(),
+        key,
+    ))
+}
+
+/// Contents of a checksum or signature file, `None` if there is none
+async fn read_sidecar(path: &Path) -> Result<Option<String>> {
+    match async_fs::read_to_string(path).await {
+        Ok(contents) => Ok(Some(contents.trim().to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
     }
 }
 
@@ -563,6 +733,8 @@ impl Default for PersistenceConfig {
             checksum_validation: true,
             auto_cleanup: true,
             max_checkpoint_age_hours: 168, // 1 week
+            signing_key: None,
+            quotas: StorageQuotaConfig::default(),
         }
     }
 }
@@ -590,3 +762,18 @@ impl Default for ResourceUsageSnapshot {
         }
     }
 }
+
+/// Checkpoint file with this id in any session of the workspace
+pub(crate) fn find_checkpoint_file(
+    workspace_root: &std::path::Path,
+    checkpoint_id: &str,
+) -> Option<PathBuf> {
+    std::fs::read_dir(crate::env::sessions_dir_path(workspace_root))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(|session_id| {
+            crate::env::checkpoint_file_path(workspace_root, &session_id, checkpoint_id)
+        })
+        .find(|path| path.exists())
+}
@@ -0,0 +1,349 @@
+//! Per-session disk quotas for artifacts, logs and checkpoints.
+//!
+//! Each [`StorageCategory`] of a session directory can be capped, as can
+//! the session as a whole. When a cap is exceeded the lowest-value items go
+//! first: logs before artifacts before checkpoints, oldest first within a
+//! category. The newest checkpoint is never removed, so a session stays
+//! recoverable however small its quota.
+
+use crate::env;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Kinds of files a session stores, in eviction order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageCategory {
+    /// Provider interaction and error logs
+    Logs,
+    /// Files tasks saved for later inspection
+    Artifacts,
+    /// Session state checkpoints and their signatures
+    Checkpoints,
+}
+
+impl StorageCategory {
+    pub const ALL: [StorageCategory; 3] = [Self::Logs, Self::Artifacts, Self::Checkpoints];
+
+    /// Directory of a session holding this category
+    pub fn dir_path(self, workspace_root: &Path, session_id: &str) -> PathBuf {
+        match self {
+            Self::Logs => env::session_logs_dir_path(workspace_root, session_id),
+            Self::Artifacts => env::session_artifacts_dir_path(workspace_root, session_id),
+            Self::Checkpoints => env::session_checkpoints_dir_path(workspace_root, session_id),
+        }
+    }
+}
+
+impl std::fmt::Display for StorageCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Logs => "logs",
+            Self::Artifacts => "artifacts",
+            Self::Checkpoints => "checkpoints",
+        })
+    }
+}
+
+/// Size limits for one session's stored files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageQuotaConfig {
+    /// Enforce the quotas after every save and checkpoint
+    pub enabled: bool,
+    pub logs_mb: Option<u64>,
+    pub artifacts_mb: Option<u64>,
+    pub checkpoints_mb: Option<u64>,
+    /// Limit for the three categories together
+    pub total_mb: Option<u64>,
+}
+
+impl Default for StorageQuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            logs_mb: Some(1024),
+            artifacts_mb: Some(1024),
+            checkpoints_mb: Some(512),
+            total_mb: Some(2048),
+        }
+    }
+}
+
+impl StorageQuotaConfig {
+    pub fn limit_mb(&self, category: StorageCategory) -> Option<u64> {
+        match category {
+            StorageCategory::Logs => self.logs_mb,
+            StorageCategory::Artifacts => self.artifacts_mb,
+            StorageCategory::Checkpoints => self.checkpoints_mb,
+        }
+    }
+}
+
+/// A file removed to bring a session under its quota
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovedItem {
+    pub category: StorageCategory,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// What one enforcement run removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuotaReport {
+    pub removed: Vec<RemovedItem>,
+}
+
+impl QuotaReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+    }
+
+    pub fn bytes_freed(&self) -> u64 {
+        self.removed.iter().map(|item| item.size).sum()
+    }
+
+    /// Removed paths and bytes freed in `category`
+    pub fn for_category(&self, category: StorageCategory) -> (Vec<PathBuf>, u64) {
+        let items = self.removed.iter().filter(|item| item.category == category);
+        (
+            items.clone().map(|item| item.path.clone()).collect(),
+            items.map(|item| item.size).sum(),
+        )
+    }
+
+    /// Ids of the checkpoints that were removed
+    pub fn removed_checkpoints(&self) -> impl Iterator<Item = &str> {
+        self.removed
+            .iter()
+            .filter(|item| item.category == StorageCategory::Checkpoints)
+            .filter_map(|item| item.path.file_stem()?.to_str())
+    }
+}
+
+/// One removable unit: a file, or a checkpoint with its signature
+#[derive(Debug)]
+struct StoredItem {
+    category: StorageCategory,
+    paths: Vec<PathBuf>,
+    size: u64,
+    modified: DateTime<Utc>,
+    /// Counted against the quota but never removed
+    protected: bool,
+}
+
+/// Bring one session's files under `config`'s limits
+pub fn enforce(
+    config: &StorageQuotaConfig,
+    workspace_root: &Path,
+    session_id: &str,
+) -> io::Result<QuotaReport> {
+    let mut report = QuotaReport::default();
+    if !config.enabled {
+        return Ok(report);
+    }
+
+    let mut items = Vec::new();
+    for category in StorageCategory::ALL {
+        collect_items(
+            category,
+            &category.dir_path(workspace_root, session_id),
+            &mut items,
+        )?;
+    }
+    // Lowest value first: by category, then oldest first
+    items.sort_by_key(|item| (item.category, item.modified));
+    if let Some(newest) = items
+        .iter_mut()
+        .rfind(|item| item.category == StorageCategory::Checkpoints)
+    {
+        newest.protected = true;
+    }
+
+    for category in StorageCategory::ALL {
+        if let Some(limit) = config.limit_mb(category) {
+            evict(&mut items, &mut report, limit * 1024 * 1024, |item| {
+                item.category == category
+            });
+        }
+    }
+    if let Some(limit) = config.total_mb {
+        evict(&mut items, &mut report, limit * 1024 * 1024, |_| true);
+    }
+
+    if !report.is_empty() {
+        info!(
+            "Storage quota removed {} file(s) from session {}, freeing {} bytes",
+            report.removed.len(),
+            session_id,
+            report.bytes_freed()
+        );
+    }
+    Ok(report)
+}
+
+/// Remove the first items matching `selected` while their total exceeds `limit`
+fn evict(
+    items: &mut Vec<StoredItem>,
+    report: &mut QuotaReport,
+    limit: u64,
+    selected: impl Fn(&StoredItem) -> bool,
+) {
+    let mut total: u64 = items
+        .iter()
+        .filter(|item| selected(item))
+        .map(|item| item.size)
+        .sum();
+    items.retain(|item| {
+        if total <= limit || !selected(item) || item.protected {
+            return true;
+        }
+        for path in &item.paths {
+            match std::fs::remove_file(path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    warn!("Failed to remove {}: {}", path.display(), e);
+                    return true;
+                }
+            }
+        }
+        total -= item.size;
+        report.removed.push(RemovedItem {
+            category: item.category,
+            path: item.paths[0].clone(),
+            size: item.size,
+        });
+        false
+    });
+}
+
+fn collect_items(
+    category: StorageCategory,
+    dir: &Path,
+    items: &mut Vec<StoredItem>,
+) -> io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_items(category, &path, items)?;
+            continue;
+        }
+        let mut item = StoredItem {
+            category,
+            paths: vec![path.clone()],
+            size: metadata.len(),
+            modified: metadata.modified()?.into(),
+            protected: false,
+        };
+        if category == StorageCategory::Checkpoints {
+            // Signatures go with their checkpoint
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let signature = path.with_extension("signature");
+            if let Ok(signature_metadata) = std::fs::metadata(&signature) {
+                item.size += signature_metadata.len();
+                item.paths.push(signature);
+            }
+        }
+        items.push(item);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    fn write_file(path: &Path, size: usize, age_minutes: i64) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, vec![b'x'; size]).unwrap();
+        let modified = Utc::now() - chrono::Duration::minutes(age_minutes);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified.into())
+            .unwrap();
+    }
+
+    fn workspace() -> PathBuf {
+        let workspace = std::env::temp_dir().join(format!("aca-quota-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        workspace
+    }
+
+    const MB: usize = 1024 * 1024;
+
+    #[test]
+    fn test_category_quota_removes_oldest_files_first() {
+        let workspace = workspace();
+        let logs = env::session_logs_dir_path(&workspace, "s1");
+        write_file(&logs.join("old.json"), MB, 30);
+        write_file(&logs.join("claude_interactions/mid.json"), MB, 20);
+        write_file(&logs.join("new.json"), MB, 10);
+
+        let config = StorageQuotaConfig {
+            logs_mb: Some(2),
+            total_mb: None,
+            ..Default::default()
+        };
+        let report = enforce(&config, &workspace, "s1").unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].path, logs.join("old.json"));
+        assert_eq!(report.bytes_freed(), MB as u64);
+        assert!(logs.join("new.json").exists());
+
+        let disabled = StorageQuotaConfig {
+            enabled: false,
+            logs_mb: Some(0),
+            ..Default::default()
+        };
+        assert!(enforce(&disabled, &workspace, "s1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_total_quota_evicts_logs_before_checkpoints_and_keeps_newest() {
+        let workspace = workspace();
+        let logs = env::session_logs_dir_path(&workspace, "s1");
+        let artifacts = env::session_artifacts_dir_path(&workspace, "s1");
+        let checkpoints = env::session_checkpoints_dir_path(&workspace, "s1");
+        write_file(&checkpoints.join("checkpoint_a.json"), MB, 50);
+        write_file(&checkpoints.join("checkpoint_a.signature"), 64, 50);
+        write_file(&checkpoints.join("checkpoint_b.json"), MB, 40);
+        write_file(&artifacts.join("report.html"), MB, 30);
+        write_file(&logs.join("stdout.json"), MB, 5);
+
+        let config = StorageQuotaConfig {
+            total_mb: Some(1),
+            ..Default::default()
+        };
+        let report = enforce(&config, &workspace, "s1").unwrap();
+        let categories: Vec<_> = report.removed.iter().map(|item| item.category).collect();
+        assert_eq!(
+            categories,
+            [
+                StorageCategory::Logs,
+                StorageCategory::Artifacts,
+                StorageCategory::Checkpoints
+            ]
+        );
+        assert_eq!(
+            report.removed_checkpoints().collect::<Vec<_>>(),
+            ["checkpoint_a"]
+        );
+        assert!(!checkpoints.join("checkpoint_a.signature").exists());
+        assert!(checkpoints.join("checkpoint_b.json").exists());
+    }
+}
@@ -217,6 +217,9 @@ impl RecoveryManager {
     }
 
     /// Recover from a specific checkpoint
+    ///
+    /// A checkpoint that fails its integrity check is replaced by the newest
+    /// earlier checkpoint that passes, with a warning in the result.
     pub async fn recover_from_checkpoint(&self, checkpoint_id: &str) -> Result<RecoveryResult> {
         info!("Starting recovery from checkpoint: {}", checkpoint_id);
 
@@ -234,13 +237,44 @@ impl RecoveryManager {
             success: false,
         };
 
+        let mut warnings = Vec::new();
+        let mut checkpoint_id = checkpoint_id.to_string();
+        if self.persistence.config.checksum_validation
+            && let Ok(integrity) = self.persistence.verify_checkpoint(&checkpoint_id).await
+            && !integrity.is_usable()
+        {
+            warn!(
+                "Checkpoint {} failed integrity check: {}",
+                checkpoint_id, integrity
+            );
+            let Some(previous) = self.previous_valid_checkpoint(&checkpoint_id).await? else {
+                recovery_info.completed_at = Some(Utc::now());
+                return Ok(RecoveryResult {
+                    success: false,
+                    recovered_state: None,
+                    recovery_info,
+                    warnings,
+                    errors: vec![format!(
+                        "Checkpoint {} failed integrity check ({}) and no earlier checkpoint is valid",
+                        checkpoint_id, integrity
+                    )],
+                });
+            };
+            warnings.push(format!(
+                "Checkpoint {} failed integrity check ({}); restored {} instead",
+                checkpoint_id, integrity, previous
+            ));
+            recovery_info.recovery_type = RecoveryType::CorruptionRecovery;
+            recovery_info.source_checkpoint = Some(previous.clone());
+            checkpoint_id = previous;
+        }
+
         match self
             .persistence
-            .restore_from_checkpoint(checkpoint_id)
+            .restore_from_checkpoint(&checkpoint_id)
             .await
         {
             Ok(state) => {
-                let mut warnings = Vec::new();
                 let mut errors = Vec::new();
 
                 // Validate if configured
@@ -289,6 +323,22 @@ impl RecoveryManager {
         }
     }
 
+    /// Newest checkpoint written before `checkpoint_id` that passes its integrity check
+    async fn previous_valid_checkpoint(&self, checkpoint_id: &str) -> Result<Option<String>> {
+        let checkpoints = self.persistence.list_checkpoints().await?;
+        let earlier = checkpoints
+            .iter()
+            .take_while(|candidate| candidate.as_str() != checkpoint_id);
+        for candidate in earlier.collect::<Vec<_>>().into_iter().rev() {
+            match self.persistence.verify_checkpoint(candidate).await {
+                Ok(integrity) if integrity.is_usable() => return Ok(Some(candidate.clone())),
+                Ok(integrity) => warn!("Skipping checkpoint {}: {}", candidate, integrity),
+                Err(e) => warn!("Skipping checkpoint {}: {}", candidate, e),
+            }
+        }
+        Ok(None)
+    }
+
     /// Validate session state integrity
     pub async fn validate_session_state(&self, state: &SessionState) -> Result<ValidationResult> {
         let mut errors = Vec::new();
@@ -0,0 +1,312 @@
+//! Remote backup of session checkpoints and run records.
+//!
+//! Runs on ephemeral machines such as CI runners lose `.aca` with the
+//! machine. [`RemoteSync`] mirrors each session's checkpoints and state,
+//! plus the workspace's plan records and cost ledger, to an object store
+//! whenever the agent checkpoints. `aca session restore --from-remote`
+//! downloads them into a fresh checkout so the run can be resumed from the
+//! latest checkpoint.
+//!
+//! Supported URLs are `s3://bucket/prefix`, `gs://bucket/prefix` and
+//! `file:///path`. Credentials and regions come from the usual `AWS_*` and
+//! `GOOGLE_*` environment variables. The store itself requires the
+//! `remote-sync` feature.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "remote-sync")]
+use crate::env;
+#[cfg(feature = "remote-sync")]
+use anyhow::{Context, Result};
+#[cfg(feature = "remote-sync")]
+use futures::TryStreamExt;
+#[cfg(feature = "remote-sync")]
+use object_store::{ObjectStore, PutPayload, path::Path as ObjectPath};
+#[cfg(feature = "remote-sync")]
+use std::collections::HashMap;
+#[cfg(feature = "remote-sync")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "remote-sync")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "remote-sync")]
+use std::time::SystemTime;
+#[cfg(feature = "remote-sync")]
+use tracing::{debug, info};
+
+/// Configuration for remote session backup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSyncConfig {
+    /// Object store URL to mirror to, e.g. `s3://bucket/aca/my-repo`;
+    /// remote sync is off when unset
+    pub url: Option<String>,
+}
+
+impl RemoteSyncConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+}
+
+/// Mirrors session checkpoints and run records to an object store
+#[cfg(feature = "remote-sync")]
+#[derive(Debug)]
+pub struct RemoteSync {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    workspace_root: PathBuf,
+    /// Modification time of each local file when it was last transferred
+    synced: Mutex<HashMap<PathBuf, SystemTime>>,
+}
+
+#[cfg(feature = "remote-sync")]
+impl RemoteSync {
+    pub fn new(url: &str, workspace_root: PathBuf) -> Result<Self> {
+        let parsed =
+            url::Url::parse(url).with_context(|| format!("Invalid remote URL: {}", url))?;
+        let (store, prefix): (Arc<dyn ObjectStore>, ObjectPath) = match parsed.scheme() {
+            "s3" => (
+                Arc::new(
+                    object_store::aws::AmazonS3Builder::from_env()
+                        .with_url(url)
+                        .build()?,
+                ),
+                ObjectPath::from_url_path(parsed.path())?,
+            ),
+            "gs" => (
+                Arc::new(
+                    object_store::gcp::GoogleCloudStorageBuilder::from_env()
+                        .with_url(url)
+                        .build()?,
+                ),
+                ObjectPath::from_url_path(parsed.path())?,
+            ),
+            "file" => {
+                let root = parsed
+                    .to_file_path()
+                    .map_err(|_| anyhow::anyhow!("Invalid file URL: {}", url))?;
+                std::fs::create_dir_all(&root)?;
+                (
+                    Arc::new(object_store::local::LocalFileSystem::new_with_prefix(root)?),
+                    ObjectPath::default(),
+                )
+            }
+            scheme => anyhow::bail!(
+                "Unsupported remote URL scheme '{}' (expected s3://, gs:// or file://)",
+                scheme
+            ),
+        };
+
+        Ok(Self {
+            store,
+            prefix,
+            workspace_root,
+            synced: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Build the configured remote, or `None` when remote sync is off
+    pub fn from_config(config: &RemoteSyncConfig, workspace_root: PathBuf) -> Result<Option<Self>> {
+        config
+            .url
+            .as_deref()
+            .map(|url| Self::new(url, workspace_root))
+            .transpose()
+    }
+
+    /// Upload a session's files that changed since the last sync
+    ///
+    /// Returns the number of files uploaded.
+    pub async fn sync_session(&self, session_id: &str) -> Result<usize> {
+        let mut uploaded = 0;
+        for file in session_files(&self.workspace_root, session_id) {
+            let modified = std::fs::metadata(&file)?.modified()?;
+            if self.synced.lock().unwrap().get(&file) == Some(&modified) {
+                continue;
+            }
+
+            let location = self.object_path(&file)?;
+            let data = tokio::fs::read(&file).await?;
+            self.store
+                .put(&location, PutPayload::from(data))
+                .await
+                .with_context(|| format!("Failed to upload {}", file.display()))?;
+            debug!("Uploaded {} to {}", file.display(), location);
+
+            self.synced.lock().unwrap().insert(file, modified);
+            uploaded += 1;
+        }
+
+        if uploaded > 0 {
+            info!(
+                "Synced {} file(s) of session {} to remote",
+                uploaded, session_id
+            );
+        }
+        Ok(uploaded)
+    }
+
+    /// Download backed-up files that are missing locally
+    ///
+    /// With `session_id`, other sessions are skipped; shared records such as
+    /// plan records are always restored. Existing local files are kept.
+    pub async fn restore(&self, session_id: Option<&str>) -> Result<Vec<PathBuf>> {
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&self.prefix))
+            .try_collect()
+            .await
+            .context("Failed to list remote backup")?;
+
+        let aca_dir = env::aca_dir_path(&self.workspace_root);
+        let mut restored = Vec::new();
+        for object in objects {
+            let Some(parts) = object.location.prefix_match(&self.prefix) else {
+                continue;
+            };
+            let relative: Vec<String> = parts.map(|part| part.as_ref().to_string()).collect();
+            if !is_wanted(&relative, session_id) {
+                continue;
+            }
+
+            let local = relative
+                .iter()
+                .fold(aca_dir.clone(), |path, part| path.join(part));
+            if local.exists() {
+                debug!("Keeping existing {}", local.display());
+                continue;
+            }
+
+            let data = self.store.get(&object.location).await?.bytes().await?;
+            if let Some(parent) = local.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&local, &data).await?;
+
+            let modified = std::fs::metadata(&local)?.modified()?;
+            self.synced.lock().unwrap().insert(local.clone(), modified);
+            restored.push(local);
+        }
+
+        info!("Restored {} file(s) from remote", restored.len());
+        Ok(restored)
+    }
+
+    fn object_path(&self, file: &Path) -> Result<ObjectPath> {
+        let relative = file
+            .strip_prefix(env::aca_dir_path(&self.workspace_root))
+            .with_context(|| format!("{} is outside the .aca directory", file.display()))?;
+        Ok(relative
+            .components()
+            .fold(self.prefix.clone(), |path, part| {
+                path.child(part.as_os_str().to_string_lossy().into_owned())
+            }))
+    }
+}
+
+/// Local files mirrored for a session
+#[cfg(feature = "remote-sync")]
+fn session_files(workspace_root: &Path, session_id: &str) -> Vec<PathBuf> {
+    let mut files = vec![
+        env::layout_file_path(workspace_root),
+        env::session_state_file_path(workspace_root, session_id),
+        env::cost_ledger_file_path(workspace_root),
+    ];
+    for dir in [
+        env::session_checkpoints_dir_path(workspace_root, session_id),
+        env::plans_dir_path(workspace_root),
+    ] {
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            files.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+    files.retain(|file| file.is_file());
+    files
+}
+
+/// Whether a remote file (relative to `.aca`) belongs in a restore
+#[cfg(feature = "remote-sync")]
+fn is_wanted(relative: &[String], session_id: Option<&str>) -> bool {
+    match (relative.first(), session_id) {
+        (Some(dir), Some(id)) if dir == env::session::SESSIONS_DIR_NAME => {
+            relative.get(1).map(String::as_str) == Some(id)
+        }
+        _ => true,
+    }
+}
+
+#[cfg(all(test, feature = "remote-sync"))]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn remote_url(dir: &Path) -> String {
+        url::Url::from_directory_path(dir).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_sync_and_restore_session() {
+        let workspace = tempfile::tempdir().unwrap();
+        let remote = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+
+        let checkpoint = env::checkpoint_file_path(root, "s1", "checkpoint_a");
+        write(&checkpoint, "{}");
+        write(&checkpoint.with_extension("checksum"), "sha256:00");
+        write(&env::session_state_file_path(root, "s1"), "{}");
+        write(&env::plan_record_file_path(root, "plan"), "{}");
+        write(
+            &env::session_logs_dir_path(root, "s1").join("run.log"),
+            "log",
+        );
+
+        let sync = RemoteSync::new(&remote_url(remote.path()), root.to_path_buf()).unwrap();
+        assert_eq!(sync.sync_session("s1").await.unwrap(), 4);
+        // Unchanged files are not uploaded again
+        assert_eq!(sync.sync_session("s1").await.unwrap(), 0);
+
+        let fresh = tempfile::tempdir().unwrap();
+        let restore =
+            RemoteSync::new(&remote_url(remote.path()), fresh.path().to_path_buf()).unwrap();
+        let restored = restore.restore(None).await.unwrap();
+        assert_eq!(restored.len(), 4);
+        assert!(env::checkpoint_file_path(fresh.path(), "s1", "checkpoint_a").exists());
+        assert!(env::plan_record_file_path(fresh.path(), "plan").exists());
+        assert!(!env::session_logs_dir_path(fresh.path(), "s1").exists());
+
+        // Restored files count as synced and existing files are kept
+        assert_eq!(restore.sync_session("s1").await.unwrap(), 0);
+        assert!(restore.restore(None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore_single_session() {
+        let workspace = tempfile::tempdir().unwrap();
+        let remote = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        let sync = RemoteSync::new(&remote_url(remote.path()), root.to_path_buf()).unwrap();
+        for session in ["s1", "s2"] {
+            write(&env::session_state_file_path(root, session), "{}");
+            sync.sync_session(session).await.unwrap();
+        }
+        write(&env::plan_record_file_path(root, "plan"), "{}");
+        sync.sync_session("s2").await.unwrap();
+
+        let fresh = tempfile::tempdir().unwrap();
+        let restored = RemoteSync::new(&remote_url(remote.path()), fresh.path().to_path_buf())
+            .unwrap()
+            .restore(Some("s2"))
+            .await
+            .unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(env::session_state_file_path(fresh.path(), "s2").exists());
+        assert!(!env::session_state_file_path(fresh.path(), "s1").exists());
+        assert!(env::plan_record_file_path(fresh.path(), "plan").exists());
+
+        assert!(RemoteSync::new("ftp://host/path", fresh.path().to_path_buf()).is_err());
+    }
+}
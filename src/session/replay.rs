@@ -0,0 +1,630 @@
+//! Step-by-step replays of a saved session.
+//!
+//! A replay orders what a session recorded into one timeline: each task the
+//! scheduler started, with the tasks still waiting at that point; every
+//! provider call with its prompt, response, tool uses and the diffs its edit
+//! tools made; event journal entries from the session's lifetime; and each
+//! task's outcome. `aca replay` pages through the steps for post-mortems of
+//! why the agent did what it did.
+
+use crate::policy::PolicyEffect;
+use crate::session::journal::{JournalEntry, JournalEvent};
+use crate::session::persistence::SessionState;
+use crate::session::transcript::TranscriptTurn;
+use crate::task::types::{Task, TaskId, TaskStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A file change made by one tool use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolDiff {
+    pub tool: String,
+    pub path: PathBuf,
+    /// Removed lines prefixed with `-`, added lines with `+`
+    pub diff: String,
+}
+
+/// Something that happened during the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    /// The scheduler started a task
+    TaskPicked {
+        task_id: TaskId,
+        title: String,
+        priority: String,
+        /// Titles of the task's dependencies, finished by then
+        dependencies: Vec<String>,
+        /// Titles of other tasks that existed but had not started, highest
+        /// priority first
+        waiting: Vec<String>,
+    },
+    /// A provider call made for a task
+    ProviderCall {
+        task_id: TaskId,
+        task_title: String,
+        turn: Box<TranscriptTurn>,
+        diffs: Vec<ToolDiff>,
+    },
+    /// An event journal entry from while the session ran
+    Journal { event: JournalEvent },
+    /// A task completed, failed or was skipped
+    TaskFinished {
+        task_id: TaskId,
+        title: String,
+        status: String,
+        /// Failure or skip reason
+        note: Option<String>,
+    },
+}
+
+impl ReplayEvent {
+    /// Order of events recorded at the same instant
+    fn rank(&self) -> u8 {
+        match self {
+            ReplayEvent::TaskPicked { .. } => 0,
+            ReplayEvent::ProviderCall { .. } => 1,
+            ReplayEvent::Journal { .. } => 2,
+            ReplayEvent::TaskFinished { .. } => 3,
+        }
+    }
+}
+
+/// One step of a replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStep {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: ReplayEvent,
+}
+
+/// A session's recorded decisions in the order they were made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionReplay {
+    pub session_id: String,
+    pub session_name: String,
+    pub steps: Vec<ReplayStep>,
+}
+
+impl SessionReplay {
+    /// Assemble the replay of `state` with journal entries from its lifetime
+    pub fn build(state: &SessionState, journal: &[JournalEntry]) -> Self {
+        let tasks = &state.task_tree.tasks;
+        let mut started: HashMap<TaskId, DateTime<Utc>> = HashMap::new();
+        for interaction in &state.interactions {
+            started
+                .entry(interaction.task_id)
+                .and_modify(|at| *at = (*at).min(interaction.started_at))
+                .or_insert(interaction.started_at);
+        }
+        for task in tasks.values() {
+            if let TaskStatus::InProgress { started_at, .. } = task.status {
+                started.entry(task.id).or_insert(started_at);
+            }
+        }
+        let title = |task_id: &TaskId| {
+            tasks
+                .get(task_id)
+                .map(|task| task.title.clone())
+                .unwrap_or_else(|| format!("task {}", task_id))
+        };
+
+        let mut steps = Vec::new();
+        for (&task_id, &at) in &started {
+            let Some(task) = tasks.get(&task_id) else {
+                continue;
+            };
+            let mut waiting: Vec<&Task> = tasks
+                .values()
+                .filter(|other| {
+                    other.id != task_id
+                        && other.children.is_empty()
+                        && other.created_at <= at
+                        && started.get(&other.id).is_none_or(|start| *start > at)
+                        && finished_at(other).is_none_or(|end| end > at)
+                })
+                .collect();
+            waiting.sort_by(|a, b| {
+                b.priority_value()
+                    .cmp(&a.priority_value())
+                    .then_with(|| a.title.cmp(&b.title))
+            });
+            steps.push(ReplayStep {
+                timestamp: at,
+                event: ReplayEvent::TaskPicked {
+                    task_id,
+                    title: task.title.clone(),
+                    priority: format!("{:?}", task.metadata.priority),
+                    dependencies: task.dependencies.iter().map(title).collect(),
+                    waiting: waiting.iter().map(|task| task.title.clone()).collect(),
+                },
+            });
+        }
+
+        for interaction in &state.interactions {
+            let turn = TranscriptTurn::from_interaction(interaction);
+            steps.push(ReplayStep {
+                timestamp: interaction.started_at,
+                event: ReplayEvent::ProviderCall {
+                    task_id: interaction.task_id,
+                    task_title: title(&interaction.task_id),
+                    diffs: tool_diffs(&turn.tool_uses),
+                    turn: Box::new(turn),
+                },
+            });
+        }
+
+        let lifetime = state.metadata.created_at..=state.metadata.last_updated;
+        for entry in journal {
+            if lifetime.contains(&entry.timestamp) {
+                steps.push(ReplayStep {
+                    timestamp: entry.timestamp,
+                    event: ReplayEvent::Journal {
+                        event: entry.event.clone(),
+                    },
+                });
+            }
+        }
+
+        for task in tasks.values() {
+            let Some(at) = finished_at(task) else {
+                continue;
+            };
+            let note = match &task.status {
+                TaskStatus::Failed { error, .. } => Some(error.to_string()),
+                TaskStatus::Skipped { reason, .. } => Some(reason.clone()),
+                _ => None,
+            };
+            steps.push(ReplayStep {
+                timestamp: at,
+                event: ReplayEvent::TaskFinished {
+                    task_id: task.id,
+                    title: task.title.clone(),
+                    status: task.status.name().to_string(),
+                    note,
+                },
+            });
+        }
+
+        steps.sort_by_key(|step| (step.timestamp, step.event.rank()));
+        Self {
+            session_id: state.metadata.id.to_string(),
+            session_name: state.metadata.name.clone(),
+            steps,
+        }
+    }
+
+    /// Text of the step at `index`, with its position in the replay
+    pub fn render_step(&self, index: usize) -> String {
+        let Some(step) = self.steps.get(index) else {
+            return format!("No step {} in this replay\n", index + 1);
+        };
+        let mut out = format!(
+            "── Step {} of {} · {} ──\n",
+            index + 1,
+            self.steps.len(),
+            step.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+
+        match &step.event {
+            ReplayEvent::TaskPicked {
+                title,
+                priority,
+                dependencies,
+                waiting,
+                ..
+            } => {
+                out.push_str(&format!(
+                    "▶ Started \"{}\" (priority {})\n",
+                    title, priority
+                ));
+                if !dependencies.is_empty() {
+                    out.push_str(&format!(
+                        "  Dependencies finished: {}\n",
+                        dependencies.join(", ")
+                    ));
+                }
+                if waiting.is_empty() {
+                    out.push_str("  No other task was waiting\n");
+                } else {
+                    out.push_str(&format!("  Still waiting: {}\n", waiting.join(", ")));
+                }
+            }
+            ReplayEvent::ProviderCall {
+                task_title,
+                turn,
+                diffs,
+                ..
+            } => {
+                out.push_str(&format!(
+                    "💬 {} ({}) for \"{}\", {:.1}s{}\n\n",
+                    turn.provider,
+                    turn.model,
+                    task_title,
+                    turn.duration_ms as f64 / 1000.0,
+                    if turn.success { "" } else { ", failed" }
+                ));
+                section(
+                    &mut out,
+                    "Prompt",
+                    turn.prompt
+                        .as_deref()
+                        .unwrap_or("(prompt log not available)"),
+                );
+                if !turn.tool_uses.is_empty() {
+                    let tools: Vec<String> = turn
+                        .tool_uses
+                        .iter()
+                        .map(|tool_use| {
+                            let name = tool_use
+                                .get("tool_name")
+                                .and_then(Value::as_str)
+                                .unwrap_or("unknown");
+                            format!("{} {}", name, tool_use.get("input").unwrap_or(&Value::Null))
+                        })
+                        .collect();
+                    section(&mut out, "Tool uses", &tools.join("\n"));
+                }
+                for diff in diffs {
+                    section(
+                        &mut out,
+                        &format!("{} ({})", diff.path.display(), diff.tool),
+                        &diff.diff,
+                    );
+                }
+                section(
+                    &mut out,
+                    "Response",
+                    turn.response.as_deref().unwrap_or("(no response recorded)"),
+                );
+                if let Some(error) = &turn.error {
+                    section(&mut out, "Error", error);
+                }
+                if let Some(stderr) = &turn.stderr {
+                    section(&mut out, "Stderr", stderr);
+                }
+            }
+            ReplayEvent::Journal { event } => {
+                out.push_str(&format!("📓 {}\n", describe_journal_event(event)));
+            }
+            ReplayEvent::TaskFinished {
+                title,
+                status,
+                note,
+                ..
+            } => {
+                out.push_str(&format!("■ \"{}\" {}\n", title, status));
+                if let Some(note) = note {
+                    section(&mut out, "Reason", note);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// When `task` reached a final status
+fn finished_at(task: &Task) -> Option<DateTime<Utc>> {
+    match task.status {
+        TaskStatus::Completed { completed_at, .. } => Some(completed_at),
+        TaskStatus::Failed { failed_at, .. } => Some(failed_at),
+        TaskStatus::Skipped { skipped_at, .. } => Some(skipped_at),
+        _ => None,
+    }
+}
+
+/// Diffs of the file edits among a provider call's tool uses
+pub fn tool_diffs(tool_uses: &[Value]) -> Vec<ToolDiff> {
+    let mut diffs = Vec::new();
+    for tool_use in tool_uses {
+        let Some(tool) = tool_use.get("tool_name").and_then(Value::as_str) else {
+            continue;
+        };
+        let input = tool_use.get("input").unwrap_or(&Value::Null);
+        let text = |key: &str| input.get(key).and_then(Value::as_str).unwrap_or("");
+        let diff = match tool {
+            "Write" => lines("+", text("content")),
+            "Edit" => replacement(text("old_string"), text("new_string")),
+            "MultiEdit" => input
+                .get("edits")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|edit| {
+                    let text = |key: &str| edit.get(key).and_then(Value::as_str).unwrap_or("");
+                    replacement(text("old_string"), text("new_string"))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            "NotebookEdit" => lines("+", text("new_source")),
+            _ => continue,
+        };
+        let path = input
+            .get("file_path")
+            .or_else(|| input.get("notebook_path"))
+            .and_then(Value::as_str)
+            .unwrap_or("(unknown file)");
+        diffs.push(ToolDiff {
+            tool: tool.to_string(),
+            path: PathBuf::from(path),
+            diff,
+        });
+    }
+    diffs
+}
+
+fn replacement(old: &str, new: &str) -> String {
+    [lines("-", old), lines("+", new)]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn lines(prefix: &str, text: &str) -> String {
+    text.lines()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Titled, indented block
+fn section(out: &mut String, title: &str, body: &str) {
+    out.push_str(title);
+    out.push_str(":\n");
+    for line in body.trim_end().lines() {
+        out.push_str("    ");
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push('\n');
+}
+
+/// One-line summary of a journal event
+fn describe_journal_event(event: &JournalEvent) -> String {
+    match event {
+        JournalEvent::ApprovalRequested {
+            title,
+            requested_by,
+            ..
+        } => format!("{} submitted \"{}\" for approval", requested_by, title),
+        JournalEvent::ApprovalGranted {
+            title,
+            approved_by,
+            role,
+            ..
+        } => format!("{} ({}) approved \"{}\"", approved_by, role, title),
+        JournalEvent::ApprovalRejected {
+            title,
+            rejected_by,
+            role,
+            reason,
+            ..
+        } => format!(
+            "{} ({}) rejected \"{}\"{}",
+            rejected_by,
+            role,
+            title,
+            reason
+                .as_deref()
+                .map(|reason| format!(": {}", reason))
+                .unwrap_or_default()
+        ),
+        JournalEvent::ApprovalCancelled {
+            title,
+            cancelled_by,
+            reason,
+            ..
+        } => format!(
+            "{} withdrew \"{}\"{}",
+            cancelled_by,
+            title,
+            reason
+                .as_deref()
+                .map(|reason| format!(": {}", reason))
+                .unwrap_or_default()
+        ),
+        JournalEvent::QuestionAsked {
+            task_title,
+            question,
+            ..
+        } => format!("The model asked, during \"{}\": {}", task_title, question),
+        JournalEvent::QuestionAnswered {
+            answer,
+            answered_by,
+            ..
+        } => format!("{} answered: {}", answered_by, answer),
+        JournalEvent::PriorityOverrideChanged {
+            task_key, after, ..
+        } => match after {
+            Some(after) => format!(
+                "Priority of \"{}\" overridden{}{}",
+                task_key,
+                after
+                    .priority
+                    .as_ref()
+                    .map(|priority| format!(" to {:?}", priority))
+                    .unwrap_or_default(),
+                if after.hold { ", on hold" } else { "" }
+            ),
+            None => format!("Priority override of \"{}\" removed", task_key),
+        },
+        JournalEvent::StorageQuotaEnforced {
+            category,
+            removed,
+            bytes_freed,
+            ..
+        } => format!(
+            "Storage quota removed {} {} files ({} bytes)",
+            removed.len(),
+            category,
+            bytes_freed
+        ),
+        JournalEvent::PolicyViolated {
+            stage,
+            rule,
+            effect,
+            task_title,
+            message,
+            ..
+        } => format!(
+            "Policy '{}' {} '{}' at {}: {}",
+            rule,
+            match effect {
+                PolicyEffect::Deny => "denied",
+                PolicyEffect::RequireApproval => "held for approval",
+            },
+            task_title,
+            stage.as_str(),
+            message
+        ),
+        JournalEvent::TaskRolledBack {
+            task_title, files, ..
+        } => format!(
+            "\"{}\" was rolled back, restoring {} files",
+            task_title,
+            files.len()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::interactions::InteractionRecord;
+    use crate::session::metadata::SessionMetadata;
+    use crate::session::persistence::{ConversationState, ExecutionContext, FileSystemState};
+    use crate::task::tree::TaskTree;
+    use crate::task::types::{TaskPriority, TaskResult, TaskSpec};
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    fn interaction(task_id: TaskId, started_at: DateTime<Utc>) -> InteractionRecord {
+        InteractionRecord {
+            request_id: Uuid::new_v4(),
+            task_id,
+            provider: "claude".to_string(),
+            model: "sonnet".to_string(),
+            started_at,
+            duration_ms: 1500,
+            input_tokens: 100,
+            output_tokens: 50,
+            estimated_cost: 0.01,
+            exit_code: Some(0),
+            success: true,
+            error: None,
+            log_files: Vec::new(),
+            payload: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_steps_follow_the_session_timeline() {
+        let start = Utc::now() - Duration::hours(1);
+        let mut tree = TaskTree::new();
+        let mut add = |title: &str, priority: TaskPriority| {
+            let mut spec = TaskSpec {
+                title: title.to_string(),
+                ..Default::default()
+            };
+            spec.metadata.priority = priority;
+            let mut task = Task::new(spec, None);
+            task.created_at = start;
+            tree.add_task(task).unwrap()
+        };
+        let urgent = add("Fix login", TaskPriority::Critical);
+        let docs = add("Update docs", TaskPriority::Low);
+        tree.update_task_status(
+            urgent,
+            TaskStatus::Completed {
+                completed_at: start + Duration::minutes(5),
+                result: TaskResult::Success {
+                    output: Value::Null,
+                    files_created: Vec::new(),
+                    files_modified: Vec::new(),
+                    build_artifacts: Vec::new(),
+                },
+            },
+        )
+        .unwrap();
+
+        let mut metadata = SessionMetadata::new("Run".to_string(), PathBuf::from("/ws"));
+        metadata.created_at = start;
+        metadata.last_updated = start + Duration::minutes(10);
+        let state = SessionState {
+            metadata,
+            task_tree: tree,
+            execution_context: ExecutionContext::default(),
+            file_system_state: FileSystemState::default(),
+            interactions: vec![
+                interaction(docs, start + Duration::minutes(6)),
+                interaction(urgent, start + Duration::minutes(1)),
+            ],
+            conversation: ConversationState::default(),
+        };
+        let journal = vec![JournalEntry {
+            timestamp: start + Duration::minutes(2),
+            event: JournalEvent::QuestionAnswered {
+                question_id: Uuid::new_v4(),
+                task_id: urgent,
+                question: "Keep the old endpoint?".to_string(),
+                answer: "No".to_string(),
+                answered_by: "alice".to_string(),
+            },
+        }];
+
+        let replay = SessionReplay::build(&state, &journal);
+        let kinds: Vec<&str> = replay
+            .steps
+            .iter()
+            .map(|step| match &step.event {
+                ReplayEvent::TaskPicked { .. } => "picked",
+                ReplayEvent::ProviderCall { .. } => "call",
+                ReplayEvent::Journal { .. } => "journal",
+                ReplayEvent::TaskFinished { .. } => "finished",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            ["picked", "call", "journal", "finished", "picked", "call"]
+        );
+
+        let ReplayEvent::TaskPicked { title, waiting, .. } = &replay.steps[0].event else {
+            unreachable!();
+        };
+        assert_eq!(title, "Fix login");
+        assert_eq!(waiting, &["Update docs".to_string()]);
+        let ReplayEvent::TaskPicked { waiting, .. } = &replay.steps[4].event else {
+            unreachable!();
+        };
+        assert!(waiting.is_empty());
+
+        let text = replay.render_step(0);
+        assert!(text.starts_with("── Step 1 of 6 · "));
+        assert!(text.contains("Still waiting: Update docs"));
+        assert!(replay.render_step(2).contains("alice answered: No"));
+    }
+
+    #[test]
+    fn test_tool_diffs_from_edit_tools() {
+        let tool_uses: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"tool_name": "Read", "input": {"file_path": "src/lib.rs"}},
+                {"tool_name": "Edit", "input": {"file_path": "src/lib.rs",
+                    "old_string": "fn a() {}", "new_string": "fn a() {}\nfn b() {}"}},
+                {"tool_name": "Write", "input": {"file_path": "NOTES.md", "content": "one\ntwo"}}
+            ]"#,
+        )
+        .unwrap();
+
+        let diffs = tool_diffs(&tool_uses);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].path, PathBuf::from("src/lib.rs"));
+        assert_eq!(diffs[0].diff, "-fn a() {}\n+fn a() {}\n+fn b() {}");
+        assert_eq!(diffs[1].tool, "Write");
+        assert_eq!(diffs[1].diff, "+one\n+two");
+    }
+}
@@ -0,0 +1,314 @@
+//! Ed25519 signatures over run reports and ledgers.
+//!
+//! A signed file gets a detached `<file>.sig` holding the SHA-256 hash of
+//! the content that was signed, when it was signed, the signer's public key
+//! and the signature. Append-only ledgers such as the cost ledger and event
+//! journal are signed up to their current length, so lines appended later
+//! leave the signed prefix verifiable.
+//!
+//! The signing key is a PKCS#8 file in the workspace, created on first use
+//! with its public key written beside it as hex. Verifiers should pin that
+//! public key: anyone can produce a valid signature with a key of their own.
+
+use crate::session::integrity::{content_hash, from_hex, to_hex};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::{ED25519, Ed25519KeyPair, KeyPair, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Extension of detached signature files
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// Report signing settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportSigningConfig {
+    /// Sign the run report, cost ledger and event journal when a run ends
+    pub enabled: bool,
+    /// PKCS#8 signing key; `.aca/keys/report-signing.pk8` when unset
+    pub key_path: Option<PathBuf>,
+}
+
+impl ReportSigningConfig {
+    pub fn key_path(&self, workspace_root: &Path) -> PathBuf {
+        self.key_path
+            .clone()
+            .unwrap_or_else(|| crate::env::report_signing_key_path(workspace_root))
+    }
+}
+
+/// Detached signature stored in `<file>.sig`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportSignature {
+    /// `sha256:<hex>` of the signed bytes
+    pub content_hash: String,
+    /// Length of the signed prefix of the file
+    pub signed_bytes: u64,
+    pub signed_at: DateTime<Utc>,
+    /// Hex Ed25519 public key of the signer
+    pub public_key: String,
+    /// Hex Ed25519 signature of [`message`](Self::message)
+    pub signature: String,
+}
+
+impl ReportSignature {
+    /// The bytes the signature covers
+    fn message(&self) -> String {
+        format!(
+            "{}\n{}\n{}",
+            self.content_hash,
+            self.signed_bytes,
+            self.signed_at.to_rfc3339()
+        )
+    }
+}
+
+/// Signature file for `path`
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(SIGNATURE_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Signs files with a workspace Ed25519 key
+pub struct ReportSigner {
+    key_pair: Ed25519KeyPair,
+}
+
+impl fmt::Debug for ReportSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ReportSigner({})", self.public_key())
+    }
+}
+
+impl ReportSigner {
+    /// Load the key at `key_path`, generating it and its `.pub` file if absent
+    pub fn load_or_create(key_path: &Path) -> Result<Self> {
+        if !key_path.exists() {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| anyhow!("Failed to generate a report signing key"))?;
+            if let Some(parent) = key_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create key directory {:?}", parent))?;
+            }
+            std::fs::write(key_path, pkcs8.as_ref())
+                .with_context(|| format!("Failed to write signing key {:?}", key_path))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(key_path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+
+        let pkcs8 = std::fs::read(key_path)
+            .with_context(|| format!("Failed to read signing key {:?}", key_path))?;
+        let key_pair = Ed25519KeyPair::from_pkcs8(&pkcs8)
+            .map_err(|e| anyhow!("Invalid signing key {:?}: {}", key_path, e))?;
+        let signer = Self { key_pair };
+
+        let public_key_path = key_path.with_extension("pub");
+        if !public_key_path.exists() {
+            std::fs::write(&public_key_path, format!("{}\n", signer.public_key()))
+                .with_context(|| format!("Failed to write public key {:?}", public_key_path))?;
+        }
+        Ok(signer)
+    }
+
+    /// Hex public key
+    pub fn public_key(&self) -> String {
+        to_hex(self.key_pair.public_key().as_ref())
+    }
+
+    /// Sign `path` as it is now, writing `<path>.sig`
+    pub fn sign_file(&self, path: &Path) -> Result<ReportSignature> {
+        let contents = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let mut signature = ReportSignature {
+            content_hash: content_hash(&contents),
+            signed_bytes: contents.len() as u64,
+            signed_at: Utc::now(),
+            public_key: self.public_key(),
+            signature: String::new(),
+        };
+        signature.signature = to_hex(self.key_pair.sign(signature.message().as_bytes()).as_ref());
+
+        let sig_path = signature_path(path);
+        std::fs::write(&sig_path, serde_json::to_string_pretty(&signature)?)
+            .with_context(|| format!("Failed to write signature {:?}", sig_path))?;
+        Ok(signature)
+    }
+}
+
+/// Outcome of checking a file against its signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureCheck {
+    /// The signed content is intact; `unsigned_bytes` were appended since
+    Verified {
+        public_key: String,
+        signed_at: DateTime<Utc>,
+        unsigned_bytes: u64,
+    },
+    /// The signed content changed after signing
+    Altered { expected: String, actual: String },
+    /// The signature does not match its content hash and key
+    BadSignature,
+    /// Validly signed, but not by the trusted key
+    UntrustedKey { public_key: String },
+}
+
+impl SignatureCheck {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Verified { .. })
+    }
+}
+
+impl fmt::Display for SignatureCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verified {
+                public_key,
+                signed_at,
+                unsigned_bytes,
+            } => {
+                write!(f, "verified: signed {} by key {}", signed_at, public_key)?;
+                if *unsigned_bytes > 0 {
+                    write!(
+                        f,
+                        " ({} bytes appended since are not covered)",
+                        unsigned_bytes
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Altered { expected, actual } => write!(
+                f,
+                "altered after signing (signed {}, now {})",
+                expected, actual
+            ),
+            Self::BadSignature => f.write_str("invalid signature"),
+            Self::UntrustedKey { public_key } => {
+                write!(f, "signed by untrusted key {}", public_key)
+            }
+        }
+    }
+}
+
+/// Check `path` against `<path>.sig`, requiring `trusted_key` (hex) if given
+pub fn verify_file(path: &Path, trusted_key: Option<&str>) -> Result<SignatureCheck> {
+    let sig_path = signature_path(path);
+    let signature: ReportSignature = serde_json::from_str(
+        &std::fs::read_to_string(&sig_path)
+            .with_context(|| format!("No signature found at {:?}", sig_path))?,
+    )
+    .with_context(|| format!("Malformed signature {:?}", sig_path))?;
+    let contents = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+
+    let signed = contents
+        .get(..signature.signed_bytes as usize)
+        .unwrap_or(&contents);
+    let actual = content_hash(signed);
+    if actual != signature.content_hash {
+        return Ok(SignatureCheck::Altered {
+            expected: signature.content_hash,
+            actual,
+        });
+    }
+
+    let valid = match (
+        from_hex(&signature.public_key),
+        from_hex(&signature.signature),
+    ) {
+        (Some(public_key), Some(sig)) => UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(signature.message().as_bytes(), &sig)
+            .is_ok(),
+        _ => false,
+    };
+    if !valid {
+        return Ok(SignatureCheck::BadSignature);
+    }
+    if let Some(trusted) = trusted_key
+        && !trusted.trim().eq_ignore_ascii_case(&signature.public_key)
+    {
+        return Ok(SignatureCheck::UntrustedKey {
+            public_key: signature.public_key,
+        });
+    }
+    Ok(SignatureCheck::Verified {
+        public_key: signature.public_key,
+        signed_at: signature.signed_at,
+        unsigned_bytes: contents.len() as u64 - signed.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sign_and_verify_report() {
+        let workspace = TempDir::new().unwrap();
+        let key_path = crate::env::report_signing_key_path(workspace.path());
+        let signer = ReportSigner::load_or_create(&key_path).unwrap();
+        let pinned = std::fs::read_to_string(key_path.with_extension("pub")).unwrap();
+        assert_eq!(pinned.trim(), signer.public_key());
+
+        // The same key is loaded again rather than replaced
+        let reloaded = ReportSigner::load_or_create(&key_path).unwrap();
+        assert_eq!(reloaded.public_key(), signer.public_key());
+
+        let report = workspace.path().join("run-report.json");
+        std::fs::write(&report, r#"{"estimated_cost": 1.25}"#).unwrap();
+        signer.sign_file(&report).unwrap();
+        assert!(verify_file(&report, Some(&pinned)).unwrap().is_verified());
+
+        std::fs::write(&report, r#"{"estimated_cost": 0.25}"#).unwrap();
+        assert!(matches!(
+            verify_file(&report, Some(&pinned)).unwrap(),
+            SignatureCheck::Altered { .. }
+        ));
+    }
+
+    #[test]
+    fn test_ledger_appends_and_forged_signatures() {
+        let workspace = TempDir::new().unwrap();
+        let signer = ReportSigner::load_or_create(&workspace.path().join("keys/a.pk8")).unwrap();
+        let other = ReportSigner::load_or_create(&workspace.path().join("keys/b.pk8")).unwrap();
+
+        let ledger = workspace.path().join("cost_ledger.jsonl");
+        std::fs::write(&ledger, "{\"cost\":1.0}\n").unwrap();
+        signer.sign_file(&ledger).unwrap();
+        std::fs::write(&ledger, "{\"cost\":1.0}\n{\"cost\":2.0}\n").unwrap();
+        let check = verify_file(&ledger, Some(&signer.public_key())).unwrap();
+        assert!(matches!(
+            check,
+            SignatureCheck::Verified {
+                unsigned_bytes: 13,
+                ..
+            }
+        ));
+
+        // Re-signed with another key: valid on its own, but not trusted
+        other.sign_file(&ledger).unwrap();
+        assert_eq!(
+            verify_file(&ledger, Some(&signer.public_key())).unwrap(),
+            SignatureCheck::UntrustedKey {
+                public_key: other.public_key()
+            }
+        );
+
+        // A tampered signature field no longer matches
+        let sig_path = signature_path(&ledger);
+        let mut signature: ReportSignature =
+            serde_json::from_str(&std::fs::read_to_string(&sig_path).unwrap()).unwrap();
+        signature.signed_at = Utc::now() + chrono::Duration::days(1);
+        std::fs::write(&sig_path, serde_json::to_string(&signature).unwrap()).unwrap();
+        assert_eq!(
+            verify_file(&ledger, None).unwrap(),
+            SignatureCheck::BadSignature
+        );
+    }
+}
@@ -0,0 +1,461 @@
+//! Summaries of a finished run, rendered for pull requests.
+//!
+//! [`RunReport`] is built from a saved session: each task with its status,
+//! the files its tool uses edited (from the interaction logs) or its result
+//! lists, failures, the token usage and cost of its provider calls, and how
+//! many bytes went to and came back from each provider.
+//! [`RunReport::render_pr_description`] turns it into a markdown PR body.
+
+use crate::llm::provider_logger::TOOLS_EXTENSION;
+use crate::session::interactions::{InteractionRecord, PayloadBytes, largest_prompts};
+use crate::session::persistence::{SessionState, load_workspace_sessions};
+use crate::session::transcript::interaction_log;
+use crate::task::rollup::RollupSummary;
+use crate::task::types::{Task, TaskId, TaskResult, TaskStatus};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Tools whose `file_path` input is a file they change
+const EDIT_TOOLS: &[&str] = &["Write", "Edit", "MultiEdit", "NotebookEdit"];
+
+/// Files listed under "Notable changes" before the rest are summarized
+const MAX_LISTED_FILES: usize = 15;
+
+/// Prompts listed in [`PayloadReport::largest_prompts`]
+pub const LARGEST_PROMPTS: usize = 10;
+
+/// Outcome of one task in the run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub title: String,
+    pub status: String,
+    /// Failure or skip reason
+    pub note: Option<String>,
+    /// Bytes sent and received for the task's provider calls
+    #[serde(default)]
+    pub payload: PayloadBytes,
+    /// What the subtasks did, for a parent completed by them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<RollupSummary>,
+}
+
+/// A file changed during the run and how many edits touched it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: PathBuf,
+    pub edits: u32,
+}
+
+/// What a run did, assembled from its saved session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub session_id: String,
+    pub session_name: String,
+    pub tasks: Vec<TaskOutcome>,
+    /// Most edited first
+    pub changed_files: Vec<ChangedFile>,
+    pub total_tokens: u64,
+    pub estimated_cost: f64,
+    pub provider_calls: usize,
+    #[serde(default)]
+    pub payload: PayloadReport,
+}
+
+/// Bytes exchanged with providers, to help find context bloat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PayloadReport {
+    pub total: PayloadBytes,
+    pub by_provider: BTreeMap<String, PayloadBytes>,
+    /// Largest first
+    pub largest_prompts: Vec<PromptSize>,
+}
+
+/// One prompt among the largest of the run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptSize {
+    pub task: String,
+    pub provider: String,
+    pub model: String,
+    pub started_at: DateTime<Utc>,
+    pub bytes: u64,
+}
+
+impl PayloadReport {
+    /// Totals of `interactions`, naming tasks with `title`
+    pub fn build(interactions: &[InteractionRecord], title: impl Fn(TaskId) -> String) -> Self {
+        let mut report = Self::default();
+        for interaction in interactions {
+            report.total += interaction.payload;
+            *report
+                .by_provider
+                .entry(interaction.provider.clone())
+                .or_default() += interaction.payload;
+        }
+        report.largest_prompts = largest_prompts(interactions, LARGEST_PROMPTS)
+            .into_iter()
+            .filter(|interaction| interaction.payload.sent > 0)
+            .map(|interaction| PromptSize {
+                task: title(interaction.task_id),
+                provider: interaction.provider.clone(),
+                model: interaction.model.clone(),
+                started_at: interaction.started_at,
+                bytes: interaction.payload.sent,
+            })
+            .collect();
+        report
+    }
+}
+
+/// The most recently updated session in `workspace_root`, or the one whose
+/// id starts with `session_ref`
+pub fn find_session(workspace_root: &Path, session_ref: Option<&str>) -> Result<SessionState> {
+    let mut sessions = load_workspace_sessions(workspace_root)?;
+    if let Some(session_ref) = session_ref {
+        sessions.retain(|state| state.metadata.id.to_string().starts_with(session_ref));
+    }
+    sessions
+        .into_iter()
+        .max_by_key(|state| state.metadata.last_updated)
+        .ok_or_else(|| match session_ref {
+            Some(session_ref) => anyhow::anyhow!("No session matches '{}'", session_ref),
+            None => anyhow::anyhow!("No saved sessions in {}", workspace_root.display()),
+        })
+}
+
+impl RunReport {
+    pub fn from_session(state: &SessionState) -> Self {
+        let mut tasks: Vec<&Task> = state.task_tree.tasks.values().collect();
+        tasks.sort_by_key(|task| task.created_at);
+
+        let mut edits: BTreeMap<PathBuf, u32> = BTreeMap::new();
+        for task in &tasks {
+            if let TaskStatus::Completed { result, .. } = &task.status {
+                let files = match result {
+                    TaskResult::Success {
+                        files_created,
+                        files_modified,
+                        ..
+                    } => files_created
+                        .iter()
+                        .chain(files_modified)
+                        .collect::<Vec<_>>(),
+                    TaskResult::Partial { files_modified, .. } => files_modified.iter().collect(),
+                };
+                for file in files {
+                    edits.entry(file.clone()).or_default();
+                }
+            }
+        }
+        for interaction in &state.interactions {
+            let Some(tools) = interaction_log(interaction, TOOLS_EXTENSION) else {
+                continue;
+            };
+            let tool_uses: Vec<Value> = serde_json::from_str(&tools).unwrap_or_default();
+            for tool_use in tool_uses {
+                let is_edit = tool_use["tool_name"]
+                    .as_str()
+                    .is_some_and(|name| EDIT_TOOLS.contains(&name));
+                let path = tool_use["input"]["file_path"]
+                    .as_str()
+                    .or_else(|| tool_use["input"]["notebook_path"].as_str());
+                if is_edit && let Some(path) = path {
+                    *edits
+                        .entry(relative_to(path, &state.metadata.workspace_root))
+                        .or_default() += 1;
+                }
+            }
+        }
+        let mut changed_files: Vec<ChangedFile> = edits
+            .into_iter()
+            .map(|(path, edits)| ChangedFile { path, edits })
+            .collect();
+        changed_files.sort_by(|a, b| b.edits.cmp(&a.edits).then(a.path.cmp(&b.path)));
+
+        Self {
+            session_id: state.metadata.id.to_string(),
+            session_name: state.metadata.name.clone(),
+            tasks: tasks
+                .iter()
+                .map(|task| TaskOutcome {
+                    title: task.title.clone(),
+                    status: task.status.name().to_string(),
+                    note: match &task.status {
+                        TaskStatus::Failed { error, .. } => Some(error.to_string()),
+                        TaskStatus::Skipped { reason, .. } => Some(reason.clone()),
+                        TaskStatus::Blocked { reason, .. } => Some(reason.to_string()),
+                        _ => None,
+                    },
+                    payload: state
+                        .interactions
+                        .iter()
+                        .filter(|interaction| interaction.task_id == task.id)
+                        .fold(PayloadBytes::default(), |mut total, interaction| {
+                            total += interaction.payload;
+                            total
+                        }),
+                    rollup: RollupSummary::of(task),
+                })
+                .collect(),
+            changed_files,
+            total_tokens: state.interactions.iter().map(|i| i.total_tokens()).sum(),
+            estimated_cost: state.interactions.iter().map(|i| i.estimated_cost).sum(),
+            provider_calls: state.interactions.len(),
+            payload: PayloadReport::build(&state.interactions, |task_id| {
+                state
+                    .task_tree
+                    .tasks
+                    .get(&task_id)
+                    .map(|task| task.title.clone())
+                    .unwrap_or_else(|| task_id.to_string())
+            }),
+        }
+    }
+
+    fn count(&self, status: &str) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.status == status)
+            .count()
+    }
+
+    /// Markdown pull request description
+    pub fn render_pr_description(&self) -> String {
+        let completed = self.count("completed");
+        let failed = self.count("failed");
+
+        let mut out = String::from("## Summary\n\n");
+        match self.tasks.as_slice() {
+            [] => out.push_str("No tasks were recorded for this run.\n\n"),
+            [task] => out.push_str(&format!("{}.\n\n", task.title.trim_end_matches('.'))),
+            tasks => out.push_str(&format!(
+                "Automated run of {} tasks: {} completed{}.\n\n",
+                tasks.len(),
+                completed,
+                if failed > 0 {
+                    format!(", {} failed", failed)
+                } else {
+                    String::new()
+                }
+            )),
+        }
+
+        if !self.tasks.is_empty() {
+            out.push_str("## Tasks\n\n");
+            for task in &self.tasks {
+                let checked = if task.status == "completed" { "x" } else { " " };
+                out.push_str(&format!("- [{}] {}", checked, task.title));
+                if task.status != "completed" {
+                    out.push_str(&format!(" — _{}_", task.status.replace('_', " ")));
+                } else if let Some(rollup) = &task.rollup {
+                    out.push_str(&format!(" — {}", rollup.headline()));
+                }
+                out.push('\n');
+                if let Some(narrative) = task.rollup.as_ref().and_then(|r| r.narrative.as_ref()) {
+                    out.push_str(&format!("  {}\n", narrative.replace('\n', " ")));
+                }
+            }
+            out.push('\n');
+        }
+
+        if !self.changed_files.is_empty() {
+            out.push_str("## Notable changes\n\n");
+            for file in self.changed_files.iter().take(MAX_LISTED_FILES) {
+                out.push_str(&format!("- `{}`", file.path.display()));
+                if file.edits > 1 {
+                    out.push_str(&format!(" ({} edits)", file.edits));
+                }
+                out.push('\n');
+            }
+            let more = self.changed_files.len().saturating_sub(MAX_LISTED_FILES);
+            if more > 0 {
+                out.push_str(&format!("- …and {} more file(s)\n", more));
+            }
+            out.push('\n');
+        }
+
+        out.push_str("## Test results\n\n");
+        let problems: Vec<&TaskOutcome> = self
+            .tasks
+            .iter()
+            .filter(|task| task.status == "failed" && task.note.is_some())
+            .collect();
+        if problems.is_empty() {
+            out.push_str("No failures were recorded in this run.\n\n");
+        } else {
+            for task in problems {
+                let note = task.note.as_deref().unwrap_or_default();
+                out.push_str(&format!(
+                    "- **{}** failed: {}\n",
+                    task.title,
+                    first_line(note)
+                ));
+            }
+            out.push('\n');
+        }
+
+        out.push_str(&format!(
+            "---\n<sub>Generated by aca from session `{}`: {} provider call(s), {} tokens, ${:.2} estimated cost.</sub>\n",
+            &self.session_id[..8.min(self.session_id.len())],
+            self.provider_calls,
+            self.total_tokens,
+            self.estimated_cost
+        ));
+        out
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+/// `path` relative to `root` when it lies inside it
+fn relative_to(path: &str, root: &Path) -> PathBuf {
+    let path = Path::new(path);
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> RunReport {
+        RunReport {
+            session_id: "0123456789abcdef".to_string(),
+            session_name: "Batch run".to_string(),
+            tasks: vec![
+                TaskOutcome {
+                    title: "Add login endpoint".to_string(),
+                    status: "completed".to_string(),
+                    note: None,
+                    payload: PayloadBytes::default(),
+                    rollup: None,
+                },
+                TaskOutcome {
+                    title: "Write integration tests".to_string(),
+                    status: "failed".to_string(),
+                    note: Some("Build failed with exit code 101\nmore".to_string()),
+                    payload: PayloadBytes::default(),
+                    rollup: None,
+                },
+            ],
+            changed_files: vec![
+                ChangedFile {
+                    path: PathBuf::from("src/auth.rs"),
+                    edits: 3,
+                },
+                ChangedFile {
+                    path: PathBuf::from("src/main.rs"),
+                    edits: 1,
+                },
+            ],
+            total_tokens: 12_000,
+            estimated_cost: 0.4213,
+            provider_calls: 3,
+            payload: PayloadReport::default(),
+        }
+    }
+
+    #[test]
+    fn test_pr_description_sections() {
+        let body = report().render_pr_description();
+        assert!(body.starts_with("## Summary\n\nAutomated run of 2 tasks: 1 completed, 1 failed."));
+        assert!(body.contains("- [x] Add login endpoint\n"));
+        assert!(body.contains("- [ ] Write integration tests — _failed_\n"));
+        assert!(body.contains("- `src/auth.rs` (3 edits)\n- `src/main.rs`\n"));
+        assert!(
+            body.contains(
+                "- **Write integration tests** failed: Build failed with exit code 101\n"
+            )
+        );
+        assert!(body.contains("session `01234567`: 3 provider call(s), 12000 tokens, $0.42"));
+    }
+
+    #[test]
+    fn test_single_task_summary_and_file_cap() {
+        let mut report = report();
+        report.tasks.truncate(1);
+        report.changed_files = (0..20)
+            .map(|i| ChangedFile {
+                path: PathBuf::from(format!("src/file{}.rs", i)),
+                edits: 1,
+            })
+            .collect();
+
+        let body = report.render_pr_description();
+        assert!(body.starts_with("## Summary\n\nAdd login endpoint.\n"));
+        assert!(body.contains("- …and 5 more file(s)\n"));
+        assert!(body.contains("No failures were recorded in this run."));
+        assert_eq!(
+            relative_to("/work/src/lib.rs", Path::new("/work")),
+            PathBuf::from("src/lib.rs")
+        );
+    }
+
+    #[test]
+    fn test_rollup_in_task_list() {
+        let mut report = report();
+        report.tasks[0].rollup = Some(RollupSummary {
+            children: vec![crate::task::ChildOutcome {
+                id: uuid::Uuid::new_v4(),
+                title: "Add route".to_string(),
+                status: "completed".to_string(),
+                note: None,
+                estimated_cost: 0.1,
+                duration_ms: 1000,
+            }],
+            narrative: Some("Added the route\nand its handler.".to_string()),
+            ..Default::default()
+        });
+
+        let body = report.render_pr_description();
+        assert!(body.contains(
+            "- [x] Add login endpoint — All 1 subtasks completed\n  Added the route and its handler.\n"
+        ));
+    }
+
+    #[test]
+    fn test_payload_totals_and_largest_prompts() {
+        let (small, large) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        let call = |task_id, provider: &str, sent, received| InteractionRecord {
+            request_id: task_id,
+            task_id,
+            provider: provider.to_string(),
+            model: "sonnet".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 100,
+            input_tokens: 0,
+            output_tokens: 0,
+            estimated_cost: 0.0,
+            exit_code: Some(0),
+            success: true,
+            error: None,
+            log_files: Vec::new(),
+            payload: PayloadBytes { sent, received },
+        };
+        let mut interactions = vec![
+            call(small, "claude", 2_000, 500),
+            call(large, "claude", 300_000, 4_000),
+            call(small, "openai-api", 1_000, 100),
+        ];
+        interactions.extend((0..12).map(|i| call(small, "claude", 10 + i, 0)));
+
+        let report = PayloadReport::build(&interactions, |task_id| {
+            if task_id == large {
+                "Refactor"
+            } else {
+                "Rename"
+            }
+            .to_string()
+        });
+        assert_eq!(report.total.received, 4_600);
+        assert_eq!(report.by_provider["openai-api"].sent, 1_000);
+        assert_eq!(report.largest_prompts.len(), LARGEST_PROMPTS);
+        assert_eq!(report.largest_prompts[0].task, "Refactor");
+        assert_eq!(report.largest_prompts[0].bytes, 300_000);
+        assert_eq!(report.largest_prompts[2].provider, "openai-api");
+    }
+}
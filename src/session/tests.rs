@@ -28,6 +28,8 @@ fn create_test_session_state() -> SessionState {
         task_tree,
         execution_context: ExecutionContext::default(),
         file_system_state: FileSystemState::default(),
+        interactions: Vec::new(),
+        conversation: ConversationState::default(),
     }
 }
 
@@ -61,6 +63,8 @@ async fn test_session_metadata_checkpoint_management() {
         trigger_reason: CheckpointTrigger::Manual {
             reason: "Testing".to_string(),
         },
+        content_hash: None,
+        signature: None,
     };
 
     metadata.add_checkpoint(checkpoint_info.clone());
@@ -263,6 +267,81 @@ async fn test_recovery_from_checkpoint() {
     assert!(recovery_result.recovered_state.is_some());
 }
 
+#[tokio::test]
+async fn test_corrupted_checkpoint_falls_back_to_previous() {
+    let temp_dir = create_test_session_dir();
+    let session_dir = temp_dir.path().to_path_buf();
+
+    let persistence_config = PersistenceConfig {
+        signing_key: Some(SigningKey::new(b"test-secret")),
+        ..PersistenceConfig::default()
+    };
+    let persistence = PersistenceManager::new(
+        session_dir.clone(),
+        "test-session",
+        persistence_config.clone(),
+    )
+    .unwrap();
+    let recovery = RecoveryManager::new(
+        PersistenceManager::new(session_dir.clone(), "test-session", persistence_config).unwrap(),
+        RecoveryConfig::default(),
+    );
+
+    let test_state = create_test_session_state();
+    let mut checkpoints = Vec::new();
+    for description in ["First checkpoint", "Second checkpoint"] {
+        let checkpoint = persistence
+            .create_checkpoint(
+                &test_state,
+                description.to_string(),
+                CheckpointTrigger::Manual {
+                    reason: "Testing integrity".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert!(checkpoint.content_hash.is_some());
+        assert!(checkpoint.signature.is_some());
+        checkpoints.push(checkpoint);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    // Corrupt the latest checkpoint on disk
+    let latest = env::checkpoint_file_path(&session_dir, "test-session", &checkpoints[1].id);
+    let mut content = std::fs::read_to_string(&latest).unwrap();
+    content = content.replace("Test Session", "Tampered Session");
+    std::fs::write(&latest, content).unwrap();
+
+    assert!(matches!(
+        persistence
+            .verify_checkpoint(&checkpoints[1].id)
+            .await
+            .unwrap(),
+        CheckpointIntegrity::Corrupted { .. }
+    ));
+    assert!(
+        persistence
+            .restore_from_checkpoint(&checkpoints[1].id)
+            .await
+            .is_err()
+    );
+
+    let recovery_result = recovery
+        .recover_from_checkpoint(&checkpoints[1].id)
+        .await
+        .unwrap();
+    assert!(recovery_result.success);
+    assert!(matches!(
+        recovery_result.recovery_info.recovery_type,
+        RecoveryType::CorruptionRecovery
+    ));
+    assert_eq!(
+        recovery_result.recovered_state.unwrap().metadata.name,
+        "Test Session"
+    );
+    assert!(!recovery_result.warnings.is_empty());
+}
+
 #[tokio::test]
 async fn test_session_manager_creation() {
     let temp_dir = create_test_session_dir();
@@ -360,6 +439,167 @@ async fn test_session_manager_save_and_validate() {
     assert!(validation_result.is_valid);
 }
 
+#[tokio::test]
+async fn test_task_transcript_from_saved_session() {
+    let temp_dir = create_test_session_dir();
+    let workspace = temp_dir.path().to_path_buf();
+    let init_options = SessionInitOptions {
+        name: "Transcript Test Session".to_string(),
+        workspace_root: workspace.clone(),
+        enable_auto_save: false,
+        ..Default::default()
+    };
+    let session_manager = SessionManager::new(
+        workspace.clone(),
+        SessionManagerConfig::default(),
+        init_options,
+    )
+    .await
+    .unwrap();
+
+    let task_id = session_manager
+        .task_manager()
+        .create_task(
+            crate::task::TaskSpec {
+                title: "Add login endpoint".to_string(),
+                description: "Add POST /login".to_string(),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let logs = workspace.join("logs");
+    std::fs::create_dir_all(&logs).unwrap();
+    let prompt = logs.join("claude-1.prompt.md");
+    let stdout = logs.join("claude-1.stdout.json");
+    std::fs::write(&prompt, "Add POST /login").unwrap();
+    std::fs::write(
+        &stdout,
+        r#"{"type":"result","result":"Added the endpoint."}"#,
+    )
+    .unwrap();
+    session_manager
+        .record_interaction(InteractionRecord {
+            request_id: task_id,
+            task_id,
+            provider: "claude".to_string(),
+            model: "sonnet".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 1500,
+            input_tokens: 10,
+            output_tokens: 5,
+            estimated_cost: 0.0,
+            exit_code: Some(0),
+            success: true,
+            error: None,
+            log_files: vec![prompt, stdout],
+            payload: Default::default(),
+        })
+        .await;
+    session_manager.save_session().await.unwrap();
+
+    let task_ref = task_id.to_string()[..8].to_string();
+    let (state, found) = transcript::find_task(&workspace, &task_ref).unwrap();
+    assert_eq!(found, task_id);
+    assert!(transcript::find_task(&workspace, "not-a-task").is_err());
+
+    let transcript = TaskTranscript::build(&state, task_id, &[]).unwrap();
+    assert_eq!(transcript.turns.len(), 1);
+    assert_eq!(
+        transcript.turns[0].response.as_deref(),
+        Some("Added the endpoint.")
+    );
+
+    let markdown = transcript.render_markdown();
+    assert!(markdown.starts_with("# Add login endpoint\n"));
+    assert!(markdown.contains("### Prompt\n\n```\nAdd POST /login\n```"));
+    assert!(markdown.contains("Added the endpoint."));
+}
+
+#[tokio::test]
+async fn test_session_manager_persists_interactions() {
+    let temp_dir = create_test_session_dir();
+    let session_dir = temp_dir.path().to_path_buf();
+
+    let init_options = SessionInitOptions {
+        name: "Interaction Test Session".to_string(),
+        workspace_root: temp_dir.path().to_path_buf(),
+        enable_auto_save: false,
+        ..Default::default()
+    };
+    let session_manager =
+        SessionManager::new(session_dir, SessionManagerConfig::default(), init_options)
+            .await
+            .unwrap();
+
+    let task_id = uuid::Uuid::new_v4();
+    session_manager
+        .record_interaction(InteractionRecord {
+            request_id: task_id,
+            task_id,
+            provider: "claude".to_string(),
+            model: "sonnet".to_string(),
+            started_at: Utc::now() - Duration::days(30),
+            duration_ms: 4200,
+            input_tokens: 900,
+            output_tokens: 150,
+            estimated_cost: 0.005,
+            exit_code: Some(1),
+            success: false,
+            error: Some("Claude command failed with exit code 1".to_string()),
+            log_files: vec![PathBuf::from("claude_interactions/claude-1.log")],
+            payload: Default::default(),
+        })
+        .await;
+    let checkpoint = session_manager
+        .create_checkpoint("With interactions".to_string())
+        .await
+        .unwrap();
+
+    // Retention drops the record, restoring the checkpoint brings it back
+    let expired = session_manager
+        .prune_interactions_before(Utc::now() - Duration::days(7))
+        .await;
+    assert_eq!(expired.len(), 1);
+    assert!(session_manager.interactions().await.is_empty());
+
+    session_manager
+        .restore_from_checkpoint(&checkpoint.id)
+        .await
+        .unwrap();
+    let restored = session_manager.interactions_for_task(task_id).await;
+    assert_eq!(restored, expired);
+    assert_eq!(restored[0].exit_code, Some(1));
+
+    // Log retention rewrites the paths of compressed logs
+    session_manager
+        .apply_log_retention(&RetentionReport {
+            compressed: vec![(
+                PathBuf::from("claude_interactions/claude-1.log"),
+                PathBuf::from("claude_interactions/claude-1.log.gz"),
+            )],
+            removed: Vec::new(),
+            bytes_freed: 10,
+        })
+        .await;
+    assert_eq!(
+        session_manager.interactions().await[0].log_files,
+        vec![PathBuf::from("claude_interactions/claude-1.log.gz")]
+    );
+}
+
+#[test]
+fn test_session_state_without_interactions_loads() {
+    let mut json = serde_json::to_value(create_test_session_state()).unwrap();
+    json.as_object_mut().unwrap().remove("interactions");
+
+    let state: SessionState = serde_json::from_value(json).unwrap();
+    assert!(state.interactions.is_empty());
+    assert!(state.interactions_for_task(uuid::Uuid::new_v4()).is_empty());
+}
+
 #[tokio::test]
 async fn test_session_manager_auto_save_control() {
     let temp_dir = create_test_session_dir();
@@ -397,6 +637,99 @@ async fn test_session_manager_auto_save_control() {
     assert!(status.is_auto_save_active);
 }
 
+#[tokio::test]
+async fn test_cloned_handles_share_session_status() {
+    let temp_dir = create_test_session_dir();
+    let init_options = SessionInitOptions {
+        name: "Shared Handle Session".to_string(),
+        workspace_root: temp_dir.path().to_path_buf(),
+        enable_auto_save: false,
+        ..Default::default()
+    };
+    let session_manager = SessionManager::new(
+        temp_dir.path().to_path_buf(),
+        SessionManagerConfig::default(),
+        init_options,
+    )
+    .await
+    .unwrap();
+    let handle = session_manager.clone();
+    let updates = session_manager.subscribe_status();
+
+    handle
+        .task_manager()
+        .create_task(
+            crate::task::TaskSpec {
+                title: "Add health endpoint".to_string(),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    // The snapshot only moves on save; the locked read sees the task at once
+    assert_eq!(session_manager.status().total_tasks, 0);
+    assert_eq!(session_manager.get_status().await.unwrap().total_tasks, 1);
+
+    handle.save_session().await.unwrap();
+    assert!(updates.has_changed().unwrap());
+    let status = session_manager.status();
+    assert_eq!(status.name, "Shared Handle Session");
+    assert_eq!(status.total_tasks, 1);
+    assert!(status.last_save.is_some());
+    assert!(status.last_checkpoint.is_none());
+
+    handle
+        .create_checkpoint("From another handle".to_string())
+        .await
+        .unwrap();
+    assert!(session_manager.status().last_checkpoint.is_some());
+
+    handle.set_auto_save_enabled(true).await.unwrap();
+    assert!(session_manager.status().is_auto_save_active);
+}
+
+#[tokio::test]
+async fn test_concurrent_saves_and_checkpoints_from_handles() {
+    let temp_dir = create_test_session_dir();
+    let init_options = SessionInitOptions {
+        name: "Concurrent Session".to_string(),
+        workspace_root: temp_dir.path().to_path_buf(),
+        enable_auto_save: false,
+        ..Default::default()
+    };
+    let session_manager = SessionManager::new(
+        temp_dir.path().to_path_buf(),
+        SessionManagerConfig::default(),
+        init_options,
+    )
+    .await
+    .unwrap();
+
+    let mut operations = Vec::new();
+    for i in 0..4 {
+        let handle = session_manager.clone();
+        operations.push(tokio::spawn(async move {
+            handle.save_session().await.unwrap();
+            handle
+                .create_checkpoint(format!("checkpoint {}", i))
+                .await
+                .unwrap();
+            handle.status()
+        }));
+    }
+    for operation in operations {
+        assert_eq!(operation.await.unwrap().name, "Concurrent Session");
+    }
+
+    let checkpoints = session_manager.list_checkpoints(false).await.unwrap();
+    for i in 0..4 {
+        let description = format!("checkpoint {}", i);
+        assert!(checkpoints.iter().any(|c| c.description == description));
+    }
+    assert!(session_manager.validate_session().await.unwrap().is_valid);
+}
+
 #[tokio::test]
 async fn test_session_statistics() {
     let temp_dir = create_test_session_dir();
@@ -0,0 +1,391 @@
+//! Readable transcripts of a task's conversation.
+//!
+//! A transcript is assembled from what a run already persists: the task in
+//! the session's task tree, its [`InteractionRecord`]s with their log files
+//! (prompt, output, tool uses, stderr), and questions the model asked
+//! mid-task from the event journal. It renders as markdown for attaching to
+//! PRs and design docs, or serializes to JSON.
+
+use crate::llm::provider_logger::{
+    PROMPT_EXTENSION, STDERR_EXTENSION, STDOUT_EXTENSION, TOOLS_EXTENSION,
+};
+use crate::session::interactions::InteractionRecord;
+use crate::session::journal::{JournalEntry, JournalEvent};
+use crate::session::log_retention::COMPRESSED_EXTENSION;
+use crate::session::persistence::{SessionState, load_workspace_sessions};
+use crate::task::types::{TaskId, TaskStatus};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// One provider call made for the task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptTurn {
+    pub request_id: Uuid,
+    pub provider: String,
+    pub model: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Prompt as sent, `None` if its log file is gone or was never written
+    pub prompt: Option<String>,
+    pub response: Option<String>,
+    pub tool_uses: Vec<Value>,
+    pub stderr: Option<String>,
+}
+
+impl TranscriptTurn {
+    /// The provider call `interaction` records, with its logs read back
+    pub fn from_interaction(interaction: &InteractionRecord) -> Self {
+        let log = |extension: &str| interaction_log(interaction, extension);
+        Self {
+            request_id: interaction.request_id,
+            provider: interaction.provider.clone(),
+            model: interaction.model.clone(),
+            started_at: interaction.started_at,
+            duration_ms: interaction.duration_ms,
+            success: interaction.success,
+            error: interaction.error.clone(),
+            prompt: log(PROMPT_EXTENSION),
+            response: log(STDOUT_EXTENSION).and_then(|stdout| response_text(&stdout)),
+            tool_uses: log(TOOLS_EXTENSION)
+                .and_then(|tools| serde_json::from_str(&tools).ok())
+                .unwrap_or_default(),
+            stderr: log(STDERR_EXTENSION).filter(|stderr| !stderr.trim().is_empty()),
+        }
+    }
+}
+
+/// A question the model asked while working on the task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptQuestion {
+    pub asked_at: DateTime<Utc>,
+    pub question: String,
+    pub answer: Option<String>,
+    pub answered_by: Option<String>,
+}
+
+/// Everything recorded about one task's conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTranscript {
+    pub session_id: String,
+    pub task_id: TaskId,
+    pub title: String,
+    pub description: String,
+    pub status: String,
+    pub turns: Vec<TranscriptTurn>,
+    pub questions: Vec<TranscriptQuestion>,
+    /// Errors from the task's execution history and final status
+    pub errors: Vec<String>,
+}
+
+/// Find the task whose id is `task_ref` or starts with it, across all
+/// sessions in `workspace_root`
+pub fn find_task(workspace_root: &Path, task_ref: &str) -> Result<(SessionState, TaskId)> {
+    let task_ref = task_ref.trim().to_lowercase();
+    let mut matches = Vec::new();
+    for state in load_workspace_sessions(workspace_root)? {
+        let ids: Vec<TaskId> = state
+            .task_tree
+            .tasks
+            .keys()
+            .filter(|id| id.to_string().starts_with(&task_ref))
+            .copied()
+            .collect();
+        matches.extend(ids.into_iter().map(|id| (state.clone(), id)));
+    }
+
+    match matches.len() {
+        0 => Err(anyhow::anyhow!("No task matches '{}'", task_ref)),
+        1 => Ok(matches.remove(0)),
+        count => Err(anyhow::anyhow!(
+            "'{}' matches {} tasks; use a longer id prefix",
+            task_ref,
+            count
+        )),
+    }
+}
+
+impl TaskTranscript {
+    /// Assemble the transcript of `task_id` from `state` and journal entries
+    pub fn build(state: &SessionState, task_id: TaskId, journal: &[JournalEntry]) -> Result<Self> {
+        let task = state.task_tree.get_task(task_id)?;
+
+        let turns = state
+            .interactions_for_task(task_id)
+            .into_iter()
+            .map(TranscriptTurn::from_interaction)
+            .collect();
+
+        let mut questions: Vec<TranscriptQuestion> = Vec::new();
+        let mut question_ids = Vec::new();
+        for entry in journal {
+            match &entry.event {
+                JournalEvent::QuestionAsked {
+                    question_id,
+                    task_id: asked_for,
+                    question,
+                    ..
+                } if *asked_for == task_id => {
+                    question_ids.push(*question_id);
+                    questions.push(TranscriptQuestion {
+                        asked_at: entry.timestamp,
+                        question: question.clone(),
+                        answer: None,
+                        answered_by: None,
+                    });
+                }
+                JournalEvent::QuestionAnswered {
+                    question_id,
+                    answer,
+                    answered_by,
+                    ..
+                } => {
+                    if let Some(idx) = question_ids.iter().position(|id| id == question_id) {
+                        questions[idx].answer = Some(answer.clone());
+                        questions[idx].answered_by = Some(answered_by.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut errors: Vec<String> = task
+            .execution_history
+            .iter()
+            .flat_map(|record| record.errors.iter().cloned())
+            .collect();
+        if let TaskStatus::Failed { error, .. } = &task.status {
+            errors.push(error.to_string());
+        }
+
+        Ok(Self {
+            session_id: state.metadata.id.to_string(),
+            task_id,
+            title: task.title.clone(),
+            description: task.description.clone(),
+            status: task.status.name().to_string(),
+            turns,
+            questions,
+            errors,
+        })
+    }
+
+    /// Markdown document with one section per provider call
+    pub fn render_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.title);
+        out.push_str(&format!(
+            "- **Task:** `{}`\n- **Session:** `{}`\n- **Status:** {}\n\n",
+            self.task_id, self.session_id, self.status
+        ));
+        out.push_str("## Task\n\n");
+        out.push_str(self.description.trim());
+        out.push_str("\n\n");
+
+        if self.turns.is_empty() {
+            out.push_str("_No provider calls were recorded for this task._\n\n");
+        }
+        for (idx, turn) in self.turns.iter().enumerate() {
+            out.push_str(&format!(
+                "## Turn {} — {} ({}), {}, {:.1}s{}\n\n",
+                idx + 1,
+                turn.provider,
+                turn.model,
+                turn.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                turn.duration_ms as f64 / 1000.0,
+                if turn.success { "" } else { ", failed" }
+            ));
+            out.push_str("### Prompt\n\n");
+            out.push_str(&fenced(
+                turn.prompt
+                    .as_deref()
+                    .unwrap_or("(prompt log not available)"),
+                "",
+            ));
+            if !turn.tool_uses.is_empty() {
+                out.push_str("### Tool uses\n\n");
+                for tool_use in &turn.tool_uses {
+                    let name = tool_use
+                        .get("tool_name")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown");
+                    let input = tool_use.get("input").cloned().unwrap_or_default();
+                    out.push_str(&format!("- **{}** `{}`\n", name, input));
+                }
+                out.push('\n');
+            }
+            out.push_str("### Response\n\n");
+            match &turn.response {
+                Some(response) => {
+                    out.push_str(response.trim());
+                    out.push_str("\n\n");
+                }
+                None => out.push_str("_No response was recorded._\n\n"),
+            }
+            if let Some(error) = &turn.error {
+                out.push_str(&format!("**Error:** {}\n\n", error));
+            }
+            if let Some(stderr) = &turn.stderr {
+                out.push_str("### Stderr\n\n");
+                out.push_str(&fenced(stderr, "text"));
+            }
+        }
+
+        if !self.questions.is_empty() {
+            out.push_str("## Questions\n\n");
+            for question in &self.questions {
+                out.push_str(&format!("**Q:** {}\n\n", question.question));
+                match (&question.answer, &question.answered_by) {
+                    (Some(answer), Some(by)) => {
+                        out.push_str(&format!("**A** ({}): {}\n\n", by, answer))
+                    }
+                    _ => out.push_str("_Unanswered._\n\n"),
+                }
+            }
+        }
+
+        if !self.errors.is_empty() {
+            out.push_str("## Errors\n\n");
+            for error in &self.errors {
+                out.push_str(&fenced(error, "text"));
+            }
+        }
+        out
+    }
+}
+
+/// Contents of the interaction's log file with `extension`, if still present
+pub(crate) fn interaction_log(interaction: &InteractionRecord, extension: &str) -> Option<String> {
+    let suffix = format!(".{}", extension);
+    let compressed = format!("{}.{}", suffix, COMPRESSED_EXTENSION);
+    interaction
+        .log_files
+        .iter()
+        .find(|path| {
+            let path = path.to_string_lossy();
+            path.ends_with(&suffix) || path.ends_with(&compressed)
+        })
+        .and_then(|path| read_log(path))
+}
+
+/// Read a log file, decompressing it if log retention gzipped it
+fn read_log(path: &Path) -> Option<String> {
+    let compressed = if path
+        .extension()
+        .is_some_and(|ext| ext == COMPRESSED_EXTENSION)
+    {
+        path.to_path_buf()
+    } else if let Ok(content) = std::fs::read_to_string(path) {
+        return Some(content);
+    } else {
+        // Compressed after the path was recorded
+        let mut compressed = path.as_os_str().to_owned();
+        compressed.push(format!(".{}", COMPRESSED_EXTENSION));
+        PathBuf::from(compressed)
+    };
+    let file = std::fs::File::open(compressed).ok()?;
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(file)
+        .read_to_string(&mut content)
+        .ok()?;
+    Some(content)
+}
+
+/// Final response text in a provider's JSON or JSONL output
+fn response_text(stdout: &str) -> Option<String> {
+    if let Ok(json) = serde_json::from_str::<Value>(stdout.trim())
+        && let Some(result) = json.get("result").and_then(Value::as_str)
+    {
+        return Some(result.to_string());
+    }
+    stdout
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+        .find_map(|event| match event.get("type").and_then(Value::as_str)? {
+            // Claude Code stream-json
+            "result" => event.get("result")?.as_str().map(str::to_string),
+            // Codex exec --json
+            "item.completed" if event["item"]["type"] == "agent_message" => {
+                event["item"]["text"].as_str().map(str::to_string)
+            }
+            _ => None,
+        })
+}
+
+/// Code block that cannot be closed early by backticks in `content`
+pub(crate) fn fenced(content: &str, language: &str) -> String {
+    let mut fence = "```".to_string();
+    while content.contains(&fence) {
+        fence.push('`');
+    }
+    format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        language,
+        content.trim_end(),
+        fence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_text_from_provider_output() {
+        assert_eq!(
+            response_text(r#"{"type":"result","result":"Done."}"#).as_deref(),
+            Some("Done.")
+        );
+        let stream = concat!(
+            r#"{"type":"assistant","message":{}}"#,
+            "\n",
+            r#"{"type":"result","subtype":"success","result":"Streamed."}"#,
+            "\n"
+        );
+        assert_eq!(response_text(stream).as_deref(), Some("Streamed."));
+        let codex = concat!(
+            r#"{"type":"item.completed","item":{"type":"agent_message","text":"From codex"}}"#,
+            "\n",
+            r#"{"type":"turn.completed","usage":{}}"#
+        );
+        assert_eq!(response_text(codex).as_deref(), Some("From codex"));
+        assert_eq!(response_text("not json"), None);
+    }
+
+    #[test]
+    fn test_fenced_outlasts_embedded_backticks() {
+        assert_eq!(fenced("a", "text"), "```text\na\n```\n\n");
+        let nested = fenced("```rust\nfn main() {}\n```", "");
+        assert!(nested.starts_with("````\n") && nested.ends_with("\n````\n\n"));
+    }
+
+    #[test]
+    fn test_read_log_falls_back_to_gzipped_copy() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("claude-1.prompt.md");
+        let mut encoder = GzEncoder::new(
+            std::fs::File::create(dir.path().join("claude-1.prompt.md.gz")).unwrap(),
+            Compression::default(),
+        );
+        encoder.write_all(b"Fix the bug").unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(read_log(&path).as_deref(), Some("Fix the bug"));
+        assert_eq!(
+            read_log(&dir.path().join("claude-1.prompt.md.gz")).as_deref(),
+            Some("Fix the bug")
+        );
+        assert_eq!(read_log(&dir.path().join("missing.log")), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Issue reproduction workflow: failing test first, then the fix.
+//!
+//! `aca fix-bug "<description>"` runs three tasks with a gate after each:
+//!
+//! 1. **Reproduce** – write a test that exposes the bug. Gate: the test
+//!    command must fail.
+//! 2. **Fix** – change the code, not the test. Gate: the test command must pass.
+//! 3. **Confirm** – check related tests for regressions. Gate: the test
+//!    command still passes.
+//!
+//! The test command comes from `--test-command` or from a
+//! `<aca-test-command>` block the reproduce task ends its response with.
+
+use super::types::{TaskMetadata, TaskPriority, TaskSpec};
+use serde::{Deserialize, Serialize};
+
+/// Opening tag of the test command block in the reproduce task's response
+pub const TEST_COMMAND_BLOCK_START: &str = "<aca-test-command>";
+
+/// Closing tag of the test command block in the reproduce task's response
+pub const TEST_COMMAND_BLOCK_END: &str = "</aca-test-command>";
+
+/// Tag shared by all tasks of the workflow
+pub const BUG_FIX_TAG: &str = "bugfix";
+
+/// Attempts per gated step before the workflow gives up
+pub const MAX_GATE_ATTEMPTS: u32 = 2;
+
+/// Test output passed back to the model as feedback (tail, in characters)
+pub const MAX_FEEDBACK_CHARS: usize = 8000;
+
+/// Step of the bug fix workflow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BugFixStage {
+    Reproduce,
+    Fix,
+    Confirm,
+}
+
+impl BugFixStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BugFixStage::Reproduce => "reproduce",
+            BugFixStage::Fix => "fix",
+            BugFixStage::Confirm => "confirm",
+        }
+    }
+
+    /// Whether the gate after this step expects the test command to pass
+    pub fn expects_passing_test(&self) -> bool {
+        !matches!(self, BugFixStage::Reproduce)
+    }
+}
+
+/// Outcome of a finished bug fix workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BugFixReport {
+    pub test_command: String,
+    pub reproduce_task: uuid::Uuid,
+    pub fix_task: uuid::Uuid,
+    pub confirm_task: uuid::Uuid,
+    /// Output of the test command while it still failed
+    pub failing_output: String,
+}
+
+/// Extract the test command block from a reproduce task's response
+pub fn parse_test_command(response: &str) -> Option<String> {
+    let start = response.rfind(TEST_COMMAND_BLOCK_START)? + TEST_COMMAND_BLOCK_START.len();
+    let end = start + response[start..].find(TEST_COMMAND_BLOCK_END)?;
+    let command = response[start..end].trim();
+    (!command.is_empty()).then(|| command.to_string())
+}
+
+/// Build the task for one step of the workflow
+///
+/// `test_command` is known from the fix step on, or up front when the user
+/// passed one; `feedback` explains why an earlier attempt failed its gate.
+pub fn bug_fix_task_spec(
+    stage: BugFixStage,
+    bug: &str,
+    test_command: Option<&str>,
+    feedback: Option<&str>,
+) -> TaskSpec {
+    let (title, instructions) = match stage {
+        BugFixStage::Reproduce => (
+            "Reproduce bug with a failing test",
+            format!(
+                "Write an automated test that reproduces the bug below. Do not fix the bug. \
+                 The test must fail now and pass once the bug is fixed.\n\n{}",
+                match test_command {
+                    Some(command) => format!("The test must run as part of `{}`.", command),
+                    None => format!(
+                        "End your response with the command that runs only this test, as \
+                         {}<command>{}.",
+                        TEST_COMMAND_BLOCK_START, TEST_COMMAND_BLOCK_END
+                    ),
+                }
+            ),
+        ),
+        BugFixStage::Fix => (
+            "Fix bug",
+            format!(
+                "Fix the bug below. A reproducing test already exists and currently fails \
+                 under `{}`. Change the code, not the test, until it passes.",
+                test_command.unwrap_or_default()
+            ),
+        ),
+        BugFixStage::Confirm => (
+            "Confirm bug fix",
+            format!(
+                "The bug below has been fixed and `{}` passes. Run the related test suites, \
+                 fix any regressions the change introduced and keep the reproducing test.",
+                test_command.unwrap_or_default()
+            ),
+        ),
+    };
+
+    let mut description = format!("{}\n\n--- Bug report ---\n{}", instructions, bug.trim());
+    if let Some(feedback) = feedback {
+        description.push_str("\n\n--- Previous attempt ---\n");
+        description.push_str(feedback);
+    }
+
+    TaskSpec {
+        title: title.to_string(),
+        description,
+        dependencies: Vec::new(),
+        metadata: TaskMetadata {
+            priority: TaskPriority::High,
+            tags: vec![
+                BUG_FIX_TAG.to_string(),
+                format!("{}:{}", BUG_FIX_TAG, stage.name()),
+            ],
+            ..Default::default()
+        },
+        plan: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_command() {
+        let response = "Added tests/login.rs.\n<aca-test-command>\ncargo test login_redirect\n</aca-test-command>";
+        assert_eq!(
+            parse_test_command(response).as_deref(),
+            Some("cargo test login_redirect")
+        );
+        assert!(parse_test_command("<aca-test-command> </aca-test-command>").is_none());
+        assert!(parse_test_command("no block").is_none());
+    }
+
+    #[test]
+    fn test_bug_fix_task_specs() {
+        let reproduce = bug_fix_task_spec(BugFixStage::Reproduce, "Login loops", None, None);
+        assert!(reproduce.description.contains(TEST_COMMAND_BLOCK_START));
+        assert!(
+            reproduce
+                .metadata
+                .tags
+                .contains(&"bugfix:reproduce".to_string())
+        );
+
+        let fix = bug_fix_task_spec(
+            BugFixStage::Fix,
+            "Login loops",
+            Some("cargo test login"),
+            Some("Test still fails"),
+        );
+        assert!(fix.description.contains("cargo test login"));
+        assert!(fix.description.ends_with("Test still fails"));
+        assert!(BugFixStage::Fix.expects_passing_test());
+        assert!(!BugFixStage::Reproduce.expects_passing_test());
+    }
+}
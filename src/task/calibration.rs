@@ -0,0 +1,361 @@
+//! Calibration of task duration estimates against actual run times.
+//!
+//! Every completed task appends an [`EstimateSample`] to
+//! `.aca/usage/estimates.jsonl`: the duration the plan simulator would have
+//! assumed for it and how long its attempts actually took. [`Calibration`]
+//! turns the samples into a correction factor per complexity level, which
+//! dry-run timelines apply to future estimates and `aca stats` reports.
+
+use super::simulation::TaskEstimate;
+use super::types::{ComplexityLevel, Task, TaskStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Samples a complexity level needs before its own factor is trusted
+pub const MIN_CALIBRATION_SAMPLES: u32 = 3;
+
+/// Estimated and actual duration of one completed task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateSample {
+    pub timestamp: DateTime<Utc>,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub complexity: Option<ComplexityLevel>,
+    pub estimated_secs: f64,
+    pub actual_secs: f64,
+}
+
+impl EstimateSample {
+    /// Sample for a completed task that took `actual` to run
+    pub fn from_task(task: &Task, actual: TimeDelta) -> Option<Self> {
+        if !matches!(task.status, TaskStatus::Completed { .. }) || actual <= TimeDelta::zero() {
+            return None;
+        }
+
+        Some(Self {
+            timestamp: Utc::now(),
+            task_id: task.id,
+            task_title: task.title.clone(),
+            complexity: task.metadata.estimated_complexity.clone(),
+            estimated_secs: seconds(TaskEstimate::for_metadata(&task.metadata).duration),
+            actual_secs: seconds(actual),
+        })
+    }
+}
+
+fn seconds(delta: TimeDelta) -> f64 {
+    delta.num_milliseconds() as f64 / 1000.0
+}
+
+/// Append-only JSONL log of estimate samples stored in the workspace
+#[derive(Debug, Clone)]
+pub struct EstimateLedger {
+    path: PathBuf,
+}
+
+impl EstimateLedger {
+    /// Ledger for a workspace (`.aca/usage/estimates.jsonl`)
+    pub fn for_workspace(workspace_root: &Path) -> Self {
+        Self::new(crate::env::estimate_ledger_file_path(workspace_root))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Append one sample to the ledger
+    pub fn append(&self, sample: &EstimateSample) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create usage directory {:?}", parent))?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open estimate ledger {:?}", self.path))?;
+        writeln!(file, "{}", serde_json::to_string(sample)?)
+            .with_context(|| format!("Failed to write estimate ledger {:?}", self.path))?;
+        Ok(())
+    }
+
+    /// Read all samples, skipping malformed lines
+    pub fn load(&self) -> Result<Vec<EstimateSample>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {:?}", self.path));
+            }
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(sample) => Some(sample),
+                Err(e) => {
+                    tracing::warn!("Skipping malformed estimate ledger line: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// Estimated and actual totals for one complexity level
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationBucket {
+    /// `None` collects tasks without a complexity estimate
+    pub complexity: Option<ComplexityLevel>,
+    pub samples: u32,
+    pub estimated_secs: f64,
+    pub actual_secs: f64,
+}
+
+impl CalibrationBucket {
+    /// How much longer tasks took than estimated (2.0 = twice as long)
+    pub fn factor(&self) -> f64 {
+        if self.estimated_secs > 0.0 {
+            self.actual_secs / self.estimated_secs
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Correction factors for duration estimates, per complexity level
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calibration {
+    /// Ordered from trivial to epic, unrated last
+    pub buckets: Vec<CalibrationBucket>,
+}
+
+impl Calibration {
+    pub fn from_samples(samples: &[EstimateSample]) -> Self {
+        let mut buckets: Vec<CalibrationBucket> = Vec::new();
+        for sample in samples {
+            if sample.estimated_secs <= 0.0 || sample.actual_secs <= 0.0 {
+                continue;
+            }
+            let bucket = match buckets
+                .iter_mut()
+                .position(|bucket| bucket.complexity == sample.complexity)
+            {
+                Some(index) => &mut buckets[index],
+                None => {
+                    buckets.push(CalibrationBucket {
+                        complexity: sample.complexity.clone(),
+                        samples: 0,
+                        estimated_secs: 0.0,
+                        actual_secs: 0.0,
+                    });
+                    buckets.last_mut().expect("bucket was just pushed")
+                }
+            };
+            bucket.samples += 1;
+            bucket.estimated_secs += sample.estimated_secs;
+            bucket.actual_secs += sample.actual_secs;
+        }
+        // Unrated tasks sort after every complexity level
+        buckets.sort_by_key(|bucket| (bucket.complexity.is_none(), bucket.complexity.clone()));
+        Self { buckets }
+    }
+
+    /// Calibration from the workspace's estimate ledger
+    pub fn for_workspace(workspace_root: &Path) -> Result<Self> {
+        Ok(Self::from_samples(
+            &EstimateLedger::for_workspace(workspace_root).load()?,
+        ))
+    }
+
+    /// All samples pooled together
+    pub fn overall(&self) -> CalibrationBucket {
+        self.buckets.iter().fold(
+            CalibrationBucket {
+                complexity: None,
+                samples: 0,
+                estimated_secs: 0.0,
+                actual_secs: 0.0,
+            },
+            |mut total, bucket| {
+                total.samples += bucket.samples;
+                total.estimated_secs += bucket.estimated_secs;
+                total.actual_secs += bucket.actual_secs;
+                total
+            },
+        )
+    }
+
+    /// Factor for a complexity level, falling back to the pooled factor and
+    /// then to 1.0 while there are too few samples
+    pub fn factor(&self, complexity: Option<&ComplexityLevel>) -> f64 {
+        if let Some(bucket) = self
+            .buckets
+            .iter()
+            .find(|bucket| bucket.complexity.as_ref() == complexity)
+            .filter(|bucket| bucket.samples >= MIN_CALIBRATION_SAMPLES)
+        {
+            return bucket.factor();
+        }
+        let overall = self.overall();
+        if overall.samples >= MIN_CALIBRATION_SAMPLES {
+            overall.factor()
+        } else {
+            1.0
+        }
+    }
+
+    /// Scale an estimate by the factor for its complexity
+    pub fn apply(&self, estimate: TimeDelta, complexity: Option<&ComplexityLevel>) -> TimeDelta {
+        let millis = estimate.num_milliseconds() as f64 * self.factor(complexity);
+        TimeDelta::milliseconds(millis.round() as i64)
+    }
+
+    /// Human-readable table for `aca stats`
+    pub fn report(&self) -> String {
+        let overall = self.overall();
+        if overall.samples == 0 {
+            return "No completed tasks have been timed yet; estimates are uncalibrated."
+                .to_string();
+        }
+
+        let mut lines = vec![
+            format!(
+                "Estimate calibration from {} completed task(s):",
+                overall.samples
+            ),
+            format!(
+                "  {:<12} {:>6} {:>10} {:>10} {:>8}",
+                "complexity", "tasks", "estimated", "actual", "factor"
+            ),
+        ];
+        let row = |label: &str, bucket: &CalibrationBucket, used: bool| {
+            format!(
+                "  {:<12} {:>6} {:>10} {:>10} {:>7.2}x{}",
+                label,
+                bucket.samples,
+                format_secs(bucket.estimated_secs),
+                format_secs(bucket.actual_secs),
+                bucket.factor(),
+                if used { "" } else { " (too few samples)" }
+            )
+        };
+        for bucket in &self.buckets {
+            let label = bucket
+                .complexity
+                .as_ref()
+                .map_or("unrated".to_string(), |c| format!("{:?}", c).to_lowercase());
+            lines.push(row(
+                &label,
+                bucket,
+                bucket.samples >= MIN_CALIBRATION_SAMPLES,
+            ));
+        }
+        lines.push(row(
+            "overall",
+            &overall,
+            overall.samples >= MIN_CALIBRATION_SAMPLES,
+        ));
+        lines.join("\n")
+    }
+}
+
+fn format_secs(secs: f64) -> String {
+    let minutes = (secs / 60.0).round() as i64;
+    if minutes >= 60 {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs.round() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::types::{TaskResult, TaskSpec};
+
+    fn sample(complexity: Option<ComplexityLevel>, estimated: f64, actual: f64) -> EstimateSample {
+        EstimateSample {
+            timestamp: Utc::now(),
+            task_id: Uuid::new_v4(),
+            task_title: "task".to_string(),
+            complexity,
+            estimated_secs: estimated,
+            actual_secs: actual,
+        }
+    }
+
+    #[test]
+    fn test_factors_per_complexity_with_fallbacks() {
+        use ComplexityLevel::*;
+        let mut samples = vec![
+            sample(Some(Simple), 600.0, 1200.0),
+            sample(Some(Simple), 600.0, 1500.0),
+            sample(Some(Simple), 600.0, 900.0),
+            sample(Some(Complex), 7200.0, 3600.0),
+        ];
+        let calibration = Calibration::from_samples(&samples);
+        assert_eq!(calibration.factor(Some(&Simple)), 2.0);
+        // One complex sample is not enough; the pooled factor is used instead
+        assert_eq!(calibration.factor(Some(&Complex)), 7200.0 / 9000.0);
+        assert_eq!(
+            calibration.apply(TimeDelta::minutes(10), Some(&Simple)),
+            TimeDelta::minutes(20)
+        );
+
+        samples.truncate(2);
+        let sparse = Calibration::from_samples(&samples);
+        assert_eq!(sparse.factor(Some(&Simple)), 1.0);
+        assert_eq!(sparse.factor(None), 1.0);
+    }
+
+    #[test]
+    fn test_sample_from_completed_task_and_report() {
+        let mut task = Task::new(
+            TaskSpec {
+                title: "Add login".to_string(),
+                ..Default::default()
+            },
+            None,
+        );
+        task.metadata.estimated_complexity = Some(ComplexityLevel::Moderate);
+        assert!(EstimateSample::from_task(&task, TimeDelta::minutes(45)).is_none());
+
+        task.status = TaskStatus::Completed {
+            completed_at: Utc::now(),
+            result: TaskResult::Success {
+                output: serde_json::Value::Null,
+                files_created: Vec::new(),
+                files_modified: Vec::new(),
+                build_artifacts: Vec::new(),
+            },
+        };
+
+        let sample = EstimateSample::from_task(&task, TimeDelta::minutes(45)).unwrap();
+        // Moderate tasks without an explicit duration are simulated at 30 minutes
+        assert_eq!(sample.estimated_secs, 1800.0);
+        assert_eq!(sample.actual_secs, 2700.0);
+
+        let ledger_dir = tempfile::tempdir().unwrap();
+        let ledger = EstimateLedger::for_workspace(ledger_dir.path());
+        for _ in 0..3 {
+            ledger.append(&sample).unwrap();
+        }
+        let report = Calibration::for_workspace(ledger_dir.path())
+            .unwrap()
+            .report();
+        assert!(report.starts_with("Estimate calibration from 3 completed task(s):"));
+        assert!(report.contains("moderate"));
+        assert!(report.contains("1h30m"));
+        assert!(report.contains("1.50x"));
+        assert!(Calibration::default().report().contains("uncalibrated"));
+    }
+}
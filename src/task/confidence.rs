@@ -0,0 +1,389 @@
+//! Self-reported confidence and risk flags, and the gates they feed.
+//!
+//! The model ends a task with a summary block saying how sure it is that
+//! the task is done and which risky changes it made:
+//!
+//! ```text
+//! <aca-summary>
+//! {"confidence": 0.6, "risks": ["migration"],
+//!  "summary": "Added the orders table and its migration"}
+//! </aca-summary>
+//! ```
+//!
+//! [`CompletionGateConfig`] decides what happens to such a task instead of
+//! completing it: a [`GateAction::Review`] runs the task once more with a
+//! prompt to review its own changes, and a [`GateAction::Approval`] blocks the
+//! task until a human looks at it. A task still below the confidence
+//! threshold after its review pass is sent for approval. Each gate a task
+//! trips is also reported as a [`Finding`] on the files it changed, for
+//! SARIF export.
+
+use super::findings::{Finding, FindingSeverity};
+use crate::workspace::WorkspaceChanges;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Opening tag of a summary block in a model response
+pub const SUMMARY_BLOCK_START: &str = "<aca-summary>";
+
+/// Closing tag of a summary block in a model response
+pub const SUMMARY_BLOCK_END: &str = "</aca-summary>";
+
+/// System prompt addition describing the summary block to the model
+pub const SUMMARY_PROTOCOL_PROMPT: &str = "End your final response with a single block of \
+the form <aca-summary>{\"confidence\": 0.8, \"risks\": [], \"summary\": \"...\"}</aca-summary>. \
+confidence is between 0 and 1 and says how sure you are that the task is done correctly. \
+List in risks each that applies: \"auth\" if you changed authentication or authorization \
+code, \"migration\" if you added or modified a database migration, \"deleted_files\" if you \
+deleted files.";
+
+/// A risky kind of change the model reports having made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskFlag {
+    /// Touched authentication or authorization code
+    Auth,
+    /// Added or modified a database migration
+    Migration,
+    /// Deleted files
+    DeletedFiles,
+    /// Any flag this version does not know
+    #[serde(other)]
+    Other,
+}
+
+impl fmt::Display for RiskFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RiskFlag::Auth => "touched auth code",
+            RiskFlag::Migration => "modified a migration",
+            RiskFlag::DeletedFiles => "deleted files",
+            RiskFlag::Other => "reported another risk",
+        })
+    }
+}
+
+/// What the model reported about a finished task
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompletionReport {
+    /// How sure the model is that the task is done correctly, 0 to 1
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    #[serde(default)]
+    pub risks: Vec<RiskFlag>,
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+impl CompletionReport {
+    /// Extract the last summary block from a model response
+    pub fn parse(response: &str) -> Option<Self> {
+        let start = response.rfind(SUMMARY_BLOCK_START)? + SUMMARY_BLOCK_START.len();
+        let end = start + response[start..].find(SUMMARY_BLOCK_END)?;
+        let mut report: CompletionReport =
+            serde_json::from_str(response[start..end].trim()).ok()?;
+        report.confidence = report
+            .confidence
+            .filter(|confidence| confidence.is_finite())
+            .map(|confidence| confidence.clamp(0.0, 1.0));
+        Some(report)
+    }
+}
+
+/// Where a gated task goes instead of completing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GateAction {
+    /// Run the task once more, asking the model to review its changes
+    Review,
+    /// Block the task until a human approves it
+    Approval,
+}
+
+/// Confidence and risk gates on task completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletionGateConfig {
+    /// Ask the model for a summary block and gate on it
+    pub enabled: bool,
+    /// Reported confidence below this is gated
+    pub min_confidence: f64,
+    /// Action for confidence below `min_confidence`
+    pub low_confidence: GateAction,
+    /// Action for each risk flag; flags not listed complete normally
+    pub risks: BTreeMap<RiskFlag, GateAction>,
+}
+
+impl Default for CompletionGateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_confidence: 0.5,
+            low_confidence: GateAction::Review,
+            risks: BTreeMap::from([
+                (RiskFlag::Auth, GateAction::Approval),
+                (RiskFlag::Migration, GateAction::Approval),
+                (RiskFlag::DeletedFiles, GateAction::Review),
+            ]),
+        }
+    }
+}
+
+/// One gate a task tripped
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateReason {
+    /// Risk flag behind the gate; `None` for low confidence
+    pub risk: Option<RiskFlag>,
+    pub text: String,
+}
+
+impl GateReason {
+    /// Finding category, which becomes the SARIF rule
+    pub fn category(&self) -> &'static str {
+        match self.risk {
+            None => "Low confidence",
+            Some(RiskFlag::Auth) => "Auth change",
+            Some(RiskFlag::Migration) => "Migration change",
+            Some(RiskFlag::DeletedFiles) => "Deleted files",
+            Some(RiskFlag::Other) => "Reported risk",
+        }
+    }
+}
+
+/// Why a task was gated and where it goes
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateDecision {
+    pub action: GateAction,
+    pub reasons: Vec<GateReason>,
+}
+
+impl GateDecision {
+    /// Reasons as one sentence fragment
+    pub fn reason(&self) -> String {
+        self.reasons
+            .iter()
+            .map(|reason| reason.text.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// One finding per reason and file the task changed
+    ///
+    /// Deleted-files gates point at the deleted files and every other gate
+    /// at the created and modified ones; a reason with no such file gives
+    /// one finding without a location.
+    pub fn findings(&self, task_title: &str, changes: Option<&WorkspaceChanges>) -> Vec<Finding> {
+        let (severity, outcome) = match self.action {
+            GateAction::Review => (FindingSeverity::Note, "sent for review"),
+            GateAction::Approval => (FindingSeverity::Warning, "held for approval"),
+        };
+        let mut findings = Vec::new();
+        for reason in &self.reasons {
+            let files: Vec<Option<PathBuf>> = match (changes, reason.risk) {
+                (Some(changes), Some(RiskFlag::DeletedFiles)) => {
+                    changes.deleted.iter().cloned().map(Some).collect()
+                }
+                (Some(changes), _) => changes
+                    .created
+                    .iter()
+                    .chain(&changes.modified)
+                    .cloned()
+                    .map(Some)
+                    .collect(),
+                (None, _) => Vec::new(),
+            };
+            let files = if files.is_empty() { vec![None] } else { files };
+            findings.extend(files.into_iter().map(|file| Finding {
+                category: reason.category().to_string(),
+                severity,
+                message: format!("Task \"{}\" {}: {}", task_title, outcome, reason.text),
+                file,
+                line: None,
+                end_line: None,
+            }));
+        }
+        findings
+    }
+}
+
+impl CompletionGateConfig {
+    /// Gate for a task's report, if any applies
+    ///
+    /// After the review pass (`reviewed`), review-only risks are accepted and
+    /// confidence still below the threshold is sent for approval. A task
+    /// without a report is not gated.
+    pub fn evaluate(
+        &self,
+        report: Option<&CompletionReport>,
+        reviewed: bool,
+    ) -> Option<GateDecision> {
+        if !self.enabled {
+            return None;
+        }
+        let report = report?;
+
+        let mut gates = Vec::new();
+        if let Some(confidence) = report.confidence
+            && confidence < self.min_confidence
+        {
+            let action = if reviewed {
+                GateAction::Approval
+            } else {
+                self.low_confidence
+            };
+            gates.push((
+                action,
+                GateReason {
+                    risk: None,
+                    text: format!(
+                        "confidence {:.2} is below {:.2}",
+                        confidence, self.min_confidence
+                    ),
+                },
+            ));
+        }
+        for risk in &report.risks {
+            if let Some(&action) = self.risks.get(risk)
+                && !(reviewed && action == GateAction::Review)
+            {
+                gates.push((
+                    action,
+                    GateReason {
+                        risk: Some(*risk),
+                        text: risk.to_string(),
+                    },
+                ));
+            }
+        }
+
+        let action = gates.iter().map(|(action, _)| *action).max()?;
+        let mut reasons: Vec<GateReason> = gates.into_iter().map(|(_, reason)| reason).collect();
+        reasons.dedup();
+        Some(GateDecision { action, reasons })
+    }
+}
+
+/// Prompt for the review pass of a gated task
+pub fn review_prompt(description: &str, decision: &GateDecision) -> String {
+    format!(
+        "{}\n\n--- Review ---\nYour previous attempt was flagged for review: {}.\nReview the changes you made against the task, fix any mistakes you find, and end with a new summary block.",
+        description,
+        decision.reason()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_summary_block() {
+        let response = "Done.\n<aca-summary>{\"confidence\": 1.4, \"risks\": [\"auth\", \"rewrote_history\"], \"summary\": \"Added login\"}</aca-summary>";
+        let report = CompletionReport::parse(response).unwrap();
+        assert_eq!(report.confidence, Some(1.0));
+        assert_eq!(report.risks, vec![RiskFlag::Auth, RiskFlag::Other]);
+        assert_eq!(report.summary.as_deref(), Some("Added login"));
+
+        assert!(CompletionReport::parse("No block here").is_none());
+        assert!(CompletionReport::parse("<aca-summary>not json</aca-summary>").is_none());
+    }
+
+    #[test]
+    fn test_gates_route_low_confidence_and_risks() {
+        let config = CompletionGateConfig::default();
+        let confident = CompletionReport {
+            confidence: Some(0.9),
+            ..Default::default()
+        };
+        assert_eq!(config.evaluate(Some(&confident), false), None);
+        assert_eq!(config.evaluate(None, false), None);
+
+        let unsure = CompletionReport {
+            confidence: Some(0.3),
+            risks: vec![RiskFlag::DeletedFiles],
+            summary: None,
+        };
+        let decision = config.evaluate(Some(&unsure), false).unwrap();
+        assert_eq!(decision.action, GateAction::Review);
+        assert_eq!(
+            decision.reason(),
+            "confidence 0.30 is below 0.50; deleted files"
+        );
+
+        let risky = CompletionReport {
+            confidence: Some(0.9),
+            risks: vec![RiskFlag::DeletedFiles, RiskFlag::Migration],
+            summary: None,
+        };
+        assert_eq!(
+            config.evaluate(Some(&risky), false).unwrap().action,
+            GateAction::Approval
+        );
+    }
+
+    #[test]
+    fn test_review_pass_accepts_reviewed_risks_and_escalates_low_confidence() {
+        let config = CompletionGateConfig::default();
+        let deleted = CompletionReport {
+            confidence: Some(0.8),
+            risks: vec![RiskFlag::DeletedFiles],
+            summary: None,
+        };
+        assert_eq!(config.evaluate(Some(&deleted), true), None);
+
+        let unsure = CompletionReport {
+            confidence: Some(0.2),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.evaluate(Some(&unsure), true).unwrap().action,
+            GateAction::Approval
+        );
+    }
+
+    #[test]
+    fn test_gate_findings_point_at_changed_files() {
+        let config = CompletionGateConfig::default();
+        let report = CompletionReport {
+            confidence: Some(0.9),
+            risks: vec![RiskFlag::Auth, RiskFlag::DeletedFiles],
+            summary: None,
+        };
+        let decision = config.evaluate(Some(&report), false).unwrap();
+        let changes = WorkspaceChanges {
+            created: vec![PathBuf::from("src/login.rs")],
+            modified: vec![PathBuf::from("src/auth.rs")],
+            deleted: vec![PathBuf::from("src/legacy.rs")],
+        };
+
+        let findings = decision.findings("Add login", Some(&changes));
+        let located: Vec<_> = findings
+            .iter()
+            .map(|finding| (finding.category.as_str(), finding.file.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                ("Auth change", PathBuf::from("src/login.rs")),
+                ("Auth change", PathBuf::from("src/auth.rs")),
+                ("Deleted files", PathBuf::from("src/legacy.rs")),
+            ]
+        );
+        assert!(
+            findings
+                .iter()
+                .all(|f| f.severity == FindingSeverity::Warning)
+        );
+        assert_eq!(
+            findings[0].message,
+            "Task \"Add login\" held for approval: touched auth code"
+        );
+
+        // Without a recorded diff each reason still gives one finding
+        let findings = decision.findings("Add login", None);
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|finding| finding.file.is_none()));
+    }
+}
@@ -392,8 +392,13 @@ impl TaskExecutor {
                     file_refs: Vec::new(),
                     tags: task.metadata.tags.clone(),
                     context_requirements: ContextRequirements::new(),
+                    requirements: Default::default(),
+                    provider_preference: Default::default(),
+                    tool_permissions: Default::default(),
+                    wait_for: Default::default(),
                 },
                 dependencies: Vec::new(),
+                plan: None,
             }];
 
             return Ok(TaskExecutionResult::CompletedWithSubtasks {
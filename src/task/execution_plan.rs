@@ -32,9 +32,26 @@
 //!     .with_task(TaskSpec::new("Run tests", "Execute test suite"));
 //! ```
 
-use crate::task::{SetupCommand, TaskSpec};
+use crate::executor::{NixConfig, ServiceSpec};
+use crate::task::{
+    CompletedTaskPolicy, PlanDiff, SetupCommand, TaskId, TaskSpec, VerificationConfig,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Tag on a plan task its source file already marks as done; the task is
+/// recorded as completed instead of being run
+pub const PRECOMPLETED_TAG: &str = "status:completed";
+
+/// Id a plan's `dependencies` use to refer to another task in the same plan,
+/// derived from that task's title
+pub fn plan_task_id(title: &str) -> TaskId {
+    // DNS namespace, kept so ids in existing plan files stay valid
+    let namespace = uuid::Uuid::parse_str("6ba7b810-9dad-11d1-80b4-00c04fd430c8")
+        .expect("valid namespace uuid");
+    uuid::Uuid::new_v5(&namespace, format!("llm-task-{}", title).as_bytes())
+}
 
 /// Execution strategy for processing tasks within a plan
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -74,6 +91,24 @@ pub struct ExecutionPlan {
     pub execution_mode: ExecutionMode,
     /// Additional metadata about the execution plan
     pub metadata: PlanMetadata,
+    /// File the plan was loaded from; enables skipping already-completed tasks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    /// Whether tasks completed by an earlier run of `source` run again
+    #[serde(default)]
+    pub completed_task_policy: CompletedTaskPolicy,
+    /// Nix development shell for this plan, replacing the agent's setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nix: Option<NixConfig>,
+    /// Batched verification for this plan, replacing the agent's setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationConfig>,
+    /// Background services kept running while the plan's tasks execute
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<ServiceSpec>,
+    /// Names of ports allocated while the plan runs, as environment variables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
 }
 
 impl ExecutionPlan {
@@ -84,6 +119,12 @@ impl ExecutionPlan {
             task_specs: Vec::new(),
             execution_mode: ExecutionMode::default(),
             metadata: PlanMetadata::default(),
+            source: None,
+            completed_task_policy: CompletedTaskPolicy::default(),
+            nix: None,
+            verification: None,
+            services: Vec::new(),
+            ports: Vec::new(),
         }
     }
 
@@ -99,6 +140,12 @@ impl ExecutionPlan {
         self
     }
 
+    /// Add background services to the plan
+    pub fn with_services(mut self, services: Vec<ServiceSpec>) -> Self {
+        self.services.extend(services);
+        self
+    }
+
     /// Add a task specification to the plan
     pub fn with_task(mut self, task_spec: TaskSpec) -> Self {
         self.task_specs.push(task_spec);
@@ -117,6 +164,18 @@ impl ExecutionPlan {
         self
     }
 
+    /// Run this plan's host commands in the given Nix shell settings
+    pub fn with_nix(mut self, nix: NixConfig) -> Self {
+        self.nix = Some(nix);
+        self
+    }
+
+    /// Verify this plan's tasks in groups with the given settings
+    pub fn with_verification(mut self, verification: VerificationConfig) -> Self {
+        self.verification = Some(verification);
+        self
+    }
+
     /// Set sequential execution mode (default)
     pub fn with_sequential_execution(self) -> Self {
         self.with_execution_mode(ExecutionMode::Sequential)
@@ -149,6 +208,18 @@ impl ExecutionPlan {
         self
     }
 
+    /// Record the file this plan was loaded from
+    pub fn with_source(mut self, source: impl Into<PathBuf>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Choose whether tasks completed by an earlier run are run again
+    pub fn with_completed_task_policy(mut self, policy: CompletedTaskPolicy) -> Self {
+        self.completed_task_policy = policy;
+        self
+    }
+
     /// Set the estimated duration for the entire plan
     pub fn with_estimated_duration(mut self, duration: chrono::Duration) -> Self {
         self.metadata.estimated_duration = Some(duration);
@@ -195,6 +266,11 @@ impl ExecutionPlan {
         }
     }
 
+    /// Tasks added, removed and changed in `other`, matched by title
+    pub fn diff(&self, other: &ExecutionPlan) -> PlanDiff {
+        PlanDiff::between(self, other)
+    }
+
     /// Validate the execution plan for consistency
     pub fn validate(&self) -> Result<(), String> {
         if self.is_empty() {
@@ -219,13 +295,75 @@ impl ExecutionPlan {
             if task.title.is_empty() {
                 return Err(format!("Task {} has empty title", i));
             }
-            if task.description.is_empty() {
+            // A task that expands into a sub-plan is described by its file
+            if task.description.is_empty() && task.plan.is_none() {
                 return Err(format!("Task '{}' has empty description", task.title));
             }
         }
 
+        if let Some(cycle) = self.dependency_cycle() {
+            return Err(format!("Dependency cycle: {}", cycle.join(" -> ")));
+        }
+
         Ok(())
     }
+
+    /// Titles along a cycle of dependencies between the plan's tasks, the
+    /// first repeated at the end
+    ///
+    /// Dependencies on tasks outside the plan cannot form a cycle here and
+    /// are ignored.
+    fn dependency_cycle(&self) -> Option<Vec<String>> {
+        let index: HashMap<TaskId, usize> = self
+            .task_specs
+            .iter()
+            .enumerate()
+            .map(|(i, spec)| (plan_task_id(&spec.title), i))
+            .collect();
+        let edges: Vec<Vec<usize>> = self
+            .task_specs
+            .iter()
+            .map(|spec| {
+                spec.dependencies
+                    .iter()
+                    .filter_map(|dependency| index.get(dependency).copied())
+                    .collect()
+            })
+            .collect();
+
+        // Depth-first search; a dependency still on the path closes a cycle
+        let mut done = vec![false; edges.len()];
+        for start in 0..edges.len() {
+            let mut path = vec![start];
+            let mut next = vec![0];
+            while let Some(&task) = path.last() {
+                let position = next.last_mut().expect("one position per path entry");
+                match edges[task].get(*position) {
+                    Some(&dependency) => {
+                        *position += 1;
+                        if let Some(at) = path.iter().position(|&on| on == dependency) {
+                            let mut cycle: Vec<String> = path[at..]
+                                .iter()
+                                .map(|&i| self.task_specs[i].title.clone())
+                                .collect();
+                            cycle.push(self.task_specs[dependency].title.clone());
+                            return Some(cycle);
+                        }
+                        if !done[dependency] {
+                            path.push(dependency);
+                            next.push(0);
+                        }
+                    }
+                    None => {
+                        done[task] = true;
+                        path.pop();
+                        next.pop();
+                    }
+                }
+            }
+        }
+        None
+    }
 }
 
 impl Default for ExecutionPlan {
@@ -255,6 +393,7 @@ mod tests {
             description: "Test description".to_string(),
             dependencies: Vec::new(),
             metadata: TaskMetadata::default(),
+            plan: None,
         };
 
         let plan = ExecutionPlan::new()
@@ -321,6 +460,7 @@ mod tests {
             description: "Valid description".to_string(),
             dependencies: Vec::new(),
             metadata: TaskMetadata::default(),
+            plan: None,
         });
         assert!(valid_plan.validate().is_ok());
 
@@ -330,10 +470,45 @@ mod tests {
             description: "Valid description".to_string(),
             dependencies: Vec::new(),
             metadata: TaskMetadata::default(),
+            plan: None,
         });
         assert!(invalid_plan.validate().is_err());
     }
 
+    #[test]
+    fn test_execution_plan_rejects_dependency_cycles() {
+        let task = |title: &str, dependencies: &[&str]| TaskSpec {
+            title: title.to_string(),
+            description: format!("{} description", title),
+            dependencies: dependencies
+                .iter()
+                .map(|title| plan_task_id(title))
+                .collect(),
+            metadata: TaskMetadata::default(),
+            plan: None,
+        };
+        let outside = uuid::Uuid::new_v4();
+        let mut plan = ExecutionPlan::new().with_tasks(vec![
+            task("Schema", &[]),
+            task("API", &["Schema"]),
+            task("UI", &["API", "Schema"]),
+        ]);
+        plan.task_specs[0].dependencies.push(outside);
+        assert!(plan.validate().is_ok());
+
+        plan.task_specs[0].dependencies.push(plan_task_id("UI"));
+        assert_eq!(
+            plan.validate().unwrap_err(),
+            "Dependency cycle: Schema -> UI -> API -> Schema"
+        );
+
+        let own = ExecutionPlan::new().with_task(task("Loop", &["Loop"]));
+        assert_eq!(
+            own.validate().unwrap_err(),
+            "Dependency cycle: Loop -> Loop"
+        );
+    }
+
     #[test]
     fn test_execution_plan_summary() {
         // Test various combinations of setup commands and tasks
@@ -345,12 +520,14 @@ mod tests {
                 description: "First task".to_string(),
                 dependencies: Vec::new(),
                 metadata: TaskMetadata::default(),
+                plan: None,
             })
             .with_task(TaskSpec {
                 title: "Task 2".to_string(),
                 description: "Second task".to_string(),
                 dependencies: Vec::new(),
                 metadata: TaskMetadata::default(),
+                plan: None,
             });
 
         assert_eq!(plan.summary(), "2 setup commands, 2 tasks");
@@ -0,0 +1,513 @@
+//! Why a task is waiting.
+//!
+//! [`TaskExplanation`] gathers what keeps a task from running: its own
+//! status, unfinished dependencies, a saturated concurrency limit, the
+//! schedule and the machine-wide rate limit. It also gives the task's
+//! scheduling score and its rank among the tasks that are ready to run.
+//! `aca task why` prints it as text or JSON.
+
+use super::scheduler::{ScoreBreakdown, TaskScheduler};
+use super::tree::TaskTree;
+use super::types::{BlockReason, TaskId, TaskStatus};
+use crate::llm::BudgetScope;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Tasks listed as running ahead of an outranked task
+const MAX_AHEAD: usize = 3;
+
+/// Run-wide conditions that can hold back a task that is otherwise ready
+#[derive(Debug, Clone, Default)]
+pub struct WaitConditions {
+    /// Tasks allowed to run at once
+    pub max_concurrent_tasks: u32,
+    /// Why the execution windows or daily quota pause the run, if they do
+    pub schedule_pause: Option<String>,
+    /// Time until the machine-wide rate limit has room again, if exhausted
+    pub rate_limit_wait: Option<std::time::Duration>,
+}
+
+/// A dependency and whether it is finished
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyState {
+    pub task_id: TaskId,
+    /// `None` when the dependency is not in the session
+    pub title: Option<String>,
+    pub status: String,
+    pub satisfied: bool,
+}
+
+/// A ready task with a higher scheduling score
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedTask {
+    pub task_id: TaskId,
+    pub title: String,
+    pub score: f64,
+}
+
+/// One thing keeping the task from running
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WaitReason {
+    /// The task is not waiting at all (running or finished)
+    NotWaiting {
+        status: String,
+    },
+    Blocked {
+        reason: String,
+        retry_after: Option<DateTime<Utc>>,
+    },
+    /// Blocked until a larger cost budget is configured
+    OverBudget {
+        scope: BudgetScope,
+        spent: f64,
+        limit: f64,
+    },
+    Paused {
+        reason: String,
+    },
+    AwaitingInput {
+        question: String,
+    },
+    Failed {
+        error: String,
+        retry_count: u32,
+    },
+    UnmetDependencies {
+        dependencies: Vec<DependencyState>,
+    },
+    ConcurrencySaturated {
+        running: usize,
+        limit: u32,
+    },
+    Schedule {
+        reason: String,
+    },
+    RateLimited {
+        wait_secs: u64,
+    },
+    /// Other ready tasks score higher and run first
+    Outranked {
+        ahead: Vec<RankedTask>,
+    },
+}
+
+impl std::fmt::Display for WaitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitReason::NotWaiting { status } => write!(f, "not waiting: the task is {}", status),
+            WaitReason::Blocked {
+                reason,
+                retry_after,
+            } => {
+                write!(f, "blocked: {}", reason)?;
+                if let Some(retry_after) = retry_after {
+                    write!(
+                        f,
+                        " (retry after {})",
+                        retry_after.format("%Y-%m-%d %H:%M UTC")
+                    )?;
+                }
+                Ok(())
+            }
+            WaitReason::OverBudget {
+                scope,
+                spent,
+                limit,
+            } => {
+                let (owner, setting) = match scope {
+                    BudgetScope::Session => ("the session", "max_session_cost_usd"),
+                    BudgetScope::Task => ("the task", "max_task_cost_usd"),
+                };
+                write!(
+                    f,
+                    "over budget: {} spent ${:.2} of its ${:.2} budget; raise [budget] {} to resume",
+                    owner, spent, limit, setting
+                )
+            }
+            WaitReason::Paused { reason } => write!(f, "paused: {}", reason),
+            WaitReason::AwaitingInput { question } => {
+                write!(f, "waiting for an answer to: {}", question)
+            }
+            WaitReason::Failed { error, retry_count } => write!(
+                f,
+                "failed after {} retr{}: {}",
+                retry_count,
+                if *retry_count == 1 { "y" } else { "ies" },
+                error
+            ),
+            WaitReason::UnmetDependencies { dependencies } => {
+                write!(f, "depends on {} unfinished task(s):", dependencies.len())?;
+                for dependency in dependencies {
+                    write!(
+                        f,
+                        "\n      {} {} ({})",
+                        short_id(dependency.task_id),
+                        dependency.title.as_deref().unwrap_or("<not in session>"),
+                        dependency.status
+                    )?;
+                }
+                Ok(())
+            }
+            WaitReason::ConcurrencySaturated { running, limit } => write!(
+                f,
+                "all concurrency slots are in use ({} running, limit {})",
+                running, limit
+            ),
+            WaitReason::Schedule { reason } => write!(f, "the schedule holds the run: {}", reason),
+            WaitReason::RateLimited { wait_secs } => write!(
+                f,
+                "the machine-wide rate limit is exhausted for another {}s",
+                wait_secs
+            ),
+            WaitReason::Outranked { ahead } => {
+                write!(f, "{} ready task(s) score higher:", ahead.len())?;
+                for task in ahead {
+                    write!(
+                        f,
+                        "\n      {} {} (score {:.1})",
+                        short_id(task.task_id),
+                        task.title,
+                        task.score
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn short_id(task_id: TaskId) -> String {
+    task_id.to_string()[..8].to_string()
+}
+
+/// Everything known about why a task is (or is not) waiting
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskExplanation {
+    pub task_id: TaskId,
+    pub title: String,
+    pub status: String,
+    /// Empty when the task is next in line
+    pub reasons: Vec<WaitReason>,
+    pub dependencies: Vec<DependencyState>,
+    /// Scheduling score, for tasks that can still be scheduled
+    pub score: Option<ScoreBreakdown>,
+    /// 1-based position among the ready tasks, if the task is ready
+    pub rank: Option<usize>,
+    pub ready_tasks: usize,
+}
+
+impl TaskExplanation {
+    pub fn build(
+        tree: &TaskTree,
+        task_id: TaskId,
+        scheduler: &TaskScheduler,
+        conditions: &WaitConditions,
+    ) -> Result<Self> {
+        let task = tree.get_task(task_id)?;
+        let mut reasons = Vec::new();
+
+        let schedulable = match &task.status {
+            TaskStatus::Pending => true,
+            TaskStatus::Paused { reason, .. } => {
+                reasons.push(WaitReason::Paused {
+                    reason: reason.clone(),
+                });
+                true
+            }
+            TaskStatus::Blocked {
+                reason:
+                    BlockReason::BudgetExceeded {
+                        scope,
+                        spent,
+                        limit,
+                    },
+                ..
+            } => {
+                reasons.push(WaitReason::OverBudget {
+                    scope: *scope,
+                    spent: *spent,
+                    limit: *limit,
+                });
+                false
+            }
+            TaskStatus::Blocked {
+                reason,
+                retry_after,
+                ..
+            } => {
+                reasons.push(WaitReason::Blocked {
+                    reason: reason.to_string(),
+                    retry_after: *retry_after,
+                });
+                false
+            }
+            TaskStatus::AwaitingInput { question, .. } => {
+                reasons.push(WaitReason::AwaitingInput {
+                    question: question.question.clone(),
+                });
+                false
+            }
+            TaskStatus::Failed {
+                error, retry_count, ..
+            } => {
+                reasons.push(WaitReason::Failed {
+                    error: error.to_string(),
+                    retry_count: *retry_count,
+                });
+                false
+            }
+            status => {
+                reasons.push(WaitReason::NotWaiting {
+                    status: status.name().to_string(),
+                });
+                false
+            }
+        };
+
+        let dependencies: Vec<DependencyState> = task
+            .dependencies
+            .iter()
+            .map(|&dep_id| match tree.get_task(dep_id) {
+                Ok(dep) => DependencyState {
+                    task_id: dep_id,
+                    title: Some(dep.title.clone()),
+                    status: dep.status.name().to_string(),
+                    satisfied: matches!(dep.status, TaskStatus::Completed { .. }),
+                },
+                Err(_) => DependencyState {
+                    task_id: dep_id,
+                    title: None,
+                    status: "missing".to_string(),
+                    satisfied: false,
+                },
+            })
+            .collect();
+        let unmet: Vec<DependencyState> = dependencies
+            .iter()
+            .filter(|dep| !dep.satisfied)
+            .cloned()
+            .collect();
+        if !unmet.is_empty() {
+            reasons.push(WaitReason::UnmetDependencies {
+                dependencies: unmet,
+            });
+        }
+
+        if schedulable {
+            let running = tree.tasks.values().filter(|t| t.is_running()).count();
+            if conditions.max_concurrent_tasks > 0
+                && running >= conditions.max_concurrent_tasks as usize
+            {
+                reasons.push(WaitReason::ConcurrencySaturated {
+                    running,
+                    limit: conditions.max_concurrent_tasks,
+                });
+            }
+            if let Some(reason) = &conditions.schedule_pause {
+                reasons.push(WaitReason::Schedule {
+                    reason: reason.clone(),
+                });
+            }
+            if let Some(wait) = conditions.rate_limit_wait {
+                reasons.push(WaitReason::RateLimited {
+                    wait_secs: wait.as_secs().max(1),
+                });
+            }
+        }
+
+        // Rank the task among everything ready to run
+        let mut ready: Vec<RankedTask> = tree
+            .get_eligible_tasks()
+            .into_iter()
+            .filter_map(|id| tree.get_task(id).ok())
+            .map(|ready_task| RankedTask {
+                task_id: ready_task.id,
+                title: ready_task.title.clone(),
+                score: scheduler.score_breakdown(ready_task, tree).total,
+            })
+            .collect();
+        ready.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let rank = ready.iter().position(|t| t.task_id == task_id);
+        if let Some(rank) = rank.filter(|&rank| rank > 0) {
+            let ahead = ready[..rank].iter().take(MAX_AHEAD).cloned().collect();
+            reasons.push(WaitReason::Outranked { ahead });
+        }
+
+        Ok(Self {
+            task_id,
+            title: task.title.clone(),
+            status: task.status.name().to_string(),
+            reasons,
+            dependencies,
+            score: schedulable.then(|| scheduler.score_breakdown(task, tree)),
+            rank: rank.map(|rank| rank + 1),
+            ready_tasks: ready.len(),
+        })
+    }
+
+    /// Plain-text explanation for the terminal
+    pub fn render_text(&self) -> String {
+        let mut lines = vec![format!(
+            "Task {} \"{}\" is {}",
+            short_id(self.task_id),
+            self.title,
+            self.status
+        )];
+
+        if self.reasons.is_empty() {
+            lines.push("Nothing is holding it back; it is next in line to run.".to_string());
+        } else {
+            lines.push("Waiting because:".to_string());
+            lines.extend(self.reasons.iter().map(|reason| format!("  - {}", reason)));
+        }
+
+        if let Some(score) = &self.score {
+            let rank = match self.rank {
+                Some(rank) => format!("rank {} of {} ready task(s)", rank, self.ready_tasks),
+                None => "not ready yet".to_string(),
+            };
+            lines.push(format!("Scheduling score {:.1} ({})", score.total, rank));
+            for component in &score.components {
+                lines.push(format!(
+                    "  {:<22} {:>7.2} x {:>5.1} = {:>7.2}",
+                    component.name, component.value, component.weight, component.contribution
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::scheduler::SchedulerConfig;
+    use crate::task::types::{Task, TaskPriority, TaskSpec};
+
+    fn add_task(tree: &mut TaskTree, title: &str, dependencies: Vec<TaskId>) -> TaskId {
+        let mut spec = TaskSpec {
+            title: title.to_string(),
+            dependencies,
+            ..Default::default()
+        };
+        if title == "Urgent fix" {
+            spec.metadata.priority = TaskPriority::Critical;
+        }
+        tree.add_task(Task::new(spec, None)).unwrap()
+    }
+
+    #[test]
+    fn test_explains_dependencies_and_concurrency() {
+        let mut tree = TaskTree::new();
+        let setup = add_task(&mut tree, "Set up database", Vec::new());
+        let migrate = add_task(&mut tree, "Migrate users", vec![setup]);
+        tree.update_task_status(
+            setup,
+            TaskStatus::InProgress {
+                started_at: Utc::now(),
+                estimated_completion: None,
+            },
+        )
+        .unwrap();
+
+        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        let conditions = WaitConditions {
+            max_concurrent_tasks: 1,
+            schedule_pause: Some("outside execution window".to_string()),
+            rate_limit_wait: None,
+        };
+        let explanation = TaskExplanation::build(&tree, migrate, &scheduler, &conditions).unwrap();
+
+        assert!(matches!(
+            &explanation.reasons[0],
+            WaitReason::UnmetDependencies { dependencies }
+                if dependencies[0].task_id == setup && dependencies[0].status == "in_progress"
+        ));
+        assert!(matches!(
+            explanation.reasons[1],
+            WaitReason::ConcurrencySaturated {
+                running: 1,
+                limit: 1
+            }
+        ));
+        assert!(matches!(
+            explanation.reasons[2],
+            WaitReason::Schedule { .. }
+        ));
+        assert_eq!(explanation.rank, None);
+
+        let text = explanation.render_text();
+        assert!(text.contains("depends on 1 unfinished task(s):"));
+        assert!(text.contains("Set up database (in_progress)"));
+        assert!(text.contains("Scheduling score"));
+
+        let json = serde_json::to_value(&explanation).unwrap();
+        assert_eq!(json["reasons"][0]["kind"], "unmet_dependencies");
+        assert_eq!(json["score"]["components"][0]["name"], "priority");
+    }
+
+    #[test]
+    fn test_ready_task_outranked_by_higher_score() {
+        let mut tree = TaskTree::new();
+        let docs = add_task(&mut tree, "Write docs", Vec::new());
+        let urgent = add_task(&mut tree, "Urgent fix", Vec::new());
+
+        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        let conditions = WaitConditions {
+            max_concurrent_tasks: 3,
+            ..Default::default()
+        };
+
+        let explanation = TaskExplanation::build(&tree, docs, &scheduler, &conditions).unwrap();
+        assert_eq!(explanation.rank, Some(2));
+        assert!(matches!(
+            &explanation.reasons[..],
+            [WaitReason::Outranked { ahead }] if ahead[0].task_id == urgent
+        ));
+
+        let next = TaskExplanation::build(&tree, urgent, &scheduler, &conditions).unwrap();
+        assert!(next.reasons.is_empty());
+        assert!(next.render_text().contains("next in line"));
+    }
+
+    #[test]
+    fn test_explains_task_over_budget() {
+        let mut tree = TaskTree::new();
+        let task = add_task(&mut tree, "Add metrics", Vec::new());
+        tree.update_task_status(
+            task,
+            TaskStatus::Blocked {
+                reason: BlockReason::BudgetExceeded {
+                    scope: BudgetScope::Task,
+                    spent: 2.5,
+                    limit: 2.0,
+                },
+                blocked_at: Utc::now(),
+                retry_after: None,
+            },
+        )
+        .unwrap();
+
+        let scheduler = TaskScheduler::new(SchedulerConfig::default());
+        let explanation =
+            TaskExplanation::build(&tree, task, &scheduler, &WaitConditions::default()).unwrap();
+
+        assert!(matches!(
+            explanation.reasons[..],
+            [WaitReason::OverBudget {
+                scope: BudgetScope::Task,
+                ..
+            }]
+        ));
+        assert!(
+            explanation
+                .render_text()
+                .contains("spent $2.50 of its $2.00 budget; raise [budget] max_task_cost_usd")
+        );
+        let json = serde_json::to_value(&explanation).unwrap();
+        assert_eq!(json["reasons"][0]["kind"], "over_budget");
+        assert_eq!(json["reasons"][0]["scope"], "task");
+    }
+}
@@ -0,0 +1,203 @@
+//! Review and scan findings, and their SARIF export.
+//!
+//! A [`Finding`] is one issue raised about the workspace, with a category,
+//! severity and optional file location. [`to_sarif`] renders findings as a
+//! SARIF 2.1.0 log for GitHub code scanning and other tools: each distinct
+//! category becomes a rule whose id is derived from the category name, and
+//! locations are made relative to the workspace root so they resolve
+//! against the repository checkout. Completion gates produce findings
+//! through [`GateDecision::findings`](super::GateDecision::findings), and
+//! `aca run --report-sarif` writes them out.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// Tool name reported in SARIF runs
+const TOOL_NAME: &str = "aca";
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    Error,
+    #[default]
+    Warning,
+    Note,
+}
+
+impl FindingSeverity {
+    /// SARIF `level` value
+    pub fn sarif_level(self) -> &'static str {
+        match self {
+            FindingSeverity::Error => "error",
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Note => "note",
+        }
+    }
+}
+
+/// One issue raised by a review or scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Free-form category, e.g. "SQL injection" or "missing tests"
+    pub category: String,
+    #[serde(default)]
+    pub severity: FindingSeverity,
+    pub message: String,
+    /// File the finding is about, absolute or relative to the workspace
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// 1-based line in `file`
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub end_line: Option<u32>,
+}
+
+/// SARIF rule id for a category: lowercase words joined by dashes
+pub fn rule_id(category: &str) -> String {
+    let id = category
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    if id.is_empty() {
+        "uncategorized".to_string()
+    } else {
+        id
+    }
+}
+
+/// Path of `file` relative to `workspace_root`, with `/` separators
+fn artifact_uri(file: &Path, workspace_root: &Path) -> String {
+    let relative = file.strip_prefix(workspace_root).unwrap_or(file);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// SARIF 2.1.0 log with one run holding `findings`
+pub fn to_sarif(findings: &[Finding], workspace_root: &Path) -> Value {
+    // One rule per id, in first-seen order, named after its first category
+    let mut rules: Vec<(String, &str)> = Vec::new();
+    for finding in findings {
+        let id = rule_id(&finding.category);
+        if !rules.iter().any(|(existing, _)| *existing == id) {
+            rules.push((id, &finding.category));
+        }
+    }
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let id = rule_id(&finding.category);
+            let index = rules.iter().position(|(rule, _)| *rule == id);
+            let mut result = json!({
+                "ruleId": id,
+                "ruleIndex": index,
+                "level": finding.severity.sarif_level(),
+                "message": { "text": finding.message },
+            });
+            if let Some(file) = &finding.file {
+                let mut location = json!({
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": artifact_uri(file, workspace_root),
+                            "uriBaseId": "%SRCROOT%",
+                        }
+                    }
+                });
+                if let Some(line) = finding.line {
+                    let mut region = json!({ "startLine": line.max(1) });
+                    if let Some(end_line) = finding.end_line.filter(|end| *end >= line) {
+                        region["endLine"] = json!(end_line);
+                    }
+                    location["physicalLocation"]["region"] = region;
+                }
+                result["locations"] = json!([location]);
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                        .iter()
+                        .map(|(id, category)| json!({
+                            "id": id,
+                            "name": category,
+                            "shortDescription": { "text": category },
+                        }))
+                        .collect::<Vec<_>>(),
+                }
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": format!("file://{}/", workspace_root.display()) }
+            },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(category: &str, file: Option<&str>, line: Option<u32>) -> Finding {
+        Finding {
+            category: category.to_string(),
+            severity: FindingSeverity::Error,
+            message: format!("{} found", category),
+            file: file.map(PathBuf::from),
+            line,
+            end_line: None,
+        }
+    }
+
+    #[test]
+    fn test_rule_ids_from_categories() {
+        assert_eq!(rule_id("SQL Injection"), "sql-injection");
+        assert_eq!(rule_id("  missing_tests!"), "missing-tests");
+        assert_eq!(rule_id("???"), "uncategorized");
+    }
+
+    #[test]
+    fn test_sarif_log_maps_rules_and_locations() {
+        let findings = vec![
+            finding("Hardcoded secret", Some("/work/src/config.rs"), Some(12)),
+            finding("Missing tests", Some("src/auth.rs"), None),
+            finding("Hardcoded Secret", None, None),
+        ];
+        let sarif = to_sarif(&findings, Path::new("/work"));
+        assert_eq!(sarif["version"], SARIF_VERSION);
+
+        let run = &sarif["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "hardcoded-secret");
+        assert_eq!(rules[1]["id"], "missing-tests");
+
+        let results = run["results"].as_array().unwrap();
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/config.rs");
+        assert_eq!(location["region"]["startLine"], 12);
+        assert_eq!(results[0]["level"], "error");
+        assert!(results[1]["locations"][0]["physicalLocation"]["region"].is_null());
+        assert_eq!(results[2]["ruleIndex"], 0);
+        assert!(results[2].get("locations").is_none());
+    }
+}
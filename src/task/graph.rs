@@ -0,0 +1,250 @@
+//! Diagrams of the task tree for visual inspection.
+//!
+//! Every task becomes a node labelled with its title and status and
+//! coloured by status, with tasks blocked by a spent cost budget shown as
+//! `over_budget` rather than `blocked`. Solid arrows run from a task to the tasks that
+//! depend on it, dashed ones from a parent to its subtasks. Graphviz DOT
+//! renders with `dot -Tsvg`; Mermaid renders in GitHub, GitLab and most
+//! Markdown previews.
+
+use super::types::{BlockReason, Task, TaskId, TaskStatus};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Diagram language of an exported task graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    /// Format for a file name: `.mmd`, `.mermaid` and `.md` are Mermaid,
+    /// `.dot` and `.gv` DOT
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" | "gv" | "graphviz" => Ok(Self::Dot),
+            "mermaid" | "mmd" | "md" => Ok(Self::Mermaid),
+            other => Err(format!(
+                "Unknown graph format '{}': use dot or mermaid",
+                other
+            )),
+        }
+    }
+}
+
+/// Diagram of `tasks`, listed in creation order
+///
+/// Dependencies on tasks not among `tasks` are left out.
+pub fn render_graph(tasks: &[Task], format: GraphFormat) -> String {
+    let mut tasks: Vec<&Task> = tasks.iter().collect();
+    tasks.sort_by_key(|task| task.created_at);
+    let node = |id: TaskId| {
+        tasks
+            .iter()
+            .position(|task| task.id == id)
+            .map(|index| format!("t{}", index))
+    };
+
+    let mut edges = Vec::new();
+    for (index, task) in tasks.iter().enumerate() {
+        let to = format!("t{}", index);
+        for dependency in task.dependencies.iter().filter_map(|id| node(*id)) {
+            edges.push((dependency, to.clone(), false));
+        }
+        if let Some(parent) = task.parent_id.and_then(node) {
+            edges.push((parent, to, true));
+        }
+    }
+
+    let mut out = String::new();
+    match format {
+        GraphFormat::Dot => {
+            out.push_str("digraph tasks {\n    rankdir=LR;\n    node [shape=box, style=\"rounded,filled\"];\n");
+            for (index, task) in tasks.iter().enumerate() {
+                out.push_str(&format!(
+                    "    t{} [label=\"{}\\n({})\", fillcolor=\"{}\"];\n",
+                    index,
+                    dot_escape(&task.title),
+                    status_class(&task.status),
+                    status_color(&task.status)
+                ));
+            }
+            for (from, to, child) in &edges {
+                let style = if *child { " [style=dashed]" } else { "" };
+                out.push_str(&format!("    {} -> {}{};\n", from, to, style));
+            }
+            out.push_str("}\n");
+        }
+        GraphFormat::Mermaid => {
+            out.push_str("flowchart LR\n");
+            for (index, task) in tasks.iter().enumerate() {
+                out.push_str(&format!(
+                    "    t{}[\"{}<br/>({})\"]:::{}\n",
+                    index,
+                    mermaid_escape(&task.title),
+                    status_class(&task.status),
+                    status_class(&task.status)
+                ));
+            }
+            for (from, to, child) in &edges {
+                let arrow = if *child { "-.->" } else { "-->" };
+                out.push_str(&format!("    {} {} {}\n", from, arrow, to));
+            }
+            let mut defined = Vec::new();
+            for status in tasks.iter().map(|task| &task.status) {
+                if !defined.contains(&status_class(status)) {
+                    defined.push(status_class(status));
+                    out.push_str(&format!(
+                        "    classDef {} fill:{}\n",
+                        status_class(status),
+                        status_color(status)
+                    ));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Status a node is labelled and styled with
+fn status_class(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Blocked {
+            reason: BlockReason::BudgetExceeded { .. },
+            ..
+        } => "over_budget",
+        _ => status.name(),
+    }
+}
+
+fn status_color(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Pending => "#eeeeee",
+        TaskStatus::Blocked {
+            reason: BlockReason::BudgetExceeded { .. },
+            ..
+        } => "#ffb38a",
+        TaskStatus::InProgress { .. } => "#9ecbff",
+        TaskStatus::Blocked { .. } | TaskStatus::AwaitingInput { .. } => "#ffd59e",
+        TaskStatus::Paused { .. } => "#e0d4ff",
+        TaskStatus::Completed { .. } => "#a8e6a1",
+        TaskStatus::Failed { .. } => "#ff9e9e",
+        TaskStatus::Skipped { .. } => "#d0d0d0",
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::types::{ContextRequirements, TaskMetadata, TaskPriority, TaskSpec};
+
+    fn task(title: &str, dependencies: Vec<TaskId>, parent_id: Option<TaskId>) -> Task {
+        Task::new(
+            TaskSpec {
+                title: title.to_string(),
+                description: String::new(),
+                metadata: TaskMetadata {
+                    priority: TaskPriority::Normal,
+                    estimated_complexity: None,
+                    estimated_duration: None,
+                    repository_refs: Vec::new(),
+                    file_refs: Vec::new(),
+                    tags: Vec::new(),
+                    context_requirements: ContextRequirements::new(),
+                    requirements: Default::default(),
+                    provider_preference: Default::default(),
+                    tool_permissions: Default::default(),
+                    wait_for: Default::default(),
+                },
+                dependencies,
+                plan: None,
+            },
+            parent_id,
+        )
+    }
+
+    /// Tasks out of creation order, which numbers the nodes
+    fn sample() -> Vec<Task> {
+        let mut schema = task("Design \"users\" schema", Vec::new(), None);
+        let mut api = task("Build API", vec![schema.id], None);
+        let mut tests = task("Write tests", Vec::new(), Some(api.id));
+        api.status = TaskStatus::Skipped {
+            reason: "later".to_string(),
+            skipped_at: chrono::Utc::now(),
+        };
+        tests.status = TaskStatus::Blocked {
+            reason: BlockReason::BudgetExceeded {
+                scope: crate::llm::BudgetScope::Task,
+                spent: 1.2,
+                limit: 1.0,
+            },
+            blocked_at: chrono::Utc::now(),
+            retry_after: None,
+        };
+        let start = chrono::Utc::now();
+        for (offset, task) in [&mut schema, &mut api, &mut tests].into_iter().enumerate() {
+            task.created_at = start + chrono::Duration::seconds(offset as i64);
+        }
+        vec![tests, schema, api]
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let dot = render_graph(&sample(), GraphFormat::Dot);
+        assert!(dot.starts_with("digraph tasks {\n"));
+        assert!(dot.contains("t0 [label=\"Design \\\"users\\\" schema\\n(pending)\""));
+        assert!(dot.contains("t1 [label=\"Build API\\n(skipped)\", fillcolor=\"#d0d0d0\"]"));
+        assert!(dot.contains("t2 [label=\"Write tests\\n(over_budget)\", fillcolor=\"#ffb38a\"]"));
+        assert!(dot.contains("    t0 -> t1;\n"));
+        assert!(dot.contains("    t1 -> t2 [style=dashed];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_render_mermaid_and_formats() {
+        let mermaid = render_graph(&sample(), GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(
+            mermaid.contains("t0[\"Design #quot;users#quot; schema<br/>(pending)\"]:::pending")
+        );
+        assert!(mermaid.contains("    t0 --> t1\n"));
+        assert!(mermaid.contains("    t1 -.-> t2\n"));
+        assert_eq!(mermaid.matches("classDef pending").count(), 1);
+        assert!(mermaid.contains("classDef skipped fill:#d0d0d0"));
+        assert!(mermaid.contains("t2[\"Write tests<br/>(over_budget)\"]:::over_budget"));
+        assert!(mermaid.contains("classDef over_budget fill:#ffb38a"));
+
+        assert_eq!(
+            GraphFormat::from_path(Path::new("plan.mmd")),
+            Some(GraphFormat::Mermaid)
+        );
+        assert_eq!(
+            GraphFormat::from_path(Path::new("plan.gv")),
+            Some(GraphFormat::Dot)
+        );
+        assert_eq!(GraphFormat::from_path(Path::new("plan.png")), None);
+    }
+}
@@ -0,0 +1,444 @@
+//! Stable task identities for re-running plan files.
+//!
+//! Each task loaded from a plan file gets an idempotency key derived from the
+//! plan's path and the task's key: an explicit `key:<name>` tag when present,
+//! otherwise its title. Completed keys are recorded per plan in the workspace
+//! (`.aca/plans/<plan-id>.json`), so running the same file again skips work
+//! that already finished unless [`CompletedTaskPolicy::Rerun`] is requested.
+//!
+//! The record also keeps the tasks of the last run with a content
+//! fingerprint. [`PlanRecord::reconcile`] diffs an edited plan against it:
+//! new and changed tasks run, unchanged completed tasks are kept, and tasks
+//! removed from the file are archived.
+
+use super::types::{TaskId, TaskSpec};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Tag prefix giving a task an explicit, title-independent key (e.g. `key:migrate-db`)
+pub const TASK_KEY_TAG_PREFIX: &str = "key:";
+
+/// Tag prefix recording a task's derived idempotency key in the session
+pub const IDEMPOTENCY_TAG_PREFIX: &str = "idempotency:";
+
+/// What to do with plan tasks that already completed in an earlier run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletedTaskPolicy {
+    /// Leave completed tasks alone
+    #[default]
+    Skip,
+    /// Run completed tasks again
+    Rerun,
+}
+
+/// The task's key within its plan: its `key:` tag, or else its title
+pub fn task_key(spec: &TaskSpec) -> String {
+    spec.metadata
+        .tags
+        .iter()
+        .filter_map(|tag| tag.strip_prefix(TASK_KEY_TAG_PREFIX))
+        .map(str::trim)
+        .find(|key| !key.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| spec.title.trim().to_lowercase())
+}
+
+/// Stable identity of a task key within a plan file
+pub fn idempotency_key(source: &Path, task_key: &str) -> Uuid {
+    let name = format!("{}#{}", source.display(), task_key);
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes())
+}
+
+/// Idempotency keys for a plan's tasks, in order
+///
+/// Repeated keys within one plan are numbered (`title#2`) so every task keeps
+/// a distinct identity.
+pub fn plan_idempotency_keys(source: &Path, specs: &[TaskSpec]) -> Vec<(String, Uuid)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    specs
+        .iter()
+        .map(|spec| {
+            let base = task_key(spec);
+            let count = seen.entry(base.clone()).or_default();
+            *count += 1;
+            let key = if *count == 1 {
+                base
+            } else {
+                format!("{}#{}", base, count)
+            };
+            let id = idempotency_key(source, &key);
+            (key, id)
+        })
+        .collect()
+}
+
+/// The idempotency key recorded on a task's tags, if any
+pub fn tagged_idempotency_key(tags: &[String]) -> Option<Uuid> {
+    tags.iter()
+        .filter_map(|tag| tag.strip_prefix(IDEMPOTENCY_TAG_PREFIX))
+        .find_map(|id| Uuid::parse_str(id.trim()).ok())
+}
+
+/// Content fingerprint of a task; changes whenever its title or description is edited
+pub fn task_fingerprint(spec: &TaskSpec) -> String {
+    let content = format!("{}\n{}", spec.title.trim(), spec.description.trim());
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, content.as_bytes())
+        .simple()
+        .to_string()
+}
+
+/// A plan task that finished successfully
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompletedPlanTask {
+    pub key: String,
+    pub title: String,
+    pub task_id: TaskId,
+    pub session_id: String,
+    pub completed_at: DateTime<Utc>,
+    /// Fingerprint of the task as it was when it completed
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+/// A task as it appeared in the last run of the plan
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlannedTask {
+    pub key: String,
+    pub title: String,
+    pub fingerprint: String,
+}
+
+/// A task that was removed from the plan file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedPlanTask {
+    pub id: Uuid,
+    pub key: String,
+    pub title: String,
+    pub archived_at: DateTime<Utc>,
+    pub completed: Option<CompletedPlanTask>,
+}
+
+/// How a task compares with the last run of its plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanTaskChange {
+    /// Not part of the last run
+    New,
+    /// Same key, edited content
+    Changed,
+    /// Same key and content
+    Unchanged,
+}
+
+/// A task of the current plan with its identity and change status
+#[derive(Debug, Clone)]
+pub struct ReconciledTask {
+    pub spec: TaskSpec,
+    pub key: String,
+    pub id: Uuid,
+    pub fingerprint: String,
+    pub change: PlanTaskChange,
+}
+
+/// Result of diffing a plan against its record
+#[derive(Debug, Clone, Default)]
+pub struct PlanReconciliation {
+    /// Current tasks, in plan order
+    pub tasks: Vec<ReconciledTask>,
+    /// Idempotency keys of tasks no longer in the plan
+    pub removed: Vec<Uuid>,
+}
+
+impl PlanReconciliation {
+    /// Number of tasks with the given change status
+    pub fn count(&self, change: PlanTaskChange) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.change == change)
+            .count()
+    }
+}
+
+/// Completed tasks of one plan file, keyed by idempotency key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanRecord {
+    pub source: PathBuf,
+    #[serde(default)]
+    pub completed: BTreeMap<Uuid, CompletedPlanTask>,
+    /// Tasks of the last run, used to detect edits
+    #[serde(default)]
+    pub tasks: BTreeMap<Uuid, PlannedTask>,
+    /// Tasks removed from the plan file
+    #[serde(default)]
+    pub archived: Vec<ArchivedPlanTask>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl PlanRecord {
+    /// Identity of a plan file, used to name its record
+    pub fn plan_id(source: &Path) -> Uuid {
+        Uuid::new_v5(
+            &Uuid::NAMESPACE_URL,
+            source.display().to_string().as_bytes(),
+        )
+    }
+
+    /// Load the record for a plan file, or start an empty one
+    pub fn load(workspace_root: &Path, source: &Path) -> Result<Self> {
+        let path =
+            crate::env::plan_record_file_path(workspace_root, &Self::plan_id(source).to_string());
+        let mut record = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str::<PlanRecord>(&content)
+                .with_context(|| format!("Failed to parse plan record {:?}", path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PlanRecord {
+                source: source.to_path_buf(),
+                completed: BTreeMap::new(),
+                tasks: BTreeMap::new(),
+                archived: Vec::new(),
+                path: PathBuf::new(),
+            },
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+        record.path = path;
+        Ok(record)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Completion recorded for an idempotency key
+    pub fn completed_task(&self, id: Uuid) -> Option<&CompletedPlanTask> {
+        self.completed.get(&id)
+    }
+
+    /// Diff the plan's current tasks against the last run
+    pub fn reconcile(&self, specs: Vec<TaskSpec>) -> PlanReconciliation {
+        let keys = plan_idempotency_keys(&self.source, &specs);
+        let tasks: Vec<ReconciledTask> = specs
+            .into_iter()
+            .zip(keys)
+            .map(|(spec, (key, id))| {
+                let fingerprint = task_fingerprint(&spec);
+                let previous = self
+                    .tasks
+                    .get(&id)
+                    .map(|task| Some(&task.fingerprint))
+                    .or_else(|| {
+                        self.completed
+                            .get(&id)
+                            .map(|task| task.fingerprint.as_ref())
+                    });
+                let change = match previous {
+                    None => PlanTaskChange::New,
+                    // Completed before fingerprints were recorded
+                    Some(None) => PlanTaskChange::Unchanged,
+                    Some(Some(previous)) if *previous == fingerprint => PlanTaskChange::Unchanged,
+                    Some(Some(_)) => PlanTaskChange::Changed,
+                };
+                ReconciledTask {
+                    spec,
+                    key,
+                    id,
+                    fingerprint,
+                    change,
+                }
+            })
+            .collect();
+
+        let current: std::collections::HashSet<Uuid> = tasks.iter().map(|task| task.id).collect();
+        let removed = self
+            .tasks
+            .keys()
+            .chain(self.completed.keys())
+            .filter(|id| !current.contains(id))
+            .copied()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        PlanReconciliation { tasks, removed }
+    }
+
+    /// Make a reconciliation the new baseline and persist the record
+    ///
+    /// Removed tasks move to the archive and completions of changed tasks are
+    /// dropped so they count as pending.
+    pub fn apply(&mut self, reconciliation: &PlanReconciliation) -> Result<()> {
+        let now = Utc::now();
+        for id in &reconciliation.removed {
+            let planned = self.tasks.remove(id);
+            let completed = self.completed.remove(id);
+            let (key, title) = match (&planned, &completed) {
+                (Some(task), _) => (task.key.clone(), task.title.clone()),
+                (None, Some(task)) => (task.key.clone(), task.title.clone()),
+                (None, None) => continue,
+            };
+            self.archived.push(ArchivedPlanTask {
+                id: *id,
+                key,
+                title,
+                archived_at: now,
+                completed,
+            });
+        }
+
+        for task in &reconciliation.tasks {
+            if task.change == PlanTaskChange::Changed {
+                self.completed.remove(&task.id);
+            }
+            self.tasks.insert(
+                task.id,
+                PlannedTask {
+                    key: task.key.clone(),
+                    title: task.spec.title.clone(),
+                    fingerprint: task.fingerprint.clone(),
+                },
+            );
+        }
+
+        self.save()
+    }
+
+    /// Record a completed task and persist the record
+    pub fn record_completed(&mut self, id: Uuid, task: CompletedPlanTask) -> Result<()> {
+        self.completed.insert(id, task);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create plans directory {:?}", parent))?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to replace {:?}", self.path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn spec(title: &str, tags: &[&str]) -> TaskSpec {
+        TaskSpec {
+            title: title.to_string(),
+            description: "description".to_string(),
+            dependencies: Vec::new(),
+            metadata: crate::task::TaskMetadata {
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                ..Default::default()
+            },
+            plan: None,
+        }
+    }
+
+    #[test]
+    fn test_idempotency_keys_are_stable() {
+        let source = Path::new("/work/plan.json");
+        let specs = vec![
+            spec("Add tests", &[]),
+            spec("Renamed title", &["key:migrate-db"]),
+            spec("Add tests", &[]),
+        ];
+
+        let keys = plan_idempotency_keys(source, &specs);
+        assert_eq!(keys[0].0, "add tests");
+        assert_eq!(keys[1].0, "migrate-db");
+        assert_eq!(keys[2].0, "add tests#2");
+        assert_eq!(keys, plan_idempotency_keys(source, &specs));
+        assert_ne!(
+            keys[0].1,
+            plan_idempotency_keys(Path::new("/work/other.json"), &specs)[0].1
+        );
+    }
+
+    #[test]
+    fn test_plan_record_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("plan.json");
+        let id = idempotency_key(&source, "add tests");
+
+        let mut record = PlanRecord::load(temp.path(), &source).unwrap();
+        assert!(record.completed_task(id).is_none());
+        record
+            .record_completed(
+                id,
+                CompletedPlanTask {
+                    key: "add tests".to_string(),
+                    title: "Add tests".to_string(),
+                    task_id: Uuid::new_v4(),
+                    session_id: "session".to_string(),
+                    completed_at: Utc::now(),
+                    fingerprint: None,
+                },
+            )
+            .unwrap();
+
+        let reloaded = PlanRecord::load(temp.path(), &source).unwrap();
+        assert_eq!(reloaded.source, source);
+        assert_eq!(reloaded.completed_task(id).unwrap().title, "Add tests");
+    }
+
+    #[test]
+    fn test_reconcile_edited_plan() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("plan.json");
+        let mut record = PlanRecord::load(temp.path(), &source).unwrap();
+
+        let first = record.reconcile(vec![
+            spec("Keep", &[]),
+            spec("Edit", &["key:edit"]),
+            spec("Remove", &[]),
+        ]);
+        assert_eq!(first.count(PlanTaskChange::New), 3);
+        record.apply(&first).unwrap();
+        for task in &first.tasks {
+            record
+                .record_completed(
+                    task.id,
+                    CompletedPlanTask {
+                        key: task.key.clone(),
+                        title: task.spec.title.clone(),
+                        task_id: Uuid::new_v4(),
+                        session_id: "session".to_string(),
+                        completed_at: Utc::now(),
+                        fingerprint: Some(task.fingerprint.clone()),
+                    },
+                )
+                .unwrap();
+        }
+
+        let mut edited = spec("Edit", &["key:edit"]);
+        edited.description = "new instructions".to_string();
+        let second = record.reconcile(vec![spec("Keep", &[]), edited, spec("Add", &[])]);
+        let changes: Vec<_> = second.tasks.iter().map(|task| task.change).collect();
+        assert_eq!(
+            changes,
+            vec![
+                PlanTaskChange::Unchanged,
+                PlanTaskChange::Changed,
+                PlanTaskChange::New
+            ]
+        );
+        assert_eq!(second.removed.len(), 1);
+
+        record.apply(&second).unwrap();
+        let reloaded = PlanRecord::load(temp.path(), &source).unwrap();
+        assert_eq!(reloaded.archived.len(), 1);
+        assert_eq!(reloaded.archived[0].title, "Remove");
+        assert!(reloaded.archived[0].completed.is_some());
+        assert!(reloaded.completed_task(second.tasks[0].id).is_some());
+        assert!(reloaded.completed_task(second.tasks[1].id).is_none());
+    }
+}
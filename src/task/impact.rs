@@ -0,0 +1,281 @@
+//! Test-impact analysis for batched verification.
+//!
+//! Maps the files a group of tasks changed to the tests likely to cover
+//! them, and narrows the verification command to those tests: cargo
+//! packages and a module filter for `cargo test`, `--findRelatedTests` for
+//! Jest and a `-k` expression for pytest. Anything the heuristics can't
+//! place, such as a lockfile or an unknown test runner, runs the full
+//! command.
+
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Extensions of files no test depends on
+const DOC_EXTENSIONS: &[&str] = &["md", "markdown", "rst", "txt", "adoc"];
+
+/// Extensions of the modules Jest can relate to tests
+const JS_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx", "mjs", "cjs", "mts", "cts"];
+
+/// Tests to run for a set of changed files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestImpact {
+    /// Run the command unchanged
+    Full,
+    /// No test depends on the changed files
+    Unaffected,
+    /// Run this narrowed command
+    Affected(String),
+}
+
+/// Narrow `command` to the tests affected by `changed`, paths relative to
+/// `root`
+pub fn affected_tests(command: &str, root: &Path, changed: &[PathBuf]) -> TestImpact {
+    let changed: Vec<&PathBuf> = changed.iter().filter(|path| !is_doc(path)).collect();
+    if changed.is_empty() {
+        return TestImpact::Unaffected;
+    }
+
+    let args = if command.contains("cargo test") {
+        cargo_args(root, &changed)
+    } else if command.contains("jest") {
+        jest_args(&changed)
+    } else if command.contains("pytest") {
+        pytest_args(&changed)
+    } else {
+        None
+    };
+    match args {
+        Some(args) => TestImpact::Affected(with_args(command, &args)),
+        None => TestImpact::Full,
+    }
+}
+
+fn is_doc(path: &Path) -> bool {
+    has_extension(path, DOC_EXTENSIONS)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext))
+}
+
+/// `-p` for each changed package, and a module filter when every change is
+/// in one module of one package
+fn cargo_args(root: &Path, changed: &[&PathBuf]) -> Option<String> {
+    let mut packages = BTreeSet::new();
+    let mut modules = BTreeSet::new();
+    for path in changed {
+        let (package_dir, name) = cargo_package(root, path)?;
+        packages.insert(name);
+        let within = path.strip_prefix(&package_dir).unwrap_or(path);
+        modules.insert(rust_module(within));
+    }
+
+    let mut args: Vec<String> = packages
+        .iter()
+        .map(|name| format!("-p {}", quote(name)))
+        .collect();
+    if packages.len() == 1
+        && let Some(module) = common_module(&modules)
+    {
+        args.push(quote(&module));
+    }
+    Some(args.join(" "))
+}
+
+/// Directory, relative to `root`, and name of the package `path` belongs to
+fn cargo_package(root: &Path, path: &Path) -> Option<(PathBuf, String)> {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if let Ok(manifest) = std::fs::read_to_string(root.join(current).join("Cargo.toml")) {
+            let manifest: toml::Value = toml::from_str(&manifest).ok()?;
+            // A virtual workspace manifest: the file is outside every package
+            let name = manifest.get("package")?.get("name")?.as_str()?;
+            return Some((current.to_path_buf(), name.to_string()));
+        }
+        dir = current.parent();
+    }
+    None
+}
+
+/// Module path of a source file under `src/`; `None` for the crate root,
+/// tests, examples and files that aren't Rust
+fn rust_module(path: &Path) -> Option<String> {
+    if !has_extension(path, &["rs"]) {
+        return None;
+    }
+    let mut parts: Vec<String> = path
+        .strip_prefix("src")
+        .ok()?
+        .with_extension("")
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str().map(String::from),
+            _ => None,
+        })
+        .collect();
+    if parts.last().is_some_and(|last| last == "mod") {
+        parts.pop();
+    }
+    if parts.len() == 1 && (parts[0] == "lib" || parts[0] == "main") {
+        parts.clear();
+    }
+    (!parts.is_empty()).then(|| parts.join("::"))
+}
+
+/// Longest module prefix shared by all changed files, if each is in a module
+fn common_module(modules: &BTreeSet<Option<String>>) -> Option<String> {
+    let mut paths = modules.iter();
+    let first = paths.next()?.as_ref()?;
+    let mut common: Vec<&str> = first.split("::").collect();
+    for module in paths {
+        let parts: Vec<&str> = module.as_ref()?.split("::").collect();
+        let shared = common
+            .iter()
+            .zip(&parts)
+            .take_while(|(a, b)| a == b)
+            .count();
+        common.truncate(shared);
+    }
+    (!common.is_empty()).then(|| common.join("::"))
+}
+
+/// `--findRelatedTests` with the changed modules
+fn jest_args(changed: &[&PathBuf]) -> Option<String> {
+    let mut files = Vec::new();
+    for path in changed {
+        if !has_extension(path, JS_EXTENSIONS) {
+            return None;
+        }
+        files.push(quote(&path.to_string_lossy()));
+    }
+    Some(format!("--findRelatedTests {}", files.join(" ")))
+}
+
+/// A `-k` expression selecting tests named after the changed modules
+fn pytest_args(changed: &[&PathBuf]) -> Option<String> {
+    let mut names = BTreeSet::new();
+    for path in changed {
+        let stem = path.file_stem()?.to_str()?;
+        // Fixtures and package setup can affect any test
+        if !has_extension(path, &["py"]) || stem == "conftest" || stem == "__init__" {
+            return None;
+        }
+        let name = stem
+            .strip_prefix("test_")
+            .or_else(|| stem.strip_suffix("_test"))
+            .unwrap_or(stem);
+        names.insert(name.to_string());
+    }
+    let expression = names.into_iter().collect::<Vec<_>>().join(" or ");
+    Some(format!("-k {}", quote(&expression)))
+}
+
+/// `command` with `args` added before any `--` separating runner arguments
+fn with_args(command: &str, args: &str) -> String {
+    match command.split_once(" -- ") {
+        Some((before, after)) => format!("{} {} -- {}", before, args, after),
+        None => format!("{} {}", command, args),
+    }
+}
+
+/// Quote `value` for `sh` when it has characters the shell would interpret
+fn quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_cargo_changes_map_to_packages_and_modules() {
+        let root = std::env::temp_dir().join(format!("aca-impact-{}", uuid::Uuid::new_v4()));
+        for (dir, name) in [("core", "app-core"), ("cli", "app-cli")] {
+            std::fs::create_dir_all(root.join(dir).join("src")).unwrap();
+            std::fs::write(
+                root.join(dir).join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\n", name),
+            )
+            .unwrap();
+        }
+        std::fs::write(root.join("Cargo.toml"), "[workspace]\n").unwrap();
+
+        let changed = paths(&["core/src/task/impact.rs", "core/src/task/mod.rs"]);
+        assert_eq!(
+            affected_tests("cargo test -- --nocapture", &root, &changed),
+            TestImpact::Affected("cargo test -p app-core task -- --nocapture".to_string())
+        );
+
+        let changed = paths(&["core/src/lib.rs", "cli/src/main.rs", "README.md"]);
+        assert_eq!(
+            affected_tests("cargo test", &root, &changed),
+            TestImpact::Affected("cargo test -p app-cli -p app-core".to_string())
+        );
+
+        // The workspace lockfile is in no package
+        let changed = paths(&["Cargo.lock", "core/src/lib.rs"]);
+        assert_eq!(
+            affected_tests("cargo test", &root, &changed),
+            TestImpact::Full
+        );
+    }
+
+    #[test]
+    fn test_jest_and_pytest_filters() {
+        let root = Path::new("/unused");
+        assert_eq!(
+            affected_tests(
+                "npx jest",
+                root,
+                &paths(&["src/api/client.ts", "src/my view.tsx"])
+            ),
+            TestImpact::Affected(
+                "npx jest --findRelatedTests src/api/client.ts 'src/my view.tsx'".to_string()
+            )
+        );
+        assert_eq!(
+            affected_tests("npx jest", root, &paths(&["package.json"])),
+            TestImpact::Full
+        );
+
+        assert_eq!(
+            affected_tests(
+                "pytest -q",
+                root,
+                &paths(&["app/models.py", "tests/test_views.py"])
+            ),
+            TestImpact::Affected("pytest -q -k 'models or views'".to_string())
+        );
+        assert_eq!(
+            affected_tests("pytest", root, &paths(&["tests/conftest.py"])),
+            TestImpact::Full
+        );
+    }
+
+    #[test]
+    fn test_docs_only_and_unknown_runners() {
+        let root = Path::new("/unused");
+        assert_eq!(
+            affected_tests("cargo test", root, &paths(&["docs/guide.md"])),
+            TestImpact::Unaffected
+        );
+        assert_eq!(
+            affected_tests("make check", root, &paths(&["src/lib.rs"])),
+            TestImpact::Full
+        );
+    }
+}
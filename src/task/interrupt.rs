@@ -0,0 +1,97 @@
+//! Interruption of a run with Ctrl-C or `SIGTERM`.
+//!
+//! The first Ctrl-C stops new tasks from starting and lets running ones
+//! finish; a second one, or `SIGTERM`, stops them too. Either way tasks left
+//! in progress are paused with [`INTERRUPTED_REASON`], plan tasks that never
+//! started are added to the tree, and a checkpoint is saved, so
+//! `aca resume` picks the run up where it stopped.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Pause reason of tasks that were running when the run was interrupted
+pub const INTERRUPTED_REASON: &str = "Interrupted";
+
+/// Signal that interrupted a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// `SIGINT`, usually Ctrl-C
+    Interrupt,
+    /// `SIGTERM`
+    Terminate,
+}
+
+/// Wait for the next `SIGINT` or `SIGTERM`
+///
+/// The signals are handled from the first poll on, so they no longer kill
+/// the process.
+#[cfg(unix)]
+pub async fn next_signal() -> std::io::Result<Signal> {
+    use tokio::signal::unix::{SignalKind, signal};
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    Ok(tokio::select! {
+        _ = interrupt.recv() => Signal::Interrupt,
+        _ = terminate.recv() => Signal::Terminate,
+    })
+}
+
+/// Wait for the next Ctrl-C
+#[cfg(not(unix))]
+pub async fn next_signal() -> std::io::Result<Signal> {
+    tokio::signal::ctrl_c().await?;
+    Ok(Signal::Interrupt)
+}
+
+/// Shared request to stop starting tasks
+#[derive(Debug, Clone, Default)]
+pub struct StopFlag(Arc<AtomicBool>);
+
+impl StopFlag {
+    pub fn request(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stop_flag_is_shared() {
+        let flag = StopFlag::default();
+        let loop_view = flag.clone();
+        assert!(!loop_view.is_requested());
+        flag.request();
+        assert!(loop_view.is_requested());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_next_signal_catches_sigterm() {
+        let mut signal = Box::pin(next_signal());
+        // Poll once so the handlers are installed before the signal is sent
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut signal)
+                .await
+                .is_err()
+        );
+        let status = tokio::process::Command::new("kill")
+            .arg("-TERM")
+            .arg(std::process::id().to_string())
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success());
+        let received = tokio::time::timeout(Duration::from_secs(5), signal)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, Signal::Terminate);
+    }
+}
@@ -0,0 +1,219 @@
+//! JUnit XML reports of task results.
+//!
+//! Each task becomes a `<testcase>`: completed tasks pass, failed tasks
+//! carry a `<failure>` built from their [`TaskError`], and tasks that did
+//! not run to completion are reported as skipped with their status. CI
+//! systems that understand JUnit XML can then show an aca run in their
+//! test UI.
+
+use super::types::{Task, TaskError, TaskStatus};
+use chrono::Duration;
+
+/// JUnit XML document with one test suite named `suite` holding `tasks`
+///
+/// Tasks are listed in creation order; subtasks use their parent's title
+/// as the test class.
+pub fn render_junit(suite: &str, tasks: &[Task]) -> String {
+    let mut tasks: Vec<&Task> = tasks.iter().collect();
+    tasks.sort_by_key(|task| task.created_at);
+
+    let failures = tasks
+        .iter()
+        .filter(|task| matches!(task.status, TaskStatus::Failed { .. }))
+        .count();
+    let skipped = tasks
+        .iter()
+        .filter(|task| {
+            !matches!(
+                task.status,
+                TaskStatus::Completed { .. } | TaskStatus::Failed { .. }
+            )
+        })
+        .count();
+    let total_time: f64 = tasks.iter().map(|task| seconds(task)).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" skipped=\"{skipped}\" time=\"{time:.3}\">\n",
+        name = escape(suite),
+        tests = tasks.len(),
+        time = total_time,
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" skipped=\"{skipped}\" time=\"{time:.3}\">\n",
+        name = escape(suite),
+        tests = tasks.len(),
+        time = total_time,
+    ));
+
+    for task in &tasks {
+        let classname = task
+            .parent_id
+            .and_then(|parent| tasks.iter().find(|other| other.id == parent))
+            .map(|parent| parent.title.as_str())
+            .unwrap_or(suite);
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape(&task.title),
+            escape(classname),
+            seconds(task)
+        ));
+
+        match &task.status {
+            TaskStatus::Completed { .. } => xml.push_str("/>\n"),
+            TaskStatus::Failed { error, .. } => {
+                xml.push_str(">\n");
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+                    escape(&error.to_string()),
+                    error.kind(),
+                    escape(&failure_details(error))
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+            status => {
+                xml.push_str(">\n");
+                xml.push_str(&format!(
+                    "      <skipped message=\"{}\"/>\n",
+                    escape(&status.name().replace('_', " "))
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Time spent running the task across all its attempts
+fn seconds(task: &Task) -> f64 {
+    task.execution_history
+        .iter()
+        .filter_map(|record| Some(record.completed_at? - record.started_at))
+        .fold(Duration::zero(), |total, elapsed| total + elapsed)
+        .num_milliseconds()
+        .max(0) as f64
+        / 1000.0
+}
+
+/// Full failure output; build errors include the command's stderr and stdout
+fn failure_details(error: &TaskError) -> String {
+    match error {
+        TaskError::BuildError { stdout, stderr, .. } => {
+            format!("{}\n\n{}\n{}", error, stderr.trim_end(), stdout.trim_end())
+                .trim_end()
+                .to_string()
+        }
+        _ => error.to_string(),
+    }
+}
+
+/// Escape text for XML attributes and element content
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::types::{ExecutionRecord, ResourceUsage, TaskResult, TaskSpec};
+    use chrono::Utc;
+
+    fn task(title: &str, status: TaskStatus) -> Task {
+        let mut task = Task::new(
+            TaskSpec {
+                title: title.to_string(),
+                ..Default::default()
+            },
+            None,
+        );
+        let started_at = Utc::now();
+        task.execution_history.push(ExecutionRecord {
+            started_at,
+            completed_at: Some(started_at + Duration::milliseconds(1500)),
+            status: status.clone(),
+            claude_session_id: None,
+            resources_used: ResourceUsage {
+                max_memory_mb: 0,
+                cpu_time_seconds: 0.0,
+                disk_io_mb: 0,
+                network_requests: 0,
+            },
+            files_modified: Vec::new(),
+            errors: Vec::new(),
+        });
+        task.status = status;
+        task
+    }
+
+    #[test]
+    fn test_task_statuses_map_to_testcases() {
+        let passed = task(
+            "Add <login> endpoint",
+            TaskStatus::Completed {
+                completed_at: Utc::now(),
+                result: TaskResult::Success {
+                    output: serde_json::Value::Null,
+                    files_created: Vec::new(),
+                    files_modified: Vec::new(),
+                    build_artifacts: Vec::new(),
+                },
+            },
+        );
+        let failed = task(
+            "Run tests",
+            TaskStatus::Failed {
+                failed_at: Utc::now(),
+                error: TaskError::BuildError {
+                    exit_code: 101,
+                    stdout: "test auth ... FAILED".to_string(),
+                    stderr: "error: test failed".to_string(),
+                    affected_files: Vec::new(),
+                },
+                retry_count: 0,
+            },
+        );
+        let mut pending = task("Deploy", TaskStatus::Pending);
+        pending.execution_history.clear();
+
+        let xml = render_junit("aca", &[passed, failed, pending]);
+        assert!(xml.contains(
+            r#"<testsuite name="aca" tests="3" failures="1" errors="0" skipped="1" time="3.000">"#
+        ));
+        assert!(xml.contains(
+            r#"<testcase name="Add &lt;login&gt; endpoint" classname="aca" time="1.500"/>"#
+        ));
+        assert!(xml.contains(
+            r#"<failure message="Build failed with exit code 101: error: test failed" type="build_error">"#
+        ));
+        assert!(xml.contains("test auth ... FAILED</failure>"));
+        assert!(xml.contains(r#"<testcase name="Deploy" classname="aca" time="0.000">"#));
+        assert!(xml.contains(r#"<skipped message="pending"/>"#));
+    }
+
+    #[test]
+    fn test_subtasks_use_parent_as_class_and_text_is_escaped() {
+        let parent = task("Auth \"v2\"", TaskStatus::Pending);
+        let mut child = task("Hash passwords", TaskStatus::Pending);
+        child.parent_id = Some(parent.id);
+
+        let xml = render_junit("run & report", &[parent, child]);
+        assert!(xml.contains(r#"<testsuites name="run &amp; report""#));
+        assert!(xml.contains(r#"name="Hash passwords" classname="Auth &quot;v2&quot;""#));
+        assert_eq!(escape("a\u{1b}b\tc"), "ab\tc");
+    }
+}
@@ -72,6 +72,7 @@
 //!         cleanup_after_hours: 24,
 //!         enable_task_metrics: true,
 //!         max_concurrent_tasks: 4,
+//!         ..Default::default()
 //!     };
 //!
 //!     let task_manager = TaskManager::new(config);
@@ -95,7 +96,12 @@
 //!                 environment_vars: HashMap::new(),
 //!                 claude_context_keys: vec!["project_structure".to_string()],
 //!             },
+//!             requirements: Default::default(),
+//!             provider_preference: Default::default(),
+//!             tool_permissions: Default::default(),
+//!             wait_for: Default::default(),
 //!         },
+//!         plan: None,
 //!     };
 //!
 //!     // Create and execute the task
@@ -214,13 +220,20 @@
 //! - Event handlers are executed safely without blocking main operations
 //! - Statistics and queries are lock-free where possible
 
+use crate::policy::{PolicyEffect, PolicyFacts, PolicyGate, PolicyStage};
+use crate::task::graph::{GraphFormat, render_graph};
+use crate::task::retry::{RETRY_REASON, RetryBackoff, RetryClass};
+use crate::task::rollup::RollupSummary;
 use crate::task::scheduler::*;
+use crate::task::suspend::TaskSuspender;
 use crate::task::tree::*;
 use crate::task::types::*;
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 
@@ -263,6 +276,11 @@ pub struct TaskManager {
     scheduler: Arc<Mutex<TaskScheduler>>,
     config: TaskManagerConfig,
     event_handlers: Vec<Box<dyn TaskEventHandler + Send + Sync>>,
+    suspender: std::sync::RwLock<Option<Arc<dyn TaskSuspender>>>,
+    policy: std::sync::RwLock<Option<Arc<PolicyGate>>>,
+    /// Block reasons whose owners lift expired blocks themselves
+    kept_block_reasons: std::sync::RwLock<HashSet<String>>,
+    maintenance: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 /// Configuration settings for the task manager behavior and policies.
@@ -291,6 +309,7 @@ pub struct TaskManager {
 ///     cleanup_after_hours: 24,
 ///     enable_task_metrics: true,
 ///     max_concurrent_tasks: 8, // Higher throughput
+///     ..Default::default()
 /// };
 /// ```
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -299,8 +318,11 @@ pub struct TaskManagerConfig {
     pub auto_retry_failed_tasks: bool,
     /// Maximum number of retry attempts for failed tasks
     pub max_retry_attempts: u32,
-    /// Delay in minutes between retry attempts
+    /// Delay in minutes before the first retry of a failed task
     pub retry_delay_minutes: u32,
+    /// How retry delays grow, and how they depend on the error
+    #[serde(default)]
+    pub retry_backoff: RetryBackoff,
     /// Whether to automatically cleanup completed tasks
     pub auto_cleanup_completed: bool,
     /// Hours after which completed tasks are automatically cleaned up
@@ -309,6 +331,32 @@ pub struct TaskManagerConfig {
     pub enable_task_metrics: bool,
     /// Maximum number of tasks that can execute concurrently
     pub max_concurrent_tasks: u32,
+    /// Background maintenance of the task tree
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+}
+
+/// Settings of the task manager's maintenance loop
+///
+/// Each pass unblocks tasks whose `retry_after` has passed, cleans up
+/// completed tasks (with `auto_cleanup_completed`), optionally merges
+/// similar tasks, and reports changed tree statistics.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// Seconds between two passes
+    pub interval_secs: u64,
+    /// Merge similar tasks on each pass
+    pub deduplicate: bool,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5,
+            deduplicate: false,
+        }
+    }
 }
 
 /// Events that can occur during task management operations.
@@ -487,6 +535,7 @@ impl TaskManager {
     ///     cleanup_after_hours: 24,
     ///     enable_task_metrics: true,
     ///     max_concurrent_tasks: 4,
+    ///     ..Default::default()
     /// };
     ///
     /// let task_manager = TaskManager::new(config);
@@ -502,9 +551,127 @@ impl TaskManager {
             scheduler: Arc::new(Mutex::new(TaskScheduler::new(scheduler_config))),
             config,
             event_handlers: Vec::new(),
+            suspender: std::sync::RwLock::new(None),
+            policy: std::sync::RwLock::new(None),
+            kept_block_reasons: std::sync::RwLock::new(HashSet::new()),
+            maintenance: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Spawn the maintenance loop (no-op when already running)
+    ///
+    /// The loop ends with the task manager. See [`MaintenanceConfig`].
+    pub fn start_maintenance_loop(self: &Arc<Self>) {
+        let mut maintenance = self.maintenance.lock().unwrap();
+        if maintenance.is_some() {
+            return;
+        }
+
+        let manager: Weak<Self> = Arc::downgrade(self);
+        let interval = Duration::from_secs(self.config.maintenance.interval_secs.max(1));
+        debug!("Maintaining the task tree every {}s", interval.as_secs());
+        *maintenance = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut statistics = None;
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                if let Err(e) = manager.run_maintenance(&mut statistics).await {
+                    warn!("Task tree maintenance failed: {}", e);
+                }
+            }
+        }));
+    }
+
+    /// Stop the maintenance loop
+    pub fn stop_maintenance_loop(&self) {
+        if let Some(maintenance) = self.maintenance.lock().unwrap().take() {
+            maintenance.abort();
         }
     }
 
+    /// One maintenance pass
+    ///
+    /// Statistics are reported when they differ from `last_statistics`,
+    /// which is updated.
+    async fn run_maintenance(
+        &self,
+        last_statistics: &mut Option<TaskTreeStatistics>,
+    ) -> Result<()> {
+        self.unblock_expired().await?;
+        self.cleanup_completed_tasks().await?;
+        if self.config.maintenance.deduplicate {
+            self.deduplicate_tasks().await?;
+        }
+
+        let statistics = self.get_statistics().await?;
+        if last_statistics.as_ref() != Some(&statistics) {
+            *last_statistics = Some(statistics.clone());
+            self.emit_event(TaskEvent::TreeStatisticsUpdated { statistics })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Leave expired blocks with `reason` for their owner to lift
+    pub fn keep_blocked(&self, reason: &str) {
+        self.kept_block_reasons
+            .write()
+            .unwrap()
+            .insert(reason.to_string());
+    }
+
+    /// Set the hook used to freeze and thaw running tasks' subprocesses
+    pub fn set_suspender(&self, suspender: Arc<dyn TaskSuspender>) {
+        *self.suspender.write().unwrap() = Some(suspender);
+    }
+
+    /// Set the policy rules new tasks are checked against
+    pub fn set_policy(&self, policy: Arc<PolicyGate>) {
+        *self.policy.write().unwrap() = Some(policy);
+    }
+
+    /// Check specs against the policy before any are created
+    ///
+    /// Fails if a rule denies any spec; otherwise returns, per spec, the
+    /// reason it must wait for approval, if any.
+    fn check_policy(&self, specs: &[TaskSpec]) -> Result<Vec<Option<String>>> {
+        let Some(policy) = self.policy.read().unwrap().clone() else {
+            return Ok(vec![None; specs.len()]);
+        };
+        specs
+            .iter()
+            .map(|spec| {
+                let facts = PolicyFacts::for_task(&spec.title, &spec.description, &spec.metadata);
+                match policy.check(PolicyStage::Create, None, &facts) {
+                    Ok(()) => Ok(None),
+                    Err(violation) if violation.effect == PolicyEffect::Deny => Err(anyhow!(
+                        "Cannot create task '{}': {}",
+                        spec.title,
+                        violation
+                    )),
+                    Err(violation) => Ok(Some(violation.to_string())),
+                }
+            })
+            .collect()
+    }
+
+    /// Block newly created tasks a policy holds for approval
+    async fn hold_for_approval(
+        &self,
+        task_ids: &[TaskId],
+        approvals: Vec<Option<String>>,
+    ) -> Result<()> {
+        for (task_id, reason) in task_ids.iter().zip(approvals) {
+            if let Some(reason) = reason {
+                self.block_task(*task_id, reason, None).await?;
+            }
+        }
+        Ok(())
+    }
+
     /// Initialize the task manager with a batch of task specifications.
     ///
     /// Creates multiple root-level tasks from the provided specifications.
@@ -537,6 +704,7 @@ impl TaskManager {
     /// }
     /// ```
     pub async fn initialize_with_specs(&self, specs: Vec<TaskSpec>) -> Result<Vec<TaskId>> {
+        let approvals = self.check_policy(&specs)?;
         let mut tree = self.tree.write().await;
         let mut created_tasks = Vec::new();
 
@@ -550,6 +718,8 @@ impl TaskManager {
             })
             .await?;
         }
+        drop(tree);
+        self.hold_for_approval(&created_tasks, approvals).await?;
 
         info!(
             "Initialized task manager with {} root tasks",
@@ -591,11 +761,14 @@ impl TaskManager {
     /// }
     /// ```
     pub async fn create_task(&self, spec: TaskSpec, parent_id: Option<TaskId>) -> Result<TaskId> {
+        let approvals = self.check_policy(std::slice::from_ref(&spec))?;
         let mut tree = self.tree.write().await;
         let task_id = tree.create_task_from_spec(spec, parent_id)?;
+        drop(tree);
 
         self.emit_event(TaskEvent::TaskCreated { task_id, parent_id })
             .await?;
+        self.hold_for_approval(&[task_id], approvals).await?;
 
         debug!("Created task {} with parent {:?}", task_id, parent_id);
         Ok(task_id)
@@ -607,14 +780,17 @@ impl TaskManager {
         parent_id: TaskId,
         subtask_specs: Vec<TaskSpec>,
     ) -> Result<Vec<TaskId>> {
+        let approvals = self.check_policy(&subtask_specs)?;
         let mut tree = self.tree.write().await;
         let subtask_ids = tree.create_subtasks(parent_id, subtask_specs).await?;
+        drop(tree);
 
         self.emit_event(TaskEvent::SubtasksCreated {
             parent_id,
             subtask_ids: subtask_ids.clone(),
         })
         .await?;
+        self.hold_for_approval(&subtask_ids, approvals).await?;
 
         info!(
             "Created {} subtasks for parent {}",
@@ -668,28 +844,34 @@ impl TaskManager {
     }
 
     /// Mark task as failed with error
+    ///
+    /// With retries left, the failed attempt is kept in the task's execution
+    /// history and the task is retried after its backoff delay.
     pub async fn fail_task(&self, task_id: TaskId, error: TaskError) -> Result<()> {
-        let mut retry_count = 0;
-
-        // Get current retry count
-        {
+        // Failed attempts that were already retried
+        let (retries, started_at) = {
             let tree = self.tree.read().await;
             let task = tree.get_task(task_id)?;
-            if let TaskStatus::Failed {
-                retry_count: count, ..
-            } = task.status
-            {
-                retry_count = count;
-            }
-        }
+            let retries = task
+                .execution_history
+                .iter()
+                .filter(|record| matches!(record.status, TaskStatus::Failed { .. }))
+                .count() as u32;
+            let started_at = match task.status {
+                TaskStatus::InProgress { started_at, .. } => started_at,
+                _ => Utc::now(),
+            };
+            (retries, started_at)
+        };
 
         let failed_status = TaskStatus::Failed {
             failed_at: Utc::now(),
             error: error.clone(),
-            retry_count: retry_count + 1,
+            retry_count: retries + 1,
         };
 
-        self.update_task_status(task_id, failed_status).await?;
+        self.update_task_status(task_id, failed_status.clone())
+            .await?;
 
         self.emit_event(TaskEvent::TaskFailed {
             task_id,
@@ -698,11 +880,24 @@ impl TaskManager {
         .await?;
 
         // Check if we should auto-retry
-        if self.config.auto_retry_failed_tasks && retry_count < self.config.max_retry_attempts {
-            self.schedule_retry(task_id).await?;
+        if self.config.auto_retry_failed_tasks && retries < self.config.max_retry_attempts {
+            {
+                let mut tree = self.tree.write().await;
+                tree.get_task_mut(task_id)?
+                    .add_execution_record(ExecutionRecord {
+                        started_at,
+                        completed_at: Some(Utc::now()),
+                        status: failed_status,
+                        claude_session_id: None,
+                        resources_used: ResourceUsage::default(),
+                        files_modified: Vec::new(),
+                        errors: vec![error.to_string()],
+                    });
+            }
+            self.schedule_retry(task_id, &error, retries).await?;
         }
 
-        warn!("Failed task {} (attempt {})", task_id, retry_count + 1);
+        warn!("Failed task {} (attempt {})", task_id, retries + 1);
         Ok(())
     }
 
@@ -710,9 +905,10 @@ impl TaskManager {
     pub async fn block_task(
         &self,
         task_id: TaskId,
-        reason: String,
+        reason: impl Into<BlockReason>,
         retry_after: Option<DateTime<Utc>>,
     ) -> Result<()> {
+        let reason = reason.into();
         let blocked_status = TaskStatus::Blocked {
             reason: reason.clone(),
             blocked_at: Utc::now(),
@@ -725,6 +921,67 @@ impl TaskManager {
         Ok(())
     }
 
+    /// Block a task whose cost budget is spent
+    pub async fn block_over_budget(
+        &self,
+        task_id: TaskId,
+        exceeded: &crate::llm::BudgetExceeded,
+    ) -> Result<()> {
+        self.block_task(task_id, exceeded, None).await
+    }
+
+    /// Pause a running task, suspending its subprocess if one is registered
+    pub async fn pause_task(&self, task_id: TaskId, reason: String) -> Result<()> {
+        let task = self.get_task(task_id).await?;
+        if !task.is_running() {
+            return Err(anyhow!(
+                "Task {} is not running and cannot be paused",
+                task_id
+            ));
+        }
+
+        let suspender = self.suspender.read().unwrap().clone();
+        if let Some(suspender) = suspender
+            && !suspender.suspend(task_id).await?
+        {
+            debug!("No subprocess to suspend for task {}", task_id);
+        }
+
+        let paused_status = TaskStatus::Paused {
+            reason: reason.clone(),
+            paused_at: Utc::now(),
+        };
+        self.update_task_status(task_id, paused_status).await?;
+
+        info!("Paused task {}: {}", task_id, reason);
+        Ok(())
+    }
+
+    /// Resume a paused task, continuing its suspended subprocess
+    pub async fn resume_task(&self, task_id: TaskId) -> Result<()> {
+        let task = self.get_task(task_id).await?;
+        if !task.is_paused() {
+            return Err(anyhow!("Task {} is not paused", task_id));
+        }
+
+        let suspender = self.suspender.read().unwrap().clone();
+        if let Some(suspender) = suspender {
+            suspender.resume(task_id).await?;
+        }
+
+        self.update_task_status(
+            task_id,
+            TaskStatus::InProgress {
+                started_at: Utc::now(),
+                estimated_completion: None,
+            },
+        )
+        .await?;
+
+        info!("Resumed task {}", task_id);
+        Ok(())
+    }
+
     /// Select next task for execution using scheduler
     pub async fn select_next_task(&self) -> Result<Option<TaskSelection>> {
         let tree = self.tree.read().await;
@@ -814,6 +1071,12 @@ impl TaskManager {
                     tree.remove_task(duplicate_id).await?;
                     merged_tasks.push(duplicate_id);
                 }
+
+                self.emit_event(TaskEvent::TasksDeduped {
+                    primary_id: primary,
+                    merged_ids: duplicates.to_vec(),
+                })
+                .await?;
             }
         }
 
@@ -824,22 +1087,59 @@ impl TaskManager {
         Ok(merged_tasks)
     }
 
-    /// Schedule a retry for a failed task
-    async fn schedule_retry(&self, task_id: TaskId) -> Result<()> {
-        let retry_time =
-            Utc::now() + chrono::Duration::minutes(self.config.retry_delay_minutes as i64);
+    /// Schedule a retry for a failed task after its backoff delay
+    ///
+    /// Without a delay the task is pending again right away.
+    async fn schedule_retry(&self, task_id: TaskId, error: &TaskError, retries: u32) -> Result<()> {
+        let delay = self.config.retry_delay(error, retries);
+        if delay.is_zero() {
+            self.update_task_status(task_id, TaskStatus::Pending)
+                .await?;
+            debug!("Retrying task {} immediately", task_id);
+            return Ok(());
+        }
 
-        self.block_task(
-            task_id,
-            "Scheduled for automatic retry".to_string(),
-            Some(retry_time),
-        )
-        .await?;
+        let retry_time = Utc::now() + chrono::Duration::from_std(delay)?;
+        self.block_task(task_id, RETRY_REASON.to_string(), Some(retry_time))
+            .await?;
 
         debug!("Scheduled retry for task {} at {}", task_id, retry_time);
         Ok(())
     }
 
+    /// Make tasks whose `retry_after` has passed pending again
+    ///
+    /// Blocks with a reason passed to [`keep_blocked`](Self::keep_blocked)
+    /// stay.
+    pub async fn unblock_expired(&self) -> Result<Vec<TaskId>> {
+        let now = Utc::now();
+        let expired: Vec<TaskId> = {
+            let kept = self.kept_block_reasons.read().unwrap().clone();
+            let tree = self.tree.read().await;
+            tree.tasks
+                .values()
+                .filter(|task| {
+                    matches!(
+                        &task.status,
+                        TaskStatus::Blocked {
+                            reason,
+                            retry_after: Some(retry_after),
+                            ..
+                        } if *retry_after <= now && !reason.text().is_some_and(|reason| kept.contains(reason))
+                    )
+                })
+                .map(|task| task.id)
+                .collect()
+        };
+
+        for &task_id in &expired {
+            self.update_task_status(task_id, TaskStatus::Pending)
+                .await?;
+            debug!("Unblocked task {}", task_id);
+        }
+        Ok(expired)
+    }
+
     /// Check if parent task should be marked as completed
     async fn check_parent_completion(&self, completed_task_id: TaskId) -> Result<()> {
         let parent_id = {
@@ -863,15 +1163,10 @@ impl TaskManager {
             };
 
             if all_children_completed {
-                let success_result = TaskResult::Success {
-                    output: serde_json::json!({"message": "All subtasks completed"}),
-                    files_created: Vec::new(),
-                    files_modified: Vec::new(),
-                    build_artifacts: Vec::new(),
-                };
+                let rollup = self.rollup(parent_id).await?.into_result();
 
                 // Use Box::pin to avoid recursion issue
-                Box::pin(self.complete_task(parent_id, success_result)).await?;
+                Box::pin(self.complete_task(parent_id, rollup)).await?;
                 info!(
                     "Auto-completed parent task {} (all children finished)",
                     parent_id
@@ -882,6 +1177,18 @@ impl TaskManager {
         Ok(())
     }
 
+    /// Roll-up summary of a task's children as they stand
+    pub async fn rollup(&self, parent_id: TaskId) -> Result<RollupSummary> {
+        let tree = self.tree.read().await;
+        let children = tree
+            .get_task(parent_id)?
+            .children
+            .iter()
+            .map(|&child_id| tree.get_task(child_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RollupSummary::build(&children))
+    }
+
     /// Add event handler
     pub fn add_event_handler(&mut self, handler: Box<dyn TaskEventHandler + Send + Sync>) {
         self.event_handlers.push(handler);
@@ -943,6 +1250,13 @@ impl TaskManager {
         serde_json::to_string_pretty(&*tree).map_err(|e| anyhow!("Serialization error: {}", e))
     }
 
+    /// Diagram of the task tree with statuses and dependency edges
+    pub async fn export_graph(&self, format: GraphFormat) -> Result<String> {
+        let tree = self.tree.read().await;
+        let tasks: Vec<Task> = tree.tasks.values().cloned().collect();
+        Ok(render_graph(&tasks, format))
+    }
+
     /// Import task tree from JSON
     pub async fn import_from_json(&self, json_data: &str) -> Result<()> {
         let imported_tree: TaskTree =
@@ -995,16 +1309,30 @@ impl TaskManager {
     }
 }
 
+impl TaskManagerConfig {
+    /// Delay before retrying a task that failed with `error` after
+    /// `retries` earlier retries
+    pub fn retry_delay(&self, error: &TaskError, retries: u32) -> Duration {
+        self.retry_backoff.delay(
+            RetryClass::of(error),
+            Duration::from_secs(u64::from(self.retry_delay_minutes) * 60),
+            retries,
+        )
+    }
+}
+
 impl Default for TaskManagerConfig {
     fn default() -> Self {
         Self {
             auto_retry_failed_tasks: true,
             max_retry_attempts: 3,
             retry_delay_minutes: 5,
+            retry_backoff: RetryBackoff::default(),
             auto_cleanup_completed: false,
             cleanup_after_hours: 24,
             enable_task_metrics: true,
             max_concurrent_tasks: 3,
+            maintenance: MaintenanceConfig::default(),
         }
     }
 }
@@ -1039,6 +1367,7 @@ impl Default for TaskManagerConfig {
 ///         cleanup_after_hours: 48,
 ///         enable_task_metrics: true,
 ///         max_concurrent_tasks: 6,
+///         ..Default::default()
 ///     };
 ///
 ///     // 2. Create and configure task manager
@@ -101,12 +101,199 @@ pub mod types;
 /// task lists and structured configurations into a common execution model.
 pub mod execution_plan;
 
+/// Stable identities for tasks loaded from plan files.
+///
+/// Lets re-running the same plan file skip tasks that already completed.
+pub mod idempotency;
+
+/// Time-window and quota constraints on task execution.
+///
+/// Pauses unattended runs outside allowed hours or when the provider's
+/// daily quota runs low.
+pub mod window;
+
+/// Cooperative preemption of background tasks by critical ones.
+///
+/// Hands out concurrency slots and asks running background work to pause
+/// when a critical task is waiting.
+pub mod preemption;
+
+/// Issue reproduction workflow.
+///
+/// Task specs, gates and test-command parsing for turning a bug report
+/// into a failing test, a fix and a confirmation.
+pub mod bugfix;
+
+/// Behavior lock for refactoring tasks.
+///
+/// Baseline and after snapshots of test outcomes and golden command output,
+/// and the comparison that blocks a refactor which changed behavior.
+pub mod refactor;
+
+/// Review and scan findings.
+///
+/// Categorized findings with workspace locations and their SARIF export
+/// for code scanning tools.
+pub mod findings;
+
+/// JUnit XML reports of task results.
+///
+/// Renders tasks as test cases so CI systems show aca runs in their test UI.
+pub mod junit;
+
+/// Structured questions from the model to the user.
+///
+/// Parses question blocks in model responses and routes them to a
+/// terminal prompt or an API-facing broker for an answer.
+pub mod question;
+
+/// Interruption of a run by Ctrl-C or SIGTERM.
+///
+/// Signal handling and the stop request that keeps new tasks from starting.
+pub mod interrupt;
+
+/// Suspension of running tasks' subprocesses.
+///
+/// Freezes provider processes (and the session container in sandbox mode)
+/// behind paused tasks so they can continue where they stopped.
+pub mod suspend;
+
+/// Watch mode verification loop.
+///
+/// Workspace change detection, fix-task throttling and the fix task built
+/// from a failed verification command.
+pub mod watch;
+
+/// Plan duration simulation for dry runs.
+///
+/// Estimates when an execution plan finishes under the provider rate limits,
+/// execution windows and daily quota, and whether it meets a deadline.
+pub mod simulation;
+
+/// Estimate calibration against actual durations.
+///
+/// Records estimated vs actual task durations and derives a correction factor
+/// per complexity level for dry-run timelines and `aca stats`.
+pub mod calibration;
+
+/// Explanations of why a task is waiting.
+///
+/// Combines a task's status, dependencies, run-wide limits and scheduling
+/// score for `aca task why`.
+pub mod explain;
+
+/// Mid-run priority overrides.
+///
+/// Reads `.aca/priorities.toml`, which lets a human bump or hold plan tasks
+/// while a run is in flight.
+pub mod priorities;
+
+/// Tasks that expand into sub-plan files.
+///
+/// Resolves a task's `plan` file, guards against plans that include
+/// themselves and defines the loader the engine parses them with.
+pub mod subplan;
+
+/// Self-reported confidence and risk flags.
+///
+/// Parses the summary block the model ends a task with and decides whether
+/// the task completes, gets a review pass or waits for approval.
+pub mod confidence;
+
+/// System package requirements for setup commands.
+///
+/// Declares the packages a plan needs and builds the scripts that find the
+/// package manager, list what is missing and install it.
+pub mod packages;
+
+/// Batched verification of plan tasks.
+///
+/// Verifies the workspace once per group of tasks and bisects a failing
+/// group to find the task that broke it.
+pub mod verification;
+
+/// Test-impact analysis.
+///
+/// Maps changed files to the tests that cover them so batched verification
+/// can run only the affected tests.
+pub mod impact;
+
+/// Mutation-style spot checks.
+///
+/// Reverts a critical task's implementation while keeping its tests to
+/// confirm the tests catch the change.
+pub mod spot_check;
+
+/// Roll-up summaries for parent tasks.
+///
+/// Aggregates the files, cost, run time and status of a parent's children
+/// into the result the parent completes with.
+pub mod rollup;
+
+/// Conditions on external state a task waits for.
+///
+/// URLs, files and commands polled with backoff before a task runs.
+pub mod wait;
+
+/// Backoff between automatic retries of failed tasks.
+///
+/// Error classes and the exponential, jittered delays before each retry.
+pub mod retry;
+
+/// Diagrams of the task tree.
+///
+/// Graphviz DOT and Mermaid renderings of tasks, statuses and dependencies.
+pub mod graph;
+
+/// Differences between execution plans.
+///
+/// Added, removed and modified tasks and changed dependencies for
+/// `aca plan diff`.
+pub mod plan_diff;
+
+/// Single-task dev loop.
+///
+/// The task spec and change summary of `aca quick`.
+pub mod quick;
+
 #[cfg(test)]
 mod tests;
 
+pub use bugfix::*;
+pub use calibration::*;
+pub use confidence::*;
 pub use execution::*;
 pub use execution_plan::*;
+pub use explain::{TaskExplanation, WaitConditions, WaitReason};
+pub use findings::{Finding, FindingSeverity};
+pub use graph::{GraphFormat, render_graph};
+pub use idempotency::*;
+pub use impact::{TestImpact, affected_tests};
+pub use interrupt::{INTERRUPTED_REASON, Signal, StopFlag};
+pub use junit::render_junit;
 pub use manager::*;
+pub use packages::{PackageCheck, PackageManager, SystemPackages};
+pub use plan_diff::{PlanDiff, TaskChange};
+pub use preemption::*;
+pub use priorities::*;
+pub use question::*;
+pub use quick::{
+    MAX_QUICK_OUTPUT_CHARS, QUICK_TAG, QuickReport, QuickVerification, quick_task_spec,
+};
+pub use refactor::*;
+pub use retry::{RETRY_REASON, RetryBackoff, RetryClass};
+pub use rollup::{ChildOutcome, RollupConfig, RollupSummary};
 pub use scheduler::*;
+pub use simulation::*;
+pub use spot_check::{SpotCheck, SpotCheckConfig, SpotCheckOutcome, SpotCheckReport};
+pub use subplan::*;
+pub use suspend::*;
 pub use tree::*;
 pub use types::*;
+pub use verification::{
+    GroupTask, VERIFY_POINT_TAG, VerificationConfig, VerificationGroup, group_fix_task_spec,
+    is_verify_point,
+};
+pub use wait::{WAITING_REASON_PREFIX, WaitCondition, WaitConfig};
+pub use watch::*;
+pub use window::*;
@@ -0,0 +1,226 @@
+//! System package requirements for setup commands.
+//!
+//! A setup command can declare the system packages a plan needs instead of
+//! a hand-written install snippet. One probe finds the package manager and
+//! the packages that are missing; inside a container those are installed,
+//! while on the host the command fails with the install command to run by
+//! hand. Packages already present are never reinstalled.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// Package managers a declaration can be satisfied with, in probe order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageManager {
+    Apt,
+    Apk,
+    Dnf,
+    Brew,
+}
+
+impl PackageManager {
+    pub const ALL: [PackageManager; 4] = [Self::Apt, Self::Apk, Self::Dnf, Self::Brew];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Apt => "apt",
+            Self::Apk => "apk",
+            Self::Dnf => "dnf",
+            Self::Brew => "brew",
+        }
+    }
+
+    /// Binary whose presence identifies the package manager
+    pub fn binary(self) -> &'static str {
+        match self {
+            Self::Apt => "apt-get",
+            Self::Apk => "apk",
+            Self::Dnf => "dnf",
+            Self::Brew => "brew",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|manager| manager.name() == name)
+    }
+
+    /// Shell test succeeding when `package` is installed
+    fn installed_check(self, package: &str) -> String {
+        let package = quote(package);
+        match self {
+            Self::Apt => format!("dpkg -s {} >/dev/null 2>&1", package),
+            Self::Apk => format!("apk info -e {} >/dev/null 2>&1", package),
+            Self::Dnf => format!("rpm -q {} >/dev/null 2>&1", package),
+            Self::Brew => format!("brew list --versions {} >/dev/null 2>&1", package),
+        }
+    }
+
+    /// Non-interactive command installing `packages`
+    pub fn install_command(self, packages: &[String]) -> String {
+        let packages = packages
+            .iter()
+            .map(|package| quote(package))
+            .collect::<Vec<_>>()
+            .join(" ");
+        match self {
+            Self::Apt => format!(
+                "apt-get update -qq && DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends {}",
+                packages
+            ),
+            Self::Apk => format!("apk add --no-cache {}", packages),
+            Self::Dnf => format!("dnf install -y {}", packages),
+            Self::Brew => format!("brew install {}", packages),
+        }
+    }
+
+    /// Install command for a person to run, with `sudo` where it is needed
+    pub fn install_hint(self, packages: &[String]) -> String {
+        match self {
+            Self::Brew => self.install_command(packages),
+            _ => format!("sudo sh -c {}", quote(&self.install_command(packages))),
+        }
+    }
+}
+
+/// System packages a setup command makes sure are installed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SystemPackages {
+    /// Package names, used with every package manager
+    pub packages: Vec<String>,
+    /// Names to use instead with a particular package manager, e.g.
+    /// `build-essential` on apt but `build-base` on apk
+    pub overrides: BTreeMap<PackageManager, Vec<String>>,
+}
+
+impl SystemPackages {
+    pub fn new(packages: &[&str]) -> Self {
+        Self {
+            packages: packages.iter().map(|package| package.to_string()).collect(),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Use `packages` instead when installing with `manager`
+    pub fn with_override(mut self, manager: PackageManager, packages: &[&str]) -> Self {
+        self.overrides.insert(
+            manager,
+            packages.iter().map(|package| package.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Package names for `manager`
+    pub fn for_manager(&self, manager: PackageManager) -> &[String] {
+        self.overrides.get(&manager).unwrap_or(&self.packages)
+    }
+
+    /// Shell script printing the first available package manager and the
+    /// declared packages it does not have installed
+    pub fn check_script(&self) -> String {
+        let mut script = String::new();
+        for manager in PackageManager::ALL {
+            script.push_str(&format!(
+                "if command -v {} >/dev/null 2>&1; then\n  echo manager={}\n",
+                manager.binary(),
+                manager.name()
+            ));
+            for package in self.for_manager(manager) {
+                script.push_str(&format!(
+                    "  {} || echo missing={}\n",
+                    manager.installed_check(package),
+                    quote(package)
+                ));
+            }
+            script.push_str("  exit 0\nfi\n");
+        }
+        script.push_str("echo manager=none\n");
+        script
+    }
+
+    /// Shell script installing `missing` with `manager`, through `sudo` when
+    /// not running as root and it is available
+    pub fn install_script(manager: PackageManager, missing: &[String]) -> String {
+        let install = manager.install_command(missing);
+        match manager {
+            PackageManager::Brew => install,
+            _ => format!(
+                "if [ \"$(id -u)\" != 0 ] && command -v sudo >/dev/null 2>&1; then\n  sudo sh -c {}\nelse\n  {}\nfi\n",
+                quote(&install),
+                install
+            ),
+        }
+    }
+}
+
+/// Outcome of running [`SystemPackages::check_script`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageCheck {
+    /// Package manager found, if any
+    pub manager: Option<PackageManager>,
+    /// Declared packages that are not installed
+    pub missing: Vec<String>,
+}
+
+impl PackageCheck {
+    pub fn parse(output: &str) -> Self {
+        let mut check = Self::default();
+        for line in output.lines() {
+            match line.trim().split_once('=') {
+                Some(("manager", name)) => check.manager = PackageManager::from_name(name),
+                Some(("missing", package)) => check.missing.push(package.to_string()),
+                _ => {}
+            }
+        }
+        check
+    }
+}
+
+fn quote(value: &str) -> Cow<'_, str> {
+    shell_escape::unix::escape(Cow::Borrowed(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_script_uses_manager_specific_names() {
+        let packages = SystemPackages::new(&["build-essential", "libssl-dev"])
+            .with_override(PackageManager::Apk, &["build-base", "openssl-dev"]);
+        let script = packages.check_script();
+        assert!(
+            script.contains(
+                "dpkg -s build-essential >/dev/null 2>&1 || echo missing=build-essential"
+            )
+        );
+        assert!(
+            script.contains("apk info -e openssl-dev >/dev/null 2>&1 || echo missing=openssl-dev")
+        );
+        assert!(script.ends_with("echo manager=none\n"));
+        assert_eq!(
+            packages.for_manager(PackageManager::Brew),
+            ["build-essential", "libssl-dev"]
+        );
+    }
+
+    #[test]
+    fn test_parse_check_output() {
+        let check = PackageCheck::parse("manager=apt\nmissing=jq\nmissing=libpq-dev\n");
+        assert_eq!(check.manager, Some(PackageManager::Apt));
+        assert_eq!(check.missing, ["jq", "libpq-dev"]);
+        assert_eq!(PackageCheck::parse("manager=none\n").manager, None);
+
+        let missing = vec!["jq".to_string()];
+        assert_eq!(
+            PackageManager::Apt.install_hint(&missing),
+            "sudo sh -c 'apt-get update -qq && DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends jq'"
+        );
+        assert_eq!(
+            PackageManager::Brew.install_hint(&missing),
+            "brew install jq"
+        );
+    }
+}
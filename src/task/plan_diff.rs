@@ -0,0 +1,225 @@
+//! Differences between two execution plans.
+//!
+//! Tasks are matched by title, which is also what their ids derive from, so
+//! a renamed task shows as one task removed and another added. Dependencies
+//! are compared by the titles of the tasks they name, and other changes are
+//! reported by field name, e.g. `description`, `priority` or `tags`.
+
+use super::execution_plan::{ExecutionPlan, plan_task_id};
+use super::types::{TaskId, TaskSpec};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// What changed from one plan to another
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PlanDiff {
+    /// Titles of tasks only in the newer plan
+    pub added: Vec<String>,
+    /// Titles of tasks only in the older plan
+    pub removed: Vec<String>,
+    /// Tasks in both plans that differ
+    pub modified: Vec<TaskChange>,
+}
+
+/// How a task in both plans changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskChange {
+    pub title: String,
+    /// Changed fields other than dependencies
+    pub fields: Vec<String>,
+    /// Titles of tasks it now depends on
+    pub dependencies_added: Vec<String>,
+    /// Titles of tasks it no longer depends on
+    pub dependencies_removed: Vec<String>,
+}
+
+impl PlanDiff {
+    /// Changes turning `old` into `new`
+    pub fn between(old: &ExecutionPlan, new: &ExecutionPlan) -> Self {
+        let mut diff = PlanDiff {
+            added: titles_missing_from(new, old),
+            removed: titles_missing_from(old, new),
+            modified: Vec::new(),
+        };
+        let (old_names, new_names) = (task_names(old), task_names(new));
+        for old_spec in &old.task_specs {
+            let Some(new_spec) = new.task_specs.iter().find(|s| s.title == old_spec.title) else {
+                continue;
+            };
+            let old_dependencies = dependency_names(&old_spec.dependencies, &old_names);
+            let new_dependencies = dependency_names(&new_spec.dependencies, &new_names);
+            let change = TaskChange {
+                title: old_spec.title.clone(),
+                fields: changed_fields(old_spec, new_spec),
+                dependencies_added: missing_from(&new_dependencies, &old_dependencies),
+                dependencies_removed: missing_from(&old_dependencies, &new_dependencies),
+            };
+            if !change.fields.is_empty()
+                || !change.dependencies_added.is_empty()
+                || !change.dependencies_removed.is_empty()
+            {
+                diff.modified.push(change);
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// One line per added (`+`), removed (`-`) and modified (`~`) task,
+    /// with changed dependencies indented below
+    pub fn render(&self) -> String {
+        if self.is_empty() {
+            return "No differences\n".to_string();
+        }
+        let mut out = String::new();
+        for title in &self.added {
+            out.push_str(&format!("+ {}\n", title));
+        }
+        for title in &self.removed {
+            out.push_str(&format!("- {}\n", title));
+        }
+        for change in &self.modified {
+            match change.fields.as_slice() {
+                [] => out.push_str(&format!("~ {}\n", change.title)),
+                fields => out.push_str(&format!("~ {} ({})\n", change.title, fields.join(", "))),
+            }
+            for title in &change.dependencies_added {
+                out.push_str(&format!("    + depends on {}\n", title));
+            }
+            for title in &change.dependencies_removed {
+                out.push_str(&format!("    - depends on {}\n", title));
+            }
+        }
+        out
+    }
+}
+
+/// Titles of tasks in `plan` that `other` does not have, in plan order
+fn titles_missing_from(plan: &ExecutionPlan, other: &ExecutionPlan) -> Vec<String> {
+    plan.task_specs
+        .iter()
+        .filter(|spec| !other.task_specs.iter().any(|o| o.title == spec.title))
+        .map(|spec| spec.title.clone())
+        .collect()
+}
+
+fn task_names(plan: &ExecutionPlan) -> HashMap<TaskId, String> {
+    plan.task_specs
+        .iter()
+        .map(|spec| (plan_task_id(&spec.title), spec.title.clone()))
+        .collect()
+}
+
+/// Dependencies by title, or by id when they name no task in the plan
+fn dependency_names(dependencies: &[TaskId], names: &HashMap<TaskId, String>) -> Vec<String> {
+    dependencies
+        .iter()
+        .map(|id| names.get(id).cloned().unwrap_or_else(|| id.to_string()))
+        .collect()
+}
+
+fn missing_from(items: &[String], other: &[String]) -> Vec<String> {
+    items
+        .iter()
+        .filter(|item| !other.contains(item))
+        .cloned()
+        .collect()
+}
+
+/// Names of the fields that differ, metadata fields by their own name
+fn changed_fields(old: &TaskSpec, new: &TaskSpec) -> Vec<String> {
+    let fields = |spec: &TaskSpec| {
+        let mut fields = match serde_json::to_value(spec) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            _ => serde_json::Map::new(),
+        };
+        fields.remove("title");
+        fields.remove("dependencies");
+        if let Some(serde_json::Value::Object(metadata)) = fields.remove("metadata") {
+            fields.extend(metadata);
+        }
+        fields
+    };
+    let (old, new) = (fields(old), fields(new));
+    let mut names: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|name| old.get(*name) != new.get(*name))
+        .cloned()
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::types::{TaskMetadata, TaskPriority};
+
+    fn task(title: &str, dependencies: &[&str]) -> TaskSpec {
+        TaskSpec {
+            title: title.to_string(),
+            description: format!("{} description", title),
+            metadata: TaskMetadata::default(),
+            dependencies: dependencies
+                .iter()
+                .map(|title| plan_task_id(title))
+                .collect(),
+            plan: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_tasks_and_dependencies() {
+        let old = ExecutionPlan::new().with_tasks(vec![
+            task("Design schema", &[]),
+            task("Write migration", &["Design schema"]),
+            task("Build API", &["Write migration"]),
+        ]);
+        let mut api = task("Build API", &["Design schema", "Add caching"]);
+        api.description = "Build the REST API".to_string();
+        api.metadata.priority = TaskPriority::High;
+        let new = ExecutionPlan::new().with_tasks(vec![
+            task("Design schema", &[]),
+            task("Add caching", &[]),
+            api,
+        ]);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, vec!["Add caching"]);
+        assert_eq!(diff.removed, vec!["Write migration"]);
+        assert_eq!(
+            diff.modified,
+            vec![TaskChange {
+                title: "Build API".to_string(),
+                fields: vec!["description".to_string(), "priority".to_string()],
+                dependencies_added: vec!["Design schema".to_string(), "Add caching".to_string()],
+                dependencies_removed: vec!["Write migration".to_string()],
+            }]
+        );
+        assert_eq!(
+            diff.render(),
+            "+ Add caching\n\
+             - Write migration\n\
+             ~ Build API (description, priority)\n    \
+             + depends on Design schema\n    \
+             + depends on Add caching\n    \
+             - depends on Write migration\n"
+        );
+    }
+
+    #[test]
+    fn test_identical_plans_have_no_diff() {
+        let plan = ExecutionPlan::new().with_tasks(vec![
+            task("Design schema", &[]),
+            task("Build API", &["Design schema"]),
+        ]);
+        let diff = plan.diff(&plan.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.render(), "No differences\n");
+    }
+}
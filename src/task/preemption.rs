@@ -0,0 +1,241 @@
+//! Cooperative preemption of low-priority work.
+//!
+//! Every running task holds one of a fixed number of concurrency slots. When a
+//! [`TaskPriority::Critical`] task arrives and no slot is free, the controller
+//! asks one running [`TaskPriority::Background`] task to yield. That task is
+//! checkpointed, marked [`TaskStatus::Paused`](super::TaskStatus::Paused),
+//! its subprocess is killed and its slot handed to the critical task. The
+//! paused task queues for a slot again and resumes once one frees up.
+//!
+//! Slots are only contended when tasks run concurrently, as in parallel plans;
+//! a sequential plan runs one task at a time and never preempts.
+//!
+//! A task paused by the user gives its slot up with
+//! [`release`](PreemptionController::release) while its subprocess is
+//! stopped, and waits for one again with
+//! [`reclaim`](PreemptionController::reclaim) before it continues.
+
+use super::types::{TaskId, TaskPriority};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug)]
+struct RunningTask {
+    priority: TaskPriority,
+    preempt: Arc<Notify>,
+    preempt_requested: bool,
+    /// `None` while the task is paused without a slot
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Hands out concurrency slots and preempts background work for critical tasks
+#[derive(Debug, Clone)]
+pub struct PreemptionController {
+    slots: Arc<Semaphore>,
+    running: Arc<Mutex<HashMap<TaskId, RunningTask>>>,
+}
+
+impl PreemptionController {
+    pub fn new(max_concurrent_tasks: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent_tasks.max(1))),
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Wait for a slot, preempting a background task if `priority` is critical
+    pub async fn acquire(&self, task_id: TaskId, priority: TaskPriority) -> SlotGuard {
+        let permit = self.wait_for_slot(task_id, &priority).await;
+
+        let preempt = Arc::new(Notify::new());
+        self.running.lock().unwrap().insert(
+            task_id,
+            RunningTask {
+                priority,
+                preempt: preempt.clone(),
+                preempt_requested: false,
+                permit: Some(permit),
+            },
+        );
+
+        SlotGuard {
+            task_id,
+            preempt,
+            running: self.running.clone(),
+        }
+    }
+
+    /// Free a paused task's slot for other tasks; `false` if it holds none
+    pub fn release(&self, task_id: TaskId) -> bool {
+        let mut running = self.running.lock().unwrap();
+        running
+            .get_mut(&task_id)
+            .and_then(|task| task.permit.take())
+            .is_some()
+    }
+
+    /// Wait for a slot again for a task [`release`](Self::release)d its slot
+    pub async fn reclaim(&self, task_id: TaskId) {
+        let priority = match self.running.lock().unwrap().get(&task_id) {
+            Some(task) if task.permit.is_none() => task.priority.clone(),
+            _ => return,
+        };
+        let permit = self.wait_for_slot(task_id, &priority).await;
+        // Dropped with the permit if the task finished in the meantime
+        if let Some(task) = self.running.lock().unwrap().get_mut(&task_id) {
+            task.permit = Some(permit);
+        }
+    }
+
+    async fn wait_for_slot(
+        &self,
+        task_id: TaskId,
+        priority: &TaskPriority,
+    ) -> OwnedSemaphorePermit {
+        if let Ok(permit) = self.slots.clone().try_acquire_owned() {
+            return permit;
+        }
+        if *priority == TaskPriority::Critical
+            && let Some(victim) = self.request_preemption()
+        {
+            tracing::info!(
+                "Preempting background task {} for critical task {}",
+                victim,
+                task_id
+            );
+        }
+        self.slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("preemption semaphore is never closed")
+    }
+
+    /// Ask one running background task to yield its slot
+    fn request_preemption(&self) -> Option<TaskId> {
+        let mut running = self.running.lock().unwrap();
+        let (task_id, victim) = running.iter_mut().find(|(_, task)| {
+            task.priority == TaskPriority::Background
+                && !task.preempt_requested
+                && task.permit.is_some()
+        })?;
+        victim.preempt_requested = true;
+        victim.preempt.notify_one();
+        Some(*task_id)
+    }
+
+    /// Number of tasks currently holding a slot
+    pub fn running_count(&self) -> usize {
+        self.running
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|task| task.permit.is_some())
+            .count()
+    }
+}
+
+/// A held concurrency slot; dropping it frees the slot
+#[derive(Debug)]
+pub struct SlotGuard {
+    task_id: TaskId,
+    preempt: Arc<Notify>,
+    running: Arc<Mutex<HashMap<TaskId, RunningTask>>>,
+}
+
+impl SlotGuard {
+    /// Resolves once a critical task has asked this one to yield
+    pub async fn preempted(&self) {
+        self.preempt.notified().await
+    }
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.task_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_critical_task_preempts_background() {
+        let controller = PreemptionController::new(1);
+        let background = controller
+            .acquire(Uuid::new_v4(), TaskPriority::Background)
+            .await;
+
+        let critical = tokio::spawn({
+            let controller = controller.clone();
+            async move {
+                controller
+                    .acquire(Uuid::new_v4(), TaskPriority::Critical)
+                    .await
+            }
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), background.preempted())
+            .await
+            .expect("background task should be asked to yield");
+        drop(background);
+
+        let _critical = tokio::time::timeout(Duration::from_secs(1), critical)
+            .await
+            .expect("critical task should get the freed slot")
+            .unwrap();
+        assert_eq!(controller.running_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_paused_task_releases_its_slot() {
+        let controller = PreemptionController::new(1);
+        let paused_id = Uuid::new_v4();
+        let _paused = controller.acquire(paused_id, TaskPriority::Normal).await;
+
+        assert!(controller.release(paused_id));
+        assert!(!controller.release(paused_id));
+        let other = tokio::time::timeout(
+            Duration::from_secs(1),
+            controller.acquire(Uuid::new_v4(), TaskPriority::Normal),
+        )
+        .await
+        .expect("the paused task's slot should be free");
+
+        // Resuming waits until the slot is free again
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), controller.reclaim(paused_id))
+                .await
+                .is_err()
+        );
+        drop(other);
+        tokio::time::timeout(Duration::from_secs(1), controller.reclaim(paused_id))
+            .await
+            .expect("the paused task should get the slot back");
+        assert_eq!(controller.running_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_normal_task_does_not_preempt() {
+        let controller = PreemptionController::new(1);
+        let background = controller
+            .acquire(Uuid::new_v4(), TaskPriority::Background)
+            .await;
+
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(50),
+            controller.acquire(Uuid::new_v4(), TaskPriority::Normal),
+        )
+        .await;
+        assert!(waiting.is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), background.preempted())
+                .await
+                .is_err()
+        );
+    }
+}
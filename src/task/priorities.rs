@@ -0,0 +1,270 @@
+//! Human priority overrides for a running plan.
+//!
+//! `.aca/priorities.toml` maps task keys (a task's `key:` tag, or else its
+//! lowercased title) to a new priority and/or a hold flag:
+//!
+//! ```toml
+//! "add login page" = { priority = "critical" }
+//! "migrate-db" = { hold = true }
+//! ```
+//!
+//! The plan loop re-reads the file before picking each task, so edits take
+//! effect without restarting the run. While any override is present the
+//! remaining tasks run by priority (plan order within a priority); held tasks
+//! wait until the hold is lifted.
+
+use super::idempotency::task_key;
+use super::types::{TaskPriority, TaskSpec};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// What a human changed for one task
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriorityOverride {
+    /// Priority replacing the one from the plan
+    #[serde(deserialize_with = "deserialize_priority")]
+    pub priority: Option<TaskPriority>,
+    /// Keep the task from starting until the hold is removed
+    pub hold: bool,
+}
+
+fn deserialize_priority<'de, D>(deserializer: D) -> Result<Option<TaskPriority>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let priority = match value.trim().to_lowercase().as_str() {
+        "critical" => TaskPriority::Critical,
+        "high" => TaskPriority::High,
+        "normal" | "medium" => TaskPriority::Normal,
+        "low" => TaskPriority::Low,
+        "background" => TaskPriority::Background,
+        other => {
+            return Err(serde::de::Error::custom(format!(
+                "unknown priority '{}' (expected critical, high, normal, low or background)",
+                other
+            )));
+        }
+    };
+    Ok(Some(priority))
+}
+
+/// One task whose override was added, edited or removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityChange {
+    pub task_key: String,
+    pub before: Option<PriorityOverride>,
+    pub after: Option<PriorityOverride>,
+}
+
+/// Overrides keyed by lowercased task key
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriorityOverrides {
+    overrides: BTreeMap<String, PriorityOverride>,
+}
+
+impl PriorityOverrides {
+    /// Parse the TOML file contents
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: BTreeMap<String, PriorityOverride> = toml::from_str(content)?;
+        Ok(Self {
+            overrides: raw
+                .into_iter()
+                .map(|(key, value)| (key.trim().to_lowercase(), value))
+                .collect(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn get(&self, spec: &TaskSpec) -> Option<&PriorityOverride> {
+        self.overrides.get(&task_key(spec).to_lowercase())
+    }
+
+    pub fn is_held(&self, spec: &TaskSpec) -> bool {
+        self.get(spec).is_some_and(|entry| entry.hold)
+    }
+
+    /// The task's priority after overrides
+    pub fn priority(&self, spec: &TaskSpec) -> TaskPriority {
+        self.get(spec)
+            .and_then(|entry| entry.priority.clone())
+            .unwrap_or_else(|| spec.metadata.priority.clone())
+    }
+
+    /// Index of the task to run next, or `None` if every task is held
+    ///
+    /// Without overrides this is simply the first task, keeping plan order.
+    pub fn next_index(&self, specs: &[TaskSpec]) -> Option<usize> {
+        self.next_index_where(specs, |_| true)
+    }
+
+    /// Like [`next_index`](Self::next_index), but only among the tasks
+    /// whose index satisfies `eligible`
+    pub fn next_index_where(
+        &self,
+        specs: &[TaskSpec],
+        eligible: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        if self.is_empty() {
+            return (0..specs.len()).find(|&index| eligible(index));
+        }
+        specs
+            .iter()
+            .enumerate()
+            .filter(|(index, spec)| eligible(*index) && !self.is_held(spec))
+            // max_by_key keeps the last maximum, so reverse to prefer plan order
+            .rev()
+            .max_by_key(|(_, spec)| self.priority(spec))
+            .map(|(index, _)| index)
+    }
+
+    /// Overrides that differ from `previous`
+    pub fn changes_since(&self, previous: &PriorityOverrides) -> Vec<PriorityChange> {
+        let mut keys: Vec<&String> = self
+            .overrides
+            .keys()
+            .chain(previous.overrides.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter_map(|key| {
+                let before = previous.overrides.get(key);
+                let after = self.overrides.get(key);
+                (before != after).then(|| PriorityChange {
+                    task_key: key.clone(),
+                    before: before.cloned(),
+                    after: after.cloned(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The workspace's `.aca/priorities.toml`
+#[derive(Debug, Clone)]
+pub struct PriorityFile {
+    path: PathBuf,
+}
+
+impl PriorityFile {
+    pub fn for_workspace(workspace_root: &Path) -> Self {
+        Self::new(crate::env::priorities_file_path(workspace_root))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Current overrides; a missing file means none
+    pub fn load(&self) -> Result<PriorityOverrides> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => PriorityOverrides::parse(&content)
+                .with_context(|| format!("Invalid priority overrides in {:?}", self.path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PriorityOverrides::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", self.path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(title: &str, priority: TaskPriority) -> TaskSpec {
+        let mut spec = TaskSpec {
+            title: title.to_string(),
+            ..Default::default()
+        };
+        spec.metadata.priority = priority;
+        spec
+    }
+
+    #[test]
+    fn test_overrides_reorder_and_hold_tasks() {
+        let specs = vec![
+            spec("Write docs", TaskPriority::Normal),
+            spec("Add login", TaskPriority::Normal),
+            spec("Migrate DB", TaskPriority::High),
+        ];
+        // No file keeps plan order regardless of plan priorities
+        assert_eq!(PriorityOverrides::default().next_index(&specs), Some(0));
+        assert_eq!(
+            PriorityOverrides::default().next_index_where(&specs, |index| index > 0),
+            Some(1)
+        );
+
+        let overrides = PriorityOverrides::parse(
+            r#"
+            "Add Login" = { priority = "critical" }
+            "migrate db" = { hold = true }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(overrides.next_index(&specs), Some(1));
+        assert_eq!(overrides.next_index(&specs[2..]), None);
+        assert_eq!(
+            overrides.next_index_where(&specs, |index| index != 1),
+            Some(0)
+        );
+
+        let bumped_only =
+            PriorityOverrides::parse(r#""write docs" = { priority = "low" }"#).unwrap();
+        assert_eq!(bumped_only.next_index(&specs), Some(2));
+        assert_eq!(bumped_only.next_index(&specs[..2]), Some(1));
+
+        assert!(PriorityOverrides::parse(r#""x" = { priority = "urgent" }"#).is_err());
+    }
+
+    #[test]
+    fn test_changes_since_reports_before_and_after() {
+        let before = PriorityOverrides::parse(
+            r#"
+            "a" = { hold = true }
+            "b" = { priority = "low" }
+            "#,
+        )
+        .unwrap();
+        let after = PriorityOverrides::parse(
+            r#"
+            "b" = { priority = "low" }
+            "c" = { priority = "high" }
+            "#,
+        )
+        .unwrap();
+
+        let changes = after.changes_since(&before);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].task_key, "a");
+        assert!(changes[0].before.as_ref().unwrap().hold);
+        assert_eq!(changes[0].after, None);
+        assert_eq!(changes[1].task_key, "c");
+        assert_eq!(
+            changes[1].after.as_ref().unwrap().priority,
+            Some(TaskPriority::High)
+        );
+    }
+
+    #[test]
+    fn test_missing_file_has_no_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = PriorityFile::for_workspace(dir.path());
+        assert!(file.load().unwrap().is_empty());
+
+        std::fs::create_dir_all(file.path().parent().unwrap()).unwrap();
+        std::fs::write(file.path(), "not = [valid").unwrap();
+        assert!(file.load().is_err());
+    }
+}
@@ -0,0 +1,402 @@
+//! Structured questions from the model to the user.
+//!
+//! When a task needs a decision it cannot make alone (for example which
+//! database to target), the model ends its turn with a question block:
+//!
+//! ```text
+//! <aca-question>
+//! {"question": "Which database should the service use?",
+//!  "options": ["postgres", "sqlite"], "default": "postgres"}
+//! </aca-question>
+//! ```
+//!
+//! The task is then marked [`TaskStatus::AwaitingInput`](super::TaskStatus::AwaitingInput)
+//! and the question goes to the registered [`QuestionHandler`]: a terminal
+//! prompt in the CLI or a [`QuestionBroker`] that an API front end answers.
+//! The answer is appended to the task prompt and the task continues.
+//!
+//! Unattended runs use an [`AnswerFile`] instead: answers keyed by question
+//! patterns, plus an [`UnansweredPolicy`] for anything the file misses.
+
+use super::types::TaskId;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Opening tag of a question block in a model response
+pub const QUESTION_BLOCK_START: &str = "<aca-question>";
+
+/// Closing tag of a question block in a model response
+pub const QUESTION_BLOCK_END: &str = "</aca-question>";
+
+/// System prompt addition describing the question protocol to the model
+pub const QUESTION_PROTOCOL_PROMPT: &str = "If you cannot continue without a decision \
+from the user, stop and end your response with a single block of the form \
+<aca-question>{\"question\": \"...\", \"options\": [\"...\"], \"default\": \"...\"}</aca-question>. \
+Options and default are optional. You will be resumed with the answer. Only ask when \
+the choice materially changes the result.";
+
+/// Question handling settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionConfig {
+    /// Tell the model it may ask questions and honour question blocks
+    pub enabled: bool,
+    /// Questions allowed per task before further blocks are ignored
+    pub max_questions_per_task: u32,
+}
+
+impl Default for QuestionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_questions_per_task: 3,
+        }
+    }
+}
+
+/// A question the model asked
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserQuestion {
+    pub question: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+impl UserQuestion {
+    /// Extract the last question block from a model response
+    ///
+    /// The block body is JSON; a plain-text body is taken as the question.
+    pub fn parse(response: &str) -> Option<Self> {
+        let start = response.rfind(QUESTION_BLOCK_START)? + QUESTION_BLOCK_START.len();
+        let end = start + response[start..].find(QUESTION_BLOCK_END)?;
+        let body = response[start..end].trim();
+        if body.is_empty() {
+            return None;
+        }
+
+        match serde_json::from_str::<UserQuestion>(body) {
+            Ok(question) if !question.question.trim().is_empty() => Some(question),
+            Ok(_) => None,
+            Err(_) => Some(UserQuestion {
+                question: body.to_string(),
+                options: Vec::new(),
+                default: None,
+            }),
+        }
+    }
+}
+
+/// A question waiting for an answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionRequest {
+    pub id: Uuid,
+    pub task_id: TaskId,
+    pub task_title: String,
+    pub question: UserQuestion,
+    pub asked_at: DateTime<Utc>,
+}
+
+/// An answer and who gave it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuestionAnswer {
+    pub answer: String,
+    pub answered_by: String,
+}
+
+/// A task left awaiting an answer to its question
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Task {task_id} is awaiting an answer to its question")]
+pub struct AwaitingAnswer {
+    pub task_id: TaskId,
+}
+
+/// Prompt for continuing a task with the user's answer
+pub fn prompt_with_answer(
+    description: &str,
+    question: &UserQuestion,
+    answer: &QuestionAnswer,
+) -> String {
+    format!(
+        "{}\n\n--- Clarification ---\nYou asked: {}\nAnswer: {}\nContinue the task using this answer.",
+        description, question.question, answer.answer
+    )
+}
+
+/// Gets answers to model questions
+pub trait QuestionHandler: Send + Sync {
+    /// Answer a question; `None` leaves the task awaiting input
+    fn ask<'a>(
+        &'a self,
+        request: &'a QuestionRequest,
+    ) -> BoxFuture<'a, Result<Option<QuestionAnswer>>>;
+}
+
+/// What an [`AnswerFile`] does with a question none of its patterns match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnansweredPolicy {
+    /// Fail the task
+    #[default]
+    Fail,
+    /// Take the question's default, or its first option
+    Assume,
+    /// Leave the task awaiting input
+    Skip,
+}
+
+/// One pattern/answer pair in an answers file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerRule {
+    /// Case-insensitive regex matched against the question text
+    pub pattern: String,
+    pub answer: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AnswerFileSpec {
+    #[serde(default)]
+    default_policy: UnansweredPolicy,
+    #[serde(default)]
+    answers: Vec<AnswerRule>,
+}
+
+/// Pre-supplied answers for runs where nobody can reply, e.g. CI
+///
+/// ```toml
+/// default_policy = "assume"  # fail | assume | skip
+///
+/// [[answers]]
+/// pattern = "which (db|database)"
+/// answer = "postgres"
+/// ```
+///
+/// The first rule whose pattern matches the question wins.
+#[derive(Debug, Clone)]
+pub struct AnswerFile {
+    default_policy: UnansweredPolicy,
+    rules: Vec<(Regex, String)>,
+}
+
+impl AnswerFile {
+    /// Name recorded as the author of answers taken from the file
+    pub const ANSWERED_BY: &'static str = "answers-file";
+
+    /// Name recorded as the author of answers assumed by policy
+    pub const ASSUMED_BY: &'static str = "default-policy";
+
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let spec: AnswerFileSpec = toml::from_str(content).context("Invalid answers file")?;
+        let rules = spec
+            .answers
+            .into_iter()
+            .map(|rule| {
+                let pattern = RegexBuilder::new(&rule.pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid answer pattern '{}'", rule.pattern))?;
+                Ok((pattern, rule.answer))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            default_policy: spec.default_policy,
+            rules,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read answers file {}", path.display()))?;
+        Self::from_toml_str(&content)
+    }
+
+    pub fn default_policy(&self) -> UnansweredPolicy {
+        self.default_policy
+    }
+
+    /// Answer a question from the rules, falling back to the default policy
+    pub fn answer(&self, question: &UserQuestion) -> Result<Option<QuestionAnswer>> {
+        if let Some((_, answer)) = self
+            .rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(&question.question))
+        {
+            return Ok(Some(QuestionAnswer {
+                answer: answer.clone(),
+                answered_by: Self::ANSWERED_BY.to_string(),
+            }));
+        }
+
+        match self.default_policy {
+            UnansweredPolicy::Fail => {
+                bail!(
+                    "No answer in answers file for question: {}",
+                    question.question
+                )
+            }
+            UnansweredPolicy::Assume => {
+                let answer = question
+                    .default
+                    .clone()
+                    .or_else(|| question.options.first().cloned())
+                    .unwrap_or_else(|| {
+                        "No answer is available. Use your best judgement and state the assumption you made.".to_string()
+                    });
+                Ok(Some(QuestionAnswer {
+                    answer,
+                    answered_by: Self::ASSUMED_BY.to_string(),
+                }))
+            }
+            UnansweredPolicy::Skip => Ok(None),
+        }
+    }
+}
+
+impl QuestionHandler for AnswerFile {
+    fn ask<'a>(
+        &'a self,
+        request: &'a QuestionRequest,
+    ) -> BoxFuture<'a, Result<Option<QuestionAnswer>>> {
+        Box::pin(async move { self.answer(&request.question) })
+    }
+}
+
+/// Holds questions until an API caller answers them
+#[derive(Debug, Default)]
+pub struct QuestionBroker {
+    pending: Mutex<HashMap<Uuid, (QuestionRequest, oneshot::Sender<QuestionAnswer>)>>,
+}
+
+impl QuestionBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Questions waiting for an answer, oldest first
+    pub fn pending(&self) -> Vec<QuestionRequest> {
+        let mut pending: Vec<_> = self
+            .pending
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(request, _)| request.clone())
+            .collect();
+        pending.sort_by_key(|request| request.asked_at);
+        pending
+    }
+
+    /// Answer a pending question; `false` if it is unknown or already answered
+    pub fn answer(&self, id: Uuid, answer: QuestionAnswer) -> bool {
+        match self.pending.lock().unwrap().remove(&id) {
+            Some((_, sender)) => sender.send(answer).is_ok(),
+            None => false,
+        }
+    }
+}
+
+impl QuestionHandler for QuestionBroker {
+    fn ask<'a>(
+        &'a self,
+        request: &'a QuestionRequest,
+    ) -> BoxFuture<'a, Result<Option<QuestionAnswer>>> {
+        Box::pin(async move {
+            let (sender, receiver) = oneshot::channel();
+            self.pending
+                .lock()
+                .unwrap()
+                .insert(request.id, (request.clone(), sender));
+            Ok(receiver.await.ok())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_question_block() {
+        let response = "I need a decision.\n<aca-question>\n{\"question\": \"Which DB?\", \"options\": [\"postgres\", \"sqlite\"]}\n</aca-question>";
+        let question = UserQuestion::parse(response).unwrap();
+        assert_eq!(question.question, "Which DB?");
+        assert_eq!(question.options, vec!["postgres", "sqlite"]);
+
+        let plain = UserQuestion::parse("<aca-question>Use tabs?</aca-question>").unwrap();
+        assert_eq!(plain.question, "Use tabs?");
+
+        assert!(UserQuestion::parse("Done, no questions.").is_none());
+    }
+
+    #[test]
+    fn test_answer_file_rules_and_policies() {
+        let question = |text: &str| UserQuestion {
+            question: text.to_string(),
+            options: vec!["postgres".to_string(), "sqlite".to_string()],
+            default: None,
+        };
+        let file = AnswerFile::from_toml_str(
+            r#"
+            default_policy = "assume"
+            [[answers]]
+            pattern = "which (db|database)"
+            answer = "sqlite"
+            "#,
+        )
+        .unwrap();
+
+        let answer = file.answer(&question("Which DATABASE?")).unwrap().unwrap();
+        assert_eq!(answer.answer, "sqlite");
+        assert_eq!(answer.answered_by, AnswerFile::ANSWERED_BY);
+
+        let assumed = file.answer(&question("Use async?")).unwrap().unwrap();
+        assert_eq!(assumed.answer, "postgres");
+        assert_eq!(assumed.answered_by, AnswerFile::ASSUMED_BY);
+
+        let strict = AnswerFile::from_toml_str("").unwrap();
+        assert_eq!(strict.default_policy(), UnansweredPolicy::Fail);
+        assert!(strict.answer(&question("Use async?")).is_err());
+
+        let skip = AnswerFile::from_toml_str("default_policy = \"skip\"").unwrap();
+        assert!(skip.answer(&question("Use async?")).unwrap().is_none());
+
+        assert!(AnswerFile::from_toml_str("[[answers]]\npattern = \"(\"\nanswer = \"x\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broker_delivers_answer() {
+        let broker = std::sync::Arc::new(QuestionBroker::new());
+        let request = QuestionRequest {
+            id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            task_title: "Set up storage".to_string(),
+            question: UserQuestion::parse("<aca-question>Which DB?</aca-question>").unwrap(),
+            asked_at: Utc::now(),
+        };
+
+        let asking = tokio::spawn({
+            let broker = broker.clone();
+            let request = request.clone();
+            async move { broker.ask(&request).await.unwrap() }
+        });
+        while broker.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        let answer = QuestionAnswer {
+            answer: "postgres".to_string(),
+            answered_by: "alice".to_string(),
+        };
+        assert!(broker.answer(request.id, answer.clone()));
+        assert_eq!(asking.await.unwrap(), Some(answer));
+        assert!(broker.pending().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! Dev-loop tasks: `aca quick "<one-liner>"`.
+//!
+//! A quick request skips plan parsing and becomes a single task. Once it
+//! finishes the workspace's verification command runs, and a compact summary
+//! of the lines changed per file is printed. The session is still saved like
+//! any other run, so an interrupted quick task resumes with `aca resume`.
+
+use super::types::{TaskMetadata, TaskPriority, TaskSpec};
+use crate::workspace::FileStat;
+use serde::{Deserialize, Serialize};
+
+/// Tag of quick tasks
+pub const QUICK_TAG: &str = "quick";
+
+/// Longest task title taken from the request
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Verification output kept in a quick report (tail, in characters)
+pub const MAX_QUICK_OUTPUT_CHARS: usize = 2000;
+
+/// How a quick task went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickReport {
+    pub task_id: uuid::Uuid,
+    /// Lines changed per file, in diff order
+    pub files: Vec<FileStat>,
+    /// Result of the verification command, if there is one
+    pub verification: Option<QuickVerification>,
+}
+
+/// Outcome of the verification command after a quick task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickVerification {
+    pub command: String,
+    pub passed: bool,
+    /// End of the command's output
+    pub output: String,
+}
+
+impl QuickReport {
+    /// One line per changed file and a total, like `git diff --stat`
+    pub fn render_diff_stat(&self) -> String {
+        if self.files.is_empty() {
+            return "No files changed\n".to_string();
+        }
+        let width = self
+            .files
+            .iter()
+            .map(|file| file.path.display().to_string().chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut out = String::new();
+        for file in &self.files {
+            out.push_str(&format!(
+                " {:<width$} | {}\n",
+                file.path.display().to_string(),
+                line_counts(file.added, file.removed),
+                width = width
+            ));
+        }
+        let added = self.files.iter().map(|file| file.added).sum();
+        let removed = self.files.iter().map(|file| file.removed).sum();
+        out.push_str(&format!(
+            " {} file{} changed, {}\n",
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" },
+            line_counts(added, removed)
+        ));
+        out
+    }
+}
+
+fn line_counts(added: usize, removed: usize) -> String {
+    match (added, removed) {
+        // Binary, skipped and empty files
+        (0, 0) => "no lines".to_string(),
+        (added, 0) => format!("+{}", added),
+        (0, removed) => format!("-{}", removed),
+        (added, removed) => format!("+{} -{}", added, removed),
+    }
+}
+
+/// Build the single task for a quick request
+///
+/// The title is the request's first line, shortened; `verify_command` is
+/// mentioned so the change is made to pass it.
+pub fn quick_task_spec(request: &str, verify_command: Option<&str>) -> TaskSpec {
+    let request = request.trim();
+    let first_line = request.lines().next().unwrap_or_default().trim();
+    let title = if first_line.chars().count() > MAX_TITLE_CHARS {
+        let short: String = first_line.chars().take(MAX_TITLE_CHARS - 3).collect();
+        format!("{}...", short.trim_end())
+    } else {
+        first_line.to_string()
+    };
+
+    let mut description = format!(
+        "{}\n\nKeep the change small and focused on this request.",
+        request
+    );
+    if let Some(command) = verify_command {
+        description.push_str(&format!(" `{}` must pass afterwards.", command));
+    }
+
+    TaskSpec {
+        title,
+        description,
+        dependencies: Vec::new(),
+        metadata: TaskMetadata {
+            priority: TaskPriority::High,
+            tags: vec![QUICK_TAG.to_string()],
+            ..Default::default()
+        },
+        plan: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_quick_task_spec() {
+        let spec = quick_task_spec(
+            "  Rename `fetch_all` to `fetch_every_page` in src/client.rs and update its callers\n",
+            Some("cargo test"),
+        );
+        assert_eq!(
+            spec.title,
+            "Rename `fetch_all` to `fetch_every_page` in src/client.rs..."
+        );
+        assert!(spec.title.chars().count() <= MAX_TITLE_CHARS);
+        assert!(spec.description.starts_with("Rename `fetch_all`"));
+        assert!(spec.description.contains("`cargo test` must pass"));
+        assert_eq!(spec.metadata.tags, vec![QUICK_TAG]);
+
+        let spec = quick_task_spec("Fix the typo in README", None);
+        assert_eq!(spec.title, "Fix the typo in README");
+        assert!(!spec.description.contains("must pass"));
+    }
+
+    #[test]
+    fn test_render_diff_stat() {
+        let stat = |path: &str, added, removed| FileStat {
+            path: PathBuf::from(path),
+            added,
+            removed,
+        };
+        let report = QuickReport {
+            task_id: uuid::Uuid::new_v4(),
+            files: vec![
+                stat("src/client.rs", 3, 1),
+                stat("README.md", 1, 0),
+                stat("logo.png", 0, 0),
+            ],
+            verification: None,
+        };
+        assert_eq!(
+            report.render_diff_stat(),
+            " src/client.rs | +3 -1\n \
+             README.md     | +1\n \
+             logo.png      | no lines\n \
+             3 files changed, +4 -1\n"
+        );
+
+        let report = QuickReport {
+            files: Vec::new(),
+            ..report
+        };
+        assert_eq!(report.render_diff_stat(), "No files changed\n");
+    }
+}
@@ -0,0 +1,363 @@
+//! Behavior lock for refactoring tasks.
+//!
+//! Before a task tagged `refactor` runs, the configured test command and
+//! golden commands are executed and recorded as a [`BehaviorSnapshot`]. The
+//! same commands run again once the task finishes; any difference in test
+//! outcomes or golden output blocks the task instead of completing it, and
+//! the [`BehaviorComparison`] is attached to the task report.
+
+use crate::executor::ExecutionResult;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Refactoring safety net settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefactorGuardConfig {
+    /// Check behavior around tasks carrying `tag`
+    pub enabled: bool,
+    /// Task tag that marks a refactor
+    pub tag: String,
+    /// Test command whose per-test outcomes must not change
+    pub test_command: Option<String>,
+    /// Commands whose exact output must not change
+    pub golden_commands: Vec<String>,
+}
+
+impl Default for RefactorGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tag: "refactor".to_string(),
+            test_command: None,
+            golden_commands: Vec::new(),
+        }
+    }
+}
+
+impl RefactorGuardConfig {
+    /// Whether a task with these tags is guarded
+    pub fn applies_to(&self, tags: &[String]) -> bool {
+        self.enabled
+            && (self.test_command.is_some() || !self.golden_commands.is_empty())
+            && tags.iter().any(|tag| tag.eq_ignore_ascii_case(&self.tag))
+    }
+}
+
+/// Result of one test, normalized across runners
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl TestOutcome {
+    fn parse(status: &str) -> Self {
+        match status {
+            "ok" | "PASSED" | "PASS" => TestOutcome::Passed,
+            "ignored" | "SKIPPED" | "SKIP" => TestOutcome::Skipped,
+            _ => TestOutcome::Failed,
+        }
+    }
+}
+
+/// Test command run with its per-test outcomes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestSnapshot {
+    pub command: String,
+    pub exit_code: i32,
+    pub outcomes: BTreeMap<String, TestOutcome>,
+}
+
+impl TestSnapshot {
+    pub fn from_result(command: &str, result: &ExecutionResult) -> Self {
+        // Outcome lines of cargo test, pytest -v and go test -v, with the
+        // capture groups holding the test name and its status
+        let patterns = [
+            (r"(?m)^test (\S+) \.\.\. (ok|FAILED|ignored)", 1, 2),
+            (r"(?m)^(\S+::\S+) (PASSED|FAILED|SKIPPED|ERROR)", 1, 2),
+            (r"(?m)^\s*--- (PASS|FAIL|SKIP): (\S+)", 2, 1),
+        ];
+
+        let output = format!("{}\n{}", result.stdout, result.stderr);
+        let mut outcomes = BTreeMap::new();
+        for (pattern, name, status) in patterns {
+            let pattern = Regex::new(pattern).unwrap();
+            for captures in pattern.captures_iter(&output) {
+                outcomes.insert(
+                    captures[name].to_string(),
+                    TestOutcome::parse(&captures[status]),
+                );
+            }
+        }
+
+        Self {
+            command: command.to_string(),
+            exit_code: result.exit_code,
+            outcomes,
+        }
+    }
+}
+
+/// Golden command run with its normalized output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenOutput {
+    pub command: String,
+    pub exit_code: i32,
+    pub stdout: String,
+}
+
+impl GoldenOutput {
+    pub fn from_result(command: &str, result: &ExecutionResult) -> Self {
+        let stdout = result
+            .stdout
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        Self {
+            command: command.to_string(),
+            exit_code: result.exit_code,
+            stdout,
+        }
+    }
+}
+
+/// Recorded behavior of the workspace at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BehaviorSnapshot {
+    pub captured_at: DateTime<Utc>,
+    pub tests: Option<TestSnapshot>,
+    pub golden: Vec<GoldenOutput>,
+}
+
+/// One behavioral change found by [`BehaviorSnapshot::compare`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BehaviorDifference {
+    /// The test command's exit status changed between success and failure
+    TestExitCode { before: i32, after: i32 },
+    /// A test changed outcome; `None` means it was not reported
+    TestOutcome {
+        name: String,
+        before: Option<TestOutcome>,
+        after: Option<TestOutcome>,
+    },
+    /// A golden command produced different output or exit code
+    GoldenOutput {
+        command: String,
+        before_exit_code: i32,
+        after_exit_code: i32,
+        first_difference: Option<String>,
+    },
+}
+
+impl std::fmt::Display for BehaviorDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = |outcome: &Option<TestOutcome>| match outcome {
+            Some(outcome) => format!("{:?}", outcome).to_lowercase(),
+            None => "missing".to_string(),
+        };
+        match self {
+            BehaviorDifference::TestExitCode { before, after } => {
+                write!(f, "test command exit code {} -> {}", before, after)
+            }
+            BehaviorDifference::TestOutcome {
+                name,
+                before,
+                after,
+            } => write!(
+                f,
+                "test {}: {} -> {}",
+                name,
+                outcome(before),
+                outcome(after)
+            ),
+            BehaviorDifference::GoldenOutput {
+                command,
+                before_exit_code,
+                after_exit_code,
+                first_difference,
+            } => {
+                write!(f, "`{}` output changed", command)?;
+                if before_exit_code != after_exit_code {
+                    write!(
+                        f,
+                        " (exit code {} -> {})",
+                        before_exit_code, after_exit_code
+                    )?;
+                }
+                if let Some(line) = first_difference {
+                    write!(f, ": {}", line)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Before/after comparison attached to a refactoring task's report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BehaviorComparison {
+    pub baseline: BehaviorSnapshot,
+    pub after: BehaviorSnapshot,
+    pub differences: Vec<BehaviorDifference>,
+}
+
+impl BehaviorComparison {
+    pub fn is_clean(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// One line per difference, for logs and blocked-task reasons
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return "No behavioral differences".to_string();
+        }
+        let mut summary = format!("{} behavioral difference(s):", self.differences.len());
+        for difference in &self.differences {
+            summary.push_str("\n- ");
+            summary.push_str(&difference.to_string());
+        }
+        summary
+    }
+}
+
+impl BehaviorSnapshot {
+    /// Compare with a later snapshot of the same commands
+    ///
+    /// Tests that only appear afterwards and do not fail are new coverage,
+    /// not behavior changes.
+    pub fn compare(&self, after: &BehaviorSnapshot) -> BehaviorComparison {
+        let mut differences = Vec::new();
+
+        if let (Some(before), Some(now)) = (&self.tests, &after.tests) {
+            if (before.exit_code == 0) != (now.exit_code == 0) {
+                differences.push(BehaviorDifference::TestExitCode {
+                    before: before.exit_code,
+                    after: now.exit_code,
+                });
+            }
+            for (name, outcome) in &before.outcomes {
+                let later = now.outcomes.get(name).copied();
+                if later != Some(*outcome) {
+                    differences.push(BehaviorDifference::TestOutcome {
+                        name: name.clone(),
+                        before: Some(*outcome),
+                        after: later,
+                    });
+                }
+            }
+            for (name, outcome) in &now.outcomes {
+                if !before.outcomes.contains_key(name) && *outcome == TestOutcome::Failed {
+                    differences.push(BehaviorDifference::TestOutcome {
+                        name: name.clone(),
+                        before: None,
+                        after: Some(*outcome),
+                    });
+                }
+            }
+        }
+
+        for golden in &self.golden {
+            let Some(now) = after.golden.iter().find(|g| g.command == golden.command) else {
+                continue;
+            };
+            if golden == now {
+                continue;
+            }
+            let first_difference = golden
+                .stdout
+                .lines()
+                .map(Some)
+                .chain(std::iter::repeat(None))
+                .zip(now.stdout.lines().map(Some).chain(std::iter::repeat(None)))
+                .take_while(|(before, after)| before.is_some() || after.is_some())
+                .find(|(before, after)| before != after)
+                .map(|(before, after)| {
+                    format!(
+                        "expected {:?}, got {:?}",
+                        before.unwrap_or(""),
+                        after.unwrap_or("")
+                    )
+                });
+            differences.push(BehaviorDifference::GoldenOutput {
+                command: golden.command.clone(),
+                before_exit_code: golden.exit_code,
+                after_exit_code: now.exit_code,
+                first_difference,
+            });
+        }
+
+        BehaviorComparison {
+            baseline: self.clone(),
+            after: after.clone(),
+            differences,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn result(stdout: &str, exit_code: i32) -> ExecutionResult {
+        ExecutionResult {
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+            exit_code,
+            duration: Duration::ZERO,
+        }
+    }
+
+    fn snapshot(tests: &str, exit_code: i32, golden: &str) -> BehaviorSnapshot {
+        BehaviorSnapshot {
+            captured_at: Utc::now(),
+            tests: Some(TestSnapshot::from_result(
+                "cargo test",
+                &result(tests, exit_code),
+            )),
+            golden: vec![GoldenOutput::from_result("aca --help", &result(golden, 0))],
+        }
+    }
+
+    #[test]
+    fn test_parse_test_outcomes() {
+        let output = "test api::login ... ok\ntest api::logout ... FAILED\n\
+                      tests/test_db.py::test_connect PASSED\n    --- SKIP: TestSlow (0.00s)\n";
+        let tests = TestSnapshot::from_result("test", &result(output, 1));
+        assert_eq!(tests.outcomes["api::login"], TestOutcome::Passed);
+        assert_eq!(tests.outcomes["api::logout"], TestOutcome::Failed);
+        assert_eq!(
+            tests.outcomes["tests/test_db.py::test_connect"],
+            TestOutcome::Passed
+        );
+        assert_eq!(tests.outcomes["TestSlow"], TestOutcome::Skipped);
+    }
+
+    #[test]
+    fn test_compare_behavior_snapshots() {
+        let baseline = snapshot("test a ... ok\ntest b ... ok\n", 0, "usage: aca\n");
+
+        let same = snapshot(
+            "test a ... ok\ntest b ... ok\ntest c ... ok\n",
+            0,
+            "usage: aca  \n",
+        );
+        assert!(baseline.compare(&same).is_clean());
+
+        let changed = snapshot("test a ... FAILED\n", 101, "usage: aca run\n");
+        let comparison = baseline.compare(&changed);
+        assert_eq!(comparison.differences.len(), 4);
+        assert!(comparison.summary().contains("test a: passed -> failed"));
+        assert!(comparison.summary().contains("test b: passed -> missing"));
+        assert!(comparison.summary().contains("`aca --help` output changed"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Backoff between automatic retries of failed tasks.
+//!
+//! Each retry of a task waits longer than the one before: the first delay
+//! grows by [`RetryBackoff::multiplier`] with every failed retry, up to
+//! [`RetryBackoff::max_delay_secs`], and a random jitter spreads out tasks
+//! that failed together. The first delay depends on the kind of error. Rate
+//! limits wait longest, transient file system errors are retried at once,
+//! and anything else starts from `retry_delay_minutes`.
+//!
+//! ```toml
+//! [task_config]
+//! retry_delay_minutes = 5
+//!
+//! [task_config.retry_backoff]
+//! multiplier = 2.0
+//! max_delay_secs = 3600
+//! jitter = 0.2
+//! rate_limit_delay_secs = 900
+//! transient_delay_secs = 0
+//! ```
+
+use crate::task::TaskError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Block reason of a task waiting for its automatic retry
+pub const RETRY_REASON: &str = "Scheduled for automatic retry";
+
+/// Kind of failure, which decides how long a retry waits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    /// The provider refused the request for now
+    RateLimited,
+    /// A file system error likely to pass on its own
+    Transient,
+    Other,
+}
+
+impl RetryClass {
+    pub fn of(error: &TaskError) -> Self {
+        match error {
+            TaskError::ClaudeError {
+                message,
+                error_code,
+                ..
+            } => {
+                let text =
+                    format!("{} {}", error_code.as_deref().unwrap_or(""), message).to_lowercase();
+                let rate_limited = ["rate limit", "rate_limit", "429", "too many requests"]
+                    .iter()
+                    .any(|marker| text.contains(marker));
+                if rate_limited {
+                    Self::RateLimited
+                } else {
+                    Self::Other
+                }
+            }
+            TaskError::ResourceError { resource_type, .. }
+                if resource_type.to_lowercase().contains("rate") =>
+            {
+                Self::RateLimited
+            }
+            TaskError::FileSystemError { .. } => Self::Transient,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// How retry delays grow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryBackoff {
+    /// Factor the delay grows by with each failed retry
+    pub multiplier: f64,
+    /// Longest delay before a retry
+    pub max_delay_secs: u64,
+    /// Fraction of the delay randomly added or taken off
+    pub jitter: f64,
+    /// First delay after a rate-limit error
+    pub rate_limit_delay_secs: u64,
+    /// First delay after a transient error; 0 retries at once
+    pub transient_delay_secs: u64,
+}
+
+impl Default for RetryBackoff {
+    fn default() -> Self {
+        Self {
+            multiplier: 2.0,
+            max_delay_secs: 3600,
+            jitter: 0.2,
+            rate_limit_delay_secs: 900,
+            transient_delay_secs: 0,
+        }
+    }
+}
+
+impl RetryBackoff {
+    /// Delay before retrying a task that failed with `class` after `retries`
+    /// earlier retries
+    ///
+    /// `other_delay` is the first delay for errors of no particular class.
+    pub fn delay(&self, class: RetryClass, other_delay: Duration, retries: u32) -> Duration {
+        self.delay_with_sample(class, other_delay, retries, rand::random::<f64>())
+    }
+
+    /// [`delay`](Self::delay) with the jitter drawn from `sample` in `[0, 1)`
+    fn delay_with_sample(
+        &self,
+        class: RetryClass,
+        other_delay: Duration,
+        retries: u32,
+        sample: f64,
+    ) -> Duration {
+        let first = match class {
+            RetryClass::RateLimited => Duration::from_secs(self.rate_limit_delay_secs),
+            RetryClass::Transient => Duration::from_secs(self.transient_delay_secs),
+            RetryClass::Other => other_delay,
+        };
+        let max = Duration::from_secs(self.max_delay_secs).max(first);
+        let grown = first.as_secs_f64() * self.multiplier.max(1.0).powi(retries.min(32) as i32);
+        let jitter = self.jitter.clamp(0.0, 1.0) * (sample * 2.0 - 1.0);
+        Duration::from_secs_f64((grown.min(max.as_secs_f64()) * (1.0 + jitter)).max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claude_error(message: &str) -> TaskError {
+        TaskError::ClaudeError {
+            message: message.to_string(),
+            error_code: None,
+            retry_possible: true,
+        }
+    }
+
+    #[test]
+    fn test_error_classes() {
+        assert_eq!(
+            RetryClass::of(&claude_error("Failed: Rate limit exceeded: slow down")),
+            RetryClass::RateLimited
+        );
+        assert_eq!(
+            RetryClass::of(&claude_error("HTTP 429 Too Many Requests")),
+            RetryClass::RateLimited
+        );
+        assert_eq!(
+            RetryClass::of(&claude_error("Failed: invalid output")),
+            RetryClass::Other
+        );
+        assert_eq!(
+            RetryClass::of(&TaskError::FileSystemError {
+                message: "Resource busy".to_string(),
+                path: None,
+                operation: "write".to_string(),
+            }),
+            RetryClass::Transient
+        );
+    }
+
+    #[test]
+    fn test_delay_grows_caps_and_jitters() {
+        let backoff = RetryBackoff::default();
+        let five_minutes = Duration::from_secs(300);
+        let delay = |class, retries, sample| {
+            backoff.delay_with_sample(class, five_minutes, retries, sample)
+        };
+
+        // A sample of 0.5 adds no jitter
+        assert_eq!(delay(RetryClass::Other, 0, 0.5), five_minutes);
+        assert_eq!(delay(RetryClass::Other, 2, 0.5), Duration::from_secs(1200));
+        assert_eq!(delay(RetryClass::Other, 10, 0.5), Duration::from_secs(3600));
+        assert_eq!(
+            delay(RetryClass::RateLimited, 0, 0.5),
+            Duration::from_secs(900)
+        );
+        assert_eq!(delay(RetryClass::Transient, 3, 0.9), Duration::ZERO);
+
+        assert_eq!(delay(RetryClass::Other, 0, 0.0), Duration::from_secs(240));
+        assert_eq!(delay(RetryClass::Other, 0, 1.0), Duration::from_secs(360));
+        let jittered = backoff.delay(RetryClass::Other, five_minutes, 0);
+        assert!(jittered >= Duration::from_secs(240) && jittered <= Duration::from_secs(360));
+    }
+}
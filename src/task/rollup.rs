@@ -0,0 +1,317 @@
+//! Roll-up summaries for parent tasks completed by their subtasks.
+//!
+//! When the last child of a parent finishes, the parent's result gathers
+//! what the children did: the files they created and modified, their
+//! combined cost and run time, and each child's status. With
+//! [`RollupConfig::narrative`] set, the model also writes a short prose
+//! summary of the children's work, stored beside the figures.
+
+use crate::task::{Task, TaskId, TaskResult, TaskStatus};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Key of the roll-up in a parent's result output
+pub const ROLLUP_KEY: &str = "rollup";
+
+/// Key of a task's run time, in milliseconds, in its result output
+pub const DURATION_KEY: &str = "duration_ms";
+
+/// Roll-up summary settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RollupConfig {
+    /// Ask the model for a prose summary of each rolled-up parent
+    pub narrative: bool,
+}
+
+/// What one child contributed to a roll-up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildOutcome {
+    pub id: TaskId,
+    pub title: String,
+    pub status: String,
+    /// Failure or skip reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub estimated_cost: f64,
+    pub duration_ms: u64,
+}
+
+/// Aggregate of a parent's children, stored in the parent's result
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RollupSummary {
+    pub children: Vec<ChildOutcome>,
+    pub files_created: Vec<PathBuf>,
+    /// Modified files that no child created
+    pub files_modified: Vec<PathBuf>,
+    pub estimated_cost: f64,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub narrative: Option<String>,
+}
+
+impl RollupSummary {
+    /// Summarize `children`, in the order given
+    pub fn build(children: &[&Task]) -> Self {
+        let mut created = BTreeSet::new();
+        let mut modified = BTreeSet::new();
+        let mut summary = Self::default();
+        for child in children {
+            let (estimated_cost, duration_ms) = match &child.status {
+                TaskStatus::Completed { result, .. } => {
+                    match result {
+                        TaskResult::Success {
+                            files_created,
+                            files_modified,
+                            ..
+                        } => {
+                            created.extend(files_created.iter().cloned());
+                            modified.extend(files_modified.iter().cloned());
+                        }
+                        TaskResult::Partial { files_modified, .. } => {
+                            modified.extend(files_modified.iter().cloned());
+                        }
+                    }
+                    cost_and_duration(result)
+                }
+                _ => (0.0, 0),
+            };
+            summary.estimated_cost += estimated_cost;
+            summary.duration_ms += duration_ms;
+            summary.children.push(ChildOutcome {
+                id: child.id,
+                title: child.title.clone(),
+                status: child.status.name().to_string(),
+                note: match &child.status {
+                    TaskStatus::Failed { error, .. } => Some(error.to_string()),
+                    TaskStatus::Skipped { reason, .. } => Some(reason.clone()),
+                    _ => None,
+                },
+                estimated_cost,
+                duration_ms,
+            });
+        }
+        summary.files_modified = modified.difference(&created).cloned().collect();
+        summary.files_created = created.into_iter().collect();
+        summary
+    }
+
+    /// The roll-up stored in `task`'s result, if it was completed by its children
+    pub fn of(task: &Task) -> Option<Self> {
+        match &task.status {
+            TaskStatus::Completed {
+                result: TaskResult::Success { output, .. },
+                ..
+            } => serde_json::from_value(output.get(ROLLUP_KEY)?.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    fn count(&self, status: &str) -> usize {
+        self.children
+            .iter()
+            .filter(|child| child.status == status)
+            .count()
+    }
+
+    /// One line such as "3 of 4 subtasks completed, 1 failed"
+    pub fn headline(&self) -> String {
+        let completed = self.count("completed");
+        if completed == self.children.len() {
+            return format!("All {} subtasks completed", completed);
+        }
+        let mut headline = format!(
+            "{} of {} subtasks completed",
+            completed,
+            self.children.len()
+        );
+        for status in ["failed", "skipped"] {
+            let count = self.count(status);
+            if count > 0 {
+                headline.push_str(&format!(", {} {}", count, status));
+            }
+        }
+        headline
+    }
+
+    /// The parent's result
+    ///
+    /// The children's costs stay with the children; the output carries no
+    /// `token_usage`, so the roll-up is not billed twice.
+    pub fn into_result(self) -> TaskResult {
+        let files_created = self.files_created.clone();
+        let files_modified = self.files_modified.clone();
+        TaskResult::Success {
+            output: serde_json::json!({
+                "message": self.headline(),
+                ROLLUP_KEY: self,
+            }),
+            files_created,
+            files_modified,
+            build_artifacts: Vec::new(),
+        }
+    }
+
+    /// Instructions asking the model to describe the children's work
+    pub fn narrative_prompt(&self, parent: &Task) -> String {
+        let mut prompt = format!(
+            "The task \"{}\" was split into subtasks, which have all finished. \
+             Write a short summary (at most one paragraph) of what they achieved \
+             together, for a reviewer. Mention anything that failed or was skipped. \
+             Do not change any files.\n\n{}\n",
+            parent.title,
+            self.headline()
+        );
+        for child in &self.children {
+            prompt.push_str(&format!("\n- {} [{}]", child.title, child.status));
+            if let Some(note) = &child.note {
+                prompt.push_str(&format!(": {}", note));
+            }
+        }
+        let files: Vec<String> = self
+            .files_created
+            .iter()
+            .chain(&self.files_modified)
+            .map(|path| path.display().to_string())
+            .collect();
+        if !files.is_empty() {
+            prompt.push_str(&format!("\n\nFiles changed: {}", files.join(", ")));
+        }
+        prompt
+    }
+}
+
+/// Cost and run time recorded in a child's result, its own or rolled up
+fn cost_and_duration(result: &TaskResult) -> (f64, u64) {
+    let TaskResult::Success { output, .. } = result else {
+        return (0.0, 0);
+    };
+    if let Some(rollup) = output.get(ROLLUP_KEY) {
+        return (
+            rollup["estimated_cost"].as_f64().unwrap_or(0.0),
+            rollup[DURATION_KEY].as_u64().unwrap_or(0),
+        );
+    }
+    (
+        output["token_usage"]["estimated_cost"]
+            .as_f64()
+            .unwrap_or(0.0),
+        output
+            .get(DURATION_KEY)
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{TaskError, TaskSpec};
+    use chrono::Utc;
+
+    fn child(title: &str, status: TaskStatus) -> Task {
+        let mut task = Task::new(
+            TaskSpec {
+                title: title.to_string(),
+                description: title.to_string(),
+                ..Default::default()
+            },
+            None,
+        );
+        task.status = status;
+        task
+    }
+
+    fn completed(output: Value, created: &[&str], modified: &[&str]) -> TaskStatus {
+        TaskStatus::Completed {
+            completed_at: Utc::now(),
+            result: TaskResult::Success {
+                output,
+                files_created: created.iter().map(PathBuf::from).collect(),
+                files_modified: modified.iter().map(PathBuf::from).collect(),
+                build_artifacts: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_build_aggregates_children() {
+        let schema = child(
+            "Add schema",
+            completed(
+                serde_json::json!({"token_usage": {"estimated_cost": 0.5}, "duration_ms": 2000}),
+                &["migrations/001.sql"],
+                &["src/db.rs"],
+            ),
+        );
+        let api = child(
+            "Add endpoint",
+            completed(
+                serde_json::json!({"token_usage": {"estimated_cost": 0.25}, "duration_ms": 1000}),
+                &[],
+                &["src/db.rs", "migrations/001.sql", "src/api.rs"],
+            ),
+        );
+        let docs = child(
+            "Document endpoint",
+            TaskStatus::Failed {
+                failed_at: Utc::now(),
+                error: TaskError::Other {
+                    message: "Build failed".to_string(),
+                    source: None,
+                },
+                retry_count: 0,
+            },
+        );
+
+        let rollup = RollupSummary::build(&[&schema, &api, &docs]);
+        assert_eq!(rollup.headline(), "2 of 3 subtasks completed, 1 failed");
+        assert_eq!(
+            rollup.files_created,
+            vec![PathBuf::from("migrations/001.sql")]
+        );
+        assert_eq!(
+            rollup.files_modified,
+            vec![PathBuf::from("src/api.rs"), PathBuf::from("src/db.rs")]
+        );
+        assert_eq!(rollup.estimated_cost, 0.75);
+        assert_eq!(rollup.duration_ms, 3000);
+        assert!(rollup.children[2].note.is_some());
+    }
+
+    #[test]
+    fn test_nested_rollups_and_round_trip() {
+        let leaf = child(
+            "Leaf",
+            completed(
+                serde_json::json!({"token_usage": {"estimated_cost": 1.0}, "duration_ms": 500}),
+                &["a.rs"],
+                &[],
+            ),
+        );
+        let inner = child(
+            "Inner",
+            TaskStatus::Completed {
+                completed_at: Utc::now(),
+                result: RollupSummary::build(&[&leaf]).into_result(),
+            },
+        );
+        let stored = RollupSummary::of(&inner).unwrap();
+        assert_eq!(stored.headline(), "All 1 subtasks completed");
+        assert!(RollupSummary::of(&leaf).is_none());
+
+        // A nested parent counts its own children's cost, not zero
+        let outer = RollupSummary::build(&[&inner]);
+        assert_eq!(outer.estimated_cost, 1.0);
+        assert_eq!(outer.duration_ms, 500);
+        assert_eq!(outer.files_created, vec![PathBuf::from("a.rs")]);
+        assert!(
+            outer
+                .narrative_prompt(&inner)
+                .contains("- Inner [completed]")
+        );
+    }
+}
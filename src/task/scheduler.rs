@@ -64,6 +64,35 @@ pub struct TaskSelection {
     pub estimated_resources: ResourceRequirement,
 }
 
+/// One weighted term of a task's scheduling score
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreComponent {
+    pub name: &'static str,
+    pub value: f64,
+    pub weight: f64,
+    /// `value * weight`
+    pub contribution: f64,
+}
+
+impl ScoreComponent {
+    fn new(name: &'static str, value: f64, weight: f64) -> Self {
+        Self {
+            name,
+            value,
+            weight,
+            contribution: value * weight,
+        }
+    }
+}
+
+/// How a task's scheduling score is made up
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreBreakdown {
+    pub components: Vec<ScoreComponent>,
+    /// Sum of the contributions, clamped to 0-100
+    pub total: f64,
+}
+
 /// Resource requirements for a task
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceRequirement {
@@ -187,27 +216,57 @@ impl TaskScheduler {
     /// Calculate comprehensive task score
     fn calculate_task_score(&self, task_id: TaskId, tree: &TaskTree) -> f64 {
         let task = tree.get_task(task_id).unwrap();
+        self.score_breakdown(task, tree).total
+    }
 
-        let priority_score = self.calculate_priority_score(task);
-        let dependency_score = self.calculate_dependency_score(task, tree);
-        let context_score = self.calculate_context_score(task);
-        let resource_score = self.calculate_resource_score(task);
-        let history_score = self.calculate_history_score(task);
-        let age_score = self.calculate_age_score(task);
-        let complexity_score = self.calculate_complexity_score(task);
-
+    /// Each weighted component of a task's score and the normalized total
+    pub fn score_breakdown(&self, task: &Task, tree: &TaskTree) -> ScoreBreakdown {
         let weights = &self.scoring_weights;
-
-        let total_score = priority_score * weights.priority_weight
-            + dependency_score * weights.dependency_weight
-            + context_score * weights.context_similarity_weight
-            + resource_score * weights.resource_availability_weight
-            + history_score * weights.failure_penalty_weight
-            + age_score * weights.age_bonus_weight
-            + complexity_score * weights.complexity_weight;
+        let components = vec![
+            ScoreComponent::new(
+                "priority",
+                self.calculate_priority_score(task),
+                weights.priority_weight,
+            ),
+            ScoreComponent::new(
+                "dependency",
+                self.calculate_dependency_score(task, tree),
+                weights.dependency_weight,
+            ),
+            ScoreComponent::new(
+                "context_similarity",
+                self.calculate_context_score(task),
+                weights.context_similarity_weight,
+            ),
+            ScoreComponent::new(
+                "resource_availability",
+                self.calculate_resource_score(task),
+                weights.resource_availability_weight,
+            ),
+            ScoreComponent::new(
+                "failure_history",
+                self.calculate_history_score(task),
+                weights.failure_penalty_weight,
+            ),
+            ScoreComponent::new(
+                "age",
+                self.calculate_age_score(task),
+                weights.age_bonus_weight,
+            ),
+            ScoreComponent::new(
+                "complexity",
+                self.calculate_complexity_score(task),
+                weights.complexity_weight,
+            ),
+        ];
 
         // Normalize score to 0-100 range
-        total_score.clamp(0.0, 100.0)
+        let total = components
+            .iter()
+            .map(|component| component.contribution)
+            .sum::<f64>()
+            .clamp(0.0, 100.0);
+        ScoreBreakdown { components, total }
     }
 
     /// Calculate priority-based score
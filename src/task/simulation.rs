@@ -0,0 +1,437 @@
+//! Dry-run simulation of an execution plan against provider limits.
+//!
+//! [`PlanSimulator`] walks a plan's tasks in order on a simulated clock. Each
+//! task gets a token and duration estimate from its metadata; the Claude rate
+//! limits stretch tasks whose tokens cannot be spent within their duration,
+//! and the [`ScheduleConfig`] windows and daily token quota hold tasks back
+//! exactly as the execution loop would pause them. The resulting
+//! [`PlanSimulation`] tells `aca run --dry-run` when the plan should finish
+//! and whether that is before the requested `--deadline`.
+
+use super::calibration::Calibration;
+use super::execution_plan::ExecutionPlan;
+use super::types::{ComplexityLevel, TaskMetadata, TaskSpec};
+use super::window::{ScheduleConfig, ScheduleDecision};
+use crate::claude::types::RateLimitConfig;
+use anyhow::{Result, bail};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+/// How far ahead the simulation looks for the schedule to allow a task
+pub const MAX_SCHEDULE_WAIT_DAYS: i64 = 14;
+
+/// Token and wall-clock estimate for one task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskEstimate {
+    pub tokens: u64,
+    pub duration: TimeDelta,
+}
+
+impl TaskEstimate {
+    /// Typical usage for a complexity level; tasks without one count as simple
+    pub fn for_complexity(complexity: Option<&ComplexityLevel>) -> Self {
+        let (tokens, minutes) = match complexity {
+            Some(ComplexityLevel::Trivial) => (20_000, 3),
+            None | Some(ComplexityLevel::Simple) => (60_000, 10),
+            Some(ComplexityLevel::Moderate) => (150_000, 30),
+            Some(ComplexityLevel::Complex) => (400_000, 120),
+            Some(ComplexityLevel::Epic) => (1_000_000, 360),
+        };
+        Self {
+            tokens,
+            duration: TimeDelta::minutes(minutes),
+        }
+    }
+
+    /// Estimate from task metadata; an explicit duration wins over the
+    /// complexity default
+    pub fn for_task(spec: &TaskSpec) -> Self {
+        Self::for_metadata(&spec.metadata)
+    }
+
+    /// Estimate from task metadata alone
+    pub fn for_metadata(metadata: &TaskMetadata) -> Self {
+        let mut estimate = Self::for_complexity(metadata.estimated_complexity.as_ref());
+        if let Some(duration) = metadata.estimated_duration {
+            estimate.duration = duration;
+        }
+        estimate
+    }
+}
+
+/// One task on the simulated timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedTask {
+    pub title: String,
+    pub start: NaiveDateTime,
+    pub finish: NaiveDateTime,
+    pub tokens: u64,
+    /// Time held back by execution windows or the daily quota
+    pub schedule_wait: TimeDelta,
+    /// Extra time spent waiting for the per-minute rate limits
+    pub rate_limit_wait: TimeDelta,
+    /// Why the task was held back, if it was
+    pub pause_reason: Option<String>,
+}
+
+/// Predicted timeline of a plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanSimulation {
+    pub start: NaiveDateTime,
+    pub finish: NaiveDateTime,
+    pub tasks: Vec<SimulatedTask>,
+    pub total_tokens: u64,
+    /// Set when the schedule never allowed the remaining tasks to start
+    pub stalled: Option<String>,
+    /// Tasks left unscheduled because the simulation stalled
+    pub unscheduled: usize,
+}
+
+impl PlanSimulation {
+    pub fn schedule_wait(&self) -> TimeDelta {
+        self.tasks.iter().map(|task| task.schedule_wait).sum()
+    }
+
+    pub fn rate_limit_wait(&self) -> TimeDelta {
+        self.tasks.iter().map(|task| task.rate_limit_wait).sum()
+    }
+
+    /// Whether all tasks finish by `deadline`
+    pub fn meets_deadline(&self, deadline: NaiveDateTime) -> bool {
+        self.stalled.is_none() && self.finish <= deadline
+    }
+
+    /// Human-readable summary for dry-run output
+    pub fn report(&self, deadline: Option<NaiveDateTime>) -> String {
+        let mut lines = vec![format!(
+            "Estimated {} task(s), ~{} tokens, finishing {} ({} from now)",
+            self.tasks.len(),
+            self.total_tokens,
+            self.finish.format("%a %Y-%m-%d %H:%M"),
+            format_delta(self.finish - self.start)
+        )];
+        if self.schedule_wait() > TimeDelta::zero() {
+            lines.push(format!(
+                "  includes {} paused by execution windows or the daily quota",
+                format_delta(self.schedule_wait())
+            ));
+        }
+        if self.rate_limit_wait() > TimeDelta::zero() {
+            lines.push(format!(
+                "  includes {} waiting on rate limits",
+                format_delta(self.rate_limit_wait())
+            ));
+        }
+        if let Some(reason) = &self.stalled {
+            lines.push(format!("⚠️  Plan cannot finish: {}", reason));
+        }
+        if let Some(deadline) = deadline {
+            if self.meets_deadline(deadline) {
+                lines.push(format!(
+                    "Finishes before the deadline {} with {} to spare",
+                    deadline.format("%a %Y-%m-%d %H:%M"),
+                    format_delta(deadline - self.finish)
+                ));
+            } else {
+                let late = self
+                    .tasks
+                    .iter()
+                    .filter(|task| task.finish > deadline)
+                    .count()
+                    + self.unscheduled;
+                lines.push(format!(
+                    "⚠️  Plan will not finish before the deadline {}; {} task(s) would still be pending",
+                    deadline.format("%a %Y-%m-%d %H:%M"),
+                    late
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Simulates plan execution under rate limits and a schedule
+#[derive(Debug, Clone)]
+pub struct PlanSimulator<'a> {
+    rate_limits: &'a RateLimitConfig,
+    schedule: &'a ScheduleConfig,
+    calibration: Option<&'a Calibration>,
+}
+
+impl<'a> PlanSimulator<'a> {
+    pub fn new(rate_limits: &'a RateLimitConfig, schedule: &'a ScheduleConfig) -> Self {
+        Self {
+            rate_limits,
+            schedule,
+            calibration: None,
+        }
+    }
+
+    /// Scale task durations by how long past estimates actually took
+    pub fn with_calibration(mut self, calibration: &'a Calibration) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
+    /// Run the plan's tasks one after another from `start`
+    ///
+    /// `tokens_used_today` seeds the daily quota, which resets at midnight of
+    /// the simulated clock.
+    pub fn simulate(
+        &self,
+        plan: &ExecutionPlan,
+        start: NaiveDateTime,
+        tokens_used_today: u64,
+    ) -> Result<PlanSimulation> {
+        let mut clock = start;
+        let mut quota_day = start.date();
+        let mut used_today = tokens_used_today;
+        let mut tasks = Vec::new();
+        let mut stalled = None;
+
+        for spec in &plan.task_specs {
+            let mut estimate = TaskEstimate::for_task(spec);
+            if let Some(calibration) = self.calibration {
+                estimate.duration = calibration.apply(
+                    estimate.duration,
+                    spec.metadata.estimated_complexity.as_ref(),
+                );
+            }
+
+            let ready = clock;
+            let mut pause_reason = None;
+            let limit = ready + TimeDelta::days(MAX_SCHEDULE_WAIT_DAYS);
+            loop {
+                if clock.date() != quota_day {
+                    quota_day = clock.date();
+                    used_today = 0;
+                }
+                match self.schedule.check_at(clock, used_today)? {
+                    ScheduleDecision::Run => break,
+                    ScheduleDecision::Pause { reason } => {
+                        if clock >= limit {
+                            stalled = Some(format!(
+                                "'{}' held back for over {} days ({})",
+                                spec.title, MAX_SCHEDULE_WAIT_DAYS, reason
+                            ));
+                            break;
+                        }
+                        pause_reason.get_or_insert(reason);
+                        clock += TimeDelta::minutes(1);
+                    }
+                }
+            }
+            if stalled.is_some() {
+                break;
+            }
+
+            let work = estimate.duration;
+            let run_time = work.max(self.rate_limited_time(estimate.tokens));
+            let task_start = clock;
+            clock += run_time;
+            used_today += estimate.tokens;
+
+            tasks.push(SimulatedTask {
+                title: spec.title.clone(),
+                start: task_start,
+                finish: clock,
+                tokens: estimate.tokens,
+                schedule_wait: task_start - ready,
+                rate_limit_wait: run_time - work,
+                pause_reason,
+            });
+        }
+
+        Ok(PlanSimulation {
+            start,
+            finish: tasks.last().map_or(start, |task| task.finish),
+            total_tokens: tasks.iter().map(|task| task.tokens).sum(),
+            unscheduled: plan.task_specs.len() - tasks.len(),
+            tasks,
+            stalled,
+        })
+    }
+
+    /// Minimum time to spend `tokens` under the per-minute limits
+    fn rate_limited_time(&self, tokens: u64) -> TimeDelta {
+        let per_minute = self.rate_limits.max_tokens_per_minute;
+        if per_minute == 0 {
+            return TimeDelta::zero();
+        }
+        TimeDelta::minutes(tokens.div_ceil(per_minute) as i64)
+    }
+}
+
+/// Parse a `--deadline` value relative to `now`
+///
+/// Accepts a duration (`90m`, `8h`, `2d`), a time of day (`06:00`, the next
+/// occurrence) or a date and time (`2025-10-20 09:00`).
+pub fn parse_deadline(value: &str, now: NaiveDateTime) -> Result<NaiveDateTime> {
+    let value = value.trim();
+
+    if let Some(unit) = value.chars().last().filter(|c| c.is_ascii_alphabetic())
+        && let Ok(amount) = value[..value.len() - 1].parse::<i64>()
+    {
+        let delta = match unit {
+            'm' => TimeDelta::minutes(amount),
+            'h' => TimeDelta::hours(amount),
+            'd' => TimeDelta::days(amount),
+            _ => bail!("Unknown deadline unit '{}', expected m, h or d", unit),
+        };
+        return Ok(now + delta);
+    }
+
+    if let Ok(time) = NaiveTime::parse_from_str(value, "%H:%M") {
+        let today = now.date().and_time(time);
+        return Ok(if today > now {
+            today
+        } else {
+            today + TimeDelta::days(1)
+        });
+    }
+
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(at) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(at);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN));
+    }
+
+    bail!(
+        "Invalid deadline '{}', expected e.g. 8h, 06:00 or 2025-10-20 09:00",
+        value
+    )
+}
+
+fn format_delta(delta: TimeDelta) -> String {
+    let minutes = delta.num_minutes().max(0);
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {:02}m", h, m),
+        (d, h, m) => format!("{}d {}h {:02}m", d, h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::types::TaskMetadata;
+    use crate::task::window::TimeWindow;
+    use std::time::Duration;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2025-10-13 is a Monday
+        NaiveDate::from_ymd_opt(2025, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn rate_limits(max_tokens_per_minute: u64) -> RateLimitConfig {
+        RateLimitConfig {
+            max_tokens_per_minute,
+            max_requests_per_minute: 50,
+            burst_allowance: 0,
+            backoff_multiplier: 2.0,
+            max_backoff_delay: Duration::from_secs(600),
+        }
+    }
+
+    fn plan(complexities: &[ComplexityLevel]) -> ExecutionPlan {
+        let mut plan = ExecutionPlan::new();
+        for (i, complexity) in complexities.iter().enumerate() {
+            plan.task_specs.push(TaskSpec {
+                title: format!("Task {}", i + 1),
+                description: String::new(),
+                dependencies: Vec::new(),
+                metadata: TaskMetadata {
+                    estimated_complexity: Some(complexity.clone()),
+                    ..Default::default()
+                },
+                plan: None,
+            });
+        }
+        plan
+    }
+
+    #[test]
+    fn test_rate_limits_stretch_tasks() {
+        let limits = rate_limits(1_000);
+        let schedule = ScheduleConfig::default();
+        let simulation = PlanSimulator::new(&limits, &schedule)
+            .simulate(&plan(&[ComplexityLevel::Trivial]), at(13, 9, 0), 0)
+            .unwrap();
+
+        // 20k tokens at 1k/minute take 20 minutes instead of the estimated 3
+        assert_eq!(simulation.finish, at(13, 9, 20));
+        assert_eq!(simulation.rate_limit_wait(), TimeDelta::minutes(17));
+        assert!(simulation.stalled.is_none());
+    }
+
+    #[test]
+    fn test_windows_and_quota_delay_tasks() {
+        let limits = rate_limits(1_000_000);
+        let schedule = ScheduleConfig {
+            windows: vec![TimeWindow::new("02:00", "06:00")],
+            daily_token_quota: Some(100_000),
+            min_quota_remaining: 50_000,
+            ..Default::default()
+        };
+        let simulation = PlanSimulator::new(&limits, &schedule)
+            .simulate(
+                &plan(&[ComplexityLevel::Simple, ComplexityLevel::Simple]),
+                at(13, 1, 0),
+                0,
+            )
+            .unwrap();
+
+        // First task waits for the window; the second for the next day's quota
+        assert_eq!(simulation.tasks[0].start, at(13, 2, 0));
+        assert_eq!(simulation.tasks[1].start, at(14, 2, 0));
+        assert!(
+            simulation.tasks[1]
+                .pause_reason
+                .as_deref()
+                .unwrap()
+                .contains("quota")
+        );
+        assert!(!simulation.meets_deadline(at(14, 0, 0)));
+        assert!(simulation.meets_deadline(at(14, 3, 0)));
+        assert!(
+            simulation
+                .report(Some(at(14, 0, 0)))
+                .contains("will not finish")
+        );
+    }
+
+    #[test]
+    fn test_unreachable_schedule_stalls() {
+        let limits = rate_limits(40_000);
+        let schedule = ScheduleConfig {
+            daily_token_quota: Some(10_000),
+            min_quota_remaining: 20_000,
+            ..Default::default()
+        };
+        let simulation = PlanSimulator::new(&limits, &schedule)
+            .simulate(&plan(&[ComplexityLevel::Simple]), at(13, 9, 0), 0)
+            .unwrap();
+        assert!(simulation.tasks.is_empty());
+        assert!(simulation.stalled.is_some());
+        assert!(!simulation.meets_deadline(at(30, 0, 0)));
+    }
+
+    #[test]
+    fn test_parse_deadline() {
+        let now = at(13, 22, 0);
+        assert_eq!(parse_deadline("8h", now).unwrap(), at(14, 6, 0));
+        assert_eq!(parse_deadline("06:00", now).unwrap(), at(14, 6, 0));
+        assert_eq!(parse_deadline("23:30", now).unwrap(), at(13, 23, 30));
+        assert_eq!(
+            parse_deadline("2025-10-20 09:00", now).unwrap(),
+            at(20, 9, 0)
+        );
+        assert!(parse_deadline("8w", now).is_err());
+        assert!(parse_deadline("tomorrow", now).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Mutation-style spot checks on critical tasks.
+//!
+//! A spot check asks whether the tests a task wrote would catch its change
+//! being lost. The task's implementation changes are reverted while its test
+//! changes are kept, and the test command must then fail; with the
+//! implementation re-applied it must pass again. Tasks carrying the
+//! configured tag are checked, and the outcome is recorded in their result.
+
+use crate::task::verification::{restorable_changes, write_state};
+use crate::workspace::WorkspaceSnapshot;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Spot check settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpotCheckConfig {
+    pub enabled: bool,
+    /// Task tag that asks for a spot check
+    pub tag: String,
+    /// Test command to run; the `[verification]` command when unset
+    pub test_command: Option<String>,
+    /// Directory names (ending in `/`) and file name fragments that mark
+    /// test files
+    pub test_patterns: Vec<String>,
+}
+
+impl Default for SpotCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tag: "critical".to_string(),
+            test_command: None,
+            test_patterns: [
+                "tests/",
+                "test/",
+                "__tests__/",
+                "spec/",
+                "test_",
+                "_test.",
+                ".test.",
+                ".spec.",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl SpotCheckConfig {
+    /// Whether a task with these tags is spot checked
+    pub fn applies_to(&self, tags: &[String]) -> bool {
+        self.enabled && tags.iter().any(|tag| tag.eq_ignore_ascii_case(&self.tag))
+    }
+
+    /// Whether `path` holds tests rather than implementation
+    pub fn is_test_file(&self, path: &Path) -> bool {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        self.test_patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('/') {
+                Some(dir) => path
+                    .parent()
+                    .is_some_and(|parent| parent.iter().any(|part| part == dir)),
+                None => file_name.contains(pattern.as_str()),
+            })
+    }
+}
+
+/// What a spot check found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum SpotCheckOutcome {
+    /// Tests fail without the implementation and pass with it
+    Covered,
+    /// Tests still pass with the implementation reverted
+    NotCovered,
+    /// Tests fail even with the implementation applied
+    FailsWithChange,
+    /// The check could not run
+    Skipped { reason: String },
+}
+
+/// Spot check result recorded in a task's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpotCheckReport {
+    #[serde(flatten)]
+    pub outcome: SpotCheckOutcome,
+    pub command: String,
+    /// Implementation files reverted for the check
+    pub reverted_files: Vec<PathBuf>,
+    /// Test files kept while reverted
+    pub test_files: Vec<PathBuf>,
+}
+
+/// The workspace as a task found it, for checking the task afterwards
+#[derive(Debug)]
+pub struct SpotCheck {
+    root: PathBuf,
+    ignore: Vec<String>,
+    before: WorkspaceSnapshot,
+}
+
+impl SpotCheck {
+    /// Snapshot the workspace before the task runs
+    pub fn start(root: &Path, ignore: &[String]) -> io::Result<Self> {
+        Ok(Self {
+            root: root.to_path_buf(),
+            ignore: ignore.to_vec(),
+            before: WorkspaceSnapshot::capture(root, ignore)?,
+        })
+    }
+
+    /// Check the task's changes since [`start`](Self::start)
+    ///
+    /// `passes` runs the test command against the workspace as it is. The
+    /// workspace is left as the task finished it.
+    pub async fn run<F>(
+        &self,
+        config: &SpotCheckConfig,
+        command: &str,
+        mut passes: impl FnMut() -> F,
+    ) -> anyhow::Result<SpotCheckReport>
+    where
+        F: Future<Output = anyhow::Result<bool>>,
+    {
+        let after = WorkspaceSnapshot::capture(&self.root, &self.ignore)?;
+        let (changes, unrestorable) = restorable_changes(&self.before, &after);
+        let (mut test_files, mut implementation): (Vec<_>, Vec<_>) = changes
+            .keys()
+            .chain(&unrestorable)
+            .cloned()
+            .partition(|path| config.is_test_file(path));
+        test_files.sort();
+        implementation.sort();
+
+        let mut report = SpotCheckReport {
+            outcome: SpotCheckOutcome::Covered,
+            command: command.to_string(),
+            reverted_files: implementation,
+            test_files,
+        };
+        let skip_reason = if report.test_files.is_empty() {
+            Some("the task changed no test files")
+        } else if report.reverted_files.is_empty() {
+            Some("the task only changed test files")
+        } else if report
+            .reverted_files
+            .iter()
+            .any(|path| unrestorable.contains(path))
+        {
+            Some("an implementation file is too large to revert")
+        } else {
+            None
+        };
+        if let Some(reason) = skip_reason {
+            report.outcome = SpotCheckOutcome::Skipped {
+                reason: reason.to_string(),
+            };
+            return Ok(report);
+        }
+
+        let reverted = async {
+            for path in &report.reverted_files {
+                write_state(&self.root, path, self.before.contents(path))?;
+            }
+            passes().await
+        }
+        .await;
+        // Re-apply the task's changes whether or not the run succeeded
+        for path in &report.reverted_files {
+            write_state(&self.root, path, changes[path].as_deref())?;
+        }
+        let passes_reverted = reverted?;
+        let passes_applied = passes().await?;
+
+        report.outcome = match (passes_reverted, passes_applied) {
+            (false, true) => SpotCheckOutcome::Covered,
+            (true, _) => SpotCheckOutcome::NotCovered,
+            (false, false) => SpotCheckOutcome::FailsWithChange,
+        };
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> PathBuf {
+        let workspace =
+            std::env::temp_dir().join(format!("aca-spot-check-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        std::fs::write(workspace.join("src/lib.rs"), "fn add() -> i32 { 0 }").unwrap();
+        workspace
+    }
+
+    /// Passes when the implementation and its test agree
+    async fn run_tests(root: &Path) -> anyhow::Result<bool> {
+        let test = std::fs::read_to_string(root.join("tests/add.rs"))?;
+        let lib = std::fs::read_to_string(root.join("src/lib.rs"))?;
+        Ok(test.contains("expect 2") == lib.contains("1 + 1"))
+    }
+
+    #[tokio::test]
+    async fn test_reverted_implementation_must_fail_tests() {
+        let root = workspace();
+        let config = SpotCheckConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let check = SpotCheck::start(&root, &[]).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn add() -> i32 { 1 + 1 }").unwrap();
+        std::fs::create_dir(root.join("tests")).unwrap();
+        std::fs::write(root.join("tests/add.rs"), "expect 2").unwrap();
+
+        let report = check
+            .run(&config, "cargo test", || run_tests(&root))
+            .await
+            .unwrap();
+        assert_eq!(report.outcome, SpotCheckOutcome::Covered);
+        assert_eq!(report.reverted_files, [PathBuf::from("src/lib.rs")]);
+        assert_eq!(report.test_files, [PathBuf::from("tests/add.rs")]);
+        // The task's change is back in place
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "fn add() -> i32 { 1 + 1 }"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tests_that_ignore_the_change_are_not_covering() {
+        let root = workspace();
+        let config = SpotCheckConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let check = SpotCheck::start(&root, &[]).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn add() -> i32 { 1 + 1 }").unwrap();
+        let report = check
+            .run(&config, "cargo test", || async { Ok(true) })
+            .await
+            .unwrap();
+        assert!(matches!(report.outcome, SpotCheckOutcome::Skipped { .. }));
+
+        std::fs::write(root.join("src/add_test.rs"), "always passes").unwrap();
+        let report = check
+            .run(&config, "cargo test", || async { Ok(true) })
+            .await
+            .unwrap();
+        assert_eq!(report.outcome, SpotCheckOutcome::NotCovered);
+        assert_eq!(report.test_files, [PathBuf::from("src/add_test.rs")]);
+    }
+
+    #[tokio::test]
+    async fn test_large_implementation_files_are_not_reverted() {
+        let root = workspace();
+        let config = SpotCheckConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let check = SpotCheck::start(&root, &[]).unwrap();
+        std::fs::write(root.join("src/table.bin"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+        std::fs::create_dir(root.join("tests")).unwrap();
+        std::fs::write(root.join("tests/add.rs"), "expect 2").unwrap();
+
+        let report = check
+            .run(&config, "cargo test", || async { Ok(true) })
+            .await
+            .unwrap();
+        assert!(matches!(report.outcome, SpotCheckOutcome::Skipped { .. }));
+        assert_eq!(report.reverted_files, [PathBuf::from("src/table.bin")]);
+        assert!(root.join("src/table.bin").exists());
+    }
+
+    #[test]
+    fn test_test_file_patterns() {
+        let config = SpotCheckConfig::default();
+        assert!(config.is_test_file(Path::new("tests/api.rs")));
+        assert!(config.is_test_file(Path::new("web/__tests__/app.tsx")));
+        assert!(config.is_test_file(Path::new("app/test_models.py")));
+        assert!(config.is_test_file(Path::new("src/button.spec.ts")));
+        assert!(!config.is_test_file(Path::new("src/latests/lib.rs")));
+        assert!(!config.is_test_file(Path::new("src/tests.rs")));
+
+        // Off unless enabled
+        assert!(!config.applies_to(&["critical".to_string()]));
+        let config = SpotCheckConfig {
+            enabled: true,
+            ..config
+        };
+        assert!(config.applies_to(&["Critical".to_string()]));
+        assert!(!config.applies_to(&["refactor".to_string()]));
+    }
+}
@@ -0,0 +1,127 @@
+//! Tasks that expand into a sub-plan file.
+//!
+//! A plan task with `plan = "subsystem/tasks.md"` does not run itself. When
+//! the engine reaches it, the file is parsed by the registered
+//! [`PlanLoader`], its tasks are added as the task's children and run in
+//! order, and the task completes once they all have. Sub-plans may name
+//! further sub-plans; [`SubPlanStack`] stops a file from including itself.
+
+use super::execution_plan::ExecutionPlan;
+use anyhow::{Result, bail};
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+
+/// Sub-plans that may be open inside one another
+pub const MAX_SUB_PLAN_DEPTH: usize = 8;
+
+/// Parses sub-plan files into execution plans
+pub trait PlanLoader: Send + Sync {
+    fn load<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<ExecutionPlan>>;
+}
+
+/// Where a task's `plan` file is
+///
+/// Relative paths are resolved against the directory of the plan that names
+/// them, or the workspace for plans not loaded from a file.
+pub fn resolve_sub_plan_path(plan: &Path, including: Option<&Path>, workspace: &Path) -> PathBuf {
+    if plan.is_absolute() {
+        return plan.to_path_buf();
+    }
+    match including.and_then(Path::parent) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(plan),
+        _ => workspace.join(plan),
+    }
+}
+
+/// The chain of sub-plan files currently being expanded, outermost first
+#[derive(Debug, Clone, Default)]
+pub struct SubPlanStack {
+    files: Vec<PathBuf>,
+}
+
+impl SubPlanStack {
+    /// The stack with `path` opened inside it
+    ///
+    /// Fails if `path` is already open, which would expand forever, or the
+    /// stack is [`MAX_SUB_PLAN_DEPTH`] deep.
+    pub fn enter(&self, path: &Path) -> Result<Self> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.files.contains(&path) {
+            let chain: Vec<String> = self
+                .files
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|file| file.display().to_string())
+                .collect();
+            bail!("Sub-plan includes itself: {}", chain.join(" -> "));
+        }
+        if self.files.len() >= MAX_SUB_PLAN_DEPTH {
+            bail!(
+                "Sub-plans are nested more than {} deep at {}",
+                MAX_SUB_PLAN_DEPTH,
+                path.display()
+            );
+        }
+
+        let mut files = self.files.clone();
+        files.push(path);
+        Ok(Self { files })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.files.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sub_plan_paths_resolve_against_the_including_plan() {
+        let workspace = Path::new("/work");
+        assert_eq!(
+            resolve_sub_plan_path(
+                Path::new("payments/tasks.md"),
+                Some(Path::new("/work/plans/program.toml")),
+                workspace
+            ),
+            PathBuf::from("/work/plans/payments/tasks.md")
+        );
+        assert_eq!(
+            resolve_sub_plan_path(Path::new("payments/tasks.md"), None, workspace),
+            PathBuf::from("/work/payments/tasks.md")
+        );
+        assert_eq!(
+            resolve_sub_plan_path(
+                Path::new("/elsewhere/tasks.md"),
+                Some(Path::new("plan.json")),
+                workspace
+            ),
+            PathBuf::from("/elsewhere/tasks.md")
+        );
+    }
+
+    #[test]
+    fn test_stack_rejects_cycles_and_deep_nesting() {
+        let stack = SubPlanStack::default()
+            .enter(Path::new("/plans/a.md"))
+            .unwrap()
+            .enter(Path::new("/plans/b.md"))
+            .unwrap();
+        assert_eq!(stack.depth(), 2);
+        let error = stack.enter(Path::new("/plans/a.md")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Sub-plan includes itself: /plans/a.md -> /plans/b.md -> /plans/a.md"
+        );
+
+        let mut deep = SubPlanStack::default();
+        for level in 0..MAX_SUB_PLAN_DEPTH {
+            deep = deep
+                .enter(Path::new(&format!("/plans/{}.md", level)))
+                .unwrap();
+        }
+        assert!(deep.enter(Path::new("/plans/last.md")).is_err());
+    }
+}
@@ -0,0 +1,403 @@
+//! Suspension of the subprocesses behind running tasks.
+//!
+//! [`TaskManager::pause_task`](super::TaskManager::pause_task) marks a task
+//! [`TaskStatus::Paused`](super::TaskStatus::Paused) and asks the registered
+//! [`TaskSuspender`] to freeze whatever is executing it, so the work can later
+//! continue exactly where it stopped instead of being restarted. On the host
+//! this sends `SIGSTOP`/`SIGCONT` to the process group of the provider
+//! process recorded in a [`ProcessRegistry`], so tools it started are frozen
+//! with it; in container mode the session container is paused too.
+//!
+//! A suspended process's request timeout stops counting:
+//! [`ProcessRegistration::timeout`] only counts the time it was running, so a
+//! task can stay paused for longer than the timeout and still finish.
+
+use super::types::TaskId;
+use anyhow::{Result, bail};
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Freezes and thaws the execution behind a task
+pub trait TaskSuspender: Send + Sync {
+    /// Suspend the task's execution; `false` if nothing was running for it
+    fn suspend(&self, task_id: TaskId) -> BoxFuture<'_, Result<bool>>;
+
+    /// Resume previously suspended execution; `false` if nothing was running
+    fn resume(&self, task_id: TaskId) -> BoxFuture<'_, Result<bool>>;
+}
+
+/// Time a process has spent running, not counting time it was suspended
+#[derive(Debug)]
+struct RunClock {
+    state: Mutex<ClockState>,
+    changed: Notify,
+}
+
+#[derive(Debug)]
+struct ClockState {
+    started: Instant,
+    suspended_for: Duration,
+    suspended_at: Option<Instant>,
+}
+
+impl RunClock {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(ClockState {
+                started: Instant::now(),
+                suspended_for: Duration::ZERO,
+                suspended_at: None,
+            }),
+            changed: Notify::new(),
+        }
+    }
+
+    fn set_suspended(&self, suspended: bool) {
+        let mut state = self.state.lock().unwrap();
+        match (suspended, state.suspended_at) {
+            (true, None) => state.suspended_at = Some(Instant::now()),
+            (false, Some(since)) => {
+                state.suspended_for += since.elapsed();
+                state.suspended_at = None;
+            }
+            _ => return,
+        }
+        self.changed.notify_waiters();
+    }
+
+    /// Running time left before `limit`; `None` while suspended
+    fn remaining(&self, limit: Duration) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        if state.suspended_at.is_some() {
+            return None;
+        }
+        let running = state.started.elapsed().saturating_sub(state.suspended_for);
+        Some(limit.saturating_sub(running))
+    }
+}
+
+#[derive(Debug)]
+struct RegisteredProcess {
+    pid: u32,
+    clock: Arc<RunClock>,
+}
+
+/// Process ids of provider subprocesses, keyed by the task they serve
+#[derive(Debug, Default)]
+pub struct ProcessRegistry {
+    pids: Arc<Mutex<HashMap<TaskId, RegisteredProcess>>>,
+}
+
+impl ProcessRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a running subprocess until the returned guard is dropped
+    pub fn register(&self, task_id: TaskId, pid: u32) -> ProcessRegistration {
+        let clock = Arc::new(RunClock::new());
+        self.pids.lock().unwrap().insert(
+            task_id,
+            RegisteredProcess {
+                pid,
+                clock: clock.clone(),
+            },
+        );
+        ProcessRegistration {
+            task_id,
+            pid,
+            clock,
+            pids: self.pids.clone(),
+        }
+    }
+
+    /// Subprocess currently running for a task
+    pub fn pid(&self, task_id: TaskId) -> Option<u32> {
+        self.pids
+            .lock()
+            .unwrap()
+            .get(&task_id)
+            .map(|process| process.pid)
+    }
+
+    /// Stop or restart the timeout clock of a task's subprocess
+    fn set_suspended(&self, task_id: TaskId, suspended: bool) {
+        if let Some(process) = self.pids.lock().unwrap().get(&task_id) {
+            process.clock.set_suspended(suspended);
+        }
+    }
+}
+
+/// Keeps a subprocess registered while it runs
+#[derive(Debug)]
+pub struct ProcessRegistration {
+    task_id: TaskId,
+    pid: u32,
+    clock: Arc<RunClock>,
+    pids: Arc<Mutex<HashMap<TaskId, RegisteredProcess>>>,
+}
+
+impl ProcessRegistration {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Wait for `future` until the process has run for `limit`, not counting
+    /// time it was suspended; `None` if the limit was reached first
+    pub async fn timeout<F: Future>(&self, limit: Duration, future: F) -> Option<F::Output> {
+        tokio::pin!(future);
+        loop {
+            // Listen before reading the clock so no change is missed
+            let changed = self.clock.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            let remaining = self.clock.remaining(limit);
+            let expired = async {
+                match remaining {
+                    Some(left) => tokio::time::sleep(left).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                output = &mut future => return Some(output),
+                _ = changed => {}
+                _ = expired => {
+                    if self.clock.remaining(limit).is_some_and(|left| left.is_zero()) {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ProcessRegistration {
+    fn drop(&mut self) {
+        let mut pids = self.pids.lock().unwrap();
+        // A restarted attempt may already have registered a newer process
+        if pids.get(&self.task_id).map(|process| process.pid) == Some(self.pid) {
+            pids.remove(&self.task_id);
+        }
+    }
+}
+
+/// Suspends host subprocesses and their process groups with `SIGSTOP` and
+/// resumes them with `SIGCONT`
+#[derive(Debug, Clone)]
+pub struct HostSuspender {
+    processes: Arc<ProcessRegistry>,
+}
+
+impl HostSuspender {
+    pub fn new(processes: Arc<ProcessRegistry>) -> Self {
+        Self { processes }
+    }
+
+    async fn signal_task(&self, task_id: TaskId, signal: Signal) -> Result<bool> {
+        let Some(pid) = self.processes.pid(task_id) else {
+            return Ok(false);
+        };
+        signal_process_group(pid, signal)?;
+        self.processes
+            .set_suspended(task_id, signal == Signal::Stop);
+        Ok(true)
+    }
+}
+
+impl TaskSuspender for HostSuspender {
+    fn suspend(&self, task_id: TaskId) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(self.signal_task(task_id, Signal::Stop))
+    }
+
+    fn resume(&self, task_id: TaskId) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(self.signal_task(task_id, Signal::Cont))
+    }
+}
+
+/// Signals sent to task process groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    Stop,
+    Cont,
+    Kill,
+}
+
+impl Signal {
+    fn name(self) -> &'static str {
+        match self {
+            Signal::Stop => "SIGSTOP",
+            Signal::Cont => "SIGCONT",
+            Signal::Kill => "SIGKILL",
+        }
+    }
+}
+
+/// Send `signal` to every process in the process group led by `pgid`
+#[cfg(unix)]
+fn signal_process_group(pgid: u32, signal: Signal) -> Result<()> {
+    let signum = match signal {
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Cont => libc::SIGCONT,
+        Signal::Kill => libc::SIGKILL,
+    };
+    let Ok(pgid) = libc::pid_t::try_from(pgid) else {
+        bail!("Invalid process group {}", pgid);
+    };
+    // SAFETY: killpg only sends a signal and has no memory safety requirements
+    if unsafe { libc::killpg(pgid, signum) } != 0 {
+        bail!(
+            "Failed to send {} to process group {}: {}",
+            signal.name(),
+            pgid,
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn signal_process_group(pgid: u32, signal: Signal) -> Result<()> {
+    bail!(
+        "Cannot send {} to process group {}: requires a Unix host",
+        signal.name(),
+        pgid
+    )
+}
+
+/// `SIGKILL` every process in the process group led by `pgid`
+pub async fn kill_process_group(pgid: u32) -> Result<()> {
+    signal_process_group(pgid, Signal::Kill)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_registration_is_removed_on_drop() {
+        let registry = ProcessRegistry::new();
+        let task_id = Uuid::new_v4();
+
+        let first = registry.register(task_id, 100);
+        let second = registry.register(task_id, 200);
+        drop(first);
+        assert_eq!(registry.pid(task_id), Some(200));
+
+        drop(second);
+        assert_eq!(registry.pid(task_id), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_host_suspender_stops_and_continues_process_group() {
+        use tokio::io::AsyncBufReadExt;
+
+        fn process_state(pid: u32) -> char {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap();
+            let after_name = &stat[stat.rfind(')').unwrap() + 2..];
+            after_name.chars().next().unwrap()
+        }
+
+        // Like the provider CLI: a group leader running a tool of its own
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "sleep 30 & echo $!; wait"])
+            .process_group(0)
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap();
+        let mut lines = tokio::io::BufReader::new(child.stdout.take().unwrap()).lines();
+        let tool: u32 = lines.next_line().await.unwrap().unwrap().parse().unwrap();
+
+        let registry = Arc::new(ProcessRegistry::new());
+        let task_id = Uuid::new_v4();
+        let _registration = registry.register(task_id, pid);
+        let suspender = HostSuspender::new(registry);
+
+        assert!(suspender.suspend(task_id).await.unwrap());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(process_state(pid), 'T');
+        assert_eq!(process_state(tool), 'T', "the tool kept running");
+
+        assert!(suspender.resume(task_id).await.unwrap());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_ne!(process_state(pid), 'T');
+        assert_ne!(process_state(tool), 'T');
+
+        assert!(!suspender.suspend(Uuid::new_v4()).await.unwrap());
+        kill_process_group(pid).await.unwrap();
+        child.wait().await.unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_does_not_count_suspended_time() {
+        use std::time::Duration;
+
+        let registry = Arc::new(ProcessRegistry::new());
+        let suspender = HostSuspender::new(registry.clone());
+        let task_id = Uuid::new_v4();
+        let spawn = || {
+            tokio::process::Command::new("sleep")
+                .arg("0.4")
+                .process_group(0)
+                .kill_on_drop(true)
+                .spawn()
+                .unwrap()
+        };
+
+        // Paused for longer than the timeout, then resumed to finish
+        let mut child = spawn();
+        let registration = registry.register(task_id, child.id().unwrap());
+        let (status, ()) = tokio::join!(
+            registration.timeout(Duration::from_millis(800), child.wait()),
+            async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                assert!(suspender.suspend(task_id).await.unwrap());
+                tokio::time::sleep(Duration::from_millis(1000)).await;
+                assert!(suspender.resume(task_id).await.unwrap());
+            }
+        );
+        assert!(status.expect("suspended time counted").unwrap().success());
+        drop(registration);
+
+        // Running past the timeout still expires
+        let mut child = spawn();
+        let registration = registry.register(task_id, child.id().unwrap());
+        assert!(
+            registration
+                .timeout(Duration::from_millis(100), child.wait())
+                .await
+                .is_none()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_kill_process_group_reaches_grandchildren() {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "sleep 30 & echo $!; wait"])
+            .process_group(0)
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let mut lines = tokio::io::BufReader::new(child.stdout.take().unwrap()).lines();
+        let grandchild = lines.next_line().await.unwrap().unwrap();
+
+        kill_process_group(child.id().unwrap()).await.unwrap();
+        child.wait().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let alive = std::fs::read_to_string(format!("/proc/{}/stat", grandchild))
+            .is_ok_and(|stat| !stat.contains(") Z "));
+        assert!(!alive, "sleep {} survived its process group", grandchild);
+    }
+}
@@ -20,8 +20,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec!["test".to_string()],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         }
     }
 
@@ -116,8 +121,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec![],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         };
         let parent_id = tree.create_task_from_spec(parent_spec, None).unwrap();
 
@@ -133,8 +143,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec![],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         };
         let child_id = tree
             .create_task_from_spec(child_spec, Some(parent_id))
@@ -167,8 +182,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec![],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         };
         let task_a_id = tree.create_task_from_spec(task_a_spec, None).unwrap();
 
@@ -184,8 +204,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec![],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![task_a_id],
+            plan: None,
         };
         let task_b_id = tree.create_task_from_spec(task_b_spec, None).unwrap();
 
@@ -241,8 +266,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec!["manager-test".to_string()],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         };
 
         let task_id = manager.create_task(spec, None).await.unwrap();
@@ -253,6 +283,290 @@ mod task_tests {
         assert!(task.is_runnable());
     }
 
+    #[tokio::test]
+    async fn test_task_manager_export_graph() {
+        let manager = TaskManager::new(TaskManagerConfig::default());
+        let schema = manager
+            .create_task(create_test_task_spec(), None)
+            .await
+            .unwrap();
+        manager
+            .create_task(
+                TaskSpec {
+                    title: "Build API".to_string(),
+                    dependencies: vec![schema],
+                    ..create_test_task_spec()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        manager
+            .complete_task(
+                schema,
+                TaskResult::Success {
+                    output: serde_json::json!({}),
+                    files_created: vec![],
+                    files_modified: vec![],
+                    build_artifacts: vec![],
+                },
+            )
+            .await
+            .unwrap();
+
+        let dot = manager
+            .export_graph(crate::task::GraphFormat::Dot)
+            .await
+            .unwrap();
+        assert!(
+            dot.contains("t0 [label=\"Test Task\\n(completed)\""),
+            "{}",
+            dot
+        );
+        assert!(
+            dot.contains("t1 [label=\"Build API\\n(pending)\""),
+            "{}",
+            dot
+        );
+        assert!(dot.contains("t0 -> t1;"));
+
+        let mermaid = manager
+            .export_graph(crate::task::GraphFormat::Mermaid)
+            .await
+            .unwrap();
+        assert!(mermaid.contains("    t0 --> t1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_task_manager_pause_and_resume() {
+        struct RecordingSuspender(std::sync::Mutex<Vec<&'static str>>);
+
+        impl crate::task::TaskSuspender for RecordingSuspender {
+            fn suspend(
+                &self,
+                _task_id: TaskId,
+            ) -> futures::future::BoxFuture<'_, anyhow::Result<bool>> {
+                self.0.lock().unwrap().push("suspend");
+                Box::pin(async { Ok(true) })
+            }
+
+            fn resume(
+                &self,
+                _task_id: TaskId,
+            ) -> futures::future::BoxFuture<'_, anyhow::Result<bool>> {
+                self.0.lock().unwrap().push("resume");
+                Box::pin(async { Ok(true) })
+            }
+        }
+
+        let manager = TaskManager::new(TaskManagerConfig::default());
+        let suspender = std::sync::Arc::new(RecordingSuspender(Default::default()));
+        manager.set_suspender(suspender.clone());
+
+        let task_id = manager
+            .create_task(create_test_task_spec(), None)
+            .await
+            .unwrap();
+
+        // Only running tasks can be paused
+        assert!(
+            manager
+                .pause_task(task_id, "hold".to_string())
+                .await
+                .is_err()
+        );
+
+        manager
+            .update_task_status(
+                task_id,
+                TaskStatus::InProgress {
+                    started_at: Utc::now(),
+                    estimated_completion: None,
+                },
+            )
+            .await
+            .unwrap();
+        manager
+            .pause_task(task_id, "operator request".to_string())
+            .await
+            .unwrap();
+        assert!(manager.get_task(task_id).await.unwrap().is_paused());
+        assert_eq!(manager.get_statistics().await.unwrap().paused_tasks, 1);
+
+        manager.resume_task(task_id).await.unwrap();
+        assert!(manager.get_task(task_id).await.unwrap().is_running());
+        assert_eq!(*suspender.0.lock().unwrap(), vec!["suspend", "resume"]);
+    }
+
+    #[tokio::test]
+    async fn test_task_manager_retries_with_backoff() {
+        let manager = std::sync::Arc::new(TaskManager::new(TaskManagerConfig {
+            max_retry_attempts: 2,
+            maintenance: MaintenanceConfig {
+                interval_secs: 1,
+                ..Default::default()
+            },
+            retry_backoff: crate::task::RetryBackoff {
+                rate_limit_delay_secs: 1,
+                jitter: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+        let task_id = manager
+            .create_task(create_test_task_spec(), None)
+            .await
+            .unwrap();
+
+        // Transient errors are retried at once
+        let io_error = TaskError::FileSystemError {
+            message: "Resource busy".to_string(),
+            path: None,
+            operation: "write".to_string(),
+        };
+        manager.fail_task(task_id, io_error.clone()).await.unwrap();
+        let task = manager.get_task(task_id).await.unwrap();
+        assert!(task.is_runnable());
+        assert_eq!(task.execution_history.len(), 1);
+
+        // Rate limits wait, and maintenance unblocks the task once they passed
+        let rate_limited = TaskError::ClaudeError {
+            message: "Rate limit exceeded".to_string(),
+            error_code: None,
+            retry_possible: true,
+        };
+        manager.fail_task(task_id, rate_limited).await.unwrap();
+        let task = manager.get_task(task_id).await.unwrap();
+        let TaskStatus::Blocked {
+            reason,
+            retry_after: Some(retry_after),
+            ..
+        } = &task.status
+        else {
+            panic!("expected a scheduled retry, got {:?}", task.status);
+        };
+        assert_eq!(reason.text(), Some(crate::task::RETRY_REASON));
+        // The second retry waits twice the first delay
+        assert!(*retry_after - Utc::now() > Duration::milliseconds(1500));
+        assert!(manager.unblock_expired().await.unwrap().is_empty());
+
+        manager.start_maintenance_loop();
+        for _ in 0..50 {
+            if manager.get_task(task_id).await.unwrap().is_runnable() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(manager.get_task(task_id).await.unwrap().is_runnable());
+        manager.stop_maintenance_loop();
+
+        // Out of retries, the task stays failed
+        manager.fail_task(task_id, io_error).await.unwrap();
+        let task = manager.get_task(task_id).await.unwrap();
+        assert!(matches!(
+            task.status,
+            TaskStatus::Failed { retry_count: 3, .. }
+        ));
+        assert_eq!(task.execution_history.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_task_manager_maintenance_loop() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct StatisticsCounter(Arc<AtomicUsize>);
+
+        impl TaskEventHandler for StatisticsCounter {
+            fn handle_event(&self, event: &TaskEvent) -> anyhow::Result<()> {
+                if matches!(event, TaskEvent::TreeStatisticsUpdated { .. }) {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(())
+            }
+        }
+
+        let mut manager = TaskManager::new(TaskManagerConfig {
+            maintenance: MaintenanceConfig {
+                interval_secs: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let updates = Arc::new(AtomicUsize::new(0));
+        manager.add_event_handler(Box::new(StatisticsCounter(updates.clone())));
+        let manager = Arc::new(manager);
+        manager.keep_blocked("offline");
+
+        let expired = Utc::now() - Duration::seconds(1);
+        let mut blocked = Vec::new();
+        for reason in ["Waiting for a deploy window", "offline"] {
+            let task_id = manager
+                .create_task(create_test_task_spec(), None)
+                .await
+                .unwrap();
+            manager
+                .block_task(task_id, reason.to_string(), Some(expired))
+                .await
+                .unwrap();
+            blocked.push(task_id);
+        }
+
+        manager.start_maintenance_loop();
+        for _ in 0..30 {
+            if updates.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        manager.stop_maintenance_loop();
+
+        assert!(manager.get_task(blocked[0]).await.unwrap().is_runnable());
+        // Offline blocks are lifted by whoever queued the task
+        assert!(manager.get_task(blocked[1]).await.unwrap().is_blocked());
+        // Statistics are only reported when they change
+        assert_eq!(updates.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_budget_blocks_are_typed() {
+        let manager = TaskManager::new(TaskManagerConfig::default());
+        let task_id = manager
+            .create_task(create_test_task_spec(), None)
+            .await
+            .unwrap();
+        let exceeded = crate::llm::BudgetExceeded {
+            scope: crate::llm::BudgetScope::Task,
+            spent: 3.0,
+            limit: 2.5,
+        };
+        manager.block_over_budget(task_id, &exceeded).await.unwrap();
+
+        let task = manager.get_task(task_id).await.unwrap();
+        let TaskStatus::Blocked { reason, .. } = &task.status else {
+            panic!("expected a blocked task, got {:?}", task.status);
+        };
+        assert!(reason.is_over_budget());
+        assert_eq!(reason.to_string(), exceeded.to_string());
+        let statistics = manager.get_statistics().await.unwrap();
+        assert_eq!(statistics.blocked_tasks, 1);
+        assert_eq!(statistics.over_budget_tasks, 1);
+
+        // Sessions saved with string reasons still load
+        let json = serde_json::to_value(&task.status).unwrap();
+        assert_eq!(json["Blocked"]["reason"]["scope"], "task");
+        let mut old = json.clone();
+        old["Blocked"]["reason"] = serde_json::json!("Waiting for a deploy window");
+        let TaskStatus::Blocked { reason, .. } = serde_json::from_value(old).unwrap() else {
+            panic!("expected a blocked status");
+        };
+        assert_eq!(reason.text(), Some("Waiting for a deploy window"));
+        assert_eq!(
+            serde_json::from_value::<TaskStatus>(json).unwrap().name(),
+            "blocked"
+        );
+    }
+
     #[tokio::test]
     async fn test_task_scheduler_selection() {
         let config = SchedulerConfig {
@@ -275,8 +589,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec![],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         };
         let _ = tree
             .create_task_from_spec(high_priority_spec, None)
@@ -293,8 +612,13 @@ mod task_tests {
                 file_refs: vec![],
                 tags: vec![],
                 context_requirements: ContextRequirements::new(),
+                requirements: Default::default(),
+                provider_preference: Default::default(),
+                tool_permissions: Default::default(),
+                wait_for: Default::default(),
             },
             dependencies: vec![],
+            plan: None,
         };
         let _low_priority_id = tree.create_task_from_spec(low_priority_spec, None).unwrap();
 
@@ -394,4 +718,35 @@ mod task_tests {
 
         assert!(TaskPriority::Critical > TaskPriority::Low);
     }
+
+    #[test]
+    fn test_tool_permissions_overrides() {
+        let configured: Vec<String> = ["Read", "Write", "Bash(git:*)", "Grep"]
+            .iter()
+            .map(|tool| tool.to_string())
+            .collect();
+        assert_eq!(
+            ToolPermissions::default().allowed_tools(&configured),
+            configured
+        );
+
+        let read_only = ToolPermissions {
+            disallowed_tools: vec!["Write".to_string(), "Bash".to_string()],
+            permission_mode: Some(crate::claude::PermissionMode::Plan),
+            ..Default::default()
+        };
+        assert_eq!(read_only.allowed_tools(&configured), vec!["Read", "Grep"]);
+
+        let explicit = ToolPermissions {
+            allowed_tools: Some(vec!["Read".to_string(), "mcp__db".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(explicit.allowed_tools(&configured), vec!["Read", "mcp__db"]);
+
+        // Plan files spell the mode as the CLI does
+        let parsed: ToolPermissions =
+            serde_json::from_str(r#"{"permission_mode": "plan", "disallowed_tools": ["Bash"]}"#)
+                .unwrap();
+        assert_eq!(parsed.permission_mode.unwrap().as_str(), "plan");
+    }
 }
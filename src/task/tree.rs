@@ -27,7 +27,7 @@ pub struct TaskTreeMetadata {
 }
 
 /// Task tree statistics for monitoring
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct TaskTreeStatistics {
     pub total_tasks: u32,
     pub pending_tasks: u32,
@@ -36,6 +36,13 @@ pub struct TaskTreeStatistics {
     pub failed_tasks: u32,
     pub blocked_tasks: u32,
     pub skipped_tasks: u32,
+    #[serde(default)]
+    pub paused_tasks: u32,
+    #[serde(default)]
+    pub awaiting_input_tasks: u32,
+    /// Blocked tasks whose cost budget is spent, counted in `blocked_tasks` too
+    #[serde(default)]
+    pub over_budget_tasks: u32,
     pub average_completion_time: Option<Duration>,
     pub success_rate: f64,
 }
@@ -526,8 +533,15 @@ impl TaskTree {
                     completion_times.push(duration);
                 }
                 TaskStatus::Failed { .. } => stats.failed_tasks += 1,
-                TaskStatus::Blocked { .. } => stats.blocked_tasks += 1,
+                TaskStatus::Blocked { reason, .. } => {
+                    stats.blocked_tasks += 1;
+                    if reason.is_over_budget() {
+                        stats.over_budget_tasks += 1;
+                    }
+                }
                 TaskStatus::Skipped { .. } => stats.skipped_tasks += 1,
+                TaskStatus::Paused { .. } => stats.paused_tasks += 1,
+                TaskStatus::AwaitingInput { .. } => stats.awaiting_input_tasks += 1,
             }
         }
 
@@ -564,6 +578,9 @@ impl Default for TaskTreeStatistics {
             failed_tasks: 0,
             blocked_tasks: 0,
             skipped_tasks: 0,
+            paused_tasks: 0,
+            awaiting_input_tasks: 0,
+            over_budget_tasks: 0,
             average_completion_time: None,
             success_rate: 0.0,
         }
@@ -1,3 +1,6 @@
+use super::packages::SystemPackages;
+use crate::claude::PermissionMode;
+use crate::llm::types::ProviderType;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -38,7 +41,7 @@ pub enum TaskStatus {
     },
     /// Task is temporarily blocked by external factors
     Blocked {
-        reason: String,
+        reason: BlockReason,
         blocked_at: DateTime<Utc>,
         retry_after: Option<DateTime<Utc>>,
     },
@@ -58,6 +61,102 @@ pub enum TaskStatus {
         reason: String,
         skipped_at: DateTime<Utc>,
     },
+    /// Task was preempted mid-run and will resume once a slot is free
+    Paused {
+        reason: String,
+        paused_at: DateTime<Utc>,
+    },
+    /// Task is waiting for the user to answer a question from the model
+    AwaitingInput {
+        question: crate::task::question::UserQuestion,
+        asked_at: DateTime<Utc>,
+    },
+}
+
+impl TaskStatus {
+    /// Short snake_case name of the status, for progress output
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::InProgress { .. } => "in_progress",
+            TaskStatus::Blocked { .. } => "blocked",
+            TaskStatus::Completed { .. } => "completed",
+            TaskStatus::Failed { .. } => "failed",
+            TaskStatus::Skipped { .. } => "skipped",
+            TaskStatus::Paused { .. } => "paused",
+            TaskStatus::AwaitingInput { .. } => "awaiting_input",
+        }
+    }
+}
+
+/// Why a task is blocked
+///
+/// Serialized untagged, so sessions saved with a plain string reason load as
+/// [`BlockReason::Other`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum BlockReason {
+    /// A cost budget is spent; the task resumes under a larger one
+    BudgetExceeded {
+        scope: crate::llm::BudgetScope,
+        spent: f64,
+        limit: f64,
+    },
+    Other(String),
+}
+
+impl BlockReason {
+    /// The reason's text, unless it is one of the typed reasons
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            BlockReason::Other(reason) => Some(reason),
+            BlockReason::BudgetExceeded { .. } => None,
+        }
+    }
+
+    pub fn is_over_budget(&self) -> bool {
+        matches!(self, BlockReason::BudgetExceeded { .. })
+    }
+}
+
+impl std::fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockReason::BudgetExceeded {
+                scope,
+                spent,
+                limit,
+            } => crate::llm::BudgetExceeded {
+                scope: *scope,
+                spent: *spent,
+                limit: *limit,
+            }
+            .fmt(f),
+            BlockReason::Other(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl From<String> for BlockReason {
+    fn from(reason: String) -> Self {
+        BlockReason::Other(reason)
+    }
+}
+
+impl From<&str> for BlockReason {
+    fn from(reason: &str) -> Self {
+        BlockReason::Other(reason.to_string())
+    }
+}
+
+impl From<&crate::llm::BudgetExceeded> for BlockReason {
+    fn from(exceeded: &crate::llm::BudgetExceeded) -> Self {
+        BlockReason::BudgetExceeded {
+            scope: exceeded.scope,
+            spent: exceeded.spent,
+            limit: exceeded.limit,
+        }
+    }
 }
 
 /// Rich metadata for task management and scheduling
@@ -70,6 +169,37 @@ pub struct TaskMetadata {
     pub file_refs: Vec<FileRef>,
     pub tags: Vec<String>,
     pub context_requirements: ContextRequirements,
+    /// What the executor running this task must provide
+    #[serde(default)]
+    pub requirements: TaskRequirements,
+    /// Provider and model to run this task on, ahead of routing rules
+    #[serde(default)]
+    pub provider_preference: ProviderPreference,
+    /// Tools the agent may use on this task, over the configured defaults
+    #[serde(default)]
+    pub tool_permissions: ToolPermissions,
+    /// External state that must be ready before the task runs
+    #[serde(default)]
+    pub wait_for: Vec<crate::task::wait::WaitCondition>,
+}
+
+/// Tag prefix marking a cost-center label (e.g. `cost:platform-team`)
+pub const COST_TAG_PREFIX: &str = "cost:";
+
+/// Cost-center labels found in a tag list, without the `cost:` prefix
+pub fn cost_tags(tags: &[String]) -> Vec<String> {
+    tags.iter()
+        .filter_map(|tag| tag.strip_prefix(COST_TAG_PREFIX))
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+impl TaskMetadata {
+    /// Cost-center labels attached to this task
+    pub fn cost_tags(&self) -> Vec<String> {
+        cost_tags(&self.tags)
+    }
 }
 
 /// Task priority levels with numeric values for scoring
@@ -129,6 +259,82 @@ pub struct ContextRequirements {
     pub claude_context_keys: Vec<String>,
 }
 
+/// Executor capabilities a task needs, matched by the placement layer
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TaskRequirements {
+    /// Needs Docker or Podman
+    pub docker: bool,
+    /// Needs a GPU
+    pub gpu: bool,
+    /// Needs this operating system (`linux`, `macos`, `windows`)
+    pub os: Option<String>,
+    /// Executor labels that must all be present
+    pub labels: Vec<String>,
+}
+
+impl TaskRequirements {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Provider and model a task asks to run on
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ProviderPreference {
+    pub provider: Option<ProviderType>,
+    /// Model name or alias understood by the provider
+    pub model: Option<String>,
+}
+
+impl ProviderPreference {
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_none() && self.model.is_none()
+    }
+}
+
+/// Tool access a task asks for, e.g. no `Write` or `Bash` for read-only analysis
+///
+/// Applies to the Claude Code CLI.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ToolPermissions {
+    /// Replaces the configured allowed tools
+    pub allowed_tools: Option<Vec<String>>,
+    /// Removed from the allowed tools and denied outright
+    pub disallowed_tools: Vec<String>,
+    /// Replaces the configured permission mode
+    pub permission_mode: Option<PermissionMode>,
+}
+
+impl ToolPermissions {
+    pub fn is_empty(&self) -> bool {
+        self.allowed_tools.is_none()
+            && self.disallowed_tools.is_empty()
+            && self.permission_mode.is_none()
+    }
+
+    /// `allowed` with this task's replacement and removals applied
+    ///
+    /// Removing a tool also removes its scoped forms, such as `Bash(git:*)`.
+    pub fn allowed_tools(&self, allowed: &[String]) -> Vec<String> {
+        let allowed = self.allowed_tools.as_deref().unwrap_or(allowed);
+        allowed
+            .iter()
+            .filter(|tool| {
+                !self.disallowed_tools.iter().any(|denied| {
+                    *tool == denied
+                        || tool
+                            .strip_prefix(denied.as_str())
+                            .is_some_and(|rest| rest.starts_with('('))
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
 /// Task dependency relationship
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TaskDependency {
@@ -163,7 +369,7 @@ pub struct ExecutionRecord {
 }
 
 /// Resource usage tracking
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ResourceUsage {
     pub max_memory_mb: u64,
     pub cpu_time_seconds: f64,
@@ -241,6 +447,10 @@ pub struct TaskSpec {
     pub description: String,
     pub metadata: TaskMetadata,
     pub dependencies: Vec<TaskId>,
+    /// Plan file this task expands into; its tasks run as the task's
+    /// children and the task completes when they have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<PathBuf>,
 }
 
 impl Task {
@@ -285,6 +495,27 @@ impl Task {
         matches!(self.status, TaskStatus::Blocked { .. })
     }
 
+    /// Check if task was preempted and is waiting to resume
+    pub fn is_paused(&self) -> bool {
+        matches!(self.status, TaskStatus::Paused { .. })
+    }
+
+    /// Check if task is waiting for an answer from the user
+    pub fn is_awaiting_input(&self) -> bool {
+        matches!(self.status, TaskStatus::AwaitingInput { .. })
+    }
+
+    /// Model response text of a successfully completed task
+    pub fn response(&self) -> Option<&str> {
+        match &self.status {
+            TaskStatus::Completed {
+                result: TaskResult::Success { output, .. },
+                ..
+            } => output.get("response")?.as_str(),
+            _ => None,
+        }
+    }
+
     /// Get task age since creation
     pub fn age(&self) -> Duration {
         Utc::now().signed_duration_since(self.created_at)
@@ -404,6 +635,21 @@ impl ComplexityLevel {
     }
 }
 
+impl TaskError {
+    /// Short snake_case name of the error kind, for reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskError::ClaudeError { .. } => "claude_error",
+            TaskError::BuildError { .. } => "build_error",
+            TaskError::FileSystemError { .. } => "file_system_error",
+            TaskError::ResourceError { .. } => "resource_error",
+            TaskError::DependencyError { .. } => "dependency_error",
+            TaskError::TimeoutError { .. } => "timeout_error",
+            TaskError::Other { .. } => "other",
+        }
+    }
+}
+
 impl std::fmt::Display for TaskError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -510,6 +756,7 @@ impl std::fmt::Display for TaskError {
 
 /// Setup command to run before the main instruction loop
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct SetupCommand {
     pub id: Uuid,
     pub name: String,
@@ -519,6 +766,9 @@ pub struct SetupCommand {
     pub timeout: Option<Duration>,           // Command timeout
     pub required: bool,                      // If false, failure won't stop initialization
     pub error_handler: Option<ErrorHandler>, // Optional error handling strategy
+    /// System packages to ensure instead of running `command`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packages: Option<SystemPackages>,
 }
 
 /// Result of executing a setup command
@@ -562,6 +812,9 @@ pub struct OutputCondition {
     pub contains: Option<String>,            // Text that must be present
     pub not_contains: Option<String>,        // Text that must NOT be present
     pub exit_code_range: Option<(i32, i32)>, // Acceptable exit code range
+    /// Binaries the environment report must list, e.g. a package manager
+    #[serde(default)]
+    pub requires: Vec<String>,
 }
 
 impl Default for SetupCommand {
@@ -575,6 +828,7 @@ impl Default for SetupCommand {
             timeout: Some(Duration::seconds(30)), // Default 30 second timeout
             required: true,
             error_handler: None,
+            packages: None,
         }
     }
 }
@@ -587,6 +841,7 @@ impl Default for OutputCondition {
             contains: None,
             not_contains: None,
             exit_code_range: None,
+            requires: Vec::new(),
         }
     }
 }
@@ -601,6 +856,18 @@ impl SetupCommand {
         }
     }
 
+    /// Create a setup command ensuring system packages are installed
+    ///
+    /// Installs can be slow, so the timeout is ten minutes.
+    pub fn packages(name: &str, packages: SystemPackages) -> Self {
+        Self {
+            name: name.to_string(),
+            timeout: Some(Duration::minutes(10)),
+            packages: Some(packages),
+            ..Default::default()
+        }
+    }
+
     /// Add arguments to the command
     pub fn with_args(mut self, args: Vec<String>) -> Self {
         self.args = args;
@@ -679,6 +946,7 @@ impl OutputCondition {
             contains: Some(text.to_string()),
             not_contains: None,
             exit_code_range: None,
+            requires: Vec::new(),
         }
     }
 
@@ -690,6 +958,7 @@ impl OutputCondition {
             contains: Some(text.to_string()),
             not_contains: None,
             exit_code_range: None,
+            requires: Vec::new(),
         }
     }
 
@@ -700,6 +969,12 @@ impl OutputCondition {
             ..Default::default()
         }
     }
+
+    /// Also require a binary to be available in the probed environment
+    pub fn requiring(mut self, binary: &str) -> Self {
+        self.requires.push(binary.to_string());
+        self
+    }
 }
 
 impl Default for TaskMetadata {
@@ -712,6 +987,10 @@ impl Default for TaskMetadata {
             file_refs: Vec::new(),
             tags: Vec::new(),
             context_requirements: ContextRequirements::default(),
+            requirements: TaskRequirements::default(),
+            provider_preference: ProviderPreference::default(),
+            tool_permissions: ToolPermissions::default(),
+            wait_for: Vec::new(),
         }
     }
 }
@@ -0,0 +1,480 @@
+//! Batched verification of plan tasks.
+//!
+//! Instead of running the test suite after every task, a plan verifies once
+//! per group: after every [`VerificationConfig::every`] tasks, at tasks
+//! tagged [`VERIFY_POINT_TAG`], and when the plan ends. A
+//! [`VerificationGroup`] keeps the contents of the files each task in the
+//! group changed, up to the size workspace snapshots keep, so when
+//! verification fails the group can be bisected: earlier states are
+//! restored and re-verified until the first failing task is found, and a
+//! fix subtask is created for it.
+
+use crate::executor::ExecutionResult;
+use crate::task::watch::fix_task_spec;
+use crate::task::{TaskId, TaskSpec};
+use crate::workspace::{WorkspaceSnapshot, resolve_within};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Tag on a plan task after which the tasks run so far are verified
+pub const VERIFY_POINT_TAG: &str = "verify:point";
+
+/// Batched verification settings for plans
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationConfig {
+    /// Command that verifies the workspace, e.g. `cargo test`; plans are not
+    /// verified when unset
+    pub command: Option<String>,
+    /// Verify after this many tasks; 0 verifies only at verify points and at
+    /// the end of the plan
+    pub every: usize,
+    /// Find the task that broke a failing group by re-verifying earlier states
+    pub bisect: bool,
+    /// Narrow the command to the tests affected by each group's changes;
+    /// the full command still runs at the end of the plan
+    pub affected_only: bool,
+    /// Characters of failing output included in a fix task
+    pub max_output_chars: usize,
+    /// Directory names left out of the file snapshots
+    pub ignore: Vec<String>,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            every: 5,
+            bisect: true,
+            affected_only: false,
+            max_output_chars: 4000,
+            ignore: [".aca", ".git", "target", "node_modules"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl VerificationConfig {
+    /// Whether a group of `len` tasks is verified now; `verify_point` when
+    /// its last task is tagged [`VERIFY_POINT_TAG`]
+    pub fn is_due(&self, verify_point: bool, len: usize) -> bool {
+        verify_point || (self.every > 0 && len >= self.every)
+    }
+}
+
+/// Whether the tasks run up to and including `spec` are verified after it
+pub fn is_verify_point(spec: &TaskSpec) -> bool {
+    spec.metadata.tags.iter().any(|tag| tag == VERIFY_POINT_TAG)
+}
+
+/// Files that differ in `later`, with their new contents; `None` when removed
+///
+/// Files too large for either snapshot to have kept are returned separately,
+/// since they cannot be put back.
+pub(crate) fn restorable_changes(
+    earlier: &WorkspaceSnapshot,
+    later: &WorkspaceSnapshot,
+) -> (HashMap<PathBuf, Option<Vec<u8>>>, Vec<PathBuf>) {
+    let mut changes = HashMap::new();
+    let mut unrestorable = Vec::new();
+    for path in earlier.changes(later).all() {
+        if earlier.can_restore(path) && later.can_restore(path) {
+            changes.insert(path.clone(), later.contents(path).map(<[u8]>::to_vec));
+        } else {
+            unrestorable.push(path.clone());
+        }
+    }
+    (changes, unrestorable)
+}
+
+/// A task in a verification group and the files it changed
+#[derive(Debug, Clone)]
+pub struct GroupTask {
+    /// `None` for work that cannot be attributed to one task, such as tasks
+    /// that ran in parallel
+    pub task_id: Option<TaskId>,
+    pub title: String,
+    changes: HashMap<PathBuf, Option<Vec<u8>>>,
+    /// Changed files too large to have been kept
+    unrestorable: Vec<PathBuf>,
+}
+
+impl GroupTask {
+    /// Files the task changed, relative to the workspace root
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<_> = self
+            .changes
+            .keys()
+            .chain(&self.unrestorable)
+            .cloned()
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// Tasks run since the last verification, in order
+#[derive(Debug)]
+pub struct VerificationGroup {
+    root: PathBuf,
+    ignore: Vec<String>,
+    /// Workspace as it was before the group's first task
+    baseline: WorkspaceSnapshot,
+    latest: WorkspaceSnapshot,
+    tasks: Vec<GroupTask>,
+    /// Whether the baseline is known to pass verification
+    baseline_verified: bool,
+}
+
+impl VerificationGroup {
+    /// Start a group from the workspace's current contents
+    pub fn start(root: &Path, ignore: &[String]) -> io::Result<Self> {
+        let baseline = WorkspaceSnapshot::capture(root, ignore)?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            ignore: ignore.to_vec(),
+            latest: baseline.clone(),
+            baseline,
+            tasks: Vec::new(),
+            baseline_verified: false,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn tasks(&self) -> &[GroupTask] {
+        &self.tasks
+    }
+
+    /// Files any task in the group changed, relative to the workspace root
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let mut changed: Vec<_> = self
+            .tasks
+            .iter()
+            .flat_map(GroupTask::changed_files)
+            .collect();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    /// Whether the workspace as the group started passed the full
+    /// verification command
+    pub fn baseline_verified(&self) -> bool {
+        self.baseline_verified
+    }
+
+    /// Record the files changed since the previous task as this task's
+    pub fn record(&mut self, task_id: Option<TaskId>, title: &str) -> io::Result<()> {
+        let current = WorkspaceSnapshot::capture(&self.root, &self.ignore)?;
+        let (changes, unrestorable) = restorable_changes(&self.latest, &current);
+        debug!(
+            "Task '{}' changed {} file(s)",
+            title,
+            changes.len() + unrestorable.len()
+        );
+        self.tasks.push(GroupTask {
+            task_id,
+            title: title.to_string(),
+            changes,
+            unrestorable,
+        });
+        self.latest = current;
+        Ok(())
+    }
+
+    /// Start the next group from the current workspace
+    ///
+    /// `passed` tells whether this group's final state passed verification,
+    /// which makes it a trusted baseline for the next bisection.
+    pub fn reset(&mut self, passed: bool) -> io::Result<()> {
+        self.latest = WorkspaceSnapshot::capture(&self.root, &self.ignore)?;
+        self.baseline = self.latest.clone();
+        self.tasks.clear();
+        self.baseline_verified = passed;
+        Ok(())
+    }
+
+    /// Changed files too large to have been kept, which keep the group from
+    /// being restored
+    pub fn unrestorable_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<_> = self
+            .tasks
+            .iter()
+            .flat_map(|task| task.unrestorable.iter().cloned())
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Put the workspace back to its state after the group's first `count`
+    /// tasks
+    ///
+    /// Fails without touching anything if a changed file was too large to
+    /// keep.
+    pub fn restore(&self, count: usize) -> io::Result<()> {
+        let unrestorable = self.unrestorable_files();
+        if !unrestorable.is_empty() {
+            return Err(io::Error::other(format!(
+                "{} too large to restore",
+                unrestorable
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+
+        let mut contents: HashMap<&PathBuf, Option<&[u8]>> = HashMap::new();
+        for (index, task) in self.tasks.iter().enumerate() {
+            for (path, changed) in &task.changes {
+                if index < count {
+                    contents.insert(path, changed.as_deref());
+                } else {
+                    contents
+                        .entry(path)
+                        .or_insert_with(|| self.baseline.contents(path));
+                }
+            }
+        }
+
+        for (path, contents) in contents {
+            write_state(&self.root, path, contents)?;
+        }
+        Ok(())
+    }
+
+    /// Index of the first task after which verification fails, given that
+    /// the group as a whole fails
+    ///
+    /// `passes(count)` verifies the workspace as restored to the first
+    /// `count` tasks. The workspace is left as the group finished it.
+    /// Returns `None` when the group's baseline already fails, the group is
+    /// empty, or a file it changed was too large to keep.
+    pub async fn bisect<F>(
+        &self,
+        mut passes: impl FnMut(usize) -> F,
+    ) -> anyhow::Result<Option<usize>>
+    where
+        F: Future<Output = anyhow::Result<bool>>,
+    {
+        if self.tasks.is_empty() {
+            return Ok(None);
+        }
+        let unrestorable = self.unrestorable_files();
+        if !unrestorable.is_empty() {
+            warn!(
+                "Not bisecting verification group: {} file(s) too large to restore",
+                unrestorable.len()
+            );
+            return Ok(None);
+        }
+        let result = async {
+            if !self.baseline_verified {
+                self.restore(0)?;
+                if !passes(0).await? {
+                    return Ok(None);
+                }
+            }
+
+            // State `passing` passes and state `failing` fails
+            let (mut passing, mut failing) = (0, self.tasks.len());
+            while failing - passing > 1 {
+                let mid = (passing + failing) / 2;
+                self.restore(mid)?;
+                if passes(mid).await? {
+                    passing = mid;
+                } else {
+                    failing = mid;
+                }
+            }
+            Ok(Some(failing - 1))
+        }
+        .await;
+        self.restore(self.tasks.len())?;
+        result
+    }
+}
+
+/// Write `contents` to `path` under `root`, or remove it when `None`
+///
+/// Symlinks created since the contents were captured must not redirect the
+/// write outside the workspace.
+pub(crate) fn write_state(root: &Path, path: &Path, contents: Option<&[u8]>) -> io::Result<()> {
+    let path = &resolve_within(root, path)?;
+    match contents {
+        Some(contents) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)
+        }
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Build the fix task for a group whose verification failed
+///
+/// `culprit` is the task bisection blamed, if any.
+pub fn group_fix_task_spec(
+    command: &str,
+    result: &ExecutionResult,
+    culprit: Option<&GroupTask>,
+    group: &VerificationGroup,
+    max_output_chars: usize,
+) -> TaskSpec {
+    let changed = match culprit {
+        Some(task) => task.changed_files(),
+        None => group.changed_files(),
+    };
+
+    let mut spec = fix_task_spec(command, result, &changed, max_output_chars);
+    if let Some(task) = culprit {
+        spec.title = format!("Fix verification failure from: {}", task.title);
+        spec.description = format!(
+            "Verification first fails after the task \"{}\".\n\n{}",
+            task.title, spec.description
+        );
+    }
+    spec.metadata.tags = vec!["verification".to_string(), "auto-fix".to_string()];
+    spec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> PathBuf {
+        let workspace =
+            std::env::temp_dir().join(format!("aca-verification-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        workspace
+    }
+
+    #[test]
+    fn test_restore_replays_group_states() {
+        let root = workspace();
+        std::fs::write(root.join("lib.rs"), "v0").unwrap();
+        let mut group = VerificationGroup::start(&root, &[]).unwrap();
+
+        std::fs::write(root.join("lib.rs"), "v1").unwrap();
+        group.record(None, "first").unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/new.rs"), "new").unwrap();
+        group.record(None, "second").unwrap();
+        std::fs::remove_file(root.join("lib.rs")).unwrap();
+        group.record(None, "third").unwrap();
+        assert_eq!(
+            group.tasks()[1].changed_files(),
+            [PathBuf::from("src/new.rs")]
+        );
+
+        group.restore(1).unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("lib.rs")).unwrap(), "v1");
+        assert!(!root.join("src/new.rs").exists());
+
+        group.restore(0).unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("lib.rs")).unwrap(), "v0");
+
+        group.restore(3).unwrap();
+        assert!(!root.join("lib.rs").exists());
+        assert!(root.join("src/new.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_bisect_finds_first_failing_task() {
+        let root = workspace();
+        std::fs::write(root.join("status"), "ok").unwrap();
+        let mut group = VerificationGroup::start(&root, &[]).unwrap();
+        for (index, status) in ["ok", "ok", "broken", "broken", "broken"]
+            .into_iter()
+            .enumerate()
+        {
+            std::fs::write(root.join(format!("task{}", index)), "").unwrap();
+            std::fs::write(root.join("status"), status).unwrap();
+            group.record(None, &format!("task {}", index)).unwrap();
+        }
+
+        let mut runs = 0;
+        let passes = |_count: usize| {
+            runs += 1;
+            let status = std::fs::read_to_string(root.join("status"));
+            async move { Ok(status? == "ok") }
+        };
+        assert_eq!(group.bisect(passes).await.unwrap(), Some(2));
+        assert!(runs <= 4);
+        // The workspace is back to the group's final state
+        assert_eq!(
+            std::fs::read_to_string(root.join("status")).unwrap(),
+            "broken"
+        );
+        assert!(root.join("task4").exists());
+
+        // A failing baseline means no task in the group is to blame
+        group.reset(false).unwrap();
+        std::fs::write(root.join("status"), "still broken").unwrap();
+        group.record(None, "task 5").unwrap();
+        let passes = |_count: usize| {
+            let status = std::fs::read_to_string(root.join("status"));
+            async move { Ok(status? == "ok") }
+        };
+        assert_eq!(group.bisect(passes).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_large_files_are_not_restored() {
+        let root = workspace();
+        std::fs::write(root.join("status"), "ok").unwrap();
+        let mut group = VerificationGroup::start(&root, &[]).unwrap();
+        std::fs::write(root.join("data.bin"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+        std::fs::write(root.join("status"), "broken").unwrap();
+        group.record(None, "task 0").unwrap();
+        assert_eq!(group.unrestorable_files(), [PathBuf::from("data.bin")]);
+        assert_eq!(
+            group.tasks()[0].changed_files(),
+            [PathBuf::from("data.bin"), PathBuf::from("status")]
+        );
+
+        assert!(group.restore(0).is_err());
+        let passes = |_count: usize| async { Ok(false) };
+        assert_eq!(group.bisect(passes).await.unwrap(), None);
+        // Nothing was touched
+        assert!(root.join("data.bin").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join("status")).unwrap(),
+            "broken"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_does_not_follow_symlinks_out_of_the_workspace() {
+        let root = workspace();
+        let outside = workspace();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "v0").unwrap();
+        let mut group = VerificationGroup::start(&root, &[]).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "v1").unwrap();
+        group.record(None, "task 0").unwrap();
+
+        // The directory is swapped for a link after the group was recorded
+        std::fs::remove_dir_all(root.join("src")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("src")).unwrap();
+        assert!(group.restore(0).is_err());
+        assert!(!outside.join("lib.rs").exists());
+    }
+}
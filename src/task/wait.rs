@@ -0,0 +1,187 @@
+//! Conditions on external state that a task waits for before running.
+//!
+//! A task can depend on something an earlier task set in motion outside the
+//! task tree: a dev server coming up, a pipeline dropping an artifact. Its
+//! `wait_for` conditions are polled with exponential backoff once the task is
+//! otherwise ready, and it runs when all of them hold. A condition still
+//! unmet when [`WaitConfig::timeout_secs`] runs out fails the task.
+//!
+//! ```json
+//! "metadata": {
+//!   "wait_for": [
+//!     { "url": "http://localhost:3000/health" },
+//!     { "file": "build/app.tar.gz" },
+//!     { "command": "pg_isready -h localhost" }
+//!   ]
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Start of the block reason of a task waiting on its conditions
+pub const WAITING_REASON_PREFIX: &str = "Waiting for ";
+
+/// External state a task needs before it can run
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitCondition {
+    /// An HTTP GET of the URL returns a success status
+    Url(String),
+    /// The file exists; relative paths are resolved against the workspace
+    File(PathBuf),
+    /// The shell command exits 0, run like a verification command
+    Command(String),
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{} to respond", url),
+            Self::File(path) => write!(f, "{} to exist", path.display()),
+            Self::Command(command) => write!(f, "`{}` to succeed", command),
+        }
+    }
+}
+
+/// How wait conditions are polled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WaitConfig {
+    /// How long a task waits for all its conditions before failing
+    pub timeout_secs: u64,
+    /// Delay before the second check; doubled after every failed check
+    pub initial_interval_ms: u64,
+    /// Longest delay between two checks
+    pub max_interval_secs: u64,
+}
+
+impl Default for WaitConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 600,
+            initial_interval_ms: 500,
+            max_interval_secs: 15,
+        }
+    }
+}
+
+impl WaitConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// Call `check` until it returns `true` or `deadline` passes
+    ///
+    /// Returns whether the check ever passed.
+    pub async fn poll<F, Fut>(&self, deadline: Instant, mut check: F) -> bool
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let max_interval = Duration::from_secs(self.max_interval_secs);
+        let mut interval = Duration::from_millis(self.initial_interval_ms).min(max_interval);
+        loop {
+            if check().await {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            tokio::time::sleep(interval.min(deadline - now)).await;
+            interval = (interval * 2).min(max_interval);
+        }
+    }
+}
+
+/// Whether `path` exists, relative to `workspace_root` unless absolute
+pub fn file_exists(workspace_root: &Path, path: &Path) -> bool {
+    workspace_root.join(path).exists()
+}
+
+/// Whether a GET of `url` returns a success status within a few seconds
+pub async fn url_responds(url: &str) -> bool {
+    let Ok(client) = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+    else {
+        return false;
+    };
+    client
+        .get(url)
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_conditions_from_plan_metadata() {
+        let conditions: Vec<WaitCondition> = serde_json::from_str(
+            r#"[{"url": "http://localhost:3000/health"}, {"file": "out.tar"}, {"command": "true"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            conditions,
+            vec![
+                WaitCondition::Url("http://localhost:3000/health".to_string()),
+                WaitCondition::File(PathBuf::from("out.tar")),
+                WaitCondition::Command("true".to_string()),
+            ]
+        );
+        assert_eq!(conditions[2].to_string(), "`true` to succeed");
+    }
+
+    #[tokio::test]
+    async fn test_poll_backs_off_until_met_or_deadline() {
+        let config = WaitConfig {
+            timeout_secs: 1,
+            initial_interval_ms: 10,
+            max_interval_secs: 1,
+        };
+        let checks = AtomicU32::new(0);
+        let deadline = Instant::now() + config.timeout();
+        let met = config
+            .poll(deadline, || async {
+                checks.fetch_add(1, Ordering::SeqCst) >= 2
+            })
+            .await;
+        assert!(met);
+        assert_eq!(checks.load(Ordering::SeqCst), 3);
+
+        let deadline = Instant::now() + Duration::from_millis(100);
+        assert!(!config.poll(deadline, || async { false }).await);
+        assert!(Instant::now() >= deadline);
+    }
+
+    #[tokio::test]
+    async fn test_url_and_file_checks() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        assert!(!file_exists(workspace.path(), Path::new("ready")));
+        std::fs::write(workspace.path().join("ready"), "").unwrap();
+        assert!(file_exists(workspace.path(), Path::new("ready")));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+        });
+        assert!(url_responds(&url).await);
+        assert!(!url_responds("http://127.0.0.1:1/health").await);
+    }
+}
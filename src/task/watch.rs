@@ -0,0 +1,287 @@
+//! Watch mode: verify the workspace after edits and queue fix tasks.
+//!
+//! `aca watch --verify "cargo test"` polls the workspace for changed files.
+//! Once edits settle it runs the verification command, and when it fails the
+//! output becomes the context of a fix task. [`FixThrottle`] bounds how often
+//! fixes are attempted so a stubborn failure cannot loop the agent.
+
+use super::types::{TaskMetadata, TaskPriority, TaskSpec};
+use crate::executor::ExecutionResult;
+use crate::workspace::workspace_files;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Watch mode settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Verification command when `--verify` is not given
+    pub verify_command: Option<String>,
+    /// How often the workspace is scanned for changes
+    pub poll_interval_secs: u64,
+    /// Quiet period after the last change before verifying
+    pub settle_secs: u64,
+    /// Minimum time between two fix tasks
+    pub min_fix_interval_secs: u64,
+    /// Maximum fix tasks in any rolling hour
+    pub max_fixes_per_hour: u32,
+    /// Ask before creating each fix task
+    pub confirm_fixes: bool,
+    /// Directory names skipped when scanning, besides those in
+    /// `[verification] ignore`
+    pub ignore: Vec<String>,
+    /// Verification output kept in the fix task (tail, in characters)
+    pub max_output_chars: usize,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            verify_command: None,
+            poll_interval_secs: 2,
+            settle_secs: 2,
+            min_fix_interval_secs: 120,
+            max_fixes_per_hour: 6,
+            confirm_fixes: true,
+            ignore: Vec::new(),
+            max_output_chars: 8000,
+        }
+    }
+}
+
+impl WatchConfig {
+    /// Directory names skipped when scanning: `workspace_ignore`, which task
+    /// snapshots skip too, and this config's own
+    pub fn ignored_dirs(&self, workspace_ignore: &[String]) -> Vec<String> {
+        let mut ignore = workspace_ignore.to_vec();
+        for name in &self.ignore {
+            if !ignore.contains(name) {
+                ignore.push(name.clone());
+            }
+        }
+        ignore
+    }
+}
+
+/// Modification times and sizes of the files in a workspace
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkspaceSnapshot {
+    files: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl WorkspaceSnapshot {
+    /// Scan `root`, skipping directories named in `ignore`
+    ///
+    /// Covers the same files as the hashed snapshots tasks are recorded
+    /// with, but only stats them, since it is taken on every poll.
+    pub fn capture(root: &Path, ignore: &[String]) -> std::io::Result<Self> {
+        let mut files = HashMap::new();
+        for relative in workspace_files(root, ignore)? {
+            let metadata = match std::fs::metadata(root.join(&relative)) {
+                Ok(metadata) => metadata,
+                // Removed since it was listed, as editors' temporary files are
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            files.insert(relative, (metadata.modified()?, metadata.len()));
+        }
+        Ok(Self { files })
+    }
+
+    /// Whether the snapshot has a file at `path` (relative to the root)
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// Files added, modified or removed since `earlier`, sorted
+    pub fn changed_since(&self, earlier: &WorkspaceSnapshot) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, stamp)| earlier.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .chain(
+                earlier
+                    .files
+                    .keys()
+                    .filter(|path| !self.files.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changed.sort();
+        changed
+    }
+}
+
+/// Limits how often watch mode starts fix tasks
+#[derive(Debug, Clone)]
+pub struct FixThrottle {
+    min_interval: Duration,
+    max_per_hour: u32,
+    recent: VecDeque<Instant>,
+}
+
+impl FixThrottle {
+    pub fn new(config: &WatchConfig) -> Self {
+        Self {
+            min_interval: Duration::from_secs(config.min_fix_interval_secs),
+            max_per_hour: config.max_fixes_per_hour,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// `None` if a fix may start at `now`, else how long until one may
+    pub fn wait_time(&mut self, now: Instant) -> Option<Duration> {
+        const HOUR: Duration = Duration::from_secs(3600);
+        while self
+            .recent
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= HOUR)
+        {
+            self.recent.pop_front();
+        }
+
+        let interval_wait = self
+            .recent
+            .back()
+            .map(|last| self.min_interval.saturating_sub(now.duration_since(*last)))
+            .filter(|wait| !wait.is_zero());
+        let hourly_wait = (self.recent.len() as u32 >= self.max_per_hour)
+            .then(|| {
+                self.recent
+                    .front()
+                    .map(|oldest| HOUR.saturating_sub(now.duration_since(*oldest)))
+            })
+            .flatten();
+
+        interval_wait.max(hourly_wait)
+    }
+
+    /// Record that a fix started at `now`
+    pub fn record(&mut self, now: Instant) {
+        self.recent.push_back(now);
+    }
+}
+
+/// Last `max_chars` characters of a command's combined output
+///
+/// The flag is set when earlier output was cut off.
+pub fn output_tail(result: &ExecutionResult, max_chars: usize) -> (String, bool) {
+    let output = format!("{}\n{}", result.stdout, result.stderr);
+    let output = output.trim();
+    let skip = output.chars().count().saturating_sub(max_chars);
+    (output.chars().skip(skip).collect(), skip > 0)
+}
+
+/// Build the fix task for a failed verification run
+pub fn fix_task_spec(
+    verify_command: &str,
+    result: &ExecutionResult,
+    changed: &[PathBuf],
+    max_output_chars: usize,
+) -> TaskSpec {
+    let (tail, truncated) = output_tail(result, max_output_chars);
+
+    let changed_list = if changed.is_empty() {
+        "(none detected)".to_string()
+    } else {
+        changed
+            .iter()
+            .map(|path| format!("- {}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    TaskSpec {
+        title: format!("Fix failing verification: {}", verify_command),
+        description: format!(
+            "The verification command `{}` failed with exit code {} after recent edits.\n\n\
+             Recently changed files:\n{}\n\n\
+             Output{}:\n```\n{}\n```\n\n\
+             Find the cause and fix it so the command passes. Prefer fixing the code over \
+             changing or deleting tests, unless the edits intentionally changed behaviour.",
+            verify_command,
+            result.exit_code,
+            changed_list,
+            if truncated { " (last part)" } else { "" },
+            tail
+        ),
+        dependencies: Vec::new(),
+        metadata: TaskMetadata {
+            priority: TaskPriority::High,
+            tags: vec!["watch".to_string(), "auto-fix".to_string()],
+            ..Default::default()
+        },
+        plan: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_detects_changes_and_ignores_dirs() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn a() {}").unwrap();
+        std::fs::write(root.join("README.md"), "docs").unwrap();
+        let config = WatchConfig {
+            ignore: vec!["dist".to_string()],
+            ..Default::default()
+        };
+        let ignore = config.ignored_dirs(&crate::task::VerificationConfig::default().ignore);
+        assert!(ignore.contains(&"target".to_string()) && ignore.contains(&"dist".to_string()));
+
+        let before = WorkspaceSnapshot::capture(root, &ignore).unwrap();
+        std::fs::write(root.join("target/build.log"), "noise").unwrap();
+        assert!(
+            WorkspaceSnapshot::capture(root, &ignore)
+                .unwrap()
+                .changed_since(&before)
+                .is_empty()
+        );
+
+        std::fs::write(root.join("src/lib.rs"), "fn a() { todo!() }").unwrap();
+        std::fs::remove_file(root.join("README.md")).unwrap();
+        std::fs::write(root.join("src/new.rs"), "").unwrap();
+        let after = WorkspaceSnapshot::capture(root, &ignore).unwrap();
+        assert_eq!(
+            after.changed_since(&before),
+            vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("src/new.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_throttle_bounds_frequency() {
+        let config = WatchConfig {
+            min_fix_interval_secs: 60,
+            max_fixes_per_hour: 2,
+            ..Default::default()
+        };
+        let mut throttle = FixThrottle::new(&config);
+        let start = Instant::now();
+
+        assert_eq!(throttle.wait_time(start), None);
+        throttle.record(start);
+        assert_eq!(
+            throttle.wait_time(start + Duration::from_secs(20)),
+            Some(Duration::from_secs(40))
+        );
+
+        throttle.record(start + Duration::from_secs(60));
+        assert_eq!(
+            throttle.wait_time(start + Duration::from_secs(600)),
+            Some(Duration::from_secs(3000))
+        );
+        assert_eq!(throttle.wait_time(start + Duration::from_secs(3600)), None);
+    }
+}
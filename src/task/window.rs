@@ -0,0 +1,237 @@
+//! Time-window and quota constraints on task execution.
+//!
+//! Lets unattended runs share provider rate limits with daytime interactive
+//! use: tasks only start inside the configured windows (for example
+//! `02:00-06:00` on weekdays) and while the provider's daily token quota has
+//! enough headroom. Outside those constraints the execution loop checkpoints,
+//! pauses and resumes automatically once they are satisfied again.
+//!
+//! ```toml
+//! [schedule]
+//! daily_token_quota = 2000000
+//! min_quota_remaining = 100000
+//!
+//! [[schedule.windows]]
+//! start = "02:00"
+//! end = "06:00"
+//! days = ["Mon", "Tue", "Wed", "Thu", "Fri"]
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A daily window (in `HH:MM`) during which tasks may start
+///
+/// Windows whose end is before their start wrap past midnight; `days` then
+/// refers to the day the window opens. An empty `days` list means every day.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeWindow {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+}
+
+impl TimeWindow {
+    pub fn new(start: impl Into<String>, end: impl Into<String>) -> Self {
+        Self {
+            start: start.into(),
+            end: end.into(),
+            days: Vec::new(),
+        }
+    }
+
+    pub fn with_days(mut self, days: Vec<Weekday>) -> Self {
+        self.days = days;
+        self
+    }
+
+    fn bounds(&self) -> Result<(NaiveTime, NaiveTime)> {
+        let parse = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .with_context(|| format!("Invalid window time '{}', expected HH:MM", value))
+        };
+        Ok((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    fn allows_day(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether `at` falls inside this window
+    pub fn contains(&self, at: NaiveDateTime) -> Result<bool> {
+        let (start, end) = self.bounds()?;
+        let time = at.time();
+        let day = at.weekday();
+
+        Ok(if start <= end {
+            time >= start && time < end && self.allows_day(day)
+        } else if time >= start {
+            self.allows_day(day)
+        } else {
+            // Early-morning part of a window that opened the previous day
+            time < end && self.allows_day(day.pred())
+        })
+    }
+}
+
+/// Scheduling constraints applied before each task starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Allowed execution windows; empty means any time
+    pub windows: Vec<TimeWindow>,
+    /// Evaluate windows in UTC instead of local time
+    pub utc: bool,
+    /// Provider daily token quota (UTC day) used for the headroom check
+    pub daily_token_quota: Option<u64>,
+    /// Pause when fewer than this many tokens of the daily quota remain
+    pub min_quota_remaining: u64,
+    /// How often a paused run re-checks its constraints
+    pub poll_interval_secs: u64,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            windows: Vec::new(),
+            utc: false,
+            daily_token_quota: None,
+            min_quota_remaining: 0,
+            poll_interval_secs: 60,
+        }
+    }
+}
+
+/// Outcome of a schedule check
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleDecision {
+    Run,
+    Pause { reason: String },
+}
+
+impl ScheduleConfig {
+    /// Whether any constraint is configured
+    pub fn is_constrained(&self) -> bool {
+        !self.windows.is_empty() || self.daily_token_quota.is_some()
+    }
+
+    /// Check that all windows parse
+    pub fn validate(&self) -> Result<()> {
+        for window in &self.windows {
+            window.bounds()?;
+        }
+        Ok(())
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs.max(1))
+    }
+
+    /// Current wall-clock time in the schedule's time zone
+    pub fn now(&self) -> NaiveDateTime {
+        if self.utc {
+            chrono::Utc::now().naive_utc()
+        } else {
+            chrono::Local::now().naive_local()
+        }
+    }
+
+    /// Evaluate the constraints against the current clock
+    pub fn check(&self, tokens_used_today: u64) -> Result<ScheduleDecision> {
+        self.check_at(self.now(), tokens_used_today)
+    }
+
+    /// Evaluate the constraints at a given wall-clock time
+    pub fn check_at(&self, at: NaiveDateTime, tokens_used_today: u64) -> Result<ScheduleDecision> {
+        if let Some(quota) = self.daily_token_quota {
+            let remaining = quota.saturating_sub(tokens_used_today);
+            if remaining < self.min_quota_remaining {
+                return Ok(ScheduleDecision::Pause {
+                    reason: format!(
+                        "daily token quota nearly exhausted ({} of {} remaining)",
+                        remaining, quota
+                    ),
+                });
+            }
+        }
+
+        if self.windows.is_empty() {
+            return Ok(ScheduleDecision::Run);
+        }
+
+        for window in &self.windows {
+            if window.contains(at)? {
+                return Ok(ScheduleDecision::Run);
+            }
+        }
+
+        Ok(ScheduleDecision::Pause {
+            reason: format!(
+                "outside allowed execution windows at {}",
+                at.format("%a %H:%M")
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2025-10-13 is a Monday
+        NaiveDate::from_ymd_opt(2025, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_simple_window() {
+        let window = TimeWindow::new("02:00", "06:00");
+        assert!(window.contains(at(13, 2, 0)).unwrap());
+        assert!(window.contains(at(13, 5, 59)).unwrap());
+        assert!(!window.contains(at(13, 6, 0)).unwrap());
+        assert!(!window.contains(at(13, 14, 0)).unwrap());
+    }
+
+    #[test]
+    fn test_overnight_window_respects_start_day() {
+        let window = TimeWindow::new("22:00", "06:00").with_days(vec![Weekday::Fri]);
+        // Friday night and the following Saturday morning
+        assert!(window.contains(at(17, 23, 0)).unwrap());
+        assert!(window.contains(at(18, 3, 0)).unwrap());
+        // Friday morning belongs to Thursday's window
+        assert!(!window.contains(at(17, 3, 0)).unwrap());
+    }
+
+    #[test]
+    fn test_quota_headroom_pauses() {
+        let config = ScheduleConfig {
+            daily_token_quota: Some(1000),
+            min_quota_remaining: 200,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.check_at(at(13, 12, 0), 700).unwrap(),
+            ScheduleDecision::Run
+        );
+        assert!(matches!(
+            config.check_at(at(13, 12, 0), 900).unwrap(),
+            ScheduleDecision::Pause { .. }
+        ));
+    }
+
+    #[test]
+    fn test_invalid_window_rejected() {
+        let config = ScheduleConfig {
+            windows: vec![TimeWindow::new("2am", "06:00")],
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,288 @@
+//! Unified diffs of workspace files.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Lines of unchanged context around each hunk
+const CONTEXT: usize = 3;
+
+/// Largest line comparison table, in cells, before a file is diffed as a
+/// whole-file replacement
+const MAX_TABLE: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tag {
+    Same,
+    Removed,
+    Added,
+}
+
+/// A line-level edit, with the old and new line indexes where it applies
+#[derive(Debug, Clone, Copy)]
+struct Op {
+    tag: Tag,
+    old: usize,
+    new: usize,
+}
+
+/// Lines added and removed in one file of a diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStat {
+    pub path: PathBuf,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Per-file line counts of a diff written by [`unified_diff`], in order;
+/// binary and skipped files count no lines
+pub fn diff_stat(diff: &str) -> Vec<FileStat> {
+    let mut stats: Vec<FileStat> = Vec::new();
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // `a/<path> b/<path>`, the same path twice
+            let len = paths.len().saturating_sub(5) / 2;
+            stats.push(FileStat {
+                path: PathBuf::from(paths.get(2..2 + len).unwrap_or(paths)),
+                added: 0,
+                removed: 0,
+            });
+            in_hunk = false;
+            continue;
+        }
+        let Some(stat) = stats.last_mut() else {
+            continue;
+        };
+        match line.as_bytes().first() {
+            Some(b'@') if line.starts_with("@@ ") => in_hunk = true,
+            Some(b'+') if in_hunk => stat.added += 1,
+            Some(b'-') if in_hunk => stat.removed += 1,
+            _ => {}
+        }
+    }
+    stats
+}
+
+/// Unified diff of one file, `None` on a side where it does not exist
+pub fn unified_diff(path: &Path, old: Option<&str>, new: Option<&str>) -> String {
+    let path = path.display();
+    let mut out = format!("diff --git a/{} b/{}\n", path, path);
+    match old {
+        Some(_) => out.push_str(&format!("--- a/{}\n", path)),
+        None => out.push_str("--- /dev/null\n"),
+    }
+    match new {
+        Some(_) => out.push_str(&format!("+++ b/{}\n", path)),
+        None => out.push_str("+++ /dev/null\n"),
+    }
+
+    let old: Vec<&str> = old.map(|text| text.lines().collect()).unwrap_or_default();
+    let new: Vec<&str> = new.map(|text| text.lines().collect()).unwrap_or_default();
+    let ops = line_ops(&old, &new);
+    for (start, end) in hunks(&ops) {
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| op.tag != Tag::Added).count();
+        let new_count = hunk.iter().filter(|op| op.tag != Tag::Removed).count();
+        // An empty side is numbered by the line before it
+        let line = |index: usize, count: usize| if count == 0 { index } else { index + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            line(hunk[0].old, old_count),
+            old_count,
+            line(hunk[0].new, new_count),
+            new_count
+        ));
+        for op in hunk {
+            let (prefix, text) = match op.tag {
+                Tag::Same => (' ', old[op.old]),
+                Tag::Removed => ('-', old[op.old]),
+                Tag::Added => ('+', new[op.new]),
+            };
+            out.push(prefix);
+            out.push_str(text);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Header for a file too large or not text enough to diff by line
+pub fn binary_diff(path: &Path, old_exists: bool, new_exists: bool) -> String {
+    format!(
+        "diff --git a/{} b/{}\nBinary files {} and {} differ\n",
+        path.display(),
+        path.display(),
+        side(path, old_exists, "a"),
+        side(path, new_exists, "b")
+    )
+}
+
+/// Header for a file left out of diffs, saying why
+pub fn skipped_diff(path: &Path, old_exists: bool, new_exists: bool, reason: &str) -> String {
+    format!(
+        "diff --git a/{} b/{}\nFiles {} and {} differ ({}; not diffed)\n",
+        path.display(),
+        path.display(),
+        side(path, old_exists, "a"),
+        side(path, new_exists, "b"),
+        reason
+    )
+}
+
+/// One side of a diff header, `/dev/null` where the file does not exist
+fn side(path: &Path, exists: bool, prefix: &str) -> String {
+    if exists {
+        format!("{}/{}", prefix, path.display())
+    } else {
+        "/dev/null".to_string()
+    }
+}
+
+/// Edits turning `old` into `new`, from their longest common subsequence
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<Op> = (0..prefix)
+        .map(|i| Op {
+            tag: Tag::Same,
+            old: i,
+            new: i,
+        })
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    if a.len().saturating_mul(b.len()) > MAX_TABLE {
+        // Too large to compare line by line: replace the middle wholesale
+        while i < a.len() {
+            ops.push(op(Tag::Removed, prefix + i, prefix));
+            i += 1;
+        }
+        while j < b.len() {
+            ops.push(op(Tag::Added, prefix + a.len(), prefix + j));
+            j += 1;
+        }
+    } else {
+        // lcs[i][j] is the common subsequence length of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push(op(Tag::Same, prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < a.len()
+                && (j == b.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(op(Tag::Removed, prefix + i, prefix + j));
+                i += 1;
+            } else {
+                ops.push(op(Tag::Added, prefix + i, prefix + j));
+                j += 1;
+            }
+        }
+    }
+    let (old_tail, new_tail) = (old.len() - suffix, new.len() - suffix);
+    ops.extend((0..suffix).map(|k| op(Tag::Same, old_tail + k, new_tail + k)));
+    ops
+}
+
+fn op(tag: Tag, old: usize, new: usize) -> Op {
+    Op { tag, old, new }
+}
+
+/// Ranges of `ops` to print: each change with its context, overlaps merged
+fn hunks(ops: &[Op]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, op) in ops.iter().enumerate() {
+        if op.tag == Tag::Same {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modified_file_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let diff = unified_diff(Path::new("src/lib.rs"), Some(old), Some(new));
+        assert_eq!(
+            diff,
+            "diff --git a/src/lib.rs b/src/lib.rs\n\
+             --- a/src/lib.rs\n\
+             +++ b/src/lib.rs\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+        );
+    }
+
+    #[test]
+    fn test_created_and_deleted_files() {
+        let created = unified_diff(Path::new("new.txt"), None, Some("one\ntwo\n"));
+        assert!(created.contains("--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"));
+
+        let deleted = unified_diff(Path::new("old.txt"), Some("gone\n"), None);
+        assert!(deleted.ends_with("--- a/old.txt\n+++ /dev/null\n@@ -1,1 +0,0 @@\n-gone\n"));
+
+        let binary = binary_diff(Path::new("logo.png"), false, true);
+        assert!(binary.contains("Binary files /dev/null and b/logo.png differ"));
+    }
+
+    #[test]
+    fn test_diff_stat() {
+        let mut diff = unified_diff(
+            Path::new("src/lib.rs"),
+            Some("a\nb\nc\n"),
+            Some("a\nB\nc\nd\n"),
+        );
+        diff.push_str(&unified_diff(Path::new("my b/new.rs"), None, Some("x\n")));
+        diff.push_str(&binary_diff(Path::new("logo.png"), true, true));
+
+        assert_eq!(
+            diff_stat(&diff),
+            vec![
+                FileStat {
+                    path: PathBuf::from("src/lib.rs"),
+                    added: 2,
+                    removed: 1,
+                },
+                FileStat {
+                    path: PathBuf::from("my b/new.rs"),
+                    added: 1,
+                    removed: 0,
+                },
+                FileStat {
+                    path: PathBuf::from("logo.png"),
+                    added: 0,
+                    removed: 0,
+                },
+            ]
+        );
+    }
+}
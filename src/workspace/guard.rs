@@ -0,0 +1,128 @@
+//! Keeping file access inside the workspace.
+//!
+//! Paths aca reads, writes or mounts on a task's behalf are resolved when
+//! they are used rather than when they were first seen: `..` components are
+//! applied and every symlink along the path is followed, so a symlink a task
+//! created since cannot lead outside the workspace. Dangling symlinks are
+//! refused, since writing through one would create its target wherever it
+//! points.
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Where `path`, relative to `root` or absolute, really is, or a
+/// `PermissionDenied` error if that is outside `root`
+///
+/// The path does not need to exist; the part of it that does is resolved
+/// through its symlinks and the rest appended as is.
+pub fn resolve_within(root: &Path, path: &Path) -> io::Result<PathBuf> {
+    let root = root.canonicalize()?;
+    let mut resolved = PathBuf::new();
+    let mut missing = false;
+    for component in root.join(path).components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => resolved.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if missing {
+                    continue;
+                }
+                match std::fs::symlink_metadata(&resolved) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        resolved = resolved.canonicalize().map_err(|_| {
+                            escape(&root, path, "is a dangling symlink".to_string())
+                        })?;
+                    }
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => missing = true,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+    if !resolved.starts_with(&root) {
+        return Err(escape(
+            &root,
+            path,
+            format!("resolves to {}", resolved.display()),
+        ));
+    }
+    Ok(resolved)
+}
+
+fn escape(root: &Path, path: &Path, reason: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+            "{} {}, outside the workspace {}",
+            path.display(),
+            reason,
+            root.display()
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_paths_inside_workspace_resolve() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+
+        assert_eq!(
+            resolve_within(&root, Path::new("src/lib.rs")).unwrap(),
+            root.join("src/lib.rs")
+        );
+        assert_eq!(
+            resolve_within(&root, Path::new("src/../new/dir/file.rs")).unwrap(),
+            root.join("new/dir/file.rs")
+        );
+        assert_eq!(
+            resolve_within(&root, &root.join("src")).unwrap(),
+            root.join("src")
+        );
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("src", root.join("code")).unwrap();
+            assert_eq!(
+                resolve_within(&root, Path::new("code/lib.rs")).unwrap(),
+                root.join("src/lib.rs")
+            );
+        }
+    }
+
+    #[test]
+    fn test_escapes_are_refused() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let root = workspace.path();
+
+        let error = resolve_within(root, Path::new("../elsewhere/file")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert!(
+            error.to_string().contains("outside the workspace"),
+            "{}",
+            error
+        );
+        assert!(resolve_within(root, outside.path()).is_err());
+
+        #[cfg(unix)]
+        {
+            // Links a task might create to reach files elsewhere
+            std::os::unix::fs::symlink(outside.path(), root.join("out")).unwrap();
+            std::os::unix::fs::symlink(root.join("gone"), root.join("dangling")).unwrap();
+            assert!(resolve_within(root, Path::new("out/secrets.txt")).is_err());
+            let error = resolve_within(root, Path::new("dangling")).unwrap_err();
+            assert!(error.to_string().contains("dangling symlink"), "{}", error);
+        }
+    }
+}
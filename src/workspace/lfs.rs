@@ -0,0 +1,176 @@
+//! Git LFS awareness.
+//!
+//! A file stored in Git LFS is either checked out as its real contents,
+//! when git-lfs is installed and the object was fetched, or as a small
+//! pointer naming the object. Neither belongs in a prompt or a line diff:
+//! the pointer says nothing about the file, and the real contents are
+//! usually large or binary. Files count as LFS files when they are pointers
+//! or match a `filter=lfs` pattern in the workspace's root
+//! `.gitattributes`; `.gitattributes` files in subdirectories are not read.
+
+use regex::Regex;
+use std::path::Path;
+
+/// First line of every Git LFS pointer
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// Pointers are well under this size; larger files are never parsed
+const MAX_POINTER_BYTES: usize = 1024;
+
+/// A Git LFS pointer file standing in for an object that was not fetched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LfsPointer {
+    /// `sha256:<hex>` id of the object
+    pub oid: String,
+    /// Size of the object in bytes
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Parse `contents` as a pointer, `None` if they are not one
+    pub fn parse(contents: &[u8]) -> Option<Self> {
+        if contents.len() > MAX_POINTER_BYTES {
+            return None;
+        }
+        let text = std::str::from_utf8(contents).ok()?;
+        let mut lines = text.lines();
+        if lines.next()? != POINTER_VERSION {
+            return None;
+        }
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            match line.split_once(' ')? {
+                ("oid", value) => oid = Some(value.to_string()),
+                ("size", value) => size = Some(value.parse().ok()?),
+                _ => {}
+            }
+        }
+        Some(Self {
+            oid: oid?,
+            size: size?,
+        })
+    }
+}
+
+/// `filter=lfs` patterns from a workspace's `.gitattributes`
+#[derive(Debug, Clone, Default)]
+pub struct LfsAttributes {
+    /// Patterns setting or unsetting the filter, in file order; the last
+    /// match decides
+    patterns: Vec<(Regex, bool)>,
+}
+
+impl LfsAttributes {
+    /// Patterns in `<root>/.gitattributes`, none if it does not exist
+    pub fn load(root: &Path) -> Self {
+        std::fs::read_to_string(root.join(".gitattributes"))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Patterns in the contents of a `.gitattributes` file
+    pub fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next().filter(|field| !field.starts_with('#'))?;
+                let lfs = fields.find_map(|attribute| match attribute {
+                    "filter=lfs" => Some(true),
+                    "-filter" | "!filter" => Some(false),
+                    other => other.starts_with("filter=").then_some(false),
+                })?;
+                Some((pattern_regex(pattern)?, lfs))
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Whether the file at `path`, relative to the workspace root, is
+    /// stored in Git LFS
+    pub fn tracks(&self, path: &Path) -> bool {
+        let Some(path) = path.to_str() else {
+            return false;
+        };
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.is_match(path))
+            .is_some_and(|(_, lfs)| *lfs)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
+/// Regex for a `.gitattributes` pattern: without a `/` it matches file
+/// names at any depth, with one it matches from the root
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let mut regex = String::from(if anchored { "^" } else { "(^|/)" });
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pointer() {
+        let pointer = b"version https://git-lfs.github.com/spec/v1\n\
+            oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+            size 12345\n";
+        assert_eq!(
+            LfsPointer::parse(pointer),
+            Some(LfsPointer {
+                oid: "sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393"
+                    .to_string(),
+                size: 12345,
+            })
+        );
+        assert_eq!(
+            LfsPointer::parse(b"version https://git-lfs.github.com/spec/v1\nsize 1\n"),
+            None
+        );
+        assert_eq!(LfsPointer::parse(b"oid sha256:abc\nsize 1\n"), None);
+    }
+
+    #[test]
+    fn test_gitattributes_patterns() {
+        let attributes = LfsAttributes::parse(
+            "# large assets\n\
+             *.psd filter=lfs diff=lfs merge=lfs -text\n\
+             /models/** filter=lfs diff=lfs merge=lfs -text\n\
+             data/*.csv filter=lfs\n\
+             models/README.md -filter\n\
+             *.rs text eol=lf\n",
+        );
+        assert!(attributes.tracks(Path::new("art/cover.psd")));
+        assert!(attributes.tracks(Path::new("models/v2/weights.bin")));
+        assert!(attributes.tracks(Path::new("data/users.csv")));
+        assert!(!attributes.tracks(Path::new("data/raw/users.csv")));
+        assert!(!attributes.tracks(Path::new("models/README.md")));
+        assert!(!attributes.tracks(Path::new("src/main.rs")));
+        assert!(LfsAttributes::parse("*.rs text\n").is_empty());
+    }
+}
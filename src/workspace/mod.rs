@@ -0,0 +1,843 @@
+//! Snapshots of the workspace taken around each task.
+//!
+//! Every file is hashed before a task runs and again after it finishes;
+//! the difference is the set of files the task created, modified and
+//! deleted. Files up to 1 MiB are also kept in memory, so the change can be
+//! written out as a unified diff artifact in the session logs and the files
+//! as they were before the task stored for rolling it back. Tasks that run
+//! in parallel share the workspace, so a file changed while two tasks
+//! overlapped cannot be attributed to either: it is listed as shared and
+//! left out of both tasks' diffs and rollback records.
+//!
+//! Diffs decode files with [`TextFileConfig`]: UTF-16 and Latin-1 files are
+//! diffed by line like UTF-8 ones, and binary, oversized or excluded files
+//! are reported as `Binary files … differ`. Files stored in Git LFS and
+//! files over the size limits are never diffed; their diffs only say the
+//! file changed and why. Files whose names are not UTF-8 cannot be recorded
+//! in the session's JSON and are left out of snapshots.
+
+mod diff;
+mod guard;
+mod lfs;
+mod text;
+
+pub use diff::{FileStat, binary_diff, diff_stat, skipped_diff, unified_diff};
+pub use guard::resolve_within;
+pub use lfs::{LfsAttributes, LfsPointer};
+pub use text::{DecodedText, TextEncoding, TextFileConfig, decode_text};
+
+use crate::session::integrity::content_hash;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Files larger than this are hashed but neither diffed nor restorable
+const MAX_STORED_FILE_BYTES: usize = 1024 * 1024;
+
+/// Rollback record within a task's directory under the diffs directory
+const ROLLBACK_FILE_NAME: &str = "rollback.json";
+
+/// Copies of the files a task changed, as they were before it ran
+const BEFORE_DIR_NAME: &str = "before";
+
+#[derive(Debug, Clone, PartialEq)]
+struct FileSnapshot {
+    hash: String,
+    len: u64,
+    /// Contents of a file small enough to keep
+    contents: Option<Vec<u8>>,
+}
+
+impl FileSnapshot {
+    /// Contents decoded as text, if kept and text
+    fn text(&self, path: &Path, text_files: &TextFileConfig) -> Option<DecodedText> {
+        text_files.decode(path, self.contents.as_deref()?)
+    }
+
+    fn is_lfs_pointer(&self) -> bool {
+        self.contents
+            .as_deref()
+            .is_some_and(|contents| LfsPointer::parse(contents).is_some())
+    }
+}
+
+/// Files under `root` that count as workspace files, relative to it
+///
+/// Directories named in `ignore` are skipped, and so are files whose names
+/// are not UTF-8. Snapshots, verification groups, spot checks and watch mode
+/// all list the workspace this way.
+pub fn workspace_files(root: &Path, ignore: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if !ignore.iter().any(|name| entry.file_name() == name.as_str()) {
+                    pending.push(entry.path());
+                }
+            } else if file_type.is_file() {
+                let path = entry.path();
+                if path.to_str().is_none() {
+                    warn!("Not tracking {:?}: its name is not UTF-8", path);
+                    continue;
+                }
+                files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Hashes of every file in the workspace at one point in time
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSnapshot {
+    files: HashMap<PathBuf, FileSnapshot>,
+    /// Git LFS patterns in effect when the snapshot was taken
+    lfs: LfsAttributes,
+}
+
+impl WorkspaceSnapshot {
+    /// Hash every file under `root`, skipping directories named in `ignore`
+    pub fn capture(root: &Path, ignore: &[String]) -> io::Result<Self> {
+        let mut files = HashMap::new();
+        for relative in workspace_files(root, ignore)? {
+            let contents = std::fs::read(root.join(&relative))?;
+            let hash = content_hash(&contents);
+            let len = contents.len() as u64;
+            let contents = (contents.len() <= MAX_STORED_FILE_BYTES).then_some(contents);
+            files.insert(
+                relative,
+                FileSnapshot {
+                    hash,
+                    len,
+                    contents,
+                },
+            );
+        }
+        Ok(Self {
+            files,
+            lfs: LfsAttributes::load(root),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// `sha256:<hex>` hash of a file, relative to the workspace root
+    pub fn hash(&self, path: &Path) -> Option<&str> {
+        self.files.get(path).map(|file| file.hash.as_str())
+    }
+
+    /// Contents of the file at `path`, if it exists and was small enough
+    /// to keep
+    pub fn contents(&self, path: &Path) -> Option<&[u8]> {
+        self.files.get(path)?.contents.as_deref()
+    }
+
+    /// Whether the file at `path` can be put back as it was: it did not
+    /// exist, or was small enough to keep
+    pub fn can_restore(&self, path: &Path) -> bool {
+        self.files
+            .get(path)
+            .is_none_or(|file| file.contents.is_some())
+    }
+
+    /// Files that differ in `later`
+    pub fn changes(&self, later: &WorkspaceSnapshot) -> WorkspaceChanges {
+        let mut changes = WorkspaceChanges::default();
+        for (path, file) in &later.files {
+            match self.files.get(path) {
+                None => changes.created.push(path.clone()),
+                Some(before) if before.hash != file.hash => changes.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.deleted = self
+            .files
+            .keys()
+            .filter(|path| !later.files.contains_key(*path))
+            .cloned()
+            .collect();
+        changes.created.sort();
+        changes.modified.sort();
+        changes.deleted.sort();
+        changes
+    }
+
+    /// Whether the file at `path` is stored in Git LFS: a pointer, or
+    /// matched by a `filter=lfs` pattern
+    pub fn is_lfs(&self, path: &Path) -> bool {
+        self.lfs.tracks(path)
+            || self
+                .files
+                .get(path)
+                .is_some_and(FileSnapshot::is_lfs_pointer)
+    }
+
+    /// Unified diff of `changes` between this snapshot and `later`
+    ///
+    /// Files `text_files` does not read as text, or whose encoding changed,
+    /// only get a header, as do Git LFS files and files over the size limit.
+    pub fn diff(
+        &self,
+        later: &WorkspaceSnapshot,
+        changes: &WorkspaceChanges,
+        text_files: &TextFileConfig,
+    ) -> String {
+        let limit = text_files.max_text_bytes.min(MAX_STORED_FILE_BYTES as u64);
+        let mut paths: Vec<&PathBuf> = changes.all().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let (before, after) = (self.files.get(path), later.files.get(path));
+                let (old_exists, new_exists) = (before.is_some(), after.is_some());
+                if self.is_lfs(path) || later.is_lfs(path) {
+                    return skipped_diff(path, old_exists, new_exists, "stored in Git LFS");
+                }
+                if before.into_iter().chain(after).any(|file| file.len > limit) {
+                    let reason = format!("over {} bytes", limit);
+                    return skipped_diff(path, old_exists, new_exists, &reason);
+                }
+                let text = |file: &FileSnapshot| file.text(path, text_files);
+                match (before.map(text), after.map(text)) {
+                    (Some(None), _) | (_, Some(None)) => binary_diff(path, old_exists, new_exists),
+                    (Some(Some(old)), Some(Some(new))) if old.encoding != new.encoding => {
+                        binary_diff(path, true, true)
+                    }
+                    (before, after) => unified_diff(
+                        path,
+                        before.flatten().as_ref().map(|old| old.text.as_str()),
+                        after.flatten().as_ref().map(|new| new.text.as_str()),
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Files a task created, modified and deleted, relative to the workspace root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceChanges {
+    pub created: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+}
+
+impl WorkspaceChanges {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+
+    /// Every changed path
+    pub fn all(&self) -> impl Iterator<Item = &PathBuf> {
+        self.created
+            .iter()
+            .chain(&self.modified)
+            .chain(&self.deleted)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.all().any(|changed| changed == path)
+    }
+
+    fn remove(&mut self, paths: &BTreeSet<PathBuf>) {
+        for list in [&mut self.created, &mut self.modified, &mut self.deleted] {
+            list.retain(|path| !paths.contains(path));
+        }
+    }
+}
+
+/// What a task changed, and where its diff was written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskDiff {
+    #[serde(flatten)]
+    pub changes: WorkspaceChanges,
+    /// Unified diff artifact; `None` when nothing changed
+    pub diff_path: Option<PathBuf>,
+    /// Created and modified files stored in Git LFS
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lfs_files: Vec<PathBuf>,
+    /// Files changed while another task was running too, so left out of
+    /// `changes`, the diff and the rollback record
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_files: Vec<PathBuf>,
+}
+
+/// Start snapshot of a running task and the files it may share with tasks
+/// that overlapped it
+#[derive(Debug)]
+struct RunningTask {
+    before: Arc<WorkspaceSnapshot>,
+    shared: BTreeSet<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct RunningTasks {
+    next_key: u64,
+    tasks: HashMap<u64, RunningTask>,
+}
+
+/// Snapshot taken as a task starts, tracked until it is dropped so tasks
+/// running at the same time know which files they may share
+#[derive(Debug)]
+pub struct TaskSnapshot {
+    key: u64,
+    before: Arc<WorkspaceSnapshot>,
+    running: Arc<Mutex<RunningTasks>>,
+}
+
+impl TaskSnapshot {
+    /// Stop tracking the task, returning the files in `changes` that a task
+    /// overlapping it may have changed too
+    fn release(&self, changes: &WorkspaceChanges, after: &WorkspaceSnapshot) -> BTreeSet<PathBuf> {
+        let mut running = self.running.lock().unwrap();
+        let mut shared = running
+            .tasks
+            .remove(&self.key)
+            .map(|task| task.shared)
+            .unwrap_or_default();
+        // Files both this task and a still running one saw change
+        for other in running.tasks.values_mut() {
+            let both: Vec<PathBuf> = other
+                .before
+                .changes(after)
+                .all()
+                .filter(|path| changes.contains(path))
+                .cloned()
+                .collect();
+            other.shared.extend(both.iter().cloned());
+            shared.extend(both);
+        }
+        shared.retain(|path| changes.contains(path));
+        shared
+    }
+}
+
+impl Deref for TaskSnapshot {
+    type Target = WorkspaceSnapshot;
+
+    fn deref(&self) -> &WorkspaceSnapshot {
+        &self.before
+    }
+}
+
+impl Drop for TaskSnapshot {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.running.lock() {
+            running.tasks.remove(&self.key);
+        }
+    }
+}
+
+/// What is needed to undo a task's changes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct RollbackRecord {
+    #[serde(flatten)]
+    changes: WorkspaceChanges,
+    /// Hashes of the created and modified files as the task left them
+    after_hashes: BTreeMap<PathBuf, String>,
+    /// Modified or deleted files too large to have been kept
+    unrestorable: Vec<PathBuf>,
+}
+
+/// Takes workspace snapshots and records each task's diff
+#[derive(Debug, Clone)]
+pub struct SnapshotManager {
+    root: PathBuf,
+    ignore: Vec<String>,
+    diffs_dir: PathBuf,
+    text_files: TextFileConfig,
+    running: Arc<Mutex<RunningTasks>>,
+}
+
+impl SnapshotManager {
+    /// Snapshot `root`, skipping directories named in `ignore`, and write
+    /// diffs to `diffs_dir`
+    pub fn new(root: &Path, ignore: &[String], diffs_dir: PathBuf) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            ignore: ignore.to_vec(),
+            diffs_dir,
+            text_files: TextFileConfig::default(),
+            running: Arc::default(),
+        }
+    }
+
+    /// Decide which files are diffed as text with `text_files`
+    pub fn with_text_files(mut self, text_files: TextFileConfig) -> Self {
+        self.text_files = text_files;
+        self
+    }
+
+    /// Hash every file in the workspace
+    pub fn capture(&self) -> io::Result<WorkspaceSnapshot> {
+        WorkspaceSnapshot::capture(&self.root, &self.ignore)
+    }
+
+    /// Snapshot the workspace as a task starts
+    pub fn start(&self) -> io::Result<TaskSnapshot> {
+        // Held while capturing so a rollback cannot land in the snapshot
+        let mut running = self.running.lock().unwrap();
+        let before = Arc::new(self.capture()?);
+        let key = running.next_key;
+        running.next_key += 1;
+        running.tasks.insert(
+            key,
+            RunningTask {
+                before: before.clone(),
+                shared: BTreeSet::new(),
+            },
+        );
+        Ok(TaskSnapshot {
+            key,
+            before,
+            running: self.running.clone(),
+        })
+    }
+
+    /// Snapshot the workspace after a task and record what changed since
+    /// `before`, writing `<diffs_dir>/<task_id>.diff` and the pre-task copies
+    /// [`rollback`](Self::rollback) restores if anything did
+    ///
+    /// Files that a task overlapping this one also saw change are reported
+    /// as shared instead.
+    pub fn finish(&self, task_id: &str, before: &TaskSnapshot) -> io::Result<TaskDiff> {
+        let after = self.capture()?;
+        let mut changes = before.changes(&after);
+        let shared = before.release(&changes, &after);
+        changes.remove(&shared);
+        let shared_files: Vec<PathBuf> = shared.into_iter().collect();
+
+        // A rerun replaces whatever an earlier run of the task recorded
+        let rollback_dir = self.diffs_dir.join(task_id);
+        if rollback_dir.exists() {
+            std::fs::remove_dir_all(&rollback_dir)?;
+        }
+        if changes.is_empty() {
+            return Ok(TaskDiff {
+                changes,
+                diff_path: None,
+                lfs_files: Vec::new(),
+                shared_files,
+            });
+        }
+
+        std::fs::create_dir_all(&self.diffs_dir)?;
+        let diff_path = self.diffs_dir.join(format!("{}.diff", task_id));
+        std::fs::write(&diff_path, before.diff(&after, &changes, &self.text_files))?;
+
+        let mut record = RollbackRecord {
+            changes: changes.clone(),
+            ..Default::default()
+        };
+        for path in changes.created.iter().chain(&changes.modified) {
+            record
+                .after_hashes
+                .insert(path.clone(), after.files[path].hash.clone());
+        }
+        for path in changes.modified.iter().chain(&changes.deleted) {
+            match &before.files[path].contents {
+                Some(contents) => {
+                    let copy = rollback_dir.join(BEFORE_DIR_NAME).join(path);
+                    if let Some(parent) = copy.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(copy, contents)?;
+                }
+                None => record.unrestorable.push(path.clone()),
+            }
+        }
+        std::fs::create_dir_all(&rollback_dir)?;
+        std::fs::write(
+            rollback_dir.join(ROLLBACK_FILE_NAME),
+            serde_json::to_string_pretty(&record)?,
+        )?;
+
+        let lfs_files = changes
+            .created
+            .iter()
+            .chain(&changes.modified)
+            .filter(|path| before.is_lfs(path) || after.is_lfs(path))
+            .cloned()
+            .collect();
+        Ok(TaskDiff {
+            changes,
+            diff_path: Some(diff_path),
+            lfs_files,
+            shared_files,
+        })
+    }
+
+    /// Put the files a task changed back as they were before it ran
+    ///
+    /// Nothing is touched if a file the task changed has been changed again
+    /// since, or was too large to keep. Files it shared with overlapping
+    /// tasks were never recorded, so they are left as they are. Refused while
+    /// any task is running, whose own changes the restore would otherwise
+    /// revert or be attributed to. Returns the changes undone.
+    pub fn rollback(&self, task_id: &str) -> Result<WorkspaceChanges> {
+        let running = self.running.lock().unwrap();
+        if !running.tasks.is_empty() {
+            return Err(anyhow!(
+                "Cannot roll back task {}: {} task(s) still running",
+                task_id,
+                running.tasks.len()
+            ));
+        }
+
+        let rollback_dir = self.diffs_dir.join(task_id);
+        let record_path = rollback_dir.join(ROLLBACK_FILE_NAME);
+        let record: RollbackRecord = serde_json::from_str(
+            &std::fs::read_to_string(&record_path)
+                .with_context(|| format!("No recorded file changes for task {}", task_id))?,
+        )
+        .with_context(|| format!("Malformed rollback record {:?}", record_path))?;
+
+        if !record.unrestorable.is_empty() {
+            return Err(anyhow!(
+                "Cannot roll back task {}: {} were too large to keep",
+                task_id,
+                join_paths(&record.unrestorable)
+            ));
+        }
+        let mut conflicts = Vec::new();
+        for (path, hash) in &record.after_hashes {
+            let current = std::fs::read(self.root.join(path)).ok();
+            if current.as_deref().map(content_hash).as_ref() != Some(hash) {
+                conflicts.push(path.clone());
+            }
+        }
+        conflicts.extend(
+            record
+                .changes
+                .deleted
+                .iter()
+                .filter(|path| self.root.join(path).exists())
+                .cloned(),
+        );
+        if !conflicts.is_empty() {
+            return Err(anyhow!(
+                "Cannot roll back task {}: changed again since it finished: {}",
+                task_id,
+                join_paths(&conflicts)
+            ));
+        }
+
+        // Symlinks created since the task ran must not redirect the restore
+        for path in &record.changes.created {
+            std::fs::remove_file(resolve_within(&self.root, path)?)
+                .with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+        for path in record
+            .changes
+            .modified
+            .iter()
+            .chain(&record.changes.deleted)
+        {
+            let target = resolve_within(&self.root, path)?;
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(rollback_dir.join(BEFORE_DIR_NAME).join(path), &target)
+                .with_context(|| format!("Failed to restore {:?}", path))?;
+        }
+
+        // The task's changes are gone, so a second rollback has nothing to undo
+        std::fs::remove_dir_all(&rollback_dir)?;
+        Ok(record.changes)
+    }
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_changes_and_diff_artifact() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn one() {}\n").unwrap();
+        std::fs::write(root.join("README.md"), "readme\n").unwrap();
+        std::fs::write(root.join("logo.png"), [0u8, 1, 2]).unwrap();
+
+        let diffs_dir = root.join(".aca/logs/diffs");
+        let manager = SnapshotManager::new(root, &[".aca".into(), "target".into()], diffs_dir);
+        let before = manager.start().unwrap();
+        assert_eq!(before.len(), 3);
+
+        std::fs::write(root.join("src/lib.rs"), "fn one() {}\nfn two() {}\n").unwrap();
+        std::fs::write(root.join("src/new.rs"), "// new\n").unwrap();
+        std::fs::remove_file(root.join("README.md")).unwrap();
+        std::fs::write(root.join("logo.png"), [0u8, 1, 3]).unwrap();
+        std::fs::write(root.join("target/out"), "ignored").unwrap();
+
+        let task = manager.finish("task-1", &before).unwrap();
+        assert_eq!(task.changes.created, vec![PathBuf::from("src/new.rs")]);
+        assert_eq!(
+            task.changes.modified,
+            vec![PathBuf::from("logo.png"), PathBuf::from("src/lib.rs")]
+        );
+        assert_eq!(task.changes.deleted, vec![PathBuf::from("README.md")]);
+
+        let diff_path = task.diff_path.unwrap();
+        assert!(diff_path.ends_with("task-1.diff"));
+        let diff = std::fs::read_to_string(diff_path).unwrap();
+        assert!(diff.contains("+++ /dev/null\n@@ -1,1 +0,0 @@\n-readme\n"));
+        assert!(diff.contains("Binary files a/logo.png and b/logo.png differ"));
+        assert!(diff.contains("@@ -1,1 +1,2 @@\n fn one() {}\n+fn two() {}\n"));
+        assert!(diff.contains("--- /dev/null\n+++ b/src/new.rs\n"));
+    }
+
+    #[test]
+    fn test_diff_decodes_other_encodings() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::write(root.join("notes.txt"), b"caf\xE9\n").unwrap();
+        std::fs::write(root.join("wide.txt"), b"\xFF\xFEa\x00\n\x00").unwrap();
+        std::fs::write(root.join("moved.txt"), "same\n").unwrap();
+        std::fs::write(root.join("data.bin"), "plain text").unwrap();
+        let text_files = TextFileConfig {
+            binary_extensions: vec!["bin".to_string()],
+            ..Default::default()
+        };
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"))
+            .with_text_files(text_files);
+
+        let before = manager.start().unwrap();
+        std::fs::write(root.join("notes.txt"), b"caf\xE9 cr\xE8me\n").unwrap();
+        std::fs::write(root.join("wide.txt"), b"\xFF\xFEb\x00\n\x00").unwrap();
+        std::fs::write(root.join("moved.txt"), b"\xEF\xBB\xBFsame\n").unwrap();
+        std::fs::write(root.join("data.bin"), "other text").unwrap();
+
+        let task = manager.finish("task-5", &before).unwrap();
+        let diff = std::fs::read_to_string(task.diff_path.unwrap()).unwrap();
+        assert!(diff.contains("-café\n+café crème\n"), "{}", diff);
+        assert!(diff.contains("-a\n+b\n"), "{}", diff);
+        assert!(diff.contains("Binary files a/moved.txt and b/moved.txt differ"));
+        assert!(diff.contains("Binary files a/data.bin and b/data.bin differ"));
+    }
+
+    #[test]
+    fn test_lfs_and_large_files_are_not_diffed() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        let pointer = "version https://git-lfs.github.com/spec/v1\noid sha256:ab\nsize 9\n";
+        std::fs::write(root.join(".gitattributes"), "*.csv filter=lfs\n").unwrap();
+        std::fs::write(root.join("model.bin"), pointer).unwrap();
+        std::fs::write(root.join("users.csv"), "id\n1\n").unwrap();
+        std::fs::write(root.join("big.txt"), "a\n".repeat(8)).unwrap();
+        let text_files = TextFileConfig {
+            max_text_bytes: 10,
+            ..Default::default()
+        };
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"))
+            .with_text_files(text_files);
+
+        let before = manager.start().unwrap();
+        std::fs::write(root.join("model.bin"), "real weights").unwrap();
+        std::fs::write(root.join("users.csv"), "id\n1\n2\n").unwrap();
+        std::fs::write(root.join("big.txt"), "b\n".repeat(8)).unwrap();
+        std::fs::write(root.join("notes.csv"), "new\n").unwrap();
+
+        let task = manager.finish("task-6", &before).unwrap();
+        assert_eq!(
+            task.lfs_files,
+            vec![
+                PathBuf::from("notes.csv"),
+                PathBuf::from("model.bin"),
+                PathBuf::from("users.csv")
+            ]
+        );
+        let diff = std::fs::read_to_string(task.diff_path.unwrap()).unwrap();
+        assert!(diff.contains(
+            "Files a/model.bin and b/model.bin differ (stored in Git LFS; not diffed)\n"
+        ));
+        assert!(diff.contains("Files /dev/null and b/notes.csv differ (stored in Git LFS"));
+        assert!(diff.contains("Files a/big.txt and b/big.txt differ (over 10 bytes; not diffed)"));
+        assert!(!diff.contains("+2\n"), "{}", diff);
+    }
+
+    #[test]
+    fn test_unchanged_workspace_writes_no_diff() {
+        let workspace = TempDir::new().unwrap();
+        std::fs::write(workspace.path().join("a.txt"), "a").unwrap();
+        let manager = SnapshotManager::new(workspace.path(), &[], workspace.path().join("diffs"));
+
+        let before = manager.start().unwrap();
+        let task = manager.finish("task-2", &before).unwrap();
+        assert!(task.changes.is_empty());
+        assert_eq!(task.diff_path, None);
+        assert!(!workspace.path().join("diffs").exists());
+    }
+
+    #[test]
+    fn test_rollback_restores_pre_task_files() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::create_dir_all(root.join("src/old")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn one() {}\n").unwrap();
+        std::fs::write(root.join("src/old/gone.rs"), "// gone\n").unwrap();
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"));
+
+        let before = manager.start().unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn broken( {}\n").unwrap();
+        std::fs::write(root.join("src/new.rs"), "// new\n").unwrap();
+        std::fs::remove_dir_all(root.join("src/old")).unwrap();
+        manager.finish("task-3", &before).unwrap();
+
+        let undone = manager.rollback("task-3").unwrap();
+        assert_eq!(undone.created, vec![PathBuf::from("src/new.rs")]);
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "fn one() {}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/old/gone.rs")).unwrap(),
+            "// gone\n"
+        );
+        assert!(!root.join("src/new.rs").exists());
+        assert!(before.changes(&manager.capture().unwrap()).is_empty());
+
+        let error = manager.rollback("task-3").unwrap_err().to_string();
+        assert!(error.contains("No recorded file changes"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rollback_refuses_symlinks_out_of_workspace() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::create_dir_all(root.join("src/old")).unwrap();
+        std::fs::write(root.join("src/old/gone.rs"), "// gone\n").unwrap();
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"));
+
+        // The task swaps the directory for a link to somewhere else
+        let before = manager.start().unwrap();
+        std::fs::remove_dir_all(root.join("src/old")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.join("src/old")).unwrap();
+        manager.finish("task-7", &before).unwrap();
+
+        let error = manager.rollback("task-7").unwrap_err().to_string();
+        assert!(error.contains("outside the workspace"), "{}", error);
+        assert!(!outside.path().join("gone.rs").exists());
+    }
+
+    #[test]
+    fn test_rollback_refuses_files_changed_since() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::write(root.join("a.txt"), "before").unwrap();
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"));
+
+        let before = manager.start().unwrap();
+        std::fs::write(root.join("a.txt"), "task").unwrap();
+        manager.finish("task-4", &before).unwrap();
+        std::fs::write(root.join("a.txt"), "later edit").unwrap();
+
+        let error = manager.rollback("task-4").unwrap_err().to_string();
+        assert!(
+            error.contains("changed again since it finished: a.txt"),
+            "{}",
+            error
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "later edit"
+        );
+    }
+
+    #[test]
+    fn test_overlapping_tasks_share_files_changed_while_both_ran() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"));
+
+        let first = manager.start().unwrap();
+        std::fs::write(root.join("first.txt"), "first").unwrap();
+        let second = manager.start().unwrap();
+        std::fs::write(root.join("during.txt"), "either").unwrap();
+        let first_diff = manager.finish("first", &first).unwrap();
+        std::fs::write(root.join("second.txt"), "second").unwrap();
+        let second_diff = manager.finish("second", &second).unwrap();
+
+        assert_eq!(first_diff.changes.created, vec![PathBuf::from("first.txt")]);
+        assert_eq!(
+            second_diff.changes.created,
+            vec![PathBuf::from("second.txt")]
+        );
+        for diff in [&first_diff, &second_diff] {
+            assert_eq!(diff.shared_files, vec![PathBuf::from("during.txt")]);
+        }
+
+        // A task running alone again owns everything it changes
+        drop((first, second));
+        let third = manager.start().unwrap();
+        std::fs::write(root.join("during.txt"), "third").unwrap();
+        let third_diff = manager.finish("third", &third).unwrap();
+        assert_eq!(
+            third_diff.changes.modified,
+            vec![PathBuf::from("during.txt")]
+        );
+        assert!(third_diff.shared_files.is_empty());
+    }
+
+    #[test]
+    fn test_rollback_keeps_overlapping_tasks_changes() {
+        let workspace = TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::write(root.join("config.toml"), "original").unwrap();
+        let manager = SnapshotManager::new(root, &["diffs".into()], root.join("diffs"));
+
+        let first = manager.start().unwrap();
+        std::fs::write(root.join("first.txt"), "first").unwrap();
+        let second = manager.start().unwrap();
+        std::fs::write(root.join("config.toml"), "either").unwrap();
+        manager.finish("first", &first).unwrap();
+        std::fs::write(root.join("second.txt"), "second").unwrap();
+
+        // The second task is still running, so its edits could be reverted
+        let error = manager.rollback("first").unwrap_err().to_string();
+        assert!(error.contains("still running"), "{}", error);
+        assert!(root.join("first.txt").exists());
+
+        manager.finish("second", &second).unwrap();
+        drop((first, second));
+        let undone = manager.rollback("first").unwrap();
+        assert_eq!(
+            undone.all().collect::<Vec<_>>(),
+            vec![Path::new("first.txt")]
+        );
+        assert!(!root.join("first.txt").exists());
+        // Only the first task's own file is undone
+        assert_eq!(
+            std::fs::read_to_string(root.join("config.toml")).unwrap(),
+            "either"
+        );
+        assert!(root.join("second.txt").exists());
+    }
+}
@@ -0,0 +1,229 @@
+//! Decoding workspace files as text for diffs and prompts.
+//!
+//! Files are not assumed to be UTF-8. A byte order mark selects UTF-8 or
+//! UTF-16, a NUL byte or a high share of control characters marks a file
+//! as binary, and text that is not valid UTF-8 is read as Latin-1, which
+//! maps every byte to a character and so loses nothing. Files over
+//! [`TextFileConfig::max_text_bytes`] or with an extension listed in
+//! [`TextFileConfig::binary_extensions`] are treated as binary without
+//! being read, and Git LFS pointers are never treated as text.
+//!
+//! ```toml
+//! [text_files]
+//! max_text_bytes = 1048576
+//! binary_extensions = ["png", "jpg", "zip", "so"]
+//! ```
+
+use super::lfs::LfsPointer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Bytes looked at for NUL and control characters
+const SNIFF_BYTES: usize = 8000;
+
+/// Share of control characters above which undecodable bytes are binary
+const MAX_CONTROL_SHARE: f64 = 0.1;
+
+/// Which files are read as text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextFileConfig {
+    /// Largest file decoded as text; larger ones are treated as binary
+    pub max_text_bytes: u64,
+    /// Extensions, without the `.`, of files always treated as binary
+    pub binary_extensions: Vec<String>,
+}
+
+impl Default for TextFileConfig {
+    fn default() -> Self {
+        Self {
+            max_text_bytes: 1024 * 1024,
+            binary_extensions: [
+                "png", "jpg", "jpeg", "gif", "ico", "webp", "pdf", "zip", "gz", "tgz", "xz", "bz2",
+                "7z", "jar", "class", "so", "dylib", "dll", "exe", "o", "a", "rlib", "wasm",
+                "woff", "woff2", "ttf", "otf", "mp3", "mp4", "sqlite", "db",
+            ]
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl TextFileConfig {
+    /// Whether the name or size of a file rules out reading it as text
+    pub fn skips(&self, path: &Path, len: u64) -> bool {
+        len > self.max_text_bytes
+            || path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    self.binary_extensions
+                        .iter()
+                        .any(|binary| binary.eq_ignore_ascii_case(ext))
+                })
+    }
+
+    /// Decode `contents` of the file at `path`, `None` if it is not text
+    pub fn decode(&self, path: &Path, contents: &[u8]) -> Option<DecodedText> {
+        if self.skips(path, contents.len() as u64) || LfsPointer::parse(contents).is_some() {
+            return None;
+        }
+        decode_text(contents)
+    }
+
+    /// Read the file at `path` as text, `None` if it is not
+    pub fn read(&self, path: &Path) -> std::io::Result<Option<DecodedText>> {
+        if self.skips(path, std::fs::metadata(path)?.len()) {
+            return Ok(None);
+        }
+        Ok(self.decode(path, &std::fs::read(path)?))
+    }
+}
+
+/// Encoding a text file was decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Bytes that are not valid UTF-8, one character each
+    Latin1,
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Latin1 => "Latin-1",
+        })
+    }
+}
+
+/// Contents of a text file and the encoding they were read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: TextEncoding,
+}
+
+/// Decode `contents` as text, `None` if they look binary
+pub fn decode_text(contents: &[u8]) -> Option<DecodedText> {
+    let decoded = |text: String, encoding| Some(DecodedText { text, encoding });
+    if let Some(rest) = contents.strip_prefix(b"\xEF\xBB\xBF") {
+        return decoded(
+            std::str::from_utf8(rest).ok()?.to_string(),
+            TextEncoding::Utf8Bom,
+        );
+    }
+    if let Some(rest) = contents.strip_prefix(b"\xFF\xFE") {
+        return decoded(utf16(rest, u16::from_le_bytes)?, TextEncoding::Utf16Le);
+    }
+    if let Some(rest) = contents.strip_prefix(b"\xFE\xFF") {
+        return decoded(utf16(rest, u16::from_be_bytes)?, TextEncoding::Utf16Be);
+    }
+
+    let head = &contents[..contents.len().min(SNIFF_BYTES)];
+    if head.contains(&0) {
+        return None;
+    }
+    if let Ok(text) = std::str::from_utf8(contents) {
+        return decoded(text.to_string(), TextEncoding::Utf8);
+    }
+    let controls = head
+        .iter()
+        .filter(|&&byte| byte < 0x20 && !b"\t\n\r\x0c\x1b".contains(&byte))
+        .count();
+    if controls as f64 > head.len() as f64 * MAX_CONTROL_SHARE {
+        return None;
+    }
+    decoded(
+        contents.iter().map(|&byte| char::from(byte)).collect(),
+        TextEncoding::Latin1,
+    )
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| unit([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_sniffs_encodings() {
+        let text =
+            |bytes: &[u8]| decode_text(bytes).map(|decoded| (decoded.text, decoded.encoding));
+        assert_eq!(
+            text("héllo\n".as_bytes()),
+            Some(("héllo\n".to_string(), TextEncoding::Utf8))
+        );
+        assert_eq!(
+            text(b"\xEF\xBB\xBFbom"),
+            Some(("bom".to_string(), TextEncoding::Utf8Bom))
+        );
+        assert_eq!(
+            text(b"\xFF\xFEh\x00i\x00"),
+            Some(("hi".to_string(), TextEncoding::Utf16Le))
+        );
+        assert_eq!(
+            text(b"\xFE\xFF\x00h\x00i"),
+            Some(("hi".to_string(), TextEncoding::Utf16Be))
+        );
+
+        // Latin-1 keeps every byte, so the file can be written back unchanged
+        let latin1 = b"caf\xE9 cr\xE8me\n";
+        let (decoded, encoding) = text(latin1).unwrap();
+        assert_eq!(encoding, TextEncoding::Latin1);
+        assert_eq!(decoded, "café crème\n");
+        assert_eq!(
+            decoded.chars().map(|c| c as u8).collect::<Vec<_>>(),
+            latin1.to_vec()
+        );
+
+        assert_eq!(text(b"PNG\x00\x01\x02"), None);
+        assert_eq!(text(b"\x01\x02\x03\x04\xFF\x05"), None);
+        assert_eq!(text(b"\xFF\xFEodd"), None);
+    }
+
+    #[test]
+    fn test_size_and_extension_filters() {
+        let config = TextFileConfig {
+            max_text_bytes: 8,
+            ..Default::default()
+        };
+        assert!(config.decode(Path::new("a.txt"), b"short").is_some());
+        assert!(
+            config
+                .decode(Path::new("a.txt"), b"much too long")
+                .is_none()
+        );
+        assert!(config.decode(Path::new("logo.PNG"), b"text").is_none());
+        let pointer = b"version https://git-lfs.github.com/spec/v1\noid sha256:ab\nsize 9\n";
+        assert!(
+            TextFileConfig::default()
+                .decode(Path::new("model.bin"), pointer)
+                .is_none()
+        );
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let path = workspace.path().join("notes.md");
+        std::fs::write(&path, b"caf\xE9").unwrap();
+        assert_eq!(config.read(&path).unwrap().unwrap().text, "café");
+        assert!(config.read(&workspace.path().join("missing.md")).is_err());
+    }
+}
@@ -179,6 +179,7 @@ async fn test_backup_strategy_complex_condition() {
                     not_contains: None,
                     check_stdout: false,
                     check_stderr: true,
+                    requires: Vec::new(),
                 },
                 "echo",
                 vec!["Complex condition backup executed!".to_string()],
@@ -134,10 +134,14 @@ fn test_task_conversion_to_agent_commands() {
         SimpleTask {
             description: "Echo 'Task 1 completed'".to_string(),
             reference_file: None,
+            completed: false,
+            section: Vec::new(),
         },
         SimpleTask {
             description: "Print message: 'Task 2 finished'".to_string(),
             reference_file: None,
+            completed: false,
+            section: Vec::new(),
         },
     ];
 
@@ -337,3 +341,117 @@ fn test_workspace_override_functionality() {
     let agent_config = config.to_agent_config(None);
     assert!(!agent_config.workspace_path.as_os_str().is_empty());
 }
+
+/// Daemon configuration with tenants `alpha` and `beta`, whose token is
+/// `second_token`
+fn write_daemon_config(dir: &std::path::Path, second_token: &str) -> PathBuf {
+    let path = dir.join("daemon.toml");
+    fs::write(
+        &path,
+        format!(
+            "[[tenants]]\n\
+             id = \"alpha\"\n\
+             workspace = {:?}\n\
+             api_token = \"token-a\"\n\
+             \n\
+             [[tenants]]\n\
+             id = \"beta\"\n\
+             workspace = {:?}\n\
+             api_token = {:?}\n",
+            dir.join("alpha"),
+            dir.join("beta"),
+            second_token
+        ),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn test_serve_loads_tenants_from_daemon_config() {
+    use std::io::{BufRead, BufReader};
+
+    let temp_dir = TempDir::new().unwrap();
+    let config = write_daemon_config(temp_dir.path(), "token-b");
+
+    let mut daemon = std::process::Command::new(env!("CARGO_BIN_EXE_aca"))
+        .args(["serve", "--config"])
+        .arg(&config)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let stdout = BufReader::new(daemon.stdout.take().unwrap());
+    let mut lines = Vec::new();
+    for line in stdout.lines() {
+        let line = line.unwrap();
+        let done = line.contains("beta");
+        lines.push(line);
+        if done {
+            break;
+        }
+    }
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+
+    let output = lines.join("\n");
+    assert!(output.contains("Serving 2 tenant(s)"), "{}", output);
+    assert!(output.contains("alpha"), "{}", output);
+}
+
+#[test]
+fn test_serve_rejects_invalid_daemon_config() {
+    let temp_dir = TempDir::new().unwrap();
+    // Both tenants would answer to the same token
+    let config = write_daemon_config(temp_dir.path(), "token-a");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_aca"))
+        .args(["serve", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already in use"), "{}", stderr);
+}
+
+#[cfg(feature = "grpc")]
+#[test]
+fn test_serve_starts_grpc_interface() {
+    use std::io::{BufRead, BufReader};
+
+    let temp_dir = TempDir::new().unwrap();
+    let config = write_daemon_config(temp_dir.path(), "token-b");
+
+    let mut daemon = std::process::Command::new(env!("CARGO_BIN_EXE_aca"))
+        .args(["serve", "--grpc-listen", "127.0.0.1:0", "--config"])
+        .arg(&config)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let stdout = BufReader::new(daemon.stdout.take().unwrap());
+    let started = stdout
+        .lines()
+        .map_while(Result::ok)
+        .any(|line| line.contains("gRPC interface on 127.0.0.1:0"));
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+    assert!(started);
+}
+
+#[cfg(not(feature = "grpc"))]
+#[test]
+fn test_serve_needs_grpc_feature_for_grpc_listen() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = write_daemon_config(temp_dir.path(), "token-b");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_aca"))
+        .args(["serve", "--grpc-listen", "127.0.0.1:0", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("`grpc` feature"), "{}", stderr);
+}
@@ -146,7 +146,37 @@ fn test_config_customization() {
         },
         claude_config: ClaudeConfig::default(),
         setup_commands: Vec::new(),
+        services: Vec::new(),
         execution_mode: aca::executor::RuntimeMode::Host,
+        container_runtime: Default::default(),
+        docker_context: None,
+        instructions: Default::default(),
+        heartbeat: Default::default(),
+        schedule: Default::default(),
+        questions: Default::default(),
+        watch: Default::default(),
+        refactor_guard: Default::default(),
+        completion_gates: Default::default(),
+        budget: Default::default(),
+        log_retention: Default::default(),
+        remote_sync: Default::default(),
+        distributed: Default::default(),
+        placement: Default::default(),
+        context_budget: Default::default(),
+        nix: Default::default(),
+        offline: Default::default(),
+        routing: Default::default(),
+        verification: Default::default(),
+        spot_check: Default::default(),
+        policy: Default::default(),
+        report_signing: Default::default(),
+        anonymization: Default::default(),
+        mcp: Default::default(),
+        prompt_size: Default::default(),
+        rollup: Default::default(),
+        wait: Default::default(),
+        ports: Default::default(),
+        text_files: Default::default(),
     };
 
     // Test serialization and deserialization of custom config
@@ -238,6 +238,7 @@ async fn test_complex_error_conditions() {
                     not_contains: Some("permission denied".to_string()),
                     check_stdout: false,
                     check_stderr: true,
+                    requires: Vec::new(),
                 },
                 "echo",
                 vec!["Complex error condition handled".to_string()],
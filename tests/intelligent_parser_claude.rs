@@ -72,6 +72,7 @@ async fn test_simple_task_analysis_with_real_claude() {
             "security critical".to_string(),
         ],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let result = parser
@@ -140,6 +141,7 @@ async fn test_hierarchical_task_analysis_with_real_claude() {
             "3 month timeline".to_string(),
         ],
         max_tokens: Some(4096),
+        decomposition: Default::default(),
     };
 
     let result = parser
@@ -200,6 +202,7 @@ async fn test_execution_plan_generation_with_real_claude() {
         source_path: Some(PathBuf::from("weather-cli.md")),
         context_hints: vec!["CLI tool".to_string(), "Python preferred".to_string()],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let analysis = parser
@@ -262,6 +265,7 @@ async fn test_dependency_detection_with_real_claude() {
             "critical migration".to_string(),
         ],
         max_tokens: Some(3072),
+        decomposition: Default::default(),
     };
 
     let result = parser
@@ -298,6 +302,7 @@ async fn test_caching_with_real_claude() {
         source_path: None,
         context_hints: vec![],
         max_tokens: Some(1024),
+        decomposition: Default::default(),
     };
 
     // First call - should hit Claude API
@@ -388,6 +393,7 @@ async fn test_complex_real_world_task_with_real_claude() {
             "4 month project".to_string(),
         ],
         max_tokens: Some(4096),
+        decomposition: Default::default(),
     };
 
     let result = parser
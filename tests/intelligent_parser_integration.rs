@@ -53,6 +53,7 @@ impl LLMProvider for MockLLMProvider {
                     output_tokens: 200,
                     total_tokens: 300,
                     estimated_cost: 0.01,
+                    ..Default::default()
                 },
                 execution_time: Duration::from_millis(100),
                 provider_metadata: HashMap::new(),
@@ -124,6 +125,7 @@ async fn test_single_simple_task() {
         source_path: None,
         context_hints: vec![],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let result = parser.analyze_tasks(request).await.unwrap();
@@ -195,6 +197,7 @@ async fn test_hierarchical_task_structure() {
         source_path: Some(PathBuf::from("tasks.md")),
         context_hints: vec!["backend project".to_string()],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let result = parser.analyze_tasks(request).await.unwrap();
@@ -253,6 +256,7 @@ async fn test_parallel_execution_strategy() {
         source_path: None,
         context_hints: vec!["independent tasks".to_string()],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let result = parser.analyze_tasks(request).await.unwrap();
@@ -295,6 +299,7 @@ async fn test_execution_plan_conversion() {
         source_path: Some(PathBuf::from("task.md")),
         context_hints: vec![],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let analysis = parser.analyze_tasks(request).await.unwrap();
@@ -339,6 +344,7 @@ async fn test_caching_mechanism() {
         source_path: None,
         context_hints: vec![],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     // First call - should hit the LLM
@@ -442,6 +448,7 @@ async fn test_complex_real_world_task() {
         source_path: Some(PathBuf::from("tasks.md")),
         context_hints: vec!["european products app".to_string()],
         max_tokens: Some(4096),
+        decomposition: Default::default(),
     };
 
     let result = parser.analyze_tasks(request).await.unwrap();
@@ -477,6 +484,7 @@ async fn test_error_handling_invalid_json() {
         source_path: None,
         context_hints: vec![],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let result = parser.analyze_tasks(request).await;
@@ -502,6 +510,7 @@ async fn test_error_handling_invalid_structure() {
         source_path: None,
         context_hints: vec![],
         max_tokens: Some(2048),
+        decomposition: Default::default(),
     };
 
     let result = parser.analyze_tasks(request).await;
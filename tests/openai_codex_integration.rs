@@ -240,6 +240,7 @@ async fn test_codex_intelligent_parser_on_nested_tasks() {
             "focus on nested dependencies and phase planning".to_string(),
         ],
         max_tokens: Some(6000),
+        decomposition: Default::default(),
     };
 
     let analysis = match parser.analyze_tasks(request).await {